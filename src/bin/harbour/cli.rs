@@ -4,6 +4,7 @@ use std::path::PathBuf;
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
+use harbour::ops::harbour_bench::{DEFAULT_BASELINE, DEFAULT_NOISE_THRESHOLD};
 
 /// Message output format for build commands.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
@@ -55,6 +56,12 @@ pub enum Commands {
     /// Build the current package
     Build(BuildArgs),
 
+    /// Check sources for errors without producing objects or linking
+    Check(CheckArgs),
+
+    /// Build and run an executable target
+    Run(RunArgs),
+
     /// Add a dependency to Harbour.toml
     Add(AddArgs),
 
@@ -85,6 +92,15 @@ pub enum Commands {
     /// Run tests
     Test(TestArgs),
 
+    /// Run tests with code coverage and write coverage reports
+    Coverage(CoverageArgs),
+
+    /// Build and run fuzz targets with libFuzzer
+    Fuzz(FuzzArgs),
+
+    /// Run benchmarks and compare them against a saved baseline
+    Bench(BenchArgs),
+
     /// Toolchain management
     Toolchain(ToolchainArgs),
 
@@ -94,6 +110,9 @@ pub enum Commands {
     /// FFI bundling operations
     Ffi(FfiArgs),
 
+    /// Record and compare the ABI of shared library targets
+    Abi(AbiArgs),
+
     /// Check environment and toolchain health
     Doctor(DoctorArgs),
 
@@ -105,6 +124,15 @@ pub enum Commands {
 
     /// Search for packages in the registry
     Search(SearchArgs),
+
+    /// Create a reproducible source tarball of the current package
+    Package(PackageArgs),
+
+    /// Publish the current package's shim to a local registry checkout
+    Publish(PublishArgs),
+
+    /// Mark a published version as yanked in a local registry checkout
+    Yank(YankArgs),
 }
 
 #[derive(Args)]
@@ -242,6 +270,33 @@ pub struct BuildArgs {
     pub message_format: MessageFormat,
 }
 
+#[derive(Args)]
+pub struct CheckArgs {
+    /// Check in release mode (uses release flags)
+    #[arg(short, long)]
+    pub release: bool,
+
+    /// Package(s) to check (can be specified multiple times)
+    #[arg(short, long)]
+    pub package: Vec<String>,
+
+    /// Specific targets to check within selected packages
+    #[arg(long)]
+    pub target: Vec<String>,
+
+    /// Number of parallel jobs
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    /// C++ standard version (11, 14, 17, 20, 23)
+    #[arg(long, value_name = "VERSION")]
+    pub std: Option<String>,
+
+    /// Output format: human (default) or json
+    #[arg(long, value_name = "FMT", default_value = "human")]
+    pub message_format: MessageFormat,
+}
+
 #[derive(Args)]
 pub struct AddArgs {
     /// Package name
@@ -409,16 +464,165 @@ pub struct LinkplanArgs {
 
 #[derive(Args)]
 pub struct TestArgs {
+    /// Only run tests whose name contains this string
+    pub filter: Option<String>,
+
     /// Specific test targets to run (defaults to all test targets)
+    #[arg(long = "target", value_name = "NAME")]
     pub targets: Vec<String>,
 
     /// Build in release mode
     #[arg(short, long)]
     pub release: bool,
 
-    /// Number of parallel jobs
+    /// Number of parallel jobs (for building and running tests)
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    /// Timeout in seconds for each test, overriding the manifest
+    #[arg(long, value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    /// Show test output as it runs instead of capturing it (runs tests serially)
+    #[arg(long)]
+    pub no_capture: bool,
+
+    /// Only run test cases whose name contains this string (framework tests)
+    #[arg(long = "case", value_name = "PATTERN")]
+    pub case_filter: Option<String>,
+
+    /// Write results as a JUnit XML report
+    #[arg(long, value_name = "PATH")]
+    pub junit: Option<PathBuf>,
+
+    /// Output format: human (default) or json
+    #[arg(long, value_name = "FMT", default_value = "human")]
+    pub message_format: MessageFormat,
+
+    /// Arguments passed to every test executable
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct CoverageArgs {
+    /// Only run tests whose name contains this string
+    pub filter: Option<String>,
+
+    /// Specific test targets to run (defaults to all test targets)
+    #[arg(long = "target", value_name = "NAME")]
+    pub targets: Vec<String>,
+
+    /// Number of parallel jobs (for building and running tests)
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    /// Timeout in seconds for each test, overriding the manifest
+    #[arg(long, value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    /// Also count the sources of dependencies
+    #[arg(long)]
+    pub include_deps: bool,
+
+    /// Directory for lcov.info and cobertura.xml
+    /// [default: .harbour/target/coverage/report]
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Arguments passed to every test executable
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct FuzzArgs {
+    #[command(subcommand)]
+    pub command: FuzzCommands,
+}
+
+#[derive(Subcommand)]
+pub enum FuzzCommands {
+    /// List fuzz targets and their corpus sizes
+    List,
+
+    /// Build a fuzz target and fuzz it until it crashes or is stopped
+    Run(FuzzRunArgs),
+}
+
+#[derive(Args)]
+pub struct FuzzRunArgs {
+    /// Fuzz target to run
+    pub target: String,
+
+    /// Number of fuzzing processes run in parallel
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    /// Stop after this many seconds
+    #[arg(long, value_name = "SECS")]
+    pub max_total_time: Option<u64>,
+
+    /// Stop after this many executions
+    #[arg(long, value_name = "N")]
+    pub runs: Option<u64>,
+
+    /// Keep crashing inputs as found instead of minimizing them
+    #[arg(long)]
+    pub no_minimize: bool,
+
+    /// Flags passed to libFuzzer
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Only run bench targets whose name contains this string
+    pub filter: Option<String>,
+
+    /// Compare results against this saved baseline
+    #[arg(long, value_name = "NAME")]
+    pub baseline: Option<String>,
+
+    /// Save results as this baseline
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_BASELINE)]
+    pub save_baseline: String,
+
+    /// Changes smaller than this percentage are reported as noise
+    #[arg(long, value_name = "PCT", default_value_t = DEFAULT_NOISE_THRESHOLD)]
+    pub noise_threshold: f64,
+
+    /// Build with the debug profile instead of release
+    #[arg(long)]
+    pub debug: bool,
+
+    /// Number of parallel build jobs (benchmarks always run one at a time)
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    /// Arguments passed to every bench executable
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    /// Executable target to run (required if the package has several)
+    #[arg(long = "bin", value_name = "NAME")]
+    pub bin: Option<String>,
+
+    /// Build in release mode
+    #[arg(short, long)]
+    pub release: bool,
+
+    /// Number of parallel build jobs
     #[arg(short, long)]
     pub jobs: Option<usize>,
+
+    /// Arguments passed to the executable
+    #[arg(last = true)]
+    pub args: Vec<String>,
 }
 
 #[derive(Args)]
@@ -518,6 +722,9 @@ pub enum FfiCommands {
 
     /// Generate language bindings from C headers
     Generate(FfiGenerateArgs),
+
+    /// Check that computed struct layouts match the compiler
+    Check(FfiCheckArgs),
 }
 
 #[derive(Args)]
@@ -541,6 +748,14 @@ pub struct FfiBundleArgs {
     /// Dry run - don't actually copy files
     #[arg(long)]
     pub dry_run: bool,
+
+    /// Additional directory to find runtime dependencies in
+    #[arg(long, value_name = "DIR")]
+    pub lib_dir: Vec<PathBuf>,
+
+    /// Root of the target system, for cross-compiled bundles
+    #[arg(long, value_name = "DIR")]
+    pub sysroot: Option<PathBuf>,
 }
 
 #[derive(Args)]
@@ -569,6 +784,16 @@ pub struct FfiGenerateArgs {
     #[arg(long)]
     pub strip_prefix: Option<String>,
 
+    /// Target triple the bindings will run on; struct layouts are computed
+    /// for it (defaults to the host)
+    #[arg(long, value_name = "TRIPLE")]
+    pub target_triple: Option<String>,
+
+    /// Run headers through the compiler's preprocessor with the target's
+    /// include dirs and defines before parsing
+    #[arg(long)]
+    pub preprocess: bool,
+
     /// Generate async function wrappers
     #[arg(long)]
     pub async_wrappers: bool,
@@ -577,11 +802,89 @@ pub struct FfiGenerateArgs {
     #[arg(long)]
     pub lib_path: Option<String>,
 
+    /// Use source-generated [LibraryImport] instead of [DllImport] (C# only)
+    #[arg(long)]
+    pub library_import: bool,
+
+    /// Also write an MSBuild props file that copies the bundled library (C# only)
+    #[arg(long)]
+    pub csproj: bool,
+
+    /// Also generate safe wrappers for functions without raw pointers (Rust only)
+    #[arg(long)]
+    pub safe_wrappers: bool,
+
+    /// Also write a build.rs that links against the bundled library (Rust only)
+    #[arg(long)]
+    pub build_script: bool,
+
     /// Build in release mode
     #[arg(short, long)]
     pub release: bool,
 }
 
+#[derive(Args)]
+pub struct FfiCheckArgs {
+    /// Header files to check (glob patterns)
+    #[arg(long)]
+    pub header: Vec<PathBuf>,
+
+    /// Target whose headers and compile flags to use
+    #[arg(short, long)]
+    pub target: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AbiArgs {
+    #[command(subcommand)]
+    pub command: AbiCommands,
+}
+
+#[derive(Subcommand)]
+pub enum AbiCommands {
+    /// Build shared libraries and record their ABI as a JSON baseline
+    Dump(AbiDumpArgs),
+
+    /// Compare two ABI baselines and check the version bump
+    Diff(AbiDiffArgs),
+}
+
+#[derive(Args)]
+pub struct AbiDumpArgs {
+    /// Shared library targets to record (defaults to all)
+    #[arg(short, long = "target")]
+    pub targets: Vec<String>,
+
+    /// Output file (defaults to target/abi/<package>-<version>.json)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Build in release mode
+    #[arg(short, long)]
+    pub release: bool,
+
+    /// Number of parallel jobs
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct AbiDiffArgs {
+    /// Baseline of the previous release
+    pub old: PathBuf,
+
+    /// Baseline to compare against it (defaults to the current build)
+    pub new: Option<PathBuf>,
+
+    /// Build in release mode
+    #[arg(short, long)]
+    pub release: bool,
+
+    /// Number of parallel jobs
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+}
+
 #[derive(Args)]
 pub struct SearchArgs {
     /// Search query (matches against package names)
@@ -591,3 +894,51 @@ pub struct SearchArgs {
     #[arg(long, value_name = "PATH")]
     pub registry_path: Option<std::path::PathBuf>,
 }
+
+#[derive(Args)]
+pub struct PackageArgs {
+    /// Output directory for the tarball (defaults to .harbour/target/package)
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<PathBuf>,
+
+    /// List the files that would be packaged without creating the tarball
+    #[arg(long)]
+    pub list: bool,
+}
+
+#[derive(Args)]
+pub struct PublishArgs {
+    /// Path to the local registry checkout
+    #[arg(long, value_name = "PATH")]
+    pub registry_path: PathBuf,
+
+    /// URL the tarball will be downloaded from
+    #[arg(long, value_name = "URL")]
+    pub tarball_url: String,
+
+    /// Output directory for the tarball (defaults to .harbour/target/package)
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<PathBuf>,
+
+    /// Generate and validate the shim without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct YankArgs {
+    /// Package name
+    pub package: String,
+
+    /// Version to yank
+    #[arg(long)]
+    pub version: String,
+
+    /// Path to the local registry checkout
+    #[arg(long, value_name = "PATH")]
+    pub registry_path: PathBuf,
+
+    /// Undo a previous yank
+    #[arg(long)]
+    pub undo: bool,
+}
@@ -0,0 +1,202 @@
+//! `harbour abi` command
+
+use anyhow::{bail, Context, Result};
+
+use super::ffi::{find_header_files, target_compile_surface};
+use crate::cli::{AbiArgs, AbiCommands, AbiDiffArgs, AbiDumpArgs};
+use harbour::builder::bindings::{HeaderParser, HeaderPreprocessor, ParsedHeader, TargetAbi};
+use harbour::builder::diagnostics::DiagnosticFormat;
+use harbour::builder::shim::LinkagePreference;
+use harbour::core::abi::TargetTriple;
+use harbour::core::target::TargetKind;
+use harbour::core::Workspace;
+use harbour::ops::harbour_build::{build, BuildOptions};
+use harbour::ops::{diff_abi, AbiDump, Bump, LibraryAbi};
+use harbour::sources::{Fetcher, SourceCache};
+use harbour::util::config::load_config;
+use harbour::util::GlobalContext;
+use harbour::util::VcpkgIntegration;
+
+pub fn execute(args: AbiArgs) -> Result<()> {
+    match args.command {
+        AbiCommands::Dump(args) => dump(args),
+        AbiCommands::Diff(args) => diff(args),
+    }
+}
+
+fn dump(args: AbiDumpArgs) -> Result<()> {
+    let ctx = GlobalContext::new()?;
+    let manifest_path = ctx.find_manifest()?;
+    let profile = if args.release { "release" } else { "debug" };
+    let ws = Workspace::new(&manifest_path, &ctx)?.with_profile(profile);
+
+    let abi = current_abi(&ws, &ctx, &args.targets, args.release, args.jobs)?;
+    let output = args.output.unwrap_or_else(|| {
+        ws.target_dir()
+            .join("abi")
+            .join(format!("{}-{}.json", abi.package, abi.version))
+    });
+    abi.save(&output)?;
+
+    for library in &abi.libraries {
+        println!(
+            "  {}: {} symbols, {} functions, {} types",
+            library.target,
+            library.symbols.len(),
+            library.functions.len(),
+            library.types.len()
+        );
+    }
+    println!();
+    println!("ABI baseline written to {}", output.display());
+    Ok(())
+}
+
+fn diff(args: AbiDiffArgs) -> Result<()> {
+    let old = AbiDump::load(&args.old)?;
+    let new = match args.new {
+        Some(ref path) => AbiDump::load(path)?,
+        None => {
+            let ctx = GlobalContext::new()?;
+            let manifest_path = ctx.find_manifest()?;
+            let profile = if args.release { "release" } else { "debug" };
+            let ws = Workspace::new(&manifest_path, &ctx)?.with_profile(profile);
+            current_abi(&ws, &ctx, &[], args.release, args.jobs)?
+        }
+    };
+
+    if old.package != new.package {
+        bail!(
+            "cannot compare ABI baselines of different packages (`{}` and `{}`)",
+            old.package,
+            new.package
+        );
+    }
+
+    println!(
+        "Comparing {} {} -> {}",
+        new.package, old.version, new.version
+    );
+    println!();
+
+    let report = diff_abi(&old, &new);
+    if report.changes.is_empty() {
+        println!("  No ABI changes");
+    }
+    for change in &report.changes {
+        println!("  {}", change);
+    }
+    println!();
+
+    let required = report.required_bump();
+    if required > Bump::Patch {
+        println!("These changes require a {} version bump.", required);
+    }
+    report.check_version(&old.version, &new.version)?;
+    println!("Version {} is compatible with these changes.", new.version);
+    Ok(())
+}
+
+/// Build the root package's `sharedlib` targets and record their ABI.
+fn current_abi(
+    ws: &Workspace,
+    ctx: &GlobalContext,
+    targets: &[String],
+    release: bool,
+    jobs: Option<usize>,
+) -> Result<AbiDump> {
+    let config = load_config(
+        &ctx.config_path(),
+        &ctx.project_harbour_dir().join("config.toml"),
+    );
+    let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), release);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
+
+    let pkg = ws.root_package();
+    let shared: Vec<String> = pkg
+        .targets()
+        .iter()
+        .filter(|t| t.kind == TargetKind::SharedLib)
+        .filter(|t| targets.is_empty() || targets.iter().any(|name| *name == *t.name))
+        .map(|t| t.name.to_string())
+        .collect();
+
+    if shared.is_empty() {
+        bail!(
+            "no sharedlib targets found in `{}`.\n\n\
+             ABI baselines record shared libraries; declare one in Harbour.toml:\n\
+             \n\
+                 [targets.{}]\n\
+                 kind = \"sharedlib\"",
+            pkg.name(),
+            pkg.name()
+        );
+    }
+
+    println!("Building {} shared library target(s)...", shared.len());
+    println!();
+
+    let opts = BuildOptions {
+        release,
+        packages: vec![],
+        targets: shared.clone(),
+        emit_compile_commands: false,
+        emit_plan: false,
+        jobs,
+        verbose: false,
+        cpp_std: None,
+        backend: None,
+        linkage: LinkagePreference::Auto { prefer: vec![] },
+        ffi: false,
+        target_triple: None,
+        locked: false,
+        vcpkg: config.vcpkg.clone(),
+        coverage: false,
+        fuzz: false,
+        diagnostics: DiagnosticFormat::Human,
+    };
+    let result = build(ws, &mut source_cache, &opts)?;
+
+    let mut abi = AbiDump::new(pkg.name().as_str(), pkg.version().clone());
+    for name in &shared {
+        let artifact = result
+            .artifacts
+            .iter()
+            .find(|a| a.target == *name)
+            .with_context(|| format!("build produced no library for target `{}`", name))?;
+        let header = target_header(ws, ctx, name)?;
+        abi = abi.with_library(LibraryAbi::from_library(
+            name,
+            &artifact.path,
+            header.as_ref(),
+        )?);
+    }
+    Ok(abi)
+}
+
+/// Parse a target's public headers, if it declares any, with layouts
+/// computed for the host.
+fn target_header(ws: &Workspace, ctx: &GlobalContext, name: &str) -> Result<Option<ParsedHeader>> {
+    let pkg = ws.root_package();
+    let has_headers = pkg.manifest().target(name).is_some_and(|t| {
+        !t.public_headers.is_empty() || t.ffi.as_ref().is_some_and(|f| !f.header_files.is_empty())
+    });
+    if !has_headers {
+        return Ok(None);
+    }
+
+    let header_files = find_header_files(pkg, name, &[])?;
+    let (build_ctx, surface, lang) = target_compile_surface(ws, ctx, name)?;
+    let output = HeaderPreprocessor::new(build_ctx.toolchain())
+        .with_lang(lang)
+        .with_compile_surface(&surface)
+        .run(&header_files)?;
+    let mut header = HeaderParser::new().parse_preprocessed(&output, &header_files)?;
+
+    if let Err(e) = header.compute_layouts(&TargetAbi::host(), None) {
+        tracing::warn!("recording `{}` without struct layouts: {:#}", name, e);
+    }
+    Ok(Some(header))
+}
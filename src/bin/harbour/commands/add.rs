@@ -7,6 +7,7 @@ use crate::GlobalOptions;
 use harbour::core::abi::TargetTriple;
 use harbour::ops::harbour_add::validate_registry_dependency;
 use harbour::ops::harbour_add::{add_dependency, AddOptions, AddResult, SourceKind};
+use harbour::sources::Fetcher;
 use harbour::util::config::load_config;
 use harbour::util::VcpkgIntegration;
 use harbour::util::{GlobalContext, Status};
@@ -158,26 +159,33 @@ pub fn execute(args: AddArgs, global_opts: &GlobalOptions) -> Result<()> {
     if opts.vcpkg {
         // Use provided triplet or auto-detect later
     } else if opts.path.is_none() && opts.git.is_none() {
+        let config = load_config(
+            &ctx.config_path(),
+            &ctx.project_harbour_dir().join("config.toml"),
+        );
+        let registries = config.registry_list();
         let registry_not_found = validate_registry_dependency(
             &opts.name,
             opts.version.as_deref(),
-            ctx.registries(),
+            &registries,
+            &Fetcher::from_config(&config.net),
             &ctx.cache_dir(),
             global_opts.offline,
         )?;
 
-        if registry_not_found.is_some() {
-            let config = load_config(
-                &ctx.config_path(),
-                &ctx.project_harbour_dir().join("config.toml"),
-            );
-            let integration =
-                VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false);
+        if let Some((name, looked_in)) = registry_not_found {
+            // Names scoped to a private registry never fall back to vcpkg
+            let scoped = registries.is_scoped(&name);
+            let integration = if scoped {
+                None
+            } else {
+                VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false)
+            };
 
             if let Some(integration) = integration {
                 opts.vcpkg = true;
                 opts.triplet = Some(integration.triplet.clone());
-            } else if let Some((name, looked_in)) = registry_not_found {
+            } else {
                 let registries = looked_in
                     .iter()
                     .map(|r| r.name.as_str())
@@ -187,9 +195,11 @@ pub fn execute(args: AddArgs, global_opts: &GlobalOptions) -> Result<()> {
                     "package `{}` not found in registries: {}",
                     name, registries
                 ));
-                shell.error(
-                    "vcpkg is not configured; set VCPKG_ROOT or configure [vcpkg]".to_string(),
-                );
+                if !scoped {
+                    shell.error(
+                        "vcpkg is not configured; set VCPKG_ROOT or configure [vcpkg]".to_string(),
+                    );
+                }
                 bail!("package `{}` not found", name);
             }
         }
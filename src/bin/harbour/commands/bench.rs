@@ -0,0 +1,182 @@
+//! `harbour bench` command
+
+use anyhow::{Context, Result};
+
+use crate::cli::BenchArgs;
+use harbour::builder::diagnostics::DiagnosticFormat;
+use harbour::builder::shim::LinkagePreference;
+use harbour::core::abi::TargetTriple;
+use harbour::core::Workspace;
+use harbour::ops::harbour_bench::{
+    baseline_dir, baseline_path, bench_command, bench_targets, compare, format_comparison,
+    format_results, load_baseline, run_bench, save_baseline, Change,
+};
+use harbour::ops::harbour_build::{build, BuildOptions};
+use harbour::ops::harbour_test::matches_filter;
+use harbour::sources::{Fetcher, SourceCache};
+use harbour::util::config::load_config;
+use harbour::util::GlobalContext;
+use harbour::util::VcpkgIntegration;
+
+pub fn execute(args: BenchArgs) -> Result<()> {
+    let ctx = GlobalContext::new()?;
+
+    let manifest_path = ctx.find_manifest()?;
+
+    let release = !args.debug;
+    let profile = if release { "release" } else { "debug" };
+    let ws = Workspace::new(&manifest_path, &ctx)?.with_profile(profile);
+
+    let config = load_config(
+        &ctx.config_path(),
+        &ctx.project_harbour_dir().join("config.toml"),
+    );
+    let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), release);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
+
+    let root_pkg = ws.root_package();
+    let candidates = bench_targets(root_pkg);
+    if candidates.is_empty() {
+        println!("No bench targets found.");
+        println!();
+        println!("help: Declare a bench target that prints `name ... N ns/iter` lines:");
+        println!("  [targets.parse_bench]");
+        println!("  kind = \"bench\"");
+        println!("  sources = [\"bench/parse_bench.c\"]");
+        return Ok(());
+    }
+
+    let selected: Vec<_> = candidates
+        .iter()
+        .copied()
+        .filter(|t| matches_filter(&t.name, args.filter.as_deref()))
+        .collect();
+    if selected.is_empty() {
+        println!(
+            "No bench targets match `{}`; {} filtered out",
+            args.filter.as_deref().unwrap_or_default(),
+            candidates.len()
+        );
+        return Ok(());
+    }
+
+    // Fail before building on a missing baseline or a bad baseline name
+    let dir = baseline_dir(&ws);
+    baseline_path(&dir, &args.save_baseline)?;
+    let baseline = args
+        .baseline
+        .as_deref()
+        .map(|name| load_baseline(&dir, name))
+        .transpose()?;
+
+    println!("Building {} bench target(s)...", selected.len());
+    println!();
+
+    let opts = BuildOptions {
+        release,
+        packages: vec![],
+        targets: selected.iter().map(|t| t.name.to_string()).collect(),
+        emit_compile_commands: false,
+        emit_plan: false,
+        jobs: args.jobs,
+        verbose: false,
+        cpp_std: None,
+        backend: None,
+        linkage: LinkagePreference::Auto { prefer: vec![] },
+        ffi: false,
+        target_triple: None,
+        locked: false,
+        vcpkg: config.vcpkg.clone(),
+        coverage: false,
+        fuzz: false,
+        diagnostics: DiagnosticFormat::Human,
+    };
+
+    let result = build(&ws, &mut source_cache, &opts)?;
+    let build_profile = ws.build_profile();
+
+    // Benchmarks run one at a time so they do not compete for the CPU
+    let mut results = Vec::new();
+    for target in &selected {
+        let artifact = result
+            .artifacts
+            .iter()
+            .find(|a| a.target == target.name.as_str())
+            .with_context(|| format!("building `{}` produced no executable", target.name))?;
+        let cmd = bench_command(root_pkg, target, artifact, &build_profile, &args.args)?;
+        eprintln!("     Running `{}`", cmd.display_command());
+        results.extend(run_bench(&cmd, target)?);
+    }
+
+    println!();
+    match (args.baseline.as_deref(), &baseline) {
+        (Some(name), Some(baseline)) => {
+            let comparisons = compare(&results, baseline, args.noise_threshold);
+            print!("{}", format_comparison(&comparisons));
+
+            let count =
+                |f: fn(&Change) -> bool| comparisons.iter().filter(|c| f(&c.change)).count();
+            println!();
+            println!(
+                "compared with `{}`: {} regressed, {} improved, {} within noise of {}%",
+                name,
+                count(|c| matches!(c, Change::Regression(_))),
+                count(|c| matches!(c, Change::Improvement(_))),
+                count(|c| matches!(c, Change::Noise(_))),
+                args.noise_threshold
+            );
+        }
+        _ => print!("{}", format_results(&results)),
+    }
+
+    let path = save_baseline(&dir, &args.save_baseline, &results)?;
+    println!();
+    println!(
+        "Saved baseline `{}` to {}",
+        args.save_baseline,
+        path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::BenchArgs;
+    use clap::Parser;
+
+    fn parse_bench_args(args: &[&str]) -> BenchArgs {
+        #[derive(Parser)]
+        struct BenchCli {
+            #[command(flatten)]
+            bench: BenchArgs,
+        }
+        BenchCli::parse_from(args).bench
+    }
+
+    #[test]
+    fn test_bench_args() {
+        let args = parse_bench_args(&["bench"]);
+        assert!(args.filter.is_none());
+        assert!(args.baseline.is_none());
+        assert_eq!(args.save_baseline, "latest");
+        assert_eq!(args.noise_threshold, 2.0);
+        assert!(!args.debug);
+
+        let args = parse_bench_args(&[
+            "bench",
+            "parse",
+            "--baseline",
+            "main",
+            "--noise-threshold",
+            "5",
+            "--",
+            "--benchmark_repetitions=5",
+        ]);
+        assert_eq!(args.filter.as_deref(), Some("parse"));
+        assert_eq!(args.baseline.as_deref(), Some("main"));
+        assert_eq!(args.noise_threshold, 5.0);
+        assert_eq!(args.args, vec!["--benchmark_repetitions=5"]);
+    }
+}
@@ -6,13 +6,14 @@ use anyhow::Result;
 
 use crate::cli::{BuildArgs, MessageFormat};
 use crate::GlobalOptions;
+use harbour::builder::diagnostics::DiagnosticFormat;
 use harbour::builder::events::BuildEvent;
 use harbour::builder::shim::{BackendId, LinkagePreference, TargetTriple};
 use harbour::core::abi::TargetTriple as AbiTargetTriple;
 use harbour::core::target::CppStandard;
 use harbour::core::Workspace;
 use harbour::ops::harbour_build::{build, BuildOptions};
-use harbour::sources::SourceCache;
+use harbour::sources::{Fetcher, SourceCache};
 use harbour::util::config::load_config;
 use harbour::util::VcpkgIntegration;
 use harbour::util::{GlobalContext, Status};
@@ -37,7 +38,9 @@ pub fn execute(args: BuildArgs, global_opts: &GlobalOptions) -> Result<()> {
 
     let vcpkg =
         VcpkgIntegration::from_config(&config.vcpkg, &AbiTargetTriple::host(), args.release);
-    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
 
     // Parse --std flag to CppStandard (CLI overrides config)
     let cpp_std = args
@@ -94,6 +97,13 @@ pub fn execute(args: BuildArgs, global_opts: &GlobalOptions) -> Result<()> {
         target_triple,
         locked: global_opts.locked,
         vcpkg: config.vcpkg.clone(),
+        coverage: false,
+        fuzz: false,
+        diagnostics: if is_json {
+            DiagnosticFormat::Json
+        } else {
+            DiagnosticFormat::Human
+        },
     };
 
     // Emit build started event in JSON mode
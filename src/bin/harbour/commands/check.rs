@@ -0,0 +1,160 @@
+//! `harbour check` command
+
+use std::time::Instant;
+
+use anyhow::{bail, Result};
+
+use crate::cli::{CheckArgs, MessageFormat};
+use crate::GlobalOptions;
+use harbour::builder::diagnostics::DiagnosticFormat;
+use harbour::builder::events::BuildEvent;
+use harbour::builder::shim::LinkagePreference;
+use harbour::core::abi::TargetTriple;
+use harbour::core::target::CppStandard;
+use harbour::core::Workspace;
+use harbour::ops::harbour_build::BuildOptions;
+use harbour::ops::harbour_check::check;
+use harbour::sources::{Fetcher, SourceCache};
+use harbour::util::config::load_config;
+use harbour::util::VcpkgIntegration;
+use harbour::util::{GlobalContext, Status};
+
+pub fn execute(args: CheckArgs, global_opts: &GlobalOptions) -> Result<()> {
+    let shell = &global_opts.shell;
+    let start = Instant::now();
+    let is_json = args.message_format == MessageFormat::Json;
+
+    let ctx = GlobalContext::new()?;
+
+    let manifest_path = ctx.find_manifest()?;
+
+    let profile = if args.release { "release" } else { "debug" };
+    let ws = Workspace::new(&manifest_path, &ctx)?.with_profile(profile);
+
+    let config = load_config(
+        &ctx.config_path(),
+        &ctx.project_harbour_dir().join("config.toml"),
+    );
+
+    let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), args.release);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
+
+    let cpp_std = args
+        .std
+        .as_ref()
+        .or(config.build.cpp_std.as_ref())
+        .map(|s| s.parse::<CppStandard>())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    let opts = BuildOptions {
+        release: args.release,
+        packages: args.package,
+        targets: args.target,
+        emit_compile_commands: false,
+        emit_plan: false,
+        jobs: args.jobs.or(config.build.jobs),
+        verbose: shell.is_verbose(),
+        cpp_std,
+        backend: None,
+        linkage: config
+            .linkage()
+            .unwrap_or(LinkagePreference::Auto { prefer: vec![] }),
+        ffi: false,
+        target_triple: None,
+        locked: global_opts.locked,
+        vcpkg: config.vcpkg.clone(),
+        coverage: false,
+        fuzz: false,
+        diagnostics: if is_json {
+            DiagnosticFormat::Json
+        } else {
+            DiagnosticFormat::Human
+        },
+    };
+
+    if is_json {
+        let event = BuildEvent::started(profile, "native");
+        println!("{}", event.to_json());
+    }
+
+    let root = ws.root_package();
+    shell.status(
+        Status::Checking,
+        format!("{} v{}", root.name(), root.version()),
+    );
+
+    let result = check(&ws, &mut source_cache, &opts)?;
+
+    let elapsed = start.elapsed();
+    if is_json {
+        let event = BuildEvent::BuildFinished {
+            success: result.success(),
+            duration_ms: elapsed.as_millis() as u64,
+            targets_built: None,
+        };
+        println!("{}", event.to_json());
+    }
+
+    if !result.success() {
+        bail!(
+            "could not check {} of {} file(s): {} error(s), {} warning(s)",
+            result.failed,
+            result.units,
+            result.errors,
+            result.warnings
+        );
+    }
+
+    shell.status(
+        Status::Finished,
+        format!(
+            "{} check of {} file(s) ({} fresh, {} warning(s)) in {:.2}s",
+            profile,
+            result.units,
+            result.fresh,
+            result.warnings,
+            elapsed.as_secs_f64()
+        ),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{CheckArgs, MessageFormat};
+    use clap::Parser;
+
+    fn parse_check_args(args: &[&str]) -> CheckArgs {
+        #[derive(Parser)]
+        struct CheckCli {
+            #[command(flatten)]
+            check: CheckArgs,
+        }
+        CheckCli::parse_from(args).check
+    }
+
+    #[test]
+    fn test_check_args() {
+        let args = parse_check_args(&["check"]);
+        assert!(!args.release);
+        assert!(args.package.is_empty());
+        assert_eq!(args.message_format, MessageFormat::Human);
+
+        let args = parse_check_args(&[
+            "check",
+            "-p",
+            "core",
+            "--target",
+            "parser",
+            "--message-format",
+            "json",
+        ]);
+        assert_eq!(args.package, vec!["core"]);
+        assert_eq!(args.target, vec!["parser"]);
+        assert_eq!(args.message_format, MessageFormat::Json);
+    }
+}
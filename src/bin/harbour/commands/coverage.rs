@@ -0,0 +1,172 @@
+//! `harbour coverage` command
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use crate::cli::CoverageArgs;
+use harbour::builder::diagnostics::DiagnosticFormat;
+use harbour::builder::shim::{LinkagePreference, TestStatus};
+use harbour::builder::toolchain::detect_toolchain;
+use harbour::core::abi::TargetTriple;
+use harbour::core::Workspace;
+use harbour::ops::coverage_report::format_summary;
+use harbour::ops::harbour_build::{build, BuildOptions};
+use harbour::ops::harbour_coverage::{
+    clean_counters, collect_coverage, coverage_env, write_reports, CoverageTool, SourceFilter,
+    COVERAGE_PROFILE,
+};
+use harbour::ops::harbour_test::{
+    matches_filter, run_tests, select_test_targets, test_binaries, TestOptions,
+};
+use harbour::sources::{Fetcher, SourceCache};
+use harbour::util::config::load_config;
+use harbour::util::GlobalContext;
+use harbour::util::VcpkgIntegration;
+
+pub fn execute(args: CoverageArgs) -> Result<()> {
+    let ctx = GlobalContext::new()?;
+
+    let manifest_path = ctx.find_manifest()?;
+    let ws = Workspace::new(&manifest_path, &ctx)?.with_profile(COVERAGE_PROFILE);
+
+    let config = load_config(
+        &ctx.config_path(),
+        &ctx.project_harbour_dir().join("config.toml"),
+    );
+    let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
+
+    // Fail before building if the tools to read the counters are missing
+    let tool = CoverageTool::find(detect_toolchain()?.as_ref())?;
+
+    let root_pkg = ws.root_package();
+    let candidates = select_test_targets(root_pkg, &args.targets)?;
+    let selected: Vec<_> = candidates
+        .iter()
+        .copied()
+        .filter(|t| matches_filter(&t.name, args.filter.as_deref()))
+        .collect();
+    if selected.is_empty() {
+        bail!("no test targets to collect coverage from");
+    }
+
+    println!(
+        "Building {} test target(s) with coverage...",
+        selected.len()
+    );
+    println!();
+
+    let opts = BuildOptions {
+        release: false,
+        packages: vec![],
+        targets: selected.iter().map(|t| t.name.to_string()).collect(),
+        emit_compile_commands: false,
+        emit_plan: false,
+        jobs: args.jobs,
+        verbose: false,
+        cpp_std: None,
+        backend: None,
+        linkage: LinkagePreference::Auto { prefer: vec![] },
+        ffi: false,
+        target_triple: None,
+        locked: false,
+        vcpkg: config.vcpkg.clone(),
+        coverage: true,
+        fuzz: false,
+        diagnostics: DiagnosticFormat::Human,
+    };
+
+    let result = build(&ws, &mut source_cache, &opts)?;
+    clean_counters(&ws)?;
+
+    let build_profile = ws.build_profile();
+    let coverage_env = coverage_env(&tool, &ws);
+
+    let mut tests = test_binaries(root_pkg, &selected, &result.artifacts, &build_profile)?;
+    for test in &mut tests {
+        test.env.extend(coverage_env.clone());
+    }
+
+    let test_opts = TestOptions {
+        args: args.args.clone(),
+        jobs: args.jobs,
+        timeout: args.timeout.map(Duration::from_secs),
+        no_capture: false,
+        case_filter: None,
+    };
+
+    println!("running {} test executable(s)", tests.len());
+    println!();
+
+    let results = run_tests(&tests, &test_opts, |case| {
+        if matches!(case.status, TestStatus::Failed | TestStatus::TimedOut) {
+            println!("  {} ... FAILED", case.full_name());
+        }
+    })?;
+
+    let binaries: Vec<PathBuf> = tests.iter().map(|t| t.path.clone()).collect();
+    let mut data = collect_coverage(&tool, &ws, &binaries)?;
+    let filter = SourceFilter::new(&ws, &mut source_cache, args.include_deps)?;
+    data.retain_files(|path| filter.matches(path));
+
+    let report_dir = args
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| ws.output_dir().join("report"));
+    let reports =
+        write_reports(&data, &report_dir, ws.root()).context("failed to write coverage reports")?;
+
+    println!();
+    print!("{}", format_summary(&data, ws.root()));
+    println!();
+    println!("Wrote {}", reports.lcov.display());
+    println!("Wrote {}", reports.cobertura.display());
+
+    // Coverage of failing tests is still reported, but the run fails
+    if results.success() {
+        Ok(())
+    } else {
+        bail!("{} test(s) failed", results.failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::CoverageArgs;
+    use clap::Parser;
+
+    fn parse_coverage_args(args: &[&str]) -> CoverageArgs {
+        #[derive(Parser)]
+        struct CoverageCli {
+            #[command(flatten)]
+            coverage: CoverageArgs,
+        }
+        CoverageCli::parse_from(args).coverage
+    }
+
+    #[test]
+    fn test_coverage_args() {
+        let args = parse_coverage_args(&["coverage"]);
+        assert!(args.filter.is_none());
+        assert!(!args.include_deps);
+        assert!(args.output_dir.is_none());
+
+        let args = parse_coverage_args(&[
+            "coverage",
+            "parser",
+            "--include-deps",
+            "--output-dir",
+            "out/cov",
+            "--",
+            "--verbose",
+        ]);
+        assert_eq!(args.filter.as_deref(), Some("parser"));
+        assert!(args.include_deps);
+        assert_eq!(args.output_dir, Some("out/cov".into()));
+        assert_eq!(args.args, vec!["--verbose"]);
+    }
+}
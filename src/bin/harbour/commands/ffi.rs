@@ -6,18 +6,31 @@ use std::path::PathBuf;
 
 use anyhow::{bail, Context, Result};
 
-use crate::cli::{FfiArgs, FfiCommands, FfiGenerateArgs};
-use harbour::builder::bindings::{HeaderParser, ParsedHeader, TypeScriptGenerator};
+use crate::cli::{FfiArgs, FfiCheckArgs, FfiCommands, FfiGenerateArgs};
+use harbour::builder::bindings::{
+    CSharpGenerator, HeaderParser, HeaderPreprocessor, LayoutProbe, ParsedHeader, PythonGenerator,
+    RustGenerator, TargetAbi, TypeScriptGenerator,
+};
 use harbour::builder::shim::{BackendRegistry, BuildContext, DiscoveredSurface};
+use harbour::builder::surface_resolver::EffectiveCompileSurface;
+use harbour::builder::toolchain::Language;
+use harbour::builder::SurfaceResolver;
+use harbour::core::abi::TargetTriple;
 use harbour::core::target::{FfiBundler, FfiLanguage};
 use harbour::core::workspace::{find_manifest, Workspace};
+use harbour::core::Package;
+use harbour::ops::resolve::resolve_workspace;
 use harbour::ops::{create_ffi_bundle, BundleOptions};
+use harbour::sources::{Fetcher, SourceCache};
+use harbour::util::config::load_config;
 use harbour::util::context::GlobalContext;
+use harbour::util::VcpkgIntegration;
 
 pub fn execute(args: FfiArgs) -> Result<()> {
     match args.command {
         FfiCommands::Bundle(bundle_args) => bundle(bundle_args),
         FfiCommands::Generate(gen_args) => generate(gen_args),
+        FfiCommands::Check(check_args) => check(check_args),
     }
 }
 
@@ -35,10 +48,16 @@ fn bundle(args: crate::cli::FfiBundleArgs) -> Result<()> {
         .unwrap_or_else(|| ws.target_dir().join("ffi_bundle"));
 
     // Create bundle options
-    let opts = BundleOptions::new(&output_dir)
+    let mut opts = BundleOptions::new(&output_dir)
         .with_transitive(!args.no_transitive)
         .with_rpath_rewrite(!args.no_rpath)
         .with_dry_run(args.dry_run);
+    for dir in &args.lib_dir {
+        opts = opts.with_lib_dir(dir);
+    }
+    if let Some(ref sysroot) = args.sysroot {
+        opts = opts.with_sysroot(sysroot);
+    }
 
     println!("Creating FFI bundle...");
     println!("  Package: {}", pkg.name());
@@ -187,48 +206,8 @@ fn generate(args: FfiGenerateArgs) -> Result<()> {
     println!();
 
     // Find header files to parse
-    let header_files: Vec<PathBuf> = if !args.header.is_empty() {
-        // Use explicitly specified headers
-        args.header.clone()
-    } else {
-        // Look for target's public_headers or ffi.header_files config
-        let target_name = args.target.as_deref().unwrap_or(pkg.name().as_str());
-        let target = pkg
-            .manifest()
-            .target(target_name)
-            .with_context(|| format!("target '{}' not found", target_name))?;
-
-        // Check if target has FFI config with header_files
-        let header_patterns = if let Some(ref ffi_config) = target.ffi {
-            if !ffi_config.header_files.is_empty() {
-                ffi_config.header_files.clone()
-            } else {
-                target.public_headers.clone()
-            }
-        } else {
-            target.public_headers.clone()
-        };
-
-        // Expand glob patterns
-        expand_header_patterns(pkg.root(), &header_patterns)?
-    };
-
-    if header_files.is_empty() {
-        bail!(
-            "no header files found.\n\n\
-             Specify headers with --header or configure public_headers in Harbour.toml:\n\
-             \n\
-                 [targets.{}]\n\
-                 public_headers = [\"include/**/*.h\"]\n\
-             \n\
-             Or configure FFI-specific headers:\n\
-             \n\
-                 [targets.{}.ffi]\n\
-                 header_files = [\"include/mylib.h\"]",
-            pkg.name(),
-            pkg.name()
-        );
-    }
+    let target_name = args.target.as_deref().unwrap_or(pkg.name().as_str());
+    let header_files = find_header_files(pkg, target_name, &args.header)?;
 
     println!("Parsing {} header file(s):", header_files.len());
     for h in &header_files {
@@ -242,6 +221,24 @@ fn generate(args: FfiGenerateArgs) -> Result<()> {
         parser = parser.with_strip_prefix(Some(prefix.clone()));
     }
 
+    // With --preprocess, parse what the compiler sees for the target instead
+    if args.preprocess {
+        let output = preprocess_headers(&ws, &ctx, target_name, &header_files)?;
+        let combined_header = parser.parse_preprocessed(&output, &header_files)?;
+        println!(
+            "  Preprocessed {} header(s) with the target's include dirs and defines",
+            header_files.len()
+        );
+        return generate_bindings(
+            &combined_header,
+            language,
+            bundler,
+            &output_dir,
+            &args,
+            pkg.name().as_str(),
+        );
+    }
+
     // Parse all headers and merge results
     let mut combined_header = ParsedHeader::default();
     for header_path in &header_files {
@@ -269,59 +266,189 @@ fn generate(args: FfiGenerateArgs) -> Result<()> {
         }
     }
 
+    generate_bindings(
+        &combined_header,
+        language,
+        bundler,
+        &output_dir,
+        &args,
+        pkg.name().as_str(),
+    )
+}
+
+fn check(args: FfiCheckArgs) -> Result<()> {
+    // Find and load workspace
+    let cwd = std::env::current_dir()?;
+    let manifest_path = find_manifest(&cwd)?;
+    let ctx = GlobalContext::default();
+    let ws = Workspace::new(&manifest_path, &ctx)?;
+    let pkg = ws.root_package();
+
+    let target_name = args.target.as_deref().unwrap_or(pkg.name().as_str());
+    let header_files = find_header_files(pkg, target_name, &args.header)?;
+
+    let (build_ctx, surface, lang) = target_compile_surface(&ws, &ctx, target_name)?;
+
+    // Layouts are checked for what the compiler sees, so always preprocess
+    let output = HeaderPreprocessor::new(build_ctx.toolchain())
+        .with_lang(lang)
+        .with_compile_surface(&surface)
+        .run(&header_files)?;
+    let header = HeaderParser::new().parse_preprocessed(&output, &header_files)?;
+
+    println!(
+        "Checking {} struct layout(s) for `{}`...",
+        header.structs.len(),
+        target_name
+    );
+
+    let diffs = LayoutProbe::new(build_ctx.toolchain())
+        .with_lang(lang)
+        .with_compile_surface(&surface)
+        .check(&header, &header_files)?;
+
+    if !diffs.is_empty() {
+        for diff in &diffs {
+            eprintln!("  {}", diff);
+        }
+        bail!(
+            "{} layout difference(s) between the compiler and generated bindings",
+            diffs.len()
+        );
+    }
+
+    println!("  All layouts match the compiler");
+    Ok(())
+}
+
+/// Print a summary of the parsed header and generate bindings for it.
+fn generate_bindings(
+    header: &ParsedHeader,
+    language: FfiLanguage,
+    bundler: FfiBundler,
+    output_dir: &std::path::Path,
+    args: &FfiGenerateArgs,
+    lib_name: &str,
+) -> Result<()> {
     println!();
     println!(
         "Total: {} functions, {} structs, {} enums, {} typedefs",
-        combined_header.functions.len(),
-        combined_header.structs.len(),
-        combined_header.enums.len(),
-        combined_header.typedefs.len()
+        header.functions.len(),
+        header.structs.len(),
+        header.enums.len(),
+        header.typedefs.len()
     );
     println!();
 
+    // Struct layouts depend on the platform the bindings will run on
+    let abi = match args.target_triple {
+        Some(ref triple) => TargetAbi::for_target(
+            &TargetTriple::parse(triple)
+                .with_context(|| format!("invalid target triple '{}'", triple))?,
+        ),
+        None => TargetAbi::host(),
+    };
+
     // Generate bindings based on language
     match language {
         FfiLanguage::TypeScript => {
-            generate_typescript(
-                &combined_header,
-                pkg.name().as_str(),
-                &output_dir,
+            generate_typescript(header, lib_name, output_dir, bundler, abi, args)?;
+        }
+        FfiLanguage::Python => {
+            generate_python(
+                header,
+                lib_name,
+                output_dir,
                 bundler,
-                args.async_wrappers,
+                abi,
+                args.strip_prefix.clone(),
                 args.lib_path.as_deref(),
             )?;
         }
-        FfiLanguage::Python => {
-            println!("Python binding generation is not yet implemented.");
-            println!("Contributions welcome!");
-        }
         FfiLanguage::CSharp => {
-            println!("C# binding generation is not yet implemented.");
-            println!("Contributions welcome!");
+            generate_csharp(header, lib_name, output_dir, bundler, abi, args)?;
         }
         FfiLanguage::Rust => {
-            println!("Rust binding generation is not yet implemented.");
-            println!("Contributions welcome!");
+            generate_rust(header, lib_name, output_dir, abi, args)?;
         }
     }
 
     Ok(())
 }
 
+/// Run headers through the toolchain's preprocessor using the target's
+/// effective include dirs, defines and flags.
+fn preprocess_headers(
+    ws: &Workspace,
+    ctx: &GlobalContext,
+    target_name: &str,
+    headers: &[PathBuf],
+) -> Result<String> {
+    let (build_ctx, surface, lang) = target_compile_surface(ws, ctx, target_name)?;
+
+    HeaderPreprocessor::new(build_ctx.toolchain())
+        .with_lang(lang)
+        .with_compile_surface(&surface)
+        .run(headers)
+}
+
+/// Resolve the toolchain and effective compile surface a target's sources
+/// are built with.
+pub fn target_compile_surface(
+    ws: &Workspace,
+    ctx: &GlobalContext,
+    target_name: &str,
+) -> Result<(
+    harbour::builder::BuildContext,
+    EffectiveCompileSurface,
+    Language,
+)> {
+    let config = load_config(
+        &ctx.config_path(),
+        &ctx.project_harbour_dir().join("config.toml"),
+    );
+    let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
+
+    let resolve = resolve_workspace(ws, &mut source_cache)?;
+    let build_ctx = harbour::builder::BuildContext::new_with_vcpkg(ws, "debug", &config.vcpkg)?;
+
+    let mut surface_resolver = SurfaceResolver::new(&resolve, &build_ctx.platform);
+    surface_resolver.load_packages(&mut source_cache)?;
+
+    let target = ws
+        .root_package()
+        .target(target_name)
+        .with_context(|| format!("target '{}' not found", target_name))?;
+    let mut surface = surface_resolver.resolve_compile_surface(ws.root_package_id(), target)?;
+    if let Some(vcpkg) = build_ctx.vcpkg() {
+        surface
+            .include_dirs
+            .extend(vcpkg.include_dirs.iter().cloned());
+    }
+
+    let lang = target.lang;
+    Ok((build_ctx, surface, lang))
+}
+
 /// Generate TypeScript bindings.
 fn generate_typescript(
     header: &ParsedHeader,
     lib_name: &str,
     output_dir: &std::path::Path,
     bundler: FfiBundler,
-    async_wrappers: bool,
-    lib_path: Option<&str>,
+    abi: TargetAbi,
+    args: &FfiGenerateArgs,
 ) -> Result<()> {
     let mut generator = TypeScriptGenerator::new(lib_name)
         .with_bundler(bundler)
-        .with_async_wrappers(async_wrappers);
+        .with_async_wrappers(args.async_wrappers)
+        .with_strip_prefix(args.strip_prefix.clone())
+        .with_abi(abi);
 
-    if let Some(path) = lib_path {
+    if let Some(ref path) = args.lib_path {
         generator = generator.with_lib_path(path);
     }
 
@@ -343,6 +470,213 @@ fn generate_typescript(
     Ok(())
 }
 
+/// Generate Python bindings.
+fn generate_python(
+    header: &ParsedHeader,
+    lib_name: &str,
+    output_dir: &std::path::Path,
+    bundler: FfiBundler,
+    abi: TargetAbi,
+    strip_prefix: Option<String>,
+    lib_path: Option<&str>,
+) -> Result<()> {
+    if !matches!(bundler, FfiBundler::Ctypes | FfiBundler::Cffi) {
+        bail!(
+            "bundler '{}' is not supported for Python bindings (use ctypes or cffi)",
+            bundler
+        );
+    }
+
+    let is_cffi = matches!(bundler, FfiBundler::Cffi);
+    let mut generator = PythonGenerator::new(lib_name)
+        .with_bundler(bundler)
+        .with_strip_prefix(strip_prefix)
+        .with_abi(abi);
+
+    if let Some(path) = lib_path {
+        generator = generator.with_lib_path(path);
+    }
+
+    // Generate to file
+    let output_file = output_dir.join(generator.module_file_name());
+    generator.generate_to_file(header, &output_file)?;
+
+    println!("Generated Python bindings: {}", output_file.display());
+    println!();
+    println!("To use the bindings:");
+    if is_cffi {
+        println!("  0. Install cffi: pip install cffi");
+    }
+    println!("  1. Build your library with: harbour build --ffi --release");
+    println!(
+        "  2. Bundle it next to the module: harbour ffi bundle --output {}",
+        output_dir.display()
+    );
+    println!(
+        "  3. Import and use: from {} import functionName",
+        output_file
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// Generate C# bindings.
+fn generate_csharp(
+    header: &ParsedHeader,
+    lib_name: &str,
+    output_dir: &std::path::Path,
+    bundler: FfiBundler,
+    abi: TargetAbi,
+    args: &FfiGenerateArgs,
+) -> Result<()> {
+    if !matches!(bundler, FfiBundler::PInvoke) {
+        bail!(
+            "bundler '{}' is not supported for C# bindings (use pinvoke)",
+            bundler
+        );
+    }
+
+    let mut generator = CSharpGenerator::new(lib_name)
+        .with_library_import(args.library_import)
+        .with_strip_prefix(args.strip_prefix.clone())
+        .with_abi(abi);
+
+    if let Some(ref path) = args.lib_path {
+        generator = generator.with_lib_path(path);
+    }
+
+    // Generate to file
+    let output_file = output_dir.join(generator.source_file_name());
+    generator.generate_to_file(header, &output_file)?;
+    println!("Generated C# bindings: {}", output_file.display());
+
+    let props_file = output_dir.join(generator.props_file_name());
+    if args.csproj {
+        generator.generate_props_to_file(&props_file)?;
+        println!("Generated MSBuild props: {}", props_file.display());
+    }
+
+    println!();
+    println!("To use the bindings:");
+    println!("  1. Build your library with: harbour build --ffi --release");
+    println!(
+        "  2. Bundle it: harbour ffi bundle --output {}",
+        output_dir.join("ffi_bundle").display()
+    );
+    if args.csproj {
+        println!(
+            "  3. Import {} from your .csproj to copy the bundle to the output directory",
+            props_file.display()
+        );
+    } else {
+        println!("  3. Copy the bundled library next to your application");
+    }
+
+    Ok(())
+}
+
+/// Generate Rust bindings.
+fn generate_rust(
+    header: &ParsedHeader,
+    lib_name: &str,
+    output_dir: &std::path::Path,
+    abi: TargetAbi,
+    args: &FfiGenerateArgs,
+) -> Result<()> {
+    let generator = RustGenerator::new(lib_name)
+        .with_safe_wrappers(args.safe_wrappers)
+        .with_strip_prefix(args.strip_prefix.clone())
+        .with_abi(abi);
+
+    // Generate to file
+    let output_file = output_dir.join(generator.module_file_name());
+    generator.generate_to_file(header, &output_file)?;
+    println!("Generated Rust bindings: {}", output_file.display());
+
+    let build_script = output_dir.join("build.rs");
+    if args.build_script {
+        generator.generate_build_script_to_file(&build_script)?;
+        println!("Generated build script: {}", build_script.display());
+    }
+
+    println!();
+    println!("To use the bindings:");
+    println!("  1. Build your library with: harbour build --ffi --release");
+    println!("  2. Bundle it: harbour ffi bundle --output <crate>/ffi_bundle");
+    if args.build_script {
+        println!(
+            "  3. Copy {} to your crate root to link against the bundle",
+            build_script.display()
+        );
+    } else {
+        println!("  3. Link the bundled library from your build.rs (see --build-script)");
+    }
+    println!(
+        "  4. Include the module: mod {};",
+        output_file
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// Find the headers to parse: those given with `--header`, else the target's
+/// `ffi.header_files` or `public_headers`.
+pub fn find_header_files(
+    pkg: &Package,
+    target_name: &str,
+    explicit: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    let header_files: Vec<PathBuf> = if !explicit.is_empty() {
+        // Use explicitly specified headers
+        explicit.to_vec()
+    } else {
+        // Look for target's public_headers or ffi.header_files config
+        let target = pkg
+            .manifest()
+            .target(target_name)
+            .with_context(|| format!("target '{}' not found", target_name))?;
+
+        // Check if target has FFI config with header_files
+        let header_patterns = if let Some(ref ffi_config) = target.ffi {
+            if !ffi_config.header_files.is_empty() {
+                ffi_config.header_files.clone()
+            } else {
+                target.public_headers.clone()
+            }
+        } else {
+            target.public_headers.clone()
+        };
+
+        // Expand glob patterns
+        expand_header_patterns(pkg.root(), &header_patterns)?
+    };
+
+    if header_files.is_empty() {
+        bail!(
+            "no header files found.\n\n\
+             Specify headers with --header or configure public_headers in Harbour.toml:\n\
+             \n\
+                 [targets.{}]\n\
+                 public_headers = [\"include/**/*.h\"]\n\
+             \n\
+             Or configure FFI-specific headers:\n\
+             \n\
+                 [targets.{}.ffi]\n\
+                 header_files = [\"include/mylib.h\"]",
+            pkg.name(),
+            pkg.name()
+        );
+    }
+
+    Ok(header_files)
+}
+
 /// Expand header patterns to file paths.
 fn expand_header_patterns(root: &std::path::Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
@@ -8,7 +8,7 @@ use harbour::builder::BuildContext;
 use harbour::core::abi::TargetTriple;
 use harbour::core::Workspace;
 use harbour::ops::resolve::resolve_workspace;
-use harbour::sources::SourceCache;
+use harbour::sources::{Fetcher, SourceCache};
 use harbour::util::config::load_config;
 use harbour::util::GlobalContext;
 use harbour::util::VcpkgIntegration;
@@ -25,7 +25,9 @@ pub fn execute(args: FlagsArgs) -> Result<()> {
         &ctx.project_harbour_dir().join("config.toml"),
     );
     let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false);
-    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
 
     let resolve = resolve_workspace(&ws, &mut source_cache)?;
 
@@ -0,0 +1,184 @@
+//! `harbour fuzz` command
+
+use anyhow::{bail, Context, Result};
+
+use crate::cli::{FuzzArgs, FuzzCommands, FuzzRunArgs};
+use harbour::builder::diagnostics::DiagnosticFormat;
+use harbour::builder::shim::LinkagePreference;
+use harbour::core::abi::TargetTriple;
+use harbour::core::Workspace;
+use harbour::ops::harbour_build::{build, BuildOptions};
+use harbour::ops::harbour_fuzz::{
+    artifact_dir, corpus_dir, corpus_size, fuzz_command, fuzz_targets, minimize_command,
+    minimized_path, select_fuzz_target, unminimized_artifacts, FuzzOptions, FUZZ_PROFILE,
+};
+use harbour::sources::{Fetcher, SourceCache};
+use harbour::util::config::load_config;
+use harbour::util::GlobalContext;
+use harbour::util::VcpkgIntegration;
+
+pub fn execute(args: FuzzArgs) -> Result<()> {
+    match args.command {
+        FuzzCommands::List => list(),
+        FuzzCommands::Run(run_args) => run(run_args),
+    }
+}
+
+fn list() -> Result<()> {
+    let ctx = GlobalContext::new()?;
+    let manifest_path = ctx.find_manifest()?;
+    let ws = Workspace::new(&manifest_path, &ctx)?;
+
+    let root_pkg = ws.root_package();
+    let targets = fuzz_targets(root_pkg);
+    if targets.is_empty() {
+        println!("No fuzz targets found.");
+        println!();
+        println!("help: Declare a fuzz target whose sources define LLVMFuzzerTestOneInput:");
+        println!("  [targets.parse_fuzz]");
+        println!("  kind = \"fuzz\"");
+        println!("  sources = [\"fuzz/parse_fuzz.c\"]");
+        return Ok(());
+    }
+
+    let width = targets.iter().map(|t| t.name.len()).max().unwrap_or(0);
+    for target in targets {
+        let corpus = corpus_dir(root_pkg, &target.name);
+        let crashes = unminimized_artifacts(&artifact_dir(root_pkg, &target.name))?.len();
+        print!(
+            "{:<width$}  {} corpus input(s) in {}",
+            target.name.as_str(),
+            corpus_size(&corpus),
+            corpus.display()
+        );
+        if crashes > 0 {
+            print!(", {} unminimized crash(es)", crashes);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn run(args: FuzzRunArgs) -> Result<()> {
+    let ctx = GlobalContext::new()?;
+
+    let manifest_path = ctx.find_manifest()?;
+    let ws = Workspace::new(&manifest_path, &ctx)?.with_profile(FUZZ_PROFILE);
+
+    let config = load_config(
+        &ctx.config_path(),
+        &ctx.project_harbour_dir().join("config.toml"),
+    );
+    let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
+
+    let root_pkg = ws.root_package();
+    let target = select_fuzz_target(root_pkg, &args.target)?;
+
+    let opts = BuildOptions {
+        release: false,
+        packages: vec![],
+        targets: vec![target.name.to_string()],
+        emit_compile_commands: false,
+        emit_plan: false,
+        jobs: config.build.jobs,
+        verbose: false,
+        cpp_std: None,
+        backend: None,
+        linkage: LinkagePreference::Auto { prefer: vec![] },
+        ffi: false,
+        target_triple: None,
+        locked: false,
+        vcpkg: config.vcpkg.clone(),
+        coverage: false,
+        fuzz: true,
+        diagnostics: DiagnosticFormat::Human,
+    };
+
+    let result = build(&ws, &mut source_cache, &opts)?;
+    let artifact = result
+        .artifacts
+        .iter()
+        .find(|a| a.target == target.name.as_str())
+        .with_context(|| format!("building `{}` produced no executable", target.name))?;
+
+    let profile = ws.build_profile();
+    let fuzz_opts = FuzzOptions {
+        jobs: args.jobs,
+        max_total_time: args.max_total_time,
+        runs: args.runs,
+        args: args.args.clone(),
+    };
+    let cmd = fuzz_command(root_pkg, target, artifact, &profile, &fuzz_opts)?;
+    eprintln!("     Fuzzing `{}`", cmd.display_command());
+
+    let status = cmd.status()?;
+
+    let artifacts = artifact_dir(root_pkg, &target.name);
+    if !args.no_minimize {
+        for crash in unminimized_artifacts(&artifacts)? {
+            eprintln!("  Minimizing {}", crash.display());
+            minimize_command(root_pkg, artifact, &profile, &crash)?.exec()?;
+            let minimized = minimized_path(&crash);
+            if minimized.exists() {
+                eprintln!("   Minimized to {}", minimized.display());
+            } else {
+                eprintln!("warning: could not minimize {}", crash.display());
+            }
+        }
+    }
+
+    if !status.success() {
+        bail!(
+            "fuzz target `{}` stopped with {}\n\
+             note: inputs that crashed it are saved in {}",
+            target.name,
+            status,
+            artifacts.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{FuzzArgs, FuzzCommands};
+    use clap::Parser;
+
+    fn parse_fuzz_args(args: &[&str]) -> FuzzArgs {
+        #[derive(Parser)]
+        struct FuzzCli {
+            #[command(flatten)]
+            fuzz: FuzzArgs,
+        }
+        FuzzCli::parse_from(args).fuzz
+    }
+
+    #[test]
+    fn test_fuzz_args() {
+        let args = parse_fuzz_args(&["fuzz", "list"]);
+        assert!(matches!(args.command, FuzzCommands::List));
+
+        let args = parse_fuzz_args(&[
+            "fuzz",
+            "run",
+            "parse_fuzz",
+            "-j",
+            "4",
+            "--max-total-time",
+            "60",
+            "--",
+            "-use_value_profile=1",
+        ]);
+        let FuzzCommands::Run(run) = args.command else {
+            panic!("expected `fuzz run`");
+        };
+        assert_eq!(run.target, "parse_fuzz");
+        assert_eq!(run.jobs, Some(4));
+        assert_eq!(run.max_total_time, Some(60));
+        assert!(!run.no_minimize);
+        assert_eq!(run.args, vec!["-use_value_profile=1"]);
+    }
+}
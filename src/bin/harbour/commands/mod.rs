@@ -1,22 +1,31 @@
 //! Command implementations
 
+pub mod abi;
 pub mod add;
 pub mod backend;
+pub mod bench;
 pub mod build;
 pub mod cache;
+pub mod check;
 pub mod clean;
 pub mod completions;
+pub mod coverage;
 pub mod doctor;
 pub mod explain;
 pub mod ffi;
 pub mod flags;
+pub mod fuzz;
 pub mod init;
 pub mod linkplan;
 pub mod new;
+pub mod package;
+pub mod publish;
 pub mod remove;
+pub mod run;
 pub mod search;
 pub mod test;
 pub mod toolchain;
 pub mod tree;
 pub mod update;
 pub mod verify;
+pub mod yank;
@@ -0,0 +1,43 @@
+//! `harbour package` command
+
+use anyhow::Result;
+
+use crate::cli::PackageArgs;
+use crate::GlobalOptions;
+use harbour::core::Workspace;
+use harbour::ops::harbour_package::{package, PackageOptions};
+use harbour::util::{GlobalContext, Status};
+
+pub fn execute(args: PackageArgs, global_opts: &GlobalOptions) -> Result<()> {
+    let shell = &global_opts.shell;
+    let ctx = GlobalContext::new()?;
+
+    let manifest_path = ctx.find_manifest()?;
+    let ws = Workspace::new(&manifest_path, &ctx)?;
+
+    let mut opts = PackageOptions::new().with_list_only(args.list);
+    if let Some(output) = args.output {
+        opts = opts.with_output_dir(output);
+    }
+
+    let result = package(&ws, &opts)?;
+
+    if args.list {
+        for file in &result.files {
+            println!("{}", file.display());
+        }
+        return Ok(());
+    }
+
+    shell.status(
+        Status::Created,
+        format!(
+            "{} ({} files)",
+            result.tarball.display(),
+            result.files.len()
+        ),
+    );
+    shell.status(Status::Info, format!("sha256 {}", result.sha256));
+
+    Ok(())
+}
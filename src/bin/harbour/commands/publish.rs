@@ -0,0 +1,60 @@
+//! `harbour publish` command
+
+use anyhow::Result;
+
+use crate::cli::PublishArgs;
+use crate::GlobalOptions;
+use harbour::core::Workspace;
+use harbour::ops::harbour_package::PackageOptions;
+use harbour::ops::harbour_publish::{publish, PublishOptions};
+use harbour::util::{GlobalContext, Status};
+
+pub fn execute(args: PublishArgs, global_opts: &GlobalOptions) -> Result<()> {
+    let shell = &global_opts.shell;
+    let ctx = GlobalContext::new()?;
+
+    let manifest_path = ctx.find_manifest()?;
+    let ws = Workspace::new(&manifest_path, &ctx)?;
+
+    let mut package_opts = PackageOptions::new();
+    if let Some(output) = args.output {
+        package_opts = package_opts.with_output_dir(output);
+    }
+
+    let opts = PublishOptions::new(&args.registry_path, &args.tarball_url)
+        .with_package_options(package_opts)
+        .with_dry_run(args.dry_run);
+
+    let result = publish(&ws, &opts)?;
+    let pkg = ws.root_package();
+
+    if args.dry_run {
+        shell.status(
+            Status::Info,
+            format!(
+                "would publish {} v{} to {}",
+                pkg.name(),
+                pkg.version(),
+                result.shim_path.display()
+            ),
+        );
+        return Ok(());
+    }
+
+    shell.status(Status::Created, result.package.tarball.display());
+    shell.status(
+        Status::Added,
+        format!(
+            "{} v{} to {}",
+            pkg.name(),
+            pkg.version(),
+            result.shim_path.display()
+        ),
+    );
+    shell.note(format!(
+        "upload the tarball to {} (sha256 {})",
+        args.tarball_url, result.package.sha256
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! `harbour run` command
+
+use anyhow::{Context, Result};
+
+use crate::cli::RunArgs;
+use harbour::builder::diagnostics::DiagnosticFormat;
+use harbour::builder::shim::LinkagePreference;
+use harbour::core::abi::TargetTriple;
+use harbour::core::Workspace;
+use harbour::ops::harbour_build::{build, BuildOptions};
+use harbour::ops::harbour_run::{run_command, select_executable};
+use harbour::sources::{Fetcher, SourceCache};
+use harbour::util::config::load_config;
+use harbour::util::GlobalContext;
+use harbour::util::VcpkgIntegration;
+
+pub fn execute(args: RunArgs) -> Result<()> {
+    let ctx = GlobalContext::new()?;
+
+    let manifest_path = ctx.find_manifest()?;
+
+    let profile = if args.release { "release" } else { "debug" };
+    let ws = Workspace::new(&manifest_path, &ctx)?.with_profile(profile);
+
+    let config = load_config(
+        &ctx.config_path(),
+        &ctx.project_harbour_dir().join("config.toml"),
+    );
+    let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), args.release);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
+
+    let root_pkg = ws.root_package();
+    let target = select_executable(root_pkg, args.bin.as_deref())?;
+
+    let opts = BuildOptions {
+        release: args.release,
+        packages: vec![],
+        targets: vec![target.name.to_string()],
+        emit_compile_commands: false,
+        emit_plan: false,
+        jobs: args.jobs.or(config.build.jobs),
+        verbose: false,
+        cpp_std: None,
+        backend: None,
+        linkage: LinkagePreference::Auto { prefer: vec![] },
+        ffi: false,
+        target_triple: None,
+        locked: false,
+        vcpkg: config.vcpkg.clone(),
+        coverage: false,
+        fuzz: false,
+        diagnostics: DiagnosticFormat::Human,
+    };
+
+    let result = build(&ws, &mut source_cache, &opts)?;
+    let artifact = result
+        .artifacts
+        .iter()
+        .find(|a| a.target == target.name.as_str())
+        .with_context(|| format!("building `{}` produced no executable", target.name))?;
+
+    let cmd = run_command(root_pkg, target, artifact, &ws.build_profile(), &args.args)?;
+    eprintln!("     Running `{}`", cmd.display_command());
+
+    // Only returns if the executable could not be started
+    cmd.exec_replace()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::RunArgs;
+    use clap::Parser;
+
+    /// Helper to parse RunArgs from command-line strings.
+    fn parse_run_args(args: &[&str]) -> RunArgs {
+        #[derive(Parser)]
+        struct RunCli {
+            #[command(flatten)]
+            run: RunArgs,
+        }
+        let cli = RunCli::parse_from(args);
+        cli.run
+    }
+
+    #[test]
+    fn test_run_args_defaults() {
+        let args = parse_run_args(&["run"]);
+        assert!(args.bin.is_none());
+        assert!(!args.release);
+        assert!(args.jobs.is_none());
+        assert!(args.args.is_empty());
+    }
+
+    #[test]
+    fn test_run_args_bin_and_trailing_args() {
+        let args = parse_run_args(&["run", "--bin", "server", "-r", "--", "--port", "8080", "-v"]);
+        assert_eq!(args.bin.as_deref(), Some("server"));
+        assert!(args.release);
+        assert_eq!(args.args, vec!["--port", "8080", "-v"]);
+    }
+}
@@ -1,41 +1,30 @@
 //! `harbour test` command
 
-use std::process::Command;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 
-use crate::cli::TestArgs;
-use harbour::builder::shim::LinkagePreference;
+use crate::cli::{MessageFormat, TestArgs};
+use harbour::builder::diagnostics::DiagnosticFormat;
+use harbour::builder::events::BuildEvent;
+use harbour::builder::shim::{LinkagePreference, TestCase, TestResult, TestStatus};
 use harbour::core::abi::TargetTriple;
-use harbour::core::target::TargetKind;
 use harbour::core::Workspace;
 use harbour::ops::harbour_build::{build, BuildOptions};
-use harbour::sources::SourceCache;
+use harbour::ops::harbour_test::{
+    matches_filter, run_tests, select_test_targets, test_binaries, TestOptions,
+};
+use harbour::ops::sanitizer::parse_sanitizer_reports;
+use harbour::ops::test_report::write_junit;
+use harbour::sources::{Fetcher, SourceCache};
 use harbour::util::config::load_config;
 use harbour::util::GlobalContext;
 use harbour::util::VcpkgIntegration;
 
-/// Check if a target name matches test patterns.
-///
-/// Test targets are identified by name patterns:
-/// - Exact matches: "test", "tests"
-/// - Suffix matches: "*_test", "*_tests"
-/// - Prefix matches: "test_*"
-///
-/// This is a public function for testability.
-pub fn is_test_target(name: &str) -> bool {
-    let name_lower = name.to_lowercase();
-    if name_lower.is_empty() || name_lower.starts_with('_') {
-        return false;
-    }
-    name_lower == "test"
-        || name_lower == "tests"
-        || name_lower.ends_with("_test")
-        || name_lower.ends_with("_tests")
-        || name_lower.starts_with("test_")
-}
+pub use harbour::ops::harbour_test::is_test_target;
 
 pub fn execute(args: TestArgs) -> Result<()> {
+    let is_json = args.message_format == MessageFormat::Json;
     let ctx = GlobalContext::new()?;
 
     let manifest_path = ctx.find_manifest()?;
@@ -48,44 +37,66 @@ pub fn execute(args: TestArgs) -> Result<()> {
         &ctx.project_harbour_dir().join("config.toml"),
     );
     let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), args.release);
-    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
 
     // Discover test targets
     let root_pkg = ws.root_package();
-    let test_targets: Vec<String> = if args.targets.is_empty() {
-        // Auto-discover test targets
-        root_pkg
-            .targets()
-            .iter()
-            .filter(|t| t.kind == TargetKind::Exe && is_test_target(&t.name))
-            .map(|t| t.name.to_string())
-            .collect()
-    } else {
-        // Use specified targets
-        args.targets.clone()
-    };
-
-    if test_targets.is_empty() {
+    let candidates = select_test_targets(root_pkg, &args.targets)?;
+
+    if candidates.is_empty() {
+        if is_json {
+            println!(
+                "{}",
+                BuildEvent::test_finished(&TestResult::default(), 0).to_json()
+            );
+            return Ok(());
+        }
         println!("No test targets found.");
         println!();
-        println!("help: Test targets are executables with names matching:");
-        println!("      *_test, *_tests, test_*, test, tests");
+        println!("help: Declare a test target with `kind = \"test\"`, or name an");
+        println!("      executable *_test, *_tests, test_*, test or tests.");
         println!();
         println!("Example Harbour.toml:");
         println!("  [targets.unit_test]");
-        println!("  kind = \"exe\"");
+        println!("  kind = \"test\"");
         println!("  sources = [\"tests/**/*.c\"]");
         return Ok(());
     }
 
-    println!("Building {} test target(s)...", test_targets.len());
-    println!();
+    let selected: Vec<_> = candidates
+        .iter()
+        .copied()
+        .filter(|t| matches_filter(&t.name, args.filter.as_deref()))
+        .collect();
+    let filtered_out = candidates.len() - selected.len();
+
+    if selected.is_empty() {
+        if is_json {
+            println!(
+                "{}",
+                BuildEvent::test_finished(&TestResult::default(), 0).to_json()
+            );
+        } else {
+            println!(
+                "test result: ok. 0 passed; 0 failed; {} filtered out",
+                filtered_out
+            );
+        }
+        return Ok(());
+    }
+
+    if !is_json {
+        println!("Building {} test target(s)...", selected.len());
+        println!();
+    }
 
     // Build test targets
     let opts = BuildOptions {
         release: args.release,
         packages: vec![],
-        targets: test_targets.clone(),
+        targets: selected.iter().map(|t| t.name.to_string()).collect(),
         emit_compile_commands: false,
         emit_plan: false,
         jobs: args.jobs,
@@ -97,57 +108,120 @@ pub fn execute(args: TestArgs) -> Result<()> {
         target_triple: None,
         locked: false,
         vcpkg: config.vcpkg.clone(),
+        coverage: false,
+        fuzz: false,
+        diagnostics: if is_json {
+            DiagnosticFormat::Json
+        } else {
+            DiagnosticFormat::Human
+        },
     };
 
     let result = build(&ws, &mut source_cache, &opts)?;
+    let build_profile = ws.build_profile();
 
-    println!("Running tests:");
-    println!();
+    let tests = test_binaries(root_pkg, &selected, &result.artifacts, &build_profile)?;
 
-    let mut passed = 0;
-    let mut failed = 0;
-    let mut failed_tests: Vec<String> = Vec::new();
-
-    for artifact in &result.artifacts {
-        if test_targets.contains(&artifact.target.to_string()) {
-            print!("  {} ... ", artifact.target);
-
-            // Run the test executable
-            let status = Command::new(&artifact.path).status();
-
-            match status {
-                Ok(exit_status) if exit_status.success() => {
-                    println!("ok");
-                    passed += 1;
-                }
-                Ok(_) => {
-                    println!("FAILED");
-                    failed += 1;
-                    failed_tests.push(artifact.target.to_string());
-                }
-                Err(e) => {
-                    println!("FAILED (could not execute: {})", e);
-                    failed += 1;
-                    failed_tests.push(artifact.target.to_string());
-                }
-            }
+    let test_opts = TestOptions {
+        args: args.args.clone(),
+        jobs: args.jobs,
+        timeout: args.timeout.map(Duration::from_secs),
+        no_capture: args.no_capture,
+        case_filter: args.case_filter.clone(),
+    };
+
+    if !is_json {
+        println!("running {} test executable(s)", tests.len());
+        println!();
+    }
+
+    let start = Instant::now();
+    let results = run_tests(&tests, &test_opts, |case| {
+        if is_json {
+            println!("{}", BuildEvent::test_case(case).to_json());
+        } else {
+            println!("  {} ... {}", case.full_name(), status_label(case));
         }
+    })?;
+    let elapsed = start.elapsed();
+
+    if let Some(ref path) = args.junit {
+        std::fs::write(path, write_junit(root_pkg.name().as_str(), &results))
+            .with_context(|| format!("failed to write JUnit report to {}", path.display()))?;
     }
 
-    println!();
+    if is_json {
+        let event = BuildEvent::test_finished(&results, elapsed.as_millis() as u64);
+        println!("{}", event.to_json());
+    } else {
+        print_summary(&results, filtered_out, elapsed);
+    }
 
-    if failed == 0 {
-        println!("test result: ok. {} passed; {} failed", passed, failed);
+    if results.success() {
         Ok(())
     } else {
-        println!("test result: FAILED. {} passed; {} failed", passed, failed);
+        bail!("{} test(s) failed", results.failed)
+    }
+}
+
+fn status_label(case: &TestCase) -> &'static str {
+    match case.status {
+        TestStatus::Passed => "ok",
+        TestStatus::Failed => "FAILED",
+        TestStatus::Skipped => "skipped",
+        TestStatus::TimedOut => "TIMEOUT",
+    }
+}
+
+fn print_summary(results: &TestResult, filtered_out: usize, elapsed: Duration) {
+    println!();
+
+    let failures: Vec<&TestCase> = results
+        .tests
+        .iter()
+        .filter(|c| matches!(c.status, TestStatus::Failed | TestStatus::TimedOut))
+        .collect();
+
+    if !failures.is_empty() {
+        println!("failures:");
         println!();
+        for case in &failures {
+            println!("---- {} output ----", case.full_name());
+            print!("{}", case.output.as_deref().unwrap_or_default());
+            println!();
+        }
         println!("failing tests:");
-        for name in &failed_tests {
-            println!("    {}", name);
+        for case in &failures {
+            println!("    {}", case.full_name());
+        }
+        println!();
+
+        let reports: Vec<_> = failures
+            .iter()
+            .flat_map(|case| {
+                let output = case.output.as_deref().unwrap_or_default();
+                parse_sanitizer_reports(output)
+                    .into_iter()
+                    .map(|diag| diag.with_context(format!("reported by `{}`", case.full_name())))
+            })
+            .collect();
+        if !reports.is_empty() {
+            println!("sanitizer reports:");
+            for diag in &reports {
+                print!("{}", diag.format(false));
+            }
+            println!();
         }
-        anyhow::bail!("some tests failed")
     }
+
+    println!(
+        "test result: {}. {} passed; {} failed; {} filtered out; finished in {:.2}s",
+        if results.success() { "ok" } else { "FAILED" },
+        results.passed,
+        results.failed,
+        filtered_out,
+        elapsed.as_secs_f64()
+    );
 }
 
 #[cfg(test)]
@@ -176,30 +250,101 @@ mod tests {
     fn test_test_args_defaults() {
         let args = parse_test_args(&["test"]);
 
+        assert!(args.filter.is_none());
         assert!(args.targets.is_empty());
         assert!(!args.release);
         assert!(args.jobs.is_none());
+        assert!(args.timeout.is_none());
+        assert!(!args.no_capture);
+        assert!(args.case_filter.is_none());
+        assert!(args.junit.is_none());
+        assert_eq!(args.message_format, MessageFormat::Human);
+        assert!(args.args.is_empty());
     }
 
     // =========================================================================
-    // Target Selection Tests
+    // Filter and Target Selection Tests
     // =========================================================================
 
+    #[test]
+    fn test_test_filter() {
+        let args = parse_test_args(&["test", "parser"]);
+        assert_eq!(args.filter.as_deref(), Some("parser"));
+        assert!(args.targets.is_empty());
+    }
+
     #[test]
     fn test_test_single_target() {
-        let args = parse_test_args(&["test", "unit_test"]);
+        let args = parse_test_args(&["test", "--target", "unit_test"]);
         assert_eq!(args.targets, vec!["unit_test"]);
     }
 
     #[test]
     fn test_test_multiple_targets() {
-        let args = parse_test_args(&["test", "unit_test", "integration_test", "e2e_test"]);
+        let args = parse_test_args(&[
+            "test",
+            "--target",
+            "unit_test",
+            "--target",
+            "integration_test",
+            "--target",
+            "e2e_test",
+        ]);
         assert_eq!(
             args.targets,
             vec!["unit_test", "integration_test", "e2e_test"]
         );
     }
 
+    // =========================================================================
+    // Test Run Options Tests
+    // =========================================================================
+
+    #[test]
+    fn test_test_trailing_args() {
+        let args = parse_test_args(&["test", "parser", "--", "--verbose", "-x"]);
+        assert_eq!(args.filter.as_deref(), Some("parser"));
+        assert_eq!(args.args, vec!["--verbose", "-x"]);
+    }
+
+    #[test]
+    fn test_test_timeout_and_no_capture() {
+        let args = parse_test_args(&["test", "--timeout", "30", "--no-capture"]);
+        assert_eq!(args.timeout, Some(30));
+        assert!(args.no_capture);
+    }
+
+    #[test]
+    fn test_test_case_filter_and_reports() {
+        let args = parse_test_args(&[
+            "test",
+            "--case",
+            "Parser",
+            "--junit",
+            "results.xml",
+            "--message-format",
+            "json",
+        ]);
+        assert_eq!(args.case_filter.as_deref(), Some("Parser"));
+        assert_eq!(args.junit, Some(std::path::PathBuf::from("results.xml")));
+        assert_eq!(args.message_format, MessageFormat::Json);
+        assert!(args.filter.is_none());
+    }
+
+    #[test]
+    fn test_status_labels() {
+        let case = |status| TestCase {
+            name: "unit_test".to_string(),
+            suite: None,
+            status,
+            output: None,
+            duration: None,
+        };
+        assert_eq!(status_label(&case(TestStatus::Passed)), "ok");
+        assert_eq!(status_label(&case(TestStatus::Failed)), "FAILED");
+        assert_eq!(status_label(&case(TestStatus::TimedOut)), "TIMEOUT");
+    }
+
     // =========================================================================
     // Release Flag Tests
     // =========================================================================
@@ -244,11 +389,23 @@ mod tests {
 
     #[test]
     fn test_test_complex_invocation() {
-        let args = parse_test_args(&["test", "--release", "-j", "4", "unit_test", "smoke_test"]);
+        let args = parse_test_args(&[
+            "test",
+            "--release",
+            "-j",
+            "4",
+            "--target",
+            "unit_test",
+            "smoke",
+            "--",
+            "--seed=1",
+        ]);
 
         assert!(args.release);
         assert_eq!(args.jobs, Some(4));
-        assert_eq!(args.targets, vec!["unit_test", "smoke_test"]);
+        assert_eq!(args.targets, vec!["unit_test"]);
+        assert_eq!(args.filter.as_deref(), Some("smoke"));
+        assert_eq!(args.args, vec!["--seed=1"]);
     }
 
     // =========================================================================
@@ -366,6 +523,9 @@ mod tests {
             target_triple: None,
             locked: false,
             vcpkg: VcpkgConfig::default(),
+            coverage: false,
+            fuzz: false,
+            diagnostics: DiagnosticFormat::Human,
         };
 
         assert!(opts.release);
@@ -393,6 +553,9 @@ mod tests {
             target_triple: None,
             locked: false,
             vcpkg: VcpkgConfig::default(),
+            coverage: false,
+            fuzz: false,
+            diagnostics: DiagnosticFormat::Human,
         };
 
         assert!(!opts.release); // Default is debug mode
@@ -7,9 +7,9 @@ use anyhow::Result;
 use crate::cli::TreeArgs;
 use harbour::core::abi::TargetTriple;
 use harbour::core::Workspace;
-use harbour::ops::resolve::resolve_workspace;
+use harbour::ops::resolve::{resolve_workspace, warn_yanked_and_deprecated};
 use harbour::resolver::Resolve;
-use harbour::sources::SourceCache;
+use harbour::sources::{Fetcher, SourceCache};
 use harbour::util::config::load_config;
 use harbour::util::{GlobalContext, VcpkgIntegration};
 use harbour::PackageId;
@@ -25,9 +25,12 @@ pub fn execute(args: TreeArgs) -> Result<()> {
         &ctx.project_harbour_dir().join("config.toml"),
     );
     let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false);
-    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
 
     let resolve = resolve_workspace(&ws, &mut source_cache)?;
+    warn_yanked_and_deprecated(&resolve, &mut source_cache);
 
     // Find root package
     let root_id = ws.root_package_id();
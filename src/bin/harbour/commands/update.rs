@@ -7,7 +7,7 @@ use crate::GlobalOptions;
 use harbour::core::abi::TargetTriple;
 use harbour::core::Workspace;
 use harbour::ops::harbour_update::{update, UpdateOptions};
-use harbour::sources::SourceCache;
+use harbour::sources::{Fetcher, SourceCache};
 use harbour::util::config::load_config;
 use harbour::util::{GlobalContext, Status, VcpkgIntegration};
 
@@ -23,7 +23,9 @@ pub fn execute(args: UpdateArgs, global_opts: &GlobalOptions) -> Result<()> {
         &ctx.project_harbour_dir().join("config.toml"),
     );
     let vcpkg = VcpkgIntegration::from_config(&config.vcpkg, &TargetTriple::host(), false);
-    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg);
+    let mut source_cache = SourceCache::new_with_vcpkg(ctx.cache_dir(), vcpkg)
+        .with_registries(config.registry_list())
+        .with_fetcher(Fetcher::from_config(&config.net));
 
     let opts = UpdateOptions {
         packages: args.packages,
@@ -0,0 +1,43 @@
+//! `harbour yank` command
+
+use anyhow::Result;
+
+use crate::cli::YankArgs;
+use crate::GlobalOptions;
+use harbour::ops::harbour_yank::{yank, YankOptions};
+use harbour::util::Status;
+
+pub fn execute(args: YankArgs, global_opts: &GlobalOptions) -> Result<()> {
+    let shell = &global_opts.shell;
+
+    let opts = YankOptions {
+        registry_path: args.registry_path,
+        name: args.package,
+        version: args.version,
+        undo: args.undo,
+    };
+
+    let result = yank(&opts)?;
+    let action = if opts.undo { "unyanked" } else { "yanked" };
+
+    if !result.changed {
+        shell.status(
+            Status::Skipped,
+            format!("{} v{} is already {}", opts.name, opts.version, action),
+        );
+        return Ok(());
+    }
+
+    shell.status(
+        Status::Updated,
+        format!(
+            "{} v{} {} in {}",
+            opts.name,
+            opts.version,
+            action,
+            result.shim_path.display()
+        ),
+    );
+
+    Ok(())
+}
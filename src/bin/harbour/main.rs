@@ -53,9 +53,13 @@ fn run() -> Result<()> {
         .parse::<ColorChoice>()
         .unwrap_or(ColorChoice::Auto);
 
-    // Determine if JSON mode is requested (only for build command)
-    let json_mode =
-        matches!(&cli.command, Commands::Build(args) if args.message_format == MessageFormat::Json);
+    // Determine if JSON mode is requested (only for build, check and test commands)
+    let json_mode = match &cli.command {
+        Commands::Build(args) => args.message_format == MessageFormat::Json,
+        Commands::Check(args) => args.message_format == MessageFormat::Json,
+        Commands::Test(args) => args.message_format == MessageFormat::Json,
+        _ => false,
+    };
 
     // Create shell with appropriate mode
     let shell = Arc::new(Shell::from_flags(
@@ -77,6 +81,8 @@ fn run() -> Result<()> {
         Commands::New(args) => commands::new::execute(args),
         Commands::Init(args) => commands::init::execute(args),
         Commands::Build(args) => commands::build::execute(args, &global_opts),
+        Commands::Check(args) => commands::check::execute(args, &global_opts),
+        Commands::Run(args) => commands::run::execute(args),
         Commands::Add(args) => commands::add::execute(args, &global_opts),
         Commands::Remove(args) => commands::remove::execute(args, &global_opts),
         Commands::Update(args) => commands::update::execute(args, &global_opts),
@@ -87,12 +93,19 @@ fn run() -> Result<()> {
         Commands::Explain(args) => commands::explain::execute(args),
         Commands::Linkplan(args) => commands::linkplan::execute(args),
         Commands::Test(args) => commands::test::execute(args),
+        Commands::Coverage(args) => commands::coverage::execute(args),
+        Commands::Fuzz(args) => commands::fuzz::execute(args),
+        Commands::Bench(args) => commands::bench::execute(args),
         Commands::Toolchain(args) => commands::toolchain::execute(args),
         Commands::Backend(args) => commands::backend::execute(args),
         Commands::Ffi(args) => commands::ffi::execute(args),
+        Commands::Abi(args) => commands::abi::execute(args),
         Commands::Doctor(args) => commands::doctor::execute(args, cli.verbose),
         Commands::Verify(args) => commands::verify::execute(args, cli.verbose),
         Commands::Completions(args) => commands::completions::execute(args),
         Commands::Search(args) => commands::search::execute(args),
+        Commands::Package(args) => commands::package::execute(args, &global_opts),
+        Commands::Publish(args) => commands::publish::execute(args, &global_opts),
+        Commands::Yank(args) => commands::yank::execute(args, &global_opts),
     }
 }
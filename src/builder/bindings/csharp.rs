@@ -0,0 +1,1381 @@
+//! C# P/Invoke binding generator.
+//!
+//! Generates a C# source file with `[StructLayout]` structs, enums,
+//! callback delegates, `SafeHandle` wrappers for opaque handles and a static
+//! class of `[DllImport]` (or source-generated `[LibraryImport]`)
+//! declarations, plus an optional MSBuild props file that copies the
+//! `harbour ffi bundle` output next to the application.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use super::layout::{bitfield_units, LayoutEngine, StructLayout, TargetAbi};
+use super::types::{
+    CConstant, CEnum, CField, CFunction, CStruct, CType, CallingConvention, Declared, ParsedHeader,
+    TypeTable,
+};
+
+/// C# keywords that must be escaped with `@` when used as identifiers.
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "checked",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "delegate",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "extern",
+    "false",
+    "finally",
+    "fixed",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "implicit",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "out",
+    "override",
+    "params",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sbyte",
+    "sealed",
+    "short",
+    "sizeof",
+    "stackalloc",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "uint",
+    "ulong",
+    "unchecked",
+    "unsafe",
+    "ushort",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Element types C# allows in `fixed` buffers.
+const FIXED_BUFFER_TYPES: &[&str] = &[
+    "bool", "byte", "char", "short", "int", "long", "sbyte", "ushort", "uint", "ulong", "float",
+    "double",
+];
+
+/// C# binding generator.
+pub struct CSharpGenerator {
+    /// Library name
+    library_name: String,
+    /// Namespace for generated types (defaults to the library name)
+    namespace: Option<String>,
+    /// Name of the static class holding the native methods
+    class_name: String,
+    /// Use `[LibraryImport]` instead of `[DllImport]`
+    library_import: bool,
+    /// Library name passed to the runtime loader (defaults to the library name)
+    lib_path: String,
+    /// Bundle directory, relative to the props file
+    bundle_dir: String,
+    /// Prefix the parser stripped from names
+    strip_prefix: Option<String>,
+    /// ABI explicit struct layouts are computed for
+    abi: TargetAbi,
+}
+
+/// Where a C type appears, which decides how it's marshalled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Param,
+    Return,
+    Field,
+}
+
+/// A C# type with an optional marshalling attribute.
+struct CsType {
+    /// `MarshalAs(...)` attribute body, without brackets
+    marshal: Option<&'static str>,
+    /// Type expression
+    ty: String,
+}
+
+impl CsType {
+    fn plain(ty: impl Into<String>) -> Self {
+        CsType {
+            marshal: None,
+            ty: ty.into(),
+        }
+    }
+
+    fn marshalled(marshal: &'static str, ty: impl Into<String>) -> Self {
+        CsType {
+            marshal: Some(marshal),
+            ty: ty.into(),
+        }
+    }
+}
+
+/// An opaque pointer type wrapped in a `SafeHandle`.
+struct Handle {
+    /// Pointee type name as referenced in the header
+    pointee: String,
+    /// Generated handle class name
+    class_name: String,
+    /// C function that releases the handle
+    destructor: String,
+}
+
+/// Members generated for an explicit-layout struct.
+#[derive(Default)]
+struct ExplicitFields {
+    /// Nested type declarations
+    nested: String,
+    /// `[FieldOffset]` fields
+    fields: String,
+    /// Bitfield storage members emitted so far
+    bitfield_count: usize,
+}
+
+impl ExplicitFields {
+    fn into_body(self) -> String {
+        self.nested + &self.fields
+    }
+}
+
+/// Names assigned while generating one header.
+struct Names<'a> {
+    types: TypeTable<'a>,
+    /// Delegate names for function pointer signatures
+    delegates: Vec<(CType, String)>,
+    /// SafeHandle wrappers for opaque pointers
+    handles: Vec<Handle>,
+}
+
+impl Names<'_> {
+    fn delegate_name(&self, ctype: &CType) -> Option<&str> {
+        self.delegates
+            .iter()
+            .find(|(t, _)| t == ctype)
+            .map(|(_, name)| name.as_str())
+    }
+
+    fn handle_for(&self, pointee: &CType) -> Option<&Handle> {
+        let name = match pointee {
+            CType::Struct(name) | CType::TypeDef(name) | CType::Opaque(name) => name,
+            _ => return None,
+        };
+        self.handles.iter().find(|h| &h.pointee == name)
+    }
+
+    fn is_destructor(&self, func: &CFunction) -> bool {
+        self.handles.iter().any(|h| h.destructor == func.name)
+    }
+}
+
+impl CSharpGenerator {
+    /// Create a new C# generator.
+    pub fn new(library_name: impl Into<String>) -> Self {
+        CSharpGenerator {
+            library_name: library_name.into(),
+            namespace: None,
+            class_name: "NativeMethods".to_string(),
+            library_import: false,
+            lib_path: String::new(),
+            bundle_dir: "ffi_bundle".to_string(),
+            strip_prefix: None,
+            abi: TargetAbi::host(),
+        }
+    }
+
+    /// Set the namespace.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Set the name of the native methods class.
+    pub fn with_class_name(mut self, class_name: impl Into<String>) -> Self {
+        self.class_name = class_name.into();
+        self
+    }
+
+    /// Use source-generated `[LibraryImport]` (.NET 7+) instead of `[DllImport]`.
+    pub fn with_library_import(mut self, library_import: bool) -> Self {
+        self.library_import = library_import;
+        self
+    }
+
+    /// Set the library path.
+    pub fn with_lib_path(mut self, path: impl Into<String>) -> Self {
+        self.lib_path = path.into();
+        self
+    }
+
+    /// Set the bundle directory copied by the props file.
+    pub fn with_bundle_dir(mut self, dir: impl Into<String>) -> Self {
+        self.bundle_dir = dir.into();
+        self
+    }
+
+    /// Set the prefix the header parser stripped from names.
+    ///
+    /// It's re-applied to function names to find the library's symbols.
+    pub fn with_strip_prefix(mut self, prefix: Option<String>) -> Self {
+        self.strip_prefix = prefix;
+        self
+    }
+
+    /// Set the ABI explicit struct layouts are computed for.
+    pub fn with_abi(mut self, abi: TargetAbi) -> Self {
+        self.abi = abi;
+        self
+    }
+
+    /// File name for the generated source.
+    pub fn source_file_name(&self) -> String {
+        format!("{}.cs", pascal_case(&self.library_name))
+    }
+
+    /// File name for the generated MSBuild props file.
+    pub fn props_file_name(&self) -> String {
+        format!("{}.Native.props", pascal_case(&self.library_name))
+    }
+
+    /// Generate C# bindings from parsed headers.
+    pub fn generate(&self, header: &ParsedHeader) -> Result<String> {
+        let ctx = self.names(header);
+        let mut output = String::new();
+
+        // File header and usings
+        output.push_str(&self.generate_header(&ctx));
+
+        let namespace = self
+            .namespace
+            .clone()
+            .unwrap_or_else(|| pascal_case(&self.library_name));
+        output.push_str(&format!("namespace {}\n{{\n", namespace));
+
+        let mut items = Vec::new();
+
+        for enum_def in &header.enums {
+            items.push(self.generate_enum(enum_def));
+        }
+
+        if !self.library_import {
+            for (ctype, name) in &ctx.delegates {
+                items.push(self.generate_delegate(ctype, name, &ctx));
+            }
+        }
+
+        for struct_def in &header.structs {
+            items.push(self.generate_struct(struct_def, &ctx)?);
+        }
+
+        for handle in &ctx.handles {
+            items.push(self.generate_handle(handle));
+        }
+
+        items.push(self.generate_class(header, &ctx));
+
+        output.push_str(&items.join("\n"));
+        output.push_str("}\n");
+
+        Ok(output)
+    }
+
+    /// Generate to a file.
+    pub fn generate_to_file(&self, header: &ParsedHeader, path: &Path) -> Result<()> {
+        let content = self.generate(header)?;
+        write_file(path, &content)
+    }
+
+    /// Generate an MSBuild props file that copies the bundled native library.
+    pub fn generate_props(&self) -> String {
+        let bundle = format!("$(MSBuildThisFileDirectory){}", self.bundle_dir);
+        format!(
+            r#"<!--
+  Native library for {name} from `harbour ffi bundle`.
+
+  Auto-generated by Harbour FFI binding generator.
+  Import it from your project file:
+
+    <Import Project="{props}" />
+-->
+<Project>
+  <PropertyGroup>
+    <AllowUnsafeBlocks>true</AllowUnsafeBlocks>
+  </PropertyGroup>
+  <ItemGroup>
+    <None Include="{bundle}/**/*"
+          Exclude="{bundle}/bundle_manifest.json"
+          Link="%(RecursiveDir)%(Filename)%(Extension)"
+          CopyToOutputDirectory="PreserveNewest"
+          Visible="false" />
+  </ItemGroup>
+</Project>
+"#,
+            name = self.library_name,
+            props = self.props_file_name(),
+            bundle = bundle,
+        )
+    }
+
+    /// Generate the props file to a file.
+    pub fn generate_props_to_file(&self, path: &Path) -> Result<()> {
+        write_file(path, &self.generate_props())
+    }
+
+    /// Assign delegate and handle names for a header.
+    fn names<'a>(&'a self, header: &'a ParsedHeader) -> Names<'a> {
+        let mut ctx = Names {
+            types: TypeTable::new(header, self.strip_prefix.as_deref()),
+            delegates: Vec::new(),
+            handles: Vec::new(),
+        };
+
+        // Typedef'd callbacks keep their own name; inline ones are named
+        // after where they appear
+        let mut add_delegate = |ctype: &CType, name: String| {
+            if matches!(ctype, CType::FunctionPointer { .. })
+                && !ctx.delegates.iter().any(|(t, _)| t == ctype)
+            {
+                ctx.delegates.push((ctype.clone(), name));
+            }
+        };
+        for typedef in &header.typedefs {
+            add_delegate(&typedef.underlying_type, pascal_case(&typedef.name));
+        }
+        for s in &header.structs {
+            for field in &s.fields {
+                add_delegate(
+                    &field.field_type,
+                    format!(
+                        "{}{}Callback",
+                        pascal_case(&s.name),
+                        pascal_case(&field.name)
+                    ),
+                );
+            }
+        }
+        for func in &header.functions {
+            for param in &func.params {
+                add_delegate(
+                    &param.param_type,
+                    format!(
+                        "{}{}Callback",
+                        pascal_case(&func.name),
+                        pascal_case(&param.name)
+                    ),
+                );
+            }
+            add_delegate(
+                &func.return_type,
+                format!("{}Callback", pascal_case(&func.name)),
+            );
+        }
+
+        // An opaque pointer gets a SafeHandle when the header has a
+        // function that looks like its destructor
+        for func in &header.functions {
+            if !func.is_destructor() {
+                continue;
+            }
+            let pointee = match &func.params[0].param_type {
+                CType::Pointer(inner) => match inner.as_ref() {
+                    CType::Struct(name) | CType::TypeDef(name) | CType::Opaque(name) => name,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if ctx.types.lookup(pointee).is_some()
+                || ctx.handles.iter().any(|h| &h.pointee == pointee)
+            {
+                continue;
+            }
+            let base = match self.strip_prefix {
+                Some(ref prefix) => pointee.strip_prefix(prefix.as_str()).unwrap_or(pointee),
+                None => pointee,
+            };
+            ctx.handles.push(Handle {
+                pointee: pointee.clone(),
+                class_name: format!("{}Handle", pascal_case(base)),
+                destructor: func.name.clone(),
+            });
+        }
+
+        ctx
+    }
+
+    /// Generate file header comment and usings.
+    fn generate_header(&self, ctx: &Names<'_>) -> String {
+        let mut output = format!(
+            r#"// <auto-generated>
+// FFI bindings for {}.
+//
+// Auto-generated by Harbour FFI binding generator.
+// Do not edit manually.
+// </auto-generated>
+
+using System;
+"#,
+            self.library_name
+        );
+
+        if self.library_import {
+            output.push_str("using System.Runtime.CompilerServices;\n");
+        }
+        output.push_str("using System.Runtime.InteropServices;\n");
+        if !ctx.handles.is_empty() {
+            output.push_str("using Microsoft.Win32.SafeHandles;\n");
+        }
+        output.push('\n');
+        output
+    }
+
+    /// Generate enum definition.
+    fn generate_enum(&self, enum_def: &CEnum) -> String {
+        let mut output = String::new();
+
+        if let Some(ref doc) = enum_def.doc {
+            output.push_str(&doc_comment(doc, "    "));
+        }
+
+        let mut values = Vec::new();
+        let mut next_value = 0i64;
+        for variant in &enum_def.variants {
+            let value = variant.value.unwrap_or(next_value);
+            values.push((cs_ident(&variant.name), value));
+            next_value = value + 1;
+        }
+
+        output.push_str(&format!(
+            "    public enum {} : {}\n    {{\n",
+            pascal_case(&enum_def.name),
+            enum_underlying_type(values.iter().map(|(_, v)| *v))
+        ));
+        for (name, value) in values {
+            output.push_str(&format!("        {} = {},\n", name, value));
+        }
+        output.push_str("    }\n");
+        output
+    }
+
+    /// Generate a delegate for a callback signature.
+    fn generate_delegate(&self, ctype: &CType, name: &str, ctx: &Names<'_>) -> String {
+        let CType::FunctionPointer {
+            return_type,
+            param_types,
+        } = ctype
+        else {
+            return String::new();
+        };
+
+        let params: Vec<String> = param_types
+            .iter()
+            .enumerate()
+            .map(|(i, p)| param_decl(&self.cs_type(p, Position::Param, ctx), &format!("arg{}", i)))
+            .collect();
+        let ret = self.cs_type(return_type, Position::Return, ctx);
+
+        let mut output = String::from("    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]\n");
+        if let Some(marshal) = ret.marshal {
+            output.push_str(&format!("    [return: {}]\n", marshal));
+        }
+        output.push_str(&format!(
+            "    public delegate {} {}({});\n",
+            ret.ty,
+            name,
+            params.join(", ")
+        ));
+        output
+    }
+
+    /// Generate a struct.
+    ///
+    /// Plain and packed structs use sequential layout. Unions, bitfields,
+    /// nested definitions and over-aligned structs have no sequential
+    /// equivalent, so they get an explicit layout with the offsets computed
+    /// for the target ABI.
+    fn generate_struct(&self, struct_def: &CStruct, ctx: &Names<'_>) -> Result<String> {
+        let explicit = struct_def.is_union()
+            || struct_def.has_bitfields()
+            || struct_def.align.is_some()
+            || struct_def
+                .fields
+                .iter()
+                .any(|f| matches!(f.field_type, CType::Aggregate(_)));
+
+        let mut output = String::new();
+        if let Some(ref doc) = struct_def.doc {
+            output.push_str(&doc_comment(doc, "    "));
+        }
+
+        let fields = if explicit {
+            let engine = LayoutEngine::new(&ctx.types, self.abi);
+            let layout = engine.layout(struct_def)?;
+            let mut members = ExplicitFields::default();
+            self.explicit_fields(struct_def, &layout, 0, &mut members, &engine, ctx)?;
+
+            output.push_str(&format!(
+                "    // Explicit layout computed for the {} ABI\n",
+                self.abi.describe()
+            ));
+            output.push_str(&format!(
+                "    [StructLayout(LayoutKind.Explicit, Size = {})]\n",
+                layout.size
+            ));
+            members.into_body()
+        } else {
+            match struct_def.pack {
+                Some(pack) => output.push_str(&format!(
+                    "    [StructLayout(LayoutKind.Sequential, Pack = {})]\n",
+                    pack
+                )),
+                None => output.push_str("    [StructLayout(LayoutKind.Sequential)]\n"),
+            }
+            struct_def
+                .fields
+                .iter()
+                .map(|field| self.generate_field(field, ctx))
+                .collect()
+        };
+
+        let unsafe_kw = if fields.contains(" fixed ") || fields.contains("delegate*") {
+            "unsafe "
+        } else {
+            ""
+        };
+        output.push_str(&format!(
+            "    public {}struct {}\n    {{\n{}    }}\n",
+            unsafe_kw,
+            pascal_case(&struct_def.name),
+            fields
+        ));
+        Ok(output)
+    }
+
+    /// Generate `[FieldOffset]` fields for an explicit-layout struct.
+    ///
+    /// Members of anonymous structs and unions are flattened into the
+    /// parent at their absolute offsets, as C makes them accessible there;
+    /// named inline definitions become nested types. Runs of bitfields are
+    /// stored in an integer (or fixed byte buffer) covering their bits.
+    fn explicit_fields(
+        &self,
+        def: &CStruct,
+        layout: &StructLayout,
+        base: u64,
+        out: &mut ExplicitFields,
+        engine: &LayoutEngine<'_>,
+        ctx: &Names<'_>,
+    ) -> Result<()> {
+        let units = bitfield_units(layout);
+
+        for (i, (field, placed)) in def.fields.iter().zip(&layout.fields).enumerate() {
+            let offset = base + placed.offset;
+
+            if let Some(unit) = units.iter().find(|u| u.first == i) {
+                let bits: Vec<String> = (unit.first..unit.end)
+                    .filter(|&j| !def.fields[j].name.is_empty())
+                    .map(|j| {
+                        let f = &layout.fields[j];
+                        let start = f.start_bit() - unit.offset * 8;
+                        format!(
+                            "{} (bits {}..{})",
+                            def.fields[j].name,
+                            start,
+                            f.end_bit() - unit.offset * 8
+                        )
+                    })
+                    .collect();
+                let storage = match (unit.is_integer(), unit.size) {
+                    (true, 1) => format!("byte _bitfield{}", out.bitfield_count),
+                    (true, 2) => format!("ushort _bitfield{}", out.bitfield_count),
+                    (true, 4) => format!("uint _bitfield{}", out.bitfield_count),
+                    (true, _) => format!("ulong _bitfield{}", out.bitfield_count),
+                    (false, size) => {
+                        format!("fixed byte _bitfield{}[{}]", out.bitfield_count, size)
+                    }
+                };
+                out.fields.push_str(&format!(
+                    "        /// <summary>Bitfields: {}</summary>\n        [FieldOffset({})]\n        public {};\n",
+                    bits.join(", "),
+                    base + unit.offset,
+                    storage
+                ));
+                out.bitfield_count += 1;
+                continue;
+            }
+            if field.bit_width.is_some() {
+                continue;
+            }
+
+            let name = cs_ident(&field.name);
+            match &field.field_type {
+                CType::Aggregate(inner) => {
+                    let inner_layout = placed.layout.as_deref().unwrap_or(layout);
+                    if let Some(anonymous) = field.anonymous() {
+                        self.explicit_fields(anonymous, inner_layout, offset, out, engine, ctx)?;
+                        continue;
+                    }
+
+                    // Named inline definitions become nested types
+                    let type_name = format!(
+                        "{}{}",
+                        pascal_case(&field.name),
+                        pascal_case(inner.kind.keyword())
+                    );
+                    let mut members = ExplicitFields::default();
+                    self.explicit_fields(inner, inner_layout, 0, &mut members, engine, ctx)?;
+                    let body = members.into_body();
+                    let unsafe_kw = if body.contains(" fixed ") {
+                        "unsafe "
+                    } else {
+                        ""
+                    };
+                    let indent = |s: &str| -> String {
+                        s.lines()
+                            .map(|l| {
+                                if l.is_empty() {
+                                    "\n".to_string()
+                                } else {
+                                    format!("    {}\n", l)
+                                }
+                            })
+                            .collect()
+                    };
+                    out.nested.push_str(&format!(
+                        "        [StructLayout(LayoutKind.Explicit, Size = {})]\n        public {}struct {}\n        {{\n{}        }}\n\n",
+                        inner_layout.size,
+                        unsafe_kw,
+                        type_name,
+                        indent(&body)
+                    ));
+                    out.fields.push_str(&format!(
+                        "        [FieldOffset({})]\n        public {} {};\n",
+                        offset, type_name, name
+                    ));
+                }
+                CType::Array(..) => {
+                    // Fixed buffers, or one field per element; managed
+                    // arrays can't be placed at explicit offsets
+                    let mut count = 1;
+                    let mut element = &field.field_type;
+                    while let CType::Array(inner, size) = element {
+                        count *= size;
+                        element = inner;
+                    }
+                    let element_ty = self
+                        .value_type(element, ctx)
+                        .unwrap_or_else(|| "IntPtr".to_string());
+                    if FIXED_BUFFER_TYPES.contains(&element_ty.as_str()) {
+                        out.fields.push_str(&format!(
+                            "        [FieldOffset({})]\n        public fixed {} {}[{}];\n",
+                            offset, element_ty, name, count
+                        ));
+                    } else {
+                        let (size, _) = engine.size_align(element)?;
+                        for k in 0..count {
+                            out.fields.push_str(&format!(
+                                "        [FieldOffset({})]\n        public {} {}_{};\n",
+                                offset + k as u64 * size,
+                                element_ty,
+                                field.name,
+                                k
+                            ));
+                        }
+                    }
+                }
+                // Delegates are managed references, which can't overlap
+                CType::FunctionPointer { .. } if !self.library_import => {
+                    out.fields.push_str(&format!(
+                        "        [FieldOffset({})]\n        public IntPtr {};\n",
+                        offset, name
+                    ));
+                }
+                other => {
+                    let ty = match self.cs_type(other, Position::Field, ctx) {
+                        ty if ty.ty == "bool" => CsType::plain("byte"),
+                        ty => ty,
+                    };
+                    out.fields.push_str(&format!(
+                        "        [FieldOffset({})]\n        public {} {};\n",
+                        offset, ty.ty, name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate a single struct field.
+    fn generate_field(&self, field: &CField, ctx: &Names<'_>) -> String {
+        let name = cs_ident(&field.name);
+
+        if let CType::Array(..) = field.field_type {
+            // Flatten nested arrays into one run of elements
+            let mut count = 1;
+            let mut element = &field.field_type;
+            while let CType::Array(inner, size) = element {
+                count *= size;
+                element = inner;
+            }
+
+            let element_ty = self
+                .value_type(element, ctx)
+                .unwrap_or_else(|| self.cs_type(element, Position::Field, ctx).ty);
+            if FIXED_BUFFER_TYPES.contains(&element_ty.as_str()) {
+                return format!("        public fixed {} {}[{}];\n", element_ty, name, count);
+            }
+            if self.library_import {
+                // Keep the struct blittable by spelling out each element
+                return (0..count)
+                    .map(|i| format!("        public {} {}_{};\n", element_ty, field.name, i))
+                    .collect();
+            }
+            return format!(
+                "        [MarshalAs(UnmanagedType.ByValArray, SizeConst = {})]\n        public {}[] {};\n",
+                count, element_ty, name
+            );
+        }
+
+        let ty = self.cs_type(&field.field_type, Position::Field, ctx);
+        match ty.marshal {
+            Some(marshal) => format!(
+                "        [{}]\n        public {} {};\n",
+                marshal, ty.ty, name
+            ),
+            None => format!("        public {} {};\n", ty.ty, name),
+        }
+    }
+
+    /// Generate a SafeHandle wrapper for an opaque pointer.
+    fn generate_handle(&self, handle: &Handle) -> String {
+        format!(
+            r#"    public sealed class {class} : SafeHandleZeroOrMinusOneIsInvalid
+    {{
+        public {class}() : base(true)
+        {{
+        }}
+
+        protected override bool ReleaseHandle()
+        {{
+            {methods}.{destructor}(handle);
+            return true;
+        }}
+    }}
+"#,
+            class = handle.class_name,
+            methods = self.class_name,
+            destructor = pascal_case(&handle.destructor),
+        )
+    }
+
+    /// Generate the static class with constants and native methods.
+    fn generate_class(&self, header: &ParsedHeader, ctx: &Names<'_>) -> String {
+        let modifiers = if self.library_import {
+            "static unsafe partial"
+        } else {
+            "static partial"
+        };
+        let lib_name = if self.lib_path.is_empty() {
+            &self.library_name
+        } else {
+            &self.lib_path
+        };
+
+        let mut output = format!(
+            "    public {} class {}\n    {{\n        public const string LibraryName = {};\n",
+            modifiers,
+            self.class_name,
+            cs_str(lib_name)
+        );
+
+        let constants: Vec<String> = header
+            .constants
+            .iter()
+            .filter_map(csharp_constant)
+            .collect();
+        if !constants.is_empty() {
+            output.push('\n');
+            for constant in constants {
+                output.push_str(&format!("        {}\n", constant));
+            }
+        }
+
+        for func in &header.functions {
+            output.push('\n');
+            output.push_str(&self.generate_function(func, ctx));
+        }
+
+        output.push_str("    }\n");
+        output
+    }
+
+    /// Generate a native method declaration.
+    fn generate_function(&self, func: &CFunction, ctx: &Names<'_>) -> String {
+        let symbol = self.symbol_name(func);
+
+        if func.variadic {
+            return format!(
+                "        // {}: variadic functions can't be called through P/Invoke\n",
+                symbol
+            );
+        }
+
+        let mut output = String::new();
+        if let Some(ref doc) = func.doc {
+            output.push_str(&doc_comment(doc, "        "));
+        }
+
+        // A destructor receives the raw handle from SafeHandle.ReleaseHandle
+        let is_destructor = ctx.is_destructor(func);
+        let params: Vec<String> = func
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let name = if p.name.is_empty() {
+                    format!("arg{}", i)
+                } else {
+                    cs_ident(&p.name)
+                };
+                let ty = if is_destructor {
+                    CsType::plain("IntPtr")
+                } else {
+                    self.cs_type(&p.param_type, Position::Param, ctx)
+                };
+                param_decl(&ty, &name)
+            })
+            .collect();
+        let ret = self.cs_type(&func.return_type, Position::Return, ctx);
+
+        if self.library_import {
+            let strings = if params.iter().any(|p| p.starts_with("string ")) {
+                ", StringMarshalling = StringMarshalling.Utf8"
+            } else {
+                ""
+            };
+            output.push_str(&format!(
+                "        [LibraryImport(LibraryName, EntryPoint = {}{})]\n",
+                cs_str(&symbol),
+                strings
+            ));
+            if let Some(conv) = call_conv_type(func.calling_convention) {
+                output.push_str(&format!(
+                    "        [UnmanagedCallConv(CallConvs = new[] {{ typeof({}) }})]\n",
+                    conv
+                ));
+            }
+        } else {
+            output.push_str(&format!(
+                "        [DllImport(LibraryName, EntryPoint = {}, CallingConvention = {})]\n",
+                cs_str(&symbol),
+                calling_convention(func.calling_convention)
+            ));
+        }
+
+        if let Some(marshal) = ret.marshal {
+            output.push_str(&format!("        [return: {}]\n", marshal));
+        }
+
+        let keyword = if self.library_import {
+            "partial"
+        } else {
+            "extern"
+        };
+        output.push_str(&format!(
+            "        public static {} {} {}({});\n",
+            keyword,
+            ret.ty,
+            pascal_case(&func.name),
+            params.join(", ")
+        ));
+        output
+    }
+
+    /// Convert a CType to a C# type for the given position.
+    fn cs_type(&self, ctype: &CType, position: Position, ctx: &Names<'_>) -> CsType {
+        match ctype {
+            CType::Void => CsType::plain("void"),
+            CType::Bool if position == Position::Field && self.library_import => {
+                CsType::plain("byte")
+            }
+            CType::Bool => CsType::marshalled("MarshalAs(UnmanagedType.U1)", "bool"),
+            CType::Pointer(inner) | CType::ConstPointer(inner) => self.cs_pointer(
+                inner,
+                matches!(ctype, CType::ConstPointer(_)),
+                position,
+                ctx,
+            ),
+            // Array parameters decay to pointers
+            CType::Array(inner, _) if position == Position::Param => {
+                self.cs_pointer(inner, false, position, ctx)
+            }
+            CType::Array(..) => CsType::plain("IntPtr"),
+            CType::Struct(name) | CType::Union(name) | CType::Enum(name) | CType::TypeDef(name) => {
+                match ctx.types.lookup(name) {
+                    Some(Declared::Typedef(t)) => self.cs_type(&t.underlying_type, position, ctx),
+                    Some(declared) => CsType::plain(pascal_case(declared.name())),
+                    None => CsType::plain("IntPtr"),
+                }
+            }
+            CType::FunctionPointer {
+                return_type,
+                param_types,
+            } => {
+                if self.library_import {
+                    let mut types: Vec<String> = param_types
+                        .iter()
+                        .map(|p| self.blittable_type(p, ctx))
+                        .collect();
+                    types.push(self.blittable_type(return_type, ctx));
+                    CsType::plain(format!("delegate* unmanaged[Cdecl]<{}>", types.join(", ")))
+                } else {
+                    match ctx.delegate_name(ctype) {
+                        Some(name) => CsType::plain(name),
+                        None => CsType::plain("IntPtr"),
+                    }
+                }
+            }
+            CType::Aggregate(_) | CType::Opaque(_) => CsType::plain("IntPtr"),
+            other => match self.value_type(other, ctx) {
+                Some(ty) => CsType::plain(ty),
+                None => CsType::plain("IntPtr"),
+            },
+        }
+    }
+
+    /// Convert a pointer to `inner` to a C# type for the given position.
+    fn cs_pointer(
+        &self,
+        inner: &CType,
+        is_const: bool,
+        position: Position,
+        ctx: &Names<'_>,
+    ) -> CsType {
+        if let Some(handle) = ctx.handle_for(inner) {
+            return match position {
+                Position::Field => CsType::plain("IntPtr"),
+                _ => CsType::plain(handle.class_name.clone()),
+            };
+        }
+
+        if position != Position::Param {
+            return CsType::plain("IntPtr");
+        }
+
+        match inner {
+            // Strings in, caller-allocated buffers out
+            CType::Char if is_const => {
+                if self.library_import {
+                    CsType::plain("string")
+                } else {
+                    CsType::marshalled("MarshalAs(UnmanagedType.LPUTF8Str)", "string")
+                }
+            }
+            CType::Char | CType::UChar => CsType::plain("byte[]"),
+            // Out-parameters for handles (`ctx_t **out`)
+            CType::Pointer(pointee) if ctx.handle_for(pointee).is_some() => CsType::plain(format!(
+                "out {}",
+                ctx.handle_for(pointee)
+                    .map(|h| h.class_name.as_str())
+                    .unwrap_or_default()
+            )),
+            CType::Pointer(_) | CType::ConstPointer(_) => CsType::plain("ref IntPtr"),
+            other => match self.value_type(other, ctx) {
+                Some(ty) if is_const => CsType::plain(format!("in {}", ty)),
+                Some(ty) => CsType::plain(format!("ref {}", ty)),
+                None => CsType::plain("IntPtr"),
+            },
+        }
+    }
+
+    /// The blittable C# value type for a C value type, if it has one.
+    fn value_type(&self, ctype: &CType, ctx: &Names<'_>) -> Option<String> {
+        let ty = match ctype {
+            CType::Int8 => "sbyte",
+            CType::Int16 => "short",
+            CType::Int32 => "int",
+            CType::Int64 => "long",
+            CType::UInt8 => "byte",
+            CType::UInt16 => "ushort",
+            CType::UInt32 => "uint",
+            CType::UInt64 => "ulong",
+            CType::Float => "float",
+            CType::Double => "double",
+            CType::Bool => "byte",
+            CType::Char | CType::UChar => "byte",
+            CType::WChar => "char",
+            CType::Size => "nuint",
+            CType::SSize | CType::PtrDiff => "nint",
+            CType::Struct(name) | CType::Union(name) | CType::Enum(name) | CType::TypeDef(name) => {
+                return match ctx.types.lookup(name)? {
+                    Declared::Typedef(t) => self.value_type(&t.underlying_type, ctx),
+                    declared => Some(pascal_case(declared.name())),
+                };
+            }
+            _ => return None,
+        };
+        Some(ty.to_string())
+    }
+
+    /// A blittable type for unmanaged function pointer signatures.
+    fn blittable_type(&self, ctype: &CType, ctx: &Names<'_>) -> String {
+        match ctype {
+            CType::Void => "void".to_string(),
+            other => self
+                .value_type(other, ctx)
+                .unwrap_or_else(|| "IntPtr".to_string()),
+        }
+    }
+
+    /// The library symbol for a function, with any stripped prefix restored.
+    fn symbol_name(&self, func: &CFunction) -> String {
+        match self.strip_prefix {
+            Some(ref prefix) => format!("{}{}", prefix, func.name),
+            None => func.name.clone(),
+        }
+    }
+}
+
+/// Write generated content, creating parent directories.
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory: {}", parent.display()))?;
+    }
+
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("failed to create file: {}", path.display()))?;
+
+    file.write_all(content.as_bytes())
+        .with_context(|| format!("failed to write to file: {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Render a parameter declaration with its marshalling attribute.
+fn param_decl(ty: &CsType, name: &str) -> String {
+    match ty.marshal {
+        Some(marshal) => format!("[{}] {} {}", marshal, ty.ty, name),
+        None => format!("{} {}", ty.ty, name),
+    }
+}
+
+/// `CallingConvention` member for `[DllImport]`.
+fn calling_convention(conv: CallingConvention) -> &'static str {
+    match conv {
+        CallingConvention::Cdecl => "CallingConvention.Cdecl",
+        CallingConvention::Stdcall => "CallingConvention.StdCall",
+        CallingConvention::Fastcall => "CallingConvention.FastCall",
+        // The x64 conventions are each platform's default
+        CallingConvention::SysV | CallingConvention::Win64 => "CallingConvention.Winapi",
+    }
+}
+
+/// `CallConv*` type for `[UnmanagedCallConv]`, if not the platform default.
+fn call_conv_type(conv: CallingConvention) -> Option<&'static str> {
+    match conv {
+        CallingConvention::Cdecl => Some("CallConvCdecl"),
+        CallingConvention::Stdcall => Some("CallConvStdcall"),
+        CallingConvention::Fastcall => Some("CallConvFastcall"),
+        CallingConvention::SysV | CallingConvention::Win64 => None,
+    }
+}
+
+/// The smallest C# enum base type that holds every value, like a C compiler would pick.
+fn enum_underlying_type(values: impl Iterator<Item = i64>) -> &'static str {
+    let (min, max) = values.fold((0i64, 0i64), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if min >= i64::from(i32::MIN) && max <= i64::from(i32::MAX) {
+        "int"
+    } else if min >= 0 && max <= i64::from(u32::MAX) {
+        "uint"
+    } else {
+        "long"
+    }
+}
+
+/// Render a `#define` as a C# constant declaration, if it has a literal value.
+fn csharp_constant(constant: &CConstant) -> Option<String> {
+    let value = constant.value.trim();
+    let name = cs_ident(&constant.name);
+
+    let (ty, value) = match constant.const_type.as_ref()? {
+        CType::Float | CType::Double => (
+            "double",
+            value.trim_end_matches(['f', 'F', 'l', 'L']).to_string(),
+        ),
+        CType::Char => ("char", value.to_string()),
+        CType::ConstPointer(_) => ("string", value.to_string()),
+        declared => {
+            let number = constant.int_value()?;
+
+            // Widen like C does when the literal doesn't fit
+            let fits = |ty: &str| match ty {
+                "int" => i32::try_from(number).is_ok(),
+                "uint" => u32::try_from(number).is_ok(),
+                "long" => i64::try_from(number).is_ok(),
+                _ => u64::try_from(number).is_ok(),
+            };
+            let start = match declared {
+                CType::UInt32 => 1,
+                CType::Int64 => 2,
+                CType::UInt64 => 3,
+                _ => 0,
+            };
+            let ty = ["int", "uint", "long", "ulong"][start..]
+                .iter()
+                .copied()
+                .find(|ty| fits(ty))?;
+
+            // C# has hex literals but no octal ones
+            let value = if constant.is_hex() {
+                value.trim_end_matches(['u', 'U', 'l', 'L']).to_string()
+            } else {
+                number.to_string()
+            };
+            (ty, value)
+        }
+    };
+
+    Some(format!("public const {} {} = {};", ty, name, value))
+}
+
+/// Render a doc string as an XML doc comment.
+fn doc_comment(doc: &str, indent: &str) -> String {
+    let escaped = doc
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let mut output = format!("{}/// <summary>\n", indent);
+    for line in escaped.lines() {
+        output.push_str(&format!("{}/// {}\n", indent, line.trim()));
+    }
+    output.push_str(&format!("{}/// </summary>\n", indent));
+    output
+}
+
+/// Convert a C name (`mylib_point_t`) to PascalCase (`MylibPoint`).
+fn pascal_case(name: &str) -> String {
+    let name = name.strip_suffix("_t").unwrap_or(name);
+    let mut output = String::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            output.push(first.to_ascii_uppercase());
+            output.push_str(chars.as_str());
+        }
+    }
+    if output.is_empty() || output.starts_with(|c: char| c.is_ascii_digit()) {
+        output.insert(0, '_');
+    }
+    output
+}
+
+/// Escape a C# keyword with `@`.
+fn cs_ident(name: &str) -> String {
+    if CSHARP_KEYWORDS.contains(&name) {
+        format!("@{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Quote a string as a C# string literal.
+fn cs_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::bindings::types::{CEnumVariant, CParam, CTypedef};
+    use crate::test_support::golden::{assert_golden, ffi_project_header, golden_abi};
+
+    #[test]
+    fn test_golden_csharp() {
+        let output = CSharpGenerator::new("mylib")
+            .with_strip_prefix(Some("mylib_".to_string()))
+            .with_abi(golden_abi())
+            .generate(&ffi_project_header())
+            .unwrap();
+        assert_golden(&output, "Mylib.cs");
+    }
+
+    /// A header with an opaque handle, a callback typedef and arrays.
+    fn handle_header() -> ParsedHeader {
+        let ctx_ptr = || CType::Pointer(Box::new(CType::TypeDef("ctx_t".to_string())));
+        let mut header = ParsedHeader::default();
+        header.typedefs.push(CTypedef::new(
+            "log_fn",
+            CType::FunctionPointer {
+                return_type: Box::new(CType::Void),
+                param_types: vec![CType::ConstPointer(Box::new(CType::Char))],
+            },
+        ));
+        header.structs.push(
+            CStruct::new("sample")
+                .with_field(CField::new(
+                    "values",
+                    CType::Array(Box::new(CType::Float), 4),
+                ))
+                .with_field(CField::new(
+                    "callback",
+                    CType::TypeDef("log_fn".to_string()),
+                )),
+        );
+        header.functions.push(
+            CFunction::new("ctx_open", ctx_ptr())
+                .with_param(CParam::new(
+                    "path",
+                    CType::ConstPointer(Box::new(CType::Char)),
+                ))
+                .with_param(CParam::new("log", CType::TypeDef("log_fn".to_string()))),
+        );
+        header.functions.push(
+            CFunction::new("ctx_read", CType::Bool)
+                .with_param(CParam::new("ctx", ctx_ptr()))
+                .with_param(CParam::new(
+                    "out",
+                    CType::Pointer(Box::new(CType::Struct("sample".to_string()))),
+                )),
+        );
+        header.functions.push(
+            CFunction::new("ctx_close", CType::Void).with_param(CParam::new("ctx", ctx_ptr())),
+        );
+        header
+    }
+
+    #[test]
+    fn test_dllimport_handles_and_delegates() {
+        let output = CSharpGenerator::new("demo")
+            .generate(&handle_header())
+            .unwrap();
+
+        assert!(output.contains(
+            "public delegate void LogFn([MarshalAs(UnmanagedType.LPUTF8Str)] string arg0);"
+        ));
+        assert!(
+            output.contains("public sealed class CtxHandle : SafeHandleZeroOrMinusOneIsInvalid")
+        );
+        assert!(output.contains("NativeMethods.CtxClose(handle);"));
+        assert!(output.contains("public unsafe struct Sample"));
+        assert!(output.contains("public fixed float values[4];"));
+        assert!(output.contains("public LogFn callback;"));
+        assert!(output.contains(
+            "public static extern CtxHandle CtxOpen([MarshalAs(UnmanagedType.LPUTF8Str)] string path, LogFn log);"
+        ));
+        assert!(output.contains("[return: MarshalAs(UnmanagedType.U1)]"));
+        assert!(
+            output.contains("public static extern bool CtxRead(CtxHandle ctx, ref Sample @out);")
+        );
+        assert!(output.contains("public static extern void CtxClose(IntPtr ctx);"));
+    }
+
+    #[test]
+    fn test_library_import() {
+        let output = CSharpGenerator::new("demo")
+            .with_library_import(true)
+            .generate(&handle_header())
+            .unwrap();
+
+        assert!(output.contains("public static unsafe partial class NativeMethods"));
+        assert!(output.contains(
+            "[LibraryImport(LibraryName, EntryPoint = \"ctx_open\", StringMarshalling = StringMarshalling.Utf8)]"
+        ));
+        assert!(output.contains("[UnmanagedCallConv(CallConvs = new[] { typeof(CallConvCdecl) })]"));
+        assert!(output.contains(
+            "public static partial CtxHandle CtxOpen(string path, delegate* unmanaged[Cdecl]<IntPtr, void> log);"
+        ));
+        assert!(!output.contains("public delegate "));
+    }
+
+    #[test]
+    fn test_enum_underlying_type() {
+        let gen = CSharpGenerator::new("test");
+        let enum_def = CEnum::new("flags_t")
+            .with_variant(CEnumVariant::new("FLAG_LOW").with_value(1))
+            .with_variant(CEnumVariant::new("FLAG_HIGH").with_value(0x8000_0000));
+
+        let output = gen.generate_enum(&enum_def);
+        assert!(output.contains("public enum Flags : uint"));
+        assert!(output.contains("FLAG_HIGH = 2147483648,"));
+    }
+
+    #[test]
+    fn test_csharp_constant() {
+        let constant = |value: &str, ty: CType| CConstant {
+            name: "X".to_string(),
+            value: value.to_string(),
+            const_type: Some(ty),
+        };
+
+        assert_eq!(
+            csharp_constant(&constant("1024", CType::Int32)).as_deref(),
+            Some("public const int X = 1024;")
+        );
+        assert_eq!(
+            csharp_constant(&constant("0755", CType::Int32)).as_deref(),
+            Some("public const int X = 493;")
+        );
+        assert_eq!(
+            csharp_constant(&constant("0xFFFFFFFF", CType::Int32)).as_deref(),
+            Some("public const uint X = 0xFFFFFFFF;")
+        );
+        assert_eq!(
+            csharp_constant(&constant("10ULL", CType::UInt64)).as_deref(),
+            Some("public const ulong X = 10;")
+        );
+    }
+
+    #[test]
+    fn test_props_copies_bundle() {
+        let props = CSharpGenerator::new("mylib").generate_props();
+        assert!(props.contains("<Import Project=\"Mylib.Native.props\" />"));
+        assert!(props.contains("Include=\"$(MSBuildThisFileDirectory)ffi_bundle/**/*\""));
+        assert!(props.contains("<AllowUnsafeBlocks>true</AllowUnsafeBlocks>"));
+    }
+}
@@ -0,0 +1,538 @@
+//! Struct and union layout for FFI binding generation.
+//!
+//! Generators need the exact size, alignment and field offsets a C compiler
+//! would produce for the target, since unions, bitfields, packing and
+//! nested anonymous members can't be described field-by-field in every
+//! binding language. Layouts follow the System V rules used by GCC and
+//! Clang, or MSVC's rules for bitfield placement on Windows.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::types::{CStruct, CType, Declared, TypeTable};
+use crate::core::abi::TargetTriple;
+
+/// How bitfields are packed into storage units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BitfieldRules {
+    /// GCC/Clang: bitfields share storage across declared types and only
+    /// move to the next unit if they would straddle their type's alignment
+    SysV,
+    /// MSVC: a new storage unit starts whenever the declared type size
+    /// changes or the current unit is full
+    Msvc,
+}
+
+/// The C ABI properties that affect aggregate layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetAbi {
+    /// Size and alignment of pointers, `size_t` and function pointers
+    pub pointer_size: u64,
+    /// Size and alignment of `wchar_t`
+    pub wchar_size: u64,
+    /// Alignment of 64-bit integers and doubles inside aggregates
+    pub int64_align: u64,
+    /// Bitfield placement rules
+    pub bitfields: BitfieldRules,
+}
+
+impl TargetAbi {
+    /// The ABI of the host.
+    pub fn host() -> Self {
+        Self::for_target(&TargetTriple::host())
+    }
+
+    /// The ABI of a target triple.
+    pub fn for_target(triple: &TargetTriple) -> Self {
+        let arch = triple.arch.as_str();
+        let pointer_size = match arch {
+            "x86" | "i386" | "i586" | "i686" | "wasm32" | "mips" | "mipsel" | "powerpc" => 4,
+            a if a.starts_with("arm") || a.starts_with("thumb") => 4,
+            _ => 8,
+        };
+        let windows = triple.os == "windows";
+        let x86_32 = matches!(arch, "x86" | "i386" | "i586" | "i686");
+
+        TargetAbi {
+            pointer_size,
+            wchar_size: if windows { 2 } else { 4 },
+            int64_align: if x86_32 && !windows { 4 } else { 8 },
+            // MinGW defaults to MSVC-compatible bitfields as well
+            bitfields: if windows {
+                BitfieldRules::Msvc
+            } else {
+                BitfieldRules::SysV
+            },
+        }
+    }
+
+    /// Short description for generated comments, e.g. `64-bit sysv`.
+    pub fn describe(&self) -> String {
+        let rules = match self.bitfields {
+            BitfieldRules::SysV => "sysv",
+            BitfieldRules::Msvc => "msvc",
+        };
+        format!("{}-bit {}", self.pointer_size * 8, rules)
+    }
+}
+
+/// Computed layout of a struct or union.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructLayout {
+    /// Size in bytes, including tail padding
+    pub size: u64,
+    /// Alignment in bytes
+    pub align: u64,
+    /// One entry per field, in declaration order
+    pub fields: Vec<FieldLayout>,
+}
+
+/// Computed placement of one field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldLayout {
+    /// Field name (empty for anonymous members and unnamed bitfields)
+    pub name: String,
+    /// Byte offset; for bitfields, the byte holding the first bit
+    pub offset: u64,
+    /// Size in bytes; for bitfields, the bytes the bits touch
+    pub size: u64,
+    /// Alignment the field contributed
+    pub align: u64,
+    /// Bit position within the byte at `offset`, for bitfields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bit_offset: Option<u32>,
+    /// Width in bits, for bitfields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bit_width: Option<u32>,
+    /// Layout of an inline nested struct or union
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Box<StructLayout>>,
+}
+
+impl FieldLayout {
+    /// First bit of the field, counted from the start of the aggregate.
+    pub fn start_bit(&self) -> u64 {
+        self.offset * 8 + u64::from(self.bit_offset.unwrap_or(0))
+    }
+
+    /// One past the last bit of the field.
+    pub fn end_bit(&self) -> u64 {
+        match self.bit_width {
+            Some(width) => self.start_bit() + u64::from(width),
+            None => (self.offset + self.size) * 8,
+        }
+    }
+}
+
+/// A run of adjacent bitfields that generators store as one member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitfieldUnit {
+    /// Index of the first field in the run
+    pub first: usize,
+    /// One past the index of the last field in the run
+    pub end: usize,
+    /// Byte offset of the storage
+    pub offset: u64,
+    /// Storage size in bytes
+    pub size: u64,
+}
+
+impl BitfieldUnit {
+    /// Whether the storage can be a single naturally aligned integer.
+    pub fn is_integer(&self) -> bool {
+        matches!(self.size, 1 | 2 | 4 | 8) && self.offset.is_multiple_of(self.size)
+    }
+}
+
+/// Group the bitfields of a struct into storage units.
+///
+/// Consecutive bitfields that share a byte go into the same unit, and each
+/// unit covers whole bytes from its first bit to its last, so storage never
+/// overlaps a regular field.
+pub fn bitfield_units(layout: &StructLayout) -> Vec<BitfieldUnit> {
+    let mut units: Vec<BitfieldUnit> = Vec::new();
+    let mut open = false;
+
+    for (i, field) in layout.fields.iter().enumerate() {
+        if field.bit_width.is_none() || field.end_bit() == field.start_bit() {
+            open = false;
+            continue;
+        }
+
+        let first_byte = field.start_bit() / 8;
+        let last_byte = field.end_bit().div_ceil(8);
+        match units.last_mut() {
+            Some(unit) if open && first_byte < unit.offset + unit.size => {
+                unit.end = i + 1;
+                unit.size = unit.size.max(last_byte - unit.offset);
+            }
+            _ => units.push(BitfieldUnit {
+                first: i,
+                end: i + 1,
+                offset: first_byte,
+                size: last_byte - first_byte,
+            }),
+        }
+        open = true;
+    }
+
+    units
+}
+
+/// Deepest nesting of aggregates and typedefs followed before giving up.
+const MAX_DEPTH: usize = 64;
+
+/// Computes layouts against the types declared in a header.
+pub struct LayoutEngine<'a> {
+    types: &'a TypeTable<'a>,
+    abi: TargetAbi,
+}
+
+impl<'a> LayoutEngine<'a> {
+    /// Create an engine for `abi`.
+    pub fn new(types: &'a TypeTable<'a>, abi: TargetAbi) -> Self {
+        LayoutEngine { types, abi }
+    }
+
+    /// The ABI layouts are computed for.
+    pub fn abi(&self) -> &TargetAbi {
+        &self.abi
+    }
+
+    /// Compute the layout of a struct or union.
+    pub fn layout(&self, def: &CStruct) -> Result<StructLayout> {
+        self.aggregate(def, 0).map_err(|e| {
+            let name = if def.name.is_empty() {
+                "anonymous aggregate"
+            } else {
+                &def.name
+            };
+            anyhow!("cannot lay out `{}`: {}", name, e)
+        })
+    }
+
+    /// Size and alignment of a type.
+    pub fn size_align(&self, ty: &CType) -> Result<(u64, u64)> {
+        self.type_layout(ty, 0)
+    }
+
+    fn type_layout(&self, ty: &CType, depth: usize) -> Result<(u64, u64)> {
+        if depth > MAX_DEPTH {
+            bail!("type nesting is too deep");
+        }
+
+        let ptr = self.abi.pointer_size;
+        Ok(match ty {
+            CType::Int8 | CType::UInt8 | CType::Bool | CType::Char | CType::UChar => (1, 1),
+            CType::Int16 | CType::UInt16 => (2, 2),
+            CType::Int32 | CType::UInt32 | CType::Float => (4, 4),
+            CType::Int64 | CType::UInt64 | CType::Double => (8, self.abi.int64_align),
+            CType::WChar => (self.abi.wchar_size, self.abi.wchar_size),
+            CType::Size
+            | CType::SSize
+            | CType::PtrDiff
+            | CType::Pointer(_)
+            | CType::ConstPointer(_)
+            | CType::FunctionPointer { .. } => (ptr, ptr),
+            CType::Array(inner, len) => {
+                let (size, align) = self.type_layout(inner, depth + 1)?;
+                (size * *len as u64, align)
+            }
+            CType::Aggregate(def) => {
+                let layout = self.aggregate(def, depth + 1)?;
+                (layout.size, layout.align)
+            }
+            CType::Struct(name) | CType::Union(name) | CType::Enum(name) | CType::TypeDef(name) => {
+                match self.types.lookup(name) {
+                    Some(Declared::Struct(def)) => {
+                        let layout = self.aggregate(def, depth + 1)?;
+                        (layout.size, layout.align)
+                    }
+                    Some(Declared::Typedef(t)) => {
+                        self.type_layout(&t.underlying_type, depth + 1)?
+                    }
+                    // Enumerations are `int` unless a value doesn't fit
+                    Some(Declared::Enum(_)) => (4, 4),
+                    None if matches!(ty, CType::Enum(_)) => (4, 4),
+                    None => bail!("`{}` is not declared, so its size is unknown", name),
+                }
+            }
+            CType::Void => bail!("`void` has no size"),
+            CType::Opaque(name) => bail!("`{}` has no known size", name),
+        })
+    }
+
+    fn aggregate(&self, def: &CStruct, depth: usize) -> Result<StructLayout> {
+        let mut fields = Vec::with_capacity(def.fields.len());
+        let mut align = 1;
+        let mut size_bits = 0;
+
+        // Current bit position, and the open MSVC storage unit (size, bits used)
+        let mut pos = 0u64;
+        let mut unit: Option<(u64, u64)> = None;
+
+        for field in &def.fields {
+            let (size, natural) = self.type_layout(&field.field_type, depth + 1)?;
+            let field_align = def.pack.map_or(natural, |pack| natural.min(pack));
+            let nested = match &field.field_type {
+                CType::Aggregate(inner) => Some(Box::new(self.aggregate(inner, depth + 1)?)),
+                _ => None,
+            };
+
+            let Some(width) = field.bit_width else {
+                if def.is_union() {
+                    pos = 0;
+                } else {
+                    if let Some((unit_size, used)) = unit.take() {
+                        pos += unit_size * 8 - used;
+                    }
+                    pos = pos.next_multiple_of(field_align * 8);
+                }
+                fields.push(FieldLayout {
+                    name: field.name.clone(),
+                    offset: pos / 8,
+                    size,
+                    align: field_align,
+                    bit_offset: None,
+                    bit_width: None,
+                    layout: nested,
+                });
+                align = align.max(field_align);
+                pos += size * 8;
+                size_bits = size_bits.max(pos);
+                continue;
+            };
+
+            if u64::from(width) > size * 8 {
+                bail!(
+                    "bitfield `{}` is wider than its type ({} > {} bits)",
+                    field.name,
+                    width,
+                    size * 8
+                );
+            }
+
+            if def.is_union() {
+                pos = 0;
+            } else {
+                match self.abi.bitfields {
+                    BitfieldRules::SysV => {
+                        let packed = def.pack.is_some_and(|pack| pack < natural);
+                        if width == 0 {
+                            pos = pos.next_multiple_of(natural * 8);
+                        } else if !packed && pos % (size * 8) + u64::from(width) > size * 8 {
+                            pos = pos.next_multiple_of(field_align * 8);
+                        }
+                    }
+                    BitfieldRules::Msvc => {
+                        let fits = unit.is_some_and(|(unit_size, used)| {
+                            width > 0 && unit_size == size && used + u64::from(width) <= size * 8
+                        });
+                        if let Some((unit_size, used)) = unit.filter(|_| !fits) {
+                            pos += unit_size * 8 - used;
+                            unit = None;
+                        }
+                        if width > 0 && !fits {
+                            pos = pos.next_multiple_of(field_align * 8);
+                            unit = Some((size, 0));
+                        }
+                        if let Some((_, used)) = &mut unit {
+                            *used += u64::from(width);
+                        }
+                    }
+                }
+            }
+
+            let bit_offset = (pos % 8) as u32;
+            fields.push(FieldLayout {
+                name: field.name.clone(),
+                offset: pos / 8,
+                size: (u64::from(bit_offset) + u64::from(width)).div_ceil(8),
+                align: field_align,
+                bit_offset: Some(bit_offset),
+                bit_width: Some(width),
+                layout: None,
+            });
+
+            // Unnamed bitfields don't affect alignment
+            if !field.name.is_empty() || self.abi.bitfields == BitfieldRules::Msvc {
+                align = align.max(field_align);
+            }
+            pos += u64::from(width);
+            let used_bits = match (self.abi.bitfields, unit) {
+                // The whole MSVC storage unit counts towards the size
+                (BitfieldRules::Msvc, Some((unit_size, used))) => pos + unit_size * 8 - used,
+                _ => pos,
+            };
+            size_bits = size_bits.max(if def.is_union() { size * 8 } else { used_bits });
+        }
+
+        if let Some(min) = def.align {
+            align = align.max(min);
+        }
+
+        Ok(StructLayout {
+            size: size_bits.div_ceil(8).next_multiple_of(align),
+            align,
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::bindings::types::{CField, ParsedHeader};
+
+    const SYSV64: TargetAbi = TargetAbi {
+        pointer_size: 8,
+        wchar_size: 4,
+        int64_align: 8,
+        bitfields: BitfieldRules::SysV,
+    };
+
+    const MSVC64: TargetAbi = TargetAbi {
+        pointer_size: 8,
+        wchar_size: 2,
+        int64_align: 8,
+        bitfields: BitfieldRules::Msvc,
+    };
+
+    fn layout(def: &CStruct, abi: TargetAbi) -> StructLayout {
+        let header = ParsedHeader::default();
+        let types = TypeTable::new(&header, None);
+        LayoutEngine::new(&types, abi).layout(def).unwrap()
+    }
+
+    fn offsets(layout: &StructLayout) -> Vec<u64> {
+        layout.fields.iter().map(|f| f.offset).collect()
+    }
+
+    #[test]
+    fn test_struct_padding() {
+        let def = CStruct::new("s")
+            .with_field(CField::new("a", CType::Char))
+            .with_field(CField::new("b", CType::Int64))
+            .with_field(CField::new("c", CType::Int16));
+
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (24, 8));
+        assert_eq!(offsets(&l), [0, 8, 16]);
+
+        let i386 = TargetAbi::for_target(&TargetTriple::parse("i686-unknown-linux-gnu").unwrap());
+        let l = layout(&def, i386);
+        assert_eq!((l.size, l.align), (16, 4));
+        assert_eq!(offsets(&l), [0, 4, 12]);
+    }
+
+    #[test]
+    fn test_packing_and_alignment() {
+        let def = CStruct::new("s")
+            .with_field(CField::new("a", CType::Char))
+            .with_field(CField::new("b", CType::Int32))
+            .with_pack(1);
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (5, 1));
+        assert_eq!(offsets(&l), [0, 1]);
+
+        let def = CStruct::new("s")
+            .with_field(CField::new("a", CType::Char))
+            .with_field(CField::new("b", CType::Int64))
+            .with_pack(2);
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (10, 2));
+
+        let def = CStruct::new("s")
+            .with_field(CField::new("a", CType::Int32))
+            .with_align(16);
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (16, 16));
+    }
+
+    #[test]
+    fn test_union() {
+        let def = CStruct::union("u")
+            .with_field(CField::new("i", CType::Int32))
+            .with_field(CField::new("d", CType::Double))
+            .with_field(CField::new("b", CType::Array(Box::new(CType::Char), 13)));
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (16, 8));
+        assert_eq!(offsets(&l), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_anonymous_members() {
+        let inner = CStruct::union("")
+            .with_field(CField::new("i", CType::Int32))
+            .with_field(CField::new("p", CType::Pointer(Box::new(CType::Void))));
+        let def = CStruct::new("s")
+            .with_field(CField::new("tag", CType::Char))
+            .with_field(CField::new("", CType::Aggregate(Box::new(inner))));
+
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (16, 8));
+        assert_eq!(offsets(&l), [0, 8]);
+        assert_eq!(l.fields[1].layout.as_ref().unwrap().size, 8);
+    }
+
+    #[test]
+    fn test_bitfields_sysv() {
+        // struct { char a; int b : 3; int c : 30; unsigned char d : 4; }
+        let def = CStruct::new("s")
+            .with_field(CField::new("a", CType::Char))
+            .with_field(CField::bitfield("b", CType::Int32, 3))
+            .with_field(CField::bitfield("c", CType::Int32, 30))
+            .with_field(CField::bitfield("d", CType::UInt8, 4));
+
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (12, 4));
+        assert_eq!(l.fields[1].start_bit(), 8);
+        assert_eq!(l.fields[2].start_bit(), 32);
+        assert_eq!(l.fields[3].start_bit(), 64);
+
+        let units = bitfield_units(&l);
+        let spans: Vec<_> = units.iter().map(|u| (u.first, u.offset, u.size)).collect();
+        assert_eq!(spans, [(1, 1, 1), (2, 4, 4), (3, 8, 1)]);
+        assert!(units.iter().all(BitfieldUnit::is_integer));
+    }
+
+    #[test]
+    fn test_bitfields_msvc() {
+        let def = CStruct::new("s")
+            .with_field(CField::new("a", CType::Char))
+            .with_field(CField::bitfield("b", CType::Int32, 3))
+            .with_field(CField::bitfield("c", CType::Int32, 30))
+            .with_field(CField::bitfield("d", CType::UInt8, 4));
+
+        let l = layout(&def, MSVC64);
+        assert_eq!((l.size, l.align), (16, 4));
+        assert_eq!(l.fields[1].start_bit(), 32);
+        assert_eq!(l.fields[2].start_bit(), 64);
+        assert_eq!(l.fields[3].start_bit(), 96);
+    }
+
+    #[test]
+    fn test_packed_bitfields_straddle() {
+        let def = CStruct::new("s")
+            .with_field(CField::bitfield("a", CType::UInt32, 7))
+            .with_field(CField::bitfield("b", CType::UInt32, 30))
+            .with_pack(1);
+
+        let l = layout(&def, SYSV64);
+        assert_eq!((l.size, l.align), (5, 1));
+        assert_eq!(l.fields[1].start_bit(), 7);
+        let units = bitfield_units(&l);
+        assert_eq!(units.len(), 1);
+        assert!(!units[0].is_integer());
+    }
+
+    #[test]
+    fn test_undeclared_type_is_an_error() {
+        let def = CStruct::new("s").with_field(CField::new("x", CType::TypeDef("foo_t".into())));
+        let header = ParsedHeader::default();
+        let types = TypeTable::new(&header, None);
+        let err = LayoutEngine::new(&types, SYSV64).layout(&def).unwrap_err();
+        assert!(err.to_string().contains("`foo_t` is not declared"));
+    }
+}
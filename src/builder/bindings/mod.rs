@@ -3,13 +3,25 @@
 //! This module provides parsing and code generation for creating
 //! foreign language bindings from C header files.
 
+pub mod csharp;
+pub mod layout;
 pub mod parser;
+pub mod preprocess;
+pub mod probe;
+pub mod python;
+pub mod rust;
 pub mod types;
 pub mod typescript;
 
+pub use csharp::CSharpGenerator;
+pub use layout::{LayoutEngine, StructLayout, TargetAbi};
 pub use parser::HeaderParser;
+pub use preprocess::HeaderPreprocessor;
+pub use probe::{LayoutDiff, LayoutProbe};
+pub use python::PythonGenerator;
+pub use rust::RustGenerator;
 pub use types::{
-    CConstant, CEnum, CEnumVariant, CField, CFunction, CParam, CStruct, CType, CTypedef,
-    CallingConvention, ParsedHeader,
+    AggregateKind, CConstant, CEnum, CEnumVariant, CField, CFunction, CParam, CStruct, CType,
+    CTypedef, CallingConvention, Declared, ParsedHeader, TypeTable,
 };
 pub use typescript::TypeScriptGenerator;
@@ -3,14 +3,15 @@
 //! Parses C header files to extract functions, structs, enums, and typedefs
 //! for generating foreign language bindings.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use regex::Regex;
 
+use super::preprocess::filter_to_headers;
 use super::types::{
-    CConstant, CEnum, CEnumVariant, CField, CFunction, CParam, CStruct, CType, CTypedef,
-    CallingConvention, ParsedHeader,
+    AggregateKind, CConstant, CEnum, CEnumVariant, CField, CFunction, CParam, CStruct, CType,
+    CTypedef, CallingConvention, ParsedHeader,
 };
 
 /// Parser for C header files.
@@ -74,16 +75,35 @@ impl HeaderParser {
 
     /// Parse header content.
     pub fn parse_content(&self, content: &str, path: &Path) -> Result<ParsedHeader> {
+        // Use original for #defines
+        Ok(self.parse_declarations(content, content, path))
+    }
+
+    /// Parse the output of a `HeaderPreprocessor`.
+    ///
+    /// Only declarations and `#define`s that came from `headers` are kept;
+    /// anything pulled in from other includes is left out.
+    pub fn parse_preprocessed(&self, output: &str, headers: &[PathBuf]) -> Result<ParsedHeader> {
+        let lines = filter_to_headers(output, headers);
+        let source = headers.first().cloned().unwrap_or_default();
+        Ok(self.parse_declarations(&lines.code, &lines.defines, &source))
+    }
+
+    /// Parse declarations from `content` and constants from `defines`.
+    fn parse_declarations(&self, content: &str, defines: &str, path: &Path) -> ParsedHeader {
         let mut header = ParsedHeader::new(path);
 
-        // Preprocess: remove comments, normalize whitespace
+        // Preprocess: remove comments, attributes, normalize whitespace
         let preprocessed = self.preprocess(content);
 
+        // Parse constants first, since array sizes may refer to them
+        header.constants = self.parse_constants(defines);
+
         // Parse functions
         header.functions = self.parse_functions(&preprocessed);
 
-        // Parse structs
-        header.structs = self.parse_structs(&preprocessed);
+        // Parse structs and unions
+        header.structs = self.parse_structs(&preprocessed, &header.constants);
 
         // Parse enums
         header.enums = self.parse_enums(&preprocessed);
@@ -91,10 +111,7 @@ impl HeaderParser {
         // Parse typedefs
         header.typedefs = self.parse_typedefs(&preprocessed);
 
-        // Parse constants
-        header.constants = self.parse_constants(content); // Use original for #defines
-
-        Ok(header)
+        header
     }
 
     /// Preprocess header content.
@@ -107,6 +124,14 @@ impl HeaderParser {
         let re_line = Regex::new(r"//[^\n]*").unwrap();
         let content = re_line.replace_all(&content, " ");
 
+        // Remove attributes and keywords that expanded export macros leave
+        // behind, keeping packing since struct layout depends on it
+        let content = strip_attributes(&content);
+        let re_kw =
+            Regex::new(r"\b(?:__extension__|__restrict__|__restrict|__inline__|__inline)\b")
+                .unwrap();
+        let content = re_kw.replace_all(&content, " ");
+
         // Normalize whitespace
         let re_ws = Regex::new(r"\s+").unwrap();
         re_ws.replace_all(&content, " ").to_string()
@@ -228,8 +253,10 @@ impl HeaderParser {
         parts.reverse();
 
         if parts.len() == 2 {
-            let type_str = parts[0].to_string();
-            let name = parts[1].to_string();
+            // Keep `*` on the type side for `int *name`
+            let stars = parts[1].len() - parts[1].trim_start_matches('*').len();
+            let type_str = format!("{}{}", parts[0], &parts[1][..stars]);
+            let name = parts[1][stars..].to_string();
 
             // Make sure name looks like an identifier
             if name.chars().all(|c| c.is_alphanumeric() || c == '_') {
@@ -240,73 +267,216 @@ impl HeaderParser {
         None
     }
 
-    /// Parse struct definitions.
-    fn parse_structs(&self, content: &str) -> Vec<CStruct> {
-        let mut structs = Vec::new();
-
-        // Match: struct name { fields };
-        // or: typedef struct { fields } name;
-        let re = Regex::new(r"(?:typedef\s+)?struct\s+(\w+)?\s*\{([^}]*)\}\s*(\w+)?\s*;").unwrap();
-
-        for cap in re.captures_iter(content) {
-            let struct_name = cap.get(1).map_or("", |m| m.as_str());
-            let body = cap.get(2).map_or("", |m| m.as_str());
-            let typedef_name = cap.get(3).map_or("", |m| m.as_str());
-
-            // Prefer typedef name over struct name
-            let name = if !typedef_name.is_empty() {
-                typedef_name
-            } else if !struct_name.is_empty() {
-                struct_name
-            } else {
-                continue; // Anonymous struct
-            };
+    /// Parse struct and union definitions.
+    ///
+    /// Tracks `#pragma pack` across the header. Named aggregates defined
+    /// inside another one are hoisted to the top level, as in C.
+    fn parse_structs(&self, content: &str, constants: &[CConstant]) -> Vec<CStruct> {
+        let re = Regex::new(r"#pragma\s+pack\s*\(([^)]*)\)|\b(?:struct|union)\b|[{}]").unwrap();
 
-            if !self.should_include_type(name) {
-                continue;
+        let mut structs = Vec::new();
+        let mut pack = PackState::default();
+        // Whether each open brace is a real scope (not `extern "C" {`)
+        let mut braces: Vec<bool> = Vec::new();
+        let mut pos = 0;
+
+        while let Some(cap) = re.captures_at(content, pos) {
+            let m = cap.get(0).unwrap();
+            pos = m.end();
+
+            match m.as_str() {
+                "{" => braces.push(!content[..m.start()].trim_end().ends_with("extern \"C\"")),
+                "}" => {
+                    braces.pop();
+                }
+                _ if braces.iter().any(|scope| *scope) => {}
+                "struct" | "union" => {
+                    let Some(agg) = self.parse_aggregate(
+                        content,
+                        m.start(),
+                        pack.current,
+                        constants,
+                        &mut structs,
+                    ) else {
+                        continue;
+                    };
+                    pos = agg.end;
+
+                    // Prefer the typedef name over the tag
+                    let typedef = content[..m.start()].trim_end().ends_with("typedef");
+                    let typedef_name = split_top_level(&agg.declarators, ',')
+                        .into_iter()
+                        .map(str::trim)
+                        .find(|d| is_identifier(d));
+                    let mut def = agg.def;
+                    let name = match (typedef, typedef_name, &agg.tag) {
+                        (true, Some(name), _) => {
+                            def.typedef = true;
+                            name.to_string()
+                        }
+                        (_, _, Some(tag)) => tag.clone(),
+                        _ => continue, // Anonymous struct
+                    };
+                    self.push_struct(&mut structs, def, &name);
+                }
+                _ => pack.apply(&cap[1]),
             }
+        }
 
-            let fields = self.parse_fields(body);
-            let final_name = self.maybe_strip_prefix(name);
+        structs
+    }
 
-            structs.push(CStruct {
-                name: final_name,
-                fields,
-                packed: content.contains("__attribute__((packed))"),
-                doc: None,
-            });
+    /// Add a named aggregate, applying the type filters and prefix.
+    fn push_struct(&self, structs: &mut Vec<CStruct>, mut def: CStruct, name: &str) {
+        if self.should_include_type(name) {
+            def.name = self.maybe_strip_prefix(name);
+            structs.push(def);
         }
+    }
 
-        structs
+    /// Parse a `struct`/`union` definition starting at `start`.
+    ///
+    /// Returns `None` if the keyword only names a type, as in `struct foo *p`.
+    fn parse_aggregate(
+        &self,
+        content: &str,
+        start: usize,
+        pack: Option<u64>,
+        constants: &[CConstant],
+        hoisted: &mut Vec<CStruct>,
+    ) -> Option<Aggregate> {
+        let rest = &content[start..];
+        let (kind, rest) = if let Some(rest) = rest.strip_prefix("struct") {
+            (AggregateKind::Struct, rest)
+        } else {
+            (AggregateKind::Union, rest.strip_prefix("union")?)
+        };
+
+        let mut def = CStruct {
+            kind,
+            pack,
+            ..CStruct::new("")
+        };
+
+        let rest = take_attributes(rest, &mut def);
+        let tag_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let tag = (tag_len > 0).then(|| rest[..tag_len].to_string());
+        let rest = take_attributes(&rest[tag_len..], &mut def);
+
+        let body = rest.strip_prefix('{')?;
+        let close = matching_brace(body)?;
+        def.fields = self.parse_members(&body[..close], pack, constants, hoisted);
+
+        let after = take_attributes(&body[close + 1..], &mut def);
+        let semi = after.find(';').unwrap_or(after.len());
+
+        Some(Aggregate {
+            def,
+            tag,
+            declarators: after[..semi].trim().to_string(),
+            end: content.len() - after.len() + semi,
+        })
     }
 
-    /// Parse struct fields.
-    fn parse_fields(&self, body: &str) -> Vec<CField> {
+    /// Parse the members of a struct or union body.
+    fn parse_members(
+        &self,
+        body: &str,
+        pack: Option<u64>,
+        constants: &[CConstant],
+        hoisted: &mut Vec<CStruct>,
+    ) -> Vec<CField> {
+        let fn_ptr = Regex::new(
+            r"^(.+?)\(\s*(?:__cdecl|__stdcall|__fastcall|WINAPI)?\s*\*\s*(\w+)\s*\)\s*\((.*)\)$",
+        )
+        .unwrap();
+        let volatile = Regex::new(r"\bvolatile\b").unwrap();
+
         let mut fields = Vec::new();
 
-        for line in body.split(';') {
-            let line = line.trim();
-            if line.is_empty() {
+        for member in split_top_level(body, ';') {
+            let member = member.trim();
+            if member.is_empty() || member.starts_with("#pragma") {
                 continue;
             }
 
-            // Handle bitfields: type name : width
-            let (line, bit_width) = if line.contains(':') {
-                let parts: Vec<&str> = line.splitn(2, ':').collect();
-                let width: Option<u32> = parts.get(1).and_then(|w| w.trim().parse().ok());
-                (parts[0], width)
-            } else {
-                (line, None)
-            };
+            // Nested struct or union definitions
+            if member.starts_with("struct") || member.starts_with("union") {
+                if let Some(agg) = self.parse_aggregate(member, 0, pack, constants, hoisted) {
+                    let declarators = split_top_level(&agg.declarators, ',');
+                    let base = match &agg.tag {
+                        Some(tag) => {
+                            let ty = match agg.def.kind {
+                                AggregateKind::Struct => CType::Struct(tag.clone()),
+                                AggregateKind::Union => CType::Union(tag.clone()),
+                            };
+                            self.push_struct(hoisted, agg.def, tag);
+                            ty
+                        }
+                        // C11 anonymous member
+                        None if agg.declarators.is_empty() => {
+                            fields.push(CField::new("", CType::Aggregate(Box::new(agg.def))));
+                            continue;
+                        }
+                        None => CType::Aggregate(Box::new(agg.def)),
+                    };
+
+                    fields.extend(
+                        declarators
+                            .into_iter()
+                            .filter_map(|d| declarator(base.clone(), false, d, constants)),
+                    );
+                    continue;
+                }
+            }
 
-            // Split into type and name
-            if let Some((field_type, field_name)) = self.split_param(line) {
-                fields.push(CField {
-                    name: field_name,
-                    field_type: CType::parse(&field_type),
-                    bit_width,
-                });
+            let member = volatile.replace_all(member, " ");
+            let member = strip_canonical_attributes(&member);
+
+            // Nested enum definitions are plain `int`s (or the named enum)
+            if let Some(rest) = member.strip_prefix("enum") {
+                if let (Some(open), Some(close)) = (rest.find('{'), rest.find('}')) {
+                    let tag = rest[..open].trim();
+                    let base = if tag.is_empty() {
+                        CType::Int32
+                    } else {
+                        CType::Enum(tag.to_string())
+                    };
+                    fields.extend(
+                        split_top_level(&rest[close + 1..], ',')
+                            .into_iter()
+                            .filter_map(|d| declarator(base.clone(), false, d, constants)),
+                    );
+                    continue;
+                }
+            }
+
+            // Function pointer members: ret (*name)(params)
+            if let Some(cap) = fn_ptr.captures(&member) {
+                let (params, _) = self.parse_params(&cap[3]);
+                fields.push(CField::new(
+                    &cap[2],
+                    CType::FunctionPointer {
+                        return_type: Box::new(CType::parse(&cap[1])),
+                        param_types: params.into_iter().map(|p| p.param_type).collect(),
+                    },
+                ));
+                continue;
             }
+
+            // One base type, possibly several declarators: int a, *b, c[4];
+            let mut declarators = split_top_level(&member, ',').into_iter();
+            let Some((base, first)) = declarators.next().and_then(split_base_type) else {
+                continue;
+            };
+            let is_const = base.starts_with("const ");
+            let base = CType::parse(&base);
+            fields.extend(declarator(base.clone(), is_const, &first, constants));
+            fields.extend(
+                declarators.filter_map(|d| declarator(base.clone(), is_const, d, constants)),
+            );
         }
 
         fields
@@ -398,8 +568,11 @@ impl HeaderParser {
             let underlying = cap.get(1).map_or("", |m| m.as_str()).trim();
             let name = cap.get(2).map_or("", |m| m.as_str());
 
-            // Skip struct/enum typedefs (handled by parse_structs/parse_enums)
-            if underlying.starts_with("struct") || underlying.starts_with("enum") {
+            // Skip struct/union/enum typedefs (handled by parse_structs/parse_enums)
+            if underlying.starts_with("struct")
+                || underlying.starts_with("union")
+                || underlying.starts_with("enum")
+            {
                 continue;
             }
 
@@ -416,6 +589,40 @@ impl HeaderParser {
             });
         }
 
+        typedefs.extend(self.parse_function_pointer_typedefs(content));
+        typedefs
+    }
+
+    /// Parse function pointer typedefs: `typedef ret (*name)(params);`.
+    fn parse_function_pointer_typedefs(&self, content: &str) -> Vec<CTypedef> {
+        let mut typedefs = Vec::new();
+
+        let re = Regex::new(
+            r"typedef\s+([\w\s*]+?)\s*\(\s*(?:__cdecl|__stdcall|__fastcall|WINAPI)?\s*\*\s*(\w+)\s*\)\s*\(([^)]*)\)\s*;",
+        )
+        .unwrap();
+
+        for cap in re.captures_iter(content) {
+            let return_type = cap.get(1).map_or("", |m| m.as_str()).trim();
+            let name = cap.get(2).map_or("", |m| m.as_str());
+            let params_str = cap.get(3).map_or("", |m| m.as_str());
+
+            if !self.should_include_type(name) {
+                continue;
+            }
+
+            let (params, _) = self.parse_params(params_str);
+
+            typedefs.push(CTypedef {
+                name: self.maybe_strip_prefix(name),
+                underlying_type: CType::FunctionPointer {
+                    return_type: Box::new(CType::parse(return_type)),
+                    param_types: params.into_iter().map(|p| p.param_type).collect(),
+                },
+                doc: None,
+            });
+        }
+
         typedefs
     }
 
@@ -547,6 +754,276 @@ impl HeaderParser {
     }
 }
 
+/// A struct or union definition found by `HeaderParser::parse_aggregate`.
+struct Aggregate {
+    /// The definition, not yet named
+    def: CStruct,
+    /// Tag after the keyword, if any
+    tag: Option<String>,
+    /// Declarators between the closing brace and the `;`
+    declarators: String,
+    /// Offset of the terminating `;`
+    end: usize,
+}
+
+/// `#pragma pack` state while scanning a header.
+#[derive(Default)]
+struct PackState {
+    current: Option<u64>,
+    stack: Vec<Option<u64>>,
+}
+
+impl PackState {
+    /// Apply the arguments of a `#pragma pack(...)`.
+    fn apply(&mut self, args: &str) {
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        let value = args.iter().find_map(|a| a.parse::<u64>().ok());
+
+        match args[0] {
+            "push" => {
+                self.stack.push(self.current);
+                if value.is_some() {
+                    self.current = value;
+                }
+            }
+            "pop" => self.current = self.stack.pop().flatten(),
+            _ => self.current = value,
+        }
+    }
+}
+
+/// Consume whitespace and canonical attributes, recording packing and
+/// alignment on `def`.
+fn take_attributes<'s>(mut s: &'s str, def: &mut CStruct) -> &'s str {
+    let aligned = Regex::new(r"^__attribute__\(\(aligned\((\d+)\)\)\)").unwrap();
+
+    loop {
+        s = s.trim_start();
+        if let Some(rest) = s.strip_prefix("__attribute__((packed))") {
+            def.pack = Some(1);
+            s = rest;
+        } else if let Some(cap) = aligned.captures(s) {
+            def.align = cap[1].parse().ok();
+            s = &s[cap[0].len()..];
+        } else {
+            return s;
+        }
+    }
+}
+
+/// Remove canonical attributes left on a member declaration.
+fn strip_canonical_attributes(s: &str) -> String {
+    let re = Regex::new(r"__attribute__\(\((?:packed|aligned\(\d+\))\)\)").unwrap();
+    re.replace_all(s, " ").trim().to_string()
+}
+
+/// Offset of the `}` closing a body that starts just after its `{`.
+fn matching_brace(body: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in body.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split on `sep`, ignoring separators nested in brackets.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            c if c == sep && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !s[start..].trim().is_empty() {
+        parts.push(&s[start..]);
+    }
+
+    parts
+}
+
+/// Keywords that can end a type, so `unsigned int : 3` has no name.
+const TYPE_WORDS: &[&str] = &[
+    "char", "short", "int", "long", "signed", "unsigned", "float", "double", "bool", "_Bool",
+];
+
+/// Split the first declaration of a member into its base type and the
+/// rest of its declarator, e.g. `unsigned int *a[4]` into `unsigned int`
+/// and `*a[4]`.
+fn split_base_type(decl: &str) -> Option<(String, String)> {
+    let (head, bits) = match decl.split_once(':') {
+        Some((head, bits)) => (head.trim(), Some(bits.trim())),
+        None => (decl.trim(), None),
+    };
+    let (head, dims) = head.split_at(head.find('[').unwrap_or(head.len()));
+    let head = head.trim_end();
+
+    let name_start = head
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |i| i + 1);
+    let (base, name) = head.split_at(name_start);
+    let (base, name) = if base.trim().is_empty() || TYPE_WORDS.contains(&name) {
+        (head, "")
+    } else {
+        (base, name)
+    };
+
+    // Pointer stars belong to the declarator
+    let base_type = base.trim_end_matches(|c: char| c == '*' || c.is_whitespace());
+    let stars: String = base[base_type.len()..]
+        .chars()
+        .filter(|c| *c == '*')
+        .collect();
+    if base_type.is_empty() {
+        return None;
+    }
+
+    let bits = bits.map(|b| format!(":{}", b)).unwrap_or_default();
+    Some((
+        base_type.to_string(),
+        format!("{}{}{}{}", stars, name, dims, bits),
+    ))
+}
+
+/// Build a field from a declarator such as `*name`, `name[4][2]` or
+/// `name : 3`, applied to `base`. A `const` base makes the innermost
+/// pointer a `ConstPointer`.
+fn declarator(base: CType, is_const: bool, decl: &str, constants: &[CConstant]) -> Option<CField> {
+    let (decl, bit_width) = match decl.split_once(':') {
+        Some((decl, bits)) => (decl, Some(const_int(bits, constants)? as u32)),
+        None => (decl, None),
+    };
+
+    let mut decl = decl.trim();
+    let mut ty = base;
+    let mut is_const = is_const;
+    while let Some(rest) = decl.strip_prefix('*') {
+        ty = if std::mem::take(&mut is_const) {
+            CType::ConstPointer(Box::new(ty))
+        } else {
+            CType::Pointer(Box::new(ty))
+        };
+        decl = rest.trim_start();
+        decl = decl.strip_prefix("const ").unwrap_or(decl).trim_start();
+    }
+
+    let mut dims = Vec::new();
+    while let Some(inner) = decl.strip_suffix(']') {
+        let open = inner.rfind('[')?;
+        dims.push(inner[open + 1..].trim());
+        decl = inner[..open].trim_end();
+    }
+    for dim in dims {
+        ty = match const_int(dim, constants) {
+            Some(len) => CType::Array(Box::new(ty), len as usize),
+            // Sizes we can't evaluate make the layout fail clearly later
+            None => CType::Opaque(format!("[{}]", dim)),
+        };
+    }
+
+    if !(is_identifier(decl) || decl.is_empty() && bit_width.is_some()) {
+        return None;
+    }
+
+    Some(CField {
+        name: decl.to_string(),
+        field_type: ty,
+        bit_width,
+    })
+}
+
+/// Evaluate an integer literal or a `#define`d integer constant.
+fn const_int(expr: &str, constants: &[CConstant]) -> Option<u64> {
+    let expr = expr.trim();
+    let value = CConstant::new("", expr).int_value().or_else(|| {
+        constants
+            .iter()
+            .find(|c| c.name == expr)
+            .and_then(CConstant::int_value)
+    })?;
+    u64::try_from(value).ok()
+}
+
+/// Whether `s` is a C identifier.
+fn is_identifier(s: &str) -> bool {
+    s.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Remove `__attribute__((...))` and `__declspec(...)` specifiers.
+///
+/// Packing and alignment attributes are kept in a canonical
+/// `__attribute__((packed))` / `__attribute__((aligned(n)))` form so struct
+/// parsing can still see them.
+fn strip_attributes(content: &str) -> String {
+    let aligned = Regex::new(r"\balign(?:ed)?\s*\(\s*(\d+)\s*\)").unwrap();
+
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+
+    loop {
+        let next = ["__attribute__", "__declspec"]
+            .iter()
+            .filter_map(|kw| rest.find(kw).map(|i| (i, kw.len())))
+            .min();
+        let Some((start, len)) = next else {
+            output.push_str(rest);
+            return output;
+        };
+
+        output.push_str(&rest[..start]);
+        let after = &rest[start + len..];
+        let args = after.trim_start();
+        if !args.starts_with('(') {
+            output.push_str(&rest[start..start + len]);
+            rest = after;
+            continue;
+        }
+
+        // Find the matching close paren
+        let mut depth = 0;
+        let mut end = args.len();
+        for (i, c) in args.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let attr = &args[..end];
+        output.push(' ');
+        if attr.contains("packed") {
+            output.push_str("__attribute__((packed)) ");
+        }
+        if let Some(cap) = aligned.captures(attr) {
+            output.push_str(&format!("__attribute__((aligned({}))) ", &cap[1]));
+        }
+        rest = &args[end..];
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -590,6 +1067,62 @@ mod tests {
         assert_eq!(s.fields.len(), 2);
     }
 
+    #[test]
+    fn test_parse_union_and_anonymous_members() {
+        let parser = HeaderParser::new();
+        let content = "typedef union { int i; float f; } number_t;\n\
+                       struct shape {\n\
+                           int kind;\n\
+                           union { struct { int w, h; } rect; int radius; };\n\
+                           struct origin { int x; int y; } at;\n\
+                       };";
+        let header = parser.parse_content(content, Path::new("test.h")).unwrap();
+
+        let names: Vec<_> = header.structs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["number_t", "origin", "shape"]);
+        assert!(header.structs[0].is_union());
+
+        let shape = &header.structs[2];
+        assert_eq!(shape.fields.len(), 3);
+        let anonymous = shape.fields[1].anonymous().unwrap();
+        assert!(anonymous.is_union());
+        assert_eq!(anonymous.fields[0].name, "rect");
+        assert!(matches!(
+            anonymous.fields[0].field_type,
+            CType::Aggregate(_)
+        ));
+        assert_eq!(
+            shape.fields[2].field_type,
+            CType::Struct("origin".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_packing_arrays_and_bitfields() {
+        let parser = HeaderParser::new();
+        let content = "#define NAME_LEN 16\n\
+                       #pragma pack(push, 2)\n\
+                       struct record { char name[NAME_LEN]; unsigned int flags : 3, : 0, mode : 2; };\n\
+                       #pragma pack(pop)\n\
+                       struct __attribute__((aligned(16))) vec4 { float v[4]; };\n\
+                       struct __attribute__((packed)) wire { char a; int b; };";
+        let header = parser.parse_content(content, Path::new("test.h")).unwrap();
+
+        let record = &header.structs[0];
+        assert_eq!(record.pack, Some(2));
+        assert_eq!(
+            record.fields[0].field_type,
+            CType::Array(Box::new(CType::Char), 16)
+        );
+        let widths: Vec<_> = record.fields[1..].iter().map(|f| f.bit_width).collect();
+        assert_eq!(widths, [Some(3), Some(0), Some(2)]);
+        assert_eq!(record.fields[2].name, "");
+
+        assert_eq!(header.structs[1].pack, None);
+        assert_eq!(header.structs[1].align, Some(16));
+        assert_eq!(header.structs[2].pack, Some(1));
+    }
+
     #[test]
     fn test_parse_enum() {
         let parser = HeaderParser::new();
@@ -603,6 +1136,58 @@ mod tests {
         assert_eq!(e.variants[0].value, Some(0));
     }
 
+    #[test]
+    fn test_parse_function_pointer_typedef() {
+        let parser = HeaderParser::new();
+        let content = "typedef void (*log_fn)(\n    int level,\n    const char* msg);";
+        let header = parser.parse_content(content, Path::new("test.h")).unwrap();
+
+        assert_eq!(header.typedefs.len(), 1);
+        let t = &header.typedefs[0];
+        assert_eq!(t.name, "log_fn");
+        match &t.underlying_type {
+            CType::FunctionPointer { param_types, .. } => assert_eq!(param_types.len(), 2),
+            other => panic!("expected function pointer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strip_attributes() {
+        let parser = HeaderParser::new();
+        let content = "__attribute__ ((visibility(\"default\"))) int api_init(void);\n\
+                       __declspec(dllimport) void api_free(void* p);";
+        let header = parser.parse_content(content, Path::new("test.h")).unwrap();
+
+        let names: Vec<_> = header.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["api_init", "api_free"]);
+    }
+
+    #[test]
+    fn test_parse_preprocessed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let own = tmp.path().join("mylib.h");
+        std::fs::write(&own, "").unwrap();
+
+        let output = format!(
+            "# 1 \"/usr/include/stdint.h\" 1 3 4\n\
+             typedef int int32_t;\n\
+             int helper(void);\n\
+             # 2 \"{}\" 2\n\
+             #define MYLIB_MAX 64\n\
+             int mylib_init(void);\n",
+            own.display()
+        );
+
+        let header = HeaderParser::new()
+            .parse_preprocessed(&output, &[own])
+            .unwrap();
+        assert_eq!(header.functions.len(), 1);
+        assert_eq!(header.functions[0].name, "mylib_init");
+        assert!(header.typedefs.is_empty());
+        assert_eq!(header.constants.len(), 1);
+        assert_eq!(header.constants[0].name, "MYLIB_MAX");
+    }
+
     #[test]
     fn test_strip_prefix() {
         let parser = HeaderParser::new().with_strip_prefix(Some("mylib_".to_string()));
@@ -0,0 +1,269 @@
+//! Compiler-driven header preprocessing for FFI binding generation.
+//!
+//! Running headers through the active toolchain's preprocessor (`-E -dD`
+//! for GCC/Clang, `/P /d1PP` for MSVC) expands export macros, resolves
+//! `#ifdef` platform branches and pulls in `#include`d types exactly as a
+//! real build would. Line markers in the output are then used to keep only
+//! the declarations and `#define`s that came from the target's own headers.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+
+use crate::builder::surface_resolver::EffectiveCompileSurface;
+use crate::builder::toolchain::{Language, PreprocessInput, Toolchain};
+use crate::util::fs::normalize_path;
+use crate::util::process::ProcessBuilder;
+
+/// Runs headers through a toolchain's preprocessor.
+pub struct HeaderPreprocessor<'a> {
+    /// Toolchain whose preprocessor to run
+    toolchain: &'a dyn Toolchain,
+    /// Language the headers are consumed as
+    lang: Language,
+    /// Include directories
+    include_dirs: Vec<PathBuf>,
+    /// Preprocessor defines (name, optional value)
+    defines: Vec<(String, Option<String>)>,
+    /// Additional compiler flags
+    cflags: Vec<String>,
+}
+
+impl<'a> HeaderPreprocessor<'a> {
+    /// Create a preprocessor for the given toolchain.
+    pub fn new(toolchain: &'a dyn Toolchain) -> Self {
+        HeaderPreprocessor {
+            toolchain,
+            lang: Language::C,
+            include_dirs: Vec::new(),
+            defines: Vec::new(),
+            cflags: Vec::new(),
+        }
+    }
+
+    /// Set the language the headers are consumed as.
+    pub fn with_lang(mut self, lang: Language) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    /// Use a target's effective include dirs, defines and flags.
+    pub fn with_compile_surface(mut self, surface: &EffectiveCompileSurface) -> Self {
+        self.include_dirs
+            .extend(surface.include_dirs.iter().cloned());
+        self.defines.extend(
+            surface
+                .defines
+                .iter()
+                .map(|d| (d.name().to_string(), d.value().map(str::to_string))),
+        );
+        self.cflags.extend(surface.cflags.iter().cloned());
+        self
+    }
+
+    /// Add an include directory.
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /// Add a preprocessor define.
+    pub fn with_define(mut self, name: impl Into<String>, value: Option<String>) -> Self {
+        self.defines.push((name.into(), value));
+        self
+    }
+
+    /// Preprocess `headers` as one translation unit and return the output.
+    pub fn run(&self, headers: &[PathBuf]) -> Result<String> {
+        let tmp = tempfile::tempdir().context("failed to create temp directory")?;
+
+        let ext = match self.lang {
+            Language::C => "c",
+            Language::Cxx => "cpp",
+        };
+        let source = tmp.path().join(format!("harbour_ffi.{}", ext));
+        let output = tmp.path().join("harbour_ffi.i");
+
+        // Absolute paths, so line markers can be matched back to the headers
+        let mut unit = String::new();
+        for header in headers {
+            let path = std::path::absolute(header)
+                .with_context(|| format!("failed to resolve header: {}", header.display()))?;
+            unit.push_str(&format!("#include \"{}\"\n", path.display()));
+        }
+        std::fs::write(&source, unit)
+            .with_context(|| format!("failed to write {}", source.display()))?;
+
+        let input = PreprocessInput {
+            source,
+            output: output.clone(),
+            include_dirs: self.include_dirs.clone(),
+            defines: self.defines.clone(),
+            cflags: self.cflags.clone(),
+        };
+        let spec = self.toolchain.preprocess_command(&input, self.lang);
+
+        let mut cmd = ProcessBuilder::new(&spec.program)
+            .args(spec.args)
+            .cwd(tmp.path());
+        for (key, value) in spec.env {
+            cmd = cmd.env(key, value);
+        }
+
+        let result = cmd.exec()?;
+        if !result.status.success() {
+            bail!(
+                "failed to preprocess headers with `{}`\n{}",
+                spec.program.display(),
+                String::from_utf8_lossy(&result.stderr).trim()
+            );
+        }
+
+        std::fs::read_to_string(&output)
+            .with_context(|| format!("failed to read preprocessed output: {}", output.display()))
+    }
+}
+
+/// Preprocessed output that came from a chosen set of headers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderLines {
+    /// Declarations, with directives removed
+    pub code: String,
+    /// Object-like `#define` lines still in effect at the end
+    pub defines: String,
+}
+
+/// Keep only the lines of preprocessed `output` that came from `headers`.
+///
+/// Understands both GCC-style (`# 12 "file.h" 1`) and MSVC-style
+/// (`#line 12 "file.h"`) line markers.
+pub fn filter_to_headers(output: &str, headers: &[PathBuf]) -> HeaderLines {
+    let marker = Regex::new(r#"^#\s*(?:line\s+)?\d+\s+"((?:[^"\\]|\\.)*)""#).unwrap();
+    let define = Regex::new(r"^#\s*define\s+(\w+)(.*)$").unwrap();
+    let undef = Regex::new(r"^#\s*undef\s+(\w+)").unwrap();
+    let pragma_pack = Regex::new(r"^#\s*pragma\s+pack\b").unwrap();
+
+    let wanted: Vec<PathBuf> = headers.iter().map(|h| normalize_path(h)).collect();
+    let mut seen: HashMap<String, bool> = HashMap::new();
+
+    let mut code = String::new();
+    let mut defines: Vec<(String, String)> = Vec::new();
+    let mut in_header = false;
+
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(cap) = marker.captures(trimmed) {
+            let file = cap[1].replace("\\\\", "\\");
+            in_header = *seen
+                .entry(file)
+                .or_insert_with_key(|file| wanted.contains(&normalize_path(Path::new(file))));
+            continue;
+        }
+
+        if !in_header {
+            continue;
+        }
+
+        if let Some(cap) = define.captures(trimmed) {
+            let name = cap[1].to_string();
+            let rest = &cap[2];
+            defines.retain(|(n, _)| *n != name);
+            // Function-like macros have no space before the parameter list
+            if !rest.starts_with('(') && !rest.trim().is_empty() {
+                defines.push((name, rest.trim().to_string()));
+            }
+        } else if let Some(cap) = undef.captures(trimmed) {
+            defines.retain(|(n, _)| *n != cap[1]);
+        } else if !trimmed.starts_with('#') || pragma_pack.is_match(trimmed) {
+            // Packing changes struct layout, so it stays with the code
+            code.push_str(line);
+            code.push('\n');
+        }
+    }
+
+    HeaderLines {
+        code,
+        defines: defines
+            .into_iter()
+            .map(|(name, value)| format!("#define {} {}\n", name, value))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::toolchain::{GccToolchain, ToolchainPlatform};
+    use crate::util::process::find_executable;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_filter_to_headers() {
+        let tmp = TempDir::new().unwrap();
+        let own = tmp.path().join("mylib.h");
+        std::fs::write(&own, "").unwrap();
+
+        let output = format!(
+            r#"# 1 "<built-in>"
+#define __STDC__ 1
+# 1 "/usr/include/stdint.h" 1 3 4
+typedef int int32_t;
+# 3 "{own}" 2
+#define MYLIB_H
+#define MYLIB_MAX 64
+#define MYLIB_TMP 1
+#define MYLIB_CHECK(x) ((x) != 0)
+#undef MYLIB_TMP
+#pragma pack(push, 1)
+#pragma once
+int mylib_init(void);
+"#,
+            own = own.display()
+        );
+
+        let lines = filter_to_headers(&output, &[own]);
+        assert_eq!(lines.code, "#pragma pack(push, 1)\nint mylib_init(void);\n");
+        assert_eq!(lines.defines, "#define MYLIB_MAX 64\n");
+    }
+
+    #[test]
+    fn test_filter_msvc_line_markers() {
+        let output = "#line 1 \"C:\\\\proj\\\\include\\\\mylib.h\"\nint mylib_init(void);\n#line 1 \"C:\\\\sdk\\\\windows.h\"\nint other(void);\n";
+
+        let lines = filter_to_headers(output, &[PathBuf::from(r"C:\proj\include\mylib.h")]);
+        assert_eq!(lines.code, "int mylib_init(void);\n");
+    }
+
+    #[test]
+    fn test_preprocess_with_gcc() {
+        let Some(cc) = find_executable("cc") else {
+            return;
+        };
+
+        let tmp = TempDir::new().unwrap();
+        let header = tmp.path().join("api.h");
+        std::fs::write(
+            &header,
+            "#ifdef API_SHARED\n#define API __attribute__((visibility(\"default\")))\n#else\n#define API\n#endif\nAPI int api_init(void);\n",
+        )
+        .unwrap();
+
+        let toolchain = GccToolchain::new(
+            cc.clone(),
+            GccToolchain::infer_cxx(&cc),
+            PathBuf::from("ar"),
+            ToolchainPlatform::Gcc,
+        );
+        let output = HeaderPreprocessor::new(&toolchain)
+            .with_define("API_SHARED", None)
+            .run(std::slice::from_ref(&header))
+            .unwrap();
+
+        let lines = filter_to_headers(&output, &[header]);
+        assert!(lines.code.contains("visibility(\"default\")"));
+        assert!(lines.code.contains("int api_init(void);"));
+    }
+}
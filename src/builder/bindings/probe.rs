@@ -0,0 +1,478 @@
+//! Compiled layout probes for FFI bindings.
+//!
+//! Generators trust `LayoutEngine` for struct sizes and offsets. A probe
+//! asks the compiler instead: it includes the target's headers, prints the
+//! size and alignment of every struct and the position of every field, and
+//! the results are compared with what the engine computed for the host.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use super::layout::{LayoutEngine, StructLayout, TargetAbi};
+use super::types::{CStruct, CType, ParsedHeader, TypeTable};
+use crate::builder::surface_resolver::EffectiveCompileSurface;
+use crate::builder::toolchain::{CompileInput, Language, Toolchain};
+use crate::core::manifest::SymbolVisibility;
+use crate::ops::verify::run_probe;
+
+/// Where a field sits, as computed or as measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    /// Byte offset from the start of the struct
+    Offset(u64),
+    /// First bit and one past the last bit, from the start of the struct
+    Bits(u64, u64),
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placement::Offset(offset) => write!(f, "offset {}", offset),
+            Placement::Bits(start, end) => write!(f, "bits {}..{}", start, end),
+        }
+    }
+}
+
+/// A field the probe reports on, named by its C member path.
+#[derive(Debug, Clone)]
+struct ProbeField {
+    path: String,
+    expected: Placement,
+}
+
+/// The engine's view of one struct.
+struct Expected<'h> {
+    def: &'h CStruct,
+    layout: Option<StructLayout>,
+    fields: Vec<ProbeField>,
+}
+
+/// What the probe printed for one struct.
+#[derive(Debug, Default)]
+struct Measured {
+    size_align: Option<(u64, u64)>,
+    fields: HashMap<String, Placement>,
+}
+
+/// A difference between the layout the bindings assume and the compiler's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutDiff {
+    /// Struct name, or `struct.member` for a field
+    pub item: String,
+    /// What the bindings assume
+    pub expected: String,
+    /// What the compiler produced
+    pub actual: String,
+}
+
+impl fmt::Display for LayoutDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: compiler has {}, bindings assume {}",
+            self.item, self.actual, self.expected
+        )
+    }
+}
+
+/// Compiles and runs layout probes with a toolchain.
+pub struct LayoutProbe<'a> {
+    /// Toolchain to compile the probe with
+    toolchain: &'a dyn Toolchain,
+    /// Language the headers are consumed as
+    lang: Language,
+    /// Include directories
+    include_dirs: Vec<PathBuf>,
+    /// Preprocessor defines (name, optional value)
+    defines: Vec<(String, Option<String>)>,
+    /// Additional compiler flags
+    cflags: Vec<String>,
+}
+
+impl<'a> LayoutProbe<'a> {
+    /// Create a probe for the given toolchain.
+    pub fn new(toolchain: &'a dyn Toolchain) -> Self {
+        LayoutProbe {
+            toolchain,
+            lang: Language::C,
+            include_dirs: Vec::new(),
+            defines: Vec::new(),
+            cflags: Vec::new(),
+        }
+    }
+
+    /// Set the language the headers are consumed as.
+    pub fn with_lang(mut self, lang: Language) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    /// Use a target's effective include dirs, defines and flags.
+    pub fn with_compile_surface(mut self, surface: &EffectiveCompileSurface) -> Self {
+        self.include_dirs
+            .extend(surface.include_dirs.iter().cloned());
+        self.defines.extend(
+            surface
+                .defines
+                .iter()
+                .map(|d| (d.name().to_string(), d.value().map(str::to_string))),
+        );
+        self.cflags.extend(surface.cflags.iter().cloned());
+        self
+    }
+
+    /// Add an include directory.
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /// Add a preprocessor define.
+    pub fn with_define(mut self, name: impl Into<String>, value: Option<String>) -> Self {
+        self.defines.push((name.into(), value));
+        self
+    }
+
+    /// Check the structs of `header`, declared in `headers`, against the
+    /// compiler.
+    ///
+    /// The header must be parsed without a prefix to strip, since the probe
+    /// names types the way C does. Layouts are computed for the host, where
+    /// the probe runs. Returns every difference found.
+    pub fn check(&self, header: &ParsedHeader, headers: &[PathBuf]) -> Result<Vec<LayoutDiff>> {
+        let types = TypeTable::new(header, None);
+        let engine = LayoutEngine::new(&types, TargetAbi::host());
+        let expected = expected_layouts(header, &engine);
+
+        let tmp = tempfile::tempdir().context("failed to create temp directory")?;
+        let ext = match self.lang {
+            Language::C => "c",
+            Language::Cxx => "cpp",
+        };
+        let source = tmp.path().join(format!("harbour_layout.{}", ext));
+        std::fs::write(&source, probe_source(&expected, headers)?)
+            .with_context(|| format!("failed to write {}", source.display()))?;
+
+        let input = CompileInput {
+            source,
+            output: tmp.path().join(format!(
+                "harbour_layout.{}",
+                self.toolchain.object_extension()
+            )),
+            include_dirs: self.include_dirs.clone(),
+            defines: self.defines.clone(),
+            cflags: self.cflags.clone(),
+            visibility: SymbolVisibility::Default,
+        };
+        let output = run_probe(self.toolchain, &input, self.lang)?;
+
+        Ok(compare(&expected, &parse_output(&output)))
+    }
+}
+
+/// Compute the expected layout of every struct in `header`.
+fn expected_layouts<'h>(header: &'h ParsedHeader, engine: &LayoutEngine<'_>) -> Vec<Expected<'h>> {
+    header
+        .structs
+        .iter()
+        .map(|def| {
+            let layout = engine.layout(def).ok();
+            let mut fields = Vec::new();
+            if let Some(ref layout) = layout {
+                probe_fields(def, layout, "", 0, &mut fields);
+            }
+            Expected {
+                def,
+                layout,
+                fields,
+            }
+        })
+        .collect()
+}
+
+/// Collect the fields a probe can measure, with their expected placement.
+///
+/// Members of anonymous structs and unions are reached through their
+/// parent, as in C; named inline definitions add a `member.` prefix.
+fn probe_fields(
+    def: &CStruct,
+    layout: &StructLayout,
+    prefix: &str,
+    base: u64,
+    out: &mut Vec<ProbeField>,
+) {
+    for (field, placed) in def.fields.iter().zip(&layout.fields) {
+        let offset = base + placed.offset;
+        if let (Some(anonymous), Some(nested)) = (field.anonymous(), &placed.layout) {
+            probe_fields(anonymous, nested, prefix, offset, out);
+            continue;
+        }
+        // Unnamed bitfields only pad
+        if field.name.is_empty() {
+            continue;
+        }
+
+        let path = format!("{}{}", prefix, field.name);
+        if placed.bit_width.is_some() {
+            out.push(ProbeField {
+                path,
+                expected: Placement::Bits(
+                    base * 8 + placed.start_bit(),
+                    base * 8 + placed.end_bit(),
+                ),
+            });
+            continue;
+        }
+
+        out.push(ProbeField {
+            path: path.clone(),
+            expected: Placement::Offset(offset),
+        });
+        if let (CType::Aggregate(inner), Some(nested)) = (&field.field_type, &placed.layout) {
+            probe_fields(inner, nested, &format!("{}.", path), offset, out);
+        }
+    }
+}
+
+/// Generate the probe program.
+///
+/// Each struct is reported by its index in the header, one line per fact:
+/// `size <i> <size> <align>`, `offset <i> <member> <offset>` and
+/// `bits <i> <member> <start> <end>`. Bitfields can't be passed to
+/// `offsetof`, so they're found by clearing them in an all-ones value.
+fn probe_source(expected: &[Expected<'_>], headers: &[PathBuf]) -> Result<String> {
+    let mut source = String::from(
+        "/* Auto-generated layout probe for Harbour */\n\
+         #include <stddef.h>\n\
+         #include <stdio.h>\n\
+         #include <string.h>\n",
+    );
+    for header in headers {
+        let path = std::path::absolute(header)
+            .with_context(|| format!("failed to resolve header: {}", header.display()))?;
+        source.push_str(&format!("#include \"{}\"\n", path.display()));
+    }
+
+    source.push_str(
+        r#"
+#if defined(__cplusplus)
+#define HARBOUR_ALIGNOF(T) alignof(T)
+#elif defined(__STDC_VERSION__) && __STDC_VERSION__ >= 201112L
+#define HARBOUR_ALIGNOF(T) _Alignof(T)
+#else
+#define HARBOUR_ALIGNOF(T) offsetof(struct { char c; T t; }, t)
+#endif
+
+static void harbour_bits(int id, const char *member, const unsigned char *p, size_t n)
+{
+    size_t i, start = 0, end = 0;
+    for (i = 0; i < n * 8; i++) {
+        if (!((p[i / 8] >> (i % 8)) & 1)) {
+            if (end == 0) {
+                start = i;
+            }
+            end = i + 1;
+        }
+    }
+    printf("bits %d %s %lu %lu\n", id, member, (unsigned long)start, (unsigned long)end);
+}
+
+int main(void)
+{
+"#,
+    );
+
+    for (id, item) in expected.iter().enumerate() {
+        let ty = item.def.c_spelling();
+        source.push_str(&format!(
+            "    printf(\"size {} %lu %lu\\n\", (unsigned long)sizeof({}), (unsigned long)HARBOUR_ALIGNOF({}));\n",
+            id, ty, ty
+        ));
+        for field in &item.fields {
+            match field.expected {
+                Placement::Offset(_) => source.push_str(&format!(
+                    "    printf(\"offset {} {} %lu\\n\", (unsigned long)offsetof({}, {}));\n",
+                    id, field.path, ty, field.path
+                )),
+                Placement::Bits(..) => source.push_str(&format!(
+                    "    {{\n        {} v;\n        memset(&v, 0xff, sizeof v);\n        v.{} = 0;\n        harbour_bits({}, \"{}\", (const unsigned char *)&v, sizeof v);\n    }}\n",
+                    ty, field.path, id, field.path
+                )),
+            }
+        }
+    }
+
+    source.push_str("    return 0;\n}\n");
+    Ok(source)
+}
+
+/// Parse the probe's output by struct index.
+fn parse_output(output: &str) -> HashMap<usize, Measured> {
+    let mut measured: HashMap<usize, Measured> = HashMap::new();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let number = |i: usize| parts.get(i).and_then(|p| p.parse::<u64>().ok());
+        let Some(id) = parts.get(1).and_then(|p| p.parse::<usize>().ok()) else {
+            continue;
+        };
+        let entry = measured.entry(id).or_default();
+        match (parts[0], parts.get(2)) {
+            ("size", _) => entry.size_align = number(2).zip(number(3)),
+            ("offset", Some(member)) => {
+                if let Some(offset) = number(3) {
+                    entry
+                        .fields
+                        .insert(member.to_string(), Placement::Offset(offset));
+                }
+            }
+            ("bits", Some(member)) => {
+                if let Some((start, end)) = number(3).zip(number(4)) {
+                    entry
+                        .fields
+                        .insert(member.to_string(), Placement::Bits(start, end));
+                }
+            }
+            _ => {}
+        }
+    }
+    measured
+}
+
+/// Compare computed layouts with measured ones.
+fn compare(expected: &[Expected<'_>], measured: &HashMap<usize, Measured>) -> Vec<LayoutDiff> {
+    let missing = Measured::default();
+    let mut diffs = Vec::new();
+
+    for (id, item) in expected.iter().enumerate() {
+        let name = &item.def.name;
+        let actual = measured.get(&id).unwrap_or(&missing);
+        let actual_size = match actual.size_align {
+            Some((size, align)) => format!("size {}, align {}", size, align),
+            None => "no size".to_string(),
+        };
+
+        let Some(ref layout) = item.layout else {
+            diffs.push(LayoutDiff {
+                item: name.clone(),
+                expected: "an unknown layout".to_string(),
+                actual: actual_size,
+            });
+            continue;
+        };
+        if actual.size_align != Some((layout.size, layout.align)) {
+            diffs.push(LayoutDiff {
+                item: name.clone(),
+                expected: format!("size {}, align {}", layout.size, layout.align),
+                actual: actual_size,
+            });
+        }
+
+        for field in &item.fields {
+            let found = actual.fields.get(&field.path);
+            if found != Some(&field.expected) {
+                diffs.push(LayoutDiff {
+                    item: format!("{}.{}", name, field.path),
+                    expected: field.expected.to_string(),
+                    actual: found.map_or_else(|| "no value".to_string(), ToString::to_string),
+                });
+            }
+        }
+    }
+
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::bindings::parser::HeaderParser;
+    use crate::builder::toolchain::{GccToolchain, ToolchainPlatform};
+    use crate::util::process::find_executable;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    const HEADER: &str = "#include <stdint.h>\n\
+                          typedef struct { char tag; union { int32_t i; double d; }; } value_t;\n\
+                          struct flags { uint8_t a : 3; uint8_t b : 4; uint16_t len; };\n";
+
+    #[test]
+    fn test_probe_source() {
+        let header = HeaderParser::new()
+            .parse_content(HEADER, Path::new("value.h"))
+            .unwrap();
+        let types = TypeTable::new(&header, None);
+        let engine = LayoutEngine::new(&types, TargetAbi::host());
+        let expected = expected_layouts(&header, &engine);
+
+        let source = probe_source(&expected, &[PathBuf::from("/inc/value.h")]).unwrap();
+        assert!(source.contains("#include \"/inc/value.h\""));
+        assert!(source.contains("sizeof(value_t)"));
+        assert!(source.contains("offsetof(value_t, d)"));
+        assert!(source.contains("sizeof(struct flags)"));
+        assert!(source.contains("        v.b = 0;\n"));
+    }
+
+    #[test]
+    fn test_compare_reports_each_field() {
+        let header = HeaderParser::new()
+            .parse_content(HEADER, Path::new("value.h"))
+            .unwrap();
+        let types = TypeTable::new(&header, None);
+        let engine = LayoutEngine::new(
+            &types,
+            TargetAbi::for_target(
+                &crate::core::abi::TargetTriple::parse("x86_64-unknown-linux-gnu").unwrap(),
+            ),
+        );
+        let expected = expected_layouts(&header, &engine);
+
+        let output = "size 0 16 8\noffset 0 tag 0\noffset 0 i 8\noffset 0 d 8\n\
+                      size 1 4 2\nbits 1 a 0 3\nbits 1 b 4 8\noffset 1 len 2\n";
+        let diffs = compare(&expected, &parse_output(output));
+        assert_eq!(
+            diffs,
+            [LayoutDiff {
+                item: "flags.b".to_string(),
+                expected: "bits 3..7".to_string(),
+                actual: "bits 4..8".to_string(),
+            }]
+        );
+        assert_eq!(
+            diffs[0].to_string(),
+            "flags.b: compiler has bits 4..8, bindings assume bits 3..7"
+        );
+    }
+
+    #[test]
+    fn test_probe_with_gcc() {
+        let Some(cc) = find_executable("cc") else {
+            return;
+        };
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("value.h");
+        std::fs::write(
+            &path,
+            format!(
+                "{}#pragma pack(push, 1)\nstruct wire {{ char a; int b : 31; int c : 5; double d; }};\n#pragma pack(pop)\n",
+                HEADER
+            ),
+        )
+        .unwrap();
+        let header = HeaderParser::new().parse_file(&path).unwrap();
+
+        let toolchain = GccToolchain::new(
+            cc.clone(),
+            GccToolchain::infer_cxx(&cc),
+            PathBuf::from("ar"),
+            ToolchainPlatform::Gcc,
+        );
+        let diffs = LayoutProbe::new(&toolchain)
+            .check(&header, &[path])
+            .unwrap();
+        assert!(diffs.is_empty(), "unexpected differences: {:?}", diffs);
+    }
+}
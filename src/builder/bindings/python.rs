@@ -0,0 +1,1036 @@
+//! Python ctypes/cffi binding generator.
+//!
+//! Generates a Python module that loads the library from the
+//! `harbour ffi bundle` layout and declares every function, struct and enum
+//! either through `ctypes` or through a cffi `cdef` in ABI mode.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use super::layout::{LayoutEngine, TargetAbi};
+use super::types::{
+    CConstant, CEnum, CFunction, CStruct, CType, CTypedef, CallingConvention, Declared,
+    ParsedHeader, TypeTable,
+};
+use crate::core::target::FfiBundler;
+
+/// Python keywords that can't be used as identifiers.
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Python binding generator.
+pub struct PythonGenerator {
+    /// Library name
+    library_name: String,
+    /// FFI bundler to use (ctypes or cffi)
+    bundler: FfiBundler,
+    /// Library file path (relative to output)
+    lib_path: String,
+    /// Prefix the parser stripped from names
+    strip_prefix: Option<String>,
+    /// ABI used to check layouts cffi can't express
+    abi: TargetAbi,
+}
+
+impl PythonGenerator {
+    /// Create a new Python generator.
+    pub fn new(library_name: impl Into<String>) -> Self {
+        PythonGenerator {
+            library_name: library_name.into(),
+            bundler: FfiBundler::Ctypes,
+            lib_path: String::new(),
+            strip_prefix: None,
+            abi: TargetAbi::host(),
+        }
+    }
+
+    /// Set the FFI bundler.
+    pub fn with_bundler(mut self, bundler: FfiBundler) -> Self {
+        self.bundler = bundler;
+        self
+    }
+
+    /// Set the library path.
+    pub fn with_lib_path(mut self, path: impl Into<String>) -> Self {
+        self.lib_path = path.into();
+        self
+    }
+
+    /// Set the prefix the header parser stripped from names.
+    ///
+    /// It's re-applied to function names to find the library's symbols.
+    pub fn with_strip_prefix(mut self, prefix: Option<String>) -> Self {
+        self.strip_prefix = prefix;
+        self
+    }
+
+    /// Set the ABI used to check layouts the bundler can't express.
+    pub fn with_abi(mut self, abi: TargetAbi) -> Self {
+        self.abi = abi;
+        self
+    }
+
+    /// File name for the generated module.
+    pub fn module_file_name(&self) -> String {
+        format!("{}.py", python_module_name(&self.library_name))
+    }
+
+    /// Generate Python bindings from parsed headers.
+    pub fn generate(&self, header: &ParsedHeader) -> Result<String> {
+        let types = TypeTable::new(header, self.strip_prefix.as_deref());
+        let mut output = String::new();
+
+        // Module docstring
+        output.push_str(&self.generate_header());
+
+        // Imports
+        output.push_str(&self.generate_imports());
+
+        // Library path resolution
+        output.push_str(&self.generate_loader());
+
+        // Constants
+        output.push_str(&self.generate_constants(&header.constants));
+
+        // Enums as IntEnum classes
+        for enum_def in &header.enums {
+            output.push_str(&self.generate_enum(enum_def));
+        }
+
+        match self.bundler {
+            FfiBundler::Cffi => {
+                output.push_str(&self.generate_cffi(header, &types)?);
+            }
+            _ => {
+                output.push_str(&self.generate_ctypes(header, &types)?);
+            }
+        }
+
+        // End with exactly one newline
+        output.truncate(output.trim_end().len());
+        output.push('\n');
+
+        Ok(output)
+    }
+
+    /// Generate to a file.
+    pub fn generate_to_file(&self, header: &ParsedHeader, path: &Path) -> Result<()> {
+        let content = self.generate(header)?;
+
+        // Create parent directories
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory: {}", parent.display()))?;
+        }
+
+        let mut file = std::fs::File::create(path)
+            .with_context(|| format!("failed to create file: {}", path.display()))?;
+
+        file.write_all(content.as_bytes())
+            .with_context(|| format!("failed to write to file: {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Generate module docstring.
+    fn generate_header(&self) -> String {
+        format!(
+            r#""""FFI bindings for {}.
+
+Auto-generated by Harbour FFI binding generator.
+Do not edit manually.
+"""
+
+"#,
+            self.library_name
+        )
+    }
+
+    /// Generate import statements.
+    fn generate_imports(&self) -> String {
+        match self.bundler {
+            FfiBundler::Cffi => r#"import json
+import os
+import sys
+from enum import IntEnum
+
+from cffi import FFI
+
+"#
+            .to_string(),
+            _ => r#"import ctypes
+import json
+import os
+import sys
+from enum import IntEnum
+
+"#
+            .to_string(),
+        }
+    }
+
+    /// Generate library path resolution.
+    ///
+    /// `harbour ffi bundle` copies the primary library next to a
+    /// `bundle_manifest.json`, so the module looks for that manifest beside
+    /// itself or in an `ffi_bundle` subdirectory before falling back to the
+    /// platform's default file name.
+    fn generate_loader(&self) -> String {
+        let lib_path = if self.lib_path.is_empty() {
+            "None".to_string()
+        } else {
+            py_str(&self.lib_path)
+        };
+        let env_var = format!(
+            "{}_LIBRARY",
+            python_module_name(&self.library_name).to_uppercase()
+        );
+
+        format!(
+            r#"# Library path resolution
+_HERE = os.path.dirname(os.path.abspath(__file__))
+_LIBRARY_NAME = {name}
+_LIBRARY_PATH = {lib_path}
+_LIBRARY_ENV = {env}
+
+
+def _find_library():
+    """Locate the native library.
+
+    Checks the {env_var} environment variable, then the bundle manifest
+    written by `harbour ffi bundle` next to this module or in an
+    `ffi_bundle` subdirectory, then the platform's default file name.
+    """
+    override = os.environ.get(_LIBRARY_ENV)
+    if override:
+        return override
+    if _LIBRARY_PATH is not None:
+        return os.path.join(_HERE, _LIBRARY_PATH)
+
+    if sys.platform == 'win32':
+        default_name = _LIBRARY_NAME + '.dll'
+    elif sys.platform == 'darwin':
+        default_name = 'lib' + _LIBRARY_NAME + '.dylib'
+    else:
+        default_name = 'lib' + _LIBRARY_NAME + '.so'
+
+    for bundle_dir in (_HERE, os.path.join(_HERE, 'ffi_bundle')):
+        manifest = os.path.join(bundle_dir, 'bundle_manifest.json')
+        if os.path.isfile(manifest):
+            with open(manifest, encoding='utf-8') as f:
+                return os.path.join(bundle_dir, json.load(f)['primary_lib'])
+        candidate = os.path.join(bundle_dir, default_name)
+        if os.path.isfile(candidate):
+            return candidate
+
+    return os.path.join(_HERE, default_name)
+
+
+_LIBRARY_FILE = _find_library()
+if sys.platform == 'win32' and hasattr(os, 'add_dll_directory'):
+    # Bundled runtime dependencies live next to the primary library
+    os.add_dll_directory(os.path.dirname(os.path.abspath(_LIBRARY_FILE)))
+
+"#,
+            name = py_str(&self.library_name),
+            lib_path = lib_path,
+            env = py_str(&env_var),
+            env_var = env_var,
+        )
+    }
+
+    /// Generate module-level constants for `#define`s with a literal value.
+    fn generate_constants(&self, constants: &[CConstant]) -> String {
+        let values: Vec<(String, String)> = constants
+            .iter()
+            .filter_map(|c| python_constant(c).map(|value| (py_ident(&c.name), value)))
+            .collect();
+
+        if values.is_empty() {
+            return String::new();
+        }
+
+        let mut output = String::from("\n# Constants\n");
+        for (name, value) in values {
+            output.push_str(&format!("{} = {}\n", name, value));
+        }
+        output.push('\n');
+        output
+    }
+
+    /// Generate enum definition.
+    fn generate_enum(&self, enum_def: &CEnum) -> String {
+        let mut output = String::new();
+
+        output.push_str(&format!("\nclass {}(IntEnum):\n", py_ident(&enum_def.name)));
+
+        if let Some(ref doc) = enum_def.doc {
+            output.push_str(&format!("    \"\"\"{}\"\"\"\n\n", doc));
+        }
+
+        if enum_def.variants.is_empty() {
+            output.push_str("    pass\n");
+        }
+
+        let mut next_value = 0i64;
+        for variant in &enum_def.variants {
+            let value = variant.value.unwrap_or(next_value);
+            output.push_str(&format!("    {} = {}\n", py_ident(&variant.name), value));
+            next_value = value + 1;
+        }
+
+        output.push('\n');
+        output
+    }
+
+    /// Generate the ctypes section: structs, typedefs and functions.
+    fn generate_ctypes(&self, header: &ParsedHeader, types: &TypeTable<'_>) -> Result<String> {
+        let mut output = String::new();
+
+        output.push_str("\n# Load the native library\n");
+        output.push_str("_lib = ctypes.CDLL(_LIBRARY_FILE)\n");
+        if header
+            .functions
+            .iter()
+            .any(|f| f.calling_convention == CallingConvention::Stdcall)
+        {
+            output.push_str(
+                "_stdcall_lib = ctypes.WinDLL(_LIBRARY_FILE) if sys.platform == 'win32' else _lib\n",
+            );
+        }
+        output.push('\n');
+
+        // `_align_` is only honoured from Python 3.13
+        if header.structs.iter().any(|s| s.align.is_some()) {
+            output.push_str(&format!(
+                "if sys.version_info < (3, 13):\n    raise ImportError({})\n\n",
+                py_str(&format!(
+                    "{} bindings need Python 3.13+ for over-aligned structs",
+                    self.library_name
+                ))
+            ));
+        }
+
+        // Declare every struct before filling in fields, so fields can
+        // point at structs declared later (or at themselves)
+        for struct_def in &header.structs {
+            for (name, nested) in nested_aggregates(&struct_def.name, struct_def) {
+                output.push_str(&self.generate_ctypes_struct(&name, nested));
+            }
+            output.push_str(&self.generate_ctypes_struct(&struct_def.name, struct_def));
+        }
+
+        if !header.typedefs.is_empty() {
+            output.push_str("\n# Typedefs\n");
+            for typedef in &header.typedefs {
+                output.push_str(&self.generate_ctypes_typedef(typedef, types));
+            }
+        }
+
+        if !header.structs.is_empty() {
+            output.push_str("\n# Struct fields\n");
+            for struct_def in &header.structs {
+                for (name, nested) in nested_aggregates(&struct_def.name, struct_def) {
+                    output.push_str(&self.generate_ctypes_fields(&name, nested, types)?);
+                }
+                output.push_str(&self.generate_ctypes_fields(
+                    &struct_def.name,
+                    struct_def,
+                    types,
+                )?);
+            }
+        }
+
+        output.push_str("\n# Function bindings\n");
+        for func in &header.functions {
+            output.push_str(&self.generate_ctypes_function(func, types));
+        }
+
+        Ok(output)
+    }
+
+    /// Generate a ctypes Structure or Union class declaration.
+    fn generate_ctypes_struct(&self, name: &str, struct_def: &CStruct) -> String {
+        let base = if struct_def.is_union() {
+            "ctypes.Union"
+        } else {
+            "ctypes.Structure"
+        };
+        let mut output = format!("\nclass {}({}):\n", py_ident(name), base);
+
+        if let Some(ref doc) = struct_def.doc {
+            output.push_str(&format!("    \"\"\"{}\"\"\"\n", doc));
+        }
+
+        // Anonymous members must be listed before `_fields_` is assigned
+        let anonymous: Vec<String> = struct_def
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.anonymous().is_some())
+            .map(|(i, f)| py_str(&member_name(i, &f.name)))
+            .collect();
+
+        let mut body = false;
+        if let Some(pack) = struct_def.pack {
+            output.push_str(&format!("    _pack_ = {}\n", pack));
+            body = true;
+        }
+        if let Some(align) = struct_def.align {
+            output.push_str(&format!("    _align_ = {}\n", align));
+            body = true;
+        }
+        if !anonymous.is_empty() {
+            output.push_str(&format!("    _anonymous_ = ({},)\n", anonymous.join(", ")));
+            body = true;
+        }
+        if !body && struct_def.doc.is_none() {
+            output.push_str("    pass\n");
+        }
+
+        output.push('\n');
+        output
+    }
+
+    /// Generate the `_fields_` assignment for a ctypes Structure.
+    fn generate_ctypes_fields(
+        &self,
+        name: &str,
+        struct_def: &CStruct,
+        types: &TypeTable<'_>,
+    ) -> Result<String> {
+        let mut output = format!("{}._fields_ = [\n", py_ident(name));
+
+        for (i, field) in struct_def.fields.iter().enumerate() {
+            let ctype = match field.field_type {
+                CType::Aggregate(_) => py_ident(&nested_name(name, i, &field.name)),
+                _ => self.ctypes_type(&field.field_type, types),
+            };
+            let field_name = py_str(&member_name(i, &field.name));
+            if field.bit_width == Some(0) {
+                bail!(
+                    "ctypes cannot express the zero-width bitfield in `{}`; use the cffi bundler instead",
+                    name
+                );
+            }
+            match field.bit_width {
+                Some(width) => {
+                    output.push_str(&format!("    ({}, {}, {}),\n", field_name, ctype, width))
+                }
+                None => output.push_str(&format!("    ({}, {}),\n", field_name, ctype)),
+            }
+        }
+
+        output.push_str("]\n");
+        Ok(output)
+    }
+
+    /// Generate a ctypes typedef alias (or CFUNCTYPE for callbacks).
+    fn generate_ctypes_typedef(&self, typedef: &CTypedef, types: &TypeTable<'_>) -> String {
+        let mut output = String::new();
+
+        if let Some(ref doc) = typedef.doc {
+            output.push_str(&output_comment(doc));
+        }
+
+        output.push_str(&format!(
+            "{} = {}\n",
+            py_ident(&typedef.name),
+            self.ctypes_type(&typedef.underlying_type, types)
+        ));
+        output
+    }
+
+    /// Generate a single ctypes function binding.
+    fn generate_ctypes_function(&self, func: &CFunction, types: &TypeTable<'_>) -> String {
+        let mut output = String::new();
+
+        if let Some(ref doc) = func.doc {
+            output.push_str(&output_comment(doc));
+        }
+
+        let handle = if func.calling_convention == CallingConvention::Stdcall {
+            "_stdcall_lib"
+        } else {
+            "_lib"
+        };
+        let name = py_ident(&func.name);
+        let argtypes: Vec<String> = func
+            .params
+            .iter()
+            .map(|p| self.ctypes_type(&p.param_type, types))
+            .collect();
+
+        output.push_str(&format!(
+            "{} = {}.{}\n",
+            name,
+            handle,
+            self.symbol_name(func)
+        ));
+        if func.variadic {
+            output.push_str("# Variadic: extra arguments must be passed as ctypes instances\n");
+        }
+        output.push_str(&format!("{}.argtypes = [{}]\n", name, argtypes.join(", ")));
+        output.push_str(&format!(
+            "{}.restype = {}\n\n",
+            name,
+            self.ctypes_type(&func.return_type, types)
+        ));
+
+        output
+    }
+
+    /// Convert a CType to a ctypes type expression.
+    fn ctypes_type(&self, ctype: &CType, types: &TypeTable<'_>) -> String {
+        match ctype {
+            CType::Void => "None".to_string(),
+            CType::Int8 => "ctypes.c_int8".to_string(),
+            CType::Int16 => "ctypes.c_int16".to_string(),
+            CType::Int32 => "ctypes.c_int32".to_string(),
+            CType::Int64 => "ctypes.c_int64".to_string(),
+            CType::UInt8 => "ctypes.c_uint8".to_string(),
+            CType::UInt16 => "ctypes.c_uint16".to_string(),
+            CType::UInt32 => "ctypes.c_uint32".to_string(),
+            CType::UInt64 => "ctypes.c_uint64".to_string(),
+            CType::Float => "ctypes.c_float".to_string(),
+            CType::Double => "ctypes.c_double".to_string(),
+            CType::Bool => "ctypes.c_bool".to_string(),
+            CType::Char => "ctypes.c_char".to_string(),
+            CType::UChar => "ctypes.c_ubyte".to_string(),
+            CType::WChar => "ctypes.c_wchar".to_string(),
+            CType::Size => "ctypes.c_size_t".to_string(),
+            CType::SSize | CType::PtrDiff => "ctypes.c_ssize_t".to_string(),
+            CType::Pointer(inner) | CType::ConstPointer(inner) => match inner.as_ref() {
+                CType::Char => "ctypes.c_char_p".to_string(),
+                CType::WChar => "ctypes.c_wchar_p".to_string(),
+                inner if self.is_opaque(inner, types) => "ctypes.c_void_p".to_string(),
+                inner => format!("ctypes.POINTER({})", self.ctypes_type(inner, types)),
+            },
+            CType::Array(inner, size) => format!("{} * {}", self.ctypes_type(inner, types), size),
+            CType::Struct(name) | CType::Union(name) | CType::Enum(name) | CType::TypeDef(name) => {
+                match types.lookup(name) {
+                    Some(Declared::Enum(_)) => "ctypes.c_int".to_string(),
+                    Some(declared) => py_ident(declared.name()),
+                    None => "ctypes.c_void_p".to_string(),
+                }
+            }
+            CType::FunctionPointer {
+                return_type,
+                param_types,
+            } => {
+                let mut args = vec![self.ctypes_type(return_type, types)];
+                args.extend(param_types.iter().map(|p| self.ctypes_type(p, types)));
+                format!("ctypes.CFUNCTYPE({})", args.join(", "))
+            }
+            // Nested definitions get their own classes; see `nested_aggregates`
+            CType::Aggregate(_) | CType::Opaque(_) => "ctypes.c_void_p".to_string(),
+        }
+    }
+
+    /// Generate the cffi section: `cdef` declarations and the ABI-mode loader.
+    ///
+    /// cffi packs every struct in one `cdef` call alike, so packed structs
+    /// go into a separate call per packing value.
+    fn generate_cffi(&self, header: &ParsedHeader, types: &TypeTable<'_>) -> Result<String> {
+        let engine = LayoutEngine::new(types, self.abi);
+        for struct_def in header.structs.iter().filter(|s| s.align.is_some()) {
+            let natural = CStruct {
+                align: None,
+                ..struct_def.clone()
+            };
+            if engine.layout(struct_def)? != engine.layout(&natural)? {
+                bail!(
+                    "cffi cannot express the {}-byte alignment of `{}`; use the ctypes bundler instead",
+                    struct_def.align.unwrap_or_default(),
+                    struct_def.name
+                );
+            }
+        }
+
+        let mut cdef = String::new();
+
+        for enum_def in &header.enums {
+            cdef.push_str("typedef enum {\n");
+            let mut next_value = 0i64;
+            for variant in &enum_def.variants {
+                let value = variant.value.unwrap_or(next_value);
+                cdef.push_str(&format!("    {} = {},\n", variant.name, value));
+                next_value = value + 1;
+            }
+            cdef.push_str(&format!("}} {};\n", enum_def.name));
+        }
+
+        // Forward-declare structs so typedefs and fields can refer to them
+        for struct_def in &header.structs {
+            cdef.push_str(&format!(
+                "typedef {} {} {};\n",
+                struct_def.kind.keyword(),
+                struct_def.name,
+                struct_def.name
+            ));
+        }
+
+        for typedef in &header.typedefs {
+            cdef.push_str(&format!(
+                "typedef {};\n",
+                self.c_declaration(&typedef.underlying_type, &typedef.name, types)
+            ));
+        }
+
+        let mut packs: Vec<u64> = header.structs.iter().filter_map(|s| s.pack).collect();
+        packs.sort_unstable();
+        packs.dedup();
+
+        for struct_def in header.structs.iter().filter(|s| s.pack.is_none()) {
+            cdef.push_str(&self.c_aggregate(struct_def, types));
+        }
+
+        for func in &header.functions {
+            let mut params: Vec<String> = func
+                .params
+                .iter()
+                .map(|p| self.c_declaration(&p.param_type, &p.name, types))
+                .collect();
+            if func.variadic {
+                params.push("...".to_string());
+            }
+            if params.is_empty() {
+                params.push("void".to_string());
+            }
+
+            let conv = if func.calling_convention == CallingConvention::Stdcall {
+                "__stdcall "
+            } else {
+                ""
+            };
+            let declarator = format!("{}{}({})", conv, self.symbol_name(func), params.join(", "));
+            cdef.push_str(&format!(
+                "{};\n",
+                self.c_declaration(&func.return_type, &declarator, types)
+            ));
+        }
+
+        let mut output = String::new();
+        output.push_str("\n# C declarations\n");
+        output.push_str("ffi = FFI()\n");
+        output.push_str(&format!("ffi.cdef(\"\"\"\n{}\"\"\")\n", cdef));
+
+        for pack in packs {
+            let packed: String = header
+                .structs
+                .iter()
+                .filter(|s| s.pack == Some(pack))
+                .map(|s| self.c_aggregate(s, types))
+                .collect();
+            let arg = if pack == 1 {
+                "packed=True".to_string()
+            } else {
+                format!("pack={}", pack)
+            };
+            output.push_str(&format!("ffi.cdef(\"\"\"\n{}\"\"\", {})\n", packed, arg));
+        }
+
+        output.push_str("\n# Load the native library (ABI mode)\n");
+        output.push_str("lib = ffi.dlopen(_LIBRARY_FILE)\n");
+
+        output.push_str("\n# Function bindings\n");
+        for func in &header.functions {
+            if let Some(ref doc) = func.doc {
+                output.push_str(&output_comment(doc));
+            }
+            output.push_str(&format!(
+                "{} = lib.{}\n",
+                py_ident(&func.name),
+                self.symbol_name(func)
+            ));
+        }
+
+        Ok(output)
+    }
+
+    /// Render a struct or union definition for a cffi `cdef`.
+    fn c_aggregate(&self, struct_def: &CStruct, types: &TypeTable<'_>) -> String {
+        format!(
+            "{} {} {{\n{}}};\n",
+            struct_def.kind.keyword(),
+            struct_def.name,
+            self.c_fields(struct_def, types, "    ")
+        )
+    }
+
+    /// Render the field declarations of a struct or union body.
+    fn c_fields(&self, struct_def: &CStruct, types: &TypeTable<'_>, indent: &str) -> String {
+        let mut output = String::new();
+        for field in &struct_def.fields {
+            let decl = match &field.field_type {
+                CType::Aggregate(inner) => {
+                    let nested = format!("{}    ", indent);
+                    format!(
+                        "{} {{\n{}{}}}{}{}",
+                        inner.kind.keyword(),
+                        self.c_fields(inner, types, &nested),
+                        indent,
+                        if field.name.is_empty() { "" } else { " " },
+                        field.name
+                    )
+                }
+                other => self.c_declaration(other, &field.name, types),
+            };
+            match field.bit_width {
+                Some(width) => output.push_str(&format!("{}{} : {};\n", indent, decl, width)),
+                None => output.push_str(&format!("{}{};\n", indent, decl)),
+            }
+        }
+        output
+    }
+
+    /// Render a C declaration of `name` with type `ctype` for a cffi `cdef`.
+    fn c_declaration(&self, ctype: &CType, name: &str, types: &TypeTable<'_>) -> String {
+        let base = |base: &str| {
+            if name.is_empty() {
+                base.to_string()
+            } else {
+                format!("{} {}", base, name)
+            }
+        };
+
+        match ctype {
+            CType::Void => base("void"),
+            CType::Int8 => base("int8_t"),
+            CType::Int16 => base("int16_t"),
+            CType::Int32 => base("int32_t"),
+            CType::Int64 => base("int64_t"),
+            CType::UInt8 => base("uint8_t"),
+            CType::UInt16 => base("uint16_t"),
+            CType::UInt32 => base("uint32_t"),
+            CType::UInt64 => base("uint64_t"),
+            CType::Float => base("float"),
+            CType::Double => base("double"),
+            CType::Bool => base("bool"),
+            CType::Char => base("char"),
+            CType::UChar => base("unsigned char"),
+            CType::WChar => base("wchar_t"),
+            CType::Size => base("size_t"),
+            CType::SSize => base("ssize_t"),
+            CType::PtrDiff => base("ptrdiff_t"),
+            CType::Pointer(inner) if self.is_opaque(inner, types) => {
+                self.c_declaration(&CType::Void, &format!("*{}", name), types)
+            }
+            CType::Pointer(inner) => self.c_declaration(inner, &format!("*{}", name), types),
+            CType::ConstPointer(inner) if self.is_opaque(inner, types) => {
+                format!("const void *{}", name)
+            }
+            CType::ConstPointer(inner) => format!(
+                "const {}",
+                self.c_declaration(inner, &format!("*{}", name), types)
+            ),
+            CType::Array(inner, size) => {
+                let name = if name.starts_with('*') {
+                    format!("({})", name)
+                } else {
+                    name.to_string()
+                };
+                self.c_declaration(inner, &format!("{}[{}]", name, size), types)
+            }
+            CType::Struct(type_name)
+            | CType::Union(type_name)
+            | CType::Enum(type_name)
+            | CType::TypeDef(type_name) => match types.lookup(type_name) {
+                Some(declared) => base(declared.name()),
+                None => self.c_declaration(&CType::Opaque(type_name.clone()), name, types),
+            },
+            CType::FunctionPointer {
+                return_type,
+                param_types,
+            } => {
+                let params: Vec<String> = if param_types.is_empty() {
+                    vec!["void".to_string()]
+                } else {
+                    param_types
+                        .iter()
+                        .map(|p| self.c_declaration(p, "", types))
+                        .collect()
+                };
+                self.c_declaration(
+                    return_type,
+                    &format!("(*{})({})", name, params.join(", ")),
+                    types,
+                )
+            }
+            CType::Aggregate(inner) => format!(
+                "{} {{ {}}} {}",
+                inner.kind.keyword(),
+                self.c_fields(inner, types, ""),
+                name
+            ),
+            CType::Opaque(_) => format!("void *{}", name),
+        }
+    }
+
+    /// Whether a pointee type is unknown and must be treated as `void`.
+    fn is_opaque(&self, ctype: &CType, types: &TypeTable<'_>) -> bool {
+        match ctype {
+            CType::Void | CType::Opaque(_) => true,
+            CType::Struct(name) | CType::Union(name) | CType::Enum(name) | CType::TypeDef(name) => {
+                types.lookup(name).is_none()
+            }
+            _ => false,
+        }
+    }
+
+    /// The library symbol for a function, with any stripped prefix restored.
+    fn symbol_name(&self, func: &CFunction) -> String {
+        match self.strip_prefix {
+            Some(ref prefix) => format!("{}{}", prefix, func.name),
+            None => func.name.clone(),
+        }
+    }
+}
+
+/// Nested struct and union definitions of `def`, innermost first, with
+/// the class names they are generated under.
+fn nested_aggregates<'s>(owner: &str, def: &'s CStruct) -> Vec<(String, &'s CStruct)> {
+    let mut nested = Vec::new();
+    for (i, field) in def.fields.iter().enumerate() {
+        if let CType::Aggregate(inner) = &field.field_type {
+            let name = nested_name(owner, i, &field.name);
+            nested.extend(nested_aggregates(&name, inner));
+            nested.push((name, inner.as_ref()));
+        }
+    }
+    nested
+}
+
+/// Class name for the nested definition of field `index` of `owner`.
+fn nested_name(owner: &str, index: usize, field: &str) -> String {
+    format!(
+        "_{}_{}",
+        owner.trim_start_matches('_'),
+        member_name(index, field)
+    )
+}
+
+/// Field name, with anonymous members named after their position.
+fn member_name(index: usize, name: &str) -> String {
+    if name.is_empty() {
+        format!("_anon{}", index)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Render a doc string as Python comment lines.
+fn output_comment(doc: &str) -> String {
+    doc.lines()
+        .map(|line| format!("# {}\n", line.trim()))
+        .collect()
+}
+
+/// Convert a library name into an importable module name.
+fn python_module_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Escape a Python keyword by appending an underscore.
+fn py_ident(name: &str) -> String {
+    if PYTHON_KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Quote a string as a Python string literal.
+fn py_str(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Convert a `#define` value into a Python literal, if it is one.
+fn python_constant(constant: &CConstant) -> Option<String> {
+    let value = constant.value.trim();
+
+    match constant.const_type.as_ref()? {
+        CType::Float | CType::Double => {
+            Some(value.trim_end_matches(['f', 'F', 'l', 'L']).to_string())
+        }
+        CType::Char => Some(format!("ord({})", value)),
+        CType::ConstPointer(_) => Some(value.to_string()),
+        _ => {
+            let number = constant.int_value()?;
+            if constant.is_hex() {
+                Some(value.trim_end_matches(['u', 'U', 'l', 'L']).to_string())
+            } else {
+                Some(number.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::bindings::types::{CEnumVariant, CField, CParam};
+    use crate::test_support::golden::{assert_golden, ffi_project_header, golden_abi};
+
+    /// Generate bindings for the FFI test project and compare them to `golden`.
+    fn assert_python_golden(generator: PythonGenerator, golden: &str) {
+        let output = generator
+            .with_abi(golden_abi())
+            .generate(&ffi_project_header())
+            .unwrap();
+        assert_golden(&output, golden);
+    }
+
+    #[test]
+    fn test_golden_ctypes() {
+        let generator = PythonGenerator::new("mylib").with_strip_prefix(Some("mylib_".to_string()));
+        assert_python_golden(generator, "mylib.py");
+    }
+
+    #[test]
+    fn test_golden_cffi() {
+        let generator = PythonGenerator::new("mylib")
+            .with_bundler(FfiBundler::Cffi)
+            .with_strip_prefix(Some("mylib_".to_string()));
+        assert_python_golden(generator, "mylib_cffi.py");
+    }
+
+    #[test]
+    fn test_generate_enum() {
+        let gen = PythonGenerator::new("test");
+        let enum_def = CEnum {
+            name: "Color".to_string(),
+            variants: vec![
+                CEnumVariant {
+                    name: "RED".to_string(),
+                    value: Some(1),
+                },
+                CEnumVariant {
+                    name: "GREEN".to_string(),
+                    value: None,
+                },
+            ],
+            doc: None,
+        };
+
+        let output = gen.generate_enum(&enum_def);
+        assert!(output.contains("class Color(IntEnum):"));
+        assert!(output.contains("RED = 1"));
+        assert!(output.contains("GREEN = 2"));
+    }
+
+    #[test]
+    fn test_ctypes_callbacks_and_pointers() {
+        let mut header = ParsedHeader::default();
+        header
+            .structs
+            .push(CStruct::new("node").with_field(CField::new(
+                "next",
+                CType::Pointer(Box::new(CType::Struct("node".to_string()))),
+            )));
+        header.functions.push(
+            CFunction::new("walk", CType::Void)
+                .with_param(CParam::new(
+                    "head",
+                    CType::Pointer(Box::new(CType::TypeDef("node".to_string()))),
+                ))
+                .with_param(CParam::new(
+                    "visit",
+                    CType::FunctionPointer {
+                        return_type: Box::new(CType::Int32),
+                        param_types: vec![CType::Pointer(Box::new(CType::Void))],
+                    },
+                ))
+                .with_param(CParam::new(
+                    "file",
+                    CType::Pointer(Box::new(CType::TypeDef("FILE".to_string()))),
+                )),
+        );
+
+        let output = PythonGenerator::new("test").generate(&header).unwrap();
+        assert!(output.contains("class node(ctypes.Structure):"));
+        assert!(output.contains("('next', ctypes.POINTER(node)),"));
+        assert!(output.contains(
+            "walk.argtypes = [ctypes.POINTER(node), \
+             ctypes.CFUNCTYPE(ctypes.c_int32, ctypes.c_void_p), ctypes.c_void_p]"
+        ));
+        assert!(output.contains("walk.restype = None"));
+    }
+
+    #[test]
+    fn test_cffi_declarations() {
+        let gen = PythonGenerator::new("test");
+        let header = ParsedHeader::default();
+        let types = TypeTable::new(&header, None);
+
+        assert_eq!(
+            gen.c_declaration(&CType::ConstPointer(Box::new(CType::Char)), "name", &types),
+            "const char *name"
+        );
+        assert_eq!(
+            gen.c_declaration(&CType::Array(Box::new(CType::UInt8), 16), "id", &types),
+            "uint8_t id[16]"
+        );
+        assert_eq!(
+            gen.c_declaration(
+                &CType::FunctionPointer {
+                    return_type: Box::new(CType::Void),
+                    param_types: vec![CType::Int32],
+                },
+                "cb",
+                &types
+            ),
+            "void (*cb)(int32_t)"
+        );
+        assert_eq!(
+            gen.c_declaration(
+                &CType::Pointer(Box::new(CType::TypeDef("FILE".to_string()))),
+                "f",
+                &types
+            ),
+            "void *f"
+        );
+    }
+
+    #[test]
+    fn test_python_constant() {
+        let constant = |value: &str, ty: CType| CConstant {
+            name: "X".to_string(),
+            value: value.to_string(),
+            const_type: Some(ty),
+        };
+
+        assert_eq!(
+            python_constant(&constant("1024U", CType::UInt32)).as_deref(),
+            Some("1024")
+        );
+        assert_eq!(
+            python_constant(&constant("0755", CType::Int32)).as_deref(),
+            Some("493")
+        );
+        assert_eq!(
+            python_constant(&constant("0xFF", CType::Int32)).as_deref(),
+            Some("0xFF")
+        );
+        assert_eq!(
+            python_constant(&constant("1.5f", CType::Double)).as_deref(),
+            Some("1.5")
+        );
+        assert_eq!(
+            python_constant(&CConstant {
+                name: "MYLIB_H".to_string(),
+                value: "#include <stdint.h>".to_string(),
+                const_type: None,
+            }),
+            None
+        );
+    }
+}
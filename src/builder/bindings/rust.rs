@@ -0,0 +1,1288 @@
+//! Rust `extern "C"` binding generator.
+//!
+//! Generates a `-sys`-style module with `#[repr(C)]` types, `extern` blocks
+//! and compile-time layout assertions, optionally followed by thin safe
+//! wrappers, plus a `build.rs` that links against the library from
+//! `harbour ffi bundle`.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use super::layout::{bitfield_units, BitfieldRules, LayoutEngine, StructLayout, TargetAbi};
+use super::types::{
+    CConstant, CEnum, CField, CFunction, CStruct, CType, CTypedef, CallingConvention, Declared,
+    ParsedHeader, TypeTable,
+};
+
+/// Rust keywords that need a raw identifier (`r#type`).
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Keywords that can't be raw identifiers and get a trailing underscore.
+const RUST_RESERVED: &[&str] = &["crate", "self", "Self", "super", "_"];
+
+/// Deepest nesting of aggregates and typedefs followed when checking types.
+const MAX_DEPTH: usize = 64;
+
+/// Rust binding generator.
+pub struct RustGenerator {
+    /// Library name
+    library_name: String,
+    /// Generate a `safe` module of wrappers
+    safe_wrappers: bool,
+    /// Bundle directory, relative to the consuming crate's manifest
+    bundle_dir: String,
+    /// Prefix the parser stripped from names
+    strip_prefix: Option<String>,
+    /// ABI struct layouts are computed for
+    abi: TargetAbi,
+}
+
+/// A field of a generated struct or union.
+struct Member {
+    /// Rust field name
+    name: String,
+    /// Rust field type
+    ty: String,
+    /// Doc comment
+    doc: Option<String>,
+    /// Offset to assert, for fields that mirror a C member
+    offset: Option<u64>,
+}
+
+impl RustGenerator {
+    /// Create a new Rust generator.
+    pub fn new(library_name: impl Into<String>) -> Self {
+        RustGenerator {
+            library_name: library_name.into(),
+            safe_wrappers: false,
+            bundle_dir: "ffi_bundle".to_string(),
+            strip_prefix: None,
+            abi: TargetAbi::host(),
+        }
+    }
+
+    /// Set safe wrapper generation.
+    pub fn with_safe_wrappers(mut self, safe_wrappers: bool) -> Self {
+        self.safe_wrappers = safe_wrappers;
+        self
+    }
+
+    /// Set the bundle directory the build script links from.
+    pub fn with_bundle_dir(mut self, dir: impl Into<String>) -> Self {
+        self.bundle_dir = dir.into();
+        self
+    }
+
+    /// Set the prefix the header parser stripped from names.
+    ///
+    /// It's re-applied to function names as `#[link_name]`.
+    pub fn with_strip_prefix(mut self, prefix: Option<String>) -> Self {
+        self.strip_prefix = prefix;
+        self
+    }
+
+    /// Set the ABI struct layouts and their assertions are computed for.
+    pub fn with_abi(mut self, abi: TargetAbi) -> Self {
+        self.abi = abi;
+        self
+    }
+
+    /// File name for the generated module.
+    pub fn module_file_name(&self) -> String {
+        format!("{}.rs", self.module_name())
+    }
+
+    /// Generate Rust bindings from parsed headers.
+    pub fn generate(&self, header: &ParsedHeader) -> Result<String> {
+        let types = TypeTable::new(header, self.strip_prefix.as_deref());
+        let mut body = String::new();
+
+        // Constants
+        body.push_str(&self.generate_constants(&header.constants));
+
+        // Opaque types referenced but never defined
+        for name in self.opaque_types(header, &types) {
+            body.push_str(&self.generate_opaque(&name));
+        }
+
+        for enum_def in &header.enums {
+            body.push_str(&self.generate_enum(enum_def));
+        }
+
+        for typedef in &header.typedefs {
+            body.push_str(&self.generate_typedef(typedef, &types));
+        }
+
+        let engine = LayoutEngine::new(&types, self.abi);
+        for struct_def in &header.structs {
+            body.push_str(&self.generate_struct(&struct_def.name, struct_def, &engine, &types)?);
+        }
+
+        body.push_str(&self.generate_extern_blocks(&header.functions, &types));
+
+        if self.safe_wrappers {
+            body.push_str(&self.generate_safe_wrappers(&header.functions, &types));
+        }
+
+        let mut output = self.generate_header();
+        output.push_str(&self.generate_imports(&body));
+        output.push_str(&body);
+
+        // End with exactly one newline
+        output.truncate(output.trim_end().len());
+        output.push('\n');
+
+        Ok(output)
+    }
+
+    /// Generate to a file.
+    pub fn generate_to_file(&self, header: &ParsedHeader, path: &Path) -> Result<()> {
+        let content = self.generate(header)?;
+        write_file(path, &content)
+    }
+
+    /// Generate a `build.rs` that links against the bundled library.
+    pub fn generate_build_script(&self) -> String {
+        let env_var = format!("{}_BUNDLE_DIR", self.module_name().to_uppercase());
+        format!(
+            r#"//! Links against {name} from `harbour ffi bundle`.
+//!
+//! Auto-generated by Harbour FFI binding generator.
+//! Set `{env}` to use a bundle outside this crate.
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {{
+    println!("cargo:rerun-if-env-changed={env}");
+
+    let bundle_dir = match env::var_os("{env}") {{
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("{bundle}"),
+    }};
+    println!(
+        "cargo:rerun-if-changed={{}}",
+        bundle_dir.join("bundle_manifest.json").display()
+    );
+
+    println!("cargo:rustc-link-search=native={{}}", bundle_dir.display());
+    println!("cargo:rustc-link-lib=dylib={name}");
+
+    // Let binaries find the bundled library without installing it
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    if target_os == "linux" || target_os == "macos" {{
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{{}}", bundle_dir.display());
+    }}
+}}
+"#,
+            name = self.library_name,
+            env = env_var,
+            bundle = self.bundle_dir,
+        )
+    }
+
+    /// Generate the build script to a file.
+    pub fn generate_build_script_to_file(&self, path: &Path) -> Result<()> {
+        write_file(path, &self.generate_build_script())
+    }
+
+    /// Generate file header comment and lint allowances.
+    fn generate_header(&self) -> String {
+        format!(
+            r#"//! FFI bindings for {}.
+//!
+//! Auto-generated by Harbour FFI binding generator.
+//! Do not edit manually.
+
+#![allow(
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    dead_code,
+    unused_imports
+)]
+
+"#,
+            self.library_name
+        )
+    }
+
+    /// Generate imports for the items the body actually uses.
+    fn generate_imports(&self, body: &str) -> String {
+        let mut ffi = Vec::new();
+        for name in ["c_char", "c_int", "c_void"] {
+            if body.contains(name) {
+                ffi.push(name);
+            }
+        }
+
+        let mut output = String::new();
+        match ffi.len() {
+            0 => {}
+            1 => output.push_str(&format!("use core::ffi::{};\n", ffi[0])),
+            _ => output.push_str(&format!("use core::ffi::{{{}}};\n", ffi.join(", "))),
+        }
+        if body.contains("PhantomData") {
+            output.push_str("use core::marker::{PhantomData, PhantomPinned};\n");
+        }
+        if body.contains("wchar_t") {
+            output.push_str(
+                "\n#[cfg(windows)]\npub type wchar_t = u16;\n#[cfg(not(windows))]\npub type wchar_t = i32;\n",
+            );
+        }
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Generate `pub const` items for `#define`s with a literal value.
+    fn generate_constants(&self, constants: &[CConstant]) -> String {
+        let items: Vec<String> = constants.iter().filter_map(rust_constant).collect();
+        if items.is_empty() {
+            return String::new();
+        }
+
+        let mut output = items.join("\n");
+        output.push_str("\n\n");
+        output
+    }
+
+    /// Generate an opaque type for a pointee the header never defines.
+    fn generate_opaque(&self, name: &str) -> String {
+        format!(
+            r#"#[repr(C)]
+pub struct {} {{
+    _data: [u8; 0],
+    _marker: PhantomData<(*mut u8, PhantomPinned)>,
+}}
+
+"#,
+            rust_ident(name)
+        )
+    }
+
+    /// Generate a C enum as an integer newtype with a const per variant.
+    ///
+    /// C code can hand back any value of the underlying integer, which
+    /// would be undefined behavior for a Rust enum.
+    fn generate_enum(&self, enum_def: &CEnum) -> String {
+        let mut output = String::new();
+
+        if let Some(ref doc) = enum_def.doc {
+            output.push_str(&doc_comment(doc, ""));
+        }
+
+        let name = rust_ident(&enum_def.name);
+        if enum_def.variants.is_empty() {
+            output.push_str(&format!("pub type {} = c_int;\n\n", name));
+            return output;
+        }
+
+        let mut variants: Vec<(String, i64)> = Vec::new();
+        let mut next_value = 0i64;
+        for variant in &enum_def.variants {
+            let value = variant.value.unwrap_or(next_value);
+            variants.push((rust_ident(&variant.name), value));
+            next_value = value + 1;
+        }
+
+        let (min, max) = variants
+            .iter()
+            .fold((0i64, 0i64), |(lo, hi), (_, v)| (lo.min(*v), hi.max(*v)));
+        let repr = if min >= i64::from(i32::MIN) && max <= i64::from(i32::MAX) {
+            "c_int"
+        } else if min >= 0 && max <= i64::from(u32::MAX) {
+            "u32"
+        } else {
+            "i64"
+        };
+
+        output.push_str(&format!(
+            "#[repr(transparent)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub struct {}(pub {});\n\n",
+            name, repr
+        ));
+        output.push_str(&format!("impl {} {{\n", name));
+        for (variant, value) in &variants {
+            output.push_str(&format!(
+                "    pub const {}: {} = {}({});\n",
+                variant, name, name, value
+            ));
+        }
+        output.push_str("}\n\n");
+
+        output
+    }
+
+    /// Generate a type alias.
+    fn generate_typedef(&self, typedef: &CTypedef, types: &TypeTable<'_>) -> String {
+        let mut output = String::new();
+
+        if let Some(ref doc) = typedef.doc {
+            output.push_str(&doc_comment(doc, ""));
+        }
+
+        output.push_str(&format!(
+            "pub type {} = {};\n\n",
+            rust_ident(&typedef.name),
+            self.rust_type(&typedef.underlying_type, types)
+        ));
+        output
+    }
+
+    /// Generate a struct or union, preceded by types for its inline
+    /// members and followed by compile-time layout assertions.
+    fn generate_struct(
+        &self,
+        name: &str,
+        struct_def: &CStruct,
+        engine: &LayoutEngine<'_>,
+        types: &TypeTable<'_>,
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        // Rust has no inline or anonymous members, so each one becomes a
+        // type of its own named after its owner
+        let mut fields = Vec::with_capacity(struct_def.fields.len());
+        for (i, field) in struct_def.fields.iter().enumerate() {
+            let Some(inner) = inline_aggregate(&field.field_type) else {
+                fields.push(field.clone());
+                continue;
+            };
+            let member = member_name(i, &field.name);
+            let helper = format!("{}_{}", name, member);
+            output.push_str(&self.generate_struct(&helper, inner, engine, types)?);
+            fields.push(CField::new(
+                member,
+                rename_aggregate(&field.field_type, &helper),
+            ));
+        }
+
+        // Plain structs still generate when a member's size is unknown,
+        // just without assertions; bitfields can't be placed without it
+        let layout = match engine.layout(struct_def) {
+            Ok(layout) => Some(layout),
+            Err(_) if !struct_def.has_bitfields() => None,
+            Err(e) => return Err(e),
+        };
+
+        if let Some(ref doc) = struct_def.doc {
+            output.push_str(&doc_comment(doc, ""));
+        }
+
+        let mut repr = vec!["C".to_string()];
+        match (struct_def.pack, struct_def.align) {
+            (Some(_), Some(_)) => bail!(
+                "`{}` is both packed and aligned, which Rust can't represent",
+                name
+            ),
+            (Some(1), None) => repr.push("packed".to_string()),
+            (Some(pack), None) => repr.push(format!("packed({})", pack)),
+            (None, Some(align)) => repr.push(format!("align({})", align)),
+            (None, None) => {}
+        }
+        // Unions can't derive Debug, and neither can anything holding one
+        let derive = if struct_def.is_union() || self.holds_union(&fields, types, 0) {
+            "Clone, Copy"
+        } else {
+            "Debug, Clone, Copy"
+        };
+        output.push_str(&format!(
+            "#[repr({})]\n#[derive({})]\npub {} {} {{\n",
+            repr.join(", "),
+            derive,
+            struct_def.kind.keyword(),
+            rust_ident(name)
+        ));
+
+        let members = match layout {
+            Some(ref layout) => self.placed_members(name, struct_def, &fields, layout, types)?,
+            None => fields
+                .iter()
+                .map(|field| Member {
+                    name: rust_ident(&field.name),
+                    ty: self.rust_type(&field.field_type, types),
+                    doc: None,
+                    offset: None,
+                })
+                .collect(),
+        };
+        for member in &members {
+            if let Some(ref doc) = member.doc {
+                output.push_str(&doc_comment(doc, "    "));
+            }
+            output.push_str(&format!("    pub {}: {},\n", member.name, member.ty));
+        }
+        output.push_str("}\n\n");
+
+        if let Some(ref layout) = layout {
+            output.push_str(&self.generate_layout_assertions(name, &members, layout));
+        }
+        Ok(output)
+    }
+
+    /// Lay out a struct's members the way C does.
+    ///
+    /// Runs of bitfields share one storage member covering their bytes.
+    /// Since that storage can be narrower than the bitfields' declared
+    /// type, padding and a zero-sized alignment member are added wherever
+    /// Rust's placement would otherwise differ from the C layout.
+    fn placed_members(
+        &self,
+        name: &str,
+        struct_def: &CStruct,
+        fields: &[CField],
+        layout: &StructLayout,
+        types: &TypeTable<'_>,
+    ) -> Result<Vec<Member>> {
+        let cap = |align: u64| struct_def.pack.map_or(align, |pack| align.min(pack));
+        let union = struct_def.is_union();
+        let units = bitfield_units(layout);
+
+        let mut members = Vec::new();
+        let mut pos = 0u64;
+        let mut align = 1;
+        let mut pads = 0;
+        let mut pad = |members: &mut Vec<Member>, size: u64| {
+            pads += 1;
+            members.push(Member {
+                name: format!("_pad_{}", pads),
+                ty: format!("[u8; {}]", size),
+                doc: None,
+                offset: None,
+            });
+        };
+
+        for (i, (field, placed)) in fields.iter().zip(&layout.fields).enumerate() {
+            let (member, size, member_align) =
+                if let Some(unit) = units.iter().find(|u| u.first == i) {
+                    let bits: Vec<String> = (unit.first..unit.end)
+                        .filter(|&j| !fields[j].name.is_empty())
+                        .map(|j| {
+                            let f = &layout.fields[j];
+                            format!(
+                                "{} (bits {}..{})",
+                                fields[j].name,
+                                f.start_bit() - unit.offset * 8,
+                                f.end_bit() - unit.offset * 8
+                            )
+                        })
+                        .collect();
+                    let (ty, unit_align) = match (unit.is_integer(), unit.size) {
+                        (true, size) => (format!("u{}", size * 8), size),
+                        (false, size) => (format!("[u8; {}]", size), 1),
+                    };
+                    let count = units.iter().take_while(|u| u.first != i).count() + 1;
+                    let member = Member {
+                        name: format!("_bitfield_{}", count),
+                        ty,
+                        doc: Some(format!("Bitfields: {}", bits.join(", "))),
+                        offset: Some(unit.offset),
+                    };
+                    (member, unit.size, unit_align)
+                } else if field.bit_width.is_some() {
+                    continue;
+                } else {
+                    let member = Member {
+                        name: rust_ident(&field.name),
+                        ty: self.rust_type(&field.field_type, types),
+                        doc: None,
+                        offset: Some(placed.offset),
+                    };
+                    (member, placed.size, placed.align)
+                };
+
+            let offset = member.offset.unwrap_or_default();
+            let member_align = cap(member_align);
+            if !union {
+                if pos.next_multiple_of(member_align) < offset {
+                    pad(&mut members, offset - pos);
+                    pos = offset;
+                }
+                if pos.next_multiple_of(member_align) != offset {
+                    bail!(
+                        "cannot place `{}.{}` at offset {} in Rust",
+                        name,
+                        member.name,
+                        offset
+                    );
+                }
+            }
+            pos = if union { pos.max(size) } else { offset + size };
+            align = align.max(member_align);
+            members.push(member);
+        }
+
+        // Storage narrower than the declared type leaves the alignment short
+        if align < layout.align && struct_def.align.is_none() {
+            if !matches!(layout.align, 1 | 2 | 4 | 8) {
+                bail!("cannot align `{}` to {} bytes in Rust", name, layout.align);
+            }
+            members.insert(
+                0,
+                Member {
+                    name: "_align".to_string(),
+                    ty: format!("[u{}; 0]", layout.align * 8),
+                    doc: None,
+                    offset: None,
+                },
+            );
+            align = layout.align;
+        }
+
+        if pos.next_multiple_of(align.max(struct_def.align.unwrap_or(1))) < layout.size {
+            let end = if union { 0 } else { pos };
+            pad(&mut members, layout.size - end);
+        }
+
+        Ok(members)
+    }
+
+    /// Generate compile-time size, alignment and offset checks.
+    ///
+    /// The expected values are computed for the configured ABI, so the
+    /// checks only run on targets with its pointer width, and for layouts
+    /// with bitfields, its bitfield rules.
+    fn generate_layout_assertions(
+        &self,
+        name: &str,
+        members: &[Member],
+        layout: &StructLayout,
+    ) -> String {
+        let width = format!("target_pointer_width = \"{}\"", self.abi.pointer_size * 8);
+        let cfg = if layout.fields.iter().any(|f| f.bit_width.is_some()) {
+            let os = match self.abi.bitfields {
+                BitfieldRules::Msvc => "windows",
+                BitfieldRules::SysV => "not(windows)",
+            };
+            format!("all({}, {})", width, os)
+        } else {
+            width
+        };
+
+        let name = rust_ident(name);
+        let mut output = format!("#[cfg({})]\nconst _: () = {{\n", cfg);
+        output.push_str(&format!(
+            "    assert!(core::mem::size_of::<{}>() == {});\n",
+            name, layout.size
+        ));
+        output.push_str(&format!(
+            "    assert!(core::mem::align_of::<{}>() == {});\n",
+            name, layout.align
+        ));
+        for member in members {
+            if let Some(offset) = member.offset {
+                output.push_str(&format!(
+                    "    assert!(core::mem::offset_of!({}, {}) == {});\n",
+                    name, member.name, offset
+                ));
+            }
+        }
+        output.push_str("};\n\n");
+        output
+    }
+
+    /// Whether any field holds a union by value.
+    fn holds_union(&self, fields: &[CField], types: &TypeTable<'_>, depth: usize) -> bool {
+        fields
+            .iter()
+            .any(|f| self.is_union_value(&f.field_type, types, depth))
+    }
+
+    fn is_union_value(&self, ctype: &CType, types: &TypeTable<'_>, depth: usize) -> bool {
+        if depth > MAX_DEPTH {
+            return false;
+        }
+        match ctype {
+            CType::Array(inner, _) => self.is_union_value(inner, types, depth + 1),
+            CType::Aggregate(def) => {
+                def.is_union() || self.holds_union(&def.fields, types, depth + 1)
+            }
+            CType::Struct(name) | CType::Union(name) | CType::TypeDef(name) => {
+                match types.lookup(name) {
+                    Some(Declared::Struct(def)) => {
+                        def.is_union() || self.holds_union(&def.fields, types, depth + 1)
+                    }
+                    Some(Declared::Typedef(t)) => {
+                        self.is_union_value(&t.underlying_type, types, depth + 1)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Generate `extern` blocks, one per calling convention.
+    fn generate_extern_blocks(&self, functions: &[CFunction], types: &TypeTable<'_>) -> String {
+        let mut output = String::new();
+
+        let mut abis: Vec<&'static str> = Vec::new();
+        for func in functions {
+            let abi = extern_abi(func.calling_convention);
+            if !abis.contains(&abi) {
+                abis.push(abi);
+            }
+        }
+
+        for abi in abis {
+            output.push_str(&format!("unsafe extern \"{}\" {{\n", abi));
+            let mut first = true;
+            for func in functions
+                .iter()
+                .filter(|f| extern_abi(f.calling_convention) == abi)
+            {
+                if !first {
+                    output.push('\n');
+                }
+                first = false;
+                output.push_str(&self.generate_function(func, types));
+            }
+            output.push_str("}\n\n");
+        }
+
+        output
+    }
+
+    /// Generate a single foreign function declaration.
+    fn generate_function(&self, func: &CFunction, types: &TypeTable<'_>) -> String {
+        let mut output = String::new();
+
+        if let Some(ref doc) = func.doc {
+            output.push_str(&doc_comment(doc, "    "));
+        }
+
+        let symbol = self.symbol_name(func);
+        if symbol != func.name {
+            output.push_str(&format!("    #[link_name = \"{}\"]\n", symbol));
+        }
+
+        let mut params: Vec<String> = func
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                // Array parameters decay to pointers
+                let ty = match &p.param_type {
+                    CType::Array(inner, _) => CType::Pointer(inner.clone()),
+                    other => other.clone(),
+                };
+                format!("{}: {}", param_name(&p.name, i), self.rust_type(&ty, types))
+            })
+            .collect();
+        if func.variadic {
+            params.push("...".to_string());
+        }
+
+        output.push_str(&format!(
+            "    pub fn {}({}){};\n",
+            rust_ident(&func.name),
+            params.join(", "),
+            self.return_type(&func.return_type, types)
+        ));
+        output
+    }
+
+    /// Generate thin safe wrappers for functions with safe signatures.
+    ///
+    /// Wrappers are only emitted when every parameter is plain data, a
+    /// `const char *` (taken as `&CStr`) or a pointer to a plain-data struct
+    /// (taken as a reference), and the return type is plain data. Plain data
+    /// holds no pointers or enums at any depth, since the C side could
+    /// leave either invalid. Destructors are never wrapped.
+    fn generate_safe_wrappers(&self, functions: &[CFunction], types: &TypeTable<'_>) -> String {
+        let mut wrappers = Vec::new();
+
+        'functions: for func in functions {
+            // Handing a reference to a destructor would leave it dangling
+            if func.variadic || func.is_destructor() || !self.is_plain(&func.return_type, types, 0)
+            {
+                continue;
+            }
+
+            let mut params = Vec::new();
+            let mut args = Vec::new();
+            for (i, param) in func.params.iter().enumerate() {
+                let name = param_name(&param.name, i);
+                match &param.param_type {
+                    CType::ConstPointer(inner) if matches!(**inner, CType::Char) => {
+                        params.push(format!("{}: &CStr", name));
+                        args.push(format!("{}.as_ptr()", name));
+                    }
+                    CType::Pointer(inner) if self.is_plain_struct(inner, types) => {
+                        params.push(format!("{}: &mut {}", name, self.rust_type(inner, types)));
+                        args.push(name);
+                    }
+                    CType::ConstPointer(inner) if self.is_plain_struct(inner, types) => {
+                        params.push(format!("{}: &{}", name, self.rust_type(inner, types)));
+                        args.push(name);
+                    }
+                    // Array parameters decay to pointers
+                    CType::Array(..) => continue 'functions,
+                    other if self.is_plain(other, types, 0) => {
+                        params.push(format!("{}: {}", name, self.rust_type(other, types)));
+                        args.push(name);
+                    }
+                    _ => continue 'functions,
+                }
+            }
+
+            let mut wrapper = String::new();
+            if let Some(ref doc) = func.doc {
+                wrapper.push_str(&doc_comment(doc, "    "));
+            }
+            let name = rust_ident(&func.name);
+            wrapper.push_str(&format!(
+                "    pub fn {}({}){} {{\n        unsafe {{ super::{}({}) }}\n    }}\n",
+                name,
+                params.join(", "),
+                self.return_type(&func.return_type, types),
+                name,
+                args.join(", ")
+            ));
+            wrappers.push(wrapper);
+        }
+
+        if wrappers.is_empty() {
+            return String::new();
+        }
+
+        let mut output = String::from(
+            "/// Safe wrappers for functions taking plain data, C strings and references.\npub mod safe {\n",
+        );
+        if wrappers.iter().any(|w| w.contains("&CStr")) {
+            output.push_str("    use core::ffi::CStr;\n\n");
+        }
+        output.push_str("    use super::*;\n\n");
+        output.push_str(&wrappers.join("\n"));
+        output.push_str("}\n");
+        output
+    }
+
+    /// Convert a CType to a Rust type.
+    fn rust_type(&self, ctype: &CType, types: &TypeTable<'_>) -> String {
+        match ctype {
+            CType::Void => "c_void".to_string(),
+            CType::Int8 => "i8".to_string(),
+            CType::Int16 => "i16".to_string(),
+            CType::Int32 => "i32".to_string(),
+            CType::Int64 => "i64".to_string(),
+            CType::UInt8 => "u8".to_string(),
+            CType::UInt16 => "u16".to_string(),
+            CType::UInt32 => "u32".to_string(),
+            CType::UInt64 => "u64".to_string(),
+            CType::Float => "f32".to_string(),
+            CType::Double => "f64".to_string(),
+            CType::Bool => "bool".to_string(),
+            CType::Char => "c_char".to_string(),
+            CType::UChar => "u8".to_string(),
+            CType::WChar => "wchar_t".to_string(),
+            CType::Size => "usize".to_string(),
+            CType::SSize | CType::PtrDiff => "isize".to_string(),
+            CType::Pointer(inner) => format!("*mut {}", self.rust_type(inner, types)),
+            CType::ConstPointer(inner) => format!("*const {}", self.rust_type(inner, types)),
+            CType::Array(inner, size) => format!("[{}; {}]", self.rust_type(inner, types), size),
+            CType::Struct(name) | CType::Union(name) | CType::Enum(name) | CType::TypeDef(name) => {
+                match types.lookup(name) {
+                    Some(declared) => rust_ident(declared.name()),
+                    None => rust_ident(self.strip(name)),
+                }
+            }
+            // Inline definitions are renamed after their generated type
+            CType::Aggregate(def) => rust_ident(&def.name),
+            CType::FunctionPointer {
+                return_type,
+                param_types,
+            } => {
+                let params: Vec<String> = param_types
+                    .iter()
+                    .map(|p| self.rust_type(p, types))
+                    .collect();
+                format!(
+                    "Option<unsafe extern \"C\" fn({}){}>",
+                    params.join(", "),
+                    self.return_type(return_type, types)
+                )
+            }
+            CType::Opaque(name) => rust_ident(self.strip(name)),
+        }
+    }
+
+    /// Render a return type suffix (` -> T`, or nothing for void).
+    fn return_type(&self, ctype: &CType, types: &TypeTable<'_>) -> String {
+        if ctype.is_void() {
+            String::new()
+        } else {
+            format!(" -> {}", self.rust_type(ctype, types))
+        }
+    }
+
+    /// Names of pointee types the header uses but never defines.
+    fn opaque_types(&self, header: &ParsedHeader, types: &TypeTable<'_>) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut visit = |ctype: &CType| {
+            let mut stack = vec![ctype];
+            while let Some(ctype) = stack.pop() {
+                match ctype {
+                    CType::Pointer(inner) | CType::ConstPointer(inner) | CType::Array(inner, _) => {
+                        stack.push(inner)
+                    }
+                    CType::FunctionPointer {
+                        return_type,
+                        param_types,
+                    } => {
+                        stack.push(return_type);
+                        stack.extend(param_types.iter());
+                    }
+                    CType::Aggregate(def) => stack.extend(def.fields.iter().map(|f| &f.field_type)),
+                    CType::Struct(name)
+                    | CType::Union(name)
+                    | CType::Enum(name)
+                    | CType::TypeDef(name)
+                    | CType::Opaque(name) => {
+                        let name = self.strip(name).to_string();
+                        if types.lookup(&name).is_none() && !names.contains(&name) {
+                            names.push(name);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        for s in &header.structs {
+            for field in &s.fields {
+                visit(&field.field_type);
+            }
+        }
+        for t in &header.typedefs {
+            visit(&t.underlying_type);
+        }
+        for func in &header.functions {
+            visit(&func.return_type);
+            for param in &func.params {
+                visit(&param.param_type);
+            }
+        }
+
+        names
+    }
+
+    /// Whether a type is plain data: no pointers or enums at any depth.
+    fn is_plain(&self, ctype: &CType, types: &TypeTable<'_>, depth: usize) -> bool {
+        if depth > MAX_DEPTH {
+            return false;
+        }
+        match ctype {
+            CType::Pointer(_)
+            | CType::ConstPointer(_)
+            | CType::FunctionPointer { .. }
+            | CType::Enum(_)
+            | CType::Opaque(_) => false,
+            CType::Array(inner, _) => self.is_plain(inner, types, depth + 1),
+            CType::Aggregate(def) => self.is_plain_fields(def, types, depth + 1),
+            CType::Struct(name) | CType::Union(name) | CType::TypeDef(name) => {
+                match types.lookup(name) {
+                    Some(Declared::Struct(def)) => self.is_plain_fields(def, types, depth + 1),
+                    Some(Declared::Typedef(t)) => {
+                        self.is_plain(&t.underlying_type, types, depth + 1)
+                    }
+                    _ => false,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    fn is_plain_fields(&self, def: &CStruct, types: &TypeTable<'_>, depth: usize) -> bool {
+        def.fields
+            .iter()
+            .all(|f| self.is_plain(&f.field_type, types, depth))
+    }
+
+    /// Whether a type names a plain-data struct or union defined in the header.
+    fn is_plain_struct(&self, ctype: &CType, types: &TypeTable<'_>) -> bool {
+        match ctype {
+            CType::Struct(name) | CType::Union(name) | CType::TypeDef(name) => {
+                matches!(types.lookup(name), Some(Declared::Struct(_)))
+                    && self.is_plain(ctype, types, 0)
+            }
+            _ => false,
+        }
+    }
+
+    /// Strip the configured prefix from a referenced type name.
+    fn strip<'n>(&self, name: &'n str) -> &'n str {
+        match self.strip_prefix {
+            Some(ref prefix) => name.strip_prefix(prefix.as_str()).unwrap_or(name),
+            None => name,
+        }
+    }
+
+    /// The library symbol for a function, with any stripped prefix restored.
+    fn symbol_name(&self, func: &CFunction) -> String {
+        match self.strip_prefix {
+            Some(ref prefix) => format!("{}{}", prefix, func.name),
+            None => func.name.clone(),
+        }
+    }
+
+    /// Library name as a Rust identifier.
+    fn module_name(&self) -> String {
+        self.library_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+}
+
+/// Write generated content, creating parent directories.
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory: {}", parent.display()))?;
+    }
+
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("failed to create file: {}", path.display()))?;
+
+    file.write_all(content.as_bytes())
+        .with_context(|| format!("failed to write to file: {}", path.display()))?;
+
+    Ok(())
+}
+
+/// ABI string for an `extern` block.
+fn extern_abi(conv: CallingConvention) -> &'static str {
+    match conv {
+        CallingConvention::Cdecl => "C",
+        // stdcall on 32-bit Windows, C everywhere else
+        CallingConvention::Stdcall => "system",
+        CallingConvention::Fastcall => "fastcall",
+        CallingConvention::SysV => "sysv64",
+        CallingConvention::Win64 => "win64",
+    }
+}
+
+/// Render a `#define` as a Rust constant, if it has a literal value.
+fn rust_constant(constant: &CConstant) -> Option<String> {
+    let value = constant.value.trim();
+    let name = rust_ident(&constant.name);
+
+    let (ty, value) = match constant.const_type.as_ref()? {
+        CType::Float | CType::Double => {
+            let digits = value.trim_end_matches(['f', 'F', 'l', 'L']);
+            let value = if digits.contains(['.', 'e', 'E']) {
+                digits.to_string()
+            } else {
+                format!("{}.0", digits)
+            };
+            ("f64", value)
+        }
+        CType::Char => ("c_char", format!("b{} as c_char", value)),
+        CType::ConstPointer(_) => ("&core::ffi::CStr", format!("c{}", value)),
+        declared => {
+            let number = constant.int_value()?;
+
+            // Widen like C does when the literal doesn't fit
+            let fits = |ty: &str| match ty {
+                "i32" => i32::try_from(number).is_ok(),
+                "u32" => u32::try_from(number).is_ok(),
+                "i64" => i64::try_from(number).is_ok(),
+                _ => u64::try_from(number).is_ok(),
+            };
+            let start = match declared {
+                CType::UInt32 => 1,
+                CType::Int64 => 2,
+                CType::UInt64 => 3,
+                _ => 0,
+            };
+            let ty = ["i32", "u32", "i64", "u64"][start..]
+                .iter()
+                .copied()
+                .find(|ty| fits(ty))?;
+
+            let value = if constant.is_hex() {
+                value.trim_end_matches(['u', 'U', 'l', 'L']).to_string()
+            } else {
+                number.to_string()
+            };
+            (ty, value)
+        }
+    };
+
+    Some(format!("pub const {}: {} = {};", name, ty, value))
+}
+
+/// Render a doc string as `///` comments.
+fn doc_comment(doc: &str, indent: &str) -> String {
+    doc.lines()
+        .map(|line| format!("{}/// {}\n", indent, line.trim()))
+        .collect()
+}
+
+/// The inline struct or union definition a field holds, if any.
+fn inline_aggregate(ctype: &CType) -> Option<&CStruct> {
+    match ctype {
+        CType::Aggregate(def) => Some(def),
+        CType::Array(inner, _) => inline_aggregate(inner),
+        _ => None,
+    }
+}
+
+/// Rename the inline definition in a field type to its generated type.
+fn rename_aggregate(ctype: &CType, name: &str) -> CType {
+    match ctype {
+        CType::Aggregate(def) => CType::Aggregate(Box::new(CStruct {
+            name: name.to_string(),
+            ..(**def).clone()
+        })),
+        CType::Array(inner, size) => CType::Array(Box::new(rename_aggregate(inner, name)), *size),
+        other => other.clone(),
+    }
+}
+
+/// Field name, with anonymous members named after their position.
+fn member_name(index: usize, name: &str) -> String {
+    if name.is_empty() {
+        format!("_anon{}", index)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Name for a parameter, inventing one for unnamed parameters.
+fn param_name(name: &str, index: usize) -> String {
+    if name.is_empty() {
+        format!("arg{}", index)
+    } else {
+        rust_ident(name)
+    }
+}
+
+/// Escape a Rust keyword.
+fn rust_ident(name: &str) -> String {
+    if RUST_RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else if RUST_KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::bindings::types::{CEnumVariant, CParam};
+    use crate::test_support::golden::{assert_golden, ffi_project_header, golden_abi};
+
+    #[test]
+    fn test_golden_rust() {
+        let output = RustGenerator::new("mylib")
+            .with_strip_prefix(Some("mylib_".to_string()))
+            .with_safe_wrappers(true)
+            .with_abi(golden_abi())
+            .generate(&ffi_project_header())
+            .unwrap();
+        assert_golden(&output, "mylib.rs");
+    }
+
+    #[test]
+    fn test_opaque_types_and_callbacks() {
+        let mut header = ParsedHeader::default();
+        header.functions.push(
+            CFunction::new(
+                "ctx_open",
+                CType::Pointer(Box::new(CType::TypeDef("ctx_t".to_string()))),
+            )
+            .with_param(CParam::new(
+                "log",
+                CType::FunctionPointer {
+                    return_type: Box::new(CType::Void),
+                    param_types: vec![CType::ConstPointer(Box::new(CType::Char))],
+                },
+            )),
+        );
+
+        let output = RustGenerator::new("demo").generate(&header).unwrap();
+        assert!(output.contains("pub struct ctx_t {\n    _data: [u8; 0],"));
+        assert!(output.contains("use core::marker::{PhantomData, PhantomPinned};"));
+        assert!(output.contains(
+            "pub fn ctx_open(log: Option<unsafe extern \"C\" fn(*const c_char)>) -> *mut ctx_t;"
+        ));
+    }
+
+    #[test]
+    fn test_layout_assertions() {
+        let mut header = ParsedHeader::default();
+        header.structs.push(
+            CStruct::new("sample")
+                .with_field(CField::new("tag", CType::UInt8))
+                .with_field(CField::new("value", CType::Double))
+                .with_field(CField::new("type", CType::Int16)),
+        );
+
+        let output = RustGenerator::new("demo")
+            .with_abi(golden_abi())
+            .generate(&header)
+            .unwrap();
+        assert!(output.contains("assert!(core::mem::size_of::<sample>() == 24);"));
+        assert!(output.contains("assert!(core::mem::align_of::<sample>() == 8);"));
+        assert!(output.contains("assert!(core::mem::offset_of!(sample, value) == 8);"));
+        assert!(output.contains("assert!(core::mem::offset_of!(sample, r#type) == 16);"));
+    }
+
+    #[test]
+    fn test_bitfields_keep_c_layout() {
+        let mut header = ParsedHeader::default();
+        header.structs.push(
+            CStruct::new("mixed")
+                .with_field(CField::new("a", CType::Char))
+                .with_field(CField::bitfield("b", CType::Int32, 3))
+                .with_field(CField::bitfield("c", CType::Int32, 2)),
+        );
+
+        // The storage is one byte, but C aligns the struct like an int
+        let output = RustGenerator::new("demo")
+            .with_abi(golden_abi())
+            .generate(&header)
+            .unwrap();
+        assert!(output.contains(
+            "pub struct mixed {\n    pub _align: [u32; 0],\n    pub a: c_char,\n    /// Bitfields: b (bits 0..3), c (bits 3..5)\n    pub _bitfield_1: u8,\n}"
+        ));
+        assert!(output.contains("#[cfg(all(target_pointer_width = \"64\", not(windows)))]"));
+        assert!(output.contains("assert!(core::mem::size_of::<mixed>() == 4);"));
+    }
+
+    #[test]
+    fn test_unions_and_anonymous_members() {
+        let mut header = ParsedHeader::default();
+        let inner = CStruct::union("")
+            .with_field(CField::new("i", CType::Int32))
+            .with_field(CField::new("f", CType::Float));
+        header.structs.push(
+            CStruct::new("value")
+                .with_field(CField::new("tag", CType::UInt8))
+                .with_field(CField::new("", CType::Aggregate(Box::new(inner)))),
+        );
+
+        let output = RustGenerator::new("demo")
+            .with_abi(golden_abi())
+            .generate(&header)
+            .unwrap();
+        assert!(output.contains("#[derive(Clone, Copy)]\npub union value__anon1 {"));
+        assert!(output.contains("#[derive(Clone, Copy)]\npub struct value {"));
+        assert!(output.contains("    pub _anon1: value__anon1,\n"));
+        assert!(output.contains("assert!(core::mem::offset_of!(value, _anon1) == 4);"));
+    }
+
+    #[test]
+    fn test_packing_and_alignment() {
+        let gen = RustGenerator::new("demo");
+        let mut header = ParsedHeader::default();
+        header.structs.push(
+            CStruct::new("wire")
+                .with_field(CField::new("a", CType::UInt8))
+                .with_field(CField::new("b", CType::UInt32))
+                .with_pack(2),
+        );
+        header.structs.push(
+            CStruct::new("cacheline")
+                .with_field(CField::new("a", CType::UInt8))
+                .with_align(64),
+        );
+        let output = gen.generate(&header).unwrap();
+        assert!(output
+            .contains("#[repr(C, packed(2))]\n#[derive(Debug, Clone, Copy)]\npub struct wire"));
+        assert!(output.contains("#[repr(C, align(64))]"));
+
+        header.structs[0].align = Some(8);
+        let err = gen.generate(&header).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`wire` is both packed and aligned"));
+    }
+
+    #[test]
+    fn test_enums_are_integer_newtypes() {
+        let gen = RustGenerator::new("demo");
+        let enum_def = CEnum::new("level_t")
+            .with_variant(CEnumVariant::new("LEVEL_LOW").with_value(0))
+            .with_variant(CEnumVariant::new("LEVEL_HIGH"))
+            .with_variant(CEnumVariant::new("LEVEL_DEFAULT").with_value(0));
+
+        let output = gen.generate_enum(&enum_def);
+        assert!(output.contains("#[repr(transparent)]"));
+        assert!(output.contains("pub struct level_t(pub c_int);"));
+        assert!(output.contains("    pub const LEVEL_HIGH: level_t = level_t(1);"));
+        assert!(output.contains("    pub const LEVEL_DEFAULT: level_t = level_t(0);"));
+    }
+
+    #[test]
+    fn test_safe_wrappers_skip_pointers_and_enums() {
+        let mut header = ParsedHeader::default();
+        header
+            .enums
+            .push(CEnum::new("mode_t").with_variant(CEnumVariant::new("MODE_A")));
+        header.structs.push(
+            CStruct::new("extent")
+                .with_field(CField::new("w", CType::Int32))
+                .with_field(CField::new("h", CType::Int32)),
+        );
+        header.structs.push(
+            CStruct::new("named")
+                .with_field(CField::new("name", CType::Pointer(Box::new(CType::Char)))),
+        );
+        header.structs.push(
+            CStruct::new("moded").with_field(CField::new("mode", CType::Enum("mode_t".into()))),
+        );
+        header
+            .functions
+            .push(CFunction::new("grow", CType::Void).with_param(CParam::new(
+                "s",
+                CType::Pointer(Box::new(CType::TypeDef("extent".into()))),
+            )));
+        header.functions.push(
+            CFunction::new("rename", CType::Void).with_param(CParam::new(
+                "n",
+                CType::Pointer(Box::new(CType::TypeDef("named".into()))),
+            )),
+        );
+        header
+            .functions
+            .push(CFunction::new("reset", CType::Void).with_param(CParam::new(
+                "m",
+                CType::ConstPointer(Box::new(CType::TypeDef("moded".into()))),
+            )));
+        header
+            .functions
+            .push(CFunction::new("mode", CType::Enum("mode_t".into())));
+
+        let output = RustGenerator::new("demo")
+            .with_safe_wrappers(true)
+            .generate(&header)
+            .unwrap();
+        let safe = &output[output.find("pub mod safe").unwrap()..];
+        assert!(safe.contains("pub fn grow(s: &mut extent)"));
+        assert!(!safe.contains("fn rename"));
+        assert!(!safe.contains("fn reset"));
+        assert!(!safe.contains("fn mode"));
+    }
+
+    #[test]
+    fn test_build_script_links_bundle() {
+        let script = RustGenerator::new("mylib").generate_build_script();
+        assert!(script.contains("cargo:rustc-link-lib=dylib=mylib"));
+        assert!(script.contains("env::var_os(\"MYLIB_BUNDLE_DIR\")"));
+        assert!(script.contains(".join(\"ffi_bundle\")"));
+    }
+}
@@ -2,10 +2,14 @@
 //!
 //! These types represent the FFI-relevant information extracted from C headers.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use super::layout::{LayoutEngine, StructLayout, TargetAbi};
+
 /// A parsed C header file containing all FFI-relevant information.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ParsedHeader {
@@ -45,6 +49,86 @@ impl ParsedHeader {
         self.typedefs.extend(other.typedefs);
         self.constants.extend(other.constants);
     }
+
+    /// Compute the layout of every struct and union for `abi`.
+    ///
+    /// Layouts are stored on each `CStruct`, including nested anonymous
+    /// members. Fails on the first aggregate whose size cannot be
+    /// determined, such as one holding an undeclared type by value.
+    pub fn compute_layouts(&mut self, abi: &TargetAbi, strip_prefix: Option<&str>) -> Result<()> {
+        let layouts = {
+            let types = TypeTable::new(self, strip_prefix);
+            let engine = LayoutEngine::new(&types, *abi);
+            self.structs
+                .iter()
+                .map(|s| engine.layout(s))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        for (s, layout) in self.structs.iter_mut().zip(layouts) {
+            s.set_layout(layout);
+        }
+        Ok(())
+    }
+}
+
+/// A type declared in a parsed header.
+#[derive(Debug, Clone, Copy)]
+pub enum Declared<'a> {
+    Struct(&'a CStruct),
+    Enum(&'a CEnum),
+    Typedef(&'a CTypedef),
+}
+
+impl<'a> Declared<'a> {
+    /// The declared (possibly prefix-stripped) name.
+    pub fn name(&self) -> &'a str {
+        match self {
+            Declared::Struct(s) => &s.name,
+            Declared::Enum(e) => &e.name,
+            Declared::Typedef(t) => &t.name,
+        }
+    }
+}
+
+/// Declared types of a header, used by generators to resolve `CType` names.
+///
+/// Declarations carry prefix-stripped names while references inside fields
+/// and parameters keep the original spelling, so lookups retry with the
+/// prefix removed.
+pub struct TypeTable<'a> {
+    names: HashMap<&'a str, Declared<'a>>,
+    strip_prefix: Option<&'a str>,
+}
+
+impl<'a> TypeTable<'a> {
+    /// Index the types declared in `header`.
+    pub fn new(header: &'a ParsedHeader, strip_prefix: Option<&'a str>) -> Self {
+        let mut names = HashMap::new();
+        for s in &header.structs {
+            names.insert(s.name.as_str(), Declared::Struct(s));
+        }
+        for e in &header.enums {
+            names.insert(e.name.as_str(), Declared::Enum(e));
+        }
+        for t in &header.typedefs {
+            names.insert(t.name.as_str(), Declared::Typedef(t));
+        }
+
+        TypeTable {
+            names,
+            strip_prefix,
+        }
+    }
+
+    /// Resolve a referenced type name to its declaration.
+    pub fn lookup(&self, name: &str) -> Option<Declared<'a>> {
+        if let Some(declared) = self.names.get(name) {
+            return Some(*declared);
+        }
+        let stripped = name.strip_prefix(self.strip_prefix?)?;
+        self.names.get(stripped).copied()
+    }
 }
 
 /// A C function declaration.
@@ -69,6 +153,11 @@ pub struct CFunction {
     pub doc: Option<String>,
 }
 
+/// Function name fragments that mark a destructor.
+const DESTRUCTOR_WORDS: &[&str] = &[
+    "free", "destroy", "release", "close", "delete", "dispose", "unref",
+];
+
 impl CFunction {
     /// Create a new function with the given name and return type.
     pub fn new(name: impl Into<String>, return_type: CType) -> Self {
@@ -87,6 +176,13 @@ impl CFunction {
         self.params.push(param);
         self
     }
+
+    /// Whether this looks like a destructor: one parameter, and a name
+    /// such as `foo_free` or `destroy_foo`.
+    pub fn is_destructor(&self) -> bool {
+        let lower = self.name.to_lowercase();
+        self.params.len() == 1 && DESTRUCTOR_WORDS.iter().any(|w| lower.contains(w))
+    }
 }
 
 /// A function parameter.
@@ -139,17 +235,58 @@ impl CallingConvention {
     }
 }
 
-/// A C structure definition.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Whether an aggregate is a `struct` or a `union`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateKind {
+    /// Fields laid out one after another
+    #[default]
+    Struct,
+    /// Fields overlapping at offset zero
+    Union,
+}
+
+impl AggregateKind {
+    /// The C keyword.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            AggregateKind::Struct => "struct",
+            AggregateKind::Union => "union",
+        }
+    }
+}
+
+/// A C structure or union definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CStruct {
-    /// Struct name
+    /// Struct name (empty for anonymous members)
     pub name: String,
 
+    /// Struct or union
+    #[serde(default)]
+    pub kind: AggregateKind,
+
+    /// Whether `name` is a typedef name rather than a tag, so C code
+    /// spells the type without `struct`/`union`
+    #[serde(default)]
+    pub typedef: bool,
+
     /// Struct fields
     pub fields: Vec<CField>,
 
-    /// Whether this is a packed struct
-    pub packed: bool,
+    /// Maximum field alignment, from `#pragma pack(n)` or
+    /// `__attribute__((packed))` (which packs to 1)
+    #[serde(default)]
+    pub pack: Option<u64>,
+
+    /// Minimum alignment, from `__attribute__((aligned(n)))` or
+    /// `__declspec(align(n))`
+    #[serde(default)]
+    pub align: Option<u64>,
+
+    /// Layout computed by `ParsedHeader::compute_layouts`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<StructLayout>,
 
     /// Documentation comment
     pub doc: Option<String>,
@@ -160,23 +297,77 @@ impl CStruct {
     pub fn new(name: impl Into<String>) -> Self {
         CStruct {
             name: name.into(),
+            kind: AggregateKind::Struct,
+            typedef: false,
             fields: Vec::new(),
-            packed: false,
+            pack: None,
+            align: None,
+            layout: None,
             doc: None,
         }
     }
 
+    /// Create a new union.
+    pub fn union(name: impl Into<String>) -> Self {
+        CStruct {
+            kind: AggregateKind::Union,
+            ..CStruct::new(name)
+        }
+    }
+
     /// Add a field.
     pub fn with_field(mut self, field: CField) -> Self {
         self.fields.push(field);
         self
     }
+
+    /// Set the maximum field alignment.
+    pub fn with_pack(mut self, pack: u64) -> Self {
+        self.pack = Some(pack);
+        self
+    }
+
+    /// Set the minimum alignment.
+    pub fn with_align(mut self, align: u64) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    /// Whether this is a union.
+    pub fn is_union(&self) -> bool {
+        self.kind == AggregateKind::Union
+    }
+
+    /// How C code spells the type, e.g. `point_t` or `struct point`.
+    pub fn c_spelling(&self) -> String {
+        if self.typedef {
+            self.name.clone()
+        } else {
+            format!("{} {}", self.kind.keyword(), self.name)
+        }
+    }
+
+    /// Store a computed layout, and those of nested anonymous members.
+    fn set_layout(&mut self, layout: StructLayout) {
+        for (field, placed) in self.fields.iter_mut().zip(&layout.fields) {
+            if let (CType::Aggregate(inner), Some(nested)) = (&mut field.field_type, &placed.layout)
+            {
+                inner.set_layout((**nested).clone());
+            }
+        }
+        self.layout = Some(layout);
+    }
+
+    /// Whether any field is a bitfield.
+    pub fn has_bitfields(&self) -> bool {
+        self.fields.iter().any(|f| f.bit_width.is_some())
+    }
 }
 
 /// A struct field.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CField {
-    /// Field name
+    /// Field name (empty for anonymous members and unnamed bitfields)
     pub name: String,
 
     /// Field type
@@ -195,6 +386,22 @@ impl CField {
             bit_width: None,
         }
     }
+
+    /// Create a bitfield.
+    pub fn bitfield(name: impl Into<String>, field_type: CType, width: u32) -> Self {
+        CField {
+            bit_width: Some(width),
+            ..CField::new(name, field_type)
+        }
+    }
+
+    /// The inline definition of an anonymous struct or union member.
+    pub fn anonymous(&self) -> Option<&CStruct> {
+        match &self.field_type {
+            CType::Aggregate(def) if self.name.is_empty() => Some(def),
+            _ => None,
+        }
+    }
 }
 
 /// A C enumeration.
@@ -225,6 +432,20 @@ impl CEnum {
         self.variants.push(variant);
         self
     }
+
+    /// Each variant's value, counting on from the previous one where no
+    /// value is given.
+    pub fn values(&self) -> Vec<(&str, i64)> {
+        let mut next_value = 0i64;
+        self.variants
+            .iter()
+            .map(|variant| {
+                let value = variant.value.unwrap_or(next_value);
+                next_value = value.wrapping_add(1);
+                (variant.name.as_str(), value)
+            })
+            .collect()
+    }
 }
 
 /// An enum variant.
@@ -299,6 +520,43 @@ impl CConstant {
             const_type: None,
         }
     }
+
+    /// Parse the value as a C integer literal.
+    ///
+    /// Handles decimal, hex and octal literals with `U`/`L` suffixes.
+    pub fn int_value(&self) -> Option<i128> {
+        let digits = self.int_digits();
+        let (negative, unsigned) = match digits.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, digits),
+        };
+
+        let (radix, body) = if let Some(hex) = unsigned
+            .strip_prefix("0x")
+            .or_else(|| unsigned.strip_prefix("0X"))
+        {
+            (16, hex)
+        } else if unsigned.len() > 1 && unsigned.starts_with('0') {
+            (8, &unsigned[1..])
+        } else {
+            (10, unsigned)
+        };
+
+        let magnitude = i128::from_str_radix(body, radix).ok()?;
+        Some(if negative { -magnitude } else { magnitude })
+    }
+
+    /// Whether the value is written as a hex literal.
+    pub fn is_hex(&self) -> bool {
+        let digits = self.int_digits();
+        let unsigned = digits.strip_prefix('-').unwrap_or(digits);
+        unsigned.starts_with("0x") || unsigned.starts_with("0X")
+    }
+
+    /// The value with integer suffixes removed.
+    fn int_digits(&self) -> &str {
+        self.value.trim().trim_end_matches(['u', 'U', 'l', 'L'])
+    }
 }
 
 /// C type representation.
@@ -348,6 +606,12 @@ pub enum CType {
     /// Reference to a struct
     Struct(String),
 
+    /// Reference to a union
+    Union(String),
+
+    /// Inline definition of a nested anonymous struct or union
+    Aggregate(Box<CStruct>),
+
     /// Reference to an enum
     Enum(String),
 
@@ -381,6 +645,13 @@ impl CType {
         // Handle const prefix
         let s = s.strip_prefix("const ").unwrap_or(s);
 
+        // A bare `unsigned` or `signed` means int
+        let s = match s {
+            "unsigned" => "unsigned int",
+            "signed" => "int",
+            s => s,
+        };
+
         // Handle unsigned prefix
         let (is_unsigned, s) = if let Some(stripped) = s.strip_prefix("unsigned ") {
             (true, stripped)
@@ -433,6 +704,8 @@ impl CType {
             other => {
                 if let Some(stripped) = other.strip_prefix("struct ") {
                     CType::Struct(stripped.to_string())
+                } else if let Some(stripped) = other.strip_prefix("union ") {
+                    CType::Union(stripped.to_string())
                 } else if let Some(stripped) = other.strip_prefix("enum ") {
                     CType::Enum(stripped.to_string())
                 } else {
@@ -452,6 +725,50 @@ impl CType {
         matches!(self, CType::Void)
     }
 
+    /// Get the C spelling of the type, as used in bundle manifests.
+    pub fn as_c(&self) -> String {
+        match self {
+            CType::Void => "void".to_string(),
+            CType::Int8 => "int8_t".to_string(),
+            CType::Int16 => "int16_t".to_string(),
+            CType::Int32 => "int32_t".to_string(),
+            CType::Int64 => "int64_t".to_string(),
+            CType::UInt8 => "uint8_t".to_string(),
+            CType::UInt16 => "uint16_t".to_string(),
+            CType::UInt32 => "uint32_t".to_string(),
+            CType::UInt64 => "uint64_t".to_string(),
+            CType::Float => "float".to_string(),
+            CType::Double => "double".to_string(),
+            CType::Bool => "bool".to_string(),
+            CType::Char => "char".to_string(),
+            CType::UChar => "unsigned char".to_string(),
+            CType::WChar => "wchar_t".to_string(),
+            CType::Size => "size_t".to_string(),
+            CType::SSize => "ssize_t".to_string(),
+            CType::PtrDiff => "ptrdiff_t".to_string(),
+            CType::Pointer(inner) => format!("{}*", inner.as_c()),
+            CType::ConstPointer(inner) => format!("const {}*", inner.as_c()),
+            CType::Array(inner, size) => format!("{}[{}]", inner.as_c(), size),
+            CType::Struct(name) => format!("struct {}", name),
+            CType::Union(name) => format!("union {}", name),
+            CType::Aggregate(def) => format!("{} {{...}}", def.kind.keyword()),
+            CType::Enum(name) => format!("enum {}", name),
+            CType::TypeDef(name) | CType::Opaque(name) => name.clone(),
+            CType::FunctionPointer {
+                return_type,
+                param_types,
+            } => {
+                let params: Vec<String> = param_types.iter().map(CType::as_c).collect();
+                let params = if params.is_empty() {
+                    "void".to_string()
+                } else {
+                    params.join(", ")
+                };
+                format!("{} (*)({})", return_type.as_c(), params)
+            }
+        }
+    }
+
     /// Get the koffi type name.
     pub fn as_koffi(&self) -> String {
         match self {
@@ -478,7 +795,8 @@ impl CType {
             CType::ConstPointer(inner) if inner.is_void() => "pointer".to_string(),
             CType::ConstPointer(inner) => format!("{}*", inner.as_koffi()),
             CType::Array(inner, size) => format!("koffi.array({}, {})", inner.as_koffi(), size),
-            CType::Struct(name) => name.clone(),
+            CType::Struct(name) | CType::Union(name) => name.clone(),
+            CType::Aggregate(_) => "pointer".to_string(),
             CType::Enum(name) => name.clone(),
             CType::TypeDef(name) => name.clone(),
             CType::FunctionPointer { .. } => "pointer".to_string(),
@@ -509,7 +827,8 @@ impl CType {
             CType::ConstPointer(inner) if matches!(**inner, CType::Char) => "string".to_string(),
             CType::Pointer(_) | CType::ConstPointer(_) => "Buffer | number".to_string(),
             CType::Array(inner, _) => format!("{}[]", inner.as_typescript()),
-            CType::Struct(name) => name.clone(),
+            CType::Struct(name) | CType::Union(name) => name.clone(),
+            CType::Aggregate(_) => "object".to_string(),
             CType::Enum(name) => name.clone(),
             CType::TypeDef(name) => name.clone(),
             CType::FunctionPointer { .. } => "Function".to_string(),
@@ -522,6 +841,16 @@ impl CType {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_constant_int_value() {
+        assert_eq!(CConstant::new("A", "1024").int_value(), Some(1024));
+        assert_eq!(CConstant::new("A", "0x10UL").int_value(), Some(16));
+        assert_eq!(CConstant::new("A", "0755").int_value(), Some(493));
+        assert_eq!(CConstant::new("A", "-1").int_value(), Some(-1));
+        assert_eq!(CConstant::new("A", "(1 << 3)").int_value(), None);
+        assert!(CConstant::new("A", "0xFFu").is_hex());
+    }
+
     #[test]
     fn test_ctype_parse() {
         assert_eq!(CType::parse("void"), CType::Void);
@@ -539,6 +868,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ctype_as_c() {
+        assert_eq!(CType::parse("const char*").as_c(), "const char*");
+        assert_eq!(CType::parse("struct point").as_c(), "struct point");
+        assert_eq!(
+            CType::Array(Box::new(CType::UInt8), 16).as_c(),
+            "uint8_t[16]"
+        );
+        let callback = CType::FunctionPointer {
+            return_type: Box::new(CType::Void),
+            param_types: vec![CType::Pointer(Box::new(CType::Void)), CType::Int32],
+        };
+        assert_eq!(callback.as_c(), "void (*)(void*, int32_t)");
+    }
+
+    #[test]
+    fn test_enum_values() {
+        let level = CEnum::new("level_t")
+            .with_variant(CEnumVariant::new("LOW"))
+            .with_variant(CEnumVariant::new("HIGH").with_value(10))
+            .with_variant(CEnumVariant::new("HIGHER"))
+            .with_variant(CEnumVariant::new("DEFAULT").with_value(0));
+        assert_eq!(
+            level.values(),
+            vec![("LOW", 0), ("HIGH", 10), ("HIGHER", 11), ("DEFAULT", 0)]
+        );
+    }
+
     #[test]
     fn test_ctype_as_koffi() {
         assert_eq!(CType::Int32.as_koffi(), "int32");
@@ -5,9 +5,12 @@
 use std::io::Write;
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
-use super::types::{CEnum, CFunction, CStruct, CType, CallingConvention, ParsedHeader};
+use super::layout::{bitfield_units, LayoutEngine, TargetAbi};
+use super::types::{
+    AggregateKind, CEnum, CFunction, CStruct, CType, CallingConvention, ParsedHeader, TypeTable,
+};
 use crate::core::target::FfiBundler;
 
 /// TypeScript binding generator.
@@ -20,6 +23,22 @@ pub struct TypeScriptGenerator {
     bundler: FfiBundler,
     /// Library file path (relative to output)
     lib_path: String,
+    /// Prefix the parser stripped from declared names
+    strip_prefix: Option<String>,
+    /// ABI struct layouts are computed for
+    abi: TargetAbi,
+}
+
+/// A member of a generated koffi struct.
+struct KoffiMember {
+    /// Property name
+    name: String,
+    /// koffi type expression
+    koffi: String,
+    /// TypeScript type
+    ts: String,
+    /// Trailing comment, e.g. the bitfields stored in the member
+    comment: Option<String>,
 }
 
 impl TypeScriptGenerator {
@@ -30,6 +49,8 @@ impl TypeScriptGenerator {
             async_wrappers: false,
             bundler: FfiBundler::Koffi,
             lib_path: String::new(),
+            strip_prefix: None,
+            abi: TargetAbi::host(),
         }
     }
 
@@ -51,8 +72,23 @@ impl TypeScriptGenerator {
         self
     }
 
+    /// Set the prefix the parser stripped from declared names.
+    pub fn with_strip_prefix(mut self, prefix: Option<String>) -> Self {
+        self.strip_prefix = prefix;
+        self
+    }
+
+    /// Set the ABI struct layouts are computed for.
+    pub fn with_abi(mut self, abi: TargetAbi) -> Self {
+        self.abi = abi;
+        self
+    }
+
     /// Generate TypeScript bindings from parsed headers.
+    ///
+    /// Fails if a struct's layout can't be expressed with the chosen bundler.
     pub fn generate(&self, header: &ParsedHeader) -> Result<String> {
+        let types = TypeTable::new(header, self.strip_prefix.as_deref());
         let mut output = String::new();
 
         // File header
@@ -71,12 +107,12 @@ impl TypeScriptGenerator {
 
         // Structs as koffi struct definitions
         for struct_def in &header.structs {
-            output.push_str(&self.generate_struct(struct_def));
+            output.push_str(&self.generate_struct(struct_def, &types)?);
         }
 
         // Struct interfaces for type safety
         for struct_def in &header.structs {
-            output.push_str(&self.generate_struct_interface(struct_def));
+            output.push_str(&self.generate_struct_interface(struct_def, &types)?);
         }
 
         // Function bindings
@@ -226,16 +262,16 @@ const lib = ffi.Library(libPath, {{
     }
 
     /// Generate koffi struct definition.
-    fn generate_struct(&self, struct_def: &CStruct) -> String {
+    fn generate_struct(&self, struct_def: &CStruct, types: &TypeTable<'_>) -> Result<String> {
         match self.bundler {
-            FfiBundler::Koffi => self.generate_koffi_struct(struct_def),
+            FfiBundler::Koffi => self.generate_koffi_struct(struct_def, types),
             FfiBundler::FfiNapi => self.generate_ffi_napi_struct(struct_def),
-            _ => String::new(),
+            _ => Ok(String::new()),
         }
     }
 
     /// Generate koffi struct.
-    fn generate_koffi_struct(&self, struct_def: &CStruct) -> String {
+    fn generate_koffi_struct(&self, struct_def: &CStruct, types: &TypeTable<'_>) -> Result<String> {
         let mut output = String::new();
 
         if let Some(ref doc) = struct_def.doc {
@@ -243,21 +279,256 @@ const lib = ffi.Library(libPath, {{
         }
 
         output.push_str(&format!(
-            "const {} = koffi.struct('{}', {{\n",
-            struct_def.name, struct_def.name
+            "const {} = {}('{}', {{\n",
+            struct_def.name,
+            koffi_constructor(struct_def),
+            struct_def.name
         ));
 
-        for field in &struct_def.fields {
-            let koffi_type = field.field_type.as_koffi();
-            output.push_str(&format!("  {}: '{}',\n", field.name, koffi_type));
+        for member in self.koffi_members(struct_def, types)? {
+            match member.comment {
+                Some(comment) => output.push_str(&format!(
+                    "  {}: {}, // {}\n",
+                    member.name, member.koffi, comment
+                )),
+                None => output.push_str(&format!("  {}: {},\n", member.name, member.koffi)),
+            }
         }
 
         output.push_str("});\n\n");
-        output
+        Ok(output)
+    }
+
+    /// The members koffi sees for a struct or union.
+    ///
+    /// koffi has no bitfields, so runs of them are stored in integer (or
+    /// byte array) members placed at the offsets the C compiler uses, with
+    /// explicit padding where koffi's own alignment would fall short.
+    fn koffi_members(&self, def: &CStruct, types: &TypeTable<'_>) -> Result<Vec<KoffiMember>> {
+        self.check_koffi_layout(def, types)?;
+
+        let member = |index: usize, field_type: &CType| -> Result<KoffiMember> {
+            let field = &def.fields[index];
+            Ok(KoffiMember {
+                name: if field.name.is_empty() {
+                    format!("_anon{}", index)
+                } else {
+                    field.name.clone()
+                },
+                koffi: self.koffi_type(field_type, types)?,
+                ts: self.ts_type(field_type, types)?,
+                comment: None,
+            })
+        };
+
+        if !def.has_bitfields() {
+            return (0..def.fields.len())
+                .map(|i| member(i, &def.fields[i].field_type))
+                .collect();
+        }
+
+        let engine = LayoutEngine::new(types, self.abi);
+        let layout = engine.layout(def)?;
+        let units = bitfield_units(&layout);
+        let packed = def.pack == Some(1);
+
+        let mut members = Vec::new();
+        let mut offset = 0u64;
+        let mut align = 1u64;
+        let mut pads = 0;
+
+        // Place a member at `at`, padding up to it if needed
+        let mut place =
+            |members: &mut Vec<KoffiMember>, m: KoffiMember, at: u64, size: u64, a: u64| {
+                let a = if packed { 1 } else { a };
+                if def.kind == AggregateKind::Struct {
+                    if offset.next_multiple_of(a) < at {
+                        members.push(KoffiMember {
+                            name: format!("_pad{}", pads),
+                            koffi: format!("koffi.array('uint8', {})", at - offset),
+                            ts: "number[]".to_string(),
+                            comment: None,
+                        });
+                        pads += 1;
+                        offset = at;
+                    }
+                    if offset.next_multiple_of(a) != at {
+                        bail!(
+                            "koffi cannot place `{}.{}` at offset {}",
+                            def.name,
+                            m.name,
+                            at
+                        );
+                    }
+                    offset = at + size;
+                } else {
+                    offset = offset.max(size);
+                }
+                align = align.max(a);
+                members.push(m);
+                Ok(())
+            };
+
+        for (i, field) in layout.fields.iter().enumerate() {
+            if let Some((n, unit)) = units.iter().enumerate().find(|(_, u)| u.first == i) {
+                let bits: Vec<String> = (unit.first..unit.end)
+                    .filter(|&j| !def.fields[j].name.is_empty())
+                    .map(|j| {
+                        let f = &layout.fields[j];
+                        let start = f.start_bit() - unit.offset * 8;
+                        format!(
+                            "{}: bits {}..{}",
+                            f.name,
+                            start,
+                            f.end_bit() - unit.offset * 8
+                        )
+                    })
+                    .collect();
+                let (koffi, ts, unit_align) = if unit.is_integer() {
+                    let ts = if unit.size == 8 { "bigint" } else { "number" };
+                    (format!("'uint{}'", unit.size * 8), ts, unit.size)
+                } else {
+                    (
+                        format!("koffi.array('uint8', {})", unit.size),
+                        "number[]",
+                        1,
+                    )
+                };
+                let m = KoffiMember {
+                    name: format!("_bitfield{}", n),
+                    koffi,
+                    ts: ts.to_string(),
+                    comment: Some(bits.join(", ")),
+                };
+                place(&mut members, m, unit.offset, unit.size, unit_align)?;
+            } else if field.bit_width.is_none() {
+                let m = member(i, &def.fields[i].field_type)?;
+                place(&mut members, m, field.offset, field.size, field.align)?;
+            }
+        }
+
+        // Tail padding, then check koffi ends up with the same size and alignment
+        if offset.next_multiple_of(align) < layout.size {
+            members.push(KoffiMember {
+                name: format!("_pad{}", pads),
+                koffi: format!("koffi.array('uint8', {})", layout.size - offset),
+                ts: "number[]".to_string(),
+                comment: None,
+            });
+            offset = layout.size;
+        }
+        if offset.next_multiple_of(align) != layout.size || align != layout.align {
+            bail!(
+                "koffi cannot reproduce the layout of `{}`: C gives it size {} and alignment {}, \
+                 but its bitfield storage only allows size {} and alignment {}",
+                def.name,
+                layout.size,
+                layout.align,
+                offset.next_multiple_of(align),
+                align
+            );
+        }
+
+        Ok(members)
+    }
+
+    /// Refuse packing and alignment koffi can't express.
+    ///
+    /// koffi can pack a struct to 1 byte but has no `#pragma pack(n)` or
+    /// `aligned(n)`; those are fine only when they don't change the layout.
+    fn check_koffi_layout(&self, def: &CStruct, types: &TypeTable<'_>) -> Result<()> {
+        let engine = LayoutEngine::new(types, self.abi);
+        let expressible_pack = def.pack == Some(1) && def.kind == AggregateKind::Struct;
+
+        if def.pack.is_some() && !expressible_pack {
+            let unpacked = CStruct {
+                pack: None,
+                ..def.clone()
+            };
+            if engine.layout(def)? != engine.layout(&unpacked)? {
+                bail!(
+                    "koffi cannot express `#pragma pack({})` on `{}`; only packing structs to 1 byte is supported",
+                    def.pack.unwrap_or_default(),
+                    def.name
+                );
+            }
+        }
+
+        if def.align.is_some() {
+            let natural = CStruct {
+                align: None,
+                ..def.clone()
+            };
+            if engine.layout(def)? != engine.layout(&natural)? {
+                bail!(
+                    "koffi cannot express the {}-byte alignment of `{}`",
+                    def.align.unwrap_or_default(),
+                    def.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// koffi type expression for a field type.
+    fn koffi_type(&self, ctype: &CType, types: &TypeTable<'_>) -> Result<String> {
+        Ok(match ctype {
+            CType::Array(inner, len) => {
+                format!("koffi.array({}, {})", self.koffi_type(inner, types)?, len)
+            }
+            CType::Aggregate(def) => {
+                let members: Vec<String> = self
+                    .koffi_members(def, types)?
+                    .into_iter()
+                    .map(|m| format!("{}: {}", m.name, m.koffi))
+                    .collect();
+                format!("{}({{ {} }})", koffi_constructor(def), members.join(", "))
+            }
+            other => format!("'{}'", self.local_type(other).as_koffi()),
+        })
+    }
+
+    /// TypeScript type for a field type.
+    fn ts_type(&self, ctype: &CType, types: &TypeTable<'_>) -> Result<String> {
+        Ok(match ctype {
+            CType::Aggregate(def) => {
+                let members: Vec<String> = self
+                    .koffi_members(def, types)?
+                    .into_iter()
+                    .map(|m| format!("{}: {}", m.name, m.ts))
+                    .collect();
+                format!("{{ {} }}", members.join("; "))
+            }
+            other => self.local_type(other).as_typescript(),
+        })
     }
 
     /// Generate ffi-napi struct.
-    fn generate_ffi_napi_struct(&self, struct_def: &CStruct) -> String {
+    fn generate_ffi_napi_struct(&self, struct_def: &CStruct) -> Result<String> {
+        let unsupported = if struct_def.is_union() {
+            Some("unions")
+        } else if struct_def.has_bitfields() {
+            Some("bitfields")
+        } else if struct_def.pack.is_some() || struct_def.align.is_some() {
+            Some("packing or alignment attributes")
+        } else if struct_def
+            .fields
+            .iter()
+            .any(|f| matches!(f.field_type, CType::Aggregate(_)))
+        {
+            Some("nested anonymous structs or unions")
+        } else {
+            None
+        };
+        if let Some(what) = unsupported {
+            bail!(
+                "ffi-napi bindings do not support {} (in `{}`); use the koffi bundler instead",
+                what,
+                struct_def.name
+            );
+        }
+
         let mut output = String::new();
 
         if let Some(ref doc) = struct_def.doc {
@@ -270,16 +541,20 @@ const lib = ffi.Library(libPath, {{
         ));
 
         for field in &struct_def.fields {
-            let ffi_type = self.ctype_to_ffi_napi(&field.field_type);
+            let ffi_type = self.ctype_to_ffi_napi(&self.local_type(&field.field_type));
             output.push_str(&format!("  {}: {},\n", field.name, ffi_type));
         }
 
         output.push_str("});\n\n");
-        output
+        Ok(output)
     }
 
     /// Generate TypeScript interface for struct.
-    fn generate_struct_interface(&self, struct_def: &CStruct) -> String {
+    fn generate_struct_interface(
+        &self,
+        struct_def: &CStruct,
+        types: &TypeTable<'_>,
+    ) -> Result<String> {
         let mut output = String::new();
 
         if let Some(ref doc) = struct_def.doc {
@@ -288,13 +563,19 @@ const lib = ffi.Library(libPath, {{
 
         output.push_str(&format!("export interface I{} {{\n", struct_def.name));
 
-        for field in &struct_def.fields {
-            let ts_type = field.field_type.as_typescript();
-            output.push_str(&format!("  {}: {};\n", field.name, ts_type));
+        if matches!(self.bundler, FfiBundler::Koffi) {
+            for member in self.koffi_members(struct_def, types)? {
+                output.push_str(&format!("  {}: {};\n", member.name, member.ts));
+            }
+        } else {
+            for field in &struct_def.fields {
+                let ts_type = self.local_type(&field.field_type).as_typescript();
+                output.push_str(&format!("  {}: {};\n", field.name, ts_type));
+            }
         }
 
         output.push_str("}\n\n");
-        output
+        Ok(output)
     }
 
     /// Generate function section.
@@ -334,25 +615,25 @@ const lib = ffi.Library(libPath, {{
                     output.push_str(&format!(
                         " * @param {} - {}\n",
                         param.name,
-                        param.param_type.as_typescript()
+                        self.local_type(&param.param_type).as_typescript()
                     ));
                 }
             }
             if !func.return_type.is_void() {
                 output.push_str(&format!(
                     " * @returns {}\n",
-                    func.return_type.as_typescript()
+                    self.local_type(&func.return_type).as_typescript()
                 ));
             }
             output.push_str(" */\n");
         }
 
         // Generate koffi prototype
-        let return_type = func.return_type.as_koffi();
+        let return_type = self.local_type(&func.return_type).as_koffi();
         let params: Vec<String> = func
             .params
             .iter()
-            .map(|p| format!("'{}'", p.param_type.as_koffi()))
+            .map(|p| format!("'{}'", self.local_type(&p.param_type).as_koffi()))
             .collect();
 
         let proto = if func.calling_convention != CallingConvention::Cdecl {
@@ -377,16 +658,20 @@ const lib = ffi.Library(libPath, {{
                 } else {
                     p.name.clone()
                 };
-                format!("{}: {}", name, p.param_type.as_typescript())
+                format!(
+                    "{}: {}",
+                    name,
+                    self.local_type(&p.param_type).as_typescript()
+                )
             })
             .collect();
 
-        let ts_return = func.return_type.as_typescript();
+        let ts_return = self.local_type(&func.return_type).as_typescript();
 
         output.push_str(&format!(
             "export const {} = lib.func('{}', {}) as ({}) => {};\n",
             func.name,
-            func.name,
+            self.symbol_name(func),
             proto,
             ts_params.join(", "),
             ts_return
@@ -397,7 +682,7 @@ const lib = ffi.Library(libPath, {{
             output.push_str(&format!(
                 "\nexport const {}Async = lib.func('{}', {}).async as ({}) => Promise<{}>;\n",
                 func.name,
-                func.name,
+                self.symbol_name(func),
                 proto,
                 ts_params.join(", "),
                 ts_return
@@ -413,16 +698,16 @@ const lib = ffi.Library(libPath, {{
         let mut output = String::new();
 
         for func in functions {
-            let return_type = self.ctype_to_ffi_napi(&func.return_type);
+            let return_type = self.ctype_to_ffi_napi(&self.local_type(&func.return_type));
             let params: Vec<String> = func
                 .params
                 .iter()
-                .map(|p| self.ctype_to_ffi_napi(&p.param_type))
+                .map(|p| self.ctype_to_ffi_napi(&self.local_type(&p.param_type)))
                 .collect();
 
             output.push_str(&format!(
                 "  '{}': [{}, [{}]],\n",
-                func.name,
+                self.symbol_name(func),
                 return_type,
                 params.join(", ")
             ));
@@ -443,16 +728,20 @@ const lib = ffi.Library(libPath, {{
                     } else {
                         p.name.clone()
                     };
-                    format!("{}: {}", name, p.param_type.as_typescript())
+                    format!(
+                        "{}: {}",
+                        name,
+                        self.local_type(&p.param_type).as_typescript()
+                    )
                 })
                 .collect();
 
-            let ts_return = func.return_type.as_typescript();
+            let ts_return = self.local_type(&func.return_type).as_typescript();
 
             output.push_str(&format!(
                 "export const {} = lib.{} as ({}) => {};\n",
                 func.name,
-                func.name,
+                self.symbol_name(func),
                 ts_params.join(", "),
                 ts_return
             ));
@@ -461,6 +750,37 @@ const lib = ffi.Library(libPath, {{
         output
     }
 
+    /// Rename type references to the names their declarations were
+    /// generated under, with the stripped prefix removed.
+    fn local_type(&self, ctype: &CType) -> CType {
+        let Some(ref prefix) = self.strip_prefix else {
+            return ctype.clone();
+        };
+        let strip = |name: &str| {
+            name.strip_prefix(prefix.as_str())
+                .unwrap_or(name)
+                .to_string()
+        };
+        match ctype {
+            CType::Pointer(inner) => CType::Pointer(Box::new(self.local_type(inner))),
+            CType::ConstPointer(inner) => CType::ConstPointer(Box::new(self.local_type(inner))),
+            CType::Array(inner, size) => CType::Array(Box::new(self.local_type(inner)), *size),
+            CType::Struct(name) => CType::Struct(strip(name)),
+            CType::Union(name) => CType::Union(strip(name)),
+            CType::Enum(name) => CType::Enum(strip(name)),
+            CType::TypeDef(name) => CType::TypeDef(strip(name)),
+            other => other.clone(),
+        }
+    }
+
+    /// The library symbol for a function, with any stripped prefix restored.
+    fn symbol_name(&self, func: &CFunction) -> String {
+        match self.strip_prefix {
+            Some(ref prefix) => format!("{}{}", prefix, func.name),
+            None => func.name.clone(),
+        }
+    }
+
     /// Convert CType to ffi-napi type.
     fn ctype_to_ffi_napi(&self, ctype: &CType) -> String {
         match ctype {
@@ -503,10 +823,30 @@ const lib = ffi.Library(libPath, {{
     }
 }
 
+/// The koffi function that defines an aggregate.
+fn koffi_constructor(def: &CStruct) -> &'static str {
+    match (def.kind, def.pack) {
+        (AggregateKind::Union, _) => "koffi.union",
+        (AggregateKind::Struct, Some(1)) => "koffi.pack",
+        (AggregateKind::Struct, _) => "koffi.struct",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::builder::bindings::types::{CEnumVariant, CField, CParam};
+    use crate::test_support::golden::{assert_golden, ffi_project_header, golden_abi};
+
+    #[test]
+    fn test_golden_typescript() {
+        let output = TypeScriptGenerator::new("mylib")
+            .with_strip_prefix(Some("mylib_".to_string()))
+            .with_abi(golden_abi())
+            .generate(&ffi_project_header())
+            .unwrap();
+        assert_golden(&output, "mylib.ts");
+    }
 
     #[test]
     fn test_generate_enum() {
@@ -535,29 +875,92 @@ mod tests {
     #[test]
     fn test_generate_struct() {
         let gen = TypeScriptGenerator::new("test");
-        let struct_def = CStruct {
-            name: "Point".to_string(),
-            fields: vec![
-                CField {
-                    name: "x".to_string(),
-                    field_type: CType::Int32,
-                    bit_width: None,
-                },
-                CField {
-                    name: "y".to_string(),
-                    field_type: CType::Int32,
-                    bit_width: None,
-                },
-            ],
-            packed: false,
-            doc: None,
-        };
+        let struct_def = CStruct::new("Point")
+            .with_field(CField::new("x", CType::Int32))
+            .with_field(CField::new("y", CType::Int32));
 
-        let output = gen.generate_struct(&struct_def);
+        let header = ParsedHeader::default();
+        let types = TypeTable::new(&header, None);
+        let output = gen.generate_struct(&struct_def, &types).unwrap();
         assert!(output.contains("koffi.struct('Point'"));
         assert!(output.contains("x: 'int32'"));
     }
 
+    #[test]
+    fn test_generate_union_and_anonymous_members() {
+        let gen = TypeScriptGenerator::new("test");
+        let value = CStruct::union("Value")
+            .with_field(CField::new("i", CType::Int32))
+            .with_field(CField::new("f", CType::Float));
+        let inner = CStruct::union("")
+            .with_field(CField::new("id", CType::UInt32))
+            .with_field(CField::new("name", CType::Array(Box::new(CType::Char), 8)));
+        let tagged = CStruct::new("Tagged")
+            .with_field(CField::new("tag", CType::Int32))
+            .with_field(CField::new("", CType::Aggregate(Box::new(inner))));
+
+        let header = ParsedHeader::default();
+        let types = TypeTable::new(&header, None);
+        let output = gen.generate_struct(&value, &types).unwrap();
+        assert!(output.contains("const Value = koffi.union('Value', {"));
+
+        let output = gen.generate_struct(&tagged, &types).unwrap();
+        assert!(
+            output.contains("_anon1: koffi.union({ id: 'uint32', name: koffi.array('char', 8) }),")
+        );
+    }
+
+    #[test]
+    fn test_generate_bitfields() {
+        let gen = TypeScriptGenerator::new("test").with_abi(TargetAbi::for_target(
+            &crate::core::abi::TargetTriple::parse("x86_64-unknown-linux-gnu").unwrap(),
+        ));
+        let flags = CStruct::new("Flags")
+            .with_field(CField::bitfield("ready", CType::UInt32, 1))
+            .with_field(CField::bitfield("mode", CType::UInt32, 3))
+            .with_field(CField::new("count", CType::UInt32));
+
+        let header = ParsedHeader::default();
+        let types = TypeTable::new(&header, None);
+        let output = gen.generate_struct(&flags, &types).unwrap();
+        assert!(output.contains("_bitfield0: 'uint8', // ready: bits 0..1, mode: bits 1..4"));
+        assert!(output.contains("count: 'uint32'"));
+
+        // C aligns this to 4 bytes, but the only storage koffi gets is a byte
+        let mixed = CStruct::new("Mixed")
+            .with_field(CField::new("a", CType::Char))
+            .with_field(CField::bitfield("b", CType::Int32, 3));
+        let err = gen.generate_struct(&mixed, &types).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("koffi cannot reproduce the layout of `Mixed`"));
+    }
+
+    #[test]
+    fn test_refuse_unsupported_packing() {
+        let gen = TypeScriptGenerator::new("test");
+        let header = ParsedHeader::default();
+        let types = TypeTable::new(&header, None);
+
+        let packed = CStruct::new("Packed")
+            .with_field(CField::new("a", CType::Char))
+            .with_field(CField::new("b", CType::Int32))
+            .with_pack(1);
+        let output = gen.generate_struct(&packed, &types).unwrap();
+        assert!(output.contains("koffi.pack('Packed'"));
+
+        let pack2 = CStruct {
+            pack: Some(2),
+            ..packed.clone()
+        };
+        let err = gen.generate_struct(&pack2, &types).unwrap_err();
+        assert!(err.to_string().contains("#pragma pack(2)"));
+
+        let napi = TypeScriptGenerator::new("test").with_bundler(FfiBundler::FfiNapi);
+        let err = napi.generate_struct(&packed, &types).unwrap_err();
+        assert!(err.to_string().contains("use the koffi bundler"));
+    }
+
     #[test]
     fn test_generate_function() {
         let gen = TypeScriptGenerator::new("test");
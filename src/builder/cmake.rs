@@ -192,7 +192,11 @@ impl<'a> CMakeBuilder<'a> {
                                 .unwrap_or("unknown")
                                 .to_string();
 
-                            artifacts.push(Artifact { path, target: name });
+                            artifacts.push(Artifact {
+                                path,
+                                target: name,
+                                lib_dirs: Vec::new(),
+                            });
                         }
                     }
                 }
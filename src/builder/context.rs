@@ -6,10 +6,12 @@ use std::sync::Arc;
 
 use anyhow::Result;
 
+use crate::builder::shim::{SanitizerKind, ToolchainValidator};
 use crate::builder::toolchain::{detect_toolchain, CxxOptions, Toolchain, ToolchainPlatform};
-use crate::core::abi::{CompilerIdentity, TargetTriple};
+use crate::core::abi::{AbiIdentity, CompilerIdentity, TargetTriple};
 use crate::core::manifest::Profile;
 use crate::core::surface::TargetPlatform;
+use crate::core::target::TargetKind;
 use crate::core::Workspace;
 use crate::resolver::CppConstraints;
 use crate::util::config::VcpkgConfig;
@@ -51,6 +53,12 @@ pub struct BuildContext {
 
     /// Vcpkg integration, if configured
     pub vcpkg: Option<VcpkgIntegration>,
+
+    /// Instrument compiled code for source-based coverage
+    pub coverage: bool,
+
+    /// Instrument for libFuzzer and link executables against its driver
+    pub fuzz: bool,
 }
 
 impl fmt::Debug for BuildContext {
@@ -67,6 +75,8 @@ impl fmt::Debug for BuildContext {
             .field("workspace_root", &self.workspace_root)
             .field("cpp_constraints", &self.cpp_constraints)
             .field("vcpkg", &self.vcpkg)
+            .field("coverage", &self.coverage)
+            .field("fuzz", &self.fuzz)
             .finish()
     }
 }
@@ -93,6 +103,11 @@ impl BuildContext {
             ws.manifest().debug_profile()
         };
 
+        // Sanitizers apply to the whole graph, so reject ones the toolchain
+        // cannot build before anything is compiled
+        let sanitizers = profile.sanitizer_kinds()?;
+        ToolchainValidator::new(toolchain.as_ref()).validate_sanitizers(&sanitizers)?;
+
         let output_dir = ws.output_dir();
         let deps_dir = ws.deps_dir();
 
@@ -108,6 +123,8 @@ impl BuildContext {
             workspace_root: ws.root().to_path_buf(),
             cpp_constraints: None,
             vcpkg: None,
+            coverage: false,
+            fuzz: false,
         })
     }
 
@@ -124,6 +141,24 @@ impl BuildContext {
         self
     }
 
+    /// Enable or disable coverage instrumentation.
+    pub fn with_coverage(mut self, coverage: bool) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Enable or disable libFuzzer instrumentation.
+    ///
+    /// Fuzzing runs under AddressSanitizer, so `address` is added to the
+    /// profile's sanitizers.
+    pub fn with_fuzz(mut self, fuzz: bool) -> Self {
+        self.fuzz = fuzz;
+        if fuzz {
+            self.profile = self.profile.clone().with_sanitizer(SanitizerKind::Address);
+        }
+        self
+    }
+
     /// Get vcpkg integration details, if configured.
     pub fn vcpkg(&self) -> Option<&VcpkgIntegration> {
         self.vcpkg.as_ref()
@@ -168,11 +203,16 @@ impl BuildContext {
             }
         }
 
-        // Sanitizers
-        for sanitizer in &self.profile.sanitizers {
-            flags.push(format!("-fsanitize={}", sanitizer));
+        // Sanitizers, with frame pointers for usable stack traces
+        let sanitizer_flags = self.sanitizer_flags();
+        if !sanitizer_flags.is_empty() {
+            flags.extend(sanitizer_flags);
+            flags.push(self.frame_pointer_flag().to_string());
         }
 
+        // Coverage instrumentation
+        flags.extend(self.coverage_flags());
+
         // Custom flags
         flags.extend(self.profile.cflags.iter().cloned());
 
@@ -189,9 +229,10 @@ impl BuildContext {
         }
 
         // Sanitizers (need to be passed to linker too)
-        for sanitizer in &self.profile.sanitizers {
-            flags.push(format!("-fsanitize={}", sanitizer));
-        }
+        flags.extend(self.sanitizer_flags());
+
+        // Coverage runtime
+        flags.extend(self.coverage_flags());
 
         // Custom flags
         flags.extend(self.profile.ldflags.iter().cloned());
@@ -199,6 +240,66 @@ impl BuildContext {
         flags
     }
 
+    /// Get the `-fsanitize=` flags of the profile's sanitizers.
+    ///
+    /// Every package in the graph, including CMake and Meson recipes, is
+    /// built with these so the sanitizer runtime sees all of the code. Fuzz
+    /// builds add libFuzzer's coverage instrumentation without its driver.
+    pub fn sanitizer_flags(&self) -> Vec<String> {
+        let mut flags: Vec<String> = self
+            .profile
+            .sanitizers
+            .iter()
+            .map(|sanitizer| format!("-fsanitize={}", sanitizer))
+            .collect();
+        if self.fuzz {
+            flags.push("-fsanitize=fuzzer-no-link".to_string());
+        }
+        flags
+    }
+
+    /// Get the flag that keeps frame pointers with the active toolchain.
+    pub fn frame_pointer_flag(&self) -> &'static str {
+        match self.toolchain.platform() {
+            ToolchainPlatform::Msvc => "/Oy-",
+            _ => "-fno-omit-frame-pointer",
+        }
+    }
+
+    /// Get the flags that link an executable against libFuzzer's `main`.
+    pub fn fuzzer_link_flags(&self) -> Vec<String> {
+        if self.fuzz {
+            vec!["-fsanitize=fuzzer".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Get the coverage instrumentation flags, if coverage is enabled.
+    ///
+    /// GCC writes `.gcda` files next to each object for `gcov`, Clang
+    /// writes `.profraw` files for `llvm-profdata` and `llvm-cov`. MSVC has
+    /// no compiler-based coverage, so this is empty there.
+    pub fn coverage_flags(&self) -> Vec<String> {
+        if !self.coverage {
+            return Vec::new();
+        }
+        match self.toolchain.platform() {
+            ToolchainPlatform::Gcc => vec!["--coverage".to_string()],
+            ToolchainPlatform::Clang | ToolchainPlatform::AppleClang => vec![
+                "-fprofile-instr-generate".to_string(),
+                "-fcoverage-mapping".to_string(),
+            ],
+            ToolchainPlatform::Msvc => Vec::new(),
+        }
+    }
+
+    /// Get the ABI identity of a target built in this context.
+    pub fn abi_identity(&self, kind: TargetKind) -> AbiIdentity {
+        AbiIdentity::new(self.target.clone(), self.compiler.clone(), kind)
+            .with_sanitizers(&self.profile.sanitizers)
+    }
+
     /// Check if this is a release build.
     pub fn is_release(&self) -> bool {
         self.profile_name == "release"
@@ -265,7 +366,7 @@ fn get_compiler_version(cc: &Path, family: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::builder::toolchain::GccToolchain;
+    use crate::builder::toolchain::{GccToolchain, MsvcToolchain};
 
     #[test]
     fn test_profile_cflags() {
@@ -295,11 +396,55 @@ mod tests {
             workspace_root: PathBuf::from("."),
             cpp_constraints: None,
             vcpkg: None,
+            coverage: false,
+            fuzz: false,
         };
 
         let flags = ctx.profile_cflags();
         assert!(flags.contains(&"-O2".to_string()));
         assert!(flags.contains(&"-g".to_string()));
         assert!(flags.contains(&"-fsanitize=address".to_string()));
+        assert!(flags.contains(&"-fno-omit-frame-pointer".to_string()));
+
+        let ldflags = ctx.profile_ldflags();
+        assert_eq!(ldflags, vec!["-fsanitize=address".to_string()]);
+
+        let abi = ctx.abi_identity(TargetKind::StaticLib);
+        assert_eq!(abi.sanitizers, vec!["address".to_string()]);
+
+        let ctx = ctx.with_coverage(true);
+        assert!(ctx.profile_cflags().contains(&"--coverage".to_string()));
+        assert_eq!(
+            ctx.profile_ldflags(),
+            vec!["-fsanitize=address".to_string(), "--coverage".to_string()]
+        );
+        assert!(ctx.fuzzer_link_flags().is_empty());
+
+        let mut ctx = ctx.with_coverage(false);
+        ctx.profile.sanitizers = vec!["undefined".to_string()];
+        let ctx = ctx.with_fuzz(true);
+        assert_eq!(
+            ctx.sanitizer_flags(),
+            vec![
+                "-fsanitize=undefined".to_string(),
+                "-fsanitize=address".to_string(),
+                "-fsanitize=fuzzer-no-link".to_string()
+            ]
+        );
+        assert_eq!(
+            ctx.fuzzer_link_flags(),
+            vec!["-fsanitize=fuzzer".to_string()]
+        );
+
+        // cl spells "keep frame pointers" differently
+        let mut ctx = ctx.with_fuzz(false);
+        ctx.toolchain = Arc::new(MsvcToolchain::new(
+            PathBuf::from("cl.exe"),
+            PathBuf::from("lib.exe"),
+            PathBuf::from("link.exe"),
+        ));
+        let flags = ctx.profile_cflags();
+        assert!(flags.contains(&"/Oy-".to_string()));
+        assert!(!flags.contains(&"-fno-omit-frame-pointer".to_string()));
     }
 }
@@ -0,0 +1,657 @@
+//! Compiler diagnostics.
+//!
+//! Parses the warnings and errors GCC, Clang and MSVC print into structured
+//! diagnostics with a source location, for `--message-format json` events.
+//! Notes, source excerpts and `In file included from` context lines are not
+//! diagnostics of their own and are skipped.
+//!
+//! Text output is parsed by default, since it works with every compiler
+//! version. If the compile flags select GCC's `-fdiagnostics-format=json` or
+//! SARIF (`-fdiagnostics-format=sarif-stderr` on GCC, `sarif` on Clang), the
+//! structured output is parsed instead.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::builder::events::BuildEvent;
+use crate::builder::toolchain::ToolchainPlatform;
+
+/// Warnings shown per dependency before the rest are only counted.
+pub const DEPENDENCY_WARNING_CAP: usize = 10;
+
+/// Severity of a compiler diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A warning or error reported by the compiler.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompilerDiagnostic {
+    /// Warning or error
+    pub severity: Severity,
+
+    /// Message text, without the location and severity prefix
+    pub message: String,
+
+    /// Source file (absent for driver and command-line diagnostics)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+
+    /// Line number (if reported)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+
+    /// Column number (if reported)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+}
+
+impl CompilerDiagnostic {
+    /// Check if this diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Formats the diagnostic the way GCC prints it: `file:line:col: error: message`.
+impl fmt::Display for CompilerDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+            if let Some(line) = self.line {
+                write!(f, "{}:", line)?;
+                if let Some(column) = self.column {
+                    write!(f, "{}:", column)?;
+                }
+            }
+            write!(f, " ")?;
+        }
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
+
+/// Parse the diagnostics in a compiler's output.
+pub fn parse_diagnostics(platform: ToolchainPlatform, output: &str) -> Vec<CompilerDiagnostic> {
+    if let Some(diagnostics) = parse_structured(output) {
+        return diagnostics;
+    }
+
+    let parse_line = match platform {
+        ToolchainPlatform::Msvc => parse_msvc_line,
+        ToolchainPlatform::Gcc | ToolchainPlatform::Clang | ToolchainPlatform::AppleClang => {
+            parse_gcc_line
+        }
+    };
+    output.lines().filter_map(parse_line).collect()
+}
+
+/// Combine a compiler's stdout and stderr into the text to parse.
+///
+/// cl prints its diagnostics on stdout, after echoing the source file name.
+pub fn compiler_output(
+    platform: ToolchainPlatform,
+    source: &Path,
+    stdout: &str,
+    stderr: &str,
+) -> String {
+    let mut stdout = stdout;
+    if platform == ToolchainPlatform::Msvc {
+        let file_name = source.file_name().map(|n| n.to_string_lossy());
+        let (first, rest) = stdout.split_once('\n').unwrap_or((stdout, ""));
+        if file_name.as_deref() == Some(first.trim()) {
+            stdout = rest;
+        }
+    }
+    format!("{}{}", stdout, stderr)
+}
+
+/// Parse GCC's JSON diagnostics or a SARIF log, if the output is either.
+///
+/// Clang prints a warning about SARIF being experimental before the log, so
+/// the structured part starts at the first line that opens a JSON value.
+fn parse_structured(output: &str) -> Option<Vec<CompilerDiagnostic>> {
+    let start = output
+        .lines()
+        .find(|line| line.starts_with('[') || line.starts_with('{'))
+        .map(|line| line.as_ptr() as usize - output.as_ptr() as usize)?;
+    let value: Value = serde_json::from_str(output[start..].trim()).ok()?;
+
+    match &value {
+        Value::Array(diagnostics) => Some(diagnostics.iter().filter_map(gcc_json).collect()),
+        Value::Object(log) => {
+            let runs = log.get("runs")?.as_array()?;
+            Some(
+                runs.iter()
+                    .filter_map(|run| run.get("results")?.as_array())
+                    .flatten()
+                    .filter_map(sarif_result)
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Convert one entry of `-fdiagnostics-format=json` output.
+///
+/// Notes are nested under `children` and are not diagnostics of their own.
+fn gcc_json(value: &Value) -> Option<CompilerDiagnostic> {
+    let severity = match value.get("kind")?.as_str()? {
+        kind if kind.contains("error") => Severity::Error,
+        "warning" => Severity::Warning,
+        _ => return None,
+    };
+    let message = value.get("message")?.as_str()?;
+    let caret = value
+        .get("locations")
+        .and_then(|l| l.get(0))
+        .and_then(|l| l.get("caret"));
+    let field = |name: &str| caret.and_then(|c| c.get(name));
+    Some(diagnostic(
+        severity,
+        message,
+        field("file").and_then(Value::as_str).map(PathBuf::from),
+        field("line").and_then(json_u32),
+        field("column").and_then(json_u32),
+    ))
+}
+
+/// Convert one SARIF `result`.
+fn sarif_result(value: &Value) -> Option<CompilerDiagnostic> {
+    // `warning` is the level SARIF implies when none is given
+    let severity = match value.get("level").and_then(Value::as_str) {
+        Some("error") => Severity::Error,
+        Some("warning") | None => Severity::Warning,
+        Some(_) => return None,
+    };
+    let message = value.get("message")?.get("text")?.as_str()?;
+    let location = value
+        .get("locations")
+        .and_then(|l| l.get(0))
+        .and_then(|l| l.get("physicalLocation"));
+    let file = location
+        .and_then(|l| l.get("artifactLocation")?.get("uri")?.as_str())
+        .map(|uri| PathBuf::from(uri.strip_prefix("file://").unwrap_or(uri)));
+    let region = location.and_then(|l| l.get("region"));
+    Some(diagnostic(
+        severity,
+        message,
+        file,
+        region.and_then(|r| r.get("startLine")).and_then(json_u32),
+        region.and_then(|r| r.get("startColumn")).and_then(json_u32),
+    ))
+}
+
+fn json_u32(value: &Value) -> Option<u32> {
+    value.as_u64().and_then(|n| u32::try_from(n).ok())
+}
+
+/// Parse `file:line:col: warning: message`, as printed by GCC and Clang.
+///
+/// Driver diagnostics such as `gcc: error: ...` or a bare `error: ...` have
+/// no source location.
+fn parse_gcc_line(line: &str) -> Option<CompilerDiagnostic> {
+    const MARKERS: [(&str, Severity); 3] = [
+        ("fatal error: ", Severity::Error),
+        ("error: ", Severity::Error),
+        ("warning: ", Severity::Warning),
+    ];
+
+    for (marker, severity) in MARKERS {
+        if let Some(message) = line.strip_prefix(marker) {
+            return Some(diagnostic(severity, message, None, None, None));
+        }
+        let Some((location, message)) = line.split_once(&format!(": {}", marker)) else {
+            continue;
+        };
+
+        // `file:line:col`, `file:line` or a tool name like `cc1`
+        let number = |s: &str| s.parse::<u32>().ok();
+        let (file, line, column) = match location.rsplit_once(':') {
+            Some((rest, last)) if number(last).is_some() => match rest.rsplit_once(':') {
+                Some((file, line)) if number(line).is_some() => {
+                    (Some(file), number(line), number(last))
+                }
+                _ => (Some(rest), number(last), None),
+            },
+            _ => (None, None, None),
+        };
+        let file = file.filter(|f| !f.is_empty()).map(PathBuf::from);
+        return Some(diagnostic(severity, message, file, line, column));
+    }
+    None
+}
+
+/// Parse `file(line,col): warning C4101: message`, as printed by MSVC.
+///
+/// Command-line diagnostics (`cl : Command line warning D9002: ...`) have no
+/// source location. The code is kept at the start of the message.
+fn parse_msvc_line(line: &str) -> Option<CompilerDiagnostic> {
+    const MARKERS: [(&str, Severity); 5] = [
+        ("Command line error ", Severity::Error),
+        ("Command line warning ", Severity::Warning),
+        (": fatal error ", Severity::Error),
+        (": error ", Severity::Error),
+        (": warning ", Severity::Warning),
+    ];
+
+    for (marker, severity) in MARKERS {
+        let Some((location, message)) = line.split_once(marker) else {
+            continue;
+        };
+
+        let location = location.trim();
+        let parsed = location
+            .strip_suffix(')')
+            .and_then(|l| l.rsplit_once('('))
+            .and_then(|(file, position)| {
+                let mut numbers = position.split(',').map(|n| n.trim().parse::<u32>());
+                let line = numbers.next()?.ok()?;
+                let column = numbers.next().and_then(|c| c.ok());
+                Some((file, line, column))
+            });
+        return Some(match parsed {
+            Some((file, line, column)) => diagnostic(
+                severity,
+                message,
+                Some(PathBuf::from(file)),
+                Some(line),
+                column,
+            ),
+            None => diagnostic(severity, message, None, None, None),
+        });
+    }
+    None
+}
+
+fn diagnostic(
+    severity: Severity,
+    message: &str,
+    file: Option<PathBuf>,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> CompilerDiagnostic {
+    CompilerDiagnostic {
+        severity,
+        message: message.trim().to_string(),
+        file,
+        line,
+        column,
+    }
+}
+
+/// How compile diagnostics are reported during a build.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// Compiler output on stderr
+    #[default]
+    Human,
+    /// `compiler-warning` and `compiler-error` events on stdout
+    Json,
+}
+
+/// Reports the diagnostics of each compiled unit as it finishes.
+///
+/// In human mode, errors and the warnings of workspace members are always
+/// shown. Dependencies show at most [`DEPENDENCY_WARNING_CAP`] warnings each;
+/// the rest are counted and summarized by [`DiagnosticReporter::finish`].
+/// JSON mode streams every diagnostic, since consumers can filter them.
+#[derive(Debug)]
+pub struct DiagnosticReporter {
+    platform: ToolchainPlatform,
+    format: DiagnosticFormat,
+    members: HashSet<String>,
+    dependency_cap: usize,
+    /// Warnings shown and hidden, by package ID
+    warnings: Mutex<BTreeMap<String, (usize, usize)>>,
+}
+
+impl DiagnosticReporter {
+    /// Create a reporter for output of the given compiler.
+    pub fn new(platform: ToolchainPlatform, format: DiagnosticFormat) -> Self {
+        DiagnosticReporter {
+            platform,
+            format,
+            members: HashSet::new(),
+            dependency_cap: DEPENDENCY_WARNING_CAP,
+            warnings: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Set the packages whose warnings are never capped.
+    pub fn with_members(mut self, members: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.members = members.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set how many warnings each dependency may show.
+    pub fn with_dependency_cap(mut self, cap: usize) -> Self {
+        self.dependency_cap = cap;
+        self
+    }
+
+    /// Report the output of compiling one unit of `target` in `package`.
+    ///
+    /// Returns the parsed diagnostics.
+    pub fn report(
+        &self,
+        package: &str,
+        package_id: &str,
+        target: &str,
+        output: &str,
+        success: bool,
+    ) -> Vec<CompilerDiagnostic> {
+        let diagnostics = parse_diagnostics(self.platform, output);
+        match self.format {
+            DiagnosticFormat::Json => {
+                for diag in &diagnostics {
+                    println!(
+                        "{}",
+                        BuildEvent::diagnostic(package_id, target, diag).to_json()
+                    );
+                }
+                // A crashed compiler may print nothing we recognize
+                if !success && !diagnostics.iter().any(|d| d.is_error()) {
+                    let event = BuildEvent::CompilerError {
+                        package_id: package_id.to_string(),
+                        target: Some(target.to_string()),
+                        message: output.trim().to_string(),
+                        file: None,
+                        line: None,
+                        column: None,
+                    };
+                    println!("{}", event.to_json());
+                }
+            }
+            DiagnosticFormat::Human => {
+                if self.show(package, package_id, success, &diagnostics) {
+                    eprint!("{}", self.render(output, &diagnostics));
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Summarize the warnings that were not shown.
+    pub fn finish(&self) {
+        let warnings = self.warnings.lock().unwrap();
+        for (package_id, (_, hidden)) in warnings.iter() {
+            if *hidden > 0 {
+                eprintln!(
+                    "warning: `{}` generated {} more warning(s) that were not shown",
+                    package_id, hidden
+                );
+            }
+        }
+    }
+
+    /// Decide whether a unit's output is shown, counting hidden warnings.
+    fn show(
+        &self,
+        package: &str,
+        package_id: &str,
+        success: bool,
+        diagnostics: &[CompilerDiagnostic],
+    ) -> bool {
+        if !success || self.members.contains(package) || diagnostics.iter().any(|d| d.is_error()) {
+            return true;
+        }
+
+        let count = diagnostics.len();
+        let mut warnings = self.warnings.lock().unwrap();
+        let (shown, hidden) = warnings.entry(package_id.to_string()).or_default();
+        if *shown + count > self.dependency_cap {
+            *hidden += count;
+            return false;
+        }
+        *shown += count;
+        true
+    }
+
+    /// Render output for a terminal; structured output is not readable as-is.
+    fn render(&self, output: &str, diagnostics: &[CompilerDiagnostic]) -> String {
+        if parse_structured(output).is_none() {
+            let mut text = output.to_string();
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            return text;
+        }
+        diagnostics.iter().map(|d| format!("{}\n", d)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn located(
+        severity: Severity,
+        message: &str,
+        file: &str,
+        line: u32,
+        column: Option<u32>,
+    ) -> CompilerDiagnostic {
+        diagnostic(
+            severity,
+            message,
+            Some(PathBuf::from(file)),
+            Some(line),
+            column,
+        )
+    }
+
+    #[test]
+    fn test_parse_gcc_diagnostics() {
+        let output = "\
+In file included from src/main.c:1:
+include/parse.h:4:1: warning: function declaration isn't a prototype [-Wstrict-prototypes]
+    4 | int parse();
+      | ^~~
+src/main.c: In function 'main':
+src/main.c:3:36: error: 'y' undeclared (first use in this function)
+src/main.c:3:36: note: each undeclared identifier is reported only once
+C:\\src\\win.c:7: warning: no column here
+src/missing.c:1:10: fatal error: nope.h: No such file or directory
+cc1: warning: command-line option '-Wfoo' is valid for C++ but not for C
+error: unknown argument: '-fbogus'
+";
+        assert_eq!(
+            parse_diagnostics(ToolchainPlatform::Gcc, output),
+            vec![
+                located(
+                    Severity::Warning,
+                    "function declaration isn't a prototype [-Wstrict-prototypes]",
+                    "include/parse.h",
+                    4,
+                    Some(1)
+                ),
+                located(
+                    Severity::Error,
+                    "'y' undeclared (first use in this function)",
+                    "src/main.c",
+                    3,
+                    Some(36)
+                ),
+                located(
+                    Severity::Warning,
+                    "no column here",
+                    "C:\\src\\win.c",
+                    7,
+                    None
+                ),
+                located(
+                    Severity::Error,
+                    "nope.h: No such file or directory",
+                    "src/missing.c",
+                    1,
+                    Some(10)
+                ),
+                diagnostic(
+                    Severity::Warning,
+                    "command-line option '-Wfoo' is valid for C++ but not for C",
+                    None,
+                    None,
+                    None
+                ),
+                diagnostic(
+                    Severity::Error,
+                    "unknown argument: '-fbogus'",
+                    None,
+                    None,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_msvc_diagnostics() {
+        let output = "\
+main.c
+C:\\src\\main.c(3,36): error C2065: 'y': undeclared identifier
+C:\\src\\main.c(3): warning C4101: 'unused': unreferenced local variable
+C:\\src\\util.h(10,1): fatal error C1083: Cannot open include file: 'x.h'
+cl : Command line warning D9002: ignoring unknown option '/bogus'
+";
+        assert_eq!(
+            parse_diagnostics(ToolchainPlatform::Msvc, output),
+            vec![
+                located(
+                    Severity::Error,
+                    "C2065: 'y': undeclared identifier",
+                    "C:\\src\\main.c",
+                    3,
+                    Some(36)
+                ),
+                located(
+                    Severity::Warning,
+                    "C4101: 'unused': unreferenced local variable",
+                    "C:\\src\\main.c",
+                    3,
+                    None
+                ),
+                located(
+                    Severity::Error,
+                    "C1083: Cannot open include file: 'x.h'",
+                    "C:\\src\\util.h",
+                    10,
+                    Some(1)
+                ),
+                diagnostic(
+                    Severity::Warning,
+                    "D9002: ignoring unknown option '/bogus'",
+                    None,
+                    None,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_gcc_json_diagnostics() {
+        let output = r#"[{"kind": "warning", "message": "unused variable 'n'", "option": "-Wunused-variable", "children": [], "locations": [{"caret": {"file": "src/parse.c", "line": 7, "display-column": 9, "byte-column": 9, "column": 9}}]}, {"kind": "note", "message": "declared here", "children": [], "locations": []}, {"kind": "error", "message": "'y' undeclared", "children": [{"kind": "note", "message": "each undeclared identifier is reported only once", "locations": []}], "locations": [{"caret": {"file": "src/main.c", "line": 3, "column": 36}}]}]"#;
+        assert_eq!(
+            parse_diagnostics(ToolchainPlatform::Gcc, output),
+            vec![
+                located(
+                    Severity::Warning,
+                    "unused variable 'n'",
+                    "src/parse.c",
+                    7,
+                    Some(9)
+                ),
+                located(Severity::Error, "'y' undeclared", "src/main.c", 3, Some(36)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sarif_diagnostics() {
+        let output = r#"clang: warning: diagnostic formatting in SARIF mode is currently unstable [-Wsarif-format-unstable]
+{"version": "2.1.0", "runs": [{"tool": {"driver": {"name": "clang"}}, "results": [{"level": "error", "message": {"text": "use of undeclared identifier 'y'"}, "locations": [{"physicalLocation": {"artifactLocation": {"uri": "file:///work/src/main.c"}, "region": {"startLine": 3, "startColumn": 36}}}], "ruleId": "3079"}, {"level": "note", "message": {"text": "declared here"}}, {"message": {"text": "no newline at end of file"}}]}]}
+"#;
+        assert_eq!(
+            parse_diagnostics(ToolchainPlatform::Clang, output),
+            vec![
+                located(
+                    Severity::Error,
+                    "use of undeclared identifier 'y'",
+                    "/work/src/main.c",
+                    3,
+                    Some(36)
+                ),
+                diagnostic(
+                    Severity::Warning,
+                    "no newline at end of file",
+                    None,
+                    None,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display_diagnostic() {
+        let diag = located(Severity::Warning, "unused", "src/a.c", 7, Some(9));
+        assert_eq!(diag.to_string(), "src/a.c:7:9: warning: unused");
+
+        let diag = diagnostic(Severity::Error, "unknown argument", None, None, None);
+        assert_eq!(diag.to_string(), "error: unknown argument");
+    }
+
+    #[test]
+    fn test_compiler_output() {
+        let source = Path::new("C:/src/main.c");
+        let stdout = "main.c\nC:\\src\\main.c(3): warning C4101: 'n': unreferenced\n";
+        assert_eq!(
+            compiler_output(ToolchainPlatform::Msvc, source, stdout, ""),
+            "C:\\src\\main.c(3): warning C4101: 'n': unreferenced\n"
+        );
+        assert_eq!(
+            compiler_output(ToolchainPlatform::Gcc, source, "", "a.c:1:1: warning: x\n"),
+            "a.c:1:1: warning: x\n"
+        );
+    }
+
+    #[test]
+    fn test_dependency_warning_cap() {
+        let reporter = DiagnosticReporter::new(ToolchainPlatform::Gcc, DiagnosticFormat::Human)
+            .with_members(["app"])
+            .with_dependency_cap(3);
+        let warnings =
+            |n: usize| vec![located(Severity::Warning, "unused", "src/a.c", 1, Some(1)); n];
+        let errors = vec![located(Severity::Error, "bad", "src/a.c", 1, Some(1))];
+
+        // Members are never capped
+        assert!(reporter.show("app", "app v1.0.0", true, &warnings(5)));
+
+        // A dependency shows units while they fit under the cap
+        assert!(reporter.show("zlib", "zlib v1.3.0", true, &warnings(2)));
+        assert!(!reporter.show("zlib", "zlib v1.3.0", true, &warnings(2)));
+        assert!(reporter.show("zlib", "zlib v1.3.0", true, &warnings(1)));
+        assert!(!reporter.show("zlib", "zlib v1.3.0", true, &warnings(1)));
+
+        // Errors and failures are always shown
+        assert!(reporter.show("zlib", "zlib v1.3.0", false, &errors));
+        assert!(reporter.show("zlib", "zlib v1.3.0", false, &[]));
+
+        let counts = reporter.warnings.lock().unwrap();
+        assert_eq!(counts.get("zlib v1.3.0"), Some(&(3, 3)));
+        assert!(!counts.contains_key("app v1.0.0"));
+    }
+}
@@ -10,6 +10,8 @@
 //! - `compiler-warning`: A compiler warning was emitted
 //! - `compiler-error`: A compiler error was emitted
 //! - `build-progress`: Progress update during build
+//! - `test-case`: A test case finished (`harbour test`)
+//! - `test-finished`: A test run completed
 //!
 //! # Stability
 //!
@@ -20,6 +22,9 @@ use std::path::PathBuf;
 
 use serde::Serialize;
 
+use crate::builder::diagnostics::{CompilerDiagnostic, Severity};
+use crate::builder::shim::{TestCase, TestResult};
+
 /// A build event emitted during the build process.
 ///
 /// Each event is serialized as a single JSON object per line.
@@ -57,6 +62,9 @@ pub enum BuildEvent {
     CompilerWarning {
         /// Package that produced the warning
         package_id: String,
+        /// Target being compiled (if known)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target: Option<String>,
         /// Warning message text
         message: String,
         /// Source file (if available)
@@ -75,6 +83,9 @@ pub enum BuildEvent {
     CompilerError {
         /// Package that produced the error
         package_id: String,
+        /// Target being compiled (if known)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target: Option<String>,
         /// Error message text
         message: String,
         /// Source file (if available)
@@ -122,6 +133,39 @@ pub enum BuildEvent {
         #[serde(skip_serializing_if = "Option::is_none")]
         package_count: Option<u64>,
     },
+
+    /// A test case finished.
+    #[serde(rename = "test-case")]
+    TestCase {
+        /// Suite or test binary the case belongs to
+        #[serde(skip_serializing_if = "Option::is_none")]
+        suite: Option<String>,
+        /// Test case name
+        name: String,
+        /// Outcome ("passed", "failed", "skipped", "timed-out")
+        status: String,
+        /// Run time in milliseconds (if measured)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duration_ms: Option<u64>,
+        /// Captured output or failure message (for unsuccessful cases)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+    },
+
+    /// A test run completed.
+    #[serde(rename = "test-finished")]
+    TestFinished {
+        /// Whether every test passed
+        success: bool,
+        /// Number of passed test cases
+        passed: u64,
+        /// Number of failed test cases (including timeouts)
+        failed: u64,
+        /// Number of skipped test cases
+        skipped: u64,
+        /// Total run duration in milliseconds
+        duration_ms: u64,
+    },
 }
 
 impl BuildEvent {
@@ -152,6 +196,7 @@ impl BuildEvent {
     pub fn warning(package_id: impl Into<String>, message: impl Into<String>) -> Self {
         BuildEvent::CompilerWarning {
             package_id: package_id.into(),
+            target: None,
             message: message.into(),
             file: None,
             line: None,
@@ -163,6 +208,7 @@ impl BuildEvent {
     pub fn error(package_id: impl Into<String>, message: impl Into<String>) -> Self {
         BuildEvent::CompilerError {
             package_id: package_id.into(),
+            target: None,
             message: message.into(),
             file: None,
             line: None,
@@ -170,6 +216,36 @@ impl BuildEvent {
         }
     }
 
+    /// Create a compiler warning or error event from a parsed diagnostic.
+    pub fn diagnostic(
+        package_id: impl Into<String>,
+        target: impl Into<String>,
+        diag: &CompilerDiagnostic,
+    ) -> Self {
+        let package_id = package_id.into();
+        let target = Some(target.into());
+        let message = diag.message.clone();
+        let (file, line, column) = (diag.file.clone(), diag.line, diag.column);
+        match diag.severity {
+            Severity::Warning => BuildEvent::CompilerWarning {
+                package_id,
+                target,
+                message,
+                file,
+                line,
+                column,
+            },
+            Severity::Error => BuildEvent::CompilerError {
+                package_id,
+                target,
+                message,
+                file,
+                line,
+                column,
+            },
+        }
+    }
+
     /// Create a progress event.
     pub fn progress(current: u64, total: u64, unit: impl Into<String>) -> Self {
         BuildEvent::Progress {
@@ -189,6 +265,28 @@ impl BuildEvent {
         }
     }
 
+    /// Create a test case event.
+    pub fn test_case(case: &TestCase) -> Self {
+        BuildEvent::TestCase {
+            suite: case.suite.clone(),
+            name: case.name.clone(),
+            status: case.status.as_str().to_string(),
+            duration_ms: case.duration.map(|d| d.as_millis() as u64),
+            output: case.output.clone(),
+        }
+    }
+
+    /// Create a test finished event.
+    pub fn test_finished(result: &TestResult, duration_ms: u64) -> Self {
+        BuildEvent::TestFinished {
+            success: result.success(),
+            passed: result.passed as u64,
+            failed: result.failed as u64,
+            skipped: result.skipped as u64,
+            duration_ms,
+        }
+    }
+
     /// Serialize this event to a JSON string.
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
@@ -240,6 +338,7 @@ mod tests {
     fn test_error_with_location() {
         let event = BuildEvent::CompilerError {
             package_id: "myapp v1.0.0".to_string(),
+            target: None,
             message: "undefined reference to 'foo'".to_string(),
             file: Some(PathBuf::from("src/main.c")),
             line: Some(42),
@@ -250,4 +349,49 @@ mod tests {
         assert!(json.contains("\"line\":42"));
         assert!(json.contains("\"column\":10"));
     }
+
+    #[test]
+    fn test_diagnostic_event() {
+        use crate::builder::diagnostics::{parse_diagnostics, CompilerDiagnostic};
+        use crate::builder::toolchain::ToolchainPlatform;
+
+        let diags: Vec<CompilerDiagnostic> = parse_diagnostics(
+            ToolchainPlatform::Gcc,
+            "src/parse.c:7:5: warning: unused variable 'n' [-Wunused-variable]\n",
+        );
+        let json = BuildEvent::diagnostic("myapp v1.0.0", "parser", &diags[0]).to_json();
+        assert!(json.contains("\"reason\":\"compiler-warning\""));
+        assert!(json.contains("\"target\":\"parser\""));
+        assert!(json.contains("\"file\":\"src/parse.c\""));
+        assert!(json.contains("\"line\":7"));
+        assert!(json.contains("\"column\":5"));
+
+        let json = BuildEvent::warning("myapp v1.0.0", "deprecated").to_json();
+        assert!(!json.contains("\"target\""));
+    }
+
+    #[test]
+    fn test_test_events_serialization() {
+        use crate::builder::shim::TestStatus;
+        use std::time::Duration;
+
+        let case = TestCase {
+            name: "Parser.Empty".to_string(),
+            suite: Some("unit_test".to_string()),
+            status: TestStatus::Failed,
+            output: Some("expected 0, got 1".to_string()),
+            duration: Some(Duration::from_millis(12)),
+        };
+        let json = BuildEvent::test_case(&case).to_json();
+        assert!(json.contains("\"reason\":\"test-case\""));
+        assert!(json.contains("\"suite\":\"unit_test\""));
+        assert!(json.contains("\"status\":\"failed\""));
+        assert!(json.contains("\"duration_ms\":12"));
+
+        let result = TestResult::from_cases(vec![case]);
+        let json = BuildEvent::test_finished(&result, 40).to_json();
+        assert!(json.contains("\"reason\":\"test-finished\""));
+        assert!(json.contains("\"success\":false"));
+        assert!(json.contains("\"failed\":1"));
+    }
 }
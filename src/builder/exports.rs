@@ -0,0 +1,377 @@
+//! Symbol export control for shared libraries.
+//!
+//! Targets built with `visibility = "hidden"` only export what their sources
+//! mark with the generated `<TARGET>_API` macro. Shared libraries can further
+//! restrict their exports with an export map, which is rendered in the format
+//! the linker understands:
+//! - ELF: a version script (`-Wl,--version-script`)
+//! - Mach-O: an exported symbols list (`-Wl,-exported_symbols_list`)
+//! - PE: a module-definition file (`/DEF:` or passed to the MinGW driver)
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::builder::toolchain::ToolchainPlatform;
+use crate::core::manifest::ExportsConfig;
+use crate::core::target::Target;
+use crate::util::fs::write_string;
+
+/// Generated header defining a target's export macro.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportHeader {
+    /// Path of the generated header
+    pub path: PathBuf,
+
+    /// Macro prefix derived from the target name (e.g. `MYLIB`)
+    pub prefix: String,
+}
+
+impl ExportHeader {
+    /// Create the export header for a target, placed in `include_dir`.
+    pub fn new(include_dir: &Path, target: &str) -> Self {
+        ExportHeader {
+            path: include_dir.join(format!("{}_export.h", target)),
+            prefix: macro_prefix(target),
+        }
+    }
+
+    /// The export macro sources put on public declarations.
+    pub fn api_macro(&self) -> String {
+        format!("{}_API", self.prefix)
+    }
+
+    /// The define set while compiling the library itself.
+    pub fn building_define(&self) -> String {
+        format!("{}_BUILDING", self.prefix)
+    }
+
+    /// Render the header contents.
+    pub fn render(&self) -> String {
+        let p = &self.prefix;
+        format!(
+            "/* Generated by harbour. Do not edit. */\n\
+             #ifndef {p}_EXPORT_H\n\
+             #define {p}_EXPORT_H\n\
+             \n\
+             #if defined({p}_STATIC)\n\
+             #  define {p}_API\n\
+             #elif defined(_WIN32) || defined(__CYGWIN__)\n\
+             #  if defined({p}_BUILDING)\n\
+             #    define {p}_API __declspec(dllexport)\n\
+             #  else\n\
+             #    define {p}_API __declspec(dllimport)\n\
+             #  endif\n\
+             #elif defined(__GNUC__) || defined(__clang__)\n\
+             #  define {p}_API __attribute__((visibility(\"default\")))\n\
+             #else\n\
+             #  define {p}_API\n\
+             #endif\n\
+             \n\
+             #endif /* {p}_EXPORT_H */\n"
+        )
+    }
+
+    /// Write the header, leaving it untouched if the contents are unchanged.
+    pub fn write(&self) -> Result<()> {
+        write_if_changed(&self.path, &self.render())
+    }
+}
+
+/// Export map file format, chosen by the linker in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// GNU ld / lld version script
+    VersionScript,
+    /// Apple ld exported symbols list
+    SymbolList,
+    /// Windows module-definition (.def) file
+    ModuleDefinition,
+}
+
+impl ExportFormat {
+    /// Select the format for a toolchain and target OS.
+    pub fn for_toolchain(platform: ToolchainPlatform, os: &str) -> Self {
+        match platform {
+            ToolchainPlatform::Msvc => ExportFormat::ModuleDefinition,
+            ToolchainPlatform::AppleClang => ExportFormat::SymbolList,
+            _ if os == "windows" => ExportFormat::ModuleDefinition,
+            _ if os == "macos" || os == "ios" => ExportFormat::SymbolList,
+            _ => ExportFormat::VersionScript,
+        }
+    }
+
+    /// File extension for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::VersionScript => "map",
+            ExportFormat::SymbolList => "exp",
+            ExportFormat::ModuleDefinition => "def",
+        }
+    }
+}
+
+/// An export map restricting the symbols a shared library exports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportMap {
+    /// Path the rendered map is written to
+    pub path: PathBuf,
+
+    /// Format understood by the linker
+    pub format: ExportFormat,
+
+    /// Library file name (used for the `LIBRARY` line of .def files)
+    pub library: String,
+
+    /// Exported symbols or glob patterns
+    pub symbols: Vec<String>,
+
+    /// Version node name (version scripts only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl ExportMap {
+    /// Create an export map for `library`, written to `dir`.
+    pub fn new(dir: &Path, library: &str, format: ExportFormat, config: ExportsConfig) -> Self {
+        let stem = library.split('.').next().unwrap_or(library);
+        ExportMap {
+            path: dir.join(format!("{}.{}", stem, format.extension())),
+            format,
+            library: library.to_string(),
+            symbols: config.symbols,
+            version: config.version,
+        }
+    }
+
+    /// Render the map in its format.
+    ///
+    /// Module-definition files have no wildcard support, so glob patterns are
+    /// left out of them.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        match self.format {
+            ExportFormat::VersionScript => {
+                match &self.version {
+                    Some(version) => out.push_str(&format!("{} {{\n", version)),
+                    None => out.push_str("{\n"),
+                }
+                out.push_str("  global:\n");
+                for symbol in &self.symbols {
+                    out.push_str(&format!("    {};\n", symbol));
+                }
+                out.push_str("  local:\n    *;\n};\n");
+            }
+            ExportFormat::SymbolList => {
+                for symbol in &self.symbols {
+                    out.push_str(&format!("_{}\n", symbol));
+                }
+            }
+            ExportFormat::ModuleDefinition => {
+                out.push_str(&format!("LIBRARY \"{}\"\nEXPORTS\n", self.library));
+                for symbol in self.symbols.iter().filter(|s| !is_pattern(s)) {
+                    out.push_str(&format!("    {}\n", symbol));
+                }
+            }
+        }
+        out
+    }
+
+    /// Write the map, leaving it untouched if the contents are unchanged.
+    pub fn write(&self) -> Result<()> {
+        if self.format == ExportFormat::ModuleDefinition {
+            for symbol in self.symbols.iter().filter(|s| is_pattern(s)) {
+                tracing::warn!(
+                    "export pattern `{}` ignored: .def files only accept exact symbol names",
+                    symbol
+                );
+            }
+        }
+        write_if_changed(&self.path, &self.render())
+    }
+}
+
+/// Resolve a target's export configuration.
+///
+/// Returns `None` when the target has no `[targets.X.exports]` table. An
+/// empty symbol list falls back to the target's FFI `include_functions`.
+pub fn resolve_exports(target: &Target) -> Result<Option<ExportsConfig>> {
+    let Some(exports) = &target.exports else {
+        return Ok(None);
+    };
+
+    let mut resolved = exports.clone();
+    if resolved.symbols.is_empty() {
+        if let Some(ffi) = &target.ffi {
+            resolved.symbols = ffi.include_functions.clone();
+        }
+    }
+
+    if resolved.symbols.is_empty() {
+        bail!(
+            "target `{}` has an empty export list\n\
+             hint: set `symbols` in [targets.{}.exports] or `include_functions` in [targets.{}.ffi]",
+            target.name,
+            target.name,
+            target.name
+        );
+    }
+
+    Ok(Some(resolved))
+}
+
+/// Derive the export macro prefix from a target name.
+pub fn macro_prefix(target: &str) -> String {
+    let mut prefix: String = target
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if prefix.starts_with(|c: char| c.is_ascii_digit()) {
+        prefix.insert(0, '_');
+    }
+    prefix
+}
+
+fn is_pattern(symbol: &str) -> bool {
+    symbol.contains(['*', '?', '['])
+}
+
+fn write_if_changed(path: &Path, contents: &str) -> Result<()> {
+    if std::fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    write_string(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(symbols: &[&str], version: Option<&str>) -> ExportsConfig {
+        ExportsConfig {
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            version: version.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_macro_prefix() {
+        assert_eq!(macro_prefix("mylib"), "MYLIB");
+        assert_eq!(macro_prefix("my-lib.core"), "MY_LIB_CORE");
+        assert_eq!(macro_prefix("3d"), "_3D");
+    }
+
+    #[test]
+    fn test_export_header() {
+        let header = ExportHeader::new(Path::new("/gen/include"), "my-lib");
+        assert_eq!(header.path, PathBuf::from("/gen/include/my-lib_export.h"));
+        assert_eq!(header.api_macro(), "MY_LIB_API");
+        assert_eq!(header.building_define(), "MY_LIB_BUILDING");
+
+        let text = header.render();
+        assert!(text.contains("#ifndef MY_LIB_EXPORT_H"));
+        assert!(text.contains("#if defined(MY_LIB_STATIC)"));
+        assert!(text.contains("#    define MY_LIB_API __declspec(dllexport)"));
+        assert!(text.contains("#  define MY_LIB_API __attribute__((visibility(\"default\")))"));
+    }
+
+    #[test]
+    fn test_export_format_for_toolchain() {
+        use ToolchainPlatform::*;
+        assert_eq!(
+            ExportFormat::for_toolchain(Gcc, "linux"),
+            ExportFormat::VersionScript
+        );
+        assert_eq!(
+            ExportFormat::for_toolchain(AppleClang, "macos"),
+            ExportFormat::SymbolList
+        );
+        assert_eq!(
+            ExportFormat::for_toolchain(Clang, "macos"),
+            ExportFormat::SymbolList
+        );
+        assert_eq!(
+            ExportFormat::for_toolchain(Gcc, "windows"),
+            ExportFormat::ModuleDefinition
+        );
+        assert_eq!(
+            ExportFormat::for_toolchain(Msvc, "windows"),
+            ExportFormat::ModuleDefinition
+        );
+    }
+
+    #[test]
+    fn test_version_script() {
+        let map = ExportMap::new(
+            Path::new("/obj"),
+            "libmylib.so",
+            ExportFormat::VersionScript,
+            config(&["mylib_init", "mylib_*"], Some("MYLIB_1.0")),
+        );
+        assert_eq!(map.path, PathBuf::from("/obj/libmylib.map"));
+        assert_eq!(
+            map.render(),
+            "MYLIB_1.0 {\n  global:\n    mylib_init;\n    mylib_*;\n  local:\n    *;\n};\n"
+        );
+
+        let map = ExportMap::new(
+            Path::new("/obj"),
+            "libmylib.so",
+            ExportFormat::VersionScript,
+            config(&["mylib_init"], None),
+        );
+        assert!(map.render().starts_with("{\n  global:\n"));
+    }
+
+    #[test]
+    fn test_symbol_list() {
+        let map = ExportMap::new(
+            Path::new("/obj"),
+            "libmylib.dylib",
+            ExportFormat::SymbolList,
+            config(&["mylib_init", "mylib_free"], Some("MYLIB_1.0")),
+        );
+        assert_eq!(map.path, PathBuf::from("/obj/libmylib.exp"));
+        assert_eq!(map.render(), "_mylib_init\n_mylib_free\n");
+    }
+
+    #[test]
+    fn test_module_definition_skips_patterns() {
+        let map = ExportMap::new(
+            Path::new("/obj"),
+            "mylib.dll",
+            ExportFormat::ModuleDefinition,
+            config(&["mylib_init", "mylib_*"], None),
+        );
+        assert_eq!(map.path, PathBuf::from("/obj/mylib.def"));
+        assert_eq!(
+            map.render(),
+            "LIBRARY \"mylib.dll\"\nEXPORTS\n    mylib_init\n"
+        );
+    }
+
+    #[test]
+    fn test_write_if_changed() {
+        let tmp = TempDir::new().unwrap();
+        let header = ExportHeader::new(&tmp.path().join("include"), "mylib");
+        header.write().unwrap();
+        let first = std::fs::metadata(&header.path).unwrap().modified().unwrap();
+
+        header.write().unwrap();
+        let second = std::fs::metadata(&header.path).unwrap().modified().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(
+            std::fs::read_to_string(&header.path).unwrap(),
+            header.render()
+        );
+    }
+}
@@ -5,13 +5,16 @@
 pub mod bindings;
 pub mod cmake;
 pub mod context;
+pub mod diagnostics;
 pub mod events;
 pub mod executor;
+pub mod exports;
 pub mod fingerprint;
 pub mod interop;
 pub mod native;
 pub mod plan;
 pub mod shim;
+pub mod soname;
 pub mod surface_resolver;
 pub mod toolchain;
 pub mod util;
@@ -2,10 +2,13 @@
 //!
 //! Compiles C/C++ source files and links them into executables or libraries.
 
+use std::path::Path;
+
 use anyhow::{bail, Result};
 use rayon::prelude::*;
 
 use crate::builder::context::BuildContext;
+use crate::builder::diagnostics::{compiler_output, DiagnosticFormat, DiagnosticReporter};
 use crate::builder::plan::{
     ArchiveStep, BuildPlan, BuildStep, CMakeStep, CompileStep, CustomStep, LinkStep, MesonStep,
 };
@@ -21,6 +24,8 @@ pub struct NativeBuilder<'a> {
     ctx: &'a BuildContext,
     /// C++ options for compilation (if any C++ is involved)
     cxx_opts: Option<CxxOptions>,
+    /// Reports compiler warnings and errors as units finish
+    reporter: DiagnosticReporter,
 }
 
 impl<'a> NativeBuilder<'a> {
@@ -29,6 +34,7 @@ impl<'a> NativeBuilder<'a> {
         NativeBuilder {
             ctx,
             cxx_opts: None,
+            reporter: DiagnosticReporter::new(ctx.toolchain().platform(), DiagnosticFormat::Human),
         }
     }
 
@@ -37,9 +43,16 @@ impl<'a> NativeBuilder<'a> {
         NativeBuilder {
             ctx,
             cxx_opts: Some(cxx_opts),
+            reporter: DiagnosticReporter::new(ctx.toolchain().platform(), DiagnosticFormat::Human),
         }
     }
 
+    /// Set how compiler diagnostics are reported.
+    pub fn with_diagnostics(mut self, reporter: DiagnosticReporter) -> Self {
+        self.reporter = reporter;
+        self
+    }
+
     /// Execute the build plan.
     ///
     /// Processes all steps in order:
@@ -54,6 +67,11 @@ impl<'a> NativeBuilder<'a> {
                 .ok(); // Ignore if already set
         }
 
+        // Export-macro headers must exist before anything includes them
+        for header in &plan.export_headers {
+            header.write()?;
+        }
+
         // Separate compile steps for parallel execution
         let compile_steps: Vec<_> = plan
             .steps
@@ -70,8 +88,9 @@ impl<'a> NativeBuilder<'a> {
 
             let compile_results: Vec<Result<()>> = compile_steps
                 .par_iter()
-                .map(|step| self.compile(step))
+                .map(|step| self.compile(step, &plan.package_id(&step.package)))
                 .collect();
+            self.reporter.finish();
 
             // Check for compile errors
             for result in compile_results {
@@ -139,12 +158,13 @@ impl<'a> NativeBuilder<'a> {
         Ok(Artifact {
             path: step.output.clone(),
             target: step.target.clone(),
+            lib_dirs: Vec::new(),
         })
     }
 
     /// Run a CMake build step.
     fn run_cmake(&self, step: &CMakeStep) -> Result<()> {
-        ensure_dir(&step.build_dir)?;
+        prepare_recipe_dir(&step.build_dir, &step.abi, &step.package)?;
 
         // Configure
         tracing::info!("Configuring CMake for {}", step.package);
@@ -184,7 +204,7 @@ impl<'a> NativeBuilder<'a> {
 
     /// Run a Meson build step.
     fn run_meson(&self, step: &MesonStep) -> Result<()> {
-        ensure_dir(&step.build_dir)?;
+        prepare_recipe_dir(&step.build_dir, &step.abi, &step.package)?;
 
         // Configure with meson setup
         tracing::info!("Configuring Meson for {}", step.package);
@@ -260,7 +280,7 @@ impl<'a> NativeBuilder<'a> {
     }
 
     /// Compile a single source file.
-    fn compile(&self, step: &CompileStep) -> Result<()> {
+    fn compile(&self, step: &CompileStep, package_id: &str) -> Result<()> {
         // Ensure output directory exists
         if let Some(parent) = step.output.parent() {
             ensure_dir(parent)?;
@@ -275,6 +295,7 @@ impl<'a> NativeBuilder<'a> {
             include_dirs: step.include_dirs.clone(),
             defines: parse_define_flags(&step.defines),
             cflags,
+            visibility: step.visibility,
         };
 
         // Generate compile command with language and C++ options
@@ -294,12 +315,23 @@ impl<'a> NativeBuilder<'a> {
 
         let output = cmd.exec()?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        // Warnings are reported even when the compile succeeds
+        let text = compiler_output(
+            self.ctx.toolchain().platform(),
+            &step.source,
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+        );
+        let success = output.status.success();
+        self.reporter
+            .report(&step.package, package_id, &step.target, &text, success);
+
+        if !success {
             bail!(
-                "compilation failed for {}\n{}",
+                "could not compile {} (`{}` target of `{}`)",
                 step.source.display(),
-                stderr
+                step.target,
+                package_id
             );
         }
 
@@ -333,17 +365,38 @@ impl<'a> NativeBuilder<'a> {
 
     /// Create a shared library.
     fn link_shared(&self, step: &LinkStep) -> Result<Artifact> {
+        if let Some(map) = &step.export_map {
+            map.write()?;
+        }
+
         let (libs, mut extra_ldflags) = split_link_flags(&step.libs);
         let mut ldflags = self.ctx.profile_ldflags();
         ldflags.extend(step.ldflags.iter().cloned());
         ldflags.append(&mut extra_ldflags);
 
+        // Versioned libraries are linked to their real name; the output
+        // becomes the end of the symlink chain
+        let link_name = step
+            .output
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let lib_path = match &step.version {
+            Some(version) => step.output.with_file_name(version.real_name(&link_name)),
+            None => step.output.clone(),
+        };
+
         let input = LinkInput {
             objects: step.objects.clone(),
-            output: step.output.clone(),
+            output: lib_path.clone(),
             lib_dirs: step.lib_dirs.clone(),
             libs,
             ldflags,
+            export_map: step.export_map.as_ref().map(|map| map.path.clone()),
+            version: step
+                .version
+                .as_ref()
+                .map(|version| version.link_version(&link_name)),
         };
 
         // Select C or C++ linker driver based on use_cxx_linker
@@ -361,7 +414,7 @@ impl<'a> NativeBuilder<'a> {
 
         tracing::debug!(
             "Creating shared library {} (driver: {})",
-            step.output.display(),
+            lib_path.display(),
             driver.as_str()
         );
 
@@ -369,12 +422,17 @@ impl<'a> NativeBuilder<'a> {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("linking failed for {}\n{}", step.output.display(), stderr);
+            bail!("linking failed for {}\n{}", lib_path.display(), stderr);
+        }
+
+        if let Some(version) = &step.version {
+            version.create_symlinks(&step.output)?;
         }
 
         Ok(Artifact {
             path: step.output.clone(),
             target: step.target.clone(),
+            lib_dirs: step.lib_dirs.clone(),
         })
     }
 
@@ -382,6 +440,7 @@ impl<'a> NativeBuilder<'a> {
     fn link_executable(&self, step: &LinkStep) -> Result<Artifact> {
         let (libs, mut extra_ldflags) = split_link_flags(&step.libs);
         let mut ldflags = self.ctx.profile_ldflags();
+        ldflags.extend(self.ctx.fuzzer_link_flags());
         ldflags.extend(step.ldflags.iter().cloned());
         ldflags.append(&mut extra_ldflags);
 
@@ -391,6 +450,8 @@ impl<'a> NativeBuilder<'a> {
             lib_dirs: step.lib_dirs.clone(),
             libs,
             ldflags,
+            export_map: None,
+            version: None,
         };
 
         // Select C or C++ linker driver based on use_cxx_linker
@@ -422,6 +483,7 @@ impl<'a> NativeBuilder<'a> {
         Ok(Artifact {
             path: step.output.clone(),
             target: step.target.clone(),
+            lib_dirs: step.lib_dirs.clone(),
         })
     }
 
@@ -477,9 +539,33 @@ fn split_link_flags(flags: &[String]) -> (Vec<String>, Vec<String>) {
     (libs, extra)
 }
 
+/// Stamp file recording the ABI a recipe build directory was configured for.
+const ABI_STAMP: &str = ".harbour-abi";
+
+/// Make sure a CMake or Meson build directory matches the current ABI.
+///
+/// Recipes keep their build directory between runs, so objects built with a
+/// different ABI (e.g. before a sanitizer was enabled) would be linked into
+/// an incompatible graph. Such a directory is cleared and configured anew.
+fn prepare_recipe_dir(build_dir: &Path, abi: &str, package: &str) -> Result<()> {
+    let stamp = build_dir.join(ABI_STAMP);
+    if let Ok(cached) = std::fs::read_to_string(&stamp) {
+        if cached.trim() != abi {
+            tracing::debug!("rebuilding {}: ABI changed", package);
+            std::fs::remove_dir_all(build_dir)?;
+        }
+    }
+    ensure_dir(build_dir)?;
+    std::fs::write(&stamp, abi)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::abi::{AbiIdentity, CompilerIdentity, TargetTriple};
+    use crate::core::manifest::SymbolVisibility;
+    use crate::core::target::TargetKind;
     use std::path::PathBuf;
 
     // Tests require a C compiler, so they're marked as ignore by default
@@ -598,6 +684,7 @@ mod tests {
             defines: vec!["-DDEBUG".to_string(), "-DVERSION=1".to_string()],
             cflags: vec!["-Wall".to_string(), "-Werror".to_string()],
             lang: Language::C,
+            visibility: SymbolVisibility::Default,
         };
 
         assert_eq!(step.source, PathBuf::from("/src/main.c"));
@@ -622,6 +709,8 @@ mod tests {
             libs: vec!["-lm".to_string()],
             ldflags: vec![],
             use_cxx_linker: false,
+            export_map: None,
+            version: None,
         };
 
         assert_eq!(step.kind, "exe");
@@ -641,6 +730,8 @@ mod tests {
             libs: vec![],
             ldflags: vec!["-shared".to_string()],
             use_cxx_linker: true,
+            export_map: None,
+            version: None,
         };
 
         assert_eq!(step.kind, "sharedlib");
@@ -659,6 +750,8 @@ mod tests {
             libs: vec![],
             ldflags: vec![],
             use_cxx_linker: false,
+            export_map: None,
+            version: None,
         };
 
         assert_eq!(step.kind, "staticlib");
@@ -691,6 +784,7 @@ mod tests {
             targets: vec!["all".to_string()],
             package: "cmake_pkg".to_string(),
             target: "cmake_target".to_string(),
+            abi: String::new(),
         };
 
         assert_eq!(step.source_dir, PathBuf::from("/project"));
@@ -723,4 +817,31 @@ mod tests {
         assert_eq!(step.env.len(), 2);
         assert_eq!(step.outputs.len(), 1);
     }
+
+    #[test]
+    fn test_sanitizer_change_clears_recipe_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let build_dir = tmp.path().join("cmake-build");
+        let plain = AbiIdentity::new(
+            TargetTriple::host(),
+            CompilerIdentity::new("gcc", "13.0"),
+            TargetKind::StaticLib,
+        );
+        let asan = plain.clone().with_sanitizers(&["address".to_string()]);
+
+        prepare_recipe_dir(&build_dir, &plain.fingerprint(), "dep").unwrap();
+        std::fs::write(build_dir.join("CMakeCache.txt"), "").unwrap();
+
+        // Same ABI keeps the configured tree
+        prepare_recipe_dir(&build_dir, &plain.fingerprint(), "dep").unwrap();
+        assert!(build_dir.join("CMakeCache.txt").exists());
+
+        // Enabling a sanitizer rebuilds the dependency from scratch
+        prepare_recipe_dir(&build_dir, &asan.fingerprint(), "dep").unwrap();
+        assert!(!build_dir.join("CMakeCache.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(build_dir.join(ABI_STAMP)).unwrap(),
+            asan.fingerprint()
+        );
+    }
 }
@@ -4,15 +4,18 @@
 //! a workspace. Steps can be native compilation, CMake invocation, or custom
 //! commands.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::builder::context::BuildContext;
+use crate::builder::exports::{resolve_exports, ExportFormat, ExportHeader, ExportMap};
+use crate::builder::soname::SharedLibVersion;
 use crate::builder::surface_resolver::SurfaceResolver;
 use crate::builder::util::parse_define_flags;
+use crate::core::manifest::SymbolVisibility;
 use crate::core::target::{BuildRecipe, Language, TargetKind};
 use crate::resolver::Resolve;
 use crate::sources::SourceCache;
@@ -32,6 +35,10 @@ pub struct BuildPlan {
 
     /// Build order (package IDs in topological order)
     pub build_order: Vec<String>,
+
+    /// Export-macro headers to generate before compiling
+    #[serde(default)]
+    pub export_headers: Vec<ExportHeader>,
 }
 
 /// A build step in the plan.
@@ -80,6 +87,8 @@ pub struct CMakeStep {
     pub package: String,
     /// Target name
     pub target: String,
+    /// ABI fingerprint the build directory is configured for
+    pub abi: String,
 }
 
 /// A Meson build step.
@@ -97,6 +106,8 @@ pub struct MesonStep {
     pub package: String,
     /// Target name
     pub target: String,
+    /// ABI fingerprint the build directory is configured for
+    pub abi: String,
 }
 
 /// A custom command step.
@@ -145,6 +156,10 @@ pub struct CompileStep {
     /// Source language (C or C++)
     #[serde(default)]
     pub lang: Language,
+
+    /// Default symbol visibility
+    #[serde(default)]
+    pub visibility: SymbolVisibility,
 }
 
 /// A single link step.
@@ -177,6 +192,14 @@ pub struct LinkStep {
     /// Whether to use C++ linker driver (g++/clang++ instead of gcc/clang)
     #[serde(default)]
     pub use_cxx_linker: bool,
+
+    /// Export map restricting the symbols of a shared library
+    #[serde(default)]
+    pub export_map: Option<ExportMap>,
+
+    /// Version of a shared library (linked to its versioned name)
+    #[serde(default)]
+    pub version: Option<SharedLibVersion>,
 }
 
 use crate::core::PackageId;
@@ -224,6 +247,11 @@ impl BuildPlan {
         let mut steps = Vec::new();
         let mut compile_steps = Vec::new();
         let mut link_steps = Vec::new();
+        let mut export_headers = Vec::new();
+
+        // Include directories holding generated export headers, keyed by the
+        // package that owns them
+        let mut export_include_dirs: HashMap<PackageId, PathBuf> = HashMap::new();
 
         // Create surface resolver
         let mut surface_resolver = SurfaceResolver::new(resolve, &ctx.platform);
@@ -237,11 +265,20 @@ impl BuildPlan {
             .collect();
 
         // Determine root package IDs
-        let root_pkg_set: std::collections::HashSet<PackageId> =
-            root_packages.iter().copied().collect();
+        let root_pkg_set: HashSet<PackageId> = root_packages.iter().copied().collect();
+
+        // Dev-dependencies are only built when a test target that can see
+        // them is part of this build
+        let skipped_dev_deps =
+            dev_only_packages(resolve, &surface_resolver, &root_pkg_set, target_filter);
 
         // Process each package in build order
         for pkg_id in resolve.topological_order() {
+            if skipped_dev_deps.contains(&pkg_id) {
+                tracing::debug!("skipping dev-dependency {} (no test targets built)", pkg_id);
+                continue;
+            }
+
             let package = surface_resolver
                 .get_package(pkg_id)
                 .ok_or_else(|| anyhow::anyhow!("package not loaded: {}", pkg_id))?;
@@ -249,6 +286,7 @@ impl BuildPlan {
             // Determine which targets to build
             // For root packages, apply filter if specified
             // For dependencies, build all targets
+            // Fuzz targets only link in fuzz builds of their own package
             let is_root = root_pkg_set.contains(&pkg_id);
             let targets_to_build: Vec<_> = if is_root && target_filter.is_some() {
                 let filter = target_filter.unwrap();
@@ -258,27 +296,45 @@ impl BuildPlan {
                     .filter(|t| filter.iter().any(|f| f == t.name.as_str()))
                     .collect()
             } else {
-                package.targets().iter().collect()
+                package
+                    .targets()
+                    .iter()
+                    .filter(|t| t.fuzz.is_none() || (is_root && ctx.fuzz))
+                    .collect()
             };
 
-            for target in targets_to_build {
-                // Determine output directory
-                // Root packages go to output_dir/<pkg>/ (for multi-package workspaces)
-                // Dependencies go to deps_dir/<pkg>-<version>/
-                let target_output_dir = if is_root {
-                    if root_packages.len() > 1 {
-                        // Multi-root workspace: each package gets its own directory
-                        ctx.output_dir.join(pkg_id.name().as_str())
-                    } else {
-                        // Single root: output directly to output_dir
-                        ctx.output_dir.clone()
-                    }
+            // Determine output directory
+            // Root packages go to output_dir/<pkg>/ (for multi-package workspaces)
+            // Dependencies go to deps_dir/<pkg>-<version>/
+            let target_output_dir = if is_root {
+                if root_packages.len() > 1 {
+                    // Multi-root workspace: each package gets its own directory
+                    ctx.output_dir.join(pkg_id.name().as_str())
                 } else {
-                    // Dependencies go to deps_dir
-                    ctx.deps_dir
-                        .join(format!("{}-{}", pkg_id.name(), pkg_id.version()))
-                };
+                    // Single root: output directly to output_dir
+                    ctx.output_dir.clone()
+                }
+            } else {
+                // Dependencies go to deps_dir
+                ctx.deps_dir
+                    .join(format!("{}-{}", pkg_id.name(), pkg_id.version()))
+            };
 
+            // Native shared libraries get a generated <TARGET>_API header
+            let gen_include_dir = target_output_dir.join("gen").join("include");
+            for target in &targets_to_build {
+                if target.kind == TargetKind::SharedLib
+                    && matches!(target.recipe, None | Some(BuildRecipe::Native))
+                {
+                    export_headers.push(ExportHeader::new(&gen_include_dir, target.name.as_str()));
+                    export_include_dirs.insert(pkg_id, gen_include_dir.clone());
+                }
+            }
+
+            let visible_export_dirs =
+                visible_export_include_dirs(resolve, pkg_id, &export_include_dirs);
+
+            for target in targets_to_build {
                 let obj_dir = target_output_dir.join("obj").join(target.name.as_str());
                 let lib_dir = target_output_dir.join("lib");
                 let bin_dir = target_output_dir.join("bin");
@@ -296,13 +352,20 @@ impl BuildPlan {
                             .unwrap_or_else(|| package.root().to_path_buf());
                         let build_dir = target_output_dir.join("cmake-build");
 
+                        // Sanitizer flags go first; a recipe's own `-D` for the
+                        // same cache entry replaces them
+                        let mut cmake_args =
+                            cmake_sanitizer_args(&ctx.sanitizer_flags(), ctx.frame_pointer_flag());
+                        cmake_args.extend(args.iter().cloned());
+
                         steps.push(BuildStep::CMake(CMakeStep {
                             source_dir: src_dir,
                             build_dir,
-                            args: args.clone(),
+                            args: cmake_args,
                             targets: cmake_targets.clone(),
                             package: pkg_id.name().to_string(),
                             target: target.name.to_string(),
+                            abi: ctx.abi_identity(target.kind).fingerprint(),
                         }));
                     }
                     Some(BuildRecipe::Custom {
@@ -342,13 +405,17 @@ impl BuildPlan {
                             .unwrap_or_else(|| package.root().to_path_buf());
                         let build_dir = target_output_dir.join("meson-build");
 
+                        let mut meson_options = meson_sanitizer_options(&ctx.profile.sanitizers);
+                        meson_options.extend(options.iter().cloned());
+
                         steps.push(BuildStep::Meson(MesonStep {
                             source_dir: src_dir,
                             build_dir,
-                            options: options.clone(),
+                            options: meson_options,
                             targets: meson_targets.clone(),
                             package: pkg_id.name().to_string(),
                             target: target.name.to_string(),
+                            abi: ctx.abi_identity(target.kind).fingerprint(),
                         }));
                     }
                     Some(BuildRecipe::Native) | None => {
@@ -368,6 +435,10 @@ impl BuildPlan {
                         let mut link_surface =
                             surface_resolver.resolve_link_surface(pkg_id, target, &ctx.deps_dir)?;
 
+                        compile_surface
+                            .include_dirs
+                            .extend(visible_export_dirs.iter().cloned());
+
                         if let Some(vcpkg) = ctx.vcpkg.as_ref() {
                             compile_surface
                                 .include_dirs
@@ -405,6 +476,16 @@ impl BuildPlan {
                         // Determine if target needs C++ compilation
                         let target_lang = target.lang;
 
+                        let mut defines: Vec<String> = compile_surface
+                            .defines
+                            .iter()
+                            .map(|d| d.to_flag())
+                            .collect();
+                        if target.kind == TargetKind::SharedLib {
+                            let header = ExportHeader::new(&gen_include_dir, target.name.as_str());
+                            defines.push(format!("-D{}", header.building_define()));
+                        }
+
                         for source in sources {
                             let rel_path = source.strip_prefix(package.root()).unwrap_or(&source);
                             let obj_name = rel_path.with_extension(obj_ext);
@@ -418,13 +499,10 @@ impl BuildPlan {
                                 package: pkg_id.name().to_string(),
                                 target: target.name.to_string(),
                                 include_dirs: compile_surface.include_dirs.clone(),
-                                defines: compile_surface
-                                    .defines
-                                    .iter()
-                                    .map(|d| d.to_flag())
-                                    .collect(),
+                                defines: defines.clone(),
                                 cflags: compile_surface.cflags.clone(),
                                 lang: target_lang,
+                                visibility: target.visibility,
                             };
                             steps.push(BuildStep::Compile(step.clone()));
                             compile_steps.push(step);
@@ -440,6 +518,33 @@ impl BuildPlan {
 
                             let output = output_dir.join(target.output_filename(ctx.os()));
 
+                            let export_map = if target.kind == TargetKind::SharedLib {
+                                resolve_exports(target)?.map(|exports| {
+                                    let format = ExportFormat::for_toolchain(
+                                        ctx.toolchain().platform(),
+                                        ctx.os(),
+                                    );
+                                    ExportMap::new(
+                                        &obj_dir,
+                                        &target.output_filename(ctx.os()),
+                                        format,
+                                        exports,
+                                    )
+                                })
+                            } else {
+                                None
+                            };
+
+                            // Windows DLLs have no SONAME or symlink chain
+                            let version = (target.kind == TargetKind::SharedLib
+                                && ctx.os() != "windows")
+                                .then(|| {
+                                    SharedLibVersion::from_package(
+                                        pkg_id.version(),
+                                        target.soversion.as_deref(),
+                                    )
+                                });
+
                             if target.kind == TargetKind::StaticLib {
                                 // Static library - use archive step (ar/lib.exe, never C++ driver)
                                 steps.push(BuildStep::Archive(ArchiveStep {
@@ -471,6 +576,8 @@ impl BuildPlan {
                                     .collect(),
                                 ldflags: link_surface.ldflags.clone(),
                                 use_cxx_linker,
+                                export_map,
+                                version,
                             };
 
                             if target.kind != TargetKind::StaticLib {
@@ -488,6 +595,7 @@ impl BuildPlan {
             compile_steps,
             link_steps,
             build_order,
+            export_headers,
         })
     }
 
@@ -506,6 +614,7 @@ impl BuildPlan {
                     include_dirs: step.include_dirs.clone(),
                     defines: parse_define_flags(&step.defines),
                     cflags,
+                    visibility: step.visibility,
                 };
 
                 let spec = ctx.toolchain().compile_command(&input, step.lang, None);
@@ -542,6 +651,16 @@ impl BuildPlan {
     pub fn link_count(&self) -> usize {
         self.link_steps.len()
     }
+
+    /// Get the ID (`name vX.Y.Z`) events report for the package `name`.
+    pub fn package_id(&self, name: &str) -> String {
+        self.build_order
+            .iter()
+            .filter_map(|id| id.split_once(' '))
+            .find(|(n, _)| *n == name)
+            .map(|(n, version)| format!("{} v{}", n, version))
+            .unwrap_or_else(|| name.to_string())
+    }
 }
 
 /// compile_commands.json entry.
@@ -554,6 +673,69 @@ struct CompileCommand {
     output: Option<String>,
 }
 
+/// Find packages reachable only through dev-dependency edges of packages
+/// whose test and bench targets are not part of this build.
+///
+/// Root packages only build the targets selected by `target_filter`; every
+/// other package builds all of its targets.
+fn dev_only_packages(
+    resolve: &Resolve,
+    surface_resolver: &SurfaceResolver,
+    root_packages: &HashSet<PackageId>,
+    target_filter: Option<&[String]>,
+) -> HashSet<PackageId> {
+    let mut dev_reachable = HashSet::new();
+    let mut needed: HashSet<PackageId> = root_packages.clone();
+    for &root in root_packages {
+        needed.extend(resolve.transitive_deps(root));
+    }
+
+    for pkg_id in resolve.topological_order() {
+        let dev_deps = resolve.transitive_dev_deps(pkg_id);
+        if dev_deps.is_empty() {
+            continue;
+        }
+
+        let is_root = root_packages.contains(&pkg_id);
+        let builds_tests = surface_resolver.get_package(pkg_id).is_some_and(|package| {
+            package.targets().iter().any(|t| {
+                (t.test.is_some() || t.bench.is_some())
+                    && (!is_root
+                        || target_filter
+                            .map_or(true, |filter| filter.iter().any(|f| f == t.name.as_str())))
+            })
+        });
+
+        if builds_tests {
+            needed.extend(dev_deps);
+        } else {
+            dev_reachable.extend(dev_deps);
+        }
+    }
+
+    dev_reachable.retain(|pkg_id| !needed.contains(pkg_id));
+    dev_reachable
+}
+
+/// Generated export-header directories a package may include: its own and
+/// those of every package it depends on, dev-dependencies included.
+fn visible_export_include_dirs(
+    resolve: &Resolve,
+    pkg_id: PackageId,
+    export_include_dirs: &HashMap<PackageId, PathBuf>,
+) -> Vec<PathBuf> {
+    let mut visible = resolve.transitive_deps(pkg_id);
+    visible.extend(resolve.transitive_dev_deps(pkg_id));
+    visible.insert(pkg_id);
+
+    let mut dirs: Vec<PathBuf> = visible
+        .iter()
+        .filter_map(|id| export_include_dirs.get(id).cloned())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
 /// Check if a file path has a C++ source extension.
 ///
 /// C++ extensions: .cpp, .cc, .cxx, .C (uppercase), .c++
@@ -572,6 +754,37 @@ fn is_cpp_extension(path: &Path) -> bool {
     ) || ext_str == "C" // Uppercase .C is C++ on case-sensitive filesystems
 }
 
+/// CMake cache entries that build a recipe with the profile's sanitizers.
+fn cmake_sanitizer_args(sanitizer_flags: &[String], frame_pointer_flag: &str) -> Vec<String> {
+    if sanitizer_flags.is_empty() {
+        return Vec::new();
+    }
+    let flags = sanitizer_flags.join(" ");
+    let cflags = format!("{} {}", flags, frame_pointer_flag);
+    vec![
+        format!("-DCMAKE_C_FLAGS={}", cflags),
+        format!("-DCMAKE_CXX_FLAGS={}", cflags),
+        format!("-DCMAKE_EXE_LINKER_FLAGS={}", flags),
+        format!("-DCMAKE_SHARED_LINKER_FLAGS={}", flags),
+        format!("-DCMAKE_MODULE_LINKER_FLAGS={}", flags),
+    ]
+}
+
+/// Meson options that build a recipe with the profile's sanitizers.
+fn meson_sanitizer_options(sanitizers: &[String]) -> Vec<String> {
+    if sanitizers.is_empty() {
+        return Vec::new();
+    }
+    // Older Meson only accepts "address,undefined" in this order
+    let mut sanitizers = sanitizers.to_vec();
+    sanitizers.sort();
+    // Sanitized shared libraries leave runtime symbols to the executable
+    vec![
+        format!("-Db_sanitize={}", sanitizers.join(",")),
+        "-Db_lundef=false".to_string(),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -647,6 +860,7 @@ mod tests {
             defines: vec!["-DDEBUG".to_string()],
             cflags: vec!["-Wall".to_string(), "-O2".to_string()],
             lang: Language::C,
+            visibility: SymbolVisibility::Default,
         };
 
         assert_eq!(step.source, PathBuf::from("/project/src/main.c"));
@@ -666,6 +880,7 @@ mod tests {
             defines: vec![],
             cflags: vec!["-std=c++17".to_string()],
             lang: Language::Cxx,
+            visibility: SymbolVisibility::Default,
         };
 
         assert_eq!(step.lang, Language::Cxx);
@@ -687,6 +902,8 @@ mod tests {
             libs: vec!["-lm".to_string(), "-lpthread".to_string()],
             ldflags: vec!["-Wl,-rpath,/opt/lib".to_string()],
             use_cxx_linker: false,
+            export_map: None,
+            version: None,
         };
 
         assert_eq!(step.objects.len(), 2);
@@ -706,6 +923,8 @@ mod tests {
             libs: vec![],
             ldflags: vec![],
             use_cxx_linker: true,
+            export_map: None,
+            version: None,
         };
 
         assert!(step.use_cxx_linker);
@@ -727,6 +946,64 @@ mod tests {
         assert_eq!(step.output, PathBuf::from("/project/lib/libmylib.a"));
     }
 
+    #[test]
+    fn test_export_include_dirs_follow_dependencies() {
+        use crate::core::{SourceId, Summary};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = SourceId::for_path(tmp.path()).unwrap();
+        let id = |name: &str| PackageId::new(name, semver::Version::new(1, 0, 0), source);
+        let (core, app, other) = (id("core"), id("app"), id("other"));
+
+        let mut resolve = Resolve::new();
+        for pkg in [core, app, other] {
+            resolve.add_package(pkg, Summary::new(pkg, vec![], None));
+        }
+        resolve.add_edge(app, core);
+
+        let mut dirs = HashMap::new();
+        dirs.insert(core, PathBuf::from("/deps/core/gen/include"));
+        dirs.insert(app, PathBuf::from("/out/gen/include"));
+
+        assert_eq!(
+            visible_export_include_dirs(&resolve, app, &dirs),
+            vec![
+                PathBuf::from("/deps/core/gen/include"),
+                PathBuf::from("/out/gen/include")
+            ]
+        );
+        assert_eq!(
+            visible_export_include_dirs(&resolve, core, &dirs),
+            vec![PathBuf::from("/deps/core/gen/include")]
+        );
+        assert!(visible_export_include_dirs(&resolve, other, &dirs).is_empty());
+    }
+
+    #[test]
+    fn test_recipe_sanitizer_args() {
+        assert!(cmake_sanitizer_args(&[], "-fno-omit-frame-pointer").is_empty());
+        let args = cmake_sanitizer_args(
+            &[
+                "-fsanitize=address".to_string(),
+                "-fsanitize=undefined".to_string(),
+            ],
+            "-fno-omit-frame-pointer",
+        );
+        assert!(args.contains(
+            &"-DCMAKE_C_FLAGS=-fsanitize=address -fsanitize=undefined -fno-omit-frame-pointer"
+                .to_string()
+        ));
+        assert!(args.contains(
+            &"-DCMAKE_SHARED_LINKER_FLAGS=-fsanitize=address -fsanitize=undefined".to_string()
+        ));
+        let args = cmake_sanitizer_args(&["-fsanitize=address".to_string()], "/Oy-");
+        assert!(args.contains(&"-DCMAKE_CXX_FLAGS=-fsanitize=address /Oy-".to_string()));
+
+        assert!(meson_sanitizer_options(&[]).is_empty());
+        let options = meson_sanitizer_options(&["undefined".to_string(), "address".to_string()]);
+        assert_eq!(options[0], "-Db_sanitize=address,undefined");
+    }
+
     #[test]
     fn test_cmake_step_creation() {
         let step = CMakeStep {
@@ -739,6 +1016,7 @@ mod tests {
             targets: vec!["mylib".to_string()],
             package: "mylib".to_string(),
             target: "mylib".to_string(),
+            abi: String::new(),
         };
 
         assert_eq!(step.args.len(), 2);
@@ -757,6 +1035,7 @@ mod tests {
             targets: vec!["mylib".to_string()],
             package: "mylib".to_string(),
             target: "mylib".to_string(),
+            abi: String::new(),
         };
 
         assert_eq!(step.options.len(), 2);
@@ -795,6 +1074,7 @@ mod tests {
             defines: vec![],
             cflags: vec![],
             lang: Language::C,
+            visibility: SymbolVisibility::Default,
         });
 
         let archive = BuildStep::Archive(ArchiveStep {
@@ -814,6 +1094,8 @@ mod tests {
             libs: vec![],
             ldflags: vec![],
             use_cxx_linker: false,
+            export_map: None,
+            version: None,
         });
 
         // Verify they can be matched
@@ -835,6 +1117,7 @@ mod tests {
                     defines: vec![],
                     cflags: vec![],
                     lang: Language::C,
+                    visibility: SymbolVisibility::Default,
                 }),
                 BuildStep::Compile(CompileStep {
                     source: PathBuf::from("b.c"),
@@ -845,6 +1128,7 @@ mod tests {
                     defines: vec![],
                     cflags: vec![],
                     lang: Language::C,
+                    visibility: SymbolVisibility::Default,
                 }),
             ],
             compile_steps: vec![
@@ -857,6 +1141,7 @@ mod tests {
                     defines: vec![],
                     cflags: vec![],
                     lang: Language::C,
+                    visibility: SymbolVisibility::Default,
                 },
                 CompileStep {
                     source: PathBuf::from("b.c"),
@@ -867,6 +1152,7 @@ mod tests {
                     defines: vec![],
                     cflags: vec![],
                     lang: Language::C,
+                    visibility: SymbolVisibility::Default,
                 },
             ],
             link_steps: vec![LinkStep {
@@ -879,8 +1165,11 @@ mod tests {
                 libs: vec![],
                 ldflags: vec![],
                 use_cxx_linker: false,
+                export_map: None,
+                version: None,
             }],
             build_order: vec!["test 1.0.0".to_string()],
+            export_headers: vec![],
         };
 
         assert_eq!(plan.compile_count(), 2);
@@ -895,6 +1184,7 @@ mod tests {
             compile_steps: vec![],
             link_steps: vec![],
             build_order: vec!["pkg-a 1.0.0".to_string(), "pkg-b 2.0.0".to_string()],
+            export_headers: vec![],
         };
 
         let json = serde_json::to_string(&plan).unwrap();
@@ -902,5 +1192,7 @@ mod tests {
 
         assert_eq!(deserialized.build_order.len(), 2);
         assert_eq!(deserialized.build_order[0], "pkg-a 1.0.0");
+        assert_eq!(deserialized.package_id("pkg-b"), "pkg-b v2.0.0");
+        assert_eq!(deserialized.package_id("pkg-c"), "pkg-c");
     }
 }
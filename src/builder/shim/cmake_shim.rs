@@ -14,6 +14,7 @@ use crate::builder::shim::defaults::BackendDefaults;
 use crate::builder::shim::intent::BackendOptions;
 use crate::builder::shim::trait_def::*;
 use crate::builder::util::{detect_tool_version, extract_lib_name};
+use crate::ops::test_report::parse_junit;
 
 /// CMake backend shim.
 ///
@@ -276,6 +277,16 @@ impl BackendShim for CMakeShim {
         // Output on failure
         args.push("--output-on-failure".to_string());
 
+        // Per-test results need CTest 3.21; older versions report a summary only
+        let junit = Self::detect_cmake_version()
+            .is_ok_and(|v| v >= semver::Version::new(3, 21, 0))
+            .then(|| ctx.build_dir.join("harbour-ctest.xml"));
+        if let Some(ref junit) = junit {
+            let _ = std::fs::remove_file(junit);
+            args.push("--output-junit".to_string());
+            args.push(junit.display().to_string());
+        }
+
         // Parallel
         if let Some(jobs) = ctx.jobs {
             args.push("-j".to_string());
@@ -289,6 +300,17 @@ impl BackendShim for CMakeShim {
             .output()
             .context("failed to run ctest")?;
 
+        if let Some(cases) = junit
+            .and_then(|junit| std::fs::read_to_string(junit).ok())
+            .and_then(|report| parse_junit(&report).ok())
+        {
+            let mut result = TestResult::from_cases(cases);
+            if !output.status.success() && result.failed == 0 {
+                result.failed = 1;
+            }
+            return Ok(result);
+        }
+
         // Parse ctest output for results
         let stdout = String::from_utf8_lossy(&output.stdout);
         let mut result = TestResult::default();
@@ -3,7 +3,7 @@
 //! Defaults are soft preferences that can change without "capabilities changed".
 //! Hard constraints belong in `capabilities.rs`.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::builder::shim::capabilities::InjectionMethod;
 use crate::core::manifest::Profile;
@@ -38,7 +38,7 @@ impl std::fmt::Display for ProfileKind {
 }
 
 /// Sanitizer kinds supported by toolchains.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SanitizerKind {
     /// Address sanitizer (memory errors)
     Address,
@@ -63,8 +63,89 @@ impl SanitizerKind {
             SanitizerKind::Leak => "leak",
         }
     }
+
+    /// Name the runtime uses in its reports, e.g. "AddressSanitizer".
+    pub fn runtime_name(&self) -> &'static str {
+        match self {
+            SanitizerKind::Address => "AddressSanitizer",
+            SanitizerKind::Thread => "ThreadSanitizer",
+            SanitizerKind::Memory => "MemorySanitizer",
+            SanitizerKind::UndefinedBehavior => "UndefinedBehaviorSanitizer",
+            SanitizerKind::Leak => "LeakSanitizer",
+        }
+    }
+
+    /// Environment variable the runtime reads its options from.
+    pub fn options_var(&self) -> &'static str {
+        match self {
+            SanitizerKind::Address => "ASAN_OPTIONS",
+            SanitizerKind::Thread => "TSAN_OPTIONS",
+            SanitizerKind::Memory => "MSAN_OPTIONS",
+            SanitizerKind::UndefinedBehavior => "UBSAN_OPTIONS",
+            SanitizerKind::Leak => "LSAN_OPTIONS",
+        }
+    }
+
+    /// Whether this sanitizer cannot instrument the same build as `other`.
+    ///
+    /// Address, thread and memory sanitizers each replace the allocator and
+    /// shadow memory, so at most one of them can be enabled. The standalone
+    /// leak sanitizer only combines with address.
+    pub fn conflicts_with(&self, other: SanitizerKind) -> bool {
+        use SanitizerKind::*;
+        matches!(
+            (*self, other),
+            (Address, Thread)
+                | (Thread, Address)
+                | (Address, Memory)
+                | (Memory, Address)
+                | (Thread, Memory)
+                | (Memory, Thread)
+                | (Leak, Thread)
+                | (Thread, Leak)
+                | (Leak, Memory)
+                | (Memory, Leak)
+        )
+    }
+}
+
+impl std::fmt::Display for SanitizerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
+impl std::str::FromStr for SanitizerKind {
+    type Err = SanitizerKindParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "address" => Ok(SanitizerKind::Address),
+            "thread" => Ok(SanitizerKind::Thread),
+            "memory" => Ok(SanitizerKind::Memory),
+            "undefined" => Ok(SanitizerKind::UndefinedBehavior),
+            "leak" => Ok(SanitizerKind::Leak),
+            _ => Err(SanitizerKindParseError(s.to_string())),
+        }
+    }
+}
+
+/// Error returned when parsing an unknown sanitizer name.
+#[derive(Debug, Clone)]
+pub struct SanitizerKindParseError(pub String);
+
+impl std::fmt::Display for SanitizerKindParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown sanitizer '{}', valid values: address, thread, memory, undefined, leak",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for SanitizerKindParseError {}
+
 /// Generator configuration (CMake/Meson specific).
 #[derive(Debug, Clone, Default)]
 pub struct GeneratorConfig {
@@ -284,6 +365,8 @@ pub fn profile_from_kind(kind: ProfileKind) -> Profile {
             debug: Some("2".to_string()),
             lto: Some(false),
             sanitizers: Vec::new(),
+            sanitizer_options: BTreeMap::new(),
+            suppressions: None,
             cflags: Vec::new(),
             ldflags: Vec::new(),
         },
@@ -292,6 +375,8 @@ pub fn profile_from_kind(kind: ProfileKind) -> Profile {
             debug: Some("0".to_string()),
             lto: Some(false),
             sanitizers: Vec::new(),
+            sanitizer_options: BTreeMap::new(),
+            suppressions: None,
             cflags: Vec::new(),
             ldflags: Vec::new(),
         },
@@ -350,4 +435,17 @@ mod tests {
         let asan_flags = defaults.get_sanitizer_flags(SanitizerKind::Address);
         assert!(asan_flags.iter().any(|f| f.contains("sanitize=address")));
     }
+
+    #[test]
+    fn test_sanitizer_kind_parse_and_conflicts() {
+        let ubsan: SanitizerKind = "undefined".parse().unwrap();
+        assert_eq!(ubsan, SanitizerKind::UndefinedBehavior);
+        assert_eq!(ubsan.options_var(), "UBSAN_OPTIONS");
+        assert!("ubsan".parse::<SanitizerKind>().is_err());
+
+        assert!(SanitizerKind::Address.conflicts_with(SanitizerKind::Thread));
+        assert!(SanitizerKind::Memory.conflicts_with(SanitizerKind::Leak));
+        assert!(!SanitizerKind::Address.conflicts_with(SanitizerKind::UndefinedBehavior));
+        assert!(!SanitizerKind::Address.conflicts_with(SanitizerKind::Leak));
+    }
 }
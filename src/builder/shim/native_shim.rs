@@ -2,6 +2,8 @@
 //!
 //! This shim provides the BackendShim interface for the existing NativeBuilder.
 
+use std::path::Path;
+
 use anyhow::Result;
 
 use crate::builder::shim::capabilities::{
@@ -12,6 +14,8 @@ use crate::builder::shim::defaults::BackendDefaults;
 use crate::builder::shim::intent::BackendOptions;
 use crate::builder::shim::trait_def::*;
 use crate::builder::util::extract_lib_name;
+use crate::util::binary;
+use crate::util::fs::{copy_file_or_link, ensure_dir};
 
 /// Native backend shim.
 ///
@@ -108,8 +112,40 @@ impl BackendShim for NativeShim {
             ctx.install_prefix.display()
         );
 
-        // Install implementation would copy artifacts to prefix
-        Ok(InstallResult::default())
+        let mut result = InstallResult::default();
+        if ctx.build_dir == ctx.install_prefix {
+            return Ok(result);
+        }
+
+        for (from, to, kind) in [
+            ("lib", "lib", InstalledFileKind::Library),
+            ("bin", "bin", InstalledFileKind::Executable),
+            ("gen/include", "include", InstalledFileKind::Header),
+        ] {
+            let Ok(entries) = std::fs::read_dir(ctx.build_dir.join(from)) else {
+                continue;
+            };
+            let dest_dir = ctx.install_prefix.join(to);
+            ensure_dir(&dest_dir)?;
+
+            for entry in entries.flatten() {
+                let source = entry.path();
+                if source.is_dir() {
+                    continue;
+                }
+
+                // Shared library version chains stay symlinks
+                let destination = dest_dir.join(entry.file_name());
+                copy_file_or_link(&source, &destination)?;
+                result.files.push(InstalledFile {
+                    source,
+                    destination,
+                    kind,
+                });
+            }
+        }
+
+        Ok(result)
     }
 
     fn clean(&self, ctx: &BuildContext, opts: &CleanOptions) -> Result<()> {
@@ -164,10 +200,21 @@ impl BackendShim for NativeShim {
                                 });
                             }
                         } else if ext == "so" || ext == "dylib" || ext == "dll" {
+                            // Versioned names (libfoo.1.dylib) are part of the
+                            // symlink chain behind the unversioned one
+                            if is_versioned_link(&path) {
+                                continue;
+                            }
+
                             // Shared library
                             if let Some(name) = extract_lib_name(&path) {
-                                let soname =
-                                    path.file_name().map(|n| n.to_string_lossy().to_string());
+                                // Prefer the SONAME recorded by the linker
+                                let soname = binary::inspect(&path)
+                                    .ok()
+                                    .and_then(|info| info.soname)
+                                    .or_else(|| {
+                                        path.file_name().map(|n| n.to_string_lossy().to_string())
+                                    });
 
                                 surface.libraries.push(LibraryInfo {
                                     name,
@@ -227,6 +274,31 @@ impl BackendShim for NativeShim {
     }
 }
 
+/// Whether `path` is a versioned member of a shared library's symlink chain
+/// (`libfoo.1.dylib`, `libfoo.1.2.3.dylib`) next to its unversioned name.
+///
+/// Names that merely contain dots, such as `libfoo-1.0.so`, are libraries in
+/// their own right.
+fn is_versioned_link(path: &Path) -> bool {
+    let (Some(stem), Some(ext)) = (path.file_stem(), path.extension()) else {
+        return false;
+    };
+    let stem = stem.to_string_lossy();
+
+    let mut base = stem.as_ref();
+    while let Some((head, tail)) = base.rsplit_once('.') {
+        if tail.is_empty() || !tail.chars().all(|c| c.is_ascii_digit()) {
+            break;
+        }
+        base = head;
+    }
+
+    base != stem
+        && path
+            .with_file_name(format!("{}.{}", base, ext.to_string_lossy()))
+            .exists()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,4 +344,54 @@ mod tests {
         let result = shim.configure(&ctx, &opts).unwrap();
         assert!(result.skipped);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_native_install_preserves_version_links() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let build_dir = tmp.path().join("build");
+        let prefix = tmp.path().join("prefix");
+        let lib_dir = build_dir.join("lib");
+        std::fs::create_dir_all(&lib_dir).unwrap();
+        std::fs::write(lib_dir.join("libfoo.so.1.2.3"), "elf").unwrap();
+        std::os::unix::fs::symlink("libfoo.so.1.2.3", lib_dir.join("libfoo.so.1")).unwrap();
+        std::os::unix::fs::symlink("libfoo.so.1", lib_dir.join("libfoo.so")).unwrap();
+
+        let shim = NativeShim::new();
+        let ctx = BuildContext::new(tmp.path().to_path_buf(), build_dir, prefix.clone());
+        let result = shim.install(&ctx, &BackendOptions::new()).unwrap();
+
+        assert_eq!(result.files.len(), 3);
+        assert_eq!(
+            std::fs::read_link(prefix.join("lib/libfoo.so")).unwrap(),
+            PathBuf::from("libfoo.so.1")
+        );
+        assert_eq!(
+            std::fs::read_to_string(prefix.join("lib/libfoo.so")).unwrap(),
+            "elf"
+        );
+
+        // Only the unversioned name is discovered
+        let surface = shim.discover_exports(&ctx).unwrap().unwrap();
+        assert_eq!(surface.libraries.len(), 1);
+        assert_eq!(surface.libraries[0].name, "foo");
+    }
+
+    #[test]
+    fn test_is_versioned_link() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        for name in [
+            "libfoo.dylib",
+            "libfoo.1.dylib",
+            "libfoo.1.2.3.dylib",
+            "libbar-1.0.so",
+        ] {
+            std::fs::write(tmp.path().join(name), "").unwrap();
+        }
+
+        assert!(!is_versioned_link(&tmp.path().join("libfoo.dylib")));
+        assert!(is_versioned_link(&tmp.path().join("libfoo.1.dylib")));
+        assert!(is_versioned_link(&tmp.path().join("libfoo.1.2.3.dylib")));
+        assert!(!is_versioned_link(&tmp.path().join("libbar-1.0.so")));
+    }
 }
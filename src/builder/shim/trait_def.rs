@@ -4,6 +4,7 @@
 //! Operations only - validation is done externally via capabilities.
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::Result;
 
@@ -189,6 +190,23 @@ pub struct TestResult {
 }
 
 impl TestResult {
+    /// Tally individual test cases into a result.
+    pub fn from_cases(tests: Vec<TestCase>) -> Self {
+        let mut result = TestResult {
+            total: tests.len(),
+            ..TestResult::default()
+        };
+        for case in &tests {
+            match case.status {
+                TestStatus::Passed => result.passed += 1,
+                TestStatus::Failed | TestStatus::TimedOut => result.failed += 1,
+                TestStatus::Skipped => result.skipped += 1,
+            }
+        }
+        result.tests = tests;
+        result
+    }
+
     /// Check if all tests passed.
     pub fn success(&self) -> bool {
         self.failed == 0
@@ -201,11 +219,27 @@ pub struct TestCase {
     /// Test name
     pub name: String,
 
+    /// Suite the case belongs to, e.g. the test binary that reported it
+    pub suite: Option<String>,
+
     /// Test status
     pub status: TestStatus,
 
     /// Output (if failed)
     pub output: Option<String>,
+
+    /// Wall-clock run time (if measured)
+    pub duration: Option<Duration>,
+}
+
+impl TestCase {
+    /// Name qualified by its suite, e.g. `unit_test::Parser.Empty`.
+    pub fn full_name(&self) -> String {
+        match &self.suite {
+            Some(suite) => format!("{}::{}", suite, self.name),
+            None => self.name.clone(),
+        }
+    }
 }
 
 /// Test status.
@@ -214,6 +248,20 @@ pub enum TestStatus {
     Passed,
     Failed,
     Skipped,
+    /// Killed after exceeding its timeout (counted as failed)
+    TimedOut,
+}
+
+impl TestStatus {
+    /// Get the status name used in machine-readable output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestStatus::Passed => "passed",
+            TestStatus::Failed => "failed",
+            TestStatus::Skipped => "skipped",
+            TestStatus::TimedOut => "timed-out",
+        }
+    }
 }
 
 /// Result of the install phase.
@@ -542,6 +590,28 @@ mod tests {
         assert!(success.success());
     }
 
+    #[test]
+    fn test_test_result_from_cases() {
+        let case = |name: &str, status| TestCase {
+            name: name.to_string(),
+            suite: Some("unit_test".to_string()),
+            status,
+            output: None,
+            duration: None,
+        };
+        let result = TestResult::from_cases(vec![
+            case("a", TestStatus::Passed),
+            case("b", TestStatus::TimedOut),
+            case("c", TestStatus::Skipped),
+        ]);
+
+        assert_eq!(result.total, 3);
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.failed, 1);
+        assert_eq!(result.skipped, 1);
+        assert_eq!(result.tests[1].full_name(), "unit_test::b");
+    }
+
     #[test]
     fn test_discovered_surface_to_surface() {
         let mut discovered = DiscoveredSurface::default();
@@ -8,6 +8,7 @@
 use thiserror::Error;
 
 use crate::builder::shim::capabilities::{BackendCapabilities, ExportDiscovery, PhaseSupport};
+use crate::builder::shim::defaults::SanitizerKind;
 use crate::builder::shim::intent::{BackendOptions, BuildIntent, LinkagePreference};
 use crate::builder::shim::trait_def::{BackendShim, ValidationResult};
 use crate::builder::toolchain::Toolchain;
@@ -47,6 +48,13 @@ pub enum ValidationError {
         max: CppStandard,
     },
 
+    #[error("sanitizer `{sanitizer}` not supported by {toolchain}, supported: {supported:?}")]
+    UnsupportedSanitizer {
+        sanitizer: String,
+        toolchain: String,
+        supported: Vec<String>,
+    },
+
     #[error("toolchain platform `{requested}` not available, have: {available}")]
     ToolchainMismatch {
         requested: String,
@@ -303,7 +311,20 @@ impl<'a> ToolchainValidator<'a> {
         // Sanitizer support varies by platform
         let supported_sanitizers = match toolchain.platform() {
             ToolchainPlatform::Msvc => vec!["address".to_string()], // MSVC has limited sanitizer support
-            _ => vec![
+            // MemorySanitizer is Clang-only; Apple's runtime has no standalone LSan
+            ToolchainPlatform::AppleClang => vec![
+                "address".to_string(),
+                "thread".to_string(),
+                "undefined".to_string(),
+            ],
+            ToolchainPlatform::Clang => vec![
+                "address".to_string(),
+                "thread".to_string(),
+                "memory".to_string(),
+                "undefined".to_string(),
+                "leak".to_string(),
+            ],
+            ToolchainPlatform::Gcc => vec![
                 "address".to_string(),
                 "thread".to_string(),
                 "undefined".to_string(),
@@ -352,6 +373,21 @@ impl<'a> ToolchainValidator<'a> {
         Ok(())
     }
 
+    /// Validate a profile's sanitizers against the toolchain.
+    pub fn validate_sanitizers(&self, sanitizers: &[SanitizerKind]) -> Result<(), ValidationError> {
+        let supported = &self.capabilities.supported_sanitizers;
+        for sanitizer in sanitizers {
+            if !supported.iter().any(|s| s == sanitizer.as_str()) {
+                return Err(ValidationError::UnsupportedSanitizer {
+                    sanitizer: sanitizer.as_str().to_string(),
+                    toolchain: self.toolchain.platform().as_str().to_string(),
+                    supported: supported.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Get the toolchain capabilities.
     pub fn capabilities(&self) -> &ToolchainCapabilities {
         &self.capabilities
@@ -581,4 +617,29 @@ mod tests {
         let err = ValidationError::multiple(errors);
         assert!(matches!(err, ValidationError::CrossCompileNotSupported));
     }
+
+    #[test]
+    fn test_toolchain_validator_sanitizers() {
+        use crate::builder::toolchain::{GccToolchain, ToolchainPlatform};
+        use std::path::PathBuf;
+
+        let gcc = GccToolchain::new(
+            PathBuf::from("gcc"),
+            PathBuf::from("g++"),
+            PathBuf::from("ar"),
+            ToolchainPlatform::Gcc,
+        );
+        let validator = ToolchainValidator::new(&gcc);
+
+        assert!(validator
+            .validate_sanitizers(&[SanitizerKind::Address, SanitizerKind::UndefinedBehavior])
+            .is_ok());
+        let err = validator
+            .validate_sanitizers(&[SanitizerKind::Memory])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::UnsupportedSanitizer { ref sanitizer, .. } if sanitizer == "memory"
+        ));
+    }
 }
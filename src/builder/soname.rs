@@ -0,0 +1,190 @@
+//! Shared library versioning.
+//!
+//! A versioned shared library is linked to a file named after its full
+//! version, with symlinks down to the unversioned name the linker looks for:
+//!
+//! | Format | Link name      | SONAME / install name | Real file            |
+//! |--------|----------------|-----------------------|----------------------|
+//! | ELF    | `libfoo.so`    | `libfoo.so.1`         | `libfoo.so.1.2.3`    |
+//! | Mach-O | `libfoo.dylib` | `libfoo.1.dylib`      | `libfoo.1.2.3.dylib` |
+//!
+//! Dependents link against the link name and the linker records the SONAME,
+//! so they keep loading any later build with the same SONAME. Windows DLLs
+//! are not versioned this way.
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::builder::toolchain::LinkVersion;
+use crate::util::fs::replace_symlink;
+
+/// Version of a shared library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedLibVersion {
+    /// Full library version (e.g. `1.2.3`)
+    pub version: String,
+
+    /// ABI version carried in the SONAME (e.g. `1`)
+    pub soversion: String,
+}
+
+impl SharedLibVersion {
+    /// Version a library after its package.
+    ///
+    /// Without an explicit `soversion`, the SONAME carries the major version,
+    /// or `0.minor` before 1.0, so it changes exactly when semver allows
+    /// breaking changes.
+    pub fn from_package(version: &Version, soversion: Option<&str>) -> Self {
+        let soversion = match soversion {
+            Some(s) => s.to_string(),
+            None if version.major == 0 => format!("0.{}", version.minor),
+            None => version.major.to_string(),
+        };
+
+        SharedLibVersion {
+            version: format!("{}.{}.{}", version.major, version.minor, version.patch),
+            soversion,
+        }
+    }
+
+    /// The SONAME (ELF) or install name (Mach-O) file name.
+    pub fn soname(&self, link_name: &str) -> String {
+        versioned_name(link_name, &self.soversion)
+    }
+
+    /// The name of the real library file.
+    pub fn real_name(&self, link_name: &str) -> String {
+        versioned_name(link_name, &self.version)
+    }
+
+    /// Mach-O compatibility version: the soversion padded to `x.y.z`.
+    pub fn compatibility_version(&self) -> String {
+        let mut parts: Vec<&str> = self.soversion.split('.').collect();
+        parts.resize(3, "0");
+        parts.join(".")
+    }
+
+    /// Versioning to pass to the linker for a library with this link name.
+    pub fn link_version(&self, link_name: &str) -> LinkVersion {
+        LinkVersion {
+            soname: self.soname(link_name),
+            current_version: self.version.clone(),
+            compatibility_version: self.compatibility_version(),
+        }
+    }
+
+    /// Create the symlink chain for a library linked to its real name.
+    ///
+    /// `output` is the unversioned link name; existing links are replaced.
+    pub fn create_symlinks(&self, output: &Path) -> Result<()> {
+        let Some(link_name) = output.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            bail!("shared library has no file name: {}", output.display());
+        };
+
+        let soname = self.soname(&link_name);
+        let real_name = self.real_name(&link_name);
+
+        for (name, target) in [(&soname, &real_name), (&link_name, &soname)] {
+            if name == target {
+                continue;
+            }
+            replace_symlink(Path::new(target), &output.with_file_name(name))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check a manifest `soversion`: up to three dot-separated numbers.
+pub fn validate_soversion(soversion: &str) -> Result<()> {
+    let parts: Vec<&str> = soversion.split('.').collect();
+    if parts.len() > 3
+        || parts
+            .iter()
+            .any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit()))
+    {
+        bail!(
+            "`{}` is not a valid soversion (expected numbers such as \"1\" or \"0.3\")",
+            soversion
+        );
+    }
+    Ok(())
+}
+
+fn versioned_name(link_name: &str, version: &str) -> String {
+    match link_name.strip_suffix(".dylib") {
+        Some(stem) => format!("{}.{}.dylib", stem, version),
+        None => format!("{}.{}", link_name, version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_package() {
+        let v = SharedLibVersion::from_package(&Version::new(1, 2, 3), None);
+        assert_eq!(v.version, "1.2.3");
+        assert_eq!(v.soversion, "1");
+
+        let v = SharedLibVersion::from_package(&Version::new(0, 4, 1), None);
+        assert_eq!(v.soversion, "0.4");
+
+        let v = SharedLibVersion::from_package(&"2.0.0-beta.1".parse().unwrap(), Some("7"));
+        assert_eq!(v.version, "2.0.0");
+        assert_eq!(v.soversion, "7");
+    }
+
+    #[test]
+    fn test_versioned_names() {
+        let v = SharedLibVersion::from_package(&Version::new(1, 2, 3), None);
+        assert_eq!(v.soname("libfoo.so"), "libfoo.so.1");
+        assert_eq!(v.real_name("libfoo.so"), "libfoo.so.1.2.3");
+        assert_eq!(v.soname("libfoo.dylib"), "libfoo.1.dylib");
+        assert_eq!(v.real_name("libfoo.dylib"), "libfoo.1.2.3.dylib");
+        assert_eq!(v.compatibility_version(), "1.0.0");
+
+        let link = v.link_version("libfoo.dylib");
+        assert_eq!(link.soname, "libfoo.1.dylib");
+        assert_eq!(link.current_version, "1.2.3");
+        assert_eq!(link.compatibility_version, "1.0.0");
+    }
+
+    #[test]
+    fn test_validate_soversion() {
+        assert!(validate_soversion("1").is_ok());
+        assert!(validate_soversion("0.3").is_ok());
+        assert!(validate_soversion("1.2.3").is_ok());
+        assert!(validate_soversion("").is_err());
+        assert!(validate_soversion("v1").is_err());
+        assert!(validate_soversion("1..2").is_err());
+        assert!(validate_soversion("1.2.3.4").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_symlinks() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let output = tmp.path().join("libfoo.so");
+        std::fs::write(tmp.path().join("libfoo.so.1.2.3"), "elf").unwrap();
+
+        let v = SharedLibVersion::from_package(&Version::new(1, 2, 3), None);
+        v.create_symlinks(&output).unwrap();
+        // Relinking replaces the existing links
+        v.create_symlinks(&output).unwrap();
+
+        assert_eq!(
+            std::fs::read_link(&output).unwrap(),
+            Path::new("libfoo.so.1")
+        );
+        assert_eq!(
+            std::fs::read_link(tmp.path().join("libfoo.so.1")).unwrap(),
+            Path::new("libfoo.so.1.2.3")
+        );
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "elf");
+    }
+}
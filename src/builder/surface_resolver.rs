@@ -3,7 +3,7 @@
 //! This module computes the effective compile and link surfaces for a target
 //! by propagating public surfaces from dependencies.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::path::PathBuf;
 
@@ -41,6 +41,16 @@ pub enum SurfaceResolveError {
         candidates: Vec<String>,
     },
 
+    /// A non-test target refers to a package that is only a dev-dependency.
+    #[error(
+        "in target `{target_name}`: `{dep_name}` is a dev-dependency and only visible to test and bench targets\n\
+             help: move `{dep_name}` to [dependencies], or mark the target with `test = true`"
+    )]
+    DevDependencyNotAllowed {
+        target_name: String,
+        dep_name: String,
+    },
+
     /// A target specified in target.deps was not found in the dependency package.
     #[error(
         "in target `{target_name}`: target `{dep_target}` not found in `{dep_pkg}`\n\
@@ -274,7 +284,18 @@ impl<'a> SurfaceResolver<'a> {
         // Validate target.deps - ensure all referenced deps exist in resolve
         for (dep_name, _dep_spec) in &target.deps {
             match self.resolve.get_package_by_name_strict(*dep_name) {
-                Ok(_) => { /* found, continue */ }
+                Ok(dep_id) => {
+                    if target.test.is_none()
+                        && target.bench.is_none()
+                        && self.resolve.is_dev_edge(pkg_id, dep_id)
+                    {
+                        return Err(SurfaceResolveError::DevDependencyNotAllowed {
+                            target_name: target.name.to_string(),
+                            dep_name: dep_name.to_string(),
+                        }
+                        .into());
+                    }
+                }
                 Err(crate::resolver::ResolveError::PackageNotFound { .. }) => {
                     return Err(SurfaceResolveError::DependencyNotFound {
                         target_name: target.name.to_string(),
@@ -306,7 +327,7 @@ impl<'a> SurfaceResolver<'a> {
         self.add_compile_requirements(&mut effective, &resolved.compile_public, package.root());
 
         // Determine effective dependencies - use target.deps if specified
-        let transitive_deps = self.resolve.transitive_deps(pkg_id);
+        let transitive_deps = self.target_deps(pkg_id, target);
 
         for dep_id in transitive_deps {
             // Check if target.deps specifies visibility for this dependency
@@ -340,6 +361,19 @@ impl<'a> SurfaceResolver<'a> {
         Ok(effective)
     }
 
+    /// Packages whose public surfaces a target sees.
+    ///
+    /// Every target sees its package's transitive dependencies. Test and bench
+    /// targets also see the package's dev-dependencies, which are never followed
+    /// through `transitive_deps` and so never reach dependents.
+    fn target_deps(&self, pkg_id: PackageId, target: &Target) -> HashSet<PackageId> {
+        let mut deps = self.resolve.transitive_deps(pkg_id);
+        if target.test.is_some() || target.bench.is_some() {
+            deps.extend(self.resolve.transitive_dev_deps(pkg_id));
+        }
+        deps
+    }
+
     /// Get compile visibility for a dependency from target.deps.
     /// Returns Public if not specified (default).
     /// O(1) lookup using HashMap.
@@ -430,13 +464,14 @@ impl<'a> SurfaceResolver<'a> {
         // Add dependencies in topological order (dependencies before dependents)
         // This ensures correct link order
         let deps_order = self.resolve.topological_order();
+        let transitive_deps = self.target_deps(pkg_id, target);
         for dep_id in deps_order {
             if dep_id == pkg_id {
                 continue;
             }
 
             // Check if this is a transitive dependency
-            if !self.resolve.transitive_deps(pkg_id).contains(&dep_id) {
+            if !transitive_deps.contains(&dep_id) {
                 continue;
             }
 
@@ -549,7 +584,7 @@ impl<'a> SurfaceResolver<'a> {
         );
 
         // Add transitive public surfaces from dependencies
-        let transitive_deps = self.resolve.transitive_deps(pkg_id);
+        let transitive_deps = self.target_deps(pkg_id, target);
         for dep_id in transitive_deps {
             if let Some(dep_package) = self.packages.get(&dep_id) {
                 if let Some(dep_target) = dep_package.default_target() {
@@ -608,13 +643,14 @@ impl<'a> SurfaceResolver<'a> {
         // Add dependencies in topological order (dependencies before dependents)
         // This ensures correct link order
         let deps_order = self.resolve.topological_order();
+        let transitive_deps = self.target_deps(pkg_id, target);
         for dep_id in deps_order {
             if dep_id == pkg_id {
                 continue;
             }
 
             // Check if this is a transitive dependency
-            if !self.resolve.transitive_deps(pkg_id).contains(&dep_id) {
+            if !transitive_deps.contains(&dep_id) {
                 continue;
             }
 
@@ -432,28 +432,29 @@ fn try_detect_gcc() -> Result<Option<Box<dyn Toolchain>>> {
 
 /// Detect whether the compiler is GCC, Clang, or Apple Clang.
 fn detect_compiler_family(cc: &Path) -> Result<ToolchainPlatform> {
-    // Check binary name first
-    let name = cc
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-
-    if name.contains("clang") {
-        // Could be Apple Clang or regular Clang
-        return detect_clang_variant(cc);
-    } else if name.contains("gcc") || name.contains("g++") {
-        return Ok(ToolchainPlatform::Gcc);
-    }
-
-    // Try to detect from --version output
+    // The version banner is checked first: `cc` is often Clang, and on
+    // macOS `gcc` is Apple Clang
     let output = std::process::Command::new(cc).arg("--version").output();
 
     if let Ok(output) = output {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_lowercase();
-        if stdout.contains("clang") {
-            return detect_clang_variant(cc);
-        } else if stdout.contains("gcc") {
+        let banner = String::from_utf8_lossy(&output.stdout);
+        if let Some(family) = compiler_family_from_version(&banner) {
+            return Ok(family);
+        }
+    }
+
+    // Fall back to the binary name, following links such as cc -> clang-17
+    let resolved = cc.canonicalize().unwrap_or_else(|_| cc.to_path_buf());
+    for path in [cc, resolved.as_path()] {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        if name.contains("clang") {
+            return Ok(ToolchainPlatform::Clang);
+        } else if name.contains("gcc") || name.contains("g++") {
             return Ok(ToolchainPlatform::Gcc);
         }
     }
@@ -462,26 +463,30 @@ fn detect_compiler_family(cc: &Path) -> Result<ToolchainPlatform> {
     Ok(ToolchainPlatform::Gcc)
 }
 
-/// Detect if Clang is Apple Clang or regular Clang.
-fn detect_clang_variant(cc: &Path) -> Result<ToolchainPlatform> {
-    let output = std::process::Command::new(cc).arg("--version").output();
-
-    if let Ok(output) = output {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_lowercase();
-        if stdout.contains("apple") {
-            return Ok(ToolchainPlatform::AppleClang);
+/// Identify the compiler family from `--version` output.
+fn compiler_family_from_version(banner: &str) -> Option<ToolchainPlatform> {
+    let banner = banner.to_lowercase();
+    if banner.contains("clang") {
+        if banner.contains("apple") {
+            Some(ToolchainPlatform::AppleClang)
+        } else {
+            Some(ToolchainPlatform::Clang)
         }
+    } else if banner.contains("gcc") || banner.contains("free software foundation") {
+        Some(ToolchainPlatform::Gcc)
+    } else {
+        None
     }
-
-    Ok(ToolchainPlatform::Clang)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::manifest::MsvcRuntime;
+    use crate::core::manifest::{MsvcRuntime, SymbolVisibility};
     use crate::core::target::{CppStandard, Language};
-    use super::super::{ArchiveInput, CompileInput, CxxOptions};
+    use super::super::{
+        ArchiveInput, CompileInput, CxxOptions, LinkInput, LinkVersion, PreprocessInput,
+    };
 
     #[test]
     fn test_gcc_compile_command() {
@@ -501,6 +506,7 @@ mod tests {
                 ("VERSION".to_string(), Some("1".to_string())),
             ],
             cflags: vec!["-Wall".to_string()],
+            visibility: SymbolVisibility::Default,
         };
 
         let cmd = toolchain.compile_command(&input, Language::C, None);
@@ -527,6 +533,7 @@ mod tests {
             include_dirs: vec![],
             defines: vec![],
             cflags: vec![],
+            visibility: SymbolVisibility::Default,
         };
 
         let cxx_opts = CxxOptions {
@@ -544,6 +551,59 @@ mod tests {
         assert!(cmd.args.contains(&"-std=c++17".to_string()));
     }
 
+    #[test]
+    fn test_gcc_syntax_check_command() {
+        let toolchain = GccToolchain::new(
+            PathBuf::from("gcc"),
+            PathBuf::from("g++"),
+            PathBuf::from("ar"),
+            ToolchainPlatform::Gcc,
+        );
+
+        let input = CompileInput {
+            source: PathBuf::from("src/main.c"),
+            output: PathBuf::from("obj/main.o"),
+            include_dirs: vec![PathBuf::from("include")],
+            defines: vec![("DEBUG".to_string(), None)],
+            cflags: vec!["-Wall".to_string()],
+            visibility: SymbolVisibility::Hidden,
+        };
+
+        // Same flags as a compile, with -fsyntax-only instead of -c and no output
+        let compile = toolchain.compile_command(&input, Language::C, None);
+        let check = toolchain.syntax_check_command(&input, Language::C, None);
+        assert_eq!(check.program, compile.program);
+        assert_eq!(check.args[0], "-fsyntax-only");
+        assert_eq!(check.args[1..], compile.args[1..compile.args.len() - 2]);
+        assert_eq!(check.args.last().map(String::as_str), Some("src/main.c"));
+    }
+
+    #[test]
+    fn test_gcc_preprocess_command() {
+        let toolchain = GccToolchain::new(
+            PathBuf::from("gcc"),
+            PathBuf::from("g++"),
+            PathBuf::from("ar"),
+            ToolchainPlatform::Gcc,
+        );
+
+        let input = PreprocessInput {
+            source: PathBuf::from("ffi/headers.c"),
+            output: PathBuf::from("ffi/headers.i"),
+            include_dirs: vec![PathBuf::from("include")],
+            defines: vec![("MYLIB_BUILD_SHARED".to_string(), None)],
+            cflags: vec![],
+        };
+
+        let cmd = toolchain.preprocess_command(&input, Language::C);
+        assert_eq!(cmd.program, PathBuf::from("gcc"));
+        assert!(cmd.args.contains(&"-E".to_string()));
+        assert!(cmd.args.contains(&"-dD".to_string()));
+        assert!(cmd.args.contains(&"-Iinclude".to_string()));
+        assert!(cmd.args.contains(&"-DMYLIB_BUILD_SHARED".to_string()));
+        assert!(!cmd.args.contains(&"-c".to_string()));
+    }
+
     #[test]
     fn test_gcc_archive_command() {
         let toolchain = GccToolchain::new(
@@ -563,6 +623,140 @@ mod tests {
         assert!(cmd.args.contains(&"rcs".to_string()));
     }
 
+    #[test]
+    fn test_gcc_hidden_visibility() {
+        let toolchain = GccToolchain::new(
+            PathBuf::from("gcc"),
+            PathBuf::from("g++"),
+            PathBuf::from("ar"),
+            ToolchainPlatform::Gcc,
+        );
+
+        let input = CompileInput {
+            source: PathBuf::from("src/lib.cpp"),
+            output: PathBuf::from("obj/lib.o"),
+            include_dirs: vec![],
+            defines: vec![],
+            cflags: vec![],
+            visibility: SymbolVisibility::Hidden,
+        };
+
+        let cmd = toolchain.compile_command(&input, Language::C, None);
+        assert!(cmd.args.contains(&"-fvisibility=hidden".to_string()));
+        assert!(!cmd.args.contains(&"-fvisibility-inlines-hidden".to_string()));
+
+        let cmd = toolchain.compile_command(&input, Language::Cxx, None);
+        assert!(cmd.args.contains(&"-fvisibility-inlines-hidden".to_string()));
+    }
+
+    #[test]
+    fn test_gcc_link_shared_export_map() {
+        let toolchain = GccToolchain::new(
+            PathBuf::from("gcc"),
+            PathBuf::from("g++"),
+            PathBuf::from("ar"),
+            ToolchainPlatform::Gcc,
+        );
+
+        let mut input = LinkInput {
+            objects: vec![PathBuf::from("obj/lib.o")],
+            output: PathBuf::from("lib/libfoo.so"),
+            lib_dirs: vec![],
+            libs: vec![],
+            ldflags: vec![],
+            export_map: Some(PathBuf::from("obj/libfoo.map")),
+            version: None,
+        };
+
+        let cmd = toolchain.link_shared_command(&input, Language::C, None);
+        assert!(cmd
+            .args
+            .contains(&"-Wl,--version-script=obj/libfoo.map".to_string()));
+
+        input.export_map = Some(PathBuf::from("obj/libfoo.exp"));
+        let cmd = toolchain.link_shared_command(&input, Language::C, None);
+        assert!(cmd
+            .args
+            .contains(&"-Wl,-exported_symbols_list,obj/libfoo.exp".to_string()));
+
+        input.export_map = Some(PathBuf::from("obj/foo.def"));
+        let cmd = toolchain.link_shared_command(&input, Language::C, None);
+        assert!(cmd.args.contains(&"obj/foo.def".to_string()));
+    }
+
+    #[test]
+    fn test_gcc_link_shared_version() {
+        let toolchain = GccToolchain::new(
+            PathBuf::from("clang"),
+            PathBuf::from("clang++"),
+            PathBuf::from("ar"),
+            ToolchainPlatform::AppleClang,
+        );
+
+        let mut input = LinkInput {
+            objects: vec![PathBuf::from("obj/lib.o")],
+            output: PathBuf::from("lib/libfoo.so.1.2.3"),
+            lib_dirs: vec![],
+            libs: vec![],
+            ldflags: vec![],
+            export_map: None,
+            version: Some(LinkVersion {
+                soname: "libfoo.so.1".to_string(),
+                current_version: "1.2.3".to_string(),
+                compatibility_version: "1.0.0".to_string(),
+            }),
+        };
+
+        let cmd = toolchain.link_shared_command(&input, Language::C, None);
+        assert!(cmd.args.contains(&"-Wl,-soname,libfoo.so.1".to_string()));
+        assert!(cmd.args.contains(&"-Wl,-rpath,$ORIGIN".to_string()));
+
+        input.output = PathBuf::from("lib/libfoo.1.2.3.dylib");
+        input.version.as_mut().unwrap().soname = "libfoo.1.dylib".to_string();
+        let cmd = toolchain.link_shared_command(&input, Language::C, None);
+        assert!(cmd
+            .args
+            .contains(&"-Wl,-install_name,@rpath/libfoo.1.dylib".to_string()));
+        assert!(cmd.args.contains(&"-Wl,-current_version,1.2.3".to_string()));
+        assert!(cmd
+            .args
+            .contains(&"-Wl,-compatibility_version,1.0.0".to_string()));
+        assert!(!cmd.args.iter().any(|a| a.starts_with("-Wl,-soname")));
+        assert!(!cmd.args.iter().any(|a| a.starts_with("-Wl,-rpath")));
+
+        // Dependent dylibs find `@rpath/` libraries where they were linked from
+        input.lib_dirs = vec![PathBuf::from("deps/bar/lib")];
+        let cmd = toolchain.link_shared_command(&input, Language::C, None);
+        assert!(cmd.args.contains(&"-Wl,-rpath,deps/bar/lib".to_string()));
+    }
+
+    #[test]
+    fn test_gcc_link_exe_rpath() {
+        let toolchain = GccToolchain::new(
+            PathBuf::from("clang"),
+            PathBuf::from("clang++"),
+            PathBuf::from("ar"),
+            ToolchainPlatform::AppleClang,
+        );
+
+        let input = LinkInput {
+            objects: vec![PathBuf::from("obj/main.o")],
+            output: PathBuf::from("bin/app"),
+            lib_dirs: vec![PathBuf::from("lib")],
+            libs: vec!["foo".to_string()],
+            ldflags: vec![],
+            export_map: None,
+            version: None,
+        };
+
+        // Only Mach-O executables load `@rpath/` install names
+        let cmd = toolchain.link_exe_command(&input, Language::C, None);
+        assert_eq!(
+            cmd.args.contains(&"-Wl,-rpath,lib".to_string()),
+            cfg!(target_os = "macos")
+        );
+    }
+
     #[test]
     fn test_msvc_compile_command() {
         let toolchain = MsvcToolchain::new(
@@ -580,6 +774,7 @@ mod tests {
                 ("VERSION".to_string(), Some("1".to_string())),
             ],
             cflags: vec!["/W4".to_string()],
+            visibility: SymbolVisibility::Default,
         };
 
         let cmd = toolchain.compile_command(&input, Language::C, None);
@@ -591,6 +786,31 @@ mod tests {
         assert!(cmd.args.contains(&"/DVERSION=1".to_string()));
     }
 
+    #[test]
+    fn test_msvc_syntax_check_command() {
+        let toolchain = MsvcToolchain::new(
+            PathBuf::from("cl"),
+            PathBuf::from("lib"),
+            PathBuf::from("link"),
+        );
+
+        let input = CompileInput {
+            source: PathBuf::from("src/main.c"),
+            output: PathBuf::from("obj/main.obj"),
+            include_dirs: vec![PathBuf::from("C:/include")],
+            defines: vec![("DEBUG".to_string(), None)],
+            cflags: vec!["/W4".to_string()],
+            visibility: SymbolVisibility::Default,
+        };
+
+        let compile = toolchain.compile_command(&input, Language::C, None);
+        let check = toolchain.syntax_check_command(&input, Language::C, None);
+        assert!(check.args.contains(&"/Zs".to_string()));
+        assert!(!check.args.contains(&"/c".to_string()));
+        assert!(!check.args.iter().any(|a| a.starts_with("/Fo")));
+        assert_eq!(check.args.len(), compile.args.len() - 1);
+    }
+
     #[test]
     fn test_msvc_cxx_compile_command() {
         let toolchain = MsvcToolchain::new(
@@ -605,6 +825,7 @@ mod tests {
             include_dirs: vec![],
             defines: vec![],
             cflags: vec![],
+            visibility: SymbolVisibility::Default,
         };
 
         let cxx_opts = CxxOptions {
@@ -624,6 +845,30 @@ mod tests {
         assert!(cmd.args.contains(&"/MD".to_string()));
     }
 
+    #[test]
+    fn test_msvc_preprocess_command() {
+        let toolchain = MsvcToolchain::new(
+            PathBuf::from("cl"),
+            PathBuf::from("lib"),
+            PathBuf::from("link"),
+        );
+
+        let input = PreprocessInput {
+            source: PathBuf::from("ffi/headers.c"),
+            output: PathBuf::from("ffi/headers.i"),
+            include_dirs: vec![],
+            defines: vec![],
+            cflags: vec![],
+        };
+
+        let cmd = toolchain.preprocess_command(&input, Language::C);
+        assert_eq!(cmd.program, PathBuf::from("cl"));
+        assert!(cmd.args.contains(&"/P".to_string()));
+        assert!(cmd.args.contains(&"/d1PP".to_string()));
+        assert!(cmd.args.iter().any(|a| a.starts_with("/Fi")));
+        assert!(!cmd.args.contains(&"/c".to_string()));
+    }
+
     #[test]
     fn test_msvc_archive_command() {
         let toolchain = MsvcToolchain::new(
@@ -642,4 +887,56 @@ mod tests {
         assert!(cmd.args.contains(&"/nologo".to_string()));
         assert!(cmd.args.iter().any(|a| a.starts_with("/OUT:")));
     }
+
+    #[test]
+    fn test_msvc_link_shared_def_file() {
+        let toolchain = MsvcToolchain::new(
+            PathBuf::from("cl"),
+            PathBuf::from("lib"),
+            PathBuf::from("link"),
+        );
+
+        let input = LinkInput {
+            objects: vec![PathBuf::from("obj/lib.obj")],
+            output: PathBuf::from("lib/foo.dll"),
+            lib_dirs: vec![],
+            libs: vec![],
+            ldflags: vec![],
+            export_map: Some(PathBuf::from("obj/foo.def")),
+            version: None,
+        };
+
+        let cmd = toolchain.link_shared_command(&input, Language::C, None);
+        assert_eq!(cmd.program, PathBuf::from("link"));
+        assert!(cmd.args.contains(&"/DLL".to_string()));
+        assert!(cmd.args.contains(&"/DEF:obj/foo.def".to_string()));
+    }
+
+    #[test]
+    fn test_compiler_family_from_version() {
+        assert_eq!(
+            compiler_family_from_version(
+                "Ubuntu clang version 17.0.6 (9ubuntu1)\nTarget: x86_64-pc-linux-gnu\n"
+            ),
+            Some(ToolchainPlatform::Clang)
+        );
+        assert_eq!(
+            compiler_family_from_version(
+                "Apple clang version 15.0.0 (clang-1500.3.9.4)\nTarget: arm64-apple-darwin23.4.0\n"
+            ),
+            Some(ToolchainPlatform::AppleClang)
+        );
+        assert_eq!(
+            compiler_family_from_version("gcc (GCC) 13.2.1 20230801\n"),
+            Some(ToolchainPlatform::Gcc)
+        );
+        // `cc --version` of GCC does not name it
+        assert_eq!(
+            compiler_family_from_version(
+                "cc (Debian 12.2.0-14) 12.2.0\nCopyright (C) 2022 Free Software Foundation, Inc.\n"
+            ),
+            Some(ToolchainPlatform::Gcc)
+        );
+        assert_eq!(compiler_family_from_version("tcc version 0.9.27\n"), None);
+    }
 }
@@ -2,9 +2,13 @@
 
 use std::path::{Path, PathBuf};
 
+use crate::core::manifest::SymbolVisibility;
 use crate::core::target::Language;
 
-use super::{ArchiveInput, CommandSpec, CompileInput, CxxOptions, LinkInput, Toolchain, ToolchainPlatform};
+use super::{
+    ArchiveInput, CommandSpec, CompileInput, CxxOptions, LinkInput, PreprocessInput, Toolchain,
+    ToolchainPlatform,
+};
 
 /// GCC/Clang toolchain (Unix-like systems).
 #[derive(Debug, Clone)]
@@ -64,23 +68,12 @@ impl GccToolchain {
         // Fallback: append ++ (handles edge cases like "tcc" -> "tcc++")
         PathBuf::from(format!("{}++", cc_str))
     }
-}
 
-impl Toolchain for GccToolchain {
-    fn platform(&self) -> ToolchainPlatform {
-        self.family
-    }
-
-    fn compiler_path(&self) -> &Path {
-        &self.cc
-    }
-
-    fn cxx_compiler_path(&self) -> &Path {
-        &self.cxx
-    }
-
-    fn compile_command(
+    /// Build a compiler invocation in `mode` (`-c` or `-fsyntax-only`) with
+    /// every flag a compile uses, ending with the source file.
+    fn compile_spec(
         &self,
+        mode: &str,
         input: &CompileInput,
         lang: Language,
         cxx_opts: Option<&CxxOptions>,
@@ -92,9 +85,7 @@ impl Toolchain for GccToolchain {
         };
 
         let mut cmd = CommandSpec::new(compiler);
-
-        // Compile only
-        cmd = cmd.arg("-c");
+        cmd = cmd.arg(mode);
 
         // C++ specific flags
         if lang == Language::Cxx {
@@ -136,6 +127,83 @@ impl Toolchain for GccToolchain {
             }
         }
 
+        // Symbol visibility
+        if input.visibility == SymbolVisibility::Hidden {
+            cmd = cmd.arg("-fvisibility=hidden");
+            if lang == Language::Cxx {
+                cmd = cmd.arg("-fvisibility-inlines-hidden");
+            }
+        }
+
+        // Custom flags
+        cmd = cmd.args(input.cflags.iter().cloned());
+
+        // Input
+        cmd = cmd.arg(input.source.display().to_string());
+
+        cmd
+    }
+}
+
+impl Toolchain for GccToolchain {
+    fn platform(&self) -> ToolchainPlatform {
+        self.family
+    }
+
+    fn compiler_path(&self) -> &Path {
+        &self.cc
+    }
+
+    fn cxx_compiler_path(&self) -> &Path {
+        &self.cxx
+    }
+
+    fn compile_command(
+        &self,
+        input: &CompileInput,
+        lang: Language,
+        cxx_opts: Option<&CxxOptions>,
+    ) -> CommandSpec {
+        // Compile only
+        self.compile_spec("-c", input, lang, cxx_opts)
+            .arg("-o")
+            .arg(input.output.display().to_string())
+    }
+
+    fn syntax_check_command(
+        &self,
+        input: &CompileInput,
+        lang: Language,
+        cxx_opts: Option<&CxxOptions>,
+    ) -> CommandSpec {
+        self.compile_spec("-fsyntax-only", input, lang, cxx_opts)
+    }
+
+    fn preprocess_command(&self, input: &PreprocessInput, lang: Language) -> CommandSpec {
+        let compiler = match lang {
+            Language::C => &self.cc,
+            Language::Cxx => &self.cxx,
+        };
+
+        let mut cmd = CommandSpec::new(compiler);
+
+        // Preprocess only, keeping #define directives in the output
+        cmd = cmd.arg("-E");
+        cmd = cmd.arg("-dD");
+
+        // Include directories
+        for dir in &input.include_dirs {
+            cmd = cmd.arg(format!("-I{}", dir.display()));
+        }
+
+        // Defines
+        for (name, value) in &input.defines {
+            match value {
+                Some(v) => cmd = cmd.arg(format!("-D{}={}", name, v)),
+                None => cmd = cmd.arg(format!("-D{}", name)),
+            }
+        }
+
         // Custom flags
         cmd = cmd.args(input.cflags.iter().cloned());
 
@@ -209,6 +277,43 @@ impl Toolchain for GccToolchain {
             cmd = cmd.arg(format!("-l{}", lib));
         }
 
+        // SONAME / install name and version
+        if let Some(version) = &input.version {
+            if version.soname.ends_with(".dylib") {
+                // Consumers find the library through their own run paths
+                cmd = cmd.arg(format!("-Wl,-install_name,@rpath/{}", version.soname));
+                cmd = cmd.arg(format!("-Wl,-current_version,{}", version.current_version));
+                cmd = cmd.arg(format!(
+                    "-Wl,-compatibility_version,{}",
+                    version.compatibility_version
+                ));
+            } else {
+                cmd = cmd.arg(format!("-Wl,-soname,{}", version.soname));
+            }
+        }
+
+        // Reserve an ELF run path that `harbour ffi bundle` rewrites in place
+        if is_elf_output(&input.output) {
+            cmd = cmd.arg("-Wl,-rpath,$ORIGIN");
+        }
+
+        // Dylibs are installed as `@rpath/<name>`, so consumers need run paths
+        if is_macho_dylib(&input.output) {
+            cmd = cmd.args(macho_rpaths(&input.lib_dirs));
+        }
+
+        // Export map (format selected by ExportFormat::for_toolchain)
+        if let Some(map) = &input.export_map {
+            match map.extension().and_then(|ext| ext.to_str()) {
+                Some("exp") => {
+                    cmd = cmd.arg(format!("-Wl,-exported_symbols_list,{}", map.display()));
+                }
+                // MinGW takes module-definition files as a regular input
+                Some("def") => cmd = cmd.arg(map.display().to_string()),
+                _ => cmd = cmd.arg(format!("-Wl,--version-script={}", map.display())),
+            }
+        }
+
         // Custom flags
         cmd = cmd.args(input.ldflags.iter().cloned());
 
@@ -259,6 +364,11 @@ impl Toolchain for GccToolchain {
             cmd = cmd.arg(format!("-l{}", lib));
         }
 
+        // Dylibs are installed as `@rpath/<name>`, so consumers need run paths
+        if self.shared_lib_extension() == "dylib" {
+            cmd = cmd.args(macho_rpaths(&input.lib_dirs));
+        }
+
         // Custom flags
         cmd = cmd.args(input.ldflags.iter().cloned());
 
@@ -293,3 +403,26 @@ impl Toolchain for GccToolchain {
         "lib"
     }
 }
+
+/// Whether a shared library output is an ELF file rather than a Mach-O
+/// dylib or a MinGW DLL.
+fn is_elf_output(output: &Path) -> bool {
+    let name = output.file_name().unwrap_or_default().to_string_lossy();
+    !(name.ends_with(".dylib") || name.ends_with(".dll"))
+}
+
+/// Whether a shared library output is a Mach-O dylib.
+fn is_macho_dylib(output: &Path) -> bool {
+    output
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(".dylib"))
+}
+
+/// Run paths under which a Mach-O output finds the dylibs it links
+/// against, in the directories it links them from.
+fn macho_rpaths(lib_dirs: &[PathBuf]) -> Vec<String> {
+    lib_dirs
+        .iter()
+        .map(|dir| format!("-Wl,-rpath,{}", dir.display()))
+        .collect()
+}
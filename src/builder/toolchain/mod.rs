@@ -10,7 +10,7 @@
 
 use std::path::{Path, PathBuf};
 
-use crate::core::manifest::{CppRuntime, MsvcRuntime};
+use crate::core::manifest::{CppRuntime, MsvcRuntime, SymbolVisibility};
 use crate::core::target::CppStandard;
 
 mod detect;
@@ -114,6 +114,23 @@ pub struct CompileInput {
     pub defines: Vec<(String, Option<String>)>,
     /// Additional compiler flags
     pub cflags: Vec<String>,
+    /// Default symbol visibility
+    pub visibility: SymbolVisibility,
+}
+
+/// Input for a preprocess-only step.
+#[derive(Debug, Clone)]
+pub struct PreprocessInput {
+    /// Source file to preprocess
+    pub source: PathBuf,
+    /// Output file for the preprocessed source
+    pub output: PathBuf,
+    /// Include directories
+    pub include_dirs: Vec<PathBuf>,
+    /// Preprocessor defines (name, optional value)
+    pub defines: Vec<(String, Option<String>)>,
+    /// Additional compiler flags
+    pub cflags: Vec<String>,
 }
 
 /// Input for an archive step (creating static library).
@@ -138,6 +155,22 @@ pub struct LinkInput {
     pub libs: Vec<String>,
     /// Additional linker flags
     pub ldflags: Vec<String>,
+    /// Export map restricting exported symbols (shared libraries only),
+    /// already rendered in the toolchain's format
+    pub export_map: Option<PathBuf>,
+    /// Version recorded in the library (shared libraries only)
+    pub version: Option<LinkVersion>,
+}
+
+/// Version information recorded in a shared library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkVersion {
+    /// SONAME (ELF) or install name (Mach-O) file name
+    pub soname: String,
+    /// Full library version (Mach-O `current_version`)
+    pub current_version: String,
+    /// Oldest compatible version (Mach-O `compatibility_version`)
+    pub compatibility_version: String,
 }
 
 /// The platform/family of a toolchain.
@@ -191,6 +224,23 @@ pub trait Toolchain: Send + Sync {
         cxx_opts: Option<&CxxOptions>,
     ) -> CommandSpec;
 
+    /// Generate a syntax-only command.
+    ///
+    /// Uses the same flags as [`Toolchain::compile_command`], but only parses
+    /// and type-checks the source: no object file is written.
+    fn syntax_check_command(
+        &self,
+        input: &CompileInput,
+        lang: Language,
+        cxx_opts: Option<&CxxOptions>,
+    ) -> CommandSpec;
+
+    /// Generate a preprocess-only command.
+    ///
+    /// The output keeps line markers and the `#define`s seen along the way,
+    /// so callers can tell which file each declaration and macro came from.
+    fn preprocess_command(&self, input: &PreprocessInput, lang: Language) -> CommandSpec;
+
     /// Generate an archive command (create static library).
     /// Note: Static libraries always use ar/lib.exe, never C++ driver.
     fn archive_command(&self, input: &ArchiveInput) -> CommandSpec;
@@ -296,6 +346,19 @@ impl<T: Toolchain> Toolchain for EnvWrapper<T> {
         self.inject_env(self.inner.compile_command(input, lang, cxx_opts))
     }
 
+    fn syntax_check_command(
+        &self,
+        input: &CompileInput,
+        lang: Language,
+        cxx_opts: Option<&CxxOptions>,
+    ) -> CommandSpec {
+        self.inject_env(self.inner.syntax_check_command(input, lang, cxx_opts))
+    }
+
+    fn preprocess_command(&self, input: &PreprocessInput, lang: Language) -> CommandSpec {
+        self.inject_env(self.inner.preprocess_command(input, lang))
+    }
+
     fn archive_command(&self, input: &ArchiveInput) -> CommandSpec {
         self.inject_env(self.inner.archive_command(input))
     }
@@ -4,7 +4,10 @@ use std::path::{Path, PathBuf};
 
 use crate::core::target::Language;
 
-use super::{ArchiveInput, CommandSpec, CompileInput, CxxOptions, LinkInput, Toolchain, ToolchainPlatform};
+use super::{
+    ArchiveInput, CommandSpec, CompileInput, CxxOptions, LinkInput, PreprocessInput, Toolchain,
+    ToolchainPlatform,
+};
 
 /// MSVC toolchain (Windows).
 #[derive(Debug, Clone)]
@@ -22,33 +25,20 @@ impl MsvcToolchain {
     pub fn new(cl: PathBuf, lib: PathBuf, link: PathBuf) -> Self {
         MsvcToolchain { cl, lib, link }
     }
-}
-
-impl Toolchain for MsvcToolchain {
-    fn platform(&self) -> ToolchainPlatform {
-        ToolchainPlatform::Msvc
-    }
-
-    fn compiler_path(&self) -> &Path {
-        &self.cl
-    }
 
-    fn cxx_compiler_path(&self) -> &Path {
-        // MSVC uses the same cl.exe for both C and C++
-        &self.cl
-    }
-
-    fn compile_command(
+    /// Build a `cl` invocation in `mode` (`/c` or `/Zs`) with every flag a
+    /// compile uses, ending with the source file.
+    fn compile_spec(
         &self,
+        mode: &str,
         input: &CompileInput,
         lang: Language,
         cxx_opts: Option<&CxxOptions>,
     ) -> CommandSpec {
         let mut cmd = CommandSpec::new(&self.cl);
 
-        // Quiet logo, compile only
         cmd = cmd.arg("/nologo");
-        cmd = cmd.arg("/c");
+        cmd = cmd.arg(mode);
 
         // C++ specific flags
         if lang == Language::Cxx {
@@ -102,8 +92,77 @@ impl Toolchain for MsvcToolchain {
         // Input
         cmd = cmd.arg(input.source.display().to_string());
 
+        cmd
+    }
+}
+
+impl Toolchain for MsvcToolchain {
+    fn platform(&self) -> ToolchainPlatform {
+        ToolchainPlatform::Msvc
+    }
+
+    fn compiler_path(&self) -> &Path {
+        &self.cl
+    }
+
+    fn cxx_compiler_path(&self) -> &Path {
+        // MSVC uses the same cl.exe for both C and C++
+        &self.cl
+    }
+
+    fn compile_command(
+        &self,
+        input: &CompileInput,
+        lang: Language,
+        cxx_opts: Option<&CxxOptions>,
+    ) -> CommandSpec {
+        // Quiet logo, compile only
+        self.compile_spec("/c", input, lang, cxx_opts)
+            .arg(format!("/Fo{}", input.output.display()))
+    }
+
+    fn syntax_check_command(
+        &self,
+        input: &CompileInput,
+        lang: Language,
+        cxx_opts: Option<&CxxOptions>,
+    ) -> CommandSpec {
+        self.compile_spec("/Zs", input, lang, cxx_opts)
+    }
+
+    fn preprocess_command(&self, input: &PreprocessInput, lang: Language) -> CommandSpec {
+        let mut cmd = CommandSpec::new(&self.cl);
+
+        // Preprocess to a file; /d1PP keeps #define directives in the output
+        cmd = cmd.arg("/nologo");
+        cmd = cmd.arg("/P");
+        cmd = cmd.arg("/d1PP");
+
+        if lang == Language::Cxx {
+            cmd = cmd.arg("/TP");
+        }
+
+        // Include directories
+        for dir in &input.include_dirs {
+            cmd = cmd.arg(format!("/I{}", dir.display()));
+        }
+
+        // Defines
+        for (name, value) in &input.defines {
+            match value {
+                Some(v) => cmd = cmd.arg(format!("/D{}={}", name, v)),
+                None => cmd = cmd.arg(format!("/D{}", name)),
+            }
+        }
+
+        // Custom flags
+        cmd = cmd.args(input.cflags.iter().cloned());
+
+        // Input
+        cmd = cmd.arg(input.source.display().to_string());
+
         // Output
-        cmd = cmd.arg(format!("/Fo{}", input.output.display()));
+        cmd = cmd.arg(format!("/Fi{}", input.output.display()));
 
         cmd
     }
@@ -150,6 +209,11 @@ impl Toolchain for MsvcToolchain {
             cmd = cmd.arg(format!("{}.lib", lib));
         }
 
+        // Module-definition file
+        if let Some(map) = &input.export_map {
+            cmd = cmd.arg(format!("/DEF:{}", map.display()));
+        }
+
         // Custom flags
         cmd = cmd.args(input.ldflags.iter().cloned());
 
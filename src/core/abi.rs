@@ -122,6 +122,8 @@ pub struct AbiIdentity {
     pub public_defines: Vec<String>,
     /// ABI toggles from surface
     pub toggles: Vec<String>,
+    /// Sanitizers the whole build graph is instrumented with
+    pub sanitizers: Vec<String>,
 }
 
 impl AbiIdentity {
@@ -135,6 +137,7 @@ impl AbiIdentity {
             visibility: "default".to_string(),
             public_defines: Vec::new(),
             toggles: Vec::new(),
+            sanitizers: Vec::new(),
         }
     }
 
@@ -150,6 +153,18 @@ impl AbiIdentity {
         self
     }
 
+    /// Set the sanitizers of the build profile.
+    ///
+    /// Instrumented and uninstrumented objects must not be mixed, so a
+    /// sanitizer change invalidates every artifact in the graph.
+    pub fn with_sanitizers(mut self, sanitizers: &[String]) -> Self {
+        let mut sanitizers = sanitizers.to_vec();
+        sanitizers.sort();
+        sanitizers.dedup();
+        self.sanitizers = sanitizers;
+        self
+    }
+
     /// Add public defines from a resolved surface.
     pub fn with_surface(mut self, surface: &ResolvedSurface) -> Self {
         // Extract define names that affect ABI
@@ -191,6 +206,10 @@ impl AbiIdentity {
             fp.update_str(toggle);
         }
 
+        for sanitizer in &self.sanitizers {
+            fp.update_str(sanitizer);
+        }
+
         fp.finish_short()
     }
 
@@ -203,6 +222,7 @@ impl AbiIdentity {
             && self.kind == other.kind
             && self.pic == other.pic
             && self.public_defines == other.public_defines
+            && self.sanitizers == other.sanitizers
     }
 }
 
@@ -240,6 +260,14 @@ pub fn needs_rebuild(current: &AbiIdentity, cached: &AbiIdentity) -> Option<Stri
         return Some("public defines changed".to_string());
     }
 
+    if current.sanitizers != cached.sanitizers {
+        return Some(format!(
+            "sanitizers changed: [{}] -> [{}]",
+            cached.sanitizers.join(", "),
+            current.sanitizers.join(", ")
+        ));
+    }
+
     None
 }
 
@@ -285,4 +313,22 @@ mod tests {
 
         assert!(!abi1.is_compatible(&abi2));
     }
+
+    #[test]
+    fn test_abi_sanitizers_force_rebuild() {
+        let target = TargetTriple::new("x86_64", "unknown", "linux", Some("gnu"));
+        let gcc = CompilerIdentity::new("gcc", "13.0");
+
+        let plain = AbiIdentity::new(target.clone(), gcc.clone(), TargetKind::StaticLib);
+        let asan = AbiIdentity::new(target, gcc, TargetKind::StaticLib)
+            .with_sanitizers(&["undefined".to_string(), "address".to_string()]);
+
+        assert_eq!(asan.sanitizers, vec!["address", "undefined"]);
+        assert_ne!(plain.fingerprint(), asan.fingerprint());
+        assert!(!plain.is_compatible(&asan));
+        assert_eq!(
+            needs_rebuild(&asan, &plain).as_deref(),
+            Some("sanitizers changed: [] -> [address, undefined]")
+        );
+    }
 }
@@ -14,6 +14,16 @@ use crate::core::source_id::{GitReference, SourceId};
 use crate::util::context::DEFAULT_REGISTRY_URL;
 use crate::util::InternedString;
 
+/// Which section of the manifest a dependency comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DepKind {
+    /// `[dependencies]`: visible to every target and propagated to dependents
+    #[default]
+    Normal,
+    /// `[dev-dependencies]`: only visible to the package's own test targets
+    Development,
+}
+
 /// A dependency specification.
 #[derive(Debug, Clone)]
 pub struct Dependency {
@@ -34,6 +44,9 @@ pub struct Dependency {
 
     /// Whether default features are enabled
     default_features: bool,
+
+    /// Normal or development dependency
+    kind: DepKind,
 }
 
 impl Dependency {
@@ -46,6 +59,7 @@ impl Dependency {
             optional: false,
             features: Vec::new(),
             default_features: true,
+            kind: DepKind::Normal,
         }
     }
 
@@ -55,6 +69,12 @@ impl Dependency {
         self
     }
 
+    /// Point this dependency at a different source.
+    pub fn with_source_id(mut self, source_id: SourceId) -> Self {
+        self.source_id = source_id;
+        self
+    }
+
     /// Set whether this dependency is optional.
     pub fn optional(mut self, optional: bool) -> Self {
         self.optional = optional;
@@ -73,6 +93,12 @@ impl Dependency {
         self
     }
 
+    /// Set the dependency kind.
+    pub fn with_kind(mut self, kind: DepKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
     /// Get the package name.
     pub fn name(&self) -> InternedString {
         self.name
@@ -103,6 +129,16 @@ impl Dependency {
         self.default_features
     }
 
+    /// Get the dependency kind.
+    pub fn kind(&self) -> DepKind {
+        self.kind
+    }
+
+    /// Check if this is a dev-dependency.
+    pub fn is_dev(&self) -> bool {
+        self.kind == DepKind::Development
+    }
+
     /// Check if a version matches this dependency's requirement.
     pub fn matches_version(&self, version: &semver::Version) -> bool {
         self.version_req.matches(version)
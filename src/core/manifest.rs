@@ -3,13 +3,14 @@
 //! The manifest is the central configuration file for a Harbour package.
 //! Supports both `Harbour.toml` (canonical) and `Harbor.toml` (alias).
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
+use crate::builder::shim::SanitizerKind;
 use crate::core::dependency::DependencySpec;
 use crate::core::surface::{
     AbiToggles, CompileRequirements, CompileSurface, ConditionalSurface, LinkRequirements,
@@ -143,6 +144,9 @@ pub struct Manifest {
     /// Top-level dependencies
     pub dependencies: HashMap<String, DependencySpec>,
 
+    /// Dependencies only visible to this package's test targets
+    pub dev_dependencies: HashMap<String, DependencySpec>,
+
     /// Build targets
     pub targets: Vec<Target>,
 
@@ -197,6 +201,15 @@ pub struct PackageMetadata {
     /// Categories
     #[serde(default)]
     pub categories: Vec<String>,
+
+    /// Files to include when packaging (globs, relative to the package root).
+    /// When empty, every file not excluded is packaged.
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Files to exclude when packaging (globs, relative to the package root)
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 impl PackageMetadata {
@@ -228,6 +241,15 @@ pub struct Profile {
     #[serde(default)]
     pub sanitizers: Vec<String>,
 
+    /// Runtime options per sanitizer, e.g. `address = "detect_leaks=1"`,
+    /// passed through `ASAN_OPTIONS` and friends by `harbour test` and `run`
+    #[serde(default, rename = "sanitizer-options")]
+    pub sanitizer_options: BTreeMap<String, String>,
+
+    /// Sanitizer suppression files, relative to the package root
+    #[serde(default)]
+    pub suppressions: Option<Suppressions>,
+
     /// Additional compiler flags
     #[serde(default)]
     pub cflags: Vec<String>,
@@ -237,6 +259,94 @@ pub struct Profile {
     pub ldflags: Vec<String>,
 }
 
+impl Profile {
+    /// Parse the enabled sanitizers.
+    ///
+    /// Fails on unknown names and on sanitizers that cannot instrument the
+    /// same build, such as `address` and `thread`.
+    pub fn sanitizer_kinds(&self) -> Result<Vec<SanitizerKind>> {
+        let mut kinds: Vec<SanitizerKind> = Vec::new();
+        for name in &self.sanitizers {
+            let kind: SanitizerKind = name.parse()?;
+            if let Some(other) = kinds.iter().find(|k| k.conflicts_with(kind)) {
+                anyhow::bail!(
+                    "sanitizers `{}` and `{}` cannot be enabled together\n\
+                     hint: use a separate profile for each",
+                    other,
+                    kind
+                );
+            }
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        Ok(kinds)
+    }
+
+    /// Enable `sanitizer` in addition to the profile's own.
+    pub fn with_sanitizer(mut self, sanitizer: SanitizerKind) -> Self {
+        if !self.sanitizers.iter().any(|s| s == sanitizer.as_str()) {
+            self.sanitizers.push(sanitizer.as_str().to_string());
+        }
+        self
+    }
+
+    /// Suppression file for each sanitizer that has one.
+    ///
+    /// A single file is only accepted with a single sanitizer enabled;
+    /// suppression formats differ, so several runtimes need one file each.
+    pub fn suppression_files(&self) -> Result<BTreeMap<SanitizerKind, PathBuf>> {
+        let mut files = BTreeMap::new();
+        match &self.suppressions {
+            None => {}
+            Some(Suppressions::File(path)) => match self.sanitizer_kinds()?.as_slice() {
+                [] => {}
+                [kind] => {
+                    files.insert(*kind, path.clone());
+                }
+                kinds => anyhow::bail!(
+                    "a single suppressions file is ambiguous with sanitizers [{}]\n\
+                     hint: give one file per sanitizer, e.g. suppressions = {{ {} = \"{}\" }}",
+                    kinds
+                        .iter()
+                        .map(|k| k.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    kinds[0].as_str(),
+                    path.display()
+                ),
+            },
+            Some(Suppressions::PerSanitizer(paths)) => {
+                for (name, path) in paths {
+                    files.insert(name.parse()?, path.clone());
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Check sanitizer names used as keys of `sanitizer-options` and
+    /// `suppressions`, and that the enabled sanitizers can be combined.
+    fn validate(&self) -> Result<()> {
+        self.sanitizer_kinds()?;
+        self.suppression_files()?;
+        for key in self.sanitizer_options.keys() {
+            key.parse::<SanitizerKind>()?;
+        }
+        Ok(())
+    }
+}
+
+/// Sanitizer suppression files of a profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Suppressions {
+    /// One file, for the only enabled sanitizer
+    File(PathBuf),
+    /// A file per sanitizer name
+    PerSanitizer(BTreeMap<String, PathBuf>),
+}
+
 /// Raw backend configuration from TOML (strings, before validation).
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -287,6 +397,204 @@ impl Default for BackendConfig {
     }
 }
 
+/// Default symbol visibility for a target's objects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolVisibility {
+    /// Every non-static symbol is exported (compiler default)
+    #[default]
+    Default,
+    /// Symbols are hidden unless marked with the target's export macro
+    Hidden,
+}
+
+/// Export map configuration for shared library targets.
+///
+/// ```toml
+/// [targets.mylib.exports]
+/// symbols = ["mylib_init", "mylib_*"]
+/// version = "MYLIB_1.0"
+/// ```
+///
+/// When `symbols` is empty, the target's `[targets.X.ffi] include_functions`
+/// filters are used instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportsConfig {
+    /// Symbols to export (glob patterns allowed on ELF and Mach-O)
+    #[serde(default)]
+    pub symbols: Vec<String>,
+
+    /// Version node name for ELF version scripts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Test framework a test executable is written with.
+///
+/// Harbour asks the executable for a machine-readable report and turns it
+/// into individual test cases instead of judging it by exit code alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    /// GoogleTest, reporting with `--gtest_output=json`
+    Gtest,
+    /// Catch2, reporting with `--reporter junit`
+    Catch2,
+    /// doctest, reporting with `--reporters=junit`
+    Doctest,
+    /// cmocka, reporting through `CMOCKA_MESSAGE_OUTPUT=xml`
+    Cmocka,
+    /// Any executable printing the Test Anything Protocol on stdout
+    Tap,
+}
+
+impl TestFramework {
+    /// Get the framework name as used in the manifest.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestFramework::Gtest => "gtest",
+            TestFramework::Catch2 => "catch2",
+            TestFramework::Doctest => "doctest",
+            TestFramework::Cmocka => "cmocka",
+            TestFramework::Tap => "tap",
+        }
+    }
+}
+
+/// Test configuration for executable targets.
+///
+/// ```toml
+/// [targets.unit_test]
+/// kind = "test"
+///
+/// [targets.unit_test.test]
+/// timeout = 30
+/// args = ["--verbose"]
+/// working_dir = "tests/data"
+/// env = { FIXTURES = "tests/fixtures" }
+/// framework = "gtest"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestConfig {
+    /// Timeout in seconds for one run of the test executable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// Arguments always passed to the test executable
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Working directory, relative to the package root (default: package root)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+
+    /// Extra environment variables
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Framework whose report is parsed into test cases (default: exit code only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub framework: Option<TestFramework>,
+}
+
+/// `harbour run` configuration for executable targets.
+///
+/// ```toml
+/// [targets.server.run]
+/// args = ["--port", "8080"]
+/// working_dir = "data"
+/// env = { LOG_LEVEL = "debug" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunConfig {
+    /// Arguments passed before any given on the command line
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Working directory, relative to the package root (default: current directory)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+
+    /// Extra environment variables
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// libFuzzer configuration for fuzz targets.
+///
+/// ```toml
+/// [targets.parse_fuzz]
+/// kind = "fuzz"
+/// sources = ["fuzz/parse_fuzz.c"]
+///
+/// [targets.parse_fuzz.fuzz]
+/// dict = "fuzz/parse.dict"
+/// max_len = 4096
+/// args = ["-use_value_profile=1"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FuzzConfig {
+    /// libFuzzer flags passed before any given on the command line
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Dictionary of input tokens, relative to the package root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dict: Option<PathBuf>,
+
+    /// Maximum length of generated inputs in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<u64>,
+}
+
+/// Output format of a benchmark executable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BenchFormat {
+    /// Lines of the form `name ... 1234 ns/iter (+/- 56)`
+    #[default]
+    NsIter,
+    /// Google Benchmark, reporting with `--benchmark_format=json`
+    Gbench,
+}
+
+impl BenchFormat {
+    /// Get the format name as used in the manifest.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BenchFormat::NsIter => "ns-iter",
+            BenchFormat::Gbench => "gbench",
+        }
+    }
+}
+
+/// Benchmark configuration for bench targets.
+///
+/// ```toml
+/// [targets.parse_bench]
+/// kind = "bench"
+/// sources = ["bench/parse_bench.cpp"]
+///
+/// [targets.parse_bench.bench]
+/// format = "gbench"
+/// args = ["--benchmark_min_time=0.5"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchConfig {
+    /// How results are read from the executable's output
+    #[serde(default)]
+    pub format: BenchFormat,
+
+    /// Arguments passed before any given on the command line
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
 /// Raw manifest as deserialized from TOML.
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -300,6 +608,9 @@ struct RawManifest {
     #[serde(default)]
     dependencies: HashMap<String, DependencySpec>,
 
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: HashMap<String, DependencySpec>,
+
     #[serde(default)]
     targets: HashMap<String, RawTarget>,
 
@@ -314,7 +625,7 @@ struct RawManifest {
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 struct RawTarget {
-    kind: Option<TargetKind>,
+    kind: Option<RawTargetKind>,
 
     #[serde(default)]
     sources: Vec<String>,
@@ -355,6 +666,77 @@ struct RawTarget {
     /// FFI binding generation configuration
     #[serde(default)]
     ffi: Option<FfiConfig>,
+
+    /// Default symbol visibility
+    #[serde(default)]
+    visibility: Option<SymbolVisibility>,
+
+    /// Export map for shared libraries
+    #[serde(default)]
+    exports: Option<ExportsConfig>,
+
+    /// ABI version in the SONAME (defaults to the package's major version)
+    #[serde(default)]
+    soversion: Option<String>,
+
+    /// Marks an executable as a test: `test = true` or a [targets.X.test] table
+    #[serde(default)]
+    test: Option<RawTestSpec>,
+
+    /// Shorthand for [targets.X.test] framework; implies `test = true`
+    #[serde(default, rename = "test-framework")]
+    test_framework: Option<TestFramework>,
+
+    /// `harbour run` defaults: [targets.X.run]
+    #[serde(default)]
+    run: Option<RunConfig>,
+
+    /// libFuzzer settings for `kind = "fuzz"`: [targets.X.fuzz]
+    #[serde(default)]
+    fuzz: Option<FuzzConfig>,
+
+    /// Benchmark settings for `kind = "bench"`: [targets.X.bench]
+    #[serde(default)]
+    bench: Option<BenchConfig>,
+}
+
+/// Target kind as written in the manifest.
+///
+/// `kind = "test"` is an executable that `harbour test` runs,
+/// `kind = "fuzz"` one that `harbour fuzz run` links against libFuzzer, and
+/// `kind = "bench"` one that `harbour bench` times.
+#[derive(Debug)]
+enum RawTargetKind {
+    Test,
+    Fuzz,
+    Bench,
+    Kind(TargetKind),
+}
+
+impl<'de> Deserialize<'de> for RawTargetKind {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::IntoDeserializer;
+
+        let kind = String::deserialize(deserializer)?;
+        match kind.as_str() {
+            "test" => return Ok(RawTargetKind::Test),
+            "fuzz" => return Ok(RawTargetKind::Fuzz),
+            "bench" => return Ok(RawTargetKind::Bench),
+            _ => {}
+        }
+        TargetKind::deserialize(kind.into_deserializer()).map(RawTargetKind::Kind)
+    }
+}
+
+/// Test designation: `test = true` or a [targets.X.test] table.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawTestSpec {
+    Enabled(bool),
+    Config(TestConfig),
 }
 
 /// Shorthand surface format for [targets.X.public] and [targets.X.private].
@@ -633,6 +1015,24 @@ impl Manifest {
             );
         }
 
+        if let Some(name) = raw
+            .dev_dependencies
+            .keys()
+            .find(|name| raw.dependencies.contains_key(*name))
+        {
+            anyhow::bail!(
+                "dependency `{}` is listed in both [dependencies] and [dev-dependencies]\n\
+                 hint: keep it in [dependencies]; test targets see normal dependencies too",
+                name
+            );
+        }
+
+        for (name, profile) in &raw.profile {
+            profile
+                .validate()
+                .with_context(|| format!("invalid [profile.{}]", name))?;
+        }
+
         // Convert raw targets to Target structs
         let mut targets = Vec::new();
         for (name, raw_target) in raw.targets {
@@ -651,6 +1051,7 @@ impl Manifest {
             package: raw.package,
             workspace: raw.workspace,
             dependencies: raw.dependencies,
+            dev_dependencies: raw.dev_dependencies,
             targets,
             profiles: raw.profile,
             build: raw.build,
@@ -669,7 +1070,13 @@ impl Manifest {
     }
 
     fn convert_target(name: String, raw: RawTarget) -> Result<Target> {
-        let kind = raw.kind.unwrap_or(TargetKind::StaticLib);
+        let (kind, kind_is_test, kind_is_fuzz, kind_is_bench) = match raw.kind {
+            Some(RawTargetKind::Test) => (TargetKind::Exe, true, false, false),
+            Some(RawTargetKind::Fuzz) => (TargetKind::Exe, false, true, false),
+            Some(RawTargetKind::Bench) => (TargetKind::Exe, false, false, true),
+            Some(RawTargetKind::Kind(kind)) => (kind, false, false, false),
+            None => (TargetKind::StaticLib, false, false, false),
+        };
 
         // Build surface from either nested format or shorthand (or both merged)
         let mut surface = if let Some(raw_surface) = raw.surface {
@@ -809,6 +1216,106 @@ impl Manifest {
             raw.sources
         };
 
+        if raw.exports.is_some() && kind != TargetKind::SharedLib {
+            anyhow::bail!(
+                "target `{}`: [targets.{}.exports] is only supported for sharedlib targets",
+                name,
+                name
+            );
+        }
+
+        if let Some(ref soversion) = raw.soversion {
+            if kind != TargetKind::SharedLib {
+                anyhow::bail!(
+                    "target `{}`: `soversion` is only supported for sharedlib targets",
+                    name
+                );
+            }
+            crate::builder::soname::validate_soversion(soversion)
+                .with_context(|| format!("target `{}`: invalid soversion", name))?;
+        }
+
+        let mut test = match raw.test {
+            Some(RawTestSpec::Config(config)) => Some(config),
+            Some(RawTestSpec::Enabled(true)) => Some(TestConfig::default()),
+            Some(RawTestSpec::Enabled(false)) => None,
+            None if kind_is_test || raw.test_framework.is_some() => Some(TestConfig::default()),
+            None => None,
+        };
+        if let Some(framework) = raw.test_framework {
+            match test.as_mut() {
+                Some(config) => match config.framework {
+                    Some(other) if other != framework => anyhow::bail!(
+                        "target `{}`: `test-framework = \"{}\"` conflicts with \
+                         [targets.{}.test] framework = \"{}\"",
+                        name,
+                        framework.as_str(),
+                        name,
+                        other.as_str()
+                    ),
+                    _ => config.framework = Some(framework),
+                },
+                None => anyhow::bail!(
+                    "target `{}`: `test-framework` is set but `test = false`",
+                    name
+                ),
+            }
+        }
+        if raw.run.is_some() && kind != TargetKind::Exe {
+            anyhow::bail!(
+                "target `{}`: [targets.{}.run] is only allowed on exe targets",
+                name,
+                name
+            );
+        }
+        if test.is_some() && kind != TargetKind::Exe {
+            anyhow::bail!(
+                "target `{}`: only exe targets can be tests\n\
+                 hint: use `kind = \"test\"` or `kind = \"exe\"` with `test = true`",
+                name
+            );
+        }
+
+        let fuzz = match raw.fuzz {
+            Some(config) if kind_is_fuzz => Some(config),
+            Some(_) => anyhow::bail!(
+                "target `{}`: [targets.{}.fuzz] is only allowed on fuzz targets\n\
+                 hint: use `kind = \"fuzz\"`",
+                name,
+                name
+            ),
+            None if kind_is_fuzz => Some(FuzzConfig::default()),
+            None => None,
+        };
+        if fuzz.is_some() && (test.is_some() || raw.run.is_some()) {
+            anyhow::bail!(
+                "target `{}`: fuzz targets cannot be tests or have [targets.{}.run]\n\
+                 hint: fuzz targets are run with `harbour fuzz run {}`",
+                name,
+                name,
+                name
+            );
+        }
+
+        let bench = match raw.bench {
+            Some(config) if kind_is_bench => Some(config),
+            Some(_) => anyhow::bail!(
+                "target `{}`: [targets.{}.bench] is only allowed on bench targets\n\
+                 hint: use `kind = \"bench\"`",
+                name,
+                name
+            ),
+            None if kind_is_bench => Some(BenchConfig::default()),
+            None => None,
+        };
+        if bench.is_some() && test.is_some() {
+            anyhow::bail!(
+                "target `{}`: bench targets cannot be tests\n\
+                 hint: bench targets are run with `harbour bench`",
+                name
+            );
+        }
+
         let target = Target {
             name: InternedString::new(name),
             kind,
@@ -822,6 +1329,13 @@ impl Manifest {
             cpp_std: raw.cpp_std,
             backend,
             ffi: raw.ffi,
+            visibility: raw.visibility.unwrap_or_default(),
+            exports: raw.exports,
+            soversion: raw.soversion,
+            test,
+            run: raw.run,
+            fuzz,
+            bench,
         };
 
         // Validate target configuration
@@ -975,6 +1489,12 @@ fn merge_profile(base: &mut Profile, custom: &Profile) {
     if !custom.sanitizers.is_empty() {
         base.sanitizers = custom.sanitizers.clone();
     }
+    if !custom.sanitizer_options.is_empty() {
+        base.sanitizer_options = custom.sanitizer_options.clone();
+    }
+    if custom.suppressions.is_some() {
+        base.suppressions = custom.suppressions.clone();
+    }
     if !custom.cflags.is_empty() {
         base.cflags = custom.cflags.clone();
     }
@@ -1296,6 +1816,38 @@ sources = ["src/**/*.c"]
         assert_eq!(manifest.dependencies.len(), 2);
     }
 
+    #[test]
+    fn test_parse_manifest_with_dev_deps() {
+        let content = r#"
+[package]
+name = "mylib"
+version = "1.0.0"
+
+[dependencies]
+zlib = "1.3"
+
+[dev-dependencies]
+unity = "2.5"
+
+[targets.mylib]
+kind = "staticlib"
+sources = ["src/**/*.c"]
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        assert_eq!(manifest.dependencies.len(), 1);
+        assert_eq!(manifest.dev_dependencies.len(), 1);
+        assert!(manifest.dev_dependencies.contains_key("unity"));
+
+        let duplicate = content.replace("unity = \"2.5\"", "zlib = \"1.3\"");
+        let err = Manifest::parse(&duplicate, &path).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("listed in both [dependencies] and [dev-dependencies]"));
+    }
+
     #[test]
     fn test_generate_lib_manifest() {
         let manifest = generate_lib_manifest("mylib");
@@ -1545,4 +2097,359 @@ sources = ["lib/**/*.c"]
 
         assert_eq!(target.sources, vec!["lib/**/*.c"]);
     }
+
+    #[test]
+    fn test_visibility_and_exports() {
+        let content = r#"
+[package]
+name = "mylib"
+version = "1.0.0"
+
+[targets.mylib]
+kind = "sharedlib"
+visibility = "hidden"
+
+[targets.mylib.exports]
+symbols = ["mylib_init", "mylib_*"]
+version = "MYLIB_1.0"
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        let target = &manifest.targets[0];
+
+        assert_eq!(target.visibility, SymbolVisibility::Hidden);
+        let exports = target.exports.as_ref().unwrap();
+        assert_eq!(exports.symbols, vec!["mylib_init", "mylib_*"]);
+        assert_eq!(exports.version.as_deref(), Some("MYLIB_1.0"));
+    }
+
+    #[test]
+    fn test_exports_rejected_for_staticlib() {
+        let content = r#"
+[package]
+name = "mylib"
+version = "1.0.0"
+
+[targets.mylib]
+kind = "staticlib"
+
+[targets.mylib.exports]
+symbols = ["mylib_init"]
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let err = Manifest::parse(content, &path).unwrap_err();
+        assert!(err.to_string().contains("only supported for sharedlib"));
+    }
+
+    #[test]
+    fn test_soversion() {
+        let content = r#"
+[package]
+name = "mylib"
+version = "1.2.3"
+
+[targets.mylib]
+kind = "sharedlib"
+soversion = "2"
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        assert_eq!(manifest.targets[0].soversion.as_deref(), Some("2"));
+
+        let invalid = content.replace("soversion = \"2\"", "soversion = \"v2\"");
+        assert!(Manifest::parse(&invalid, &path).is_err());
+
+        let staticlib = content.replace("sharedlib", "staticlib");
+        let err = Manifest::parse(&staticlib, &path).unwrap_err();
+        assert!(err.to_string().contains("only supported for sharedlib"));
+    }
+
+    #[test]
+    fn test_test_targets() {
+        let content = r#"
+[package]
+name = "mylib"
+version = "1.0.0"
+
+[targets.mylib]
+kind = "staticlib"
+
+[targets.unit]
+kind = "test"
+
+[targets.smoke]
+kind = "exe"
+test = true
+
+[targets.integration]
+kind = "exe"
+
+[targets.integration.test]
+timeout = 30
+args = ["--verbose"]
+working_dir = "tests/data"
+env = { FIXTURES = "tests/fixtures" }
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        let target = |name: &str| {
+            manifest
+                .targets
+                .iter()
+                .find(|t| t.name.as_str() == name)
+                .unwrap()
+        };
+
+        assert!(target("mylib").test.is_none());
+        assert_eq!(target("unit").kind, TargetKind::Exe);
+        assert_eq!(target("unit").test, Some(TestConfig::default()));
+        assert_eq!(target("smoke").test, Some(TestConfig::default()));
+
+        let config = target("integration").test.clone().unwrap();
+        assert_eq!(config.timeout, Some(30));
+        assert_eq!(config.args, vec!["--verbose"]);
+        assert_eq!(config.working_dir, Some(PathBuf::from("tests/data")));
+        assert_eq!(config.env["FIXTURES"], "tests/fixtures");
+
+        let staticlib =
+            content.replace("kind = \"staticlib\"", "kind = \"staticlib\"\ntest = true");
+        let err = Manifest::parse(&staticlib, &path).unwrap_err();
+        assert!(err.to_string().contains("only exe targets can be tests"));
+    }
+
+    #[test]
+    fn test_run_config() {
+        let content = r#"
+[package]
+name = "server"
+version = "1.0.0"
+
+[targets.server]
+kind = "exe"
+
+[targets.server.run]
+args = ["--port", "8080"]
+working_dir = "data"
+env = { LOG_LEVEL = "debug" }
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        let run = manifest.targets[0].run.clone().unwrap();
+        assert_eq!(run.args, vec!["--port", "8080"]);
+        assert_eq!(run.working_dir, Some(PathBuf::from("data")));
+        assert_eq!(run.env["LOG_LEVEL"], "debug");
+
+        let lib = content.replace("kind = \"exe\"", "kind = \"sharedlib\"");
+        let err = Manifest::parse(&lib, &path).unwrap_err();
+        assert!(err.to_string().contains("only allowed on exe targets"));
+    }
+
+    #[test]
+    fn test_profile_sanitizers() {
+        let content = r#"
+[package]
+name = "parser"
+version = "1.0.0"
+
+[profile.debug]
+sanitizers = ["address", "undefined"]
+suppressions = { address = "asan.supp" }
+
+[profile.debug.sanitizer-options]
+address = "detect_stack_use_after_return=1"
+undefined = "halt_on_error=1:print_stacktrace=1"
+
+[profile.release]
+sanitizers = ["thread"]
+suppressions = { thread = "tsan.supp" }
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        let debug = manifest.debug_profile();
+        assert_eq!(debug.opt_level.as_deref(), Some("0"));
+        assert_eq!(
+            debug.sanitizer_kinds().unwrap(),
+            vec![SanitizerKind::Address, SanitizerKind::UndefinedBehavior]
+        );
+        assert_eq!(
+            debug.sanitizer_options["undefined"],
+            "halt_on_error=1:print_stacktrace=1"
+        );
+        let files = debug.suppression_files().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[&SanitizerKind::Address], PathBuf::from("asan.supp"));
+
+        let release = manifest.release_profile();
+        let files = release.suppression_files().unwrap();
+        assert_eq!(files[&SanitizerKind::Thread], PathBuf::from("tsan.supp"));
+        // Fuzz builds add AddressSanitizer, which conflicts with `thread`
+        let fuzzed = release.with_sanitizer(SanitizerKind::Address);
+        assert_eq!(fuzzed.sanitizers, vec!["thread", "address"]);
+        assert!(fuzzed.sanitizer_kinds().is_err());
+
+        let conflicting = content.replace(r#"["thread"]"#, r#"["address", "thread"]"#);
+        let err = Manifest::parse(&conflicting, &path).unwrap_err();
+        assert!(format!("{:#}", err).contains("cannot be enabled together"));
+
+        // A bare file cannot tell which of several runtimes it is for
+        let ambiguous = content.replace(r#"{ address = "asan.supp" }"#, r#""asan.supp""#);
+        let err = Manifest::parse(&ambiguous, &path).unwrap_err();
+        assert!(format!("{:#}", err).contains("single suppressions file is ambiguous"));
+
+        let unknown = content.replace("undefined = ", "ubsan = ");
+        let err = Manifest::parse(&unknown, &path).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown sanitizer 'ubsan'"));
+    }
+
+    #[test]
+    fn test_test_framework() {
+        let content = r#"
+[package]
+name = "mylib"
+version = "1.0.0"
+
+[targets.gtest_suite]
+kind = "exe"
+test-framework = "gtest"
+
+[targets.tap_suite]
+kind = "test"
+
+[targets.tap_suite.test]
+framework = "tap"
+timeout = 10
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        let framework = |name: &str| {
+            manifest
+                .targets
+                .iter()
+                .find(|t| t.name.as_str() == name)
+                .and_then(|t| t.test.as_ref())
+                .and_then(|config| config.framework)
+        };
+        assert_eq!(framework("gtest_suite"), Some(TestFramework::Gtest));
+        assert_eq!(framework("tap_suite"), Some(TestFramework::Tap));
+
+        let conflict = content.replace(
+            "kind = \"test\"",
+            "kind = \"test\"\ntest-framework = \"catch2\"",
+        );
+        let err = Manifest::parse(&conflict, &path).unwrap_err();
+        assert!(err.to_string().contains("conflicts with"));
+
+        let unknown = content.replace("\"gtest\"", "\"boost\"");
+        assert!(Manifest::parse(&unknown, &path).is_err());
+    }
+
+    #[test]
+    fn test_fuzz_targets() {
+        let content = r#"
+[package]
+name = "parser"
+version = "1.0.0"
+
+[targets.parser]
+kind = "staticlib"
+
+[targets.parse_fuzz]
+kind = "fuzz"
+sources = ["fuzz/parse_fuzz.c"]
+
+[targets.parse_fuzz.fuzz]
+dict = "fuzz/parse.dict"
+max_len = 4096
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        let target = |name: &str| {
+            manifest
+                .targets
+                .iter()
+                .find(|t| t.name.as_str() == name)
+                .unwrap()
+        };
+        assert!(target("parser").fuzz.is_none());
+        assert_eq!(target("parse_fuzz").kind, TargetKind::Exe);
+        assert!(target("parse_fuzz").test.is_none());
+        let fuzz = target("parse_fuzz").fuzz.clone().unwrap();
+        assert_eq!(fuzz.dict, Some(PathBuf::from("fuzz/parse.dict")));
+        assert_eq!(fuzz.max_len, Some(4096));
+
+        let exe = content.replace("kind = \"fuzz\"", "kind = \"exe\"");
+        let err = Manifest::parse(&exe, &path).unwrap_err();
+        assert!(err.to_string().contains("only allowed on fuzz targets"));
+
+        let test = content.replace("kind = \"fuzz\"", "kind = \"fuzz\"\ntest = true");
+        let err = Manifest::parse(&test, &path).unwrap_err();
+        assert!(err.to_string().contains("fuzz targets cannot be tests"));
+    }
+
+    #[test]
+    fn test_bench_targets() {
+        let content = r#"
+[package]
+name = "parser"
+version = "1.0.0"
+
+[targets.parser]
+kind = "staticlib"
+
+[targets.parse_bench]
+kind = "bench"
+sources = ["bench/parse_bench.cpp"]
+
+[targets.parse_bench.bench]
+format = "gbench"
+args = ["--benchmark_min_time=0.5"]
+
+[targets.tokenize_bench]
+kind = "bench"
+sources = ["bench/tokenize_bench.c"]
+"#;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("Harbour.toml");
+
+        let manifest = Manifest::parse(content, &path).unwrap();
+        let target = |name: &str| {
+            manifest
+                .targets
+                .iter()
+                .find(|t| t.name.as_str() == name)
+                .unwrap()
+        };
+        assert!(target("parser").bench.is_none());
+        assert_eq!(target("parse_bench").kind, TargetKind::Exe);
+        assert!(target("parse_bench").test.is_none());
+        let bench = target("parse_bench").bench.clone().unwrap();
+        assert_eq!(bench.format, BenchFormat::Gbench);
+        assert_eq!(bench.args, vec!["--benchmark_min_time=0.5"]);
+        let bench = target("tokenize_bench").bench.clone().unwrap();
+        assert_eq!(bench.format, BenchFormat::NsIter);
+
+        let exe = content.replacen("kind = \"bench\"", "kind = \"exe\"", 1);
+        let err = Manifest::parse(&exe, &path).unwrap_err();
+        assert!(err.to_string().contains("only allowed on bench targets"));
+
+        let test = content.replacen("kind = \"bench\"", "kind = \"bench\"\ntest = true", 1);
+        let err = Manifest::parse(&test, &path).unwrap_err();
+        assert!(err.to_string().contains("bench targets cannot be tests"));
+    }
 }
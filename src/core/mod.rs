@@ -18,7 +18,7 @@ pub mod surface;
 pub mod target;
 pub mod workspace;
 
-pub use dependency::Dependency;
+pub use dependency::{DepKind, Dependency};
 pub use manifest::Manifest;
 pub use package::Package;
 pub use package_id::PackageId;
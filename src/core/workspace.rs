@@ -11,7 +11,7 @@ use anyhow::{Context, Result};
 use glob::Pattern;
 use thiserror::Error;
 
-use crate::core::manifest::WorkspaceConfig;
+use crate::core::manifest::{Profile, WorkspaceConfig};
 use crate::core::{Manifest, Package, PackageId};
 use crate::util::{GlobalContext, InternedString};
 
@@ -549,6 +549,15 @@ impl Workspace {
         self.profile == "release"
     }
 
+    /// Get the settings of the current profile.
+    pub fn build_profile(&self) -> Profile {
+        if self.is_release() {
+            self.manifest().release_profile()
+        } else {
+            self.manifest().debug_profile()
+        }
+    }
+
     /// Get the lockfile path.
     ///
     /// Returns existing lockfile if found (checking both Harbour.lock and Harbor.lock),
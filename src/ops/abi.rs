@@ -0,0 +1,920 @@
+//! ABI baselines for shared libraries.
+//!
+//! A baseline records what each built `sharedlib` target offers its
+//! dependents: the symbols in its dynamic symbol table, and the functions
+//! and types its public headers declare. Diffing two baselines grades each
+//! change by the semver bump it needs, so a release can be checked against
+//! the package version before it ships.
+//!
+//! ## Usage
+//!
+//! ```bash
+//! harbour abi dump                          # Record target/abi/<name>-<version>.json
+//! harbour abi diff target/abi/foo-1.2.0.json  # Compare with the current build
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::builder::bindings::ParsedHeader;
+use crate::ops::ffi_bundle::{get_platform_string, ExportedFunction, StructField, TypeDefinition};
+use crate::util::binary::{self, Symbol, SymbolKind};
+
+/// Baseline file format version
+const ABI_DUMP_VERSION: u32 = 1;
+
+/// The ABI of a package's shared libraries at one version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbiDump {
+    /// Baseline file format version
+    pub format: u32,
+
+    /// Package name
+    pub package: String,
+
+    /// Package version the libraries were built from
+    pub version: Version,
+
+    /// Platform the libraries were built for
+    pub platform: String,
+
+    /// One entry per `sharedlib` target
+    pub libraries: Vec<LibraryAbi>,
+}
+
+impl AbiDump {
+    /// Create an empty baseline for a package version.
+    pub fn new(package: impl Into<String>, version: Version) -> Self {
+        AbiDump {
+            format: ABI_DUMP_VERSION,
+            package: package.into(),
+            version,
+            platform: get_platform_string(),
+            libraries: Vec::new(),
+        }
+    }
+
+    /// Add a library.
+    pub fn with_library(mut self, library: LibraryAbi) -> Self {
+        self.libraries.push(library);
+        self
+    }
+
+    /// Read a baseline from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let dump: AbiDump = serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse ABI baseline {}", path.display()))?;
+        if dump.format > ABI_DUMP_VERSION {
+            bail!(
+                "{} uses ABI baseline format {}, but this version of harbour reads up to {}",
+                path.display(),
+                dump.format,
+                ABI_DUMP_VERSION
+            );
+        }
+        Ok(dump)
+    }
+
+    /// Write the baseline as JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// The ABI of one shared library.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryAbi {
+    /// Target name
+    pub target: String,
+
+    /// Library file name
+    pub file: String,
+
+    /// Name dependents record for the library
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soname: Option<String>,
+
+    /// Exported dynamic symbols, sorted by name
+    pub symbols: Vec<Symbol>,
+
+    /// Functions declared in the public headers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub functions: Vec<ExportedFunction>,
+
+    /// Types declared in the public headers, with their layouts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<TypeDefinition>,
+}
+
+impl LibraryAbi {
+    /// Record a built library, and the API its parsed headers declare.
+    ///
+    /// Struct layouts are included for headers whose layouts have been
+    /// computed.
+    pub fn from_library(target: &str, path: &Path, header: Option<&ParsedHeader>) -> Result<Self> {
+        let info = binary::inspect(path)?;
+        let symbols = binary::exported_symbols(path)?;
+
+        let mut library = LibraryAbi {
+            target: target.to_string(),
+            file: path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            soname: info.soname,
+            symbols,
+            functions: Vec::new(),
+            types: Vec::new(),
+        };
+
+        if let Some(header) = header {
+            library.functions = header.functions.iter().map(Into::into).collect();
+            library.types = header
+                .structs
+                .iter()
+                .map(TypeDefinition::from)
+                .chain(header.enums.iter().map(TypeDefinition::from))
+                .chain(header.typedefs.iter().map(TypeDefinition::from))
+                .collect();
+
+            let exported: HashSet<&str> = library.symbols.iter().map(|s| s.name.as_str()).collect();
+            for func in &library.functions {
+                if !exported.contains(func.name.as_str()) {
+                    tracing::warn!(
+                        "`{}` is declared in the headers of `{}` but not exported",
+                        func.name,
+                        target
+                    );
+                }
+            }
+        }
+
+        Ok(library)
+    }
+}
+
+/// A semver version bump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    /// Compatible fixes only
+    Patch,
+    /// Compatible additions
+    Minor,
+    /// Breaking changes
+    Major,
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bump::Patch => write!(f, "patch"),
+            Bump::Minor => write!(f, "minor"),
+            Bump::Major => write!(f, "major"),
+        }
+    }
+}
+
+/// The kind of change going from `old` to `new` signals to dependents,
+/// or `None` if `new` is not newer.
+///
+/// Follows Cargo's reading of semver: before 1.0.0 the first non-zero
+/// component is the breaking one, and the next one covers additions.
+pub fn version_bump(old: &Version, new: &Version) -> Option<Bump> {
+    if new <= old {
+        return None;
+    }
+    let bump = if new.major != old.major {
+        Bump::Major
+    } else if old.major > 0 {
+        if new.minor != old.minor {
+            Bump::Minor
+        } else {
+            Bump::Patch
+        }
+    } else if new.minor != old.minor || old.minor == 0 {
+        // 0.x.y: minor is breaking; 0.0.z: every release is
+        if new.minor == old.minor && new.patch == old.patch {
+            Bump::Patch
+        } else {
+            Bump::Major
+        }
+    } else if new.patch != old.patch {
+        Bump::Minor
+    } else {
+        Bump::Patch
+    };
+    Some(bump)
+}
+
+/// One ABI difference between two baselines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbiChange {
+    /// Target the change is in
+    pub library: String,
+
+    /// Version bump the change needs
+    pub bump: Bump,
+
+    /// What changed
+    pub description: String,
+}
+
+impl fmt::Display for AbiChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.bump, self.library, self.description)
+    }
+}
+
+/// Differences between two ABI baselines.
+#[derive(Debug, Clone, Default)]
+pub struct AbiReport {
+    /// Changes, in library order
+    pub changes: Vec<AbiChange>,
+}
+
+impl AbiReport {
+    /// The smallest version bump that covers every change.
+    pub fn required_bump(&self) -> Bump {
+        self.changes
+            .iter()
+            .map(|c| c.bump)
+            .max()
+            .unwrap_or(Bump::Patch)
+    }
+
+    /// Check that going from `old` to `new` is a large enough version bump.
+    pub fn check_version(&self, old: &Version, new: &Version) -> Result<()> {
+        let required = self.required_bump();
+        if required == Bump::Patch {
+            return Ok(());
+        }
+        match version_bump(old, new) {
+            Some(actual) if actual >= required => Ok(()),
+            Some(actual) => bail!(
+                "ABI changes require a {} version bump, but {} -> {} is a {} bump",
+                required,
+                old,
+                new,
+                actual
+            ),
+            None => bail!(
+                "ABI changes require a {} version bump, but the version is still {}",
+                required,
+                new
+            ),
+        }
+    }
+
+    fn push(&mut self, library: &str, bump: Bump, description: String) {
+        self.changes.push(AbiChange {
+            library: library.to_string(),
+            bump,
+            description,
+        });
+    }
+}
+
+/// Compare two baselines of the same package.
+pub fn diff_abi(old: &AbiDump, new: &AbiDump) -> AbiReport {
+    if old.platform != new.platform {
+        tracing::warn!(
+            "comparing ABI baselines from different platforms ({} and {})",
+            old.platform,
+            new.platform
+        );
+    }
+
+    let mut report = AbiReport::default();
+    for old_lib in &old.libraries {
+        match new.libraries.iter().find(|l| l.target == old_lib.target) {
+            Some(new_lib) => diff_library(&mut report, old_lib, new_lib),
+            None => report.push(&old_lib.target, Bump::Major, "library removed".to_string()),
+        }
+    }
+    for new_lib in &new.libraries {
+        if !old.libraries.iter().any(|l| l.target == new_lib.target) {
+            report.push(&new_lib.target, Bump::Minor, "library added".to_string());
+        }
+    }
+    report
+}
+
+fn diff_library(report: &mut AbiReport, old: &LibraryAbi, new: &LibraryAbi) {
+    let lib = old.target.as_str();
+
+    if old.soname != new.soname {
+        report.push(
+            lib,
+            Bump::Major,
+            format!(
+                "soname changed from `{}` to `{}`",
+                old.soname.as_deref().unwrap_or("(none)"),
+                new.soname.as_deref().unwrap_or("(none)")
+            ),
+        );
+    }
+
+    // Symbols already reported aren't reported again as header changes
+    let mut reported = HashSet::new();
+    let new_symbols: HashMap<&str, &Symbol> =
+        new.symbols.iter().map(|s| (s.name.as_str(), s)).collect();
+    for symbol in &old.symbols {
+        match new_symbols.get(symbol.name.as_str()) {
+            None => {
+                report.push(
+                    lib,
+                    Bump::Major,
+                    format!("symbol `{}` removed", symbol.name),
+                );
+                reported.insert(symbol.name.as_str());
+            }
+            Some(new_symbol) if new_symbol.kind != symbol.kind => {
+                report.push(
+                    lib,
+                    Bump::Major,
+                    format!(
+                        "symbol `{}` changed from {} to {}",
+                        symbol.name, symbol.kind, new_symbol.kind
+                    ),
+                );
+            }
+            Some(new_symbol) => {
+                if let (SymbolKind::Data, Some(old_size), Some(new_size)) =
+                    (symbol.kind, symbol.size, new_symbol.size)
+                {
+                    if old_size != new_size {
+                        report.push(
+                            lib,
+                            Bump::Major,
+                            format!(
+                                "data symbol `{}` changed size from {} to {} bytes",
+                                symbol.name, old_size, new_size
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    let old_symbols: HashSet<&str> = old.symbols.iter().map(|s| s.name.as_str()).collect();
+    for symbol in &new.symbols {
+        if !old_symbols.contains(symbol.name.as_str()) {
+            report.push(lib, Bump::Minor, format!("symbol `{}` added", symbol.name));
+            reported.insert(symbol.name.as_str());
+        }
+    }
+
+    for func in &old.functions {
+        match new.functions.iter().find(|f| f.name == func.name) {
+            None if !reported.contains(func.name.as_str()) => report.push(
+                lib,
+                Bump::Major,
+                format!("function `{}` removed from the headers", func.name),
+            ),
+            Some(new_func) if signature(func) != signature(new_func) => report.push(
+                lib,
+                Bump::Major,
+                format!(
+                    "function `{}` changed from `{}` to `{}`",
+                    func.name,
+                    signature(func),
+                    signature(new_func)
+                ),
+            ),
+            _ => {}
+        }
+    }
+    for func in &new.functions {
+        if !reported.contains(func.name.as_str())
+            && !old.functions.iter().any(|f| f.name == func.name)
+        {
+            report.push(
+                lib,
+                Bump::Minor,
+                format!("function `{}` added to the headers", func.name),
+            );
+        }
+    }
+
+    for ty in &old.types {
+        match new.types.iter().find(|t| t.name() == ty.name()) {
+            Some(new_ty) => diff_type(report, lib, ty, new_ty),
+            None => report.push(
+                lib,
+                Bump::Major,
+                format!("{} `{}` removed", kind_name(ty), ty.name()),
+            ),
+        }
+    }
+    for ty in &new.types {
+        if !old.types.iter().any(|t| t.name() == ty.name()) {
+            report.push(
+                lib,
+                Bump::Minor,
+                format!("{} `{}` added", kind_name(ty), ty.name()),
+            );
+        }
+    }
+}
+
+fn diff_type(report: &mut AbiReport, lib: &str, old: &TypeDefinition, new: &TypeDefinition) {
+    let name = old.name();
+    match (old, new) {
+        (
+            TypeDefinition::Struct {
+                fields: old_fields,
+                size: old_size,
+                align: old_align,
+                ..
+            },
+            TypeDefinition::Struct {
+                fields: new_fields,
+                size: new_size,
+                align: new_align,
+                ..
+            },
+        )
+        | (
+            TypeDefinition::Union {
+                fields: old_fields,
+                size: old_size,
+                align: old_align,
+                ..
+            },
+            TypeDefinition::Union {
+                fields: new_fields,
+                size: new_size,
+                align: new_align,
+                ..
+            },
+        ) => {
+            let kind = kind_name(old);
+            if let (Some(old_size), Some(new_size)) = (old_size, new_size) {
+                if old_size != new_size {
+                    report.push(
+                        lib,
+                        Bump::Major,
+                        format!(
+                            "{} `{}` changed size from {} to {} bytes",
+                            kind, name, old_size, new_size
+                        ),
+                    );
+                }
+            }
+            if let (Some(old_align), Some(new_align)) = (old_align, new_align) {
+                if old_align != new_align {
+                    report.push(
+                        lib,
+                        Bump::Major,
+                        format!(
+                            "{} `{}` changed alignment from {} to {}",
+                            kind, name, old_align, new_align
+                        ),
+                    );
+                }
+            }
+            diff_fields(report, lib, name, old_fields, new_fields);
+        }
+        (
+            TypeDefinition::Enum {
+                variants: old_variants,
+                ..
+            },
+            TypeDefinition::Enum {
+                variants: new_variants,
+                ..
+            },
+        ) => {
+            for variant in old_variants {
+                match new_variants.iter().find(|v| v.name == variant.name) {
+                    None => report.push(
+                        lib,
+                        Bump::Major,
+                        format!("enumerator `{}::{}` removed", name, variant.name),
+                    ),
+                    Some(new_variant) if new_variant.value != variant.value => report.push(
+                        lib,
+                        Bump::Major,
+                        format!(
+                            "enumerator `{}::{}` changed value from {} to {}",
+                            name,
+                            variant.name,
+                            display_value(variant.value),
+                            display_value(new_variant.value)
+                        ),
+                    ),
+                    _ => {}
+                }
+            }
+            for variant in new_variants {
+                if !old_variants.iter().any(|v| v.name == variant.name) {
+                    report.push(
+                        lib,
+                        Bump::Minor,
+                        format!("enumerator `{}::{}` added", name, variant.name),
+                    );
+                }
+            }
+        }
+        (
+            TypeDefinition::Typedef {
+                underlying_type: old_type,
+                ..
+            },
+            TypeDefinition::Typedef {
+                underlying_type: new_type,
+                ..
+            },
+        ) => {
+            if old_type != new_type {
+                report.push(
+                    lib,
+                    Bump::Major,
+                    format!(
+                        "typedef `{}` changed from `{}` to `{}`",
+                        name, old_type, new_type
+                    ),
+                );
+            }
+        }
+        _ => report.push(
+            lib,
+            Bump::Major,
+            format!(
+                "`{}` changed from {} to {}",
+                name,
+                kind_name(old),
+                kind_name(new)
+            ),
+        ),
+    }
+}
+
+/// Compare fields by name; anonymous members are covered by the size and
+/// offsets of their neighbours.
+fn diff_fields(
+    report: &mut AbiReport,
+    lib: &str,
+    name: &str,
+    old: &[StructField],
+    new: &[StructField],
+) {
+    for field in old.iter().filter(|f| !f.name.is_empty()) {
+        let Some(new_field) = new.iter().find(|f| f.name == field.name) else {
+            report.push(
+                lib,
+                Bump::Major,
+                format!("field `{}.{}` removed", name, field.name),
+            );
+            continue;
+        };
+        if new_field.field_type != field.field_type || new_field.bit_width != field.bit_width {
+            report.push(
+                lib,
+                Bump::Major,
+                format!(
+                    "field `{}.{}` changed type from `{}` to `{}`",
+                    name,
+                    field.name,
+                    field_type(field),
+                    field_type(new_field)
+                ),
+            );
+        } else if (new_field.offset, new_field.bit_offset) != (field.offset, field.bit_offset) {
+            report.push(
+                lib,
+                Bump::Major,
+                format!(
+                    "field `{}.{}` moved from offset {} to {}",
+                    name,
+                    field.name,
+                    display_value(field.offset),
+                    display_value(new_field.offset)
+                ),
+            );
+        }
+    }
+    for field in new.iter().filter(|f| !f.name.is_empty()) {
+        if !old.iter().any(|f| f.name == field.name) {
+            report.push(
+                lib,
+                Bump::Minor,
+                format!("field `{}.{}` added", name, field.name),
+            );
+        }
+    }
+}
+
+/// A function's type, ignoring parameter names.
+fn signature(func: &ExportedFunction) -> String {
+    let mut params: Vec<&str> = func.params.iter().map(|p| p.param_type.as_str()).collect();
+    if func.variadic {
+        params.push("...");
+    }
+    let convention = if func.calling_convention == "cdecl" {
+        String::new()
+    } else {
+        format!("{} ", func.calling_convention)
+    };
+    format!("{}{}({})", convention, func.return_type, params.join(", "))
+}
+
+fn field_type(field: &StructField) -> String {
+    match field.bit_width {
+        Some(width) => format!("{} : {}", field.field_type, width),
+        None => field.field_type.clone(),
+    }
+}
+
+fn kind_name(ty: &TypeDefinition) -> &'static str {
+    match ty {
+        TypeDefinition::Struct { .. } => "struct",
+        TypeDefinition::Union { .. } => "union",
+        TypeDefinition::Enum { .. } => "enum",
+        TypeDefinition::Typedef { .. } => "typedef",
+    }
+}
+
+fn display_value<T: fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "unknown".to_string(), |v| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::ffi_bundle::{EnumVariant, FunctionParam};
+
+    fn version(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    fn function(name: &str, ret: &str, params: &[&str]) -> ExportedFunction {
+        ExportedFunction {
+            name: name.to_string(),
+            return_type: ret.to_string(),
+            params: params
+                .iter()
+                .map(|p| FunctionParam {
+                    name: String::new(),
+                    param_type: p.to_string(),
+                })
+                .collect(),
+            calling_convention: "cdecl".to_string(),
+            variadic: false,
+            doc: None,
+        }
+    }
+
+    fn symbol(name: &str, kind: SymbolKind, size: u64) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            kind,
+            size: Some(size),
+        }
+    }
+
+    fn field(name: &str, ty: &str, offset: u64) -> StructField {
+        StructField {
+            name: name.to_string(),
+            field_type: ty.to_string(),
+            bit_width: None,
+            offset: Some(offset),
+            bit_offset: None,
+        }
+    }
+
+    fn library(
+        symbols: Vec<Symbol>,
+        functions: Vec<ExportedFunction>,
+        types: Vec<TypeDefinition>,
+    ) -> AbiDump {
+        AbiDump::new("demo", version("1.0.0")).with_library(LibraryAbi {
+            target: "demo".to_string(),
+            file: "libdemo.so".to_string(),
+            soname: Some("libdemo.so.1".to_string()),
+            symbols,
+            functions,
+            types,
+        })
+    }
+
+    fn point(fields: Vec<StructField>, size: u64) -> TypeDefinition {
+        TypeDefinition::Struct {
+            name: "point".to_string(),
+            fields,
+            pack: None,
+            size: Some(size),
+            align: Some(4),
+        }
+    }
+
+    fn mode(variants: &[(&str, i64)]) -> TypeDefinition {
+        TypeDefinition::Enum {
+            name: "mode".to_string(),
+            variants: variants
+                .iter()
+                .map(|(name, value)| EnumVariant {
+                    name: name.to_string(),
+                    value: Some(*value),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_version_bump() {
+        let bump = |old: &str, new: &str| version_bump(&version(old), &version(new));
+        assert_eq!(bump("1.2.3", "2.0.0"), Some(Bump::Major));
+        assert_eq!(bump("1.2.3", "1.3.0"), Some(Bump::Minor));
+        assert_eq!(bump("1.2.3", "1.2.4"), Some(Bump::Patch));
+        assert_eq!(bump("0.2.3", "0.3.0"), Some(Bump::Major));
+        assert_eq!(bump("0.2.3", "0.2.4"), Some(Bump::Minor));
+        assert_eq!(bump("0.0.3", "0.0.4"), Some(Bump::Major));
+        assert_eq!(bump("1.2.3", "1.2.3"), None);
+        assert_eq!(bump("1.2.3", "1.2.0"), None);
+    }
+
+    #[test]
+    fn test_identical_baselines() {
+        let dump = library(
+            vec![symbol("demo_open", SymbolKind::Function, 32)],
+            vec![function("demo_open", "int32_t", &["const char*"])],
+            vec![point(vec![field("x", "int32_t", 0)], 4)],
+        );
+        let report = diff_abi(&dump, &dump);
+        assert!(report.changes.is_empty());
+        assert_eq!(report.required_bump(), Bump::Patch);
+        report
+            .check_version(&version("1.0.0"), &version("1.0.0"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_additions_need_minor_bump() {
+        let old = library(
+            vec![symbol("demo_open", SymbolKind::Function, 32)],
+            vec![function("demo_open", "int32_t", &[])],
+            vec![mode(&[("READ", 0)])],
+        );
+        let new = library(
+            vec![
+                symbol("demo_close", SymbolKind::Function, 16),
+                symbol("demo_open", SymbolKind::Function, 48),
+            ],
+            vec![
+                function("demo_open", "int32_t", &[]),
+                function("demo_close", "void", &[]),
+            ],
+            vec![mode(&[("READ", 0), ("WRITE", 1)])],
+        );
+
+        let report = diff_abi(&old, &new);
+        let descriptions: Vec<_> = report
+            .changes
+            .iter()
+            .map(|c| c.description.as_str())
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "symbol `demo_close` added",
+                "enumerator `mode::WRITE` added"
+            ]
+        );
+        assert_eq!(report.required_bump(), Bump::Minor);
+        report
+            .check_version(&version("1.0.0"), &version("1.1.0"))
+            .unwrap();
+        let err = report
+            .check_version(&version("1.0.0"), &version("1.0.1"))
+            .unwrap_err();
+        assert!(err.to_string().contains("require a minor version bump"));
+    }
+
+    #[test]
+    fn test_breaking_changes_need_major_bump() {
+        let old = library(
+            vec![
+                symbol("demo_count", SymbolKind::Data, 4),
+                symbol("demo_legacy", SymbolKind::Function, 8),
+                symbol("demo_open", SymbolKind::Function, 32),
+            ],
+            vec![
+                function("demo_legacy", "void", &[]),
+                function("demo_open", "int32_t", &["const char*"]),
+            ],
+            vec![
+                point(vec![field("x", "int32_t", 0), field("y", "int32_t", 4)], 8),
+                mode(&[("READ", 0), ("WRITE", 1)]),
+            ],
+        );
+        let new = library(
+            vec![
+                symbol("demo_count", SymbolKind::Data, 8),
+                symbol("demo_open", SymbolKind::Function, 32),
+            ],
+            vec![function(
+                "demo_open",
+                "int32_t",
+                &["const char*", "int32_t"],
+            )],
+            vec![
+                point(vec![field("y", "int64_t", 0)], 8),
+                mode(&[("READ", 0), ("WRITE", 2)]),
+            ],
+        );
+
+        let report = diff_abi(&old, &new);
+        let descriptions: Vec<_> = report
+            .changes
+            .iter()
+            .map(|c| c.description.as_str())
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "data symbol `demo_count` changed size from 4 to 8 bytes",
+                "symbol `demo_legacy` removed",
+                "function `demo_open` changed from `int32_t(const char*)` to \
+                 `int32_t(const char*, int32_t)`",
+                "field `point.x` removed",
+                "field `point.y` changed type from `int32_t` to `int64_t`",
+                "enumerator `mode::WRITE` changed value from 1 to 2",
+            ]
+        );
+        assert!(report.changes.iter().all(|c| c.bump == Bump::Major));
+
+        report
+            .check_version(&version("1.4.2"), &version("2.0.0"))
+            .unwrap();
+        report
+            .check_version(&version("0.4.2"), &version("0.5.0"))
+            .unwrap();
+        assert!(report
+            .check_version(&version("1.4.2"), &version("1.5.0"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_soname_and_library_changes() {
+        let old = library(Vec::new(), Vec::new(), Vec::new());
+        let mut new = old.clone();
+        new.libraries[0].soname = Some("libdemo.so.2".to_string());
+        new.libraries.push(LibraryAbi {
+            target: "demo_extra".to_string(),
+            ..new.libraries[0].clone()
+        });
+
+        let report = diff_abi(&old, &new);
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(
+            report.changes[0].to_string(),
+            "[major] demo: soname changed from `libdemo.so.1` to `libdemo.so.2`"
+        );
+        assert_eq!(
+            report.changes[1].to_string(),
+            "[minor] demo_extra: library added"
+        );
+
+        let report = diff_abi(&new, &old);
+        assert!(report
+            .changes
+            .iter()
+            .any(|c| c.library == "demo_extra" && c.bump == Bump::Major));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("abi").join("demo-1.0.0.json");
+        let dump = library(
+            vec![symbol("demo_open", SymbolKind::Function, 32)],
+            vec![function("demo_open", "int32_t", &[])],
+            vec![mode(&[("READ", 0)])],
+        );
+        dump.save(&path).unwrap();
+
+        let loaded = AbiDump::load(&path).unwrap();
+        assert_eq!(loaded.version, version("1.0.0"));
+        assert_eq!(loaded.libraries[0].symbols, dump.libraries[0].symbols);
+        assert!(diff_abi(&dump, &loaded).changes.is_empty());
+
+        let json = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"format\": 1", "\"format\": 99");
+        std::fs::write(&path, json).unwrap();
+        assert!(AbiDump::load(&path).is_err());
+    }
+}
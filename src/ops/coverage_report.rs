@@ -0,0 +1,561 @@
+//! Line and function coverage data and its report formats.
+//!
+//! Coverage is read from the tools of the toolchain that built the tests:
+//!
+//! - GCC's `gcov --json-format`
+//! - LLVM's `llvm-cov export -format=lcov`
+//!
+//! and written out as an lcov tracefile, Cobertura XML and a terminal
+//! summary.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::ops::test_report::xml_escape;
+
+/// Coverage of a set of source files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageData {
+    /// Coverage per source file
+    pub files: BTreeMap<PathBuf, FileCoverage>,
+}
+
+/// Coverage of one source file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// Execution count per instrumented line
+    pub lines: BTreeMap<u32, u64>,
+
+    /// Functions by name
+    pub functions: BTreeMap<String, FunctionCoverage>,
+}
+
+/// Coverage of one function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FunctionCoverage {
+    /// Line the function starts on
+    pub line: u32,
+
+    /// Number of calls
+    pub count: u64,
+}
+
+impl FileCoverage {
+    /// Add the counts of `other`, e.g. the same header seen from another
+    /// object file.
+    pub fn merge(&mut self, other: &FileCoverage) {
+        for (line, count) in &other.lines {
+            *self.lines.entry(*line).or_default() += count;
+        }
+        for (name, function) in &other.functions {
+            let entry = self
+                .functions
+                .entry(name.clone())
+                .or_insert(FunctionCoverage {
+                    line: function.line,
+                    count: 0,
+                });
+            entry.count += function.count;
+        }
+    }
+
+    /// Number of instrumented lines.
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Number of lines executed at least once.
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|count| **count > 0).count()
+    }
+
+    /// Number of functions.
+    pub fn functions_found(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Number of functions called at least once.
+    pub fn functions_hit(&self) -> usize {
+        self.functions.values().filter(|f| f.count > 0).count()
+    }
+}
+
+impl CoverageData {
+    /// Add the counts of `other`.
+    pub fn merge(&mut self, other: CoverageData) {
+        for (path, file) in other.files {
+            match self.files.get_mut(&path) {
+                Some(existing) => existing.merge(&file),
+                None => {
+                    self.files.insert(path, file);
+                }
+            }
+        }
+    }
+
+    /// Keep only the files for which `keep` returns true.
+    pub fn retain_files(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        self.files.retain(|path, _| keep(path));
+    }
+
+    /// Number of instrumented lines in all files.
+    pub fn lines_found(&self) -> usize {
+        self.files.values().map(FileCoverage::lines_found).sum()
+    }
+
+    /// Number of lines executed at least once in all files.
+    pub fn lines_hit(&self) -> usize {
+        self.files.values().map(FileCoverage::lines_hit).sum()
+    }
+
+    /// Fraction of instrumented lines executed, 1.0 if there are none.
+    pub fn line_rate(&self) -> f64 {
+        rate(self.lines_hit(), self.lines_found())
+    }
+}
+
+fn rate(hit: usize, found: usize) -> f64 {
+    if found == 0 {
+        1.0
+    } else {
+        hit as f64 / found as f64
+    }
+}
+
+/// Parse an lcov tracefile, as written by `llvm-cov export -format=lcov`.
+pub fn parse_lcov(tracefile: &str) -> Result<CoverageData> {
+    let mut data = CoverageData::default();
+    let mut current: Option<(PathBuf, FileCoverage)> = None;
+    // FN records name the start line, FNDA records the count
+    let mut function_lines: BTreeMap<String, u32> = BTreeMap::new();
+
+    for (index, line) in tracefile.lines().enumerate() {
+        let line = line.trim();
+        let invalid = || format!("invalid lcov record on line {}: {}", index + 1, line);
+
+        if line == "end_of_record" {
+            if let Some((path, file)) = current.take() {
+                data.merge(CoverageData {
+                    files: BTreeMap::from([(path, file)]),
+                });
+            }
+            function_lines.clear();
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        if key == "SF" {
+            current = Some((PathBuf::from(value), FileCoverage::default()));
+            continue;
+        }
+        let Some((_, file)) = current.as_mut() else {
+            continue;
+        };
+        match key {
+            "DA" => {
+                let mut fields = value.split(',');
+                let line_number = fields.next().and_then(|f| f.parse().ok());
+                let count = fields.next().and_then(|f| f.parse::<u64>().ok());
+                let (Some(line_number), Some(count)) = (line_number, count) else {
+                    bail!(invalid());
+                };
+                *file.lines.entry(line_number).or_default() += count;
+            }
+            "FN" => {
+                let (start, name) = value.split_once(',').with_context(invalid)?;
+                let start = start.parse().with_context(invalid)?;
+                function_lines.insert(name.to_string(), start);
+                file.functions
+                    .entry(name.to_string())
+                    .or_insert(FunctionCoverage {
+                        line: start,
+                        count: 0,
+                    });
+            }
+            "FNDA" => {
+                let (count, name) = value.split_once(',').with_context(invalid)?;
+                let count: u64 = count.parse().with_context(invalid)?;
+                let line = function_lines.get(name).copied().unwrap_or_default();
+                file.functions
+                    .entry(name.to_string())
+                    .or_insert(FunctionCoverage { line, count: 0 })
+                    .count += count;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(data)
+}
+
+#[derive(Deserialize)]
+struct GcovReport {
+    #[serde(default)]
+    current_working_directory: Option<PathBuf>,
+    #[serde(default)]
+    files: Vec<GcovFile>,
+}
+
+#[derive(Deserialize)]
+struct GcovFile {
+    file: PathBuf,
+    #[serde(default)]
+    lines: Vec<GcovLine>,
+    #[serde(default)]
+    functions: Vec<GcovFunction>,
+}
+
+#[derive(Deserialize)]
+struct GcovLine {
+    line_number: u32,
+    count: u64,
+}
+
+#[derive(Deserialize)]
+struct GcovFunction {
+    #[serde(default)]
+    demangled_name: Option<String>,
+    name: String,
+    start_line: u32,
+    execution_count: u64,
+}
+
+/// Parse the output of `gcov --json-format --stdout`.
+///
+/// gcov prints one JSON document per line, one for each data file it was
+/// given. Relative source paths are resolved against the directory the
+/// object was compiled in.
+pub fn parse_gcov_json(output: &str) -> Result<CoverageData> {
+    let mut data = CoverageData::default();
+
+    for document in output.lines().filter(|l| !l.trim().is_empty()) {
+        let report: GcovReport =
+            serde_json::from_str(document).context("invalid gcov JSON output")?;
+
+        for gcov_file in report.files {
+            let path = match &report.current_working_directory {
+                Some(cwd) if gcov_file.file.is_relative() => cwd.join(&gcov_file.file),
+                _ => gcov_file.file,
+            };
+
+            let mut file = FileCoverage::default();
+            for line in gcov_file.lines {
+                *file.lines.entry(line.line_number).or_default() += line.count;
+            }
+            for function in gcov_file.functions {
+                let name = function.demangled_name.unwrap_or(function.name);
+                file.merge(&FileCoverage {
+                    lines: BTreeMap::new(),
+                    functions: BTreeMap::from([(
+                        name,
+                        FunctionCoverage {
+                            line: function.start_line,
+                            count: function.execution_count,
+                        },
+                    )]),
+                });
+            }
+
+            data.merge(CoverageData {
+                files: BTreeMap::from([(path, file)]),
+            });
+        }
+    }
+
+    Ok(data)
+}
+
+/// Write coverage as an lcov tracefile.
+pub fn write_lcov(data: &CoverageData) -> String {
+    let mut out = String::new();
+
+    for (path, file) in &data.files {
+        out.push_str("TN:\n");
+        let _ = writeln!(out, "SF:{}", path.display());
+
+        let mut functions: Vec<(&String, &FunctionCoverage)> = file.functions.iter().collect();
+        functions.sort_by_key(|(name, f)| (f.line, *name));
+        for (name, function) in &functions {
+            let _ = writeln!(out, "FN:{},{}", function.line, name);
+        }
+        for (name, function) in &functions {
+            let _ = writeln!(out, "FNDA:{},{}", function.count, name);
+        }
+        let _ = writeln!(out, "FNF:{}", file.functions_found());
+        let _ = writeln!(out, "FNH:{}", file.functions_hit());
+
+        for (line, count) in &file.lines {
+            let _ = writeln!(out, "DA:{},{}", line, count);
+        }
+        let _ = writeln!(out, "LF:{}", file.lines_found());
+        let _ = writeln!(out, "LH:{}", file.lines_hit());
+        out.push_str("end_of_record\n");
+    }
+
+    out
+}
+
+/// Write coverage as Cobertura XML.
+///
+/// File names are relative to `root`, and each directory becomes a
+/// package. Branch coverage is not collected, so branch rates are 0.
+pub fn write_cobertura(data: &CoverageData, root: &Path, timestamp: u64) -> String {
+    let mut packages: BTreeMap<String, Vec<(String, &FileCoverage)>> = BTreeMap::new();
+    for (path, file) in &data.files {
+        let relative = display_path(path, root);
+        let package = Path::new(&relative)
+            .parent()
+            .map(|p| p.to_string_lossy().replace(['/', '\\'], "."))
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| ".".to_string());
+        packages.entry(package).or_default().push((relative, file));
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(
+        "<!DOCTYPE coverage SYSTEM \"http://cobertura.sourceforge.net/xml/coverage-04.dtd\">\n",
+    );
+    let _ = writeln!(
+        xml,
+        "<coverage line-rate=\"{:.4}\" branch-rate=\"0\" lines-covered=\"{}\" lines-valid=\"{}\" \
+         branches-covered=\"0\" branches-valid=\"0\" complexity=\"0\" version=\"{}\" timestamp=\"{}\">",
+        data.line_rate(),
+        data.lines_hit(),
+        data.lines_found(),
+        env!("CARGO_PKG_VERSION"),
+        timestamp
+    );
+    let _ = writeln!(
+        xml,
+        "  <sources>\n    <source>{}</source>\n  </sources>",
+        xml_escape(&root.display().to_string())
+    );
+    xml.push_str("  <packages>\n");
+
+    for (package, files) in &packages {
+        let hit: usize = files.iter().map(|(_, f)| f.lines_hit()).sum();
+        let found: usize = files.iter().map(|(_, f)| f.lines_found()).sum();
+        let _ = writeln!(
+            xml,
+            "    <package name=\"{}\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"0\">",
+            xml_escape(package),
+            rate(hit, found)
+        );
+        xml.push_str("      <classes>\n");
+
+        for (filename, file) in files {
+            let name = Path::new(filename)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| filename.clone());
+            let _ = writeln!(
+                xml,
+                "        <class name=\"{}\" filename=\"{}\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"0\">",
+                xml_escape(&name),
+                xml_escape(filename),
+                rate(file.lines_hit(), file.lines_found())
+            );
+
+            xml.push_str("          <methods>\n");
+            for (function, coverage) in &file.functions {
+                let hits = file.lines.get(&coverage.line).copied().unwrap_or(0);
+                let _ = writeln!(
+                    xml,
+                    "            <method name=\"{}\" signature=\"\" line-rate=\"{}\" branch-rate=\"0\" complexity=\"0\">\n\
+                     \x20             <lines><line number=\"{}\" hits=\"{}\"/></lines>\n\
+                     \x20           </method>",
+                    xml_escape(function),
+                    if coverage.count > 0 { 1 } else { 0 },
+                    coverage.line,
+                    hits.max(coverage.count)
+                );
+            }
+            xml.push_str("          </methods>\n");
+
+            xml.push_str("          <lines>\n");
+            for (line, count) in &file.lines {
+                let _ = writeln!(
+                    xml,
+                    "            <line number=\"{}\" hits=\"{}\" branch=\"false\"/>",
+                    line, count
+                );
+            }
+            xml.push_str("          </lines>\n");
+            xml.push_str("        </class>\n");
+        }
+
+        xml.push_str("      </classes>\n");
+        xml.push_str("    </package>\n");
+    }
+
+    xml.push_str("  </packages>\n");
+    xml.push_str("</coverage>\n");
+    xml
+}
+
+/// Format a per-file table of line and function coverage with a total.
+pub fn format_summary(data: &CoverageData, root: &Path) -> String {
+    let rows: Vec<(String, &FileCoverage)> = data
+        .files
+        .iter()
+        .map(|(path, file)| (display_path(path, root), file))
+        .collect();
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["Filename".len(), "TOTAL".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:>7}  {:>7}  {:>7}  {:>9}  {:>7}",
+        "Filename", "Lines", "Missed", "Cover", "Functions", "Cover"
+    );
+    let _ = writeln!(out, "{}", "-".repeat(width + 50));
+
+    let mut functions = (0, 0);
+    for (name, file) in &rows {
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>7}  {:>7}  {:>6.2}%  {:>9}  {:>6.2}%",
+            name,
+            file.lines_found(),
+            file.lines_found() - file.lines_hit(),
+            100.0 * rate(file.lines_hit(), file.lines_found()),
+            file.functions_found(),
+            100.0 * rate(file.functions_hit(), file.functions_found()),
+        );
+        functions.0 += file.functions_hit();
+        functions.1 += file.functions_found();
+    }
+
+    let _ = writeln!(out, "{}", "-".repeat(width + 50));
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:>7}  {:>7}  {:>6.2}%  {:>9}  {:>6.2}%",
+        "TOTAL",
+        data.lines_found(),
+        data.lines_found() - data.lines_hit(),
+        100.0 * data.line_rate(),
+        functions.1,
+        100.0 * rate(functions.0, functions.1),
+    );
+    out
+}
+
+/// Show `path` relative to `root` when it is inside it.
+fn display_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LCOV: &str = "\
+SF:/ws/src/parse.c
+FN:3,parse
+FN:10,unused
+FNDA:4,parse
+FNDA:0,unused
+FNF:2
+FNH:1
+DA:3,4
+DA:4,4
+DA:10,0
+DA:11,0
+LF:4
+LH:2
+end_of_record
+SF:/ws/src/lex.c
+FN:1,lex
+FNDA:1,lex
+DA:1,1
+end_of_record
+SF:/ws/src/parse.c
+FNDA:1,unused
+DA:10,1
+end_of_record
+";
+
+    #[test]
+    fn test_parse_lcov_merges_records() {
+        let data = parse_lcov(LCOV).unwrap();
+        assert_eq!(data.files.len(), 2);
+
+        let parse = &data.files[Path::new("/ws/src/parse.c")];
+        assert_eq!(parse.lines[&3], 4);
+        assert_eq!(parse.lines[&10], 1);
+        assert_eq!(parse.lines_hit(), 3);
+        assert_eq!(
+            parse.functions["unused"],
+            FunctionCoverage { line: 10, count: 1 }
+        );
+
+        assert_eq!(data.lines_found(), 5);
+        assert_eq!(data.lines_hit(), 4);
+        assert!(parse_lcov("SF:/a.c\nDA:x,1\nend_of_record\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_gcov_json() {
+        let output = r#"{"format_version": "1", "current_working_directory": "/ws", "files": [{"file": "src/util.h", "lines": [{"line_number": 2, "count": 3, "branches": []}], "functions": [{"name": "_Z3maxii", "demangled_name": "max(int, int)", "start_line": 2, "execution_count": 3}]}]}
+{"format_version": "1", "current_working_directory": "/ws", "files": [{"file": "/ws/src/util.h", "lines": [{"line_number": 2, "count": 1}, {"line_number": 5, "count": 0}], "functions": [{"name": "_Z3maxii", "demangled_name": "max(int, int)", "start_line": 2, "execution_count": 1}]}]}
+"#;
+        let data = parse_gcov_json(output).unwrap();
+        let util = &data.files[Path::new("/ws/src/util.h")];
+        assert_eq!(util.lines[&2], 4);
+        assert_eq!(util.lines[&5], 0);
+        assert_eq!(util.functions["max(int, int)"].count, 4);
+    }
+
+    #[test]
+    fn test_write_lcov_round_trip() {
+        let data = parse_lcov(LCOV).unwrap();
+        let written = write_lcov(&data);
+        assert!(written.contains("SF:/ws/src/parse.c\nFN:3,parse\nFN:10,unused\n"));
+        assert!(written.contains("LF:4\nLH:3\nend_of_record\n"));
+        assert_eq!(parse_lcov(&written).unwrap(), data);
+    }
+
+    #[test]
+    fn test_write_cobertura() {
+        let data = parse_lcov(LCOV).unwrap();
+        let xml = write_cobertura(&data, Path::new("/ws"), 1700000000);
+
+        assert!(xml.contains("lines-covered=\"4\" lines-valid=\"5\""));
+        assert!(xml.contains("<source>/ws</source>"));
+        assert!(xml.contains("<package name=\"src\" line-rate=\"0.8000\""));
+        assert!(
+            xml.contains("<class name=\"parse.c\" filename=\"src/parse.c\" line-rate=\"0.7500\"")
+        );
+        assert!(xml.contains("<line number=\"11\" hits=\"0\" branch=\"false\"/>"));
+        assert!(xml.contains("<method name=\"parse\""));
+    }
+
+    #[test]
+    fn test_format_summary() {
+        let data = parse_lcov(LCOV).unwrap();
+        let summary = format_summary(&data, Path::new("/ws"));
+        let lines: Vec<&str> = summary.lines().collect();
+
+        assert!(lines[0].starts_with("Filename"));
+        assert!(lines[2].starts_with("src/lex.c"));
+        assert!(lines[2].contains("100.00%"));
+        assert!(lines[3].starts_with("src/parse.c"));
+        assert!(lines[3].contains("75.00%"));
+        assert!(lines[5].starts_with("TOTAL"));
+        assert!(lines[5].contains("80.00%"));
+    }
+}
@@ -3,13 +3,16 @@
 //! Creates a self-contained bundle of a shared library and its runtime dependencies
 //! for FFI consumption by other languages.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-#[cfg_attr(target_os = "windows", allow(unused_imports))]
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 
+use crate::builder::bindings::{CEnum, CFunction, CStruct, CTypedef};
 use crate::builder::shim::{DiscoveredSurface, LibraryKind};
+use crate::util::binary::{self, BinaryFormat, LibrarySearch, RuntimeDeps};
+use crate::util::fs::{copy_with_links, symlink_chain};
+use crate::util::process::{find_executable, ProcessBuilder};
 
 /// Options for creating an FFI bundle.
 #[derive(Debug, Clone)]
@@ -20,7 +23,7 @@ pub struct BundleOptions {
     /// Include transitive runtime dependencies
     pub include_transitive: bool,
 
-    /// Rewrite RPATH to $ORIGIN (Linux) or @executable_path (macOS)
+    /// Rewrite RPATH to $ORIGIN (Linux) or @loader_path (macOS)
     pub rpath_rewrite: bool,
 
     /// Copy debug symbols if available
@@ -31,6 +34,13 @@ pub struct BundleOptions {
 
     /// Dry run - don't actually copy files
     pub dry_run: bool,
+
+    /// Extra directories to find runtime dependencies in
+    pub lib_dirs: Vec<PathBuf>,
+
+    /// Root of the target system, for telling system libraries apart when
+    /// cross-compiling
+    pub sysroot: Option<PathBuf>,
 }
 
 impl Default for BundleOptions {
@@ -42,6 +52,8 @@ impl Default for BundleOptions {
             include_debug: false,
             create_manifest: true,
             dry_run: false,
+            lib_dirs: Vec::new(),
+            sysroot: None,
         }
     }
 }
@@ -72,6 +84,18 @@ impl BundleOptions {
         self.dry_run = dry_run;
         self
     }
+
+    /// Add a directory to find runtime dependencies in.
+    pub fn with_lib_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.lib_dirs.push(dir.into());
+        self
+    }
+
+    /// Set the target system's root directory.
+    pub fn with_sysroot(mut self, sysroot: impl Into<PathBuf>) -> Self {
+        self.sysroot = Some(sysroot.into());
+        self
+    }
 }
 
 /// Result of creating an FFI bundle.
@@ -156,7 +180,19 @@ pub fn create_ffi_bundle(
 
     // Add runtime dependencies
     if opts.include_transitive {
-        for dep_path in &surface.runtime_deps {
+        let found = collect_runtime_deps(&primary_lib.path, &library_search(surface, opts))?;
+        for name in &found.missing {
+            tracing::warn!("Runtime dependency not found: {}", name);
+        }
+
+        let mut dep_paths = surface.runtime_deps.clone();
+        for dep in found.libraries {
+            if !dep_paths.iter().any(|p| p.file_name() == dep.file_name()) {
+                dep_paths.push(dep);
+            }
+        }
+
+        for dep_path in &dep_paths {
             if dep_path.exists() {
                 let dest = opts
                     .output_dir
@@ -187,7 +223,7 @@ pub fn create_ffi_bundle(
     // Copy files
     let mut total_size = 0u64;
     let mut runtime_deps = Vec::new();
-    let install_names = HashMap::new();
+    let mut install_names = HashMap::new();
 
     for file in &files_to_bundle {
         total_size += file.size;
@@ -199,7 +235,8 @@ pub fn create_ffi_bundle(
                 file.destination.display()
             );
         } else {
-            std::fs::copy(&file.source, &file.destination).with_context(|| {
+            // Versioned libraries keep their symlink chain (libfoo.so -> libfoo.so.1 -> ...)
+            copy_with_links(&file.source, &opts.output_dir).with_context(|| {
                 format!(
                     "failed to copy {} -> {}",
                     file.source.display(),
@@ -221,15 +258,23 @@ pub fn create_ffi_bundle(
 
     // Rewrite RPATH if requested
     if opts.rpath_rewrite && !opts.dry_run {
+        let mut bundled: HashSet<String> = HashSet::new();
+        for file in &files_to_bundle {
+            for path in symlink_chain(&file.destination)? {
+                if let Some(name) = path.file_name() {
+                    bundled.insert(name.to_string_lossy().to_string());
+                }
+            }
+        }
+
         for file in &files_to_bundle {
             if file.kind == BundledFileKind::PrimaryLib || file.kind == BundledFileKind::RuntimeDep
             {
-                if let Err(e) = rewrite_rpath(&file.destination) {
-                    tracing::warn!(
-                        "Failed to rewrite RPATH for {}: {}",
-                        file.destination.display(),
-                        e
-                    );
+                let renamed = rewrite_rpath(&file.destination, &bundled).with_context(|| {
+                    format!("failed to rewrite RPATH for {}", file.destination.display())
+                })?;
+                if let Some((old, new)) = renamed {
+                    install_names.insert(PathBuf::from(old), new);
                 }
             }
         }
@@ -291,7 +336,7 @@ pub struct BundleManifest {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub exports: Vec<ExportedFunction>,
 
-    /// Type definitions (structs, enums)
+    /// Type definitions (structs, unions, enums)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub types: Vec<TypeDefinition>,
 
@@ -335,12 +380,12 @@ impl BundleManifest {
 }
 
 /// Get platform string for the current target.
-fn get_platform_string() -> String {
+pub(crate) fn get_platform_string() -> String {
     format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
 }
 
 /// An exported function signature.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ExportedFunction {
     /// Function name
     pub name: String,
@@ -369,7 +414,7 @@ fn default_calling_convention() -> String {
 }
 
 /// A function parameter.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct FunctionParam {
     /// Parameter name (may be empty)
     pub name: String,
@@ -378,8 +423,8 @@ pub struct FunctionParam {
     pub param_type: String,
 }
 
-/// A type definition (struct, enum, or typedef).
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// A type definition (struct, union, enum, or typedef).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "kind")]
 pub enum TypeDefinition {
     /// Struct definition
@@ -389,9 +434,30 @@ pub enum TypeDefinition {
         name: String,
         /// Struct fields
         fields: Vec<StructField>,
-        /// Whether this is a packed struct
-        #[serde(default)]
-        packed: bool,
+        /// Maximum field alignment, for packed structs
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pack: Option<u64>,
+        /// Size in bytes on the bundle's platform
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        /// Alignment in bytes on the bundle's platform
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        align: Option<u64>,
+    },
+
+    /// Union definition
+    #[serde(rename = "union")]
+    Union {
+        /// Union name
+        name: String,
+        /// Union members
+        fields: Vec<StructField>,
+        /// Size in bytes on the bundle's platform
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        /// Alignment in bytes on the bundle's platform
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        align: Option<u64>,
     },
 
     /// Enum definition
@@ -414,7 +480,7 @@ pub enum TypeDefinition {
 }
 
 /// A struct field.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct StructField {
     /// Field name
     pub name: String,
@@ -425,10 +491,19 @@ pub struct StructField {
     /// Bit width for bitfields
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bit_width: Option<u32>,
+
+    /// Byte offset on the bundle's platform; for bitfields, the byte
+    /// holding the first bit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+
+    /// Bit position within the byte at `offset`, for bitfields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bit_offset: Option<u32>,
 }
 
 /// An enum variant.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct EnumVariant {
     /// Variant name
     pub name: String,
@@ -452,247 +527,295 @@ pub struct ExportedConstant {
     pub const_type: Option<String>,
 }
 
-/// Rewrite RPATH/runpath to be relative.
-///
-/// On Linux, sets RPATH to $ORIGIN.
-/// On macOS, updates install_name and @rpath references.
-fn rewrite_rpath(lib_path: &Path) -> Result<()> {
-    #[cfg(target_os = "linux")]
-    {
-        rewrite_rpath_linux(lib_path)
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        rewrite_rpath_macos(lib_path)
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        // Windows doesn't use RPATH - DLLs are found via PATH or same directory
-        let _ = lib_path;
-        Ok(())
-    }
-
-    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
-    {
-        let _ = lib_path;
-        tracing::warn!("RPATH rewriting not supported on this platform");
-        Ok(())
+impl From<&CFunction> for ExportedFunction {
+    fn from(func: &CFunction) -> Self {
+        ExportedFunction {
+            name: func.name.clone(),
+            return_type: func.return_type.as_c(),
+            params: func
+                .params
+                .iter()
+                .map(|p| FunctionParam {
+                    name: p.name.clone(),
+                    param_type: p.param_type.as_c(),
+                })
+                .collect(),
+            calling_convention: func.calling_convention.as_koffi().to_string(),
+            variadic: func.variadic,
+            doc: func.doc.clone(),
+        }
     }
 }
 
-#[cfg(target_os = "linux")]
-fn rewrite_rpath_linux(lib_path: &Path) -> Result<()> {
-    use std::process::Command;
-
-    // Check if patchelf is available
-    let patchelf_check = Command::new("patchelf").arg("--version").output();
-
-    if patchelf_check.is_err() {
-        tracing::debug!("patchelf not found, skipping RPATH rewrite");
-        return Ok(());
-    }
-
-    // Set RPATH to $ORIGIN
-    let status = Command::new("patchelf")
-        .args(["--set-rpath", "$ORIGIN", lib_path.to_str().unwrap()])
-        .status()
-        .context("failed to run patchelf")?;
+impl From<&CStruct> for TypeDefinition {
+    /// Fields carry offsets when the struct's layout has been computed.
+    fn from(def: &CStruct) -> Self {
+        let layout = def.layout.as_ref();
+        let fields = def
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let placed = layout.and_then(|l| l.fields.get(i));
+                StructField {
+                    name: field.name.clone(),
+                    field_type: field.field_type.as_c(),
+                    bit_width: field.bit_width,
+                    offset: placed.map(|p| p.offset),
+                    bit_offset: placed.and_then(|p| p.bit_offset),
+                }
+            })
+            .collect();
 
-    if !status.success() {
-        bail!("patchelf failed with exit code: {:?}", status.code());
+        if def.is_union() {
+            TypeDefinition::Union {
+                name: def.name.clone(),
+                fields,
+                size: layout.map(|l| l.size),
+                align: layout.map(|l| l.align),
+            }
+        } else {
+            TypeDefinition::Struct {
+                name: def.name.clone(),
+                fields,
+                pack: def.pack,
+                size: layout.map(|l| l.size),
+                align: layout.map(|l| l.align),
+            }
+        }
     }
-
-    tracing::debug!("Set RPATH to $ORIGIN for {}", lib_path.display());
-    Ok(())
 }
 
-#[cfg(target_os = "macos")]
-fn rewrite_rpath_macos(lib_path: &Path) -> Result<()> {
-    use std::process::Command;
-
-    // Get current install name
-    let output = Command::new("otool")
-        .args(["-D", lib_path.to_str().unwrap()])
-        .output()
-        .context("failed to run otool")?;
-
-    if !output.status.success() {
-        bail!("otool failed");
+impl From<&CEnum> for TypeDefinition {
+    /// Every variant gets its value, including implicit ones.
+    fn from(def: &CEnum) -> Self {
+        TypeDefinition::Enum {
+            name: def.name.clone(),
+            variants: def
+                .values()
+                .into_iter()
+                .map(|(name, value)| EnumVariant {
+                    name: name.to_string(),
+                    value: Some(value),
+                })
+                .collect(),
+        }
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.lines().collect();
-
-    if lines.len() < 2 {
-        return Ok(()); // No install name to change
+impl From<&CTypedef> for TypeDefinition {
+    fn from(def: &CTypedef) -> Self {
+        TypeDefinition::Typedef {
+            name: def.name.clone(),
+            underlying_type: def.underlying_type.as_c(),
+        }
     }
+}
 
-    let old_install_name = lines[1].trim();
-    let filename = lib_path
-        .file_name()
-        .context("no filename")?
-        .to_string_lossy();
-    let new_install_name = format!("@rpath/{}", filename);
-
-    if old_install_name != new_install_name {
-        // Change install name
-        let status = Command::new("install_name_tool")
-            .args(["-id", &new_install_name, lib_path.to_str().unwrap()])
-            .status()
-            .context("failed to run install_name_tool")?;
-
-        if !status.success() {
-            bail!("install_name_tool failed");
+impl TypeDefinition {
+    /// The type's name.
+    pub fn name(&self) -> &str {
+        match self {
+            TypeDefinition::Struct { name, .. }
+            | TypeDefinition::Union { name, .. }
+            | TypeDefinition::Enum { name, .. }
+            | TypeDefinition::Typedef { name, .. } => name,
         }
-
-        tracing::debug!(
-            "Changed install name: {} -> {}",
-            old_install_name,
-            new_install_name
-        );
     }
-
-    // Add @loader_path to rpath
-    let _ = Command::new("install_name_tool")
-        .args(["-add_rpath", "@loader_path", lib_path.to_str().unwrap()])
-        .status();
-
-    Ok(())
 }
 
-/// Collect runtime dependencies for a shared library.
-///
-/// This uses platform-specific tools (ldd, otool, dumpbin) to find
-/// the shared libraries that will be needed at runtime.
-pub fn collect_runtime_deps(lib_path: &Path) -> Result<Vec<PathBuf>> {
-    #[cfg(target_os = "linux")]
-    {
-        collect_runtime_deps_linux(lib_path)
+/// Search the build's library directories, plus any given in `opts`.
+fn library_search(surface: &DiscoveredSurface, opts: &BundleOptions) -> LibrarySearch {
+    let mut search = LibrarySearch::new();
+    for lib in &surface.libraries {
+        if let Some(dir) = lib.path.parent() {
+            search = search.with_lib_dir(dir);
+        }
     }
-
-    #[cfg(target_os = "macos")]
-    {
-        collect_runtime_deps_macos(lib_path)
+    for flag in &surface.ldflags {
+        if let Some(dir) = flag.strip_prefix("-L") {
+            search = search.with_lib_dir(dir);
+        }
     }
-
-    #[cfg(target_os = "windows")]
-    {
-        collect_runtime_deps_windows(lib_path)
+    for dir in &opts.lib_dirs {
+        search = search.with_lib_dir(dir);
     }
-
-    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
-    {
-        let _ = lib_path;
-        Ok(Vec::new())
+    if let Some(ref sysroot) = opts.sysroot {
+        search = search.with_sysroot(sysroot);
     }
+    search
 }
 
-#[cfg(target_os = "linux")]
-fn collect_runtime_deps_linux(lib_path: &Path) -> Result<Vec<PathBuf>> {
-    use std::process::Command;
-
-    let output = Command::new("ldd")
-        .arg(lib_path)
-        .output()
-        .context("failed to run ldd")?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut deps = Vec::new();
-
-    for line in stdout.lines() {
-        // Parse ldd output: "libfoo.so.1 => /usr/lib/libfoo.so.1 (0x...)"
-        if let Some(arrow_pos) = line.find("=>") {
-            let after_arrow = &line[arrow_pos + 2..];
-            if let Some(path_end) = after_arrow.find(" (") {
-                let path = after_arrow[..path_end].trim();
-                if !path.is_empty() && !path.starts_with("linux-") {
-                    // Skip virtual DSOs like linux-vdso.so
-                    deps.push(PathBuf::from(path));
-                }
+/// Point a bundled library's run path at the bundle directory.
+///
+/// ELF libraries get a `$ORIGIN` RUNPATH. Mach-O libraries get an
+/// `@rpath/` install name, `@rpath/` references to the other bundled
+/// libraries and an `@loader_path` rpath. PE files need no changes, since
+/// Windows looks for DLLs next to the library that loads them.
+///
+/// Returns the library's install name change, if any.
+fn rewrite_rpath(lib_path: &Path, bundled: &HashSet<String>) -> Result<Option<(String, String)>> {
+    let info = binary::inspect(lib_path)?;
+    let is_bundled = |name: &str| {
+        Path::new(name)
+            .file_name()
+            .is_some_and(|n| bundled.contains(n.to_string_lossy().as_ref()))
+    };
+    let needs_bundle = info.needed.iter().any(|name| is_bundled(name));
+
+    match info.format {
+        BinaryFormat::Elf => {
+            // Libraries with no run path and nothing bundled to find are fine as-is
+            if !needs_bundle && info.runpath.is_empty() {
+                return Ok(None);
+            }
+            if info.runpath == ["$ORIGIN"] {
+                return Ok(None);
             }
-        }
-    }
 
-    Ok(deps)
-}
+            // Run paths are rewritten in place; Harbour links shared
+            // libraries with a `$ORIGIN` run path to reserve the room.
+            // Libraries from recipes, vcpkg or prebuilt packages may have
+            // none, and adding one grows the string table, which needs patchelf
+            if let Err(e) = binary::set_runpath(lib_path, "$ORIGIN") {
+                let Some(patchelf) = find_executable("patchelf") else {
+                    if needs_bundle {
+                        return Err(e.context(
+                            "install patchelf to add a run path to libraries Harbour did not link",
+                        ));
+                    }
+                    tracing::warn!("Leaving run path of {}: {:#}", lib_path.display(), e);
+                    return Ok(None);
+                };
+                ProcessBuilder::new(patchelf)
+                    .args(["--set-rpath", "$ORIGIN"])
+                    .arg(lib_path)
+                    .exec_and_check()?;
+            }
 
-#[cfg(target_os = "macos")]
-fn collect_runtime_deps_macos(lib_path: &Path) -> Result<Vec<PathBuf>> {
-    use std::process::Command;
-
-    let output = Command::new("otool")
-        .args(["-L", lib_path.to_str().unwrap()])
-        .output()
-        .context("failed to run otool")?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut deps = Vec::new();
-
-    for line in stdout.lines().skip(1) {
-        // Skip first line (library itself)
-        let line = line.trim();
-        if let Some(paren_pos) = line.find(" (") {
-            let path = &line[..paren_pos].trim();
-            if !path.starts_with("@")
-                && !path.starts_with("/usr/lib/")
-                && !path.starts_with("/System/")
+            tracing::debug!("Set RUNPATH to $ORIGIN for {}", lib_path.display());
+            Ok(None)
+        }
+        BinaryFormat::MachO => {
+            let file_name = lib_path
+                .file_name()
+                .context("no filename")?
+                .to_string_lossy()
+                .to_string();
+
+            // Keep a versioned install name (libfoo.1.dylib) when it is bundled
+            let install_file = info
+                .soname
+                .as_deref()
+                .and_then(|old| Path::new(old).file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .filter(|n| bundled.contains(n))
+                .unwrap_or(file_name);
+
+            let mut renamed = None;
+            if let Some(old) = info
+                .soname
+                .filter(|old| *old != format!("@rpath/{}", install_file))
             {
-                // Skip system libraries and @rpath references
-                deps.push(PathBuf::from(path));
+                let new = format!("@rpath/{}", install_file);
+                binary::set_install_name(lib_path, &new)?;
+                tracing::debug!("Changed install name: {} -> {}", old, new);
+                renamed = Some((old, new));
             }
-        }
-    }
 
-    Ok(deps)
-}
-
-#[cfg(target_os = "windows")]
-fn collect_runtime_deps_windows(lib_path: &Path) -> Result<Vec<PathBuf>> {
-    use std::process::Command;
-
-    // Try dumpbin if available (from Visual Studio)
-    let output = Command::new("dumpbin")
-        .args(["/DEPENDENTS", lib_path.to_str().unwrap()])
-        .output();
-
-    if let Ok(output) = output {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let mut deps = Vec::new();
-
-            let mut in_deps_section = false;
-            for line in stdout.lines() {
-                if line.contains("Image has the following dependencies:") {
-                    in_deps_section = true;
-                    continue;
+            for name in info.needed.iter().filter(|name| is_bundled(name)) {
+                let dep_file = Path::new(name).file_name().unwrap().to_string_lossy();
+                let new = format!("@rpath/{}", dep_file);
+                if *name != new {
+                    binary::change_dependency(lib_path, name, &new)?;
                 }
-                if in_deps_section {
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() {
-                        break;
-                    }
-                    if trimmed.ends_with(".dll") || trimmed.ends_with(".DLL") {
-                        // Note: dumpbin only gives names, not paths
-                        // Would need to search PATH to find actual locations
-                        deps.push(PathBuf::from(trimmed));
-                    }
+            }
+
+            if needs_bundle {
+                binary::add_rpath(lib_path, "@loader_path")?;
+            }
+
+            // Edits invalidate the signature, and arm64 macOS won't load unsigned code
+            if info.signed {
+                if cfg!(target_os = "macos") {
+                    ProcessBuilder::new("codesign")
+                        .args(["--force", "--sign", "-"])
+                        .arg(lib_path)
+                        .exec_and_check()?;
+                } else {
+                    tracing::warn!(
+                        "{} must be re-signed (`codesign --force --sign -`) before use",
+                        lib_path.display()
+                    );
                 }
             }
 
-            return Ok(deps);
+            Ok(renamed)
         }
+        BinaryFormat::Pe => Ok(None),
     }
+}
 
-    // Fallback: no dependency analysis
-    tracing::debug!("dumpbin not available, cannot analyze DLL dependencies");
-    Ok(Vec::new())
+/// Collect runtime dependencies for a shared library.
+///
+/// Dependencies are read from the library itself and resolved against the
+/// search's library directories, transitively. System libraries are left
+/// out, and names that can't be found are reported as missing.
+pub fn collect_runtime_deps(lib_path: &Path, search: &LibrarySearch) -> Result<RuntimeDeps> {
+    search.runtime_deps(lib_path)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::builder::shim::LibraryInfo;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_types_from_header() {
+        use crate::builder::bindings::{
+            CEnumVariant, CField, CParam, CType, ParsedHeader, TargetAbi,
+        };
+
+        let mut header = ParsedHeader::new("api.h");
+        header.functions.push(
+            CFunction::new("api_open", CType::Pointer(Box::new(CType::Void))).with_param(
+                CParam::new("path", CType::ConstPointer(Box::new(CType::Char))),
+            ),
+        );
+        header.structs.push(
+            CStruct::new("api_point")
+                .with_field(CField::new("tag", CType::Char))
+                .with_field(CField::new("x", CType::Int32)),
+        );
+        header.enums.push(
+            CEnum::new("api_mode")
+                .with_variant(CEnumVariant::new("API_READ"))
+                .with_variant(CEnumVariant::new("API_WRITE")),
+        );
+        header.compute_layouts(&TargetAbi::host(), None).unwrap();
+
+        let func = ExportedFunction::from(&header.functions[0]);
+        assert_eq!(func.return_type, "void*");
+        assert_eq!(func.params[0].param_type, "const char*");
+        assert_eq!(func.calling_convention, "cdecl");
+
+        match TypeDefinition::from(&header.structs[0]) {
+            TypeDefinition::Struct { fields, size, .. } => {
+                assert_eq!(size, Some(8));
+                assert_eq!(fields[1].offset, Some(4));
+                assert_eq!(fields[1].field_type, "int32_t");
+            }
+            other => panic!("expected a struct, got {:?}", other),
+        }
+
+        match TypeDefinition::from(&header.enums[0]) {
+            TypeDefinition::Enum { variants, .. } => {
+                assert_eq!(variants[1].value, Some(1));
+            }
+            other => panic!("expected an enum, got {:?}", other),
+        }
+    }
 
     #[test]
     fn test_bundle_options_default() {
@@ -712,4 +835,129 @@ mod tests {
         assert!(!opts.include_transitive);
         assert!(opts.dry_run);
     }
+
+    #[test]
+    fn test_bundle_resolves_and_relinks_dependencies() {
+        let Some(cc) = find_executable("cc") else {
+            return;
+        };
+        if !cfg!(target_os = "linux") {
+            return;
+        }
+
+        let tmp = TempDir::new().unwrap();
+        let deps = tmp.path().join("deps");
+        let build = tmp.path().join("build");
+        std::fs::create_dir_all(&deps).unwrap();
+        std::fs::create_dir_all(&build).unwrap();
+
+        let compile = |dir: &Path, name: &str, body: &str, args: &[String]| {
+            let src = dir.join(format!("{}.c", name));
+            std::fs::write(&src, body).unwrap();
+            let out = dir.join(format!("lib{}.so", name));
+            ProcessBuilder::new(&cc)
+                .args(["-shared", "-fPIC", "-o"])
+                .arg(&out)
+                .arg(&src)
+                .args(args)
+                .exec_and_check()
+                .unwrap();
+            out
+        };
+        compile(
+            &deps,
+            "dep",
+            "int dep_value(void) { return 1; }\n",
+            &["-Wl,-soname,libdep.so".to_string()],
+        );
+        // Linked the way a build tree would be, with an absolute run path
+        let lib = compile(
+            &build,
+            "main",
+            "int dep_value(void);\nint main_value(void) { return dep_value(); }\n",
+            &[
+                format!("-L{}", deps.display()),
+                "-ldep".to_string(),
+                format!("-Wl,-rpath,{}", deps.display()),
+            ],
+        );
+
+        let surface = DiscoveredSurface {
+            libraries: vec![LibraryInfo {
+                name: "main".to_string(),
+                kind: LibraryKind::Shared,
+                path: lib,
+                soname: None,
+                import_lib: None,
+            }],
+            ..Default::default()
+        };
+        let out = tmp.path().join("bundle");
+        let result = create_ffi_bundle(&surface, &BundleOptions::new(&out)).unwrap();
+
+        // libc and the loader come from the system and stay out
+        assert_eq!(result.runtime_deps, vec![out.join("libdep.so")]);
+        let info = binary::inspect(&out.join("libmain.so")).unwrap();
+        assert_eq!(info.runpath, vec!["$ORIGIN"]);
+    }
+
+    #[test]
+    fn test_bundle_adds_missing_run_path() {
+        let Some(cc) = find_executable("cc") else {
+            return;
+        };
+        if !cfg!(target_os = "linux") {
+            return;
+        }
+
+        let tmp = TempDir::new().unwrap();
+        let dep_src = tmp.path().join("dep.c");
+        let main_src = tmp.path().join("main.c");
+        std::fs::write(&dep_src, "int dep_value(void) { return 1; }\n").unwrap();
+        std::fs::write(
+            &main_src,
+            "int dep_value(void);\nint main_value(void) { return dep_value(); }\n",
+        )
+        .unwrap();
+        ProcessBuilder::new(&cc)
+            .args(["-shared", "-fPIC", "-Wl,-soname,libdep.so", "-o"])
+            .arg(tmp.path().join("libdep.so"))
+            .arg(&dep_src)
+            .exec_and_check()
+            .unwrap();
+        // Linked like a prebuilt library: it needs a sibling but has no run path
+        let lib = tmp.path().join("libmain.so");
+        ProcessBuilder::new(&cc)
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(&lib)
+            .arg(&main_src)
+            .arg(format!("-L{}", tmp.path().display()))
+            .arg("-ldep")
+            .exec_and_check()
+            .unwrap();
+        assert!(binary::inspect(&lib).unwrap().runpath.is_empty());
+
+        let surface = DiscoveredSurface {
+            libraries: vec![LibraryInfo {
+                name: "main".to_string(),
+                kind: LibraryKind::Shared,
+                path: lib,
+                soname: None,
+                import_lib: None,
+            }],
+            ..Default::default()
+        };
+        let out = tmp.path().join("bundle");
+        let result = create_ffi_bundle(&surface, &BundleOptions::new(&out));
+
+        if find_executable("patchelf").is_some() {
+            result.unwrap();
+            let info = binary::inspect(&out.join("libmain.so")).unwrap();
+            assert_eq!(info.runpath, vec!["$ORIGIN"]);
+        } else {
+            // The hint names a tool the user can install rather than a relink
+            let err = format!("{:#}", result.unwrap_err());
+            assert!(err.contains("install patchelf"), "{}", err);
+        }
+    }
 }
@@ -6,7 +6,7 @@ use anyhow::{Context, Result};
 use toml_edit::{Array, DocumentMut, InlineTable, Item, Table};
 
 use crate::core::{Dependency, SourceId};
-use crate::sources::SourceCache;
+use crate::sources::{Fetcher, SourceCache};
 use crate::util::context::RegistryList;
 use crate::util::fs;
 
@@ -253,11 +253,14 @@ fn get_existing_dependency(doc: &DocumentMut, name: &str) -> Option<String> {
 /// Validate that a registry dependency exists.
 ///
 /// This performs fast index-only validation without full resolution.
-/// Returns the registries that were searched if not found.
+/// Only registries that may serve `name` are searched (see
+/// [`RegistryList::candidates`]). Returns the registries that were searched
+/// if not found.
 pub fn validate_registry_dependency(
     name: &str,
     version: Option<&str>,
     registries: &RegistryList,
+    fetcher: &Fetcher,
     cache_dir: &Path,
     offline: bool,
 ) -> Result<Option<(String, Vec<RegistryId>)>> {
@@ -268,7 +271,7 @@ pub fn validate_registry_dependency(
 
     let mut looked_in = Vec::new();
 
-    for registry in registries.enabled() {
+    for registry in registries.candidates(name) {
         looked_in.push(RegistryId {
             name: registry.name.clone(),
             url: Some(registry.url.clone()),
@@ -286,7 +289,8 @@ pub fn validate_registry_dependency(
             Err(_) => continue,
         };
 
-        let mut source_cache = SourceCache::new_with_vcpkg(cache_dir.to_path_buf(), None);
+        let mut source_cache = SourceCache::new_with_vcpkg(cache_dir.to_path_buf(), None)
+            .with_fetcher(fetcher.clone());
 
         // Create dependency for query
         let version_req: semver::VersionReq = version
@@ -0,0 +1,610 @@
+//! Implementation of `harbour bench`.
+//!
+//! Bench targets are executables built with the release profile unless asked
+//! otherwise. Each prints its measurements either as Google Benchmark JSON or
+//! as `name ... 1234 ns/iter (+/- 56)` lines. Results are saved as named
+//! baselines in `.harbour/bench/<name>.json` so later runs can be compared
+//! against them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::core::manifest::{BenchFormat, Profile};
+use crate::core::target::Target;
+use crate::core::{Package, Workspace};
+use crate::ops::harbour_build::Artifact;
+use crate::ops::harbour_run::runtime_env;
+use crate::util::process::ProcessBuilder;
+
+/// Baseline every run is saved as unless another name is given.
+pub const DEFAULT_BASELINE: &str = "latest";
+
+/// Changes smaller than this percentage are reported as noise.
+pub const DEFAULT_NOISE_THRESHOLD: f64 = 2.0;
+
+/// One measured benchmark.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+    /// Bench target the benchmark belongs to
+    pub target: String,
+
+    /// Benchmark name as printed by the executable
+    pub name: String,
+
+    /// Time per iteration in nanoseconds
+    pub ns_per_iter: f64,
+
+    /// Spread of the measurement in nanoseconds, if reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deviation: Option<f64>,
+}
+
+impl BenchResult {
+    /// Get the name qualified by the target.
+    pub fn full_name(&self) -> String {
+        format!("{}::{}", self.target, self.name)
+    }
+}
+
+/// Saved results of a benchmark run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub benchmarks: Vec<BenchResult>,
+}
+
+impl Baseline {
+    /// Add `results`, replacing earlier results of the same benchmarks.
+    pub fn merge(&mut self, results: &[BenchResult]) {
+        for result in results {
+            match self
+                .benchmarks
+                .iter_mut()
+                .find(|b| b.target == result.target && b.name == result.name)
+            {
+                Some(existing) => *existing = result.clone(),
+                None => self.benchmarks.push(result.clone()),
+            }
+        }
+    }
+
+    fn get(&self, target: &str, name: &str) -> Option<&BenchResult> {
+        self.benchmarks
+            .iter()
+            .find(|b| b.target == target && b.name == name)
+    }
+}
+
+/// Get a package's bench targets.
+pub fn bench_targets(package: &Package) -> Vec<&Target> {
+    package
+        .targets()
+        .iter()
+        .filter(|t| t.bench.is_some())
+        .collect()
+}
+
+/// Build the command that runs a built bench target.
+///
+/// Google Benchmark executables are asked for JSON. Arguments from
+/// `[targets.X.bench]` come before `args`, and the package root is the
+/// working directory.
+pub fn bench_command(
+    package: &Package,
+    target: &Target,
+    artifact: &Artifact,
+    profile: &Profile,
+    args: &[String],
+) -> Result<ProcessBuilder> {
+    let config = target.bench.clone().unwrap_or_default();
+
+    let mut cmd = ProcessBuilder::new(&artifact.path).cwd(package.root());
+    if config.format == BenchFormat::Gbench {
+        cmd = cmd.arg("--benchmark_format=json");
+    }
+    cmd = cmd.args(&config.args).args(args);
+
+    for (key, value) in runtime_env(package, artifact, profile, &BTreeMap::new())? {
+        cmd = cmd.env(&key, &value);
+    }
+    Ok(cmd)
+}
+
+/// Run a bench target and read its results.
+pub fn run_bench(cmd: &ProcessBuilder, target: &Target) -> Result<Vec<BenchResult>> {
+    let format = target.bench.as_ref().map(|b| b.format).unwrap_or_default();
+    let output = cmd.exec()?;
+    if !output.status.success() {
+        bail!(
+            "bench target `{}` failed with {}\n{}",
+            target.name,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let results = parse_bench_output(&target.name, format, &stdout)?;
+    if results.is_empty() {
+        bail!(
+            "bench target `{}` reported no results\n\
+             hint: it is read as `format = \"{}\"`; print `name ... N ns/iter` lines \
+             or set `format = \"gbench\"` in [targets.{}.bench]",
+            target.name,
+            format.as_str(),
+            target.name
+        );
+    }
+    Ok(results)
+}
+
+/// Parse the output of a bench target.
+pub fn parse_bench_output(
+    target: &str,
+    format: BenchFormat,
+    output: &str,
+) -> Result<Vec<BenchResult>> {
+    match format {
+        BenchFormat::Gbench => parse_gbench_json(target, output),
+        BenchFormat::NsIter => Ok(parse_ns_iter(target, output)),
+    }
+}
+
+/// Parse a Google Benchmark `--benchmark_format=json` report.
+///
+/// With `--benchmark_repetitions`, the `mean` and `stddev` aggregates are
+/// used; otherwise the repetitions of a benchmark are averaged.
+pub fn parse_gbench_json(target: &str, json: &str) -> Result<Vec<BenchResult>> {
+    let report: serde_json::Value =
+        serde_json::from_str(json).context("failed to parse Google Benchmark JSON output")?;
+    let entries = report
+        .get("benchmarks")
+        .and_then(|b| b.as_array())
+        .context("Google Benchmark output has no `benchmarks` array")?;
+
+    #[derive(Default)]
+    struct Samples {
+        times: Vec<f64>,
+        mean: Option<f64>,
+        stddev: Option<f64>,
+    }
+
+    let mut order: Vec<String> = Vec::new();
+    let mut samples: HashMap<String, Samples> = HashMap::new();
+    for entry in entries {
+        let str_field = |key: &str| entry.get(key).and_then(|v| v.as_str());
+        let name = str_field("run_name")
+            .or_else(|| str_field("name"))
+            .context("Google Benchmark result has no name")?;
+        if entry.get("error_occurred").and_then(|v| v.as_bool()) == Some(true) {
+            bail!(
+                "benchmark `{}` failed: {}",
+                name,
+                str_field("error_message").unwrap_or("unknown error")
+            );
+        }
+
+        let Some(time) = entry.get("real_time").and_then(|v| v.as_f64()) else {
+            continue;
+        };
+        let ns = time * time_unit_ns(str_field("time_unit").unwrap_or("ns"))?;
+
+        let sample = samples.entry(name.to_string()).or_insert_with(|| {
+            order.push(name.to_string());
+            Samples::default()
+        });
+        match (str_field("run_type"), str_field("aggregate_name")) {
+            (Some("aggregate"), Some("mean")) => sample.mean = Some(ns),
+            (Some("aggregate"), Some("stddev")) => sample.stddev = Some(ns),
+            (Some("aggregate"), _) => {}
+            _ => sample.times.push(ns),
+        }
+    }
+
+    Ok(order
+        .into_iter()
+        .filter_map(|name| {
+            let sample = samples.remove(&name)?;
+            let ns_per_iter = sample.mean.or_else(|| {
+                (!sample.times.is_empty())
+                    .then(|| sample.times.iter().sum::<f64>() / sample.times.len() as f64)
+            })?;
+            Some(BenchResult {
+                target: target.to_string(),
+                name,
+                ns_per_iter,
+                deviation: sample.stddev,
+            })
+        })
+        .collect())
+}
+
+/// Nanoseconds in a Google Benchmark `time_unit`.
+fn time_unit_ns(unit: &str) -> Result<f64> {
+    Ok(match unit {
+        "ns" => 1.0,
+        "us" => 1e3,
+        "ms" => 1e6,
+        "s" => 1e9,
+        _ => bail!("unknown Google Benchmark time unit `{}`", unit),
+    })
+}
+
+/// Parse `name ... 1234 ns/iter (+/- 56)` lines.
+///
+/// The libtest form `test name ... bench: 1,234 ns/iter (+/- 56)` is
+/// accepted as well. Other lines are ignored.
+pub fn parse_ns_iter(target: &str, output: &str) -> Vec<BenchResult> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (before, after) = line.split_once("ns/iter")?;
+            let (name, value) = before.trim_end().rsplit_once(char::is_whitespace)?;
+            let ns_per_iter = parse_number(value)?;
+
+            let name = name.trim();
+            let name = name.strip_suffix("bench:").unwrap_or(name).trim_end();
+            let name = name.trim_end_matches(['.', ':']).trim_end();
+            let name = name.strip_prefix("test ").unwrap_or(name).trim();
+            if name.is_empty() {
+                return None;
+            }
+
+            let deviation = after
+                .split_once("(+/-")
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .and_then(|(value, _)| parse_number(value));
+
+            Some(BenchResult {
+                target: target.to_string(),
+                name: name.to_string(),
+                ns_per_iter,
+                deviation,
+            })
+        })
+        .collect()
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    s.trim().replace(',', "").parse().ok()
+}
+
+/// Directory baselines are saved in.
+pub fn baseline_dir(ws: &Workspace) -> PathBuf {
+    ws.harbour_dir().join("bench")
+}
+
+/// Path of the baseline named `name`.
+pub fn baseline_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "invalid baseline name `{}`\n\
+             hint: use letters, digits, `-`, `_` and `.`",
+            name
+        );
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+/// Load the baseline named `name`.
+pub fn load_baseline(dir: &Path, name: &str) -> Result<Baseline> {
+    let path = baseline_path(dir, name)?;
+    if !path.exists() {
+        let available = saved_baselines(dir);
+        if available.is_empty() {
+            bail!(
+                "no baseline named `{}`\n\
+                 hint: save one with `harbour bench --save-baseline {}`",
+                name,
+                name
+            );
+        }
+        bail!(
+            "no baseline named `{}`\nhint: saved baselines: {}",
+            name,
+            available.join(", ")
+        );
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Save `results` into the baseline named `name`.
+///
+/// Benchmarks that did not run keep their saved results, so a filtered run
+/// does not drop the rest of the baseline.
+pub fn save_baseline(dir: &Path, name: &str, results: &[BenchResult]) -> Result<PathBuf> {
+    let path = baseline_path(dir, name)?;
+    let mut baseline = if path.exists() {
+        load_baseline(dir, name)?
+    } else {
+        Baseline::default()
+    };
+    baseline.merge(results);
+
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let json = serde_json::to_string_pretty(&baseline)?;
+    std::fs::write(&path, json).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Names of the baselines saved in `dir`.
+pub fn saved_baselines(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    let path = e.path();
+                    if path.extension().is_some_and(|ext| ext == "json") {
+                        Some(path.file_stem()?.to_string_lossy().into_owned())
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// How a benchmark changed relative to the baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    /// Slower by more than the noise threshold
+    Regression(f64),
+    /// Faster by more than the noise threshold
+    Improvement(f64),
+    /// Within the noise threshold
+    Noise(f64),
+    /// Not in the baseline
+    New,
+}
+
+/// A benchmark result compared against the baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub result: BenchResult,
+    pub baseline: Option<f64>,
+    pub change: Change,
+}
+
+/// Compare `results` against `baseline`.
+///
+/// A change is noise when it is within `threshold` percent, or when both
+/// runs report a deviation and the difference is within their sum.
+pub fn compare(results: &[BenchResult], baseline: &Baseline, threshold: f64) -> Vec<Comparison> {
+    results
+        .iter()
+        .map(|result| {
+            let Some(old) = baseline.get(&result.target, &result.name) else {
+                return Comparison {
+                    result: result.clone(),
+                    baseline: None,
+                    change: Change::New,
+                };
+            };
+
+            let diff = result.ns_per_iter - old.ns_per_iter;
+            let percent = if old.ns_per_iter > 0.0 {
+                diff / old.ns_per_iter * 100.0
+            } else {
+                0.0
+            };
+            let within_deviation = match (result.deviation, old.deviation) {
+                (Some(new_dev), Some(old_dev)) => diff.abs() <= new_dev + old_dev,
+                _ => false,
+            };
+
+            let change = if percent.abs() <= threshold || within_deviation {
+                Change::Noise(percent)
+            } else if diff > 0.0 {
+                Change::Regression(percent)
+            } else {
+                Change::Improvement(percent)
+            };
+            Comparison {
+                result: result.clone(),
+                baseline: Some(old.ns_per_iter),
+                change,
+            }
+        })
+        .collect()
+}
+
+/// Format a time in nanoseconds with precision that suits its size.
+pub fn format_ns(ns: f64) -> String {
+    if ns < 10.0 {
+        format!("{:.2}", ns)
+    } else if ns < 1000.0 {
+        format!("{:.1}", ns)
+    } else {
+        format!("{:.0}", ns)
+    }
+}
+
+/// Format results, one line per benchmark.
+pub fn format_results(results: &[BenchResult]) -> String {
+    let width = results
+        .iter()
+        .map(|r| r.full_name().len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for result in results {
+        let _ = write!(
+            out,
+            "{:<width$}  {:>12} ns/iter",
+            result.full_name(),
+            format_ns(result.ns_per_iter)
+        );
+        if let Some(deviation) = result.deviation {
+            let _ = write!(out, " (+/- {})", format_ns(deviation));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Format a comparison against a baseline, one line per benchmark.
+pub fn format_comparison(comparisons: &[Comparison]) -> String {
+    let width = comparisons
+        .iter()
+        .map(|c| c.result.full_name().len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for comparison in comparisons {
+        let _ = write!(
+            out,
+            "{:<width$}  {:>12} ns/iter  ",
+            comparison.result.full_name(),
+            format_ns(comparison.result.ns_per_iter)
+        );
+        let _ = match comparison.change {
+            Change::Regression(p) => writeln!(out, "{:+.2}% regression", p),
+            Change::Improvement(p) => writeln!(out, "{:+.2}% improvement", p),
+            Change::Noise(p) => writeln!(out, "{:+.2}% within noise", p),
+            Change::New => writeln!(out, "new"),
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, ns: f64, deviation: Option<f64>) -> BenchResult {
+        BenchResult {
+            target: "parse_bench".to_string(),
+            name: name.to_string(),
+            ns_per_iter: ns,
+            deviation,
+        }
+    }
+
+    #[test]
+    fn test_parse_ns_iter() {
+        let output = "\
+running 3 benchmarks
+test parse_small ... bench:       1,234 ns/iter (+/- 56)
+parse_large   98765.5 ns/iter
+tokenize: 12 ns/iter (+/- 0.5)
+ns/iter
+done
+";
+        assert_eq!(
+            parse_ns_iter("parse_bench", output),
+            vec![
+                result("parse_small", 1234.0, Some(56.0)),
+                result("parse_large", 98765.5, None),
+                result("tokenize", 12.0, Some(0.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_gbench_json() {
+        let json = r#"{
+  "context": {"library_build_type": "release"},
+  "benchmarks": [
+    {"name": "BM_Parse/64", "run_name": "BM_Parse/64", "run_type": "iteration",
+     "real_time": 1.5, "cpu_time": 1.4, "time_unit": "us"},
+    {"name": "BM_Tokenize", "run_name": "BM_Tokenize", "run_type": "iteration",
+     "real_time": 10.0, "time_unit": "ns"},
+    {"name": "BM_Tokenize", "run_name": "BM_Tokenize", "run_type": "iteration",
+     "real_time": 14.0, "time_unit": "ns"},
+    {"name": "BM_Hash", "run_name": "BM_Hash", "run_type": "iteration",
+     "real_time": 99.0, "time_unit": "ns"},
+    {"name": "BM_Hash_mean", "run_name": "BM_Hash", "run_type": "aggregate",
+     "aggregate_name": "mean", "real_time": 100.0, "time_unit": "ns"},
+    {"name": "BM_Hash_stddev", "run_name": "BM_Hash", "run_type": "aggregate",
+     "aggregate_name": "stddev", "real_time": 2.0, "time_unit": "ns"},
+    {"name": "BM_Hash_median", "run_name": "BM_Hash", "run_type": "aggregate",
+     "aggregate_name": "median", "real_time": 98.0, "time_unit": "ns"}
+  ]
+}"#;
+        assert_eq!(
+            parse_gbench_json("parse_bench", json).unwrap(),
+            vec![
+                result("BM_Parse/64", 1500.0, None),
+                result("BM_Tokenize", 12.0, None),
+                result("BM_Hash", 100.0, Some(2.0)),
+            ]
+        );
+
+        let failed = r#"{"benchmarks": [{"name": "BM_Io", "error_occurred": true,
+            "error_message": "no input file"}]}"#;
+        let err = parse_gbench_json("parse_bench", failed).unwrap_err();
+        assert!(err.to_string().contains("no input file"));
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = Baseline {
+            benchmarks: vec![
+                result("slower", 100.0, None),
+                result("faster", 100.0, None),
+                result("steady", 100.0, None),
+                result("noisy", 100.0, Some(5.0)),
+            ],
+        };
+        let results = vec![
+            result("slower", 110.0, None),
+            result("faster", 80.0, None),
+            result("steady", 101.0, None),
+            result("noisy", 108.0, Some(4.0)),
+            result("added", 50.0, None),
+        ];
+
+        let changes: Vec<Change> = compare(&results, &baseline, DEFAULT_NOISE_THRESHOLD)
+            .into_iter()
+            .map(|c| c.change)
+            .collect();
+        assert!(matches!(changes[0], Change::Regression(p) if (p - 10.0).abs() < 1e-9));
+        assert!(matches!(changes[1], Change::Improvement(p) if (p + 20.0).abs() < 1e-9));
+        assert!(matches!(changes[2], Change::Noise(_)));
+        assert!(matches!(changes[3], Change::Noise(_)));
+        assert_eq!(changes[4], Change::New);
+    }
+
+    #[test]
+    fn test_save_and_load_baseline() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("bench");
+
+        let err = load_baseline(&dir, "main").unwrap_err();
+        assert!(err.to_string().contains("--save-baseline main"));
+        assert!(baseline_path(&dir, "../main").is_err());
+
+        save_baseline(
+            &dir,
+            "main",
+            &[result("a", 1.0, None), result("b", 2.0, None)],
+        )
+        .unwrap();
+        save_baseline(&dir, "main", &[result("b", 3.0, Some(0.1))]).unwrap();
+
+        let baseline = load_baseline(&dir, "main").unwrap();
+        assert_eq!(
+            baseline.benchmarks,
+            vec![result("a", 1.0, None), result("b", 3.0, Some(0.1))]
+        );
+        assert_eq!(saved_baselines(&dir), vec!["main"]);
+
+        let err = load_baseline(&dir, "feature").unwrap_err();
+        assert!(err.to_string().contains("saved baselines: main"));
+    }
+}
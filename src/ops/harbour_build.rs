@@ -3,16 +3,22 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 
+use crate::builder::diagnostics::{DiagnosticFormat, DiagnosticReporter};
 use crate::builder::shim::{
     BackendAvailability, BackendId, BackendRegistry, BuildIntent, LinkagePreference, TargetTriple,
 };
+use crate::builder::toolchain::ToolchainPlatform;
 use crate::builder::{BuildContext, BuildPlan, NativeBuilder};
 use crate::core::target::CppStandard;
 use crate::core::workspace::WorkspaceMember;
 use crate::core::{Package, Workspace};
-use crate::ops::resolve::{resolve_workspace_with_opts, ResolveOptions};
+use crate::ops::harbour_coverage::COVERAGE_PROFILE;
+use crate::ops::harbour_fuzz::FUZZ_PROFILE;
+use crate::ops::resolve::{
+    resolve_workspace_with_opts, warn_yanked_and_deprecated, ResolveOptions,
+};
 use crate::resolver::{CppConstraints, Resolve};
 use crate::sources::SourceCache;
 use crate::util::config::VcpkgConfig;
@@ -46,6 +52,23 @@ fn validate_target_filter(packages: &[&Package], targets: &[String]) -> Result<(
     Ok(())
 }
 
+/// Reject fuzz targets in a normal build; they only link against libFuzzer.
+fn reject_fuzz_targets(packages: &[&Package], targets: &[String]) -> Result<()> {
+    let fuzz_target = packages
+        .iter()
+        .flat_map(|p| p.targets())
+        .find(|t| t.fuzz.is_some() && targets.iter().any(|f| f == t.name.as_str()));
+    if let Some(target) = fuzz_target {
+        bail!(
+            "target `{}` is a fuzz target\n\
+             hint: build and run it with `harbour fuzz run {}`",
+            target.name,
+            target.name
+        );
+    }
+    Ok(())
+}
+
 /// Options for the build command.
 #[derive(Debug, Clone, Default)]
 pub struct BuildOptions {
@@ -90,6 +113,15 @@ pub struct BuildOptions {
 
     /// Vcpkg integration settings
     pub vcpkg: VcpkgConfig,
+
+    /// Instrument for code coverage, building into the coverage profile
+    pub coverage: bool,
+
+    /// Instrument for libFuzzer, building into the fuzz profile
+    pub fuzz: bool,
+
+    /// How compiler warnings and errors are reported
+    pub diagnostics: DiagnosticFormat,
 }
 
 /// Select workspace members based on the filter.
@@ -156,6 +188,9 @@ pub struct Artifact {
 
     /// Target name
     pub target: String,
+
+    /// Library search paths the artifact was linked with
+    pub lib_dirs: Vec<PathBuf>,
 }
 
 /// Build the workspace.
@@ -272,6 +307,66 @@ pub fn build(
         tracing::info!("Cross-compiling for {}", triple);
     }
 
+    // Store intent for potential later use (e.g., FFI bundling)
+    let _ = intent;
+
+    let (build_ctx, plan) = plan_build(ws, source_cache, opts)?;
+
+    // If only emitting plan, return early
+    if opts.emit_plan {
+        let plan_json = serde_json::to_string_pretty(&plan)?;
+        println!("{}", plan_json);
+
+        return Ok(BuildResult {
+            artifacts: vec![],
+            plan: Some(plan),
+        });
+    }
+
+    // Emit compile_commands.json if requested (enabled by default for IDE support)
+    if opts.emit_compile_commands {
+        // Put in .harbour/ directory
+        let harbour_dir = ws.root().join(".harbour");
+        std::fs::create_dir_all(&harbour_dir).ok();
+        let cc_path = harbour_dir.join("compile_commands.json");
+        plan.emit_compile_commands(&build_ctx, &cc_path)?;
+
+        // Also create/update symlink in project root for IDE discovery
+        // (clangd, VSCode C/C++, etc. look for compile_commands.json in project root)
+        let root_cc_path = ws.root().join("compile_commands.json");
+        create_compile_commands_link(&cc_path, &root_cc_path);
+
+        tracing::info!("Wrote {}", cc_path.display());
+    }
+
+    // Execute build with C++ options if needed
+    let cxx_opts = build_ctx.cxx_options();
+    let builder = if let Some(opts) = cxx_opts {
+        NativeBuilder::with_cxx_options(&build_ctx, opts)
+    } else {
+        NativeBuilder::new(&build_ctx)
+    };
+    let reporter = DiagnosticReporter::new(build_ctx.toolchain().platform(), opts.diagnostics)
+        .with_members(ws.member_names());
+    let artifacts = builder
+        .with_diagnostics(reporter)
+        .execute(&plan, opts.jobs)?;
+
+    Ok(BuildResult {
+        artifacts,
+        plan: None,
+    })
+}
+
+/// Resolve the workspace and plan the build `opts` describe.
+///
+/// Returns the build context alongside the plan, so callers other than
+/// [`build`] run compile steps with the same flags a build would.
+pub fn plan_build(
+    ws: &Workspace,
+    source_cache: &mut SourceCache,
+    opts: &BuildOptions,
+) -> Result<(BuildContext, BuildPlan)> {
     // Select packages to build
     let selected_packages = select_packages(ws, &opts.packages)?;
 
@@ -291,6 +386,9 @@ pub fn build(
     // Validate target filter if specified
     let target_filter = if !opts.targets.is_empty() {
         validate_target_filter(&selected_packages, &opts.targets)?;
+        if !opts.fuzz {
+            reject_fuzz_targets(&selected_packages, &opts.targets)?;
+        }
         Some(opts.targets.as_slice())
     } else {
         None
@@ -301,16 +399,57 @@ pub fn build(
         locked: opts.locked,
     };
     let resolve = resolve_workspace_with_opts(ws, source_cache, &resolve_opts)?;
+    warn_yanked_and_deprecated(&resolve, source_cache);
 
-    // Store intent for potential later use (e.g., FFI bundling)
-    let _ = intent;
+    if opts.coverage && opts.fuzz {
+        bail!("a build cannot be instrumented for both coverage and fuzzing");
+    }
+    let profile = if opts.coverage {
+        COVERAGE_PROFILE
+    } else if opts.fuzz {
+        FUZZ_PROFILE
+    } else if opts.release {
+        "release"
+    } else {
+        "debug"
+    };
+
+    // Instrumented objects must not mix with normal build outputs
+    if (opts.coverage || opts.fuzz) && ws.profile() != profile {
+        bail!(
+            "instrumented builds must use the `{}` profile, not `{}`",
+            profile,
+            ws.profile()
+        );
+    }
 
     // Ensure output directory exists
     ws.ensure_output_dir()?;
 
     // Create build context
-    let profile = if opts.release { "release" } else { "debug" };
-    let mut build_ctx = BuildContext::new_with_vcpkg(ws, profile, &opts.vcpkg)?;
+    let mut build_ctx = BuildContext::new_with_vcpkg(ws, profile, &opts.vcpkg)?
+        .with_coverage(opts.coverage)
+        .with_fuzz(opts.fuzz);
+    if opts.coverage && build_ctx.coverage_flags().is_empty() {
+        bail!(
+            "code coverage is not supported with the {} toolchain",
+            build_ctx.toolchain().platform().as_str()
+        );
+    }
+    if opts.fuzz {
+        let platform = build_ctx.toolchain().platform();
+        if platform != ToolchainPlatform::Clang {
+            bail!(
+                "fuzz targets need Clang with libFuzzer, but the {} toolchain was detected\n\
+                 hint: set CC=clang and CXX=clang++",
+                platform.as_str()
+            );
+        }
+        build_ctx
+            .profile
+            .sanitizer_kinds()
+            .context("fuzz targets are built with AddressSanitizer")?;
+    }
 
     if let Some(vcpkg) = build_ctx.vcpkg() {
         tracing::info!(
@@ -344,46 +483,7 @@ pub fn build(
     // Create build plan with target filter
     let plan = BuildPlan::new(&build_ctx, &resolve, source_cache, target_filter)?;
 
-    // If only emitting plan, return early
-    if opts.emit_plan {
-        let plan_json = serde_json::to_string_pretty(&plan)?;
-        println!("{}", plan_json);
-
-        return Ok(BuildResult {
-            artifacts: vec![],
-            plan: Some(plan),
-        });
-    }
-
-    // Emit compile_commands.json if requested (enabled by default for IDE support)
-    if opts.emit_compile_commands {
-        // Put in .harbour/ directory
-        let harbour_dir = ws.root().join(".harbour");
-        std::fs::create_dir_all(&harbour_dir).ok();
-        let cc_path = harbour_dir.join("compile_commands.json");
-        plan.emit_compile_commands(&build_ctx, &cc_path)?;
-
-        // Also create/update symlink in project root for IDE discovery
-        // (clangd, VSCode C/C++, etc. look for compile_commands.json in project root)
-        let root_cc_path = ws.root().join("compile_commands.json");
-        create_compile_commands_link(&cc_path, &root_cc_path);
-
-        tracing::info!("Wrote {}", cc_path.display());
-    }
-
-    // Execute build with C++ options if needed
-    let cxx_opts = build_ctx.cxx_options();
-    let builder = if let Some(opts) = cxx_opts {
-        NativeBuilder::with_cxx_options(&build_ctx, opts)
-    } else {
-        NativeBuilder::new(&build_ctx)
-    };
-    let artifacts = builder.execute(&plan, opts.jobs)?;
-
-    Ok(BuildResult {
-        artifacts,
-        plan: None,
-    })
+    Ok((build_ctx, plan))
 }
 
 /// Create a symlink or copy of compile_commands.json in the project root.
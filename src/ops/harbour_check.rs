@@ -0,0 +1,488 @@
+//! Implementation of `harbour check`.
+//!
+//! Runs every translation unit of the selected packages through the
+//! compiler's syntax-only mode with the flags a build would use. Nothing is
+//! code-generated or linked, so this is cheap enough for editor save-hooks.
+//! Results are cached by [`CompileFingerprint`]: a unit whose source, flags
+//! and included headers are unchanged replays its previous diagnostics.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::builder::diagnostics::{CompilerDiagnostic, DiagnosticReporter};
+use crate::builder::fingerprint::CompileFingerprint;
+use crate::builder::plan::CompileStep;
+use crate::builder::toolchain::{CommandSpec, CompileInput, ToolchainPlatform};
+use crate::builder::util::parse_define_flags;
+use crate::builder::BuildContext;
+use crate::core::Workspace;
+use crate::ops::harbour_build::{plan_build, select_packages, BuildOptions};
+use crate::sources::SourceCache;
+use crate::util::fs::ensure_dir;
+use crate::util::process::ProcessBuilder;
+
+/// The outcome of checking one translation unit.
+#[derive(Debug, Clone)]
+pub struct UnitResult {
+    /// Package ID (`name vX.Y.Z`)
+    pub package_id: String,
+
+    /// Target the unit belongs to
+    pub target: String,
+
+    /// Source file
+    pub source: PathBuf,
+
+    /// Whether the result was replayed from the cache
+    pub fresh: bool,
+
+    /// Whether the compiler accepted the unit
+    pub success: bool,
+
+    /// Compiler output, with dependency-tracking noise removed
+    pub output: String,
+
+    /// Diagnostics parsed from the output
+    pub diagnostics: Vec<CompilerDiagnostic>,
+}
+
+/// Summary of a `harbour check` run.
+#[derive(Debug, Clone, Default)]
+pub struct CheckResult {
+    /// Translation units checked
+    pub units: usize,
+
+    /// Units replayed from the cache
+    pub fresh: usize,
+
+    /// Units the compiler rejected
+    pub failed: usize,
+
+    /// Errors reported
+    pub errors: usize,
+
+    /// Warnings reported
+    pub warnings: usize,
+}
+
+impl CheckResult {
+    /// Check if every unit passed.
+    pub fn success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// Cached result of a unit that passed the check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CheckedUnit {
+    fingerprint: CompileFingerprint,
+    output: String,
+}
+
+/// Check results from earlier runs, keyed by object path.
+///
+/// The object path rather than the source is the key: the same source can be
+/// compiled into several targets with different flags.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckCache {
+    units: BTreeMap<PathBuf, CheckedUnit>,
+}
+
+impl CheckCache {
+    fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Store the result of a unit that ran. Failing units are dropped so
+    /// they always run again.
+    fn record(&mut self, object: PathBuf, checked: Option<CheckedUnit>) {
+        match checked {
+            Some(checked) => {
+                self.units.insert(object, checked);
+            }
+            None => {
+                self.units.remove(&object);
+            }
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            ensure_dir(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// Get the path of the check cache for the workspace's profile.
+pub fn check_cache_path(ws: &Workspace) -> PathBuf {
+    ws.output_dir().join("check").join("fingerprints.json")
+}
+
+/// Syntax-check the selected packages.
+///
+/// Diagnostics are reported as each translation unit finishes, in
+/// `opts.diagnostics` format and exactly as a build reports them.
+/// Recipe-built dependencies (CMake, Meson, custom) are not built, so their
+/// headers must already exist from an earlier `harbour build`.
+pub fn check(
+    ws: &Workspace,
+    source_cache: &mut SourceCache,
+    opts: &BuildOptions,
+) -> Result<CheckResult> {
+    let selected: HashSet<String> = select_packages(ws, &opts.packages)?
+        .iter()
+        .map(|p| p.name().to_string())
+        .collect();
+
+    let (build_ctx, plan) = plan_build(ws, source_cache, opts)?;
+
+    // Export-macro headers must exist before anything includes them
+    for header in &plan.export_headers {
+        header.write()?;
+    }
+
+    let steps: Vec<&CompileStep> = plan
+        .compile_steps
+        .iter()
+        .filter(|s| selected.contains(&s.package))
+        .collect();
+
+    let cache_path = check_cache_path(ws);
+    let mut cache = CheckCache::load(&cache_path);
+
+    // 0 lets rayon pick the number of CPUs
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(opts.jobs.unwrap_or(0))
+        .build()
+        .context("failed to create check thread pool")?;
+
+    let reporter = DiagnosticReporter::new(build_ctx.toolchain().platform(), opts.diagnostics)
+        .with_members(ws.member_names());
+
+    let checked: Vec<(PathBuf, UnitResult, Option<CheckedUnit>)> = pool.install(|| {
+        steps
+            .par_iter()
+            .map(|step| {
+                let package_id = plan.package_id(&step.package);
+                let cached = cache.units.get(&step.output);
+                let (mut unit, checked) = check_unit(&build_ctx, step, package_id, cached)?;
+                unit.diagnostics = reporter.report(
+                    &step.package,
+                    &unit.package_id,
+                    &unit.target,
+                    &unit.output,
+                    unit.success,
+                );
+                Ok((step.output.clone(), unit, checked))
+            })
+            .collect::<Result<_>>()
+    })?;
+    reporter.finish();
+
+    // Units outside this run's packages and targets keep their entries
+    let mut result = CheckResult::default();
+    for (object, unit, checked) in checked {
+        result.units += 1;
+        result.fresh += usize::from(unit.fresh);
+        result.failed += usize::from(!unit.success);
+        result.errors += unit.diagnostics.iter().filter(|d| d.is_error()).count();
+        result.warnings += unit.diagnostics.iter().filter(|d| !d.is_error()).count();
+
+        cache.record(object, checked);
+    }
+    cache.save(&cache_path)?;
+
+    Ok(result)
+}
+
+/// Check one unit, or replay its cached result if nothing changed.
+fn check_unit(
+    build_ctx: &BuildContext,
+    step: &CompileStep,
+    package_id: String,
+    cached: Option<&CheckedUnit>,
+) -> Result<(UnitResult, Option<CheckedUnit>)> {
+    let toolchain = build_ctx.toolchain();
+    let platform = toolchain.platform();
+
+    let mut cflags = build_ctx.profile_cflags();
+    cflags.extend(step.cflags.iter().cloned());
+
+    let input = CompileInput {
+        source: step.source.clone(),
+        output: step.output.clone(),
+        include_dirs: step.include_dirs.clone(),
+        defines: parse_define_flags(&step.defines),
+        cflags,
+        visibility: step.visibility,
+    };
+    let spec = toolchain.syntax_check_command(&input, step.lang, build_ctx.cxx_options().as_ref());
+    let compiler = spec.program.display().to_string();
+
+    let unit = |fresh: bool, success: bool, output: String| UnitResult {
+        package_id: package_id.clone(),
+        target: step.target.clone(),
+        source: step.source.clone(),
+        fresh,
+        success,
+        // Filled in when the unit is reported
+        diagnostics: Vec::new(),
+        output,
+    };
+
+    // Fingerprint against the headers the unit included last time
+    if let Some(cached) = cached {
+        let headers: Vec<PathBuf> = cached.fingerprint.header_hashes.keys().cloned().collect();
+        let current = CompileFingerprint::for_source(
+            &step.source,
+            &compiler,
+            &spec.args,
+            &headers,
+            step.lang,
+        )?;
+        if cached.fingerprint.matches(&current) {
+            tracing::debug!("Fresh {}", step.source.display());
+            return Ok((
+                unit(true, true, cached.output.clone()),
+                Some(cached.clone()),
+            ));
+        }
+    }
+
+    let dep_file = step.output.with_extension("d");
+    if let Some(parent) = dep_file.parent() {
+        ensure_dir(parent)?;
+    }
+    let args = spec.args.clone();
+    let cmd = process_builder(with_dependency_tracking(spec, platform, &dep_file));
+
+    tracing::debug!("Checking {}", step.source.display());
+    let output = cmd.exec()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let (text, headers) = match platform {
+        // cl writes diagnostics and `/showIncludes` notes to stdout
+        ToolchainPlatform::Msvc => {
+            let (text, headers) = split_show_includes(&stdout, &step.source);
+            (text + &stderr, headers)
+        }
+        ToolchainPlatform::Gcc | ToolchainPlatform::Clang | ToolchainPlatform::AppleClang => {
+            let headers = std::fs::read_to_string(&dep_file)
+                .map(|content| parse_dep_file(&content))
+                .unwrap_or_default();
+            (stderr.into_owned(), headers)
+        }
+    };
+
+    if !output.status.success() {
+        return Ok((unit(false, false, text), None));
+    }
+
+    let fingerprint =
+        CompileFingerprint::for_source(&step.source, &compiler, &args, &headers, step.lang)?;
+    let checked = CheckedUnit {
+        fingerprint,
+        output: text.clone(),
+    };
+    Ok((unit(false, true, text), Some(checked)))
+}
+
+/// Ask the compiler to report the headers a unit includes.
+fn with_dependency_tracking(
+    spec: CommandSpec,
+    platform: ToolchainPlatform,
+    dep_file: &Path,
+) -> CommandSpec {
+    match platform {
+        ToolchainPlatform::Msvc => spec.arg("/showIncludes"),
+        ToolchainPlatform::Gcc | ToolchainPlatform::Clang | ToolchainPlatform::AppleClang => spec
+            .arg("-MMD")
+            .arg("-MF")
+            .arg(dep_file.display().to_string()),
+    }
+}
+
+fn process_builder(spec: CommandSpec) -> ProcessBuilder {
+    let mut cmd = ProcessBuilder::new(&spec.program);
+    for arg in spec.args {
+        cmd = cmd.arg(arg);
+    }
+    for (key, value) in spec.env {
+        cmd = cmd.env(key, value);
+    }
+    cmd
+}
+
+/// Parse the headers out of a Make-style dependency file.
+///
+/// The first prerequisite is the source itself and is skipped.
+fn parse_dep_file(content: &str) -> Vec<PathBuf> {
+    let joined = content.replace("\\\r\n", " ").replace("\\\n", " ");
+    let Some((_, prerequisites)) = joined.split_once(": ") else {
+        return Vec::new();
+    };
+
+    // Spaces inside a path are escaped as `\ `
+    let mut paths = Vec::new();
+    let mut current = String::new();
+    for token in prerequisites.split_whitespace() {
+        match token.strip_suffix('\\') {
+            Some(part) => {
+                current.push_str(part);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(token);
+                paths.push(PathBuf::from(std::mem::take(&mut current)));
+            }
+        }
+    }
+    paths.into_iter().skip(1).collect()
+}
+
+/// Separate `/showIncludes` notes from the rest of cl's output.
+///
+/// cl also echoes the source file name first, which is dropped.
+fn split_show_includes(stdout: &str, source: &Path) -> (String, Vec<PathBuf>) {
+    const PREFIX: &str = "Note: including file:";
+
+    let file_name = source.file_name().map(|n| n.to_string_lossy());
+    let mut text = String::new();
+    let mut headers = Vec::new();
+    for (i, line) in stdout.lines().enumerate() {
+        if let Some(header) = line.strip_prefix(PREFIX) {
+            headers.push(PathBuf::from(header.trim()));
+        } else if i == 0 && file_name.as_deref() == Some(line.trim()) {
+            continue;
+        } else {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    (text, headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::toolchain::Language;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_dep_file() {
+        let content = "\
+target/debug/obj/main.o: src/main.c include/parse.h \\
+ /usr/include/stdio.h \\
+ include/with\\ space.h
+";
+        assert_eq!(
+            parse_dep_file(content),
+            vec![
+                PathBuf::from("include/parse.h"),
+                PathBuf::from("/usr/include/stdio.h"),
+                PathBuf::from("include/with space.h"),
+            ]
+        );
+        assert!(parse_dep_file("main.o: src/main.c\n").is_empty());
+        assert!(parse_dep_file("").is_empty());
+    }
+
+    #[test]
+    fn test_split_show_includes() {
+        let stdout = "\
+main.c
+Note: including file: C:\\src\\include\\parse.h
+Note: including file:  C:\\Program Files\\VC\\include\\stdio.h
+C:\\src\\main.c(3,36): error C2065: 'y': undeclared identifier
+";
+        let (text, headers) = split_show_includes(stdout, Path::new("C:/src/main.c"));
+        assert_eq!(
+            text,
+            "C:\\src\\main.c(3,36): error C2065: 'y': undeclared identifier\n"
+        );
+        assert_eq!(
+            headers,
+            vec![
+                PathBuf::from("C:\\src\\include\\parse.h"),
+                PathBuf::from("C:\\Program Files\\VC\\include\\stdio.h"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_cache_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("main.c");
+        std::fs::write(&source, "int main(void) { return 0; }\n").unwrap();
+
+        let fingerprint =
+            CompileFingerprint::for_source(&source, "cc", &[], &[], Language::C).unwrap();
+        let mut cache = CheckCache::default();
+        cache.units.insert(
+            tmp.path().join("main.o"),
+            CheckedUnit {
+                fingerprint: fingerprint.clone(),
+                output: "main.c:1:1: warning: unused\n".to_string(),
+            },
+        );
+
+        let path = tmp.path().join("check").join("fingerprints.json");
+        cache.save(&path).unwrap();
+
+        let loaded = CheckCache::load(&path);
+        let unit = &loaded.units[&tmp.path().join("main.o")];
+        assert!(unit.fingerprint.matches(&fingerprint));
+        assert_eq!(unit.output, "main.c:1:1: warning: unused\n");
+
+        // A missing or corrupt cache starts empty
+        std::fs::write(&path, "not json").unwrap();
+        assert!(CheckCache::load(&path).units.is_empty());
+        assert!(CheckCache::load(&tmp.path().join("missing.json"))
+            .units
+            .is_empty());
+    }
+
+    #[test]
+    fn test_check_cache_keeps_other_units() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("main.c");
+        std::fs::write(&source, "int main(void) { return 0; }\n").unwrap();
+        let checked = || CheckedUnit {
+            fingerprint: CompileFingerprint::for_source(&source, "cc", &[], &[], Language::C)
+                .unwrap(),
+            output: String::new(),
+        };
+        let path = tmp.path().join("fingerprints.json");
+        let core = tmp.path().join("core/obj/core.o");
+        let app = tmp.path().join("app/obj/main.o");
+
+        // `harbour check -p core`
+        let mut cache = CheckCache::load(&path);
+        cache.record(core.clone(), Some(checked()));
+        cache.save(&path).unwrap();
+
+        // `harbour check -p app` leaves core's entry alone
+        let mut cache = CheckCache::load(&path);
+        cache.record(app.clone(), Some(checked()));
+        cache.save(&path).unwrap();
+
+        let mut cache = CheckCache::load(&path);
+        assert!(cache.units.contains_key(&core));
+        assert!(cache.units.contains_key(&app));
+
+        // A unit that now fails is forgotten
+        cache.record(app.clone(), None);
+        assert!(!cache.units.contains_key(&app));
+        assert!(cache.units.contains_key(&core));
+    }
+}
@@ -0,0 +1,394 @@
+//! Implementation of `harbour coverage`.
+//!
+//! Test targets are built into a separate `coverage` profile with the
+//! toolchain's instrumentation, run through the test runner, and the
+//! counters they leave behind are turned into reports:
+//!
+//! - GCC writes a `.gcda` file next to each object, read with `gcov`
+//! - Clang writes `.profraw` files, merged with `llvm-profdata` and read
+//!   with `llvm-cov`
+//!
+//! Only sources of workspace members are counted unless dependencies are
+//! asked for.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use walkdir::WalkDir;
+
+use crate::builder::toolchain::{Toolchain, ToolchainPlatform};
+use crate::core::Workspace;
+use crate::ops::coverage_report::{
+    parse_gcov_json, parse_lcov, write_cobertura, write_lcov, CoverageData,
+};
+use crate::ops::resolve::{resolve_workspace_with_opts, ResolveOptions};
+use crate::sources::SourceCache;
+use crate::util::process::{find_executable, ProcessBuilder};
+
+/// Profile coverage builds use, so their instrumented objects and
+/// fingerprints stay apart from normal debug builds.
+pub const COVERAGE_PROFILE: &str = "coverage";
+
+/// Number of `.gcno` files passed to one `gcov` invocation.
+const GCOV_BATCH: usize = 128;
+
+/// Tools that read the counters of an instrumented build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoverageTool {
+    /// GCC's `gcov`
+    Gcov { gcov: PathBuf },
+
+    /// LLVM's `llvm-profdata` and `llvm-cov`
+    Llvm { profdata: PathBuf, cov: PathBuf },
+}
+
+impl CoverageTool {
+    /// Find the coverage tools matching `toolchain`.
+    ///
+    /// Tools next to the compiler are preferred, with the compiler's
+    /// prefix and version suffix, e.g. `gcov-13` for `gcc-13`.
+    pub fn find(toolchain: &dyn Toolchain) -> Result<Self> {
+        let compiler = toolchain.compiler_path();
+        match toolchain.platform() {
+            ToolchainPlatform::Gcc => Ok(CoverageTool::Gcov {
+                gcov: find_tool(compiler, "gcc", "gcov")?,
+            }),
+            ToolchainPlatform::Clang | ToolchainPlatform::AppleClang => Ok(CoverageTool::Llvm {
+                profdata: find_tool(compiler, "clang", "llvm-profdata")?,
+                cov: find_tool(compiler, "clang", "llvm-cov")?,
+            }),
+            ToolchainPlatform::Msvc => {
+                bail!("code coverage is not supported with the msvc toolchain")
+            }
+        }
+    }
+}
+
+/// Find `tool` for the compiler at `compiler`, named like `compiler_name`.
+fn find_tool(compiler: &Path, compiler_name: &str, tool: &str) -> Result<PathBuf> {
+    let candidates = tool_names(compiler, compiler_name, tool);
+
+    if let Some(dir) = compiler.parent() {
+        if let Some(path) = candidates.iter().map(|n| dir.join(n)).find(|p| p.is_file()) {
+            return Ok(path);
+        }
+    }
+    candidates
+        .iter()
+        .find_map(|name| find_executable(name))
+        .with_context(|| {
+            format!(
+                "`{}` not found\nhint: install it alongside `{}` to collect coverage",
+                tool,
+                compiler.display()
+            )
+        })
+}
+
+/// Names `tool` may have for a compiler, most specific first.
+///
+/// For `/usr/bin/x86_64-linux-gnu-gcc-13` and `gcov` these are
+/// `x86_64-linux-gnu-gcov-13`, `gcov-13` and `gcov`.
+fn tool_names(compiler: &Path, compiler_name: &str, tool: &str) -> Vec<String> {
+    let stem = compiler
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut names = Vec::new();
+    if let Some(index) = stem.find(compiler_name) {
+        let prefix = &stem[..index];
+        let suffix = &stem[index + compiler_name.len()..];
+        names.push(format!("{}{}{}", prefix, tool, suffix));
+        names.push(format!("{}{}", tool, suffix));
+    }
+    names.push(tool.to_string());
+    names.dedup();
+    names
+}
+
+/// Directory the LLVM runtime writes `.profraw` files to.
+pub fn profile_dir(ws: &Workspace) -> PathBuf {
+    ws.output_dir().join("profraw")
+}
+
+/// Remove counters left by earlier runs so they are not counted twice.
+pub fn clean_counters(ws: &Workspace) -> Result<()> {
+    let profraw = profile_dir(ws);
+    if profraw.exists() {
+        std::fs::remove_dir_all(&profraw)
+            .with_context(|| format!("failed to remove {}", profraw.display()))?;
+    }
+    for gcda in files_with_extension(&ws.output_dir(), "gcda") {
+        std::fs::remove_file(&gcda)
+            .with_context(|| format!("failed to remove {}", gcda.display()))?;
+    }
+    Ok(())
+}
+
+/// Environment variables for running instrumented test executables.
+pub fn coverage_env(tool: &CoverageTool, ws: &Workspace) -> BTreeMap<String, String> {
+    let mut env = BTreeMap::new();
+    if let CoverageTool::Llvm { .. } = tool {
+        // One file per process and binary, so parallel tests don't race
+        let pattern = profile_dir(ws).join("%p-%m.profraw");
+        env.insert(
+            "LLVM_PROFILE_FILE".to_string(),
+            pattern.display().to_string(),
+        );
+    }
+    env
+}
+
+/// Read the counters of the test executables that were run.
+///
+/// `binaries` are the instrumented executables; LLVM needs them to map
+/// counters back to source lines. GCC's counters are found next to the
+/// objects in the coverage profile's output directory.
+pub fn collect_coverage(
+    tool: &CoverageTool,
+    ws: &Workspace,
+    binaries: &[PathBuf],
+) -> Result<CoverageData> {
+    match tool {
+        CoverageTool::Gcov { gcov } => {
+            let notes = files_with_extension(&ws.output_dir(), "gcno");
+            if notes.is_empty() {
+                bail!("no coverage notes found in {}", ws.output_dir().display());
+            }
+            // Batched to stay under command line length limits
+            let mut data = CoverageData::default();
+            for batch in notes.chunks(GCOV_BATCH) {
+                let output = ProcessBuilder::new(gcov)
+                    .args(["--json-format", "--stdout"])
+                    .args(batch)
+                    .exec_and_check()?;
+                data.merge(parse_gcov_json(&String::from_utf8_lossy(&output.stdout))?);
+            }
+            Ok(data)
+        }
+        CoverageTool::Llvm { profdata, cov } => {
+            let raw = files_with_extension(&profile_dir(ws), "profraw");
+            if raw.is_empty() {
+                bail!(
+                    "the tests wrote no coverage profiles to {}",
+                    profile_dir(ws).display()
+                );
+            }
+            let Some((first, rest)) = binaries.split_first() else {
+                bail!("no instrumented executables to read coverage from");
+            };
+
+            let merged = profile_dir(ws).join("merged.profdata");
+            ProcessBuilder::new(profdata)
+                .args(["merge", "-sparse", "-o"])
+                .arg(&merged)
+                .args(&raw)
+                .exec_and_check()?;
+
+            let mut export = ProcessBuilder::new(cov)
+                .args(["export", "-format=lcov"])
+                .arg(format!("-instr-profile={}", merged.display()))
+                .arg(first);
+            for binary in rest {
+                export = export.arg("-object").arg(binary);
+            }
+            let output = export.exec_and_check()?;
+            parse_lcov(&String::from_utf8_lossy(&output.stdout))
+        }
+    }
+}
+
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Which source files count towards coverage.
+///
+/// A file counts when the closest enclosing root is an included one, so a
+/// dependency vendored inside a member is still left out.
+#[derive(Debug, Clone, Default)]
+pub struct SourceFilter {
+    include: Vec<PathBuf>,
+    exclude: Vec<PathBuf>,
+}
+
+impl SourceFilter {
+    /// Count the sources of workspace members, and of every resolved
+    /// dependency with `include_deps`. Build outputs never count.
+    pub fn new(ws: &Workspace, source_cache: &mut SourceCache, include_deps: bool) -> Result<Self> {
+        let mut filter = SourceFilter::default();
+        for member in ws.members() {
+            filter.include.push(canonical(&member.dir));
+        }
+        filter.exclude.push(canonical(ws.target_dir()));
+
+        let resolve = resolve_workspace_with_opts(ws, source_cache, &ResolveOptions::default())?;
+        for pkg_id in resolve.topological_order() {
+            let Ok(package) = source_cache.load_package(pkg_id) else {
+                continue;
+            };
+            let root = canonical(package.root());
+            if filter.include.contains(&root) {
+                continue;
+            }
+            if include_deps {
+                filter.include.push(root);
+            } else {
+                filter.exclude.push(root);
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Check whether `path` counts towards coverage.
+    pub fn matches(&self, path: &Path) -> bool {
+        let depth = |roots: &[PathBuf]| {
+            roots
+                .iter()
+                .filter(|root| path.starts_with(root))
+                .map(|root| root.components().count())
+                .max()
+        };
+        match (depth(&self.include), depth(&self.exclude)) {
+            (Some(include), Some(exclude)) => include > exclude,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Paths of the written coverage reports.
+#[derive(Debug, Clone)]
+pub struct CoverageReports {
+    /// lcov tracefile
+    pub lcov: PathBuf,
+
+    /// Cobertura XML
+    pub cobertura: PathBuf,
+}
+
+/// Write `lcov.info` and `cobertura.xml` to `dir`.
+///
+/// Cobertura file names are relative to `root`.
+pub fn write_reports(data: &CoverageData, dir: &Path, root: &Path) -> Result<CoverageReports> {
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+    let reports = CoverageReports {
+        lcov: dir.join("lcov.info"),
+        cobertura: dir.join("cobertura.xml"),
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    std::fs::write(&reports.lcov, write_lcov(data))
+        .with_context(|| format!("failed to write {}", reports.lcov.display()))?;
+    std::fs::write(&reports.cobertura, write_cobertura(data, root, timestamp))
+        .with_context(|| format!("failed to write {}", reports.cobertura.display()))?;
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_names() {
+        assert_eq!(
+            tool_names(Path::new("/usr/bin/x86_64-linux-gnu-gcc-13"), "gcc", "gcov"),
+            vec!["x86_64-linux-gnu-gcov-13", "gcov-13", "gcov"]
+        );
+        assert_eq!(
+            tool_names(Path::new("/usr/bin/clang-17"), "clang", "llvm-cov"),
+            vec!["llvm-cov-17", "llvm-cov"]
+        );
+        assert_eq!(
+            tool_names(Path::new("/usr/bin/gcc"), "gcc", "gcov"),
+            vec!["gcov"]
+        );
+        assert_eq!(
+            tool_names(Path::new("/usr/bin/cc"), "gcc", "gcov"),
+            vec!["gcov"]
+        );
+    }
+
+    #[test]
+    fn test_source_filter() {
+        let filter = SourceFilter {
+            include: vec![PathBuf::from("/ws"), PathBuf::from("/ws/libs/core")],
+            exclude: vec![
+                PathBuf::from("/ws/.harbour/target"),
+                PathBuf::from("/ws/third_party/zlib"),
+                PathBuf::from("/cache/fmt"),
+            ],
+        };
+
+        assert!(filter.matches(Path::new("/ws/src/main.c")));
+        assert!(filter.matches(Path::new("/ws/libs/core/src/core.c")));
+        assert!(!filter.matches(Path::new("/ws/third_party/zlib/inflate.c")));
+        assert!(!filter.matches(Path::new("/ws/.harbour/target/coverage/gen.c")));
+        assert!(!filter.matches(Path::new("/cache/fmt/src/format.cc")));
+        assert!(!filter.matches(Path::new("/usr/include/stdio.h")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_source_filter_through_symlink() {
+        use crate::util::GlobalContext;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let real = tmp.path().join("real");
+        std::fs::create_dir_all(real.join("src")).unwrap();
+        std::fs::write(
+            real.join("Harbour.toml"),
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n\
+             [targets.app]\nkind = \"exe\"\nsources = [\"src/*.c\"]\n",
+        )
+        .unwrap();
+        std::fs::write(real.join("src/main.c"), "int main(void) { return 0; }\n").unwrap();
+        let link = tmp.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        // The member is opened through the link, while the resolved package
+        // root and the compiler's paths are canonical
+        let ctx = GlobalContext::with_cwd(link.clone()).unwrap();
+        let ws = Workspace::new(&link.join("Harbour.toml"), &ctx).unwrap();
+        let mut cache = SourceCache::new(tmp.path().join("cache"));
+        let filter = SourceFilter::new(&ws, &mut cache, false).unwrap();
+
+        let source = real.canonicalize().unwrap().join("src/main.c");
+        assert!(filter.matches(&source));
+    }
+
+    #[test]
+    fn test_files_with_extension() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let obj = tmp.path().join("obj");
+        std::fs::create_dir_all(&obj).unwrap();
+        std::fs::write(obj.join("a.c.gcno"), "").unwrap();
+        std::fs::write(obj.join("a.c.gcda"), "").unwrap();
+        std::fs::write(obj.join("a.c.o"), "").unwrap();
+
+        assert_eq!(
+            files_with_extension(tmp.path(), "gcno"),
+            vec![obj.join("a.c.gcno")]
+        );
+        assert!(files_with_extension(&tmp.path().join("missing"), "gcda").is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+//! Implementation of `harbour fuzz`.
+//!
+//! Fuzz targets are executables whose sources define
+//! `LLVMFuzzerTestOneInput`. They are built into a separate `fuzz` profile
+//! with libFuzzer and AddressSanitizer instrumentation across the whole
+//! dependency graph, and linked against libFuzzer's `main`. Each target
+//! keeps its corpus in `fuzz/corpus/<target>` and the inputs that crashed it
+//! in `fuzz/artifacts/<target>` under the package root.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::builder::shim::SanitizerKind;
+use crate::core::manifest::Profile;
+use crate::core::target::Target;
+use crate::core::Package;
+use crate::ops::harbour_build::Artifact;
+use crate::ops::harbour_run::runtime_env;
+use crate::util::process::ProcessBuilder;
+
+/// Profile fuzz builds use, so libFuzzer-instrumented objects stay apart
+/// from normal debug builds.
+pub const FUZZ_PROFILE: &str = "fuzz";
+
+/// Executions libFuzzer spends minimizing one crashing input.
+const MINIMIZE_RUNS: u64 = 10_000;
+
+/// Prefixes libFuzzer gives the inputs it saves.
+const ARTIFACT_PREFIXES: [&str; 4] = ["crash-", "leak-", "timeout-", "oom-"];
+
+/// Options for a fuzzing run.
+#[derive(Debug, Clone, Default)]
+pub struct FuzzOptions {
+    /// Number of fuzzing processes (libFuzzer `-fork`)
+    pub jobs: Option<usize>,
+
+    /// Stop after this many seconds
+    pub max_total_time: Option<u64>,
+
+    /// Stop after this many executions
+    pub runs: Option<u64>,
+
+    /// libFuzzer flags from the command line
+    pub args: Vec<String>,
+}
+
+/// Get a package's fuzz targets.
+pub fn fuzz_targets(package: &Package) -> Vec<&Target> {
+    package
+        .targets()
+        .iter()
+        .filter(|t| t.fuzz.is_some())
+        .collect()
+}
+
+/// Find the fuzz target named `name`.
+pub fn select_fuzz_target<'a>(package: &'a Package, name: &str) -> Result<&'a Target> {
+    match package.targets().iter().find(|t| t.name.as_str() == name) {
+        Some(t) if t.fuzz.is_some() => Ok(t),
+        Some(_) => bail!(
+            "target `{}` is not a fuzz target\n\
+             hint: declare it with `kind = \"fuzz\"`",
+            name
+        ),
+        None => {
+            let names: Vec<&str> = fuzz_targets(package)
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect();
+            if names.is_empty() {
+                bail!(
+                    "no fuzz target named `{}`; package `{}` has no fuzz targets",
+                    name,
+                    package.name()
+                )
+            }
+            bail!(
+                "no fuzz target named `{}`\nhint: available fuzz targets: {}",
+                name,
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Directory holding the corpus of a fuzz target.
+pub fn corpus_dir(package: &Package, target: &str) -> PathBuf {
+    package.root().join("fuzz").join("corpus").join(target)
+}
+
+/// Directory libFuzzer writes crashing inputs of a fuzz target to.
+pub fn artifact_dir(package: &Package, target: &str) -> PathBuf {
+    package.root().join("fuzz").join("artifacts").join(target)
+}
+
+/// Build the command that fuzzes a built fuzz target.
+///
+/// Creates the corpus and artifact directories. Flags from
+/// `[targets.X.fuzz]` come before `opts.args`, and the corpus directory is
+/// last so libFuzzer adds new inputs to it.
+pub fn fuzz_command(
+    package: &Package,
+    target: &Target,
+    artifact: &Artifact,
+    profile: &Profile,
+    opts: &FuzzOptions,
+) -> Result<ProcessBuilder> {
+    let config = target.fuzz.clone().unwrap_or_default();
+    let corpus = corpus_dir(package, &target.name);
+    let artifacts = artifact_dir(package, &target.name);
+    for dir in [&corpus, &artifacts] {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+
+    let mut args = vec![format!(
+        "-artifact_prefix={}{}",
+        artifacts.display(),
+        std::path::MAIN_SEPARATOR
+    )];
+    if let Some(dict) = &config.dict {
+        let dict = package.root().join(dict);
+        if !dict.is_file() {
+            bail!("fuzz dictionary `{}` does not exist", dict.display());
+        }
+        args.push(format!("-dict={}", dict.display()));
+    }
+    if let Some(max_len) = config.max_len {
+        args.push(format!("-max_len={}", max_len));
+    }
+    if let Some(jobs) = opts.jobs.filter(|jobs| *jobs > 1) {
+        args.push(format!("-fork={}", jobs));
+    }
+    if let Some(seconds) = opts.max_total_time {
+        args.push(format!("-max_total_time={}", seconds));
+    }
+    if let Some(runs) = opts.runs {
+        args.push(format!("-runs={}", runs));
+    }
+    args.extend(config.args);
+    args.extend(opts.args.iter().cloned());
+    args.push(corpus.display().to_string());
+
+    let cmd = ProcessBuilder::new(&artifact.path)
+        .args(&args)
+        .cwd(package.root());
+    with_runtime_env(cmd, package, artifact, profile)
+}
+
+/// Build the command that shrinks a crashing input to [`minimized_path`].
+pub fn minimize_command(
+    package: &Package,
+    artifact: &Artifact,
+    profile: &Profile,
+    crash: &Path,
+) -> Result<ProcessBuilder> {
+    let cmd = ProcessBuilder::new(&artifact.path)
+        .arg("-minimize_crash=1")
+        .arg(format!("-runs={}", MINIMIZE_RUNS))
+        .arg(format!(
+            "-exact_artifact_path={}",
+            minimized_path(crash).display()
+        ))
+        .arg(crash)
+        .cwd(package.root());
+    with_runtime_env(cmd, package, artifact, profile)
+}
+
+/// Set sanitizer options and the shared library search path.
+fn with_runtime_env(
+    mut cmd: ProcessBuilder,
+    package: &Package,
+    artifact: &Artifact,
+    profile: &Profile,
+) -> Result<ProcessBuilder> {
+    let profile = profile.clone().with_sanitizer(SanitizerKind::Address);
+    for (key, value) in runtime_env(package, artifact, &profile, &BTreeMap::new())? {
+        cmd = cmd.env(&key, &value);
+    }
+    Ok(cmd)
+}
+
+/// Where the minimized form of a crashing input is written.
+pub fn minimized_path(crash: &Path) -> PathBuf {
+    let name = crash
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    crash.with_file_name(format!("minimized-{}", name))
+}
+
+/// Inputs libFuzzer saved in `dir` that are not yet minimized.
+pub fn unminimized_artifacts(dir: &Path) -> Result<Vec<PathBuf>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut artifacts = Vec::new();
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .path();
+        let is_artifact = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| ARTIFACT_PREFIXES.iter().any(|p| name.starts_with(p)));
+        if is_artifact && path.is_file() && !minimized_path(&path).exists() {
+            artifacts.push(path);
+        }
+    }
+    artifacts.sort();
+    Ok(artifacts)
+}
+
+/// Number of inputs in a corpus directory.
+pub fn corpus_size(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().filter(|e| e.path().is_file()).count())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimized_path() {
+        assert_eq!(
+            minimized_path(Path::new("/pkg/fuzz/artifacts/parse/crash-1f2e")),
+            PathBuf::from("/pkg/fuzz/artifacts/parse/minimized-crash-1f2e")
+        );
+    }
+
+    #[test]
+    fn test_unminimized_artifacts() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        for name in [
+            "crash-aa",
+            "crash-bb",
+            "minimized-crash-bb",
+            "leak-cc",
+            "timeout-dd",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.join(name), "input").unwrap();
+        }
+
+        assert_eq!(
+            unminimized_artifacts(dir).unwrap(),
+            vec![
+                dir.join("crash-aa"),
+                dir.join("leak-cc"),
+                dir.join("timeout-dd")
+            ]
+        );
+        assert!(unminimized_artifacts(&dir.join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_corpus_size() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert_eq!(corpus_size(&tmp.path().join("missing")), 0);
+        std::fs::write(tmp.path().join("a"), "1").unwrap();
+        std::fs::write(tmp.path().join("b"), "2").unwrap();
+        std::fs::create_dir(tmp.path().join("nested")).unwrap();
+        assert_eq!(corpus_size(tmp.path()), 2);
+    }
+}
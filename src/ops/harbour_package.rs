@@ -0,0 +1,384 @@
+//! Implementation of `harbour package`.
+//!
+//! Creates a reproducible source tarball of the current package, suitable
+//! for hosting and referencing from a registry shim.
+//!
+//! ## Reproducibility
+//!
+//! Two runs over the same file contents produce byte-identical archives:
+//! - Files are added in sorted order under a `<name>-<version>/` prefix
+//! - Timestamps, ownership and gzip header mtime are zeroed
+//! - Modes are normalized to `0644` (or `0755` for executables)
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use flate2::{Compression, GzBuilder};
+use glob::Pattern;
+use walkdir::WalkDir;
+
+use crate::core::{Package, Workspace};
+use crate::util::fs::ensure_dir;
+use crate::util::hash::sha256_bytes;
+
+/// Directories that are never packaged, wherever they appear.
+const ALWAYS_EXCLUDED_DIRS: &[&str] = &[".harbour", ".git", ".hg", ".svn"];
+
+/// Options for the package command.
+#[derive(Debug, Clone, Default)]
+pub struct PackageOptions {
+    /// Output directory for the tarball (defaults to `<target-dir>/package`)
+    pub output_dir: Option<PathBuf>,
+
+    /// Only compute the file list, don't write the tarball
+    pub list_only: bool,
+}
+
+impl PackageOptions {
+    /// Create new package options with defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the output directory.
+    pub fn with_output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(output_dir.into());
+        self
+    }
+
+    /// Only list the files that would be packaged.
+    pub fn with_list_only(mut self, list_only: bool) -> Self {
+        self.list_only = list_only;
+        self
+    }
+}
+
+/// Result of packaging.
+#[derive(Debug, Clone)]
+pub struct PackageResult {
+    /// Path to the tarball (not written when listing only)
+    pub tarball: PathBuf,
+
+    /// SHA256 of the tarball bytes
+    pub sha256: String,
+
+    /// Directory prefix inside the tarball (`<name>-<version>`)
+    pub prefix: String,
+
+    /// Packaged files, relative to the package root, in archive order
+    pub files: Vec<PathBuf>,
+}
+
+/// Package the workspace's root package into a reproducible `.tar.gz`.
+pub fn package(ws: &Workspace, opts: &PackageOptions) -> Result<PackageResult> {
+    if ws.is_virtual() {
+        bail!(
+            "cannot package a virtual workspace\n\
+             hint: run `harbour package` from a member package directory"
+        );
+    }
+
+    let output_dir = opts
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| ws.target_dir().join("package"));
+
+    package_dir(ws.root_package(), &output_dir, opts.list_only)
+}
+
+/// Package a single package into `output_dir`.
+pub fn package_dir(pkg: &Package, output_dir: &Path, list_only: bool) -> Result<PackageResult> {
+    let prefix = format!("{}-{}", pkg.name(), pkg.version());
+    let files = list_package_files(pkg, Some(output_dir))?;
+    let tarball = output_dir.join(format!("{}.tar.gz", prefix));
+
+    let bytes = build_tarball(pkg.root(), &prefix, &files)?;
+    let sha256 = sha256_bytes(&bytes);
+
+    if !list_only {
+        ensure_dir(output_dir)?;
+        std::fs::write(&tarball, &bytes)
+            .with_context(|| format!("failed to write {}", tarball.display()))?;
+
+        let checksum_file = output_dir.join(format!("{}.tar.gz.sha256", prefix));
+        std::fs::write(&checksum_file, format!("{}  {}.tar.gz\n", sha256, prefix))
+            .with_context(|| format!("failed to write {}", checksum_file.display()))?;
+    }
+
+    Ok(PackageResult {
+        tarball,
+        sha256,
+        prefix,
+        files,
+    })
+}
+
+/// List the files that would be packaged, relative to the package root.
+///
+/// Respects `[package] include`/`exclude`. The manifest is always included.
+/// Files under `skip_dir` (typically the tarball output directory) are skipped.
+pub fn list_package_files(pkg: &Package, skip_dir: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let root = pkg.root();
+    let metadata = pkg.manifest().package.as_ref();
+
+    let include = compile_patterns(metadata.map(|m| m.include.as_slice()).unwrap_or(&[]))?;
+    let exclude = compile_patterns(metadata.map(|m| m.exclude.as_slice()).unwrap_or(&[]))?;
+
+    let manifest_rel = pkg
+        .manifest_path()
+        .strip_prefix(root)
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let skip_dir = skip_dir.map(|d| d.canonicalize().unwrap_or_else(|_| d.to_path_buf()));
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+    let mut files = Vec::new();
+
+    let walker = WalkDir::new(root).follow_links(false).into_iter();
+    for entry in walker.filter_entry(|e| {
+        let excluded_name = e.depth() > 0
+            && e.file_type().is_dir()
+            && ALWAYS_EXCLUDED_DIRS.contains(&e.file_name().to_string_lossy().as_ref());
+        let in_skip_dir = skip_dir.as_ref().is_some_and(|skip| {
+            e.path()
+                .strip_prefix(root)
+                .map(|rel| canonical_root.join(rel).starts_with(skip))
+                .unwrap_or(false)
+        });
+        !excluded_name && !in_skip_dir
+    }) {
+        let entry = entry.with_context(|| format!("failed to walk {}", root.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let rel = entry
+            .path()
+            .strip_prefix(root)
+            .context("walked file outside package root")?
+            .to_path_buf();
+
+        if rel == manifest_rel {
+            files.push(rel);
+            continue;
+        }
+
+        if !include.is_empty() && !matches_any(&include, &rel) {
+            continue;
+        }
+        if matches_any(&exclude, &rel) {
+            continue;
+        }
+
+        files.push(rel);
+    }
+
+    files.sort_by_key(|p| archive_path(p));
+    Ok(files)
+}
+
+/// Build the gzipped tarball in memory.
+fn build_tarball(root: &Path, prefix: &str, files: &[PathBuf]) -> Result<Vec<u8>> {
+    let encoder = GzBuilder::new()
+        .mtime(0)
+        .write(Vec::new(), Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    for rel in files {
+        let path = root.join(rel);
+        let data =
+            std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(data.len() as u64);
+        header.set_mode(if is_executable(&path) { 0o755 } else { 0o644 });
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+
+        let entry_path = format!("{}/{}", prefix, archive_path(rel));
+        builder
+            .append_data(&mut header, &entry_path, data.as_slice())
+            .with_context(|| format!("failed to add {} to tarball", entry_path))?;
+    }
+
+    let encoder = builder.into_inner().context("failed to finish tarball")?;
+    encoder.finish().context("failed to compress tarball")
+}
+
+/// Compile include/exclude globs.
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|p| {
+            Pattern::new(p.trim_end_matches('/'))
+                .with_context(|| format!("invalid package include/exclude pattern: {}", p))
+        })
+        .collect()
+}
+
+/// Check whether a relative path, or any of its parent directories, matches.
+///
+/// Matching parents lets `exclude = ["tests"]` drop the whole directory.
+fn matches_any(patterns: &[Pattern], rel: &Path) -> bool {
+    rel.ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| {
+            let s = archive_path(p);
+            patterns.iter().any(|pat| pat.matches(&s))
+        })
+}
+
+/// Render a relative path with `/` separators, as stored in the archive.
+fn archive_path(rel: &Path) -> String {
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::GlobalContext;
+    use tempfile::TempDir;
+
+    fn create_package(dir: &Path, extra: &str) {
+        std::fs::write(
+            dir.join("Harbour.toml"),
+            format!(
+                r#"
+[package]
+name = "mylib"
+version = "0.3.0"
+{}
+
+[targets.mylib]
+kind = "staticlib"
+sources = ["src/**/*.c"]
+public_headers = ["include/**/*.h"]
+"#,
+                extra
+            ),
+        )
+        .unwrap();
+
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("include")).unwrap();
+        std::fs::create_dir_all(dir.join("tests")).unwrap();
+        std::fs::create_dir_all(dir.join(".harbour/target")).unwrap();
+        std::fs::write(dir.join("src/mylib.c"), "int mylib(void) { return 1; }").unwrap();
+        std::fs::write(dir.join("include/mylib.h"), "int mylib(void);").unwrap();
+        std::fs::write(dir.join("tests/t.c"), "int main(void) { return 0; }").unwrap();
+        std::fs::write(dir.join("README.md"), "# mylib").unwrap();
+        std::fs::write(dir.join(".harbour/target/junk.o"), "junk").unwrap();
+    }
+
+    fn load(dir: &Path) -> Workspace {
+        let ctx = GlobalContext::with_cwd(dir.to_path_buf()).unwrap();
+        Workspace::new(&dir.join("Harbour.toml"), &ctx).unwrap()
+    }
+
+    #[test]
+    fn test_package_skips_harbour_dir() {
+        let tmp = TempDir::new().unwrap();
+        create_package(tmp.path(), "");
+        let ws = load(tmp.path());
+
+        let files = list_package_files(ws.root_package(), None).unwrap();
+        let names: Vec<String> = files.iter().map(|f| archive_path(f)).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "Harbour.toml",
+                "README.md",
+                "include/mylib.h",
+                "src/mylib.c",
+                "tests/t.c"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_package_include_exclude() {
+        let tmp = TempDir::new().unwrap();
+        create_package(
+            tmp.path(),
+            r#"include = ["src/**", "include/**", "tests/**"]
+exclude = ["tests"]"#,
+        );
+        let ws = load(tmp.path());
+
+        let files = list_package_files(ws.root_package(), None).unwrap();
+        let names: Vec<String> = files.iter().map(|f| archive_path(f)).collect();
+
+        // Manifest is always included, README is not in `include`, tests are excluded
+        assert_eq!(
+            names,
+            vec!["Harbour.toml", "include/mylib.h", "src/mylib.c"]
+        );
+    }
+
+    #[test]
+    fn test_package_is_reproducible() {
+        let tmp = TempDir::new().unwrap();
+        create_package(tmp.path(), "");
+        let ws = load(tmp.path());
+
+        let out = tmp.path().join("dist");
+        let first = package(&ws, &PackageOptions::new().with_output_dir(&out)).unwrap();
+        assert!(first.tarball.exists());
+        assert_eq!(first.prefix, "mylib-0.3.0");
+        assert!(out.join("mylib-0.3.0.tar.gz.sha256").exists());
+
+        // Touch a file to change its mtime; the archive must not change
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(
+            tmp.path().join("src/mylib.c"),
+            "int mylib(void) { return 1; }",
+        )
+        .unwrap();
+
+        let second = package(&ws, &PackageOptions::new().with_output_dir(&out)).unwrap();
+        assert_eq!(first.sha256, second.sha256);
+        assert_eq!(
+            sha256_bytes(&std::fs::read(&second.tarball).unwrap()),
+            second.sha256
+        );
+
+        // Output directory inside the package is not packaged into itself
+        assert!(!second.files.iter().any(|f| f.starts_with("dist")));
+    }
+
+    #[test]
+    fn test_package_round_trips_through_extract() {
+        let tmp = TempDir::new().unwrap();
+        create_package(tmp.path(), "");
+        let ws = load(tmp.path());
+
+        let result = package(&ws, &PackageOptions::new()).unwrap();
+        let bytes = std::fs::read(&result.tarball).unwrap();
+
+        let dest = tmp.path().join("extracted");
+        crate::sources::registry::extract_tarball(&bytes, &dest, Some(&result.prefix)).unwrap();
+
+        assert!(dest.join("Harbour.toml").exists());
+        assert!(dest.join("src/mylib.c").exists());
+        assert!(!dest.join(".harbour").exists());
+    }
+}
@@ -0,0 +1,308 @@
+//! Implementation of `harbour publish`.
+//!
+//! Packages the current package and writes a registry shim for it into a
+//! local registry checkout:
+//!
+//! ```text
+//! <registry>/index/<letter>/<name>/<version>.toml
+//! ```
+//!
+//! The shim references the packaged tarball by URL and checksum. The tarball
+//! itself must be uploaded to that URL separately.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::core::target::Target;
+use crate::core::{Package, Workspace};
+use crate::ops::harbour_package::{package, PackageOptions, PackageResult};
+use crate::sources::registry::shim::{
+    self, Shim, ShimMetadata, ShimPackage, ShimSource, TarballSource,
+};
+use crate::sources::registry::sparse::write_version_listing;
+use crate::sources::registry::RegistryConfig;
+use crate::util::fs::write_string;
+
+/// Options for the publish command.
+#[derive(Debug, Clone)]
+pub struct PublishOptions {
+    /// Root of the local registry checkout (contains `config.toml`)
+    pub registry_path: PathBuf,
+
+    /// URL the packaged tarball will be served from
+    pub tarball_url: String,
+
+    /// Options for packaging the tarball
+    pub package: PackageOptions,
+
+    /// Generate and validate the shim without writing it
+    pub dry_run: bool,
+}
+
+impl PublishOptions {
+    /// Create new publish options.
+    pub fn new(registry_path: impl Into<PathBuf>, tarball_url: impl Into<String>) -> Self {
+        PublishOptions {
+            registry_path: registry_path.into(),
+            tarball_url: tarball_url.into(),
+            package: PackageOptions::default(),
+            dry_run: false,
+        }
+    }
+
+    /// Set the packaging options.
+    pub fn with_package_options(mut self, package: PackageOptions) -> Self {
+        self.package = package;
+        self
+    }
+
+    /// Set dry run mode.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Result of publishing.
+#[derive(Debug, Clone)]
+pub struct PublishResult {
+    /// The generated shim
+    pub shim: Shim,
+
+    /// Where the shim was (or would be) written
+    pub shim_path: PathBuf,
+
+    /// The packaged tarball
+    pub package: PackageResult,
+}
+
+/// Package the root package and publish its shim to a local registry.
+pub fn publish(ws: &Workspace, opts: &PublishOptions) -> Result<PublishResult> {
+    RegistryConfig::load(&opts.registry_path.join("config.toml")).with_context(|| {
+        format!(
+            "not a valid registry checkout: {}",
+            opts.registry_path.display()
+        )
+    })?;
+
+    let pkg = ws.root_package();
+    let name = pkg.name().to_string();
+    let version = pkg.version().to_string();
+
+    let shim_path = opts
+        .registry_path
+        .join("index")
+        .join(shim::shim_path(&name, &version)?);
+
+    if shim_path.exists() {
+        bail!(
+            "{} v{} already exists in the registry at {}\n\
+             hint: published versions are immutable; bump the version in {}",
+            name,
+            version,
+            shim_path.display(),
+            pkg.manifest_path()
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        );
+    }
+
+    let packaged = package(ws, &opts.package.clone().with_list_only(opts.dry_run))?;
+    let shim = generate_shim(pkg, &packaged, &opts.tarball_url)?;
+    shim.validate()
+        .with_context(|| format!("generated shim for {} v{} is invalid", name, version))?;
+
+    if !opts.dry_run {
+        let content = toml::to_string_pretty(&shim).context("failed to serialize shim")?;
+        write_string(&shim_path, &content)?;
+
+        if let Some(package_dir) = shim_path.parent() {
+            write_version_listing(package_dir)?;
+        }
+    }
+
+    Ok(PublishResult {
+        shim,
+        shim_path,
+        package: packaged,
+    })
+}
+
+/// Generate a registry shim for a packaged tarball.
+///
+/// `harbour package` always ships the package's Harbour.toml, which the
+/// registry reads as the source of truth, so no surface override is written.
+pub fn generate_shim(pkg: &Package, packaged: &PackageResult, tarball_url: &str) -> Result<Shim> {
+    let metadata = pkg.manifest().package.as_ref();
+    library_target(pkg)?;
+
+    let shim_metadata = ShimMetadata {
+        category: metadata.and_then(|m| m.categories.first().cloned()),
+        license: metadata.and_then(|m| m.license.clone()),
+        upstream_url: metadata.and_then(|m| m.repository.clone().or_else(|| m.homepage.clone())),
+        ..Default::default()
+    };
+
+    Ok(Shim {
+        package: ShimPackage {
+            name: pkg.name().to_string(),
+            version: pkg.version().to_string(),
+        },
+        source: ShimSource {
+            git: None,
+            tarball: Some(TarballSource {
+                url: tarball_url.to_string(),
+                sha256: packaged.sha256.clone(),
+                strip_prefix: Some(packaged.prefix.clone()),
+            }),
+        },
+        patches: Vec::new(),
+        metadata: Some(shim_metadata),
+        features: None,
+        surface_override: None,
+        surface: None,
+        build: None,
+    })
+}
+
+/// Find the library target a registry shim describes.
+fn library_target(pkg: &Package) -> Result<&Target> {
+    pkg.targets()
+        .iter()
+        .find(|t| t.kind.is_library())
+        .with_context(|| {
+            format!(
+                "package '{}' has no library target\n\
+                 hint: only libraries can be published to a registry",
+                pkg.name()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::GlobalContext;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    const TARBALL_URL: &str = "https://example.com/dl/mylib-0.3.0.tar.gz";
+
+    fn create_package(dir: &Path) {
+        std::fs::write(
+            dir.join("Harbour.toml"),
+            r#"
+[package]
+name = "mylib"
+version = "0.3.0"
+license = "MIT"
+repository = "https://github.com/example/mylib"
+categories = ["compression"]
+
+[targets.mylib]
+kind = "staticlib"
+sources = ["src/**/*.c"]
+public_headers = ["include/**/*.h"]
+
+[targets.mylib.surface.compile.public]
+include_dirs = ["include"]
+defines = ["MYLIB_STATIC", { name = "MYLIB_LEVEL", value = "2" }]
+
+[targets.mylib.surface.link.public]
+libs = ["m", { kind = "framework", name = "Security" }]
+"#,
+        )
+        .unwrap();
+
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/mylib.c"), "int mylib(void) { return 1; }").unwrap();
+    }
+
+    fn create_registry(dir: &Path) {
+        std::fs::create_dir_all(dir.join("index")).unwrap();
+        std::fs::write(
+            dir.join("config.toml"),
+            "[registry]\nname = \"test\"\nregistry_version = 1\n",
+        )
+        .unwrap();
+    }
+
+    fn setup() -> (TempDir, Workspace, PathBuf) {
+        let tmp = TempDir::new().unwrap();
+        let pkg_dir = tmp.path().join("mylib");
+        let registry = tmp.path().join("registry");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        create_package(&pkg_dir);
+        create_registry(&registry);
+
+        let ctx = GlobalContext::with_cwd(pkg_dir.clone()).unwrap();
+        let ws = Workspace::new(&pkg_dir.join("Harbour.toml"), &ctx).unwrap();
+        (tmp, ws, registry)
+    }
+
+    #[test]
+    fn test_publish_writes_shim() {
+        let (_tmp, ws, registry) = setup();
+
+        let result = publish(&ws, &PublishOptions::new(&registry, TARBALL_URL)).unwrap();
+
+        let expected = registry.join("index/m/mylib/0.3.0.toml");
+        assert_eq!(result.shim_path, expected);
+
+        // Round-trip through the shim parser (which validates)
+        let shim = Shim::load(&expected).unwrap();
+        let tarball = shim.tarball_source().unwrap();
+        assert_eq!(tarball.url, TARBALL_URL);
+        assert_eq!(tarball.sha256, result.package.sha256);
+        assert_eq!(tarball.strip_prefix.as_deref(), Some("mylib-0.3.0"));
+
+        let metadata = shim.metadata().unwrap();
+        assert_eq!(metadata.license.as_deref(), Some("MIT"));
+        assert_eq!(metadata.category.as_deref(), Some("compression"));
+
+        // The tarball ships Harbour.toml, so the shim carries no surface
+        assert!(shim.surface_override.is_none());
+
+        // Sparse version listing is kept in sync
+        let versions = std::fs::read_to_string(registry.join("index/m/mylib/versions")).unwrap();
+        assert_eq!(versions, "0.3.0\n");
+    }
+
+    #[test]
+    fn test_publish_refuses_overwrite() {
+        let (_tmp, ws, registry) = setup();
+
+        publish(&ws, &PublishOptions::new(&registry, TARBALL_URL)).unwrap();
+        let err = publish(&ws, &PublishOptions::new(&registry, TARBALL_URL)).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+    }
+
+    #[test]
+    fn test_publish_dry_run_writes_nothing() {
+        let (_tmp, ws, registry) = setup();
+
+        let opts = PublishOptions::new(&registry, TARBALL_URL).with_dry_run(true);
+        let result = publish(&ws, &opts).unwrap();
+
+        assert!(!result.shim_path.exists());
+        assert!(!result.package.tarball.exists());
+    }
+
+    #[test]
+    fn test_publish_rejects_invalid_url() {
+        let (_tmp, ws, registry) = setup();
+
+        let err = publish(&ws, &PublishOptions::new(&registry, "not a url")).unwrap_err();
+        assert!(format!("{:#}", err).contains("invalid tarball URL"));
+    }
+
+    #[test]
+    fn test_publish_requires_registry_config() {
+        let (tmp, ws, _registry) = setup();
+
+        let err = publish(&ws, &PublishOptions::new(tmp.path(), TARBALL_URL)).unwrap_err();
+        assert!(err.to_string().contains("not a valid registry checkout"));
+    }
+}
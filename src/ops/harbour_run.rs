@@ -0,0 +1,281 @@
+//! Implementation of `harbour run`.
+//!
+//! Builds one executable target and runs it with the directories of the
+//! shared libraries it links against on the platform's runtime search path,
+//! so Harbour-built shared dependencies are found without installing them.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::core::manifest::Profile;
+use crate::core::target::{Target, TargetKind};
+use crate::core::Package;
+use crate::ops::harbour_build::Artifact;
+use crate::ops::harbour_test::is_test_target;
+use crate::ops::sanitizer::sanitizer_env;
+use crate::util::process::ProcessBuilder;
+
+/// Environment variable the dynamic loader searches for shared libraries.
+pub fn runtime_path_var() -> &'static str {
+    if cfg!(windows) {
+        "PATH"
+    } else if cfg!(target_os = "macos") {
+        "DYLD_LIBRARY_PATH"
+    } else {
+        "LD_LIBRARY_PATH"
+    }
+}
+
+/// Pick the executable target `harbour run` builds.
+///
+/// With `name`, that target must exist and be an executable. Otherwise the
+/// package must have exactly one executable that is not a test, fuzz or
+/// bench target, or one named after the package.
+pub fn select_executable<'a>(package: &'a Package, name: Option<&str>) -> Result<&'a Target> {
+    if let Some(name) = name {
+        return match package.targets().iter().find(|t| t.name.as_str() == name) {
+            Some(t) if t.fuzz.is_some() => bail!(
+                "target `{}` is a fuzz target\n\
+                 hint: run it with `harbour fuzz run {}`",
+                name,
+                name
+            ),
+            Some(t) if t.kind == TargetKind::Exe => Ok(t),
+            Some(_) => bail!("target `{}` is not an executable and cannot be run", name),
+            None => bail!("no executable target named `{}`", name),
+        };
+    }
+
+    let executables: Vec<&Target> = package
+        .targets()
+        .iter()
+        .filter(|t| {
+            t.kind == TargetKind::Exe
+                && t.test.is_none()
+                && t.fuzz.is_none()
+                && t.bench.is_none()
+                && !is_test_target(&t.name)
+        })
+        .collect();
+
+    match executables.as_slice() {
+        [] => bail!(
+            "package `{}` has no executable targets to run",
+            package.name()
+        ),
+        [target] => Ok(target),
+        _ => {
+            if let Some(target) = executables
+                .iter()
+                .find(|t| t.name.as_str() == package.name().as_str())
+            {
+                return Ok(target);
+            }
+            let names: Vec<&str> = executables.iter().map(|t| t.name.as_str()).collect();
+            bail!(
+                "`harbour run` could not determine which executable to run\n\
+                 hint: use `--bin NAME`; available executables: {}",
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Directories among `lib_dirs` that hold shared libraries.
+pub fn runtime_dirs(lib_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in lib_dirs {
+        if dirs.contains(dir) {
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let has_shared = entries
+            .flatten()
+            .any(|entry| is_shared_library(&entry.path()));
+        if has_shared {
+            dirs.push(dir.clone());
+        }
+    }
+    dirs
+}
+
+/// Check for `.so` (including versioned `.so.1.2`), `.dylib` and `.dll` files.
+fn is_shared_library(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name.ends_with(".so")
+        || name.contains(".so.")
+        || name.ends_with(".dylib")
+        || name.to_ascii_lowercase().ends_with(".dll")
+}
+
+/// Prepend `dirs` to a search path such as the current `LD_LIBRARY_PATH`.
+pub fn prepend_search_path(dirs: &[PathBuf], existing: Option<OsString>) -> Result<OsString> {
+    // An empty entry would make the loader search the working directory
+    let existing = existing.unwrap_or_default();
+    let paths = dirs
+        .iter()
+        .cloned()
+        .chain(env::split_paths(&existing).filter(|p| !p.as_os_str().is_empty()));
+    env::join_paths(paths).context("library directory cannot be used in a search path")
+}
+
+/// Environment a built executable of `package` runs with.
+///
+/// Sanitizer runtime options come from `profile` and are overridden by
+/// `overrides`, a target's own `env`. The directories of the shared
+/// libraries `artifact` links against go in front of the runtime search
+/// path, whether `overrides` sets it or it is inherited.
+pub fn runtime_env(
+    package: &Package,
+    artifact: &Artifact,
+    profile: &Profile,
+    overrides: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut vars = sanitizer_env(profile, package.root())?;
+    vars.extend(overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let dirs = runtime_dirs(&artifact.lib_dirs);
+    if !dirs.is_empty() {
+        let var = runtime_path_var();
+        let existing = overrides
+            .get(var)
+            .map(OsString::from)
+            .or_else(|| env::var_os(var));
+        let search_path = prepend_search_path(&dirs, existing)?;
+        vars.insert(var.to_string(), search_path.to_string_lossy().into_owned());
+    }
+    Ok(vars)
+}
+
+/// Build the command that runs a built executable target.
+///
+/// Arguments from `[targets.X.run]` come before `args`. The working
+/// directory defaults to the current directory. Sanitizer runtime options
+/// come from `profile`, and `[targets.X.run]` env overrides them.
+pub fn run_command(
+    package: &Package,
+    target: &Target,
+    artifact: &Artifact,
+    profile: &Profile,
+    args: &[String],
+) -> Result<ProcessBuilder> {
+    let config = target.run.clone().unwrap_or_default();
+
+    let mut cmd = ProcessBuilder::new(&artifact.path)
+        .args(&config.args)
+        .args(args);
+    if let Some(dir) = &config.working_dir {
+        cmd = cmd.cwd(package.root().join(dir));
+    }
+    for (key, value) in runtime_env(package, artifact, profile, &config.env)? {
+        cmd = cmd.env(&key, &value);
+    }
+
+    Ok(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_shared_library() {
+        assert!(is_shared_library(Path::new("/out/libz.so")));
+        assert!(is_shared_library(Path::new("/out/libz.so.1.3")));
+        assert!(is_shared_library(Path::new("/out/libz.dylib")));
+        assert!(is_shared_library(Path::new("C:/out/ZLIB.DLL")));
+        assert!(!is_shared_library(Path::new("/out/libz.a")));
+        assert!(!is_shared_library(Path::new("/out/zlib.lib")));
+        assert!(!is_shared_library(Path::new("/out/libz.sources")));
+    }
+
+    #[test]
+    fn test_runtime_dirs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let shared = tmp.path().join("shared");
+        let archives = tmp.path().join("static");
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::create_dir_all(&archives).unwrap();
+        std::fs::write(shared.join("libfoo.so.1"), "").unwrap();
+        std::fs::write(archives.join("libbar.a"), "").unwrap();
+
+        let dirs = runtime_dirs(&[
+            archives,
+            shared.clone(),
+            shared.clone(),
+            tmp.path().join("missing"),
+        ]);
+        assert_eq!(dirs, vec![shared]);
+    }
+
+    #[test]
+    fn test_prepend_search_path() {
+        let dirs = vec![PathBuf::from("/deps/zlib"), PathBuf::from("/deps/png")];
+        let existing = env::join_paths([PathBuf::from("/usr/local/lib")]).unwrap();
+
+        let joined = prepend_search_path(&dirs, Some(existing)).unwrap();
+        let paths: Vec<PathBuf> = env::split_paths(&joined).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/deps/zlib"),
+                PathBuf::from("/deps/png"),
+                PathBuf::from("/usr/local/lib")
+            ]
+        );
+
+        let joined = prepend_search_path(&dirs, None).unwrap();
+        assert_eq!(env::split_paths(&joined).count(), 2);
+    }
+
+    #[test]
+    fn test_runtime_env() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            tmp.path().join("Harbour.toml"),
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n\
+             [targets.app]\nkind = \"exe\"\nsources = [\"src/*.c\"]\n",
+        )
+        .unwrap();
+        let package = Package::load(&tmp.path().join("Harbour.toml")).unwrap();
+
+        let lib_dir = tmp.path().join("lib");
+        std::fs::create_dir_all(&lib_dir).unwrap();
+        std::fs::write(lib_dir.join("libfoo.so"), "").unwrap();
+        let artifact = Artifact {
+            path: tmp.path().join("bin/app"),
+            target: "app".to_string(),
+            lib_dirs: vec![lib_dir.clone()],
+        };
+
+        let mut profile = Profile {
+            sanitizers: vec!["address".to_string(), "undefined".to_string()],
+            ..Default::default()
+        };
+        profile
+            .sanitizer_options
+            .insert("undefined".to_string(), "halt_on_error=1".to_string());
+
+        let var = runtime_path_var();
+        let overrides = BTreeMap::from([
+            ("UBSAN_OPTIONS".to_string(), "halt_on_error=0".to_string()),
+            (var.to_string(), "/opt/lib".to_string()),
+        ]);
+        let env = runtime_env(&package, &artifact, &profile, &overrides).unwrap();
+
+        // The target's own options win over the profile's
+        assert_eq!(env["UBSAN_OPTIONS"], "halt_on_error=0");
+        assert_eq!(
+            env::split_paths(&env[var]).collect::<Vec<_>>(),
+            vec![lib_dir, PathBuf::from("/opt/lib")]
+        );
+    }
+}
@@ -0,0 +1,683 @@
+//! Implementation of `harbour test`.
+//!
+//! Test targets are executables built like any other target and then run in
+//! parallel, each with its own arguments, working directory, environment and
+//! optional timeout. Output is captured and only kept for tests that fail.
+//!
+//! Tests written with a known framework report each of their cases
+//! separately; see [`crate::ops::test_report`].
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
+
+use crate::builder::shim::{TestCase, TestResult, TestStatus};
+use crate::core::manifest::{Profile, TestFramework};
+use crate::core::target::{Target, TargetKind};
+use crate::core::Package;
+use crate::ops::harbour_build::Artifact;
+use crate::ops::harbour_run::runtime_env;
+use crate::ops::test_report::{parse_report, report_invocation, ReportInvocation};
+
+/// How often a test with a timeout is polled for exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Options for running tests.
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    /// Extra arguments passed to every test executable
+    pub args: Vec<String>,
+
+    /// Number of tests run at once (default: number of CPUs)
+    pub jobs: Option<usize>,
+
+    /// Timeout overriding each target's configured timeout
+    pub timeout: Option<Duration>,
+
+    /// Let tests write to the terminal instead of capturing their output.
+    /// Tests then run one at a time so their output does not interleave.
+    /// Reports read from stdout, such as TAP, are still parsed.
+    pub no_capture: bool,
+
+    /// Only report test cases whose name contains this string. Applies to
+    /// tests with a framework; other test executables are a single case.
+    pub case_filter: Option<String>,
+}
+
+/// A built test executable and how to run it.
+#[derive(Debug, Clone)]
+pub struct TestBinary {
+    /// Test target name
+    pub name: String,
+
+    /// Path to the executable
+    pub path: PathBuf,
+
+    /// Arguments from the target's test configuration
+    pub args: Vec<String>,
+
+    /// Working directory
+    pub working_dir: PathBuf,
+
+    /// Environment variables set for the run
+    pub env: BTreeMap<String, String>,
+
+    /// Timeout from the target's test configuration
+    pub timeout: Option<Duration>,
+
+    /// Framework whose report lists the individual test cases
+    pub framework: Option<TestFramework>,
+}
+
+impl TestBinary {
+    /// Describe how to run a built test target of `package`.
+    ///
+    /// The working directory defaults to the package root, and
+    /// `HARBOUR_PACKAGE_ROOT` and `HARBOUR_TEST_NAME` are always set. Tests
+    /// run with the same sanitizer options and shared library search path
+    /// as `harbour run`; see [`runtime_env`].
+    pub fn new(
+        package: &Package,
+        target: &Target,
+        artifact: &Artifact,
+        profile: &Profile,
+    ) -> Result<Self> {
+        let config = target.test.clone().unwrap_or_default();
+
+        let mut env = BTreeMap::new();
+        env.insert(
+            "HARBOUR_PACKAGE_ROOT".to_string(),
+            package.root().display().to_string(),
+        );
+        env.insert("HARBOUR_TEST_NAME".to_string(), target.name.to_string());
+        env.extend(runtime_env(package, artifact, profile, &config.env)?);
+
+        Ok(TestBinary {
+            name: target.name.to_string(),
+            path: artifact.path.clone(),
+            args: config.args,
+            working_dir: match config.working_dir {
+                Some(dir) => package.root().join(dir),
+                None => package.root().to_path_buf(),
+            },
+            env,
+            timeout: config.timeout.map(Duration::from_secs),
+            framework: config.framework,
+        })
+    }
+}
+
+/// Check if a target name matches test patterns.
+///
+/// Test targets are identified by name patterns:
+/// - Exact matches: "test", "tests"
+/// - Suffix matches: "*_test", "*_tests"
+/// - Prefix matches: "test_*"
+pub fn is_test_target(name: &str) -> bool {
+    let name_lower = name.to_lowercase();
+    if name_lower.is_empty() || name_lower.starts_with('_') {
+        return false;
+    }
+    name_lower == "test"
+        || name_lower == "tests"
+        || name_lower.ends_with("_test")
+        || name_lower.ends_with("_tests")
+        || name_lower.starts_with("test_")
+}
+
+/// Get a package's test targets.
+///
+/// Targets declared with `kind = "test"` or `test = true` are tests, as are
+/// executables other than fuzz and bench targets whose names match
+/// [`is_test_target`].
+pub fn test_targets(package: &Package) -> Vec<&Target> {
+    package
+        .targets()
+        .iter()
+        .filter(|t| {
+            t.test.is_some()
+                || (t.kind == TargetKind::Exe
+                    && t.fuzz.is_none()
+                    && t.bench.is_none()
+                    && is_test_target(&t.name))
+        })
+        .collect()
+}
+
+/// Select the test targets to build.
+///
+/// Targets named with `--target` must be executables of `package`; with no
+/// names, every target of [`test_targets`] is selected.
+pub fn select_test_targets<'a>(package: &'a Package, names: &[String]) -> Result<Vec<&'a Target>> {
+    if names.is_empty() {
+        return Ok(test_targets(package));
+    }
+
+    let mut selected = Vec::new();
+    for name in names {
+        match package.targets().iter().find(|t| t.name.as_str() == name) {
+            Some(t) if t.kind == TargetKind::Exe => selected.push(t),
+            Some(_) => bail!(
+                "target `{}` is not an executable and cannot be run as a test",
+                name
+            ),
+            None => bail!("unknown test target `{}`", name),
+        }
+    }
+    Ok(selected)
+}
+
+/// Describe how to run each built test target.
+///
+/// Fails if a target has no artifact among `artifacts`, so a selected test
+/// is never silently skipped.
+pub fn test_binaries(
+    package: &Package,
+    targets: &[&Target],
+    artifacts: &[Artifact],
+    profile: &Profile,
+) -> Result<Vec<TestBinary>> {
+    targets
+        .iter()
+        .map(|target| {
+            let artifact = artifacts
+                .iter()
+                .find(|a| a.target == target.name.as_str())
+                .with_context(|| format!("building `{}` produced no executable", target.name))?;
+            TestBinary::new(package, target, artifact, profile)
+        })
+        .collect()
+}
+
+/// Check if a test name is selected by a `harbour test FILTER` substring.
+pub fn matches_filter(name: &str, filter: Option<&str>) -> bool {
+    filter.is_none_or(|f| name.contains(f))
+}
+
+/// Run test executables in parallel.
+///
+/// `on_complete` is called as each test case finishes. The returned result
+/// lists the test cases in the order of the executables given.
+pub fn run_tests(
+    tests: &[TestBinary],
+    opts: &TestOptions,
+    on_complete: impl Fn(&TestCase) + Sync,
+) -> Result<TestResult> {
+    let jobs = if opts.no_capture {
+        1
+    } else {
+        // 0 lets rayon pick the number of CPUs
+        opts.jobs.unwrap_or(0)
+    };
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .context("failed to create test thread pool")?;
+
+    let cases: Vec<Vec<TestCase>> = pool.install(|| {
+        tests
+            .par_iter()
+            .map(|test| {
+                let cases = run_test(test, opts);
+                cases.iter().for_each(&on_complete);
+                cases
+            })
+            .collect()
+    });
+
+    Ok(TestResult::from_cases(
+        cases.into_iter().flatten().collect(),
+    ))
+}
+
+fn run_test(test: &TestBinary, opts: &TestOptions) -> Vec<TestCase> {
+    tracing::debug!("running test `{}`: {}", test.name, test.path.display());
+
+    if let Some(framework) = test.framework {
+        return run_framework_test(test, framework, opts);
+    }
+
+    let start = Instant::now();
+    let (status, output) = match execute(test, opts, &ReportInvocation::default()) {
+        Ok(run) => (run.status, run.output()),
+        Err(e) => (TestStatus::Failed, format!("{:#}\n", e)),
+    };
+    vec![binary_case(test, status, output, start.elapsed())]
+}
+
+/// Run a framework test and report its individual cases.
+///
+/// The executable itself is reported as a failed case when it fails without
+/// any case failing, e.g. when it crashes, times out or writes no report.
+fn run_framework_test(
+    test: &TestBinary,
+    framework: TestFramework,
+    opts: &TestOptions,
+) -> Vec<TestCase> {
+    let start = Instant::now();
+    let fail = |output: String| {
+        vec![binary_case(
+            test,
+            TestStatus::Failed,
+            output,
+            start.elapsed(),
+        )]
+    };
+
+    let report_dir = match tempfile::TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => return fail(format!("could not create test report directory: {}\n", e)),
+    };
+    let invocation = report_invocation(framework, report_dir.path(), opts.case_filter.as_deref());
+    let run = match execute(test, opts, &invocation) {
+        Ok(run) => run,
+        Err(e) => return fail(format!("{:#}\n", e)),
+    };
+
+    let mut cases = match parse_report(framework, &invocation, &run.stdout) {
+        Ok(cases) => cases,
+        Err(_) if run.status == TestStatus::TimedOut => Vec::new(),
+        Err(e) => return fail(format!("{}{:#}\n", run.output(), e)),
+    };
+    cases.retain(|case| matches_filter(&case.name, opts.case_filter.as_deref()));
+    for case in &mut cases {
+        case.suite = Some(test.name.clone());
+    }
+
+    let case_failed = cases.iter().any(|c| c.status == TestStatus::Failed);
+    if run.status != TestStatus::Passed && !case_failed {
+        cases.push(binary_case(test, run.status, run.output(), start.elapsed()));
+    }
+    cases
+}
+
+/// Result for a test executable as a whole.
+fn binary_case(
+    test: &TestBinary,
+    status: TestStatus,
+    output: String,
+    duration: Duration,
+) -> TestCase {
+    TestCase {
+        name: test.name.clone(),
+        suite: None,
+        status,
+        output: (status != TestStatus::Passed).then_some(output),
+        duration: Some(duration),
+    }
+}
+
+/// Outcome of running a test executable once.
+struct Execution {
+    status: TestStatus,
+    stdout: String,
+    /// Standard error, followed by how the run ended if it failed
+    stderr: String,
+}
+
+impl Execution {
+    fn output(&self) -> String {
+        format!("{}{}", self.stdout, self.stderr)
+    }
+}
+
+/// Run one test with the extra arguments and environment of `invocation`.
+fn execute(
+    test: &TestBinary,
+    opts: &TestOptions,
+    invocation: &ReportInvocation,
+) -> Result<Execution> {
+    let mut cmd = Command::new(&test.path);
+    cmd.args(&test.args)
+        .args(&opts.args)
+        .args(&invocation.args)
+        .current_dir(&test.working_dir)
+        .envs(&test.env)
+        .envs(&invocation.env)
+        .stdin(Stdio::null());
+
+    // A report read from stdout is still captured without capture, and
+    // echoed to the terminal as it arrives
+    let tee_stdout = opts.no_capture && test.framework.is_some() && invocation.report.is_none();
+    if !opts.no_capture {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    } else if tee_stdout {
+        cmd.stdout(Stdio::piped());
+    }
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("could not execute `{}`", test.path.display()))?;
+
+    let stdout = child.stdout.take().map(|pipe| capture(pipe, tee_stdout));
+    let stderr = child.stderr.take().map(|pipe| capture(pipe, false));
+
+    let timeout = opts.timeout.or(test.timeout);
+    let exit = match timeout {
+        Some(timeout) => wait_timeout(&mut child, timeout)?,
+        None => Some(child.wait()?),
+    };
+
+    let finish =
+        |reader: Option<Capture>| reader.map(|r| r.finish(exit.is_some())).unwrap_or_default();
+    let stdout = finish(stdout);
+    let mut stderr = finish(stderr);
+
+    let status = match exit {
+        Some(exit) if exit.success() => TestStatus::Passed,
+        Some(exit) => {
+            stderr.push_str(&format!("test process {}\n", exit));
+            TestStatus::Failed
+        }
+        None => {
+            stderr.push_str(&format!(
+                "test timed out after {:.1}s\n",
+                timeout.unwrap_or_default().as_secs_f64()
+            ));
+            TestStatus::TimedOut
+        }
+    };
+
+    Ok(Execution {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Wait for a child to exit, killing it once `timeout` has passed.
+///
+/// Returns `None` if the child was killed.
+fn wait_timeout(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            child.kill().ok();
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Output read from a pipe on its own thread, so a chatty test cannot block
+/// on a full pipe buffer.
+struct Capture {
+    buf: Arc<Mutex<Vec<u8>>>,
+    handle: JoinHandle<()>,
+}
+
+impl Capture {
+    /// Collect the output. Unless `wait` is set, return what was read so far
+    /// instead of waiting for the pipe to close: processes left behind by a
+    /// killed test may keep it open.
+    fn finish(self, wait: bool) -> String {
+        if wait {
+            self.handle.join().ok();
+        }
+        let buf = self.buf.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+}
+
+/// Start capturing `pipe`, also copying it to stdout if `echo` is set.
+fn capture(mut pipe: impl Read + Send + 'static, echo: bool) -> Capture {
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&buf);
+    let handle = thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        while let Ok(n) = pipe.read(&mut chunk) {
+            if n == 0 {
+                break;
+            }
+            if echo {
+                let mut out = std::io::stdout().lock();
+                out.write_all(&chunk[..n]).ok();
+                out.flush().ok();
+            }
+            sink.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .extend_from_slice(&chunk[..n]);
+        }
+    });
+    Capture { buf, handle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell_test(name: &str, script: &str) -> TestBinary {
+        TestBinary {
+            name: name.to_string(),
+            path: PathBuf::from("sh"),
+            args: vec!["-c".to_string(), script.to_string()],
+            working_dir: std::env::temp_dir(),
+            env: BTreeMap::new(),
+            timeout: None,
+            framework: None,
+        }
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        assert!(matches_filter("parser_test", None));
+        assert!(matches_filter("parser_test", Some("parser")));
+        assert!(!matches_filter("lexer_test", Some("parser")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tests_captures_failures() {
+        let tests = vec![
+            shell_test("passes", "echo quiet"),
+            shell_test("fails", "echo loud; echo err >&2; exit 3"),
+        ];
+
+        let completed = Mutex::new(Vec::new());
+        let result = run_tests(&tests, &TestOptions::default(), |case| {
+            completed.lock().unwrap().push(case.name.clone());
+        })
+        .unwrap();
+
+        assert_eq!(result.total, 2);
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.failed, 1);
+        assert!(!result.success());
+        assert_eq!(completed.into_inner().unwrap().len(), 2);
+
+        let passed = &result.tests[0];
+        assert_eq!(passed.status, TestStatus::Passed);
+        assert!(passed.output.is_none());
+        assert!(passed.duration.is_some());
+
+        let failed = &result.tests[1];
+        assert_eq!(failed.status, TestStatus::Failed);
+        let output = failed.output.as_deref().unwrap();
+        assert!(output.contains("loud\n"));
+        assert!(output.contains("err\n"));
+        assert!(output.contains("exit status: 3"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tests_timeout() {
+        let mut test = shell_test("hangs", "sleep 5");
+        test.timeout = Some(Duration::from_secs(30));
+        let opts = TestOptions {
+            timeout: Some(Duration::from_millis(100)),
+            ..TestOptions::default()
+        };
+
+        let result = run_tests(&[test], &opts, |_| {}).unwrap();
+        let case = &result.tests[0];
+        assert_eq!(case.status, TestStatus::TimedOut);
+        assert_eq!(result.failed, 1);
+        assert!(case.duration.unwrap() < Duration::from_secs(5));
+        assert!(case
+            .output
+            .as_deref()
+            .unwrap()
+            .contains("timed out after 0.1s"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tests_env_args_and_working_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().canonicalize().unwrap();
+
+        let mut test = shell_test(
+            "env",
+            &format!(
+                "test \"$FIXTURE\" = data && test \"$(pwd -P)\" = '{}' && test \"$0\" = extra",
+                dir.display()
+            ),
+        );
+        test.working_dir = dir.clone();
+        test.env.insert("FIXTURE".to_string(), "data".to_string());
+        let opts = TestOptions {
+            args: vec!["extra".to_string()],
+            ..TestOptions::default()
+        };
+
+        let result = run_tests(&[test], &opts, |_| {}).unwrap();
+        assert!(result.success(), "{:?}", result.tests[0].output);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tests_tap_cases() {
+        let mut test = shell_test(
+            "tap",
+            "echo 1..3; echo 'ok 1 - parses'; echo 'not ok 2 - nests'; echo '# got 2'; \
+             echo 'ok 3 - skipped # SKIP later'; exit 1",
+        );
+        test.framework = Some(TestFramework::Tap);
+
+        let result = run_tests(&[test.clone()], &TestOptions::default(), |_| {}).unwrap();
+        assert_eq!(result.total, 3);
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.failed, 1);
+        assert_eq!(result.skipped, 1);
+        assert_eq!(result.tests[1].full_name(), "tap::nests");
+        assert_eq!(result.tests[1].output.as_deref(), Some("# got 2\n"));
+
+        let opts = TestOptions {
+            case_filter: Some("pars".to_string()),
+            ..TestOptions::default()
+        };
+        let result = run_tests(&[test.clone()], &opts, |_| {}).unwrap();
+        // The non-zero exit is reported once no failing case explains it
+        let names: Vec<_> = result.tests.iter().map(|c| c.full_name()).collect();
+        assert_eq!(names, vec!["tap::parses", "tap"]);
+        assert_eq!(result.tests[1].status, TestStatus::Failed);
+
+        // The report is still parsed when output goes to the terminal
+        let opts = TestOptions {
+            no_capture: true,
+            ..TestOptions::default()
+        };
+        let result = run_tests(&[test], &opts, |_| {}).unwrap();
+        assert_eq!(result.total, 3);
+        assert_eq!(result.failed, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tests_missing_report() {
+        let mut test = shell_test("gtest", "exit 0");
+        test.framework = Some(TestFramework::Gtest);
+
+        let result = run_tests(&[test], &TestOptions::default(), |_| {}).unwrap();
+        let case = &result.tests[0];
+        assert_eq!(case.name, "gtest");
+        assert_eq!(case.status, TestStatus::Failed);
+        assert!(case
+            .output
+            .as_deref()
+            .unwrap()
+            .contains("did not write a gtest report"));
+    }
+
+    #[test]
+    fn test_run_tests_missing_executable() {
+        let mut test = shell_test("missing", "");
+        test.path = PathBuf::from("/nonexistent/harbour-test-binary");
+
+        let result = run_tests(&[test], &TestOptions::default(), |_| {}).unwrap();
+        let case = &result.tests[0];
+        assert_eq!(case.status, TestStatus::Failed);
+        assert!(case
+            .output
+            .as_deref()
+            .unwrap()
+            .contains("could not execute"));
+    }
+
+    #[test]
+    fn test_select_test_targets() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let manifest_path = tmp.path().join("Harbour.toml");
+        std::fs::write(
+            &manifest_path,
+            r#"
+[package]
+name = "app"
+version = "1.0.0"
+
+[targets.core]
+kind = "staticlib"
+sources = ["src/**/*.c"]
+
+[targets.app]
+kind = "exe"
+sources = ["src/main.c"]
+
+[targets.unit_test]
+kind = "exe"
+sources = ["tests/*.c"]
+"#,
+        )
+        .unwrap();
+        let package = Package::load(&manifest_path).unwrap();
+
+        let names = |targets: Vec<&Target>| -> Vec<String> {
+            targets.iter().map(|t| t.name.to_string()).collect()
+        };
+        assert_eq!(
+            names(select_test_targets(&package, &[]).unwrap()),
+            vec!["unit_test"]
+        );
+        assert_eq!(
+            names(select_test_targets(&package, &["app".to_string()]).unwrap()),
+            vec!["app"]
+        );
+        assert!(select_test_targets(&package, &["core".to_string()])
+            .unwrap_err()
+            .to_string()
+            .contains("not an executable"));
+        assert!(select_test_targets(&package, &["missing".to_string()])
+            .unwrap_err()
+            .to_string()
+            .contains("unknown test target `missing`"));
+
+        // A selected test that was not built is an error, not a skip
+        let targets = select_test_targets(&package, &[]).unwrap();
+        let err = test_binaries(&package, &targets, &[], &Profile::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "building `unit_test` produced no executable"
+        );
+    }
+}
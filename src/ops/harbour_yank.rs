@@ -0,0 +1,185 @@
+//! Implementation of `harbour yank`.
+//!
+//! Marks a published version as yanked (or un-yanks it) by editing its shim
+//! in a local registry checkout. Formatting and comments in the shim are
+//! preserved; only `[metadata] yanked` is touched.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::sources::registry::{shim_path, RegistryConfig, Shim};
+
+/// Options for the yank command.
+#[derive(Debug, Clone)]
+pub struct YankOptions {
+    /// Root of the local registry checkout (contains `config.toml`)
+    pub registry_path: PathBuf,
+
+    /// Package name
+    pub name: String,
+
+    /// Exact version to yank
+    pub version: String,
+
+    /// Un-yank instead of yanking
+    pub undo: bool,
+}
+
+/// Result of a yank.
+#[derive(Debug, Clone)]
+pub struct YankResult {
+    /// Path of the edited shim
+    pub shim_path: PathBuf,
+
+    /// Whether the shim changed (false if it was already in the requested state)
+    pub changed: bool,
+}
+
+/// Yank or un-yank a version in a local registry checkout.
+pub fn yank(opts: &YankOptions) -> Result<YankResult> {
+    RegistryConfig::load(&opts.registry_path.join("config.toml")).with_context(|| {
+        format!(
+            "not a valid registry checkout: {}",
+            opts.registry_path.display()
+        )
+    })?;
+
+    let path = opts
+        .registry_path
+        .join("index")
+        .join(shim_path(&opts.name, &opts.version)?);
+
+    if !path.exists() {
+        bail!(
+            "{} v{} not found in registry at {}",
+            opts.name,
+            opts.version,
+            path.display()
+        );
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read shim file: {}", path.display()))?;
+    let was_yanked = Shim::parse(&content, &path)?.is_yanked();
+
+    if was_yanked != opts.undo {
+        return Ok(YankResult {
+            shim_path: path,
+            changed: false,
+        });
+    }
+
+    let mut doc: DocumentMut = content
+        .parse()
+        .with_context(|| format!("failed to parse shim file: {}", path.display()))?;
+
+    if opts.undo {
+        if let Some(metadata) = doc.get_mut("metadata").and_then(Item::as_table_mut) {
+            metadata.remove("yanked");
+        }
+    } else {
+        if !doc.contains_key("metadata") {
+            doc["metadata"] = Item::Table(Table::new());
+        }
+        doc["metadata"]["yanked"] = toml_edit::value(true);
+    }
+
+    let updated = doc.to_string();
+
+    // Never leave an unparseable shim behind
+    Shim::parse(&updated, &path)?;
+
+    std::fs::write(&path, updated)
+        .with_context(|| format!("failed to write shim file: {}", path.display()))?;
+
+    Ok(YankResult {
+        shim_path: path,
+        changed: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    const SHIM: &str = r#"# zlib 1.3.0
+[package]
+name = "zlib"
+version = "1.3.0"
+
+[source.git]
+url = "https://github.com/madler/zlib"
+rev = "04f42ceca40f73e2978b50e93806c2a18c1281fc"
+
+[metadata]
+license = "Zlib"
+"#;
+
+    fn create_registry(dir: &Path) {
+        std::fs::create_dir_all(dir.join("index/z/zlib")).unwrap();
+        std::fs::write(
+            dir.join("config.toml"),
+            "[registry]\nname = \"test\"\nregistry_version = 1\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("index/z/zlib/1.3.0.toml"), SHIM).unwrap();
+    }
+
+    fn opts(registry: &Path, undo: bool) -> YankOptions {
+        YankOptions {
+            registry_path: registry.to_path_buf(),
+            name: "zlib".to_string(),
+            version: "1.3.0".to_string(),
+            undo,
+        }
+    }
+
+    #[test]
+    fn test_yank_and_undo() {
+        let tmp = TempDir::new().unwrap();
+        create_registry(tmp.path());
+
+        let result = yank(&opts(tmp.path(), false)).unwrap();
+        assert!(result.changed);
+
+        let content = std::fs::read_to_string(&result.shim_path).unwrap();
+        assert!(content.starts_with("# zlib 1.3.0"));
+        assert!(Shim::parse(&content, &result.shim_path)
+            .unwrap()
+            .is_yanked());
+
+        // Yanking again is a no-op
+        assert!(!yank(&opts(tmp.path(), false)).unwrap().changed);
+
+        let result = yank(&opts(tmp.path(), true)).unwrap();
+        assert!(result.changed);
+        let content = std::fs::read_to_string(&result.shim_path).unwrap();
+        assert_eq!(content, SHIM);
+    }
+
+    #[test]
+    fn test_yank_adds_metadata_table() {
+        let tmp = TempDir::new().unwrap();
+        create_registry(tmp.path());
+        let path = tmp.path().join("index/z/zlib/1.3.0.toml");
+        std::fs::write(&path, SHIM.replace("[metadata]\nlicense = \"Zlib\"\n", "")).unwrap();
+
+        yank(&opts(tmp.path(), false)).unwrap();
+        assert!(Shim::load(&path).unwrap().is_yanked());
+    }
+
+    #[test]
+    fn test_yank_missing_version() {
+        let tmp = TempDir::new().unwrap();
+        create_registry(tmp.path());
+
+        let mut missing = opts(tmp.path(), false);
+        missing.version = "9.9.9".to_string();
+        let err = yank(&missing).unwrap_err();
+        assert!(err.to_string().contains("not found in registry"));
+    }
+}
@@ -65,6 +65,21 @@ fn compute_manifest_hash_from_manifest(manifest: &Manifest) -> Result<String> {
         .into();
     normalized.insert("dependencies".to_string(), deps_json);
 
+    // Dev-dependencies (only when present, so existing lockfiles stay fresh)
+    if !manifest.dev_dependencies.is_empty() {
+        let mut dev_deps: Vec<_> = manifest.dev_dependencies.iter().collect();
+        dev_deps.sort_by_key(|(name, _)| *name);
+        let dev_deps_json: serde_json::Value = dev_deps
+            .iter()
+            .map(|(name, spec)| {
+                let spec_json = serde_json::to_value(spec).unwrap_or(serde_json::Value::Null);
+                ((*name).clone(), spec_json)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into();
+        normalized.insert("dev_dependencies".to_string(), dev_deps_json);
+    }
+
     // Target deps (sorted for determinism)
     let mut targets_json = serde_json::Map::new();
     let mut targets: Vec<_> = manifest.targets.iter().collect();
@@ -387,6 +402,31 @@ version = "1.0.0"
         assert_eq!(hash1, hash2);
     }
 
+    #[test]
+    fn test_lockfile_hash_covers_dev_dependencies() {
+        let tmp = TempDir::new().unwrap();
+        let manifest_path = tmp.path().join("Harbour.toml");
+        let manifest = r#"[package]
+name = "test"
+version = "1.0.0"
+"#;
+
+        std::fs::write(&manifest_path, manifest).unwrap();
+        let hash1 = compute_manifest_hash(&manifest_path).unwrap();
+
+        std::fs::write(
+            &manifest_path,
+            format!(
+                "{}\n[dev-dependencies]\nunity = {{ path = \"unity\" }}\n",
+                manifest
+            ),
+        )
+        .unwrap();
+        let hash2 = compute_manifest_hash(&manifest_path).unwrap();
+
+        assert_ne!(hash1, hash2);
+    }
+
     #[test]
     fn test_missing_lockfile() {
         let tmp = TempDir::new().unwrap();
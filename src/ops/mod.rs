@@ -2,16 +2,31 @@
 //!
 //! This module contains the implementation of Harbour commands.
 
+pub mod abi;
+pub mod coverage_report;
 pub mod doctor;
 pub mod ffi_bundle;
 pub mod harbour_add;
+pub mod harbour_bench;
 pub mod harbour_build;
+pub mod harbour_check;
+pub mod harbour_coverage;
+pub mod harbour_fuzz;
 pub mod harbour_new;
+pub mod harbour_package;
+pub mod harbour_publish;
+pub mod harbour_run;
+pub mod harbour_test;
 pub mod harbour_update;
+pub mod harbour_yank;
 pub mod lockfile;
 pub mod resolve;
+pub mod sanitizer;
+pub mod test_report;
 pub mod verify;
 
+pub use abi::{diff_abi, version_bump, AbiChange, AbiDump, AbiReport, Bump, LibraryAbi};
+pub use coverage_report::{format_summary, CoverageData};
 pub use doctor::{doctor, format_report, DoctorOptions, DoctorReport};
 pub use ffi_bundle::{
     create_ffi_bundle, BundleManifest, BundleOptions, BundleResult, EnumVariant, ExportedConstant,
@@ -21,9 +36,22 @@ pub use harbour_add::{
     add_dependency, remove_dependency, AddOptions, AddResult, RegistryId, RemoveOptions,
     RemoveResult, SourceKind,
 };
+pub use harbour_bench::{bench_targets, compare, run_bench, BenchResult, Baseline, Change};
 pub use harbour_build::{build, BuildOptions};
+pub use harbour_check::{check, CheckResult, UnitResult};
+pub use harbour_coverage::{collect_coverage, CoverageTool, SourceFilter, COVERAGE_PROFILE};
+pub use harbour_fuzz::{fuzz_command, fuzz_targets, FuzzOptions, FUZZ_PROFILE};
 pub use harbour_new::{init_project, new_project};
+pub use harbour_package::{package, PackageOptions, PackageResult};
+pub use harbour_publish::{publish, PublishOptions, PublishResult};
+pub use harbour_run::{run_command, select_executable};
+pub use harbour_test::{
+    run_tests, select_test_targets, test_binaries, test_targets, TestBinary, TestOptions,
+};
 pub use harbour_update::update;
+pub use harbour_yank::{yank, YankOptions, YankResult};
 pub use lockfile::{load_lockfile, save_lockfile};
 pub use resolve::{resolve_workspace, resolve_workspace_with_opts, ResolveOptions};
+pub use sanitizer::{parse_sanitizer_reports, sanitizer_env};
+pub use test_report::{parse_junit, write_junit};
 pub use verify::{format_result, verify, VerifyOptions, VerifyResult};
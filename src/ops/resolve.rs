@@ -4,8 +4,10 @@ use std::collections::HashSet;
 
 use anyhow::{bail, Result};
 
-use crate::core::dependency::{resolve_dependency, warn_workspace_dep_matches_member, Dependency};
-use crate::core::Workspace;
+use crate::core::dependency::{
+    resolve_dependency, warn_workspace_dep_matches_member, DepKind, Dependency,
+};
+use crate::core::{PackageId, Summary, Workspace};
 use crate::ops::lockfile::{
     load_lockfile, save_workspace_lockfile, workspace_lockfile_needs_update,
 };
@@ -75,6 +77,12 @@ pub fn resolve_workspace_with_opts(
     // Lockfile doesn't exist, is stale, or couldn't be loaded - resolve fresh
     if lockfile_path.exists() {
         tracing::info!("Workspace changed, re-resolving dependencies");
+
+        // Packages that were already locked stay resolvable even if yanked since
+        if let Ok(Some(previous)) = load_lockfile(&lockfile_path) {
+            let locked: Vec<PackageId> = previous.packages().map(|(id, _)| *id).collect();
+            source_cache.add_to_yanked_whitelist(&locked);
+        }
     } else {
         tracing::info!("No lockfile found, resolving dependencies");
     }
@@ -99,6 +107,8 @@ pub fn resolve_fresh(
     let workspace_deps = ws.workspace_dependencies();
     let member_paths = ws.member_paths();
     let mut all_deps: Vec<Dependency> = Vec::new();
+    let mut dev_deps: Vec<Dependency> = Vec::new();
+    let mut member_dev_deps: Vec<(PackageId, Vec<Dependency>)> = Vec::new();
     let mut seen: HashSet<(String, String)> = HashSet::new(); // (name, source_id)
 
     // Add dependencies from each member
@@ -116,11 +126,40 @@ pub fn resolve_fresh(
                 all_deps.push(dep);
             }
         }
+
+        // Dev-dependencies are only resolved for workspace members, never for
+        // packages pulled in as dependencies (their summaries omit them)
+        let mut member_devs = Vec::new();
+        for (name, spec) in &manifest.dev_dependencies {
+            let dep = resolve_dependency(name, spec, workspace_deps, &member_paths, manifest_dir)?
+                .with_kind(DepKind::Development);
+
+            let key = (dep.name().to_string(), dep.source_id().to_string());
+            if !seen.contains(&key) {
+                seen.insert(key);
+                all_deps.push(dep.clone());
+                dev_deps.push(dep.clone());
+            }
+            member_devs.push(dep);
+        }
+        if !member_devs.is_empty() {
+            member_dev_deps.push((member.package.package_id(), member_devs));
+        }
     }
 
     // Use first member as root for resolver (will be improved when resolver supports multiple roots)
     let root_package = ws.root_package();
-    let root_summary = root_package.summary()?;
+    let mut root_summary = root_package.summary()?;
+
+    // The root requires every member's dev-dependencies so they get resolved
+    // and locked; the dev edges are attached to their members below
+    if !dev_deps.is_empty() {
+        let mut deps = root_summary.dependencies().to_vec();
+        for dev in dev_deps {
+            add_root_dev_dependency(&mut deps, dev)?;
+        }
+        root_summary = Summary::new(root_summary.package_id(), deps, None);
+    }
     let mut resolver = HarbourResolver::new(root_summary.clone());
 
     // Ensure all sources are ready
@@ -133,7 +172,18 @@ pub fn resolve_fresh(
     }
 
     // Resolve
-    let resolve = resolver.resolve()?;
+    let mut resolve = resolver.resolve()?;
+
+    for (member_id, devs) in &member_dev_deps {
+        if !resolve.contains(*member_id) {
+            continue;
+        }
+        for dev in devs {
+            if let Some(dev_id) = resolve.get_package_by_name(dev.name()) {
+                resolve.add_dev_edge(*member_id, dev_id);
+            }
+        }
+    }
 
     // Save lockfile with workspace hash (unless in dry-run mode)
     if save_lockfile {
@@ -143,6 +193,79 @@ pub fn resolve_fresh(
     Ok(resolve)
 }
 
+/// Add a member's dev-dependency to the root requirements.
+///
+/// A dependency of the same name must agree on source and version, since the
+/// resolver picks one package per name.
+fn add_root_dev_dependency(deps: &mut Vec<Dependency>, dev: Dependency) -> Result<()> {
+    let Some(existing) = deps.iter().find(|d| d.name() == dev.name()) else {
+        deps.push(dev);
+        return Ok(());
+    };
+
+    if existing.source_id() != dev.source_id() || existing.version_req() != dev.version_req() {
+        bail!(
+            "dev-dependency `{}` ({} from {}) conflicts with dependency `{}` ({} from {})\n\
+             hint: use the same version and source for `{}` in every workspace member",
+            dev.name(),
+            dev.version_req(),
+            dev.source_id(),
+            existing.name(),
+            existing.version_req(),
+            existing.source_id(),
+            dev.name()
+        );
+    }
+    Ok(())
+}
+
+/// Warn about resolved registry packages that are yanked or deprecated.
+///
+/// Status lookups that fail are logged at debug level and skipped, so a
+/// flaky registry never blocks a build. Returns the emitted warnings.
+pub fn warn_yanked_and_deprecated(
+    resolve: &Resolve,
+    source_cache: &mut SourceCache,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for pkg_id in resolve.topological_order() {
+        if !pkg_id.source_id().is_registry() {
+            continue;
+        }
+
+        let status = match source_cache.version_status(pkg_id) {
+            Ok(status) => status,
+            Err(e) => {
+                tracing::debug!("could not check registry status of {}: {:#}", pkg_id, e);
+                continue;
+            }
+        };
+
+        if status.yanked {
+            warnings.push(format!(
+                "{} v{} has been yanked from the registry; run `harbour update` to move off it",
+                pkg_id.name(),
+                pkg_id.version()
+            ));
+        }
+        if let Some(reason) = status.deprecated {
+            warnings.push(format!(
+                "{} v{} is deprecated: {}",
+                pkg_id.name(),
+                pkg_id.version(),
+                reason
+            ));
+        }
+    }
+
+    for warning in &warnings {
+        tracing::warn!("{}", warning);
+    }
+
+    warnings
+}
+
 /// Update the lockfile by re-resolving dependencies.
 ///
 /// If `dry_run` is true, performs resolution but does not save the lockfile.
@@ -193,4 +316,28 @@ sources = ["src/**/*.c"]
 
         assert_eq!(resolve.len(), 1);
     }
+
+    #[test]
+    fn test_dev_dependency_conflicts_with_root_dependency() {
+        let tmp = TempDir::new().unwrap();
+        let source = crate::core::SourceId::for_path(tmp.path()).unwrap();
+        let req = |r: &str| semver::VersionReq::parse(r).unwrap();
+
+        let mut deps = vec![Dependency::new("zlib", source).with_version_req(req("^1.2"))];
+
+        // Same requirement is merged
+        let dev = Dependency::new("zlib", source).with_version_req(req("^1.2"));
+        add_root_dev_dependency(&mut deps, dev).unwrap();
+        assert_eq!(deps.len(), 1);
+
+        // New names are added
+        let dev = Dependency::new("unity", source);
+        add_root_dev_dependency(&mut deps, dev).unwrap();
+        assert_eq!(deps.len(), 2);
+
+        // A different version is reported instead of dropped
+        let dev = Dependency::new("zlib", source).with_version_req(req("^2.0"));
+        let err = add_root_dev_dependency(&mut deps, dev).unwrap_err();
+        assert!(err.to_string().contains("conflicts with dependency `zlib`"));
+    }
 }
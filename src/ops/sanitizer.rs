@@ -0,0 +1,293 @@
+//! Sanitizer runtime options and reports.
+//!
+//! Executables built with a profile's `sanitizers` read their runtime
+//! options from variables such as `ASAN_OPTIONS`. `harbour test` and
+//! `harbour run` set these from the profile's `sanitizer-options` and
+//! `suppressions`, and the reports the runtimes print are turned into
+//! [`Diagnostic`]s.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::builder::shim::SanitizerKind;
+use crate::core::manifest::Profile;
+use crate::util::diagnostic::Diagnostic;
+
+/// Environment variables for running an executable built with `profile`.
+///
+/// Options already set in the environment come last, so they override the
+/// profile's for a single run. Relative suppression files are resolved
+/// against `package_root`.
+pub fn sanitizer_env(profile: &Profile, package_root: &Path) -> Result<BTreeMap<String, String>> {
+    sanitizer_env_with(profile, package_root, |var| env::var(var).ok())
+}
+
+fn sanitizer_env_with(
+    profile: &Profile,
+    package_root: &Path,
+    existing: impl Fn(&str) -> Option<String>,
+) -> Result<BTreeMap<String, String>> {
+    let enabled = profile.sanitizer_kinds()?;
+    // AddressSanitizer includes LeakSanitizer, which keeps its own options
+    let active = |kind: SanitizerKind| {
+        enabled.contains(&kind)
+            || (kind == SanitizerKind::Leak && enabled.contains(&SanitizerKind::Address))
+    };
+
+    let mut options: BTreeMap<SanitizerKind, Vec<String>> = BTreeMap::new();
+    for (name, value) in &profile.sanitizer_options {
+        let kind: SanitizerKind = name.parse()?;
+        if active(kind) && !value.is_empty() {
+            options.entry(kind).or_default().push(value.clone());
+        }
+    }
+    for (kind, file) in profile.suppression_files()? {
+        if !active(kind) {
+            continue;
+        }
+        let path = package_root.join(&file);
+        if !path.is_file() {
+            bail!(
+                "{} suppression file `{}` does not exist",
+                kind.runtime_name(),
+                path.display()
+            );
+        }
+        let path = path.display().to_string();
+        options
+            .entry(kind)
+            .or_default()
+            .push(format!("suppressions={}", quote_option(&path)));
+    }
+
+    let mut vars = BTreeMap::new();
+    for (kind, mut values) in options {
+        let var = kind.options_var();
+        values.extend(existing(var).filter(|v| !v.is_empty()));
+        vars.insert(var.to_string(), values.join(":"));
+    }
+    Ok(vars)
+}
+
+/// Quote an option value the runtimes would otherwise split, such as a
+/// Windows path containing `:`.
+fn quote_option(value: &str) -> String {
+    if value.contains([':', ' ', '\t']) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Turn the sanitizer reports in an executable's output into diagnostics.
+///
+/// Recognizes AddressSanitizer, LeakSanitizer, MemorySanitizer and
+/// ThreadSanitizer reports and UndefinedBehaviorSanitizer runtime errors.
+/// The location is the first stack frame with a source position.
+pub fn parse_sanitizer_reports(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    // Whether the last diagnostic is a report still collecting stack frames
+    let mut in_report = false;
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if let Some(message) = report_header(line) {
+            diagnostics.push(Diagnostic::error(message));
+            in_report = true;
+        } else if let Some((location, message)) = line.split_once(": runtime error: ") {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "{}: {}",
+                    SanitizerKind::UndefinedBehavior.runtime_name(),
+                    message
+                ))
+                .with_location(location),
+            );
+            in_report = false;
+        } else if in_report {
+            let last = diagnostics.last_mut().filter(|d| d.location.is_none());
+            if let (Some(last), Some((function, location))) = (last, stack_frame(line)) {
+                last.location = Some(location.into());
+                last.context.push(format!("in `{}`", function));
+            }
+        }
+
+        if line.starts_with("SUMMARY: ") {
+            in_report = false;
+        }
+    }
+
+    diagnostics
+}
+
+/// Parse `==42==ERROR: AddressSanitizer: heap-use-after-free on address ...`
+/// or `WARNING: ThreadSanitizer: data race (pid=42)` into a message.
+fn report_header(line: &str) -> Option<String> {
+    let line = match line.strip_prefix("==") {
+        Some(rest) => rest.split_once("==")?.1,
+        None => line,
+    };
+    let report = line
+        .strip_prefix("ERROR: ")
+        .or_else(|| line.strip_prefix("WARNING: "))?;
+
+    let runtimes = [
+        SanitizerKind::Address,
+        SanitizerKind::Leak,
+        SanitizerKind::Memory,
+        SanitizerKind::Thread,
+    ];
+    if !runtimes
+        .iter()
+        .any(|kind| report.starts_with(&format!("{}: ", kind.runtime_name())))
+    {
+        return None;
+    }
+
+    // Drop the registers and pid, which differ on every run
+    let end = [" at pc ", " (pid="]
+        .iter()
+        .filter_map(|marker| report.find(marker))
+        .min()
+        .unwrap_or(report.len());
+    Some(report[..end].trim_end().to_string())
+}
+
+/// Parse a stack frame into the function and source location.
+///
+/// AddressSanitizer and MemorySanitizer print `#1 0x4f2a in parse src/a.c:42:7`
+/// and ThreadSanitizer `#0 parse src/a.c:42:7 (test+0x4f2a)`. Frames without
+/// a source position, such as those in the runtime itself, are skipped.
+fn stack_frame(line: &str) -> Option<(&str, &str)> {
+    let (_, frame) = line.strip_prefix('#')?.split_once(' ')?;
+    let frame = match frame.split_once(' ') {
+        Some((pc, rest)) if pc.starts_with("0x") => rest.strip_prefix("in ")?,
+        _ => frame,
+    };
+    let frame = match frame.rfind(" (") {
+        Some(module) if frame.ends_with(')') => &frame[..module],
+        _ => frame,
+    };
+    let (function, location) = frame.rsplit_once(' ')?;
+    if !location.contains(':') {
+        return None;
+    }
+    Some((function, location))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::manifest::Suppressions;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_sanitizer_env() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("asan.supp"), "interceptor_via_fun:legacy\n").unwrap();
+
+        let mut profile = Profile {
+            sanitizers: vec!["address".to_string(), "undefined".to_string()],
+            suppressions: Some(Suppressions::PerSanitizer(
+                [("address".to_string(), PathBuf::from("asan.supp"))].into(),
+            )),
+            ..Default::default()
+        };
+        profile.sanitizer_options.insert(
+            "address".to_string(),
+            "detect_stack_use_after_return=1".to_string(),
+        );
+        profile
+            .sanitizer_options
+            .insert("leak".to_string(), "report_objects=1".to_string());
+        profile
+            .sanitizer_options
+            .insert("thread".to_string(), "history_size=7".to_string());
+
+        let env = sanitizer_env_with(&profile, tmp.path(), |var| {
+            (var == "UBSAN_OPTIONS").then(|| "print_stacktrace=1".to_string())
+        })
+        .unwrap();
+
+        let supp = tmp.path().join("asan.supp").display().to_string();
+        assert_eq!(
+            env["ASAN_OPTIONS"],
+            format!(
+                "detect_stack_use_after_return=1:suppressions={}",
+                quote_option(&supp)
+            )
+        );
+        assert_eq!(env["LSAN_OPTIONS"], "report_objects=1");
+        // Nothing configured for UBSan, so the environment is left alone
+        assert!(!env.contains_key("UBSAN_OPTIONS"));
+        // ThreadSanitizer is not enabled by this profile
+        assert!(!env.contains_key("TSAN_OPTIONS"));
+
+        profile
+            .sanitizer_options
+            .insert("undefined".to_string(), "halt_on_error=1".to_string());
+        let env = sanitizer_env_with(&profile, tmp.path(), |var| {
+            (var == "UBSAN_OPTIONS").then(|| "print_stacktrace=1".to_string())
+        })
+        .unwrap();
+        assert_eq!(env["UBSAN_OPTIONS"], "halt_on_error=1:print_stacktrace=1");
+
+        profile.sanitizers = vec!["address".to_string()];
+        profile.suppressions = Some(Suppressions::File(PathBuf::from("missing.supp")));
+        let err = sanitizer_env_with(&profile, tmp.path(), |_| None).unwrap_err();
+        assert!(err.to_string().contains("missing.supp"));
+    }
+
+    #[test]
+    fn test_parse_sanitizer_reports() {
+        let output = "\
+=================================================================
+==4711==ERROR: AddressSanitizer: heap-use-after-free on address 0x602000000010 at pc 0x55d4 bp 0x7ffc sp 0x7ff0
+READ of size 4 at 0x602000000010 thread T0
+    #0 0x55d4 in __interceptor_memcpy (/usr/lib/libasan.so.8+0x3f1a2)
+    #1 0x55d5 in parse_header /src/parser/src/parse.c:42:7
+    #2 0x55d6 in main /src/parser/tests/parse_test.c:10:3
+SUMMARY: AddressSanitizer: heap-use-after-free /src/parser/src/parse.c:42:7 in parse_header
+/src/parser/src/util.c:17:12: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+WARNING: ThreadSanitizer: data race (pid=4712)
+  Write of size 4 at 0x7b04 by thread T1:
+    #0 worker /src/parser/src/pool.c:88:5 (parse_test+0x1234)
+";
+
+        let reports = parse_sanitizer_reports(output);
+        assert_eq!(reports.len(), 3);
+
+        assert_eq!(
+            reports[0].message,
+            "AddressSanitizer: heap-use-after-free on address 0x602000000010"
+        );
+        assert_eq!(
+            reports[0].location,
+            Some(PathBuf::from("/src/parser/src/parse.c:42:7"))
+        );
+        assert_eq!(reports[0].context, vec!["in `parse_header`"]);
+
+        assert_eq!(
+            reports[1].message,
+            "UndefinedBehaviorSanitizer: signed integer overflow: \
+             2147483647 + 1 cannot be represented in type 'int'"
+        );
+        assert_eq!(
+            reports[1].location,
+            Some(PathBuf::from("/src/parser/src/util.c:17:12"))
+        );
+
+        assert_eq!(reports[2].message, "ThreadSanitizer: data race");
+        assert_eq!(
+            reports[2].location,
+            Some(PathBuf::from("/src/parser/src/pool.c:88:5"))
+        );
+        assert_eq!(reports[2].context, vec!["in `worker`"]);
+
+        assert!(parse_sanitizer_reports("all 12 tests passed\n").is_empty());
+    }
+}
@@ -0,0 +1,695 @@
+//! Structured results from test frameworks.
+//!
+//! A test executable with a known framework is asked for a machine-readable
+//! report, which is turned into one [`TestCase`] per test case:
+//!
+//! - GoogleTest writes JSON (`--gtest_output=json:FILE`)
+//! - Catch2 and doctest write JUnit XML (`--reporter junit`)
+//! - cmocka writes JUnit XML when `CMOCKA_MESSAGE_OUTPUT=xml`
+//! - TAP producers are parsed from stdout
+//!
+//! CTest's `--output-junit` files are read with the same JUnit reader, and
+//! results can be written back out as JUnit XML for CI systems.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use roxmltree::{Document, Node, ParsingOptions};
+use serde::Deserialize;
+
+use crate::builder::shim::{TestCase, TestResult, TestStatus};
+use crate::core::manifest::TestFramework;
+
+/// Extra arguments and environment asking a test executable for a report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportInvocation {
+    /// Arguments appended to the test's own arguments
+    pub args: Vec<String>,
+
+    /// Environment variables set for the run
+    pub env: BTreeMap<String, String>,
+
+    /// File the report is written to (`None` when it is read from stdout)
+    pub report: Option<PathBuf>,
+}
+
+/// Describe how to run a `framework` test so it writes its report into
+/// `report_dir`.
+///
+/// Where the framework can select cases itself, only cases whose name
+/// contains `case_filter` are run. cmocka and TAP producers run everything
+/// and are filtered after parsing.
+pub fn report_invocation(
+    framework: TestFramework,
+    report_dir: &Path,
+    case_filter: Option<&str>,
+) -> ReportInvocation {
+    let mut invocation = ReportInvocation::default();
+
+    match framework {
+        TestFramework::Gtest => {
+            let report = report_dir.join("report.json");
+            invocation
+                .args
+                .push(format!("--gtest_output=json:{}", report.display()));
+            if let Some(filter) = case_filter {
+                invocation.args.push(format!("--gtest_filter=*{}*", filter));
+            }
+            invocation.report = Some(report);
+        }
+        TestFramework::Catch2 => {
+            let report = report_dir.join("report.xml");
+            invocation.args.extend([
+                "--reporter".to_string(),
+                "junit".to_string(),
+                "--out".to_string(),
+                report.display().to_string(),
+            ]);
+            if let Some(filter) = case_filter {
+                invocation.args.push(format!("*{}*", filter));
+            }
+            invocation.report = Some(report);
+        }
+        TestFramework::Doctest => {
+            let report = report_dir.join("report.xml");
+            invocation.args.push("--reporters=junit".to_string());
+            invocation.args.push(format!("--out={}", report.display()));
+            if let Some(filter) = case_filter {
+                invocation.args.push(format!("--test-case=*{}*", filter));
+            }
+            invocation.report = Some(report);
+        }
+        TestFramework::Cmocka => {
+            // Only the first group is written when a binary runs several
+            let report = report_dir.join("report.xml");
+            invocation
+                .env
+                .insert("CMOCKA_MESSAGE_OUTPUT".to_string(), "xml".to_string());
+            invocation
+                .env
+                .insert("CMOCKA_XML_FILE".to_string(), report.display().to_string());
+            invocation.report = Some(report);
+        }
+        TestFramework::Tap => {}
+    }
+
+    invocation
+}
+
+/// Parse the report of a finished `framework` test run.
+///
+/// `stdout` is the run's standard output, used by TAP producers.
+pub fn parse_report(
+    framework: TestFramework,
+    invocation: &ReportInvocation,
+    stdout: &str,
+) -> Result<Vec<TestCase>> {
+    if framework == TestFramework::Tap {
+        return Ok(parse_tap(stdout));
+    }
+
+    let Some(path) = &invocation.report else {
+        bail!("no {} report was requested", framework.as_str());
+    };
+    let report = fs::read_to_string(path).with_context(|| {
+        format!(
+            "test did not write a {} report to {}",
+            framework.as_str(),
+            path.display()
+        )
+    })?;
+
+    match framework {
+        TestFramework::Gtest => parse_gtest_json(&report),
+        _ => parse_junit(&report),
+    }
+}
+
+#[derive(Deserialize)]
+struct GtestReport {
+    #[serde(default)]
+    testsuites: Vec<GtestSuite>,
+}
+
+#[derive(Deserialize)]
+struct GtestSuite {
+    name: String,
+    #[serde(default)]
+    testsuite: Vec<GtestCase>,
+}
+
+#[derive(Deserialize)]
+struct GtestCase {
+    name: String,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(default)]
+    time: Option<String>,
+    #[serde(default)]
+    failures: Vec<GtestFailure>,
+}
+
+#[derive(Deserialize)]
+struct GtestFailure {
+    failure: String,
+}
+
+/// Parse a GoogleTest JSON report. Cases are named `Suite.Test`.
+pub fn parse_gtest_json(report: &str) -> Result<Vec<TestCase>> {
+    let report: GtestReport =
+        serde_json::from_str(report).context("invalid GoogleTest JSON report")?;
+
+    let mut cases = Vec::new();
+    for suite in report.testsuites {
+        for test in suite.testsuite {
+            let skipped = test.status.as_deref() == Some("NOTRUN")
+                || matches!(test.result.as_deref(), Some("SKIPPED" | "SUPPRESSED"));
+            let status = if !test.failures.is_empty() {
+                TestStatus::Failed
+            } else if skipped {
+                TestStatus::Skipped
+            } else {
+                TestStatus::Passed
+            };
+
+            let mut output = String::new();
+            for failure in &test.failures {
+                output.push_str(&failure.failure);
+                output.push('\n');
+            }
+
+            cases.push(TestCase {
+                name: format!("{}.{}", suite.name, test.name),
+                suite: None,
+                status,
+                output: (!output.is_empty()).then_some(output),
+                duration: test.time.as_deref().and_then(parse_seconds),
+            });
+        }
+    }
+
+    Ok(cases)
+}
+
+/// Parse a JUnit XML report, as written by Catch2, doctest, cmocka and CTest.
+pub fn parse_junit(report: &str) -> Result<Vec<TestCase>> {
+    // Some writers add a DOCTYPE; entity expansion is bounded by the parser
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document =
+        Document::parse_with_options(report, options).context("invalid JUnit XML report")?;
+
+    let mut cases = Vec::new();
+    collect_junit_cases(document.root_element(), &mut cases);
+    Ok(cases)
+}
+
+fn collect_junit_cases(element: Node, cases: &mut Vec<TestCase>) {
+    match element.tag_name().name() {
+        "testcase" => cases.push(junit_case(element)),
+        "testsuites" | "testsuite" => {
+            for child in element.children().filter(Node::is_element) {
+                collect_junit_cases(child, cases);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn junit_case(element: Node) -> TestCase {
+    // CTest records the outcome as an attribute as well as a child element
+    let mut status = match element.attribute("status") {
+        Some("fail") => TestStatus::Failed,
+        Some("notrun" | "disabled" | "skipped") => TestStatus::Skipped,
+        _ => TestStatus::Passed,
+    };
+
+    let mut output = String::new();
+    for child in element.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "failure" | "error" => status = TestStatus::Failed,
+            "skipped" if status != TestStatus::Failed => status = TestStatus::Skipped,
+            "skipped" | "system-out" | "system-err" => {}
+            _ => continue,
+        }
+        if let Some(message) = child.attribute("message") {
+            output.push_str(message);
+            output.push('\n');
+        }
+        // Text directly inside the element, including CDATA sections
+        let text: String = child
+            .children()
+            .filter(Node::is_text)
+            .filter_map(|node| node.text())
+            .filter(|text| !text.trim().is_empty())
+            .collect();
+        if !text.trim().is_empty() {
+            output.push_str(text.trim_matches('\n'));
+            output.push('\n');
+        }
+    }
+
+    TestCase {
+        name: element.attribute("name").unwrap_or("unnamed").to_string(),
+        suite: None,
+        status,
+        output: (status != TestStatus::Passed && !output.is_empty()).then_some(output),
+        duration: element.attribute("time").and_then(parse_seconds),
+    }
+}
+
+/// Parse Test Anything Protocol output.
+///
+/// Diagnostics (`# ...` lines and indented YAML blocks) following a test
+/// line are kept as that test's output. `# SKIP` and failing `# TODO`
+/// tests count as skipped.
+pub fn parse_tap(output: &str) -> Vec<TestCase> {
+    let mut cases: Vec<TestCase> = Vec::new();
+
+    for line in output.lines() {
+        if let Some(case) = parse_tap_line(line, cases.len() + 1) {
+            cases.push(case);
+        } else if let Some(reason) = line.strip_prefix("Bail out!") {
+            cases.push(TestCase {
+                name: "bail out".to_string(),
+                suite: None,
+                status: TestStatus::Failed,
+                output: Some(format!("Bail out! {}\n", reason.trim())),
+                duration: None,
+            });
+            break;
+        } else if line.starts_with('#') || line.starts_with([' ', '\t']) {
+            if let Some(case) = cases.last_mut() {
+                let output = case.output.get_or_insert_with(String::new);
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+
+    for case in &mut cases {
+        if case.status == TestStatus::Passed {
+            case.output = None;
+        }
+    }
+    cases
+}
+
+fn parse_tap_line(line: &str, number: usize) -> Option<TestCase> {
+    let (ok, rest) = match line.strip_prefix("not ok") {
+        Some(rest) => (false, rest),
+        None => (true, line.strip_prefix("ok")?),
+    };
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    let (description, directive) = match rest.split_once('#') {
+        Some((description, directive)) => (description, Some(directive.trim())),
+        None => (rest, None),
+    };
+    let directive_is = |name: &str| {
+        directive.is_some_and(|d| {
+            d.get(..name.len())
+                .is_some_and(|d| d.eq_ignore_ascii_case(name))
+        })
+    };
+
+    let description = description
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start();
+    let description = description.strip_prefix('-').unwrap_or(description).trim();
+
+    let status = if directive_is("SKIP") || (!ok && directive_is("TODO")) {
+        TestStatus::Skipped
+    } else if ok {
+        TestStatus::Passed
+    } else {
+        TestStatus::Failed
+    };
+
+    Some(TestCase {
+        name: if description.is_empty() {
+            format!("test {}", number)
+        } else {
+            description.to_string()
+        },
+        suite: None,
+        status,
+        output: directive
+            .filter(|_| status == TestStatus::Skipped)
+            .map(|d| format!("# {}\n", d)),
+        duration: None,
+    })
+}
+
+/// Parse a duration in seconds, as in `0.25` or GoogleTest's `0.25s`.
+fn parse_seconds(time: &str) -> Option<Duration> {
+    let seconds: f64 = time.trim().trim_end_matches('s').parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Write test results as a JUnit XML report.
+///
+/// Cases are grouped into one `<testsuite>` per suite; cases without a
+/// suite are grouped under `name`.
+pub fn write_junit(name: &str, result: &TestResult) -> String {
+    let mut suites: Vec<(&str, Vec<&TestCase>)> = Vec::new();
+    for case in &result.tests {
+        let suite = case.suite.as_deref().unwrap_or(name);
+        match suites.iter_mut().find(|(s, _)| *s == suite) {
+            Some((_, cases)) => cases.push(case),
+            None => suites.push((suite, vec![case])),
+        }
+    }
+
+    let total_time: Duration = result.tests.iter().filter_map(|c| c.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        xml_escape(name),
+        result.total,
+        result.failed,
+        result.skipped,
+        total_time.as_secs_f64()
+    );
+
+    for (suite, cases) in suites {
+        let failures = cases
+            .iter()
+            .filter(|c| matches!(c.status, TestStatus::Failed | TestStatus::TimedOut))
+            .count();
+        let skipped = cases
+            .iter()
+            .filter(|c| c.status == TestStatus::Skipped)
+            .count();
+        let time: Duration = cases.iter().filter_map(|c| c.duration).sum();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            xml_escape(suite),
+            cases.len(),
+            failures,
+            skipped,
+            time.as_secs_f64()
+        );
+
+        for case in cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                xml_escape(&case.name),
+                xml_escape(suite),
+                case.duration.unwrap_or_default().as_secs_f64()
+            );
+            let output = case.output.as_deref().map(xml_escape);
+            match (case.status, output) {
+                (TestStatus::Passed, _) => xml.push_str("/>\n"),
+                (TestStatus::Skipped, output) => {
+                    xml.push_str(">\n      <skipped/>\n");
+                    if let Some(output) = output {
+                        let _ = writeln!(xml, "      <system-out>{}</system-out>", output);
+                    }
+                    xml.push_str("    </testcase>\n");
+                }
+                (status, output) => {
+                    let message = if status == TestStatus::TimedOut {
+                        "timed out"
+                    } else {
+                        "failed"
+                    };
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                        message,
+                        output.unwrap_or_default()
+                    );
+                }
+            }
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for XML content and attribute values, dropping control
+/// characters XML 1.0 cannot represent.
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_invocation() {
+        let dir = Path::new("/tmp/report");
+
+        let gtest = report_invocation(TestFramework::Gtest, dir, Some("Parser"));
+        let report = dir.join("report.json");
+        assert_eq!(
+            gtest.args,
+            vec![
+                format!("--gtest_output=json:{}", report.display()),
+                "--gtest_filter=*Parser*".to_string()
+            ]
+        );
+        assert_eq!(gtest.report, Some(report));
+
+        let cmocka = report_invocation(TestFramework::Cmocka, dir, None);
+        assert!(cmocka.args.is_empty());
+        assert_eq!(cmocka.env["CMOCKA_MESSAGE_OUTPUT"], "xml");
+        assert_eq!(
+            cmocka.env["CMOCKA_XML_FILE"],
+            dir.join("report.xml").display().to_string()
+        );
+
+        let tap = report_invocation(TestFramework::Tap, dir, Some("x"));
+        assert_eq!(tap, ReportInvocation::default());
+    }
+
+    #[test]
+    fn test_parse_gtest_json() {
+        let report = r#"{
+  "tests": 3, "failures": 1,
+  "testsuites": [{
+    "name": "Parser",
+    "testsuite": [
+      {"name": "Empty", "status": "RUN", "result": "COMPLETED", "time": "0.002s"},
+      {"name": "Nested", "status": "RUN", "result": "COMPLETED", "time": "0s",
+       "failures": [{"failure": "parser_test.cc:12\nExpected equality", "type": ""}]},
+      {"name": "Slow", "status": "RUN", "result": "SKIPPED", "time": "0s"}
+    ]
+  }]
+}"#;
+        let cases = parse_gtest_json(report).unwrap();
+        assert_eq!(cases.len(), 3);
+
+        assert_eq!(cases[0].name, "Parser.Empty");
+        assert_eq!(cases[0].status, TestStatus::Passed);
+        assert_eq!(cases[0].duration, Some(Duration::from_millis(2)));
+
+        assert_eq!(cases[1].status, TestStatus::Failed);
+        assert!(cases[1]
+            .output
+            .as_deref()
+            .unwrap()
+            .contains("Expected equality"));
+
+        assert_eq!(cases[2].status, TestStatus::Skipped);
+        assert!(parse_gtest_json("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_junit() {
+        let report = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- written by Catch2 -->
+<testsuites>
+  <testsuite name="unit_test" tests="4" failures="1" time="0.01">
+    <testcase classname="unit_test.global" name="vectors &amp; sizes" time="0.004"/>
+    <testcase classname="unit_test.global" name="division" time="0.001">
+      <failure message="REQUIRE( a / b == 2 )" type="REQUIRE"><![CDATA[with expansion:
+  1 == 2
+at math.cpp:20]]></failure>
+    </testcase>
+    <testcase classname="unit_test.global" name="later" time="0">
+      <skipped message="not yet &lt;implemented&gt;"/>
+    </testcase>
+    <testcase name="crash" status="fail" time="0.5">
+      <system-out>Segmentation fault</system-out>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+        let cases = parse_junit(report).unwrap();
+        assert_eq!(cases.len(), 4);
+
+        assert_eq!(cases[0].name, "vectors & sizes");
+        assert_eq!(cases[0].status, TestStatus::Passed);
+        assert!(cases[0].output.is_none());
+
+        assert_eq!(cases[1].status, TestStatus::Failed);
+        let output = cases[1].output.as_deref().unwrap();
+        assert!(output.starts_with("REQUIRE( a / b == 2 )\n"));
+        assert!(output.contains("1 == 2"));
+
+        assert_eq!(cases[2].status, TestStatus::Skipped);
+        assert_eq!(cases[2].output.as_deref(), Some("not yet <implemented>\n"));
+
+        assert_eq!(cases[3].status, TestStatus::Failed);
+        assert_eq!(cases[3].output.as_deref(), Some("Segmentation fault\n"));
+
+        assert!(parse_junit("<testsuite><testcase></testsuite>").is_err());
+    }
+
+    #[test]
+    fn test_parse_junit_entities() {
+        let report = r#"<?xml version="1.0"?>
+<!DOCTYPE testsuites [<!ENTITY project "harbour">]>
+<testsuites>
+  <testsuite name="&project;">
+    <testcase name="&#x3C;tag&#62; &quot;q&quot; &apos;a&apos; &amp;&amp;" status="fail">
+      <failure message="&project; &#233;">plain &lt;text&gt;<![CDATA[ & <raw> ]]></failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+        let cases = parse_junit(report).unwrap();
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].name, "<tag> \"q\" 'a' &&");
+        assert_eq!(
+            cases[0].output.as_deref(),
+            Some("harbour \u{e9}\nplain <text> & <raw> \n")
+        );
+    }
+
+    #[test]
+    fn test_parse_junit_malformed() {
+        for report in [
+            "",
+            "not xml",
+            "<testsuite><testcase></testsuite>",
+            "<testsuite>",
+            "<testsuite/><testsuite/>",
+            r#"<testsuite><testcase name="a></testsuite>"#,
+            "<testsuite><testcase name=a/></testsuite>",
+            r#"<testsuite><testcase name="&bogus;"/></testsuite>"#,
+            r#"<!DOCTYPE t [<!ENTITY a "&b;"><!ENTITY b "&a;">]><testsuite name="&a;"/>"#,
+            "<testsuite><![CDATA[unterminated</testsuite>",
+            "<testsuite><!-- unterminated</testsuite>",
+        ] {
+            let err = parse_junit(report).unwrap_err();
+            assert_eq!(err.to_string(), "invalid JUnit XML report", "{report}");
+        }
+    }
+
+    #[test]
+    fn test_parse_tap() {
+        let output = "\
+TAP version 13
+1..5
+ok 1 - parses empty input
+not ok 2 - parses nested lists
+  ---
+  message: expected 3 elements
+  ...
+# at parser.c:40
+ok 3 # SKIP no network
+not ok 4 - unicode # TODO not implemented
+ok
+Bail out! database went away
+ok 6 - never reached
+";
+        let cases = parse_tap(output);
+        assert_eq!(cases.len(), 6);
+
+        assert_eq!(cases[0].name, "parses empty input");
+        assert_eq!(cases[0].status, TestStatus::Passed);
+
+        assert_eq!(cases[1].status, TestStatus::Failed);
+        let diagnostics = cases[1].output.as_deref().unwrap();
+        assert!(diagnostics.contains("expected 3 elements"));
+        assert!(diagnostics.contains("# at parser.c:40"));
+
+        assert_eq!(cases[2].name, "test 3");
+        assert_eq!(cases[2].status, TestStatus::Skipped);
+        assert_eq!(cases[3].name, "unicode");
+        assert_eq!(cases[3].status, TestStatus::Skipped);
+        assert_eq!(cases[4].name, "test 5");
+        assert_eq!(cases[4].status, TestStatus::Passed);
+
+        assert_eq!(cases[5].name, "bail out");
+        assert_eq!(cases[5].status, TestStatus::Failed);
+    }
+
+    #[test]
+    fn test_write_junit_roundtrip() {
+        let case = |suite: Option<&str>, name: &str, status, output: Option<&str>| TestCase {
+            name: name.to_string(),
+            suite: suite.map(str::to_string),
+            status,
+            output: output.map(str::to_string),
+            duration: Some(Duration::from_millis(250)),
+        };
+        let result = TestResult::from_cases(vec![
+            case(Some("unit_test"), "Parser.Empty", TestStatus::Passed, None),
+            case(
+                Some("unit_test"),
+                "Parser.<Nested>",
+                TestStatus::Failed,
+                Some("expected \"a\" & \"b\"\x1b[0m\n"),
+            ),
+            case(None, "smoke", TestStatus::TimedOut, Some("timed out\n")),
+            case(None, "slow", TestStatus::Skipped, None),
+        ]);
+
+        let xml = write_junit("mylib", &result);
+        assert!(xml.contains(
+            "<testsuites name=\"mylib\" tests=\"4\" failures=\"2\" skipped=\"1\" time=\"1.000\">"
+        ));
+        assert!(xml.contains("<testsuite name=\"unit_test\" tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<failure message=\"timed out\">"));
+        assert!(!xml.contains('\x1b'));
+
+        let cases = parse_junit(&xml).unwrap();
+        assert_eq!(cases.len(), 4);
+        assert_eq!(cases[1].name, "Parser.<Nested>");
+        assert_eq!(cases[1].status, TestStatus::Failed);
+        assert!(cases[1]
+            .output
+            .as_deref()
+            .unwrap()
+            .contains("expected \"a\" & \"b\"[0m"));
+        assert_eq!(cases[2].status, TestStatus::Failed);
+        assert_eq!(cases[3].status, TestStatus::Skipped);
+        assert_eq!(cases[0].duration, Some(Duration::from_millis(250)));
+    }
+}
@@ -7,6 +7,7 @@ use anyhow::{bail, Context, Result};
 use toml::Value;
 
 use super::types::{VerifyContext, VerifyLinkage, VerifyOptions};
+use crate::builder::diagnostics::DiagnosticFormat;
 use crate::builder::shim::intent::TargetTriple;
 use crate::core::workspace::{MANIFEST_ALIAS, MANIFEST_NAME};
 use crate::core::Workspace;
@@ -106,6 +107,9 @@ pub(crate) fn build_package(
         target_triple: options.target_triple.as_ref().map(|s| TargetTriple::new(s)),
         locked: false,
         vcpkg: VcpkgConfig::default(),
+        coverage: false,
+        fuzz: false,
+        diagnostics: DiagnosticFormat::Human,
     };
 
     // Run the build using standard infrastructure
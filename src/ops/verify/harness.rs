@@ -6,7 +6,9 @@ use std::process::Command;
 use anyhow::{bail, Context, Result};
 
 use super::types::VerifyContext;
+use crate::builder::toolchain::{CommandSpec, CompileInput, Language, LinkInput, Toolchain};
 use crate::sources::registry::shim::HarnessConfig;
+use crate::util::process::ProcessBuilder;
 
 /// Verify that artifacts exist.
 pub(crate) fn verify_artifacts(artifacts: &[PathBuf]) -> Result<()> {
@@ -101,6 +103,7 @@ pub(crate) fn run_harness_test(
             include_dirs: vec![],
             defines: vec![],
             cflags: vec![],
+            visibility: crate::core::manifest::SymbolVisibility::Default,
         };
         let spec = toolchain.compile_command(&dummy_input, crate::core::target::Language::C, None);
         for (key, value) in &spec.env {
@@ -189,6 +192,64 @@ pub(crate) fn run_harness_test(
     Ok(())
 }
 
+/// Compile a generated probe program with `toolchain`, run it and return
+/// its standard output.
+///
+/// Unlike the consumer harness, probes link nothing but the C runtime: they
+/// only report what the compiler decided, such as struct layouts. The
+/// executable is written next to `input.output`.
+pub fn run_probe(
+    toolchain: &dyn Toolchain,
+    input: &CompileInput,
+    lang: Language,
+) -> Result<String> {
+    let run = |spec: CommandSpec, step: &str| -> Result<()> {
+        let mut cmd = ProcessBuilder::new(&spec.program).args(spec.args);
+        for (key, value) in spec.env {
+            cmd = cmd.env(key, value);
+        }
+        let output = cmd
+            .exec()
+            .with_context(|| format!("failed to {} probe", step))?;
+        if !output.status.success() {
+            bail!(
+                "probe {} failed:\n{}\n{}",
+                step,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(())
+    };
+
+    run(toolchain.compile_command(input, lang, None), "compile")?;
+
+    let exe = input.output.with_extension(toolchain.exe_extension());
+    let link = LinkInput {
+        objects: vec![input.output.clone()],
+        output: exe.clone(),
+        lib_dirs: Vec::new(),
+        libs: Vec::new(),
+        ldflags: Vec::new(),
+        export_map: None,
+        version: None,
+    };
+    run(toolchain.link_exe_command(&link, lang, None), "link")?;
+
+    let output = Command::new(&exe)
+        .output()
+        .with_context(|| format!("failed to execute probe: {}", exe.display()))?;
+    if !output.status.success() {
+        bail!(
+            "probe execution failed (exit code {:?}):\n{}",
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 /// Get the host target triple for the current platform.
 pub(crate) fn get_host_triple() -> &'static str {
     #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
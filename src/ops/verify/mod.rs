@@ -43,7 +43,7 @@ use crate::util::context::GlobalContext;
 
 // Re-export public types
 pub use self::format::{format_result, format_result_for_output};
-pub use self::harness::{generate_harness, generate_harness_c, generate_harness_cxx};
+pub use self::harness::{generate_harness, generate_harness_c, generate_harness_cxx, run_probe};
 pub use self::types::{
     OutputFormat, OutputFormatParseError, VerifyLinkage, VerifyLinkageParseError, VerifyOptions,
     VerifyResult, VerifyStep,
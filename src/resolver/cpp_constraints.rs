@@ -243,9 +243,12 @@ mod tests {
                 documentation: None,
                 keywords: vec![],
                 categories: vec![],
+                include: vec![],
+                exclude: vec![],
             }),
             workspace: None,
             dependencies: HashMap::new(),
+            dev_dependencies: HashMap::new(),
             targets: vec![target],
             profiles: HashMap::new(),
             build: BuildConfig::default(),
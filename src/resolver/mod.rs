@@ -116,10 +116,11 @@ impl HarbourResolver {
                     }
                 }
 
-                // Add dependency edges
+                // Add dependency edges. Dev-dependencies only pull packages into
+                // the resolve; the caller attaches them to the members declaring them.
                 let packages: Vec<_> = resolve.packages().map(|(id, s)| (*id, s.clone())).collect();
                 for (pkg_id, summary) in packages {
-                    for dep in summary.dependencies() {
+                    for dep in summary.dependencies().iter().filter(|d| !d.is_dev()) {
                         if let Some(dep_id) = resolve.get_package_by_name(dep.name()) {
                             resolve.add_edge(pkg_id, dep_id);
                         }
@@ -81,6 +81,9 @@ pub struct Resolve {
     /// Registry provenance for reproducibility (only for registry packages)
     registry_provenances: HashMap<PackageId, RegistryProvenance>,
 
+    /// Edges that come only from `[dev-dependencies]`
+    dev_edges: HashSet<(PackageId, PackageId)>,
+
     /// Format version
     version: ResolveVersion,
 }
@@ -95,6 +98,7 @@ impl Resolve {
             summaries: HashMap::new(),
             checksums: HashMap::new(),
             registry_provenances: HashMap::new(),
+            dev_edges: HashSet::new(),
             version: ResolveVersion::V1,
         }
     }
@@ -128,9 +132,31 @@ impl Resolve {
             if !self.graph.contains_edge(from_node, to_node) {
                 self.graph.add_edge(from_node, to_node, ());
             }
+            // A normal dependency wins over a dev-dependency on the same package
+            self.dev_edges.remove(&(from, to));
         }
     }
 
+    /// Add a dev-dependency edge between packages.
+    ///
+    /// Dev edges order the build like normal edges, but are left out of
+    /// `transitive_deps` so their surfaces never reach dependents.
+    pub fn add_dev_edge(&mut self, from: PackageId, to: PackageId) {
+        if let (Some(&from_node), Some(&to_node)) =
+            (self.pkg_to_node.get(&from), self.pkg_to_node.get(&to))
+        {
+            if !self.graph.contains_edge(from_node, to_node) {
+                self.graph.add_edge(from_node, to_node, ());
+                self.dev_edges.insert((from, to));
+            }
+        }
+    }
+
+    /// Check if an edge comes only from `[dev-dependencies]`.
+    pub fn is_dev_edge(&self, from: PackageId, to: PackageId) -> bool {
+        self.dev_edges.contains(&(from, to))
+    }
+
     /// Get a package ID by name.
     ///
     /// If multiple packages with the same name exist from different sources,
@@ -254,7 +280,7 @@ impl Resolve {
         self.summaries.is_empty()
     }
 
-    /// Get direct dependencies of a package.
+    /// Get direct dependencies of a package, including dev-dependencies.
     pub fn deps(&self, pkg_id: PackageId) -> Vec<PackageId> {
         if let Some(&node) = self.pkg_to_node.get(&pkg_id) {
             self.graph.neighbors(node).map(|n| self.graph[n]).collect()
@@ -263,6 +289,22 @@ impl Resolve {
         }
     }
 
+    /// Get direct normal (non-dev) dependencies of a package.
+    pub fn normal_deps(&self, pkg_id: PackageId) -> Vec<PackageId> {
+        self.deps(pkg_id)
+            .into_iter()
+            .filter(|dep| !self.is_dev_edge(pkg_id, *dep))
+            .collect()
+    }
+
+    /// Get direct dev-dependencies of a package.
+    pub fn dev_deps(&self, pkg_id: PackageId) -> Vec<PackageId> {
+        self.deps(pkg_id)
+            .into_iter()
+            .filter(|dep| self.is_dev_edge(pkg_id, *dep))
+            .collect()
+    }
+
     /// Get packages that depend on the given package.
     pub fn dependents(&self, pkg_id: PackageId) -> Vec<PackageId> {
         if let Some(&node) = self.pkg_to_node.get(&pkg_id) {
@@ -321,13 +363,15 @@ impl Resolve {
     }
 
     /// Get all transitive dependencies of a package.
+    ///
+    /// Dev-dependencies are not followed; see [`Resolve::transitive_dev_deps`].
     pub fn transitive_deps(&self, pkg_id: PackageId) -> HashSet<PackageId> {
         let mut visited = HashSet::new();
         let mut stack = vec![pkg_id];
 
         while let Some(current) = stack.pop() {
             if visited.insert(current) {
-                for dep in self.deps(current) {
+                for dep in self.normal_deps(current) {
                     stack.push(dep);
                 }
             }
@@ -336,6 +380,17 @@ impl Resolve {
         visited.remove(&pkg_id);
         visited
     }
+
+    /// Get a package's dev-dependencies and everything they depend on.
+    pub fn transitive_dev_deps(&self, pkg_id: PackageId) -> HashSet<PackageId> {
+        let mut deps = HashSet::new();
+        for dev in self.dev_deps(pkg_id) {
+            deps.extend(self.transitive_deps(dev));
+            deps.insert(dev);
+        }
+        deps.remove(&pkg_id);
+        deps
+    }
 }
 
 impl Default for Resolve {
@@ -572,6 +627,52 @@ mod tests {
         assert!(transitive.is_empty());
     }
 
+    #[test]
+    fn test_resolve_dev_deps() {
+        let mut resolve = Resolve::new();
+
+        let (id_app, sum_app) = create_test_summary("app", "1.0.0");
+        let (id_lib, sum_lib) = create_test_summary("lib", "1.0.0");
+        let (id_unity, sum_unity) = create_test_summary("unity", "2.5.0");
+        let (id_support, sum_support) = create_test_summary("support", "1.0.0");
+
+        resolve.add_package(id_app, sum_app);
+        resolve.add_package(id_lib, sum_lib);
+        resolve.add_package(id_unity, sum_unity);
+        resolve.add_package(id_support, sum_support);
+
+        // app -> lib, app -(dev)-> unity -> support
+        resolve.add_edge(id_app, id_lib);
+        resolve.add_dev_edge(id_app, id_unity);
+        resolve.add_edge(id_unity, id_support);
+        // A normal edge is never downgraded to a dev edge
+        resolve.add_dev_edge(id_app, id_lib);
+
+        assert!(resolve.is_dev_edge(id_app, id_unity));
+        assert!(!resolve.is_dev_edge(id_app, id_lib));
+        assert_eq!(resolve.deps(id_app).len(), 2);
+        assert_eq!(resolve.normal_deps(id_app), vec![id_lib]);
+        assert_eq!(resolve.dev_deps(id_app), vec![id_unity]);
+
+        let transitive = resolve.transitive_deps(id_app);
+        assert_eq!(transitive.len(), 1);
+        assert!(transitive.contains(&id_lib));
+
+        let dev = resolve.transitive_dev_deps(id_app);
+        assert_eq!(dev.len(), 2);
+        assert!(dev.contains(&id_unity));
+        assert!(dev.contains(&id_support));
+
+        // Dev-dependencies still build before the package that uses them
+        let order = resolve.topological_order();
+        let pos = |id| order.iter().position(|p| *p == id).unwrap();
+        assert!(pos(id_unity) < pos(id_app));
+
+        // A later normal edge upgrades a dev edge
+        resolve.add_edge(id_app, id_unity);
+        assert!(!resolve.is_dev_edge(id_app, id_unity));
+    }
+
     #[test]
     fn test_resolve_diamond_dependency() {
         let mut resolve = Resolve::new();
@@ -4,12 +4,15 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
+use url::Url;
 
 use crate::core::abi::TargetTriple;
 use crate::core::{Dependency, Package, PackageId, SourceId, Summary};
-use crate::sources::{GitSource, PathSource, RegistrySource, Source, VcpkgSource};
+use crate::sources::{
+    Fetcher, GitSource, PathSource, RegistrySource, Source, VcpkgSource, VersionStatus,
+};
 use crate::util::config::VcpkgConfig;
-use crate::util::context::DEFAULT_REGISTRY_URL;
+use crate::util::context::{RegistryList, DEFAULT_REGISTRY_URL};
 use crate::util::VcpkgIntegration;
 
 /// Manages all package sources and caching.
@@ -22,6 +25,15 @@ pub struct SourceCache {
 
     /// Vcpkg integration settings
     vcpkg: Option<VcpkgIntegration>,
+
+    /// Locked packages whose yanked versions remain selectable
+    yanked_whitelist: Vec<PackageId>,
+
+    /// Registries searched for version-only dependencies
+    registries: RegistryList,
+
+    /// Authenticated fetcher shared by git and registry sources
+    fetcher: Fetcher,
 }
 
 impl SourceCache {
@@ -38,16 +50,32 @@ impl SourceCache {
             cache_dir,
             sources: HashMap::new(),
             vcpkg,
+            yanked_whitelist: Vec::new(),
+            registries: RegistryList::with_defaults(),
+            fetcher: Fetcher::default(),
         }
     }
 
+    /// Use the given fetcher (credentials, git CLI fallback) for remote sources.
+    pub fn with_fetcher(mut self, fetcher: Fetcher) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Use the given registries for version-only dependency lookup.
+    pub fn with_registries(mut self, registries: RegistryList) -> Self {
+        self.registries = registries;
+        self
+    }
+
     /// Get or create a source for a dependency.
     pub fn get_or_create(&mut self, dep: &Dependency) -> Result<&mut dyn Source> {
         let source_id = dep.source_id();
 
         // Create source first if needed (avoids borrow checker issues with entry API)
         if !self.sources.contains_key(&source_id) {
-            let source = self.create_source(source_id)?;
+            let mut source = self.create_source(source_id)?;
+            source.add_to_yanked_whitelist(&self.yanked_whitelist);
             self.sources.insert(source_id, source);
         }
 
@@ -64,18 +92,20 @@ impl SourceCache {
             Ok(Box::new(PathSource::new(path.to_path_buf(), source_id)))
         } else if source_id.is_git() {
             let reference = source_id.git_reference().cloned().unwrap_or_default();
-            Ok(Box::new(GitSource::new(
-                source_id.url().clone(),
-                reference,
-                &self.cache_dir,
-                source_id,
-            )))
+            Ok(Box::new(
+                GitSource::new(
+                    source_id.url().clone(),
+                    reference,
+                    &self.cache_dir,
+                    source_id,
+                )
+                .with_fetcher(self.fetcher.clone()),
+            ))
         } else if source_id.is_registry() {
-            Ok(Box::new(RegistrySource::new(
-                source_id.url().clone(),
-                &self.cache_dir,
-                source_id,
-            )))
+            Ok(Box::new(
+                RegistrySource::new(source_id.url().clone(), &self.cache_dir, source_id)
+                    .with_fetcher(self.fetcher.clone()),
+            ))
         } else if source_id.is_vcpkg() {
             let integration = self
                 .vcpkg
@@ -92,26 +122,69 @@ impl SourceCache {
     }
 
     /// Query all sources for versions matching a dependency.
+    ///
+    /// Version-only dependencies (those pointing at the default registry) are
+    /// looked up across the configured registries in priority order; the
+    /// returned summaries carry the source ID of the registry that matched.
     pub fn query(&mut self, dep: &Dependency) -> Result<Vec<Summary>> {
-        let source = self.get_or_create(dep)?;
-        let results = source.query(dep)?;
-
-        if results.is_empty()
-            && dep.source_id().is_registry()
-            && self.vcpkg.is_some()
-            && is_default_registry(dep.source_id())
-        {
-            let vcpkg_source_id = SourceId::for_vcpkg(dep.name().as_str(), None, None, None, None, None)?;
-            let vcpkg_dep = Dependency::new(dep.name(), vcpkg_source_id)
-                .with_version_req(dep.version_req().clone())
-                .optional(dep.is_optional())
-                .with_features(dep.features().to_vec())
-                .with_default_features(dep.uses_default_features());
+        if !(dep.source_id().is_registry() && is_default_registry(dep.source_id())) {
+            let source = self.get_or_create(dep)?;
+            return source.query(dep);
+        }
+
+        let name = dep.name();
+        let candidates: Vec<String> = self
+            .registries
+            .candidates(name.as_str())
+            .into_iter()
+            .map(|r| r.url.clone())
+            .collect();
+
+        for registry_url in &candidates {
+            let url = Url::parse(registry_url)
+                .map_err(|e| anyhow::anyhow!("invalid registry URL `{}`: {}", registry_url, e))?;
+            let registry_dep = dep.clone().with_source_id(SourceId::for_registry(&url)?);
+
+            let source = self.get_or_create(&registry_dep)?;
+            let results = source.query(&registry_dep)?;
+            if !results.is_empty() {
+                tracing::debug!("found {} in registry {}", name, registry_url);
+                return Ok(results);
+            }
+        }
+
+        // Names scoped to a private registry never fall through to vcpkg
+        if self.vcpkg.is_some() && !self.registries.is_scoped(name.as_str()) {
+            let vcpkg_source_id = SourceId::for_vcpkg(name.as_str(), None, None, None, None, None)?;
+            let vcpkg_dep = dep.clone().with_source_id(vcpkg_source_id);
             let source = self.get_or_create(&vcpkg_dep)?;
             return source.query(&vcpkg_dep);
         }
 
-        Ok(results)
+        Ok(vec![])
+    }
+
+    /// Keep yanked versions of these (already locked) packages selectable.
+    ///
+    /// Applies to sources that already exist and to sources created later.
+    pub fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        for source in self.sources.values_mut() {
+            source.add_to_yanked_whitelist(pkgs);
+        }
+        self.yanked_whitelist.extend_from_slice(pkgs);
+    }
+
+    /// Get the publication status (yanked/deprecated) of a package version.
+    pub fn version_status(&mut self, pkg_id: PackageId) -> Result<VersionStatus> {
+        let source_id = pkg_id.source_id();
+
+        if !self.sources.contains_key(&source_id) {
+            let source = self.create_source(source_id)?;
+            self.sources.insert(source_id, source);
+        }
+
+        let source = self.sources.get_mut(&source_id).unwrap();
+        source.version_status(pkg_id)
     }
 
     /// Load a package from its source.
@@ -142,8 +215,14 @@ impl SourceCache {
     }
 
     /// Ensure all sources for the given dependencies are ready.
+    ///
+    /// Version-only registry dependencies are skipped: which registry serves
+    /// them is only known at query time, and querying fetches the index.
     pub fn ensure_ready(&mut self, deps: &[Dependency]) -> Result<()> {
         for dep in deps {
+            if dep.source_id().is_registry() && is_default_registry(dep.source_id()) {
+                continue;
+            }
             let source = self.get_or_create(dep)?;
             source.ensure_ready()?;
         }
@@ -189,4 +268,78 @@ mod tests {
         let summaries = cache.query(&dep).unwrap();
         assert_eq!(summaries.len(), 1);
     }
+
+    /// Serve a sparse registry containing the given `(name, version)` shims.
+    fn serve_registry(
+        dir: &Path,
+        packages: &[(&str, &str)],
+    ) -> crate::test_support::StaticHttpServer {
+        let server = crate::test_support::StaticHttpServer::serve(dir);
+        std::fs::write(dir.join("config.toml"), "[registry]\nname = \"test\"\n").unwrap();
+
+        for (name, version) in packages {
+            let pkg_dir = dir.join("index").join(&name[..1]).join(name);
+            std::fs::create_dir_all(&pkg_dir).unwrap();
+            std::fs::write(
+                pkg_dir.join(format!("{}.toml", version)),
+                format!(
+                    "[package]\nname = \"{name}\"\nversion = \"{version}\"\n\n\
+                     [source.tarball]\nurl = \"{}{name}.tar.gz\"\nsha256 = \"{}\"\n",
+                    server.url(),
+                    "0".repeat(64)
+                ),
+            )
+            .unwrap();
+            crate::sources::registry::sparse::write_version_listing(&pkg_dir).unwrap();
+        }
+
+        server
+    }
+
+    #[test]
+    fn test_query_searches_registries_in_priority_order() {
+        use crate::util::context::RegistryEntry;
+
+        let tmp = TempDir::new().unwrap();
+        let (public_dir, internal_dir) = (tmp.path().join("public"), tmp.path().join("internal"));
+        std::fs::create_dir_all(&public_dir).unwrap();
+        std::fs::create_dir_all(&internal_dir).unwrap();
+
+        let public = serve_registry(
+            &public_dir,
+            &[("zlib", "1.3.1"), ("internal-auth", "9.0.0")],
+        );
+        let internal = serve_registry(&internal_dir, &[("internal-auth", "1.0.0")]);
+
+        let mut registries = RegistryList::new();
+        registries
+            .add(RegistryEntry::new("public", format!("sparse+{}", public.url())).with_priority(0));
+        registries.add(
+            RegistryEntry::new("internal", format!("sparse+{}", internal.url()))
+                .with_priority(100)
+                .with_packages(vec!["internal-*".to_string()]),
+        );
+
+        let mut cache =
+            SourceCache::new_with_vcpkg(tmp.path().join("cache"), None).with_registries(registries);
+        let default_id =
+            SourceId::for_registry(&Url::parse(DEFAULT_REGISTRY_URL).unwrap()).unwrap();
+
+        // Unscoped names come from the highest-priority registry that has them
+        let zlib = cache.query(&Dependency::new("zlib", default_id)).unwrap();
+        assert_eq!(zlib.len(), 1);
+        assert!(zlib[0].source_id().url().as_str().contains(&public.url()));
+
+        // Scoped names only come from the claiming registry, even if a
+        // higher-priority registry publishes the same name
+        let auth = cache
+            .query(&Dependency::new("internal-auth", default_id))
+            .unwrap();
+        assert_eq!(auth.len(), 1);
+        assert_eq!(auth[0].version().to_string(), "1.0.0");
+        assert!(auth[0].source_id().url().as_str().contains(&internal.url()));
+        assert!(public
+            .status_for("/index/i/internal-auth/versions")
+            .is_empty());
+    }
 }
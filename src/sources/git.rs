@@ -9,7 +9,8 @@ use url::Url;
 use crate::core::source_id::GitReference;
 use crate::core::workspace::find_manifest;
 use crate::core::{Dependency, Package, PackageId, SourceId, Summary};
-use crate::sources::Source;
+use crate::sources::{Fetcher, Source};
+use crate::util::auth::redact_url;
 use crate::util::hash::sha256_str;
 
 /// A source for git dependencies.
@@ -31,6 +32,9 @@ pub struct GitSource {
 
     /// Resolved commit hash
     precise: Option<String>,
+
+    /// Authenticated git fetcher
+    fetcher: Fetcher,
 }
 
 impl GitSource {
@@ -57,9 +61,16 @@ impl GitSource {
             source_id,
             package: None,
             precise: None,
+            fetcher: Fetcher::default(),
         }
     }
 
+    /// Use the given fetcher for clones and updates.
+    pub fn with_fetcher(mut self, fetcher: Fetcher) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
     /// Clone or update the repository.
     fn fetch(&mut self) -> Result<()> {
         if self.checkout_path.exists() {
@@ -77,25 +88,25 @@ impl GitSource {
     }
 
     fn clone(&self) -> Result<()> {
-        tracing::info!("Cloning {}", self.remote);
+        tracing::info!("Cloning {}", redact_url(self.remote.as_str()));
 
         std::fs::create_dir_all(self.checkout_path.parent().unwrap())?;
 
-        Repository::clone(self.remote.as_str(), &self.checkout_path)
-            .with_context(|| format!("failed to clone {}", self.remote))?;
+        self.fetcher
+            .git_clone(self.remote.as_str(), &self.checkout_path)?;
 
         Ok(())
     }
 
     fn update(&self) -> Result<()> {
-        tracing::info!("Updating {}", self.remote);
+        tracing::info!("Updating {}", redact_url(self.remote.as_str()));
 
         let repo = Repository::open(&self.checkout_path)
             .with_context(|| "failed to open git repository")?;
 
         // Fetch from remote
-        let mut remote = repo.find_remote("origin")?;
-        remote.fetch(&["refs/heads/*:refs/heads/*"], None, None)?;
+        self.fetcher
+            .git_fetch(&repo, &["refs/heads/*:refs/heads/*"])?;
 
         Ok(())
     }
@@ -136,7 +147,11 @@ impl GitSource {
     fn load(&mut self) -> Result<&Package> {
         if self.package.is_none() {
             let manifest_path = find_manifest(&self.checkout_path).map_err(|err| {
-                anyhow::anyhow!("{}\n  while loading git repository: {}", err, self.remote)
+                anyhow::anyhow!(
+                    "{}\n  while loading git repository: {}",
+                    err,
+                    redact_url(self.remote.as_str())
+                )
             })?;
 
             // Create package with precise source ID
@@ -4,6 +4,7 @@
 //! (local paths, git repositories, registries).
 
 pub mod cache;
+pub mod fetch;
 pub mod git;
 pub mod path;
 pub mod registry;
@@ -11,8 +12,9 @@ pub mod source;
 pub mod vcpkg;
 
 pub use cache::SourceCache;
+pub use fetch::Fetcher;
 pub use git::GitSource;
 pub use path::PathSource;
 pub use registry::RegistrySource;
-pub use source::Source;
+pub use source::{Source, VersionStatus};
 pub use vcpkg::VcpkgSource;
@@ -32,10 +32,23 @@
 //! url = "https://github.com/madler/zlib"
 //! rev = "04f42ceca40f73e2978b50e93806c2a18c1281fc"
 //! ```
+//!
+//! # Sparse Registries
+//!
+//! A registry URL prefixed with `sparse+` (e.g., `sparse+https://example.com/registry`)
+//! is fetched file-by-file over HTTP instead of being cloned. See [`sparse`].
+//!
+//! # Yanked Versions
+//!
+//! A shim with `yanked = true` under `[metadata]` is skipped for new
+//! resolutions. Versions already recorded in a lockfile stay resolvable
+//! (see [`Source::add_to_yanked_whitelist`]).
 
 pub mod config;
 pub mod shim;
+pub mod sparse;
 
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
@@ -44,21 +57,23 @@ use url::Url;
 
 use crate::core::workspace::{find_manifest, ManifestError};
 use crate::core::{Dependency, Manifest, Package, PackageId, SourceId, Summary};
-use crate::sources::Source;
+use crate::sources::{Fetcher, Source, VersionStatus};
+use crate::util::auth::redact_url;
 use crate::util::hash::sha256_file;
 
 pub use config::RegistryConfig;
 pub use shim::{shim_path, validate_package_name, Shim, ShimPatch};
+pub use sparse::{is_sparse_url, SparseIndex};
 
 /// A source for registry dependencies.
 ///
-/// RegistrySource manages a cached git clone of the registry index
-/// and fetches package sources on demand based on shim files.
+/// RegistrySource manages a cached git clone (or sparse HTTP mirror) of the
+/// registry index and fetches package sources on demand based on shim files.
 pub struct RegistrySource {
-    /// Registry git URL
+    /// Registry git URL (or `sparse+` HTTP URL)
     registry_url: Url,
 
-    /// Local path to cloned registry index
+    /// Local path to cloned registry index (or sparse mirror)
     index_path: PathBuf,
 
     /// Local path for fetched package sources
@@ -75,13 +90,26 @@ pub struct RegistrySource {
 
     /// Whether the index has been fetched this session
     index_fetched: bool,
+
+    /// Sparse HTTP index (only for `sparse+` registry URLs, created on first fetch)
+    sparse: Option<SparseIndex>,
+
+    /// Locked (name, version) pairs that may be selected even if yanked
+    yanked_whitelist: HashSet<(String, String)>,
+
+    /// Authenticated git/HTTP fetcher
+    fetcher: Fetcher,
 }
 
 impl RegistrySource {
     /// Create a new registry source.
     pub fn new(registry_url: Url, cache_dir: &Path, source_id: SourceId) -> Self {
         // Create unique directory names for this registry
-        let registry_dir_name = sanitize_url_for_path(&registry_url);
+        let registry_dir_name = if is_sparse_url(&registry_url) {
+            format!("sparse-{}", sanitize_url_for_path(&registry_url))
+        } else {
+            sanitize_url_for_path(&registry_url)
+        };
 
         let index_path = cache_dir.join("registry").join(&registry_dir_name);
         let src_cache_path = cache_dir.join("registry-src").join(&registry_dir_name);
@@ -94,9 +122,18 @@ impl RegistrySource {
             config: None,
             packages: std::collections::HashMap::new(),
             index_fetched: false,
+            sparse: None,
+            yanked_whitelist: HashSet::new(),
+            fetcher: Fetcher::default(),
         }
     }
 
+    /// Use the given fetcher for the index, git sources and tarballs.
+    pub fn with_fetcher(mut self, fetcher: Fetcher) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
     /// Create a registry source from a local directory path.
     ///
     /// This is used for CI verification where the registry is already cloned
@@ -142,6 +179,9 @@ impl RegistrySource {
             config: None,
             packages: std::collections::HashMap::new(),
             index_fetched: true, // Mark as fetched since we're using a local path
+            sparse: None,
+            yanked_whitelist: HashSet::new(),
+            fetcher: Fetcher::default(),
         };
 
         // Load the config immediately
@@ -156,7 +196,9 @@ impl RegistrySource {
             return Ok(());
         }
 
-        if self.index_path.exists() {
+        if is_sparse_url(&self.registry_url) {
+            self.fetch_sparse_config()?;
+        } else if self.index_path.exists() {
             self.update_index()?;
         } else {
             self.clone_index()?;
@@ -170,27 +212,57 @@ impl RegistrySource {
 
     /// Clone the registry index.
     fn clone_index(&self) -> Result<()> {
-        tracing::info!("Cloning registry index from {}", self.registry_url);
+        let display_url = redact_url(self.registry_url.as_str());
+        tracing::info!("Cloning registry index from {}", display_url);
 
         std::fs::create_dir_all(self.index_path.parent().unwrap())?;
 
-        Repository::clone(self.registry_url.as_str(), &self.index_path).with_context(|| {
-            format!("failed to clone registry index from {}", self.registry_url)
-        })?;
+        self.fetcher
+            .git_clone(self.registry_url.as_str(), &self.index_path)
+            .with_context(|| format!("failed to clone registry index from {}", display_url))?;
+
+        Ok(())
+    }
+
+    /// Set up the sparse index and refresh its `config.toml`.
+    fn fetch_sparse_config(&mut self) -> Result<()> {
+        let sparse = match self.sparse.take() {
+            Some(sparse) => sparse,
+            None => SparseIndex::new(&self.registry_url, self.index_path.clone())?
+                .with_credentials(self.fetcher.credentials().clone()),
+        };
+
+        tracing::info!(
+            "Updating sparse registry index from {}",
+            redact_url(sparse.base_url().as_str())
+        );
+
+        let found = sparse.fetch("config.toml")?;
+        self.sparse = Some(sparse);
+
+        if found.is_none() {
+            bail!(
+                "sparse registry at {} has no config.toml",
+                redact_url(self.registry_url.as_str())
+            );
+        }
 
         Ok(())
     }
 
     /// Update the registry index.
     fn update_index(&self) -> Result<()> {
-        tracing::info!("Updating registry index from {}", self.registry_url);
+        tracing::info!(
+            "Updating registry index from {}",
+            redact_url(self.registry_url.as_str())
+        );
 
         let repo =
             Repository::open(&self.index_path).with_context(|| "failed to open registry index")?;
 
         // Fetch from remote
-        let mut remote = repo.find_remote("origin")?;
-        remote.fetch(&["refs/heads/*:refs/heads/*"], None, None)?;
+        self.fetcher
+            .git_fetch(&repo, &["refs/heads/*:refs/heads/*"])?;
 
         // Reset to origin/HEAD or origin/main
         let head = repo.head()?;
@@ -242,6 +314,10 @@ impl RegistrySource {
     /// `Ok(None)` if the shim file doesn't exist,
     /// or an error if the shim exists but is invalid.
     pub fn load_shim(&self, name: &str, version: &str) -> Result<Option<Shim>> {
+        if let Some(sparse) = &self.sparse {
+            sparse.fetch(&format!("index/{}", shim_path(name, version)?))?;
+        }
+
         let shim_path = self.get_shim_path(name, version)?;
 
         if !shim_path.exists() {
@@ -319,10 +395,13 @@ impl RegistrySource {
 
     /// Fetch a git source.
     fn fetch_git_source(&self, git: &shim::GitSource, dest: &Path) -> Result<()> {
-        tracing::info!("Fetching git source from {} at {}", git.url, &git.rev[..8]);
+        tracing::info!(
+            "Fetching git source from {} at {}",
+            redact_url(&git.url),
+            &git.rev[..8]
+        );
 
-        let repo = Repository::clone(&git.url, dest)
-            .with_context(|| format!("failed to clone {}", git.url))?;
+        let repo = self.fetcher.git_clone(&git.url, dest)?;
 
         // Checkout specific commit
         let oid = git2::Oid::from_str(&git.rev)?;
@@ -337,30 +416,18 @@ impl RegistrySource {
     /// Downloads the tarball from the URL, verifies the SHA256 hash,
     /// and extracts the contents to the destination directory.
     fn fetch_tarball_source(&self, tarball: &shim::TarballSource, dest: &Path) -> Result<()> {
-        tracing::info!("Fetching tarball from {}", tarball.url);
+        let display_url = redact_url(&tarball.url);
+        tracing::info!("Fetching tarball from {}", display_url);
 
-        // Download the tarball to a temporary file
-        let response = reqwest::blocking::get(&tarball.url)
-            .with_context(|| format!("failed to download tarball from {}", tarball.url))?;
-
-        if !response.status().is_success() {
-            bail!(
-                "failed to download tarball from {}: HTTP {}",
-                tarball.url,
-                response.status()
-            );
-        }
-
-        let tarball_bytes = response
-            .bytes()
-            .with_context(|| "failed to read tarball response body")?;
+        // Download the tarball, with credentials for private artifact servers
+        let tarball_bytes = self.fetcher.http_get(&tarball.url)?;
 
         // Verify SHA256 hash
         let actual_hash = crate::util::hash::sha256_bytes(&tarball_bytes);
         if actual_hash != tarball.sha256 {
             bail!(
                 "tarball hash mismatch for {}:\n  expected: {}\n  actual:   {}",
-                tarball.url,
+                display_url,
                 tarball.sha256,
                 actual_hash
             );
@@ -370,7 +437,7 @@ impl RegistrySource {
 
         // Extract the tarball
         extract_tarball(&tarball_bytes, dest, tarball.strip_prefix.as_deref())
-            .with_context(|| format!("failed to extract tarball from {}", tarball.url))?;
+            .with_context(|| format!("failed to extract tarball from {}", display_url))?;
 
         tracing::info!(
             "Extracted tarball to {} (strip_prefix: {:?})",
@@ -393,6 +460,12 @@ impl RegistrySource {
         let shim_dir = shim_path.parent().unwrap();
 
         for patch in &shim.patches {
+            if let Some(sparse) = &self.sparse {
+                let relative = shim::shim_path(&shim.package.name, &shim.package.version)?;
+                let shim_rel_dir = relative.rsplit_once('/').map_or("", |(dir, _)| dir);
+                sparse.fetch(&format!("index/{}/{}", shim_rel_dir, patch.file))?;
+            }
+
             let patch_path = shim_dir.join(&patch.file);
 
             if !patch_path.exists() {
@@ -514,6 +587,8 @@ impl RegistrySource {
             documentation: None,
             keywords: Vec::new(),
             categories: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
         };
 
         // Build the surface from override
@@ -590,6 +665,7 @@ impl RegistrySource {
             package: Some(package),
             workspace: None,
             dependencies: std::collections::HashMap::new(),
+            dev_dependencies: std::collections::HashMap::new(),
             targets: vec![target],
             profiles: std::collections::HashMap::new(),
             build: crate::core::manifest::BuildConfig::default(),
@@ -597,6 +673,12 @@ impl RegistrySource {
         })
     }
 
+    /// Check if a yanked version is allowed because it is already locked.
+    fn is_whitelisted(&self, name: &str, version: &str) -> bool {
+        self.yanked_whitelist
+            .contains(&(name.to_string(), version.to_string()))
+    }
+
     /// Compute the shim file hash for lockfile provenance.
     #[allow(dead_code)] // Will be used when lockfile provenance is implemented
     fn compute_shim_hash(&self, name: &str, version: &str) -> Result<String> {
@@ -607,12 +689,19 @@ impl RegistrySource {
     /// List all available versions for a package by scanning the directory.
     ///
     /// This is used when a version range or wildcard is specified.
+    /// Sparse registries read the package's `versions` listing instead.
     fn list_available_versions(&self, name: &str) -> Result<Vec<String>> {
         let first_char = name
             .chars()
             .next()
             .ok_or_else(|| anyhow::anyhow!("invalid empty package name"))?;
 
+        if let Some(sparse) = &self.sparse {
+            let mut versions = sparse.fetch_versions(&format!("index/{}/{}", first_char, name))?;
+            sort_versions_newest_first(&mut versions);
+            return Ok(versions);
+        }
+
         // Path: index/<first_char>/<name>/
         let package_dir = self
             .index_path
@@ -641,17 +730,23 @@ impl RegistrySource {
             }
         }
 
-        // Sort versions (newest first for better resolver behavior)
-        versions.sort_by(|a, b| {
-            let va: semver::Version = a.parse().unwrap();
-            let vb: semver::Version = b.parse().unwrap();
-            vb.cmp(&va)
-        });
+        sort_versions_newest_first(&mut versions);
 
         Ok(versions)
     }
 }
 
+/// Sort version strings newest first (for better resolver behavior).
+///
+/// All entries must be valid semver.
+fn sort_versions_newest_first(versions: &mut [String]) {
+    versions.sort_by(|a, b| {
+        let va: semver::Version = a.parse().unwrap();
+        let vb: semver::Version = b.parse().unwrap();
+        vb.cmp(&va)
+    });
+}
+
 impl Source for RegistrySource {
     fn name(&self) -> &str {
         "registry"
@@ -688,6 +783,16 @@ impl Source for RegistrySource {
                     return Ok(vec![]);
                 }
 
+                if shim.is_yanked() && !self.is_whitelisted(name, &shim.package.version) {
+                    bail!(
+                        "{} v{} has been yanked from the registry\n\
+                         help: choose another version of `{}` in Harbour.toml",
+                        name,
+                        shim.package.version,
+                        name
+                    );
+                }
+
                 // Fetch the actual source
                 let source_dir = self.fetch_package_source(&shim)?;
 
@@ -718,6 +823,12 @@ impl Source for RegistrySource {
                 }
 
                 if let Some(shim) = self.load_shim(name, &version_str)? {
+                    // Yanked versions are only eligible if already locked
+                    if shim.is_yanked() && !self.is_whitelisted(name, &version_str) {
+                        tracing::debug!("skipping yanked version {} v{}", name, version_str);
+                        continue;
+                    }
+
                     // Fetch the actual source
                     let source_dir = self.fetch_package_source(&shim)?;
 
@@ -809,6 +920,30 @@ impl Source for RegistrySource {
         Ok(package)
     }
 
+    fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        for pkg_id in pkgs {
+            let source_id = pkg_id.source_id();
+            if source_id.is_registry() && source_id.url() == self.source_id.url() {
+                self.yanked_whitelist
+                    .insert((pkg_id.name().to_string(), pkg_id.version().to_string()));
+            }
+        }
+    }
+
+    fn version_status(&mut self, pkg_id: PackageId) -> Result<VersionStatus> {
+        self.fetch_index()?;
+
+        let status = match self.load_shim(pkg_id.name().as_str(), &pkg_id.version().to_string())? {
+            Some(shim) => VersionStatus {
+                yanked: shim.is_yanked(),
+                deprecated: shim.deprecation().map(str::to_string),
+            },
+            None => VersionStatus::default(),
+        };
+
+        Ok(status)
+    }
+
     fn is_cached(&self, pkg_id: PackageId) -> bool {
         let name = pkg_id.name().as_str();
         let version = pkg_id.version().to_string();
@@ -1181,4 +1316,143 @@ mod tests {
         // The prefix directory itself should not exist
         assert!(!dest.join("mylib-1.0.0").exists());
     }
+
+    /// Build a gzipped tarball containing a minimal `mylib` Harbour.toml.
+    fn mylib_tarball(version: &str) -> Vec<u8> {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let manifest = format!("[package]\nname = \"mylib\"\nversion = \"{}\"\n", version);
+        let mut tar_data = Vec::new();
+        {
+            let encoder = GzEncoder::new(&mut tar_data, Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            let mut header = tar::Header::new_gnu();
+            header.set_path("Harbour.toml").unwrap();
+            header.set_size(manifest.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, manifest.as_bytes()).unwrap();
+            builder.finish().unwrap();
+        }
+        tar_data
+    }
+
+    #[test]
+    fn test_sparse_registry_query() {
+        use crate::test_support::StaticHttpServer;
+
+        // Package tarball containing a Harbour.toml
+        let tar_data = mylib_tarball("1.0.0");
+
+        let served = TempDir::new().unwrap();
+        let server = StaticHttpServer::serve(served.path());
+        std::fs::write(served.path().join("mylib-1.0.0.tar.gz"), &tar_data).unwrap();
+        std::fs::write(
+            served.path().join("config.toml"),
+            "[registry]\nname = \"sparse\"\n",
+        )
+        .unwrap();
+
+        let pkg_dir = served.path().join("index").join("m").join("mylib");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(
+            pkg_dir.join("1.0.0.toml"),
+            format!(
+                "[package]\nname = \"mylib\"\nversion = \"1.0.0\"\n\n\
+                 [source.tarball]\nurl = \"{}mylib-1.0.0.tar.gz\"\nsha256 = \"{}\"\n",
+                server.url(),
+                crate::util::hash::sha256_bytes(&tar_data)
+            ),
+        )
+        .unwrap();
+        sparse::write_version_listing(&pkg_dir).unwrap();
+
+        let cache = TempDir::new().unwrap();
+        let url = Url::parse(&format!("sparse+{}", server.url())).unwrap();
+        let source_id = SourceId::for_registry(&url).unwrap();
+        let mut source = RegistrySource::new(url, cache.path(), source_id);
+
+        let dep = Dependency::new("mylib", source_id).with_version_req("^1.0".parse().unwrap());
+        let summaries = source.query(&dep).unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].version().to_string(), "1.0.0");
+
+        // Only the needed index files were fetched
+        assert_eq!(server.status_for("/config.toml"), vec![200]);
+        assert_eq!(server.status_for("/index/m/mylib/versions"), vec![200]);
+        assert_eq!(server.status_for("/index/m/mylib/1.0.0.toml"), vec![200]);
+        assert!(source.index_path().join("index/m/mylib/1.0.0.toml").exists());
+    }
+
+    #[test]
+    fn test_yanked_versions_skipped_unless_locked() {
+        use crate::test_support::StaticHttpServer;
+
+        let served = TempDir::new().unwrap();
+        let server = StaticHttpServer::serve(served.path());
+        std::fs::write(
+            served.path().join("config.toml"),
+            "[registry]\nname = \"sparse\"\n",
+        )
+        .unwrap();
+
+        let pkg_dir = served.path().join("index").join("m").join("mylib");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        for (version, yanked) in [("1.0.0", false), ("1.1.0", true)] {
+            let tar_data = mylib_tarball(version);
+            let tarball = format!("mylib-{}.tar.gz", version);
+            std::fs::write(served.path().join(&tarball), &tar_data).unwrap();
+            std::fs::write(
+                pkg_dir.join(format!("{}.toml", version)),
+                format!(
+                    "[package]\nname = \"mylib\"\nversion = \"{}\"\n\n\
+                     [source.tarball]\nurl = \"{}{}\"\nsha256 = \"{}\"\n\n\
+                     [metadata]\nyanked = {}\ndeprecated = \"use mylib2\"\n",
+                    version,
+                    server.url(),
+                    tarball,
+                    crate::util::hash::sha256_bytes(&tar_data),
+                    yanked
+                ),
+            )
+            .unwrap();
+        }
+        sparse::write_version_listing(&pkg_dir).unwrap();
+
+        let cache = TempDir::new().unwrap();
+        let url = Url::parse(&format!("sparse+{}", server.url())).unwrap();
+        let source_id = SourceId::for_registry(&url).unwrap();
+        let range = Dependency::new("mylib", source_id).with_version_req("^1.0".parse().unwrap());
+        let exact = Dependency::new("mylib", source_id).with_version_req("=1.1.0".parse().unwrap());
+
+        // New resolutions never see the yanked version
+        let mut source = RegistrySource::new(url.clone(), cache.path(), source_id);
+        let versions: Vec<String> = source
+            .query(&range)
+            .unwrap()
+            .iter()
+            .map(|s| s.version().to_string())
+            .collect();
+        assert_eq!(versions, vec!["1.0.0"]);
+
+        let err = source.query(&exact).unwrap_err();
+        assert!(err.to_string().contains("has been yanked"));
+
+        // Once locked, the yanked version stays selectable
+        let locked = PackageId::new("mylib", "1.1.0".parse().unwrap(), source_id);
+        source.add_to_yanked_whitelist(&[locked]);
+        let versions: Vec<String> = source
+            .query(&range)
+            .unwrap()
+            .iter()
+            .map(|s| s.version().to_string())
+            .collect();
+        assert_eq!(versions, vec!["1.1.0", "1.0.0"]);
+        assert_eq!(source.query(&exact).unwrap().len(), 1);
+
+        let status = source.version_status(locked).unwrap();
+        assert!(status.yanked);
+        assert_eq!(status.deprecated.as_deref(), Some("use mylib2"));
+    }
 }
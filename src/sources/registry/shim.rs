@@ -28,7 +28,7 @@ pub struct Shim {
     pub source: ShimSource,
 
     /// Patches to apply in order
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub patches: Vec<ShimPatch>,
 
     /// Curation metadata (tier, category, platforms, etc.)
@@ -203,6 +203,15 @@ pub struct ShimMetadata {
     /// Consumer test harness configuration
     #[serde(default)]
     pub harness: Option<HarnessConfig>,
+
+    /// Whether this version has been withdrawn.
+    /// Yanked versions are skipped by new resolutions but still honoured when locked.
+    #[serde(default)]
+    pub yanked: bool,
+
+    /// Deprecation notice (e.g., "use zlib-ng instead")
+    #[serde(default)]
+    pub deprecated: Option<String>,
 }
 
 /// Platform support information.
@@ -548,6 +557,16 @@ impl Shim {
         self.metadata.as_ref().and_then(|m| m.harness.as_ref())
     }
 
+    /// Check if this version has been yanked.
+    pub fn is_yanked(&self) -> bool {
+        self.metadata.as_ref().is_some_and(|m| m.yanked)
+    }
+
+    /// Get the deprecation notice, if this version is deprecated.
+    pub fn deprecation(&self) -> Option<&str> {
+        self.metadata.as_ref().and_then(|m| m.deprecated.as_deref())
+    }
+
     /// Get platform support info.
     pub fn platforms(&self) -> Option<&PlatformSupport> {
         self.metadata.as_ref().and_then(|m| m.platforms.as_ref())
@@ -1023,4 +1042,48 @@ checksum = "sha256:abc123def456789012345678901234567890123456789012345678901234a
             )
         );
     }
+
+    #[test]
+    fn test_parse_yanked_and_deprecated() {
+        let content = r#"
+[package]
+name = "zlib"
+version = "1.3.0"
+
+[source.git]
+url = "https://github.com/madler/zlib"
+rev = "04f42ceca40f73e2978b50e93806c2a18c1281fc"
+
+[metadata]
+yanked = true
+deprecated = "CVE-2023-45853; upgrade to 1.3.1"
+"#;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("shim.toml");
+
+        let shim = Shim::parse(content, &path).unwrap();
+        assert!(shim.is_yanked());
+        assert_eq!(shim.deprecation(), Some("CVE-2023-45853; upgrade to 1.3.1"));
+    }
+
+    #[test]
+    fn test_not_yanked_by_default() {
+        let content = r#"
+[package]
+name = "zlib"
+version = "1.3.1"
+
+[source.git]
+url = "https://github.com/madler/zlib"
+rev = "04f42ceca40f73e2978b50e93806c2a18c1281fc"
+"#;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("shim.toml");
+
+        let shim = Shim::parse(content, &path).unwrap();
+        assert!(!shim.is_yanked());
+        assert_eq!(shim.deprecation(), None);
+    }
 }
@@ -0,0 +1,425 @@
+//! Sparse HTTP registry index.
+//!
+//! A sparse registry serves the same layout as a git registry checkout over
+//! plain HTTP, so clients only download the files they actually need instead
+//! of cloning the whole index.
+//!
+//! # Layout
+//!
+//! ```text
+//! https://example.com/registry/
+//! ├── config.toml                      # Registry metadata
+//! └── index/
+//!     └── z/
+//!         └── zlib/
+//!             ├── versions             # One version per line
+//!             ├── 1.3.0.toml           # Shim files
+//!             └── 1.3.1.toml
+//! ```
+//!
+//! A sparse registry is selected with a `sparse+` URL prefix:
+//!
+//! ```toml
+//! [dependencies]
+//! zlib = { version = "1.3", registry = "sparse+https://example.com/registry" }
+//! ```
+//!
+//! Fetched files are mirrored on disk so the rest of [`RegistrySource`]
+//! can read shims and patches from a local directory, exactly as it does
+//! for a git checkout. Each mirrored file has a small metadata record with
+//! its `ETag` and `Last-Modified` headers, which are sent back as
+//! `If-None-Match` / `If-Modified-Since` on the next revalidation.
+//!
+//! [`RegistrySource`]: super::RegistrySource
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::util::auth::{redact_url, Credentials};
+
+/// URL scheme prefix that selects the sparse protocol.
+pub const SPARSE_PREFIX: &str = "sparse+";
+
+/// Name of the per-package version listing file.
+pub const VERSIONS_FILE: &str = "versions";
+
+/// Directory (inside the mirror) holding HTTP cache metadata.
+const CACHE_META_DIR: &str = ".cache";
+
+/// Check if a registry URL uses the sparse protocol.
+pub fn is_sparse_url(url: &Url) -> bool {
+    url.scheme().starts_with(SPARSE_PREFIX)
+}
+
+/// HTTP cache validators recorded for a mirrored file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    /// `ETag` response header
+    #[serde(default)]
+    etag: Option<String>,
+
+    /// `Last-Modified` response header
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+/// A sparse registry index fetched file-by-file over HTTP.
+pub struct SparseIndex {
+    /// Base URL with the `sparse+` prefix removed (always ends in `/`)
+    base_url: Url,
+
+    /// Local mirror of fetched files
+    mirror_path: PathBuf,
+
+    /// HTTP client (reused across requests)
+    client: reqwest::blocking::Client,
+
+    /// Files already revalidated this session
+    fresh: Mutex<HashSet<String>>,
+
+    /// Per-host credentials attached to each request
+    credentials: Credentials,
+}
+
+impl SparseIndex {
+    /// Create a sparse index for a `sparse+http(s)://` registry URL.
+    ///
+    /// Fetched files are mirrored under `mirror_path`.
+    pub fn new(registry_url: &Url, mirror_path: PathBuf) -> Result<Self> {
+        let raw = registry_url
+            .as_str()
+            .strip_prefix(SPARSE_PREFIX)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "not a sparse registry URL: {}",
+                    redact_url(registry_url.as_str())
+                )
+            })?;
+
+        let mut base_url = Url::parse(raw)
+            .with_context(|| format!("invalid sparse registry URL: {}", redact_url(raw)))?;
+
+        if !matches!(base_url.scheme(), "http" | "https") {
+            bail!(
+                "sparse registries must use http or https, got '{}'",
+                base_url.scheme()
+            );
+        }
+
+        // Ensure relative joins stay under the base path
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("harbour/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .context("failed to create HTTP client")?;
+
+        Ok(SparseIndex {
+            base_url,
+            mirror_path,
+            client,
+            fresh: Mutex::new(HashSet::new()),
+            credentials: Credentials::default(),
+        })
+    }
+
+    /// Attach per-host credentials to index requests.
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Get the HTTP base URL (without the `sparse+` prefix).
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Get the local mirror directory.
+    pub fn mirror_path(&self) -> &Path {
+        &self.mirror_path
+    }
+
+    /// Ensure a registry file is present and up to date in the local mirror.
+    ///
+    /// `relative` is a `/`-separated path relative to the registry root
+    /// (e.g., `index/z/zlib/1.3.1.toml`). Each file is revalidated at most
+    /// once per session.
+    ///
+    /// Returns the local path if the file exists, or `None` if the server
+    /// reports it missing.
+    pub fn fetch(&self, relative: &str) -> Result<Option<PathBuf>> {
+        let local_path = self.local_path(relative);
+
+        if self.fresh.lock().unwrap().contains(relative) {
+            return Ok(local_path.exists().then_some(local_path));
+        }
+
+        let url = self
+            .base_url
+            .join(relative)
+            .with_context(|| format!("invalid registry path: {}", relative))?;
+
+        let meta_path = self.meta_path(relative);
+        let cached_meta = if local_path.exists() {
+            read_meta(&meta_path)
+        } else {
+            None
+        };
+
+        let display_url = redact_url(url.as_str());
+        tracing::debug!("Fetching {}", display_url);
+
+        let mut request = self
+            .credentials
+            .authorize(self.client.get(url.clone()), &url);
+        if let Some(meta) = &cached_meta {
+            if let Some(etag) = &meta.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request
+            .send()
+            .map_err(|e| anyhow::anyhow!("failed to fetch {}: {}", display_url, e.without_url()))?;
+
+        let status = response.status();
+        let result = if status == StatusCode::NOT_MODIFIED && cached_meta.is_some() {
+            tracing::debug!("Not modified: {}", relative);
+            Some(local_path)
+        } else if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+            // Drop stale mirror entries so they aren't picked up later
+            let _ = std::fs::remove_file(&local_path);
+            let _ = std::fs::remove_file(&meta_path);
+            None
+        } else if status.is_success() {
+            let meta = CacheMeta {
+                etag: header_string(response.headers(), ETAG),
+                last_modified: header_string(response.headers(), LAST_MODIFIED),
+            };
+
+            let body = response
+                .bytes()
+                .with_context(|| format!("failed to read response body from {}", display_url))?;
+
+            write_file(&local_path, &body)?;
+            write_file(&meta_path, serde_json::to_string(&meta)?.as_bytes())?;
+
+            Some(local_path)
+        } else {
+            bail!("failed to fetch {}: HTTP {}", display_url, status);
+        };
+
+        self.fresh.lock().unwrap().insert(relative.to_string());
+
+        Ok(result)
+    }
+
+    /// Fetch the version listing for a package.
+    ///
+    /// The listing is a plain-text file at `index/<letter>/<name>/versions`
+    /// with one version per line. Blank lines and `#` comments are ignored.
+    pub fn fetch_versions(&self, package_dir: &str) -> Result<Vec<String>> {
+        let relative = format!("{}/{}", package_dir.trim_end_matches('/'), VERSIONS_FILE);
+
+        let Some(path) = self.fetch(&relative)? else {
+            return Ok(vec![]);
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read version listing: {}", path.display()))?;
+
+        Ok(parse_version_listing(&content))
+    }
+
+    /// Map a registry-relative path to its location in the mirror.
+    fn local_path(&self, relative: &str) -> PathBuf {
+        relative
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .fold(self.mirror_path.clone(), |p, s| p.join(s))
+    }
+
+    /// Map a registry-relative path to its cache metadata file.
+    fn meta_path(&self, relative: &str) -> PathBuf {
+        let mut path = self.mirror_path.join(CACHE_META_DIR);
+        for segment in relative.split('/').filter(|s| !s.is_empty()) {
+            path.push(segment);
+        }
+        path.set_extension(match path.extension() {
+            Some(ext) => format!("{}.json", ext.to_string_lossy()),
+            None => "json".to_string(),
+        });
+        path
+    }
+}
+
+/// Parse a version listing, keeping only valid semver entries.
+pub fn parse_version_listing(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| semver::Version::parse(line).is_ok())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Regenerate the `versions` listing for a package directory in a local
+/// registry checkout, so it can be served as a sparse index.
+///
+/// Versions are written oldest first, one per line.
+pub fn write_version_listing(package_dir: &Path) -> Result<()> {
+    let mut versions: Vec<semver::Version> = Vec::new();
+
+    for entry in std::fs::read_dir(package_dir)
+        .with_context(|| format!("failed to read {}", package_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            if let Some(version) = path
+                .file_stem()
+                .and_then(|s| semver::Version::parse(&s.to_string_lossy()).ok())
+            {
+                versions.push(version);
+            }
+        }
+    }
+
+    versions.sort();
+
+    let content: String = versions.iter().map(|v| format!("{}\n", v)).collect();
+    write_file(&package_dir.join(VERSIONS_FILE), content.as_bytes())
+}
+
+fn header_string(
+    headers: &reqwest::header::HeaderMap,
+    name: reqwest::header::HeaderName,
+) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+fn read_meta(path: &Path) -> Option<CacheMeta> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory: {}", parent.display()))?;
+    }
+    std::fs::write(path, content)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::StaticHttpServer;
+    use tempfile::TempDir;
+
+    fn setup_registry(root: &Path) {
+        std::fs::write(root.join("config.toml"), "[registry]\nname = \"sparse\"\n").unwrap();
+        let pkg_dir = root.join("index").join("z").join("zlib");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(pkg_dir.join("1.3.1.toml"), "shim").unwrap();
+        std::fs::write(pkg_dir.join("1.2.0.toml"), "old shim").unwrap();
+        write_version_listing(&pkg_dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_sparse_url() {
+        let sparse = Url::parse("sparse+https://example.com/registry").unwrap();
+        let git = Url::parse("https://github.com/example/registry").unwrap();
+        assert!(is_sparse_url(&sparse));
+        assert!(!is_sparse_url(&git));
+    }
+
+    #[test]
+    fn test_new_rejects_non_http() {
+        let tmp = TempDir::new().unwrap();
+        let url = Url::parse("sparse+ftp://example.com/registry").unwrap();
+        assert!(SparseIndex::new(&url, tmp.path().to_path_buf()).is_err());
+    }
+
+    #[test]
+    fn test_parse_version_listing() {
+        let versions = parse_version_listing("# comment\n1.0.0\n\nnot-a-version\n1.1.0\n");
+        assert_eq!(versions, vec!["1.0.0", "1.1.0"]);
+    }
+
+    #[test]
+    fn test_write_version_listing() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("1.10.0.toml"), "").unwrap();
+        std::fs::write(tmp.path().join("1.2.0.toml"), "").unwrap();
+        std::fs::write(tmp.path().join("notes.txt"), "").unwrap();
+
+        write_version_listing(tmp.path()).unwrap();
+
+        let content = std::fs::read_to_string(tmp.path().join(VERSIONS_FILE)).unwrap();
+        assert_eq!(content, "1.2.0\n1.10.0\n");
+    }
+
+    #[test]
+    fn test_fetch_and_revalidate() {
+        let served = TempDir::new().unwrap();
+        setup_registry(served.path());
+        let server = StaticHttpServer::serve(served.path());
+
+        let mirror = TempDir::new().unwrap();
+        let url = Url::parse(&format!("sparse+{}", server.url())).unwrap();
+
+        let index = SparseIndex::new(&url, mirror.path().to_path_buf()).unwrap();
+        let path = index.fetch("index/z/zlib/1.3.1.toml").unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "shim");
+        assert_eq!(server.status_for("/index/z/zlib/1.3.1.toml"), vec![200]);
+
+        // Same session: no second request
+        index.fetch("index/z/zlib/1.3.1.toml").unwrap().unwrap();
+        assert_eq!(server.status_for("/index/z/zlib/1.3.1.toml"), vec![200]);
+
+        // New session: conditional request answered with 304
+        let index = SparseIndex::new(&url, mirror.path().to_path_buf()).unwrap();
+        let path = index.fetch("index/z/zlib/1.3.1.toml").unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "shim");
+        assert_eq!(
+            server.status_for("/index/z/zlib/1.3.1.toml"),
+            vec![200, 304]
+        );
+    }
+
+    #[test]
+    fn test_fetch_missing_and_versions() {
+        let served = TempDir::new().unwrap();
+        setup_registry(served.path());
+        let server = StaticHttpServer::serve(served.path());
+
+        let mirror = TempDir::new().unwrap();
+        let url = Url::parse(&format!("sparse+{}", server.url())).unwrap();
+        let index = SparseIndex::new(&url, mirror.path().to_path_buf()).unwrap();
+
+        assert!(index.fetch("index/n/nope/1.0.0.toml").unwrap().is_none());
+        assert_eq!(
+            index.fetch_versions("index/z/zlib").unwrap(),
+            vec!["1.2.0", "1.3.1"]
+        );
+        assert!(index.fetch_versions("index/n/nope").unwrap().is_empty());
+    }
+}
@@ -28,4 +28,24 @@ pub trait Source {
 
     /// Check if a package is cached locally.
     fn is_cached(&self, pkg_id: PackageId) -> bool;
+
+    /// Allow yanked versions of these packages to be returned from `query`.
+    ///
+    /// Used to keep already-locked packages resolvable after they are yanked.
+    fn add_to_yanked_whitelist(&mut self, _pkgs: &[PackageId]) {}
+
+    /// Get the publication status (yanked/deprecated) of a package version.
+    fn version_status(&mut self, _pkg_id: PackageId) -> Result<VersionStatus> {
+        Ok(VersionStatus::default())
+    }
+}
+
+/// Publication status of a package version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionStatus {
+    /// The version was withdrawn from its registry
+    pub yanked: bool,
+
+    /// Deprecation notice, if the version is deprecated
+    pub deprecated: Option<String>,
 }
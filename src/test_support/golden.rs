@@ -0,0 +1,40 @@
+//! Golden-file helpers for the FFI bindings generators.
+//!
+//! Every generator is checked against the checked-in output for
+//! `test_ffi_project/include/mylib.h`. Set `HARBOUR_BLESS=1` to rewrite the
+//! golden files instead of comparing against them.
+
+use std::path::{Path, PathBuf};
+
+use crate::builder::bindings::{HeaderParser, ParsedHeader, TargetAbi};
+use crate::core::abi::TargetTriple;
+
+/// Root of the FFI test project.
+pub fn ffi_project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("test_ffi_project")
+}
+
+/// Parse the FFI test project's header with the `mylib_` prefix stripped.
+pub fn ffi_project_header() -> ParsedHeader {
+    HeaderParser::new()
+        .with_strip_prefix(Some("mylib_".to_string()))
+        .parse_file(&ffi_project_root().join("include/mylib.h"))
+        .unwrap()
+}
+
+/// The ABI golden files are generated for, so layouts don't depend on the host.
+pub fn golden_abi() -> TargetAbi {
+    TargetAbi::for_target(&TargetTriple::parse("x86_64-unknown-linux-gnu").unwrap())
+}
+
+/// Compare generated output against `test_ffi_project/bindings/<golden>`.
+pub fn assert_golden(output: &str, golden: &str) {
+    let golden_path = ffi_project_root().join("bindings").join(golden);
+    if std::env::var_os("HARBOUR_BLESS").is_some() {
+        std::fs::write(&golden_path, output).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&golden_path).unwrap();
+    assert_eq!(output, expected, "{} is out of date", golden_path.display());
+}
@@ -22,6 +22,7 @@
 //! ```
 
 pub mod fixtures;
+pub mod golden;
 
 use std::collections::HashMap;
 use std::io::Write;
@@ -549,6 +550,163 @@ impl MockHttpClient {
     }
 }
 
+/// A request received by [`StaticHttpServer`].
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// Request path (e.g., `/index/z/zlib/1.3.1.toml`).
+    pub path: String,
+    /// Request headers (names lowercased).
+    pub headers: HashMap<String, String>,
+    /// Response status code sent back.
+    pub status: u16,
+}
+
+/// A minimal HTTP/1.1 server that serves files from a directory.
+///
+/// Used to test code that talks to real HTTP endpoints (sparse registries,
+/// tarball downloads). Responses carry a content-hash `ETag` and honour
+/// `If-None-Match` with `304 Not Modified`.
+pub struct StaticHttpServer {
+    addr: std::net::SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    shutdown: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl StaticHttpServer {
+    /// Start serving `root` on an ephemeral localhost port.
+    pub fn serve(root: &Path) -> Self {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let listener =
+            std::net::TcpListener::bind("127.0.0.1:0").expect("failed to bind test server");
+        let addr = listener.local_addr().expect("failed to get server address");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let root = root.to_path_buf();
+        let log = Arc::clone(&requests);
+        let stop = Arc::clone(&shutdown);
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let _ = handle_static_request(stream, &root, &log);
+                }
+            }
+        });
+
+        StaticHttpServer {
+            addr,
+            requests,
+            shutdown,
+        }
+    }
+
+    /// Base URL of the server (ends in `/`).
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// All requests received so far.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Status codes returned for a given path, in order.
+    pub fn status_for(&self, path: &str) -> Vec<u16> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.path == path)
+            .map(|r| r.status)
+            .collect()
+    }
+}
+
+impl Drop for StaticHttpServer {
+    fn drop(&mut self) {
+        self.shutdown
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        // Wake the accept loop so it can observe the flag
+        let _ = std::net::TcpStream::connect(self.addr);
+    }
+}
+
+fn handle_static_request(
+    stream: std::net::TcpStream,
+    root: &Path,
+    log: &Mutex<Vec<RecordedRequest>>,
+) -> std::io::Result<()> {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let file_path = path
+        .trim_start_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != "..")
+        .fold(root.to_path_buf(), |p, s| p.join(s));
+
+    let (status, reason, etag, body) = match std::fs::read(&file_path) {
+        Ok(content) if file_path.is_file() => {
+            let etag = format!("\"{}\"", &crate::util::hash::sha256_bytes(&content)[..16]);
+            if headers.get("if-none-match") == Some(&etag) {
+                (304, "Not Modified", Some(etag), Vec::new())
+            } else {
+                (200, "OK", Some(etag), content)
+            }
+        }
+        _ => (404, "Not Found", None, b"Not Found".to_vec()),
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    if let Some(etag) = etag {
+        response.push_str(&format!("ETag: {}\r\n", etag));
+    }
+    response.push_str("\r\n");
+
+    // Record before responding so callers observe the request once they
+    // have the response
+    log.lock().unwrap().push(RecordedRequest {
+        path,
+        headers,
+        status,
+    });
+
+    let mut stream = stream;
+    stream.write_all(response.as_bytes())?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
 /// Helper to create a temporary test workspace with a Harbor.toml.
 ///
 /// Returns the TempDir handle - dropping it will clean up the directory.
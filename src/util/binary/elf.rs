@@ -0,0 +1,412 @@
+//! ELF dynamic section reading and run path rewriting.
+
+use anyhow::{bail, Context, Result};
+
+use super::{add_offset, to_usize, BinaryFormat, BinaryInfo, Bytes, Symbol, SymbolKind};
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_SONAME: u64 = 14;
+const DT_RPATH: u64 = 15;
+const DT_RUNPATH: u64 = 29;
+
+const SHT_DYNSYM: u32 = 11;
+const SHN_UNDEF: u16 = 0;
+
+const STB_GLOBAL: u8 = 1;
+const STB_WEAK: u8 = 2;
+const STB_GNU_UNIQUE: u8 = 10;
+
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+const STT_TLS: u8 = 6;
+const STT_GNU_IFUNC: u8 = 10;
+
+const STV_DEFAULT: u8 = 0;
+const STV_PROTECTED: u8 = 3;
+
+/// An ELF image's dynamic section.
+struct Dynamic<'a> {
+    bytes: Bytes<'a>,
+    /// `(tag, value)` entries up to `DT_NULL`
+    entries: Vec<(u64, u64)>,
+    /// File offset of the dynamic string table
+    strtab: usize,
+}
+
+impl Dynamic<'_> {
+    fn string(&self, offset: u64) -> Result<String> {
+        self.bytes.cstr(add_offset(self.strtab, offset)?)
+    }
+
+    fn value(&self, tag: u64) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, v)| *v)
+    }
+}
+
+/// Read the ELF class and byte order from the identification bytes.
+fn ident(data: &[u8]) -> Result<(Bytes<'_>, bool)> {
+    let is64 = match data.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => bail!("unknown ELF class"),
+    };
+    let big_endian = match data.get(5) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => bail!("unknown ELF byte order"),
+    };
+    Ok((Bytes::new(data, big_endian), is64))
+}
+
+/// Parse the dynamic section, or `None` for statically linked files.
+fn dynamic(data: &[u8]) -> Result<Option<Dynamic<'_>>> {
+    let (bytes, is64) = ident(data)?;
+
+    let (phoff, phentsize, phnum) = if is64 {
+        (bytes.u64(0x20)?, bytes.u16(0x36)?, bytes.u16(0x38)?)
+    } else {
+        (
+            u64::from(bytes.u32(0x1c)?),
+            bytes.u16(0x2a)?,
+            bytes.u16(0x2c)?,
+        )
+    };
+
+    // (vaddr, offset, filesz) of each loaded segment
+    let mut loads = Vec::new();
+    let mut dynamic = None;
+    for i in 0..usize::from(phnum) {
+        let ph = bytes.entry(to_usize(phoff)?, i, usize::from(phentsize))?;
+        let p_type = bytes.u32(ph)?;
+        let (offset, vaddr, filesz) = if is64 {
+            (bytes.u64(ph + 8)?, bytes.u64(ph + 16)?, bytes.u64(ph + 32)?)
+        } else {
+            (
+                u64::from(bytes.u32(ph + 4)?),
+                u64::from(bytes.u32(ph + 8)?),
+                u64::from(bytes.u32(ph + 16)?),
+            )
+        };
+        match p_type {
+            PT_LOAD => loads.push((vaddr, offset, filesz)),
+            PT_DYNAMIC => dynamic = Some((offset, filesz)),
+            _ => {}
+        }
+    }
+
+    let Some((offset, size)) = dynamic else {
+        return Ok(None);
+    };
+
+    let entry_size = if is64 { 16 } else { 8 };
+    let mut entries = Vec::new();
+    for i in 0..to_usize(size)? / entry_size {
+        let entry = bytes.entry(to_usize(offset)?, i, entry_size)?;
+        let tag = bytes.word(entry, is64)?;
+        if tag == DT_NULL {
+            break;
+        }
+        entries.push((tag, bytes.word(entry + entry_size / 2, is64)?));
+    }
+
+    // DT_STRTAB holds a virtual address; map it back to a file offset
+    let strtab_addr = entries
+        .iter()
+        .find(|(tag, _)| *tag == DT_STRTAB)
+        .map(|(_, value)| *value)
+        .context("dynamic section has no string table")?;
+    let (vaddr, offset, _) = loads
+        .iter()
+        .find(|(vaddr, _, filesz)| {
+            vaddr
+                .checked_add(*filesz)
+                .is_some_and(|end| (*vaddr..end).contains(&strtab_addr))
+        })
+        .context("dynamic string table is not in a loaded segment")?;
+    let strtab = add_offset(to_usize(*offset)?, strtab_addr - vaddr)?;
+
+    Ok(Some(Dynamic {
+        bytes,
+        entries,
+        strtab,
+    }))
+}
+
+pub(super) fn inspect(data: &[u8]) -> Result<BinaryInfo> {
+    let mut info = BinaryInfo::new(BinaryFormat::Elf);
+    let Some(dynamic) = dynamic(data)? else {
+        return Ok(info);
+    };
+
+    for &(tag, value) in &dynamic.entries {
+        if tag == DT_NEEDED {
+            info.needed.push(dynamic.string(value)?);
+        }
+    }
+    if let Some(value) = dynamic.value(DT_SONAME) {
+        info.soname = Some(dynamic.string(value)?);
+    }
+
+    // The loader ignores DT_RPATH when DT_RUNPATH is present
+    if let Some(value) = dynamic
+        .value(DT_RUNPATH)
+        .or_else(|| dynamic.value(DT_RPATH))
+    {
+        info.runpath = dynamic
+            .string(value)?
+            .split(':')
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    Ok(info)
+}
+
+/// Read the defined, visible global symbols of the `.dynsym` section.
+pub(super) fn symbols(data: &[u8]) -> Result<Vec<Symbol>> {
+    let (bytes, is64) = ident(data)?;
+    let (shoff, shentsize, shnum) = if is64 {
+        (bytes.u64(0x28)?, bytes.u16(0x3a)?, bytes.u16(0x3c)?)
+    } else {
+        (
+            u64::from(bytes.u32(0x20)?),
+            bytes.u16(0x2e)?,
+            bytes.u16(0x30)?,
+        )
+    };
+
+    // (offset, size, link) of each section
+    let sections = (0..usize::from(shnum))
+        .map(|i| {
+            let sh = bytes.entry(to_usize(shoff)?, i, usize::from(shentsize))?;
+            let section = if is64 {
+                (
+                    bytes.u64(sh + 24)?,
+                    bytes.u64(sh + 32)?,
+                    bytes.u32(sh + 40)?,
+                )
+            } else {
+                (
+                    u64::from(bytes.u32(sh + 16)?),
+                    u64::from(bytes.u32(sh + 20)?),
+                    bytes.u32(sh + 24)?,
+                )
+            };
+            Ok((bytes.u32(sh + 4)?, section))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let Some(&(_, (offset, size, link))) = sections.iter().find(|(t, _)| *t == SHT_DYNSYM) else {
+        bail!("file has no dynamic symbol table");
+    };
+    let (strtab, _, _) = sections
+        .get(to_usize(link.into())?)
+        .map(|(_, section)| *section)
+        .context("dynamic symbol table has no string table")?;
+    let strtab = to_usize(strtab)?;
+
+    let entry_size = if is64 { 24 } else { 16 };
+    let mut symbols = Vec::new();
+    // Entry 0 is the reserved undefined symbol
+    for i in 1..to_usize(size)? / entry_size {
+        let entry = bytes.entry(to_usize(offset)?, i, entry_size)?;
+        let (info, other, shndx, sym_size) = if is64 {
+            (
+                bytes.u8(entry + 4)?,
+                bytes.u8(entry + 5)?,
+                bytes.u16(entry + 6)?,
+                bytes.u64(entry + 16)?,
+            )
+        } else {
+            (
+                bytes.u8(entry + 12)?,
+                bytes.u8(entry + 13)?,
+                bytes.u16(entry + 14)?,
+                u64::from(bytes.u32(entry + 8)?),
+            )
+        };
+
+        if shndx == SHN_UNDEF
+            || !matches!(info >> 4, STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE)
+            || !matches!(other & 0x3, STV_DEFAULT | STV_PROTECTED)
+        {
+            continue;
+        }
+        let kind = match info & 0xf {
+            STT_FUNC | STT_GNU_IFUNC => SymbolKind::Function,
+            STT_OBJECT | STT_TLS => SymbolKind::Data,
+            _ => continue,
+        };
+
+        let name = bytes.cstr(add_offset(strtab, bytes.u32(entry)?.into())?)?;
+        // Code size changes with every rebuild and isn't part of the ABI
+        let size = (kind == SymbolKind::Data).then_some(sym_size);
+        symbols.push(Symbol { name, kind, size });
+    }
+    Ok(symbols)
+}
+
+pub(super) fn set_runpath(data: &mut [u8], runpath: &str) -> Result<()> {
+    let (start, len) = {
+        let dynamic = dynamic(data)?.context("file is statically linked")?;
+        let value = dynamic
+            .value(DT_RUNPATH)
+            .or_else(|| dynamic.value(DT_RPATH))
+            .context("file has no run path to replace")?;
+        let start = add_offset(dynamic.strtab, value)?;
+        (start, dynamic.string(value)?.len())
+    };
+
+    if runpath.len() > len {
+        bail!(
+            "new run path `{}` is longer than the existing {}-byte entry",
+            runpath,
+            len
+        );
+    }
+
+    // Pad with NULs so the tail of the old value doesn't leak through
+    let slot = &mut data[start..start + len];
+    slot.fill(0);
+    slot[..runpath.len()].copy_from_slice(runpath.as_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        exported_symbols, inspect as inspect_file, set_runpath as set_runpath_file,
+    };
+    use super::*;
+    use crate::util::process::find_executable;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn compile_shared(cc: &Path, dir: &Path, name: &str, args: &[&str]) -> std::path::PathBuf {
+        let src = dir.join(format!("{}.c", name));
+        std::fs::write(&src, format!("int {}_value(void) {{ return 1; }}\n", name)).unwrap();
+        let out = dir.join(format!("lib{}.so", name));
+        let status = Command::new(cc)
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(&out)
+            .arg(&src)
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        out
+    }
+
+    #[test]
+    fn test_inspect_and_set_runpath() {
+        let Some(cc) = find_executable("cc") else {
+            return;
+        };
+        if !cfg!(target_os = "linux") {
+            return;
+        }
+
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        compile_shared(&cc, dir, "dep", &["-Wl,-soname,libdep.so.1"]);
+        let lib = compile_shared(
+            &cc,
+            dir,
+            "main",
+            &[
+                "-Wl,-soname,libmain.so",
+                "-L.",
+                "-Wl,--no-as-needed",
+                "-ldep",
+                "-Wl,-rpath,/opt/build/very/long/lib",
+            ],
+        );
+
+        let info = inspect_file(&lib).unwrap();
+        assert_eq!(info.format, BinaryFormat::Elf);
+        assert_eq!(info.soname.as_deref(), Some("libmain.so"));
+        assert!(info.needed.contains(&"libdep.so.1".to_string()));
+        assert_eq!(info.runpath, vec!["/opt/build/very/long/lib"]);
+
+        set_runpath_file(&lib, "$ORIGIN").unwrap();
+        assert_eq!(inspect_file(&lib).unwrap().runpath, vec!["$ORIGIN"]);
+
+        let err = set_runpath_file(&lib, "$ORIGIN/../lib:$ORIGIN/../../lib").unwrap_err();
+        assert!(format!("{:#}", err).contains("longer than the existing"));
+
+        // No run path at all means there is nothing to rewrite in place
+        let dep = dir.join("libdep.so");
+        assert!(inspect_file(&dep).unwrap().runpath.is_empty());
+        assert!(set_runpath_file(&dep, "$ORIGIN").is_err());
+    }
+
+    #[test]
+    fn test_malformed_offsets_are_errors() {
+        // 64-bit little-endian header with program headers at the end of memory
+        let mut data = vec![0; 64];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        data[0x20..0x28].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+        data[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+        data[0x38..0x3a].copy_from_slice(&2u16.to_le_bytes());
+        assert!(inspect(&data).is_err());
+
+        // Section headers likewise
+        data[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+        data[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3c..0x3e].copy_from_slice(&2u16.to_le_bytes());
+        assert!(symbols(&data).is_err());
+    }
+
+    #[test]
+    fn test_exported_symbols() {
+        let Some(cc) = find_executable("cc") else {
+            return;
+        };
+        if !cfg!(target_os = "linux") {
+            return;
+        }
+
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("api.c");
+        std::fs::write(
+            &src,
+            "int api_counter;\n\
+             const char api_name[16] = \"api\";\n\
+             static int helper(void) { return 2; }\n\
+             __attribute__((visibility(\"hidden\"))) int api_internal(void) { return 3; }\n\
+             int api_call(void) { return helper() + api_internal(); }\n",
+        )
+        .unwrap();
+        let lib = tmp.path().join("libapi.so");
+        let status = Command::new(&cc)
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(&lib)
+            .arg(&src)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let symbols = exported_symbols(&lib).unwrap();
+        let find = |name: &str| symbols.iter().find(|s| s.name == name);
+        assert_eq!(find("api_call").unwrap().kind, SymbolKind::Function);
+        assert_eq!(find("api_call").unwrap().size, None);
+        assert_eq!(find("api_counter").unwrap().kind, SymbolKind::Data);
+        assert_eq!(find("api_counter").unwrap().size, Some(4));
+        assert_eq!(find("api_name").unwrap().size, Some(16));
+        assert!(find("helper").is_none());
+        assert!(find("api_internal").is_none());
+        // Imports from libc are not exports
+        assert!(symbols.iter().all(|s| !s.name.starts_with("__cxa")));
+    }
+}
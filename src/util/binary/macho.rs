@@ -0,0 +1,545 @@
+//! Mach-O load command reading and install name rewriting.
+//!
+//! Edits rebuild the whole load command area, so names may grow as long as
+//! the header padding before the first section allows. Universal binaries
+//! are edited slice by slice.
+
+use anyhow::{bail, Result};
+
+use super::{add_offset, put_u32, to_usize, BinaryFormat, BinaryInfo, Bytes, Symbol, SymbolKind};
+
+const FAT_MAGIC: u32 = 0xcafe_babe;
+const FAT_MAGIC_64: u32 = 0xcafe_babf;
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const MH_CIGAM: u32 = 0xcefa_edfe;
+const MH_CIGAM_64: u32 = 0xcffa_edfe;
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_SYMTAB: u32 = 0x2;
+const LC_LOAD_DYLIB: u32 = 0xc;
+const LC_ID_DYLIB: u32 = 0xd;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+const LC_LAZY_LOAD_DYLIB: u32 = 0x20;
+const LC_LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+const LC_RPATH: u32 = 0x8000_001c;
+const LC_REEXPORT_DYLIB: u32 = 0x8000_001f;
+const LC_LOAD_UPWARD_DYLIB: u32 = 0x8000_0023;
+
+const N_STAB: u8 = 0xe0;
+const N_PEXT: u8 = 0x10;
+const N_TYPE: u8 = 0x0e;
+const N_EXT: u8 = 0x01;
+const N_SECT: u8 = 0x0e;
+
+/// A change to a Mach-O binary's load commands.
+pub(super) enum Edit<'a> {
+    /// Replace the `LC_ID_DYLIB` name
+    InstallName(&'a str),
+    /// Replace a dependency's recorded name
+    Dependency { old: &'a str, new: &'a str },
+    /// Add an `LC_RPATH` entry unless present
+    AddRpath(&'a str),
+}
+
+pub(super) fn is_macho(magic: [u8; 4]) -> bool {
+    matches!(
+        u32::from_le_bytes(magic),
+        MH_MAGIC | MH_MAGIC_64 | MH_CIGAM | MH_CIGAM_64
+    ) || matches!(u32::from_be_bytes(magic), FAT_MAGIC | FAT_MAGIC_64)
+}
+
+fn is_dylib_load(cmd: u32) -> bool {
+    matches!(
+        cmd,
+        LC_LOAD_DYLIB
+            | LC_LOAD_WEAK_DYLIB
+            | LC_REEXPORT_DYLIB
+            | LC_LAZY_LOAD_DYLIB
+            | LC_LOAD_UPWARD_DYLIB
+    )
+}
+
+/// `(offset, size)` of each architecture in a thin or universal file.
+fn slices(data: &[u8]) -> Result<Vec<(usize, usize)>> {
+    let bytes = Bytes::new(data, true);
+    let magic = bytes.u32(0)?;
+    if magic != FAT_MAGIC && magic != FAT_MAGIC_64 {
+        return Ok(vec![(0, data.len())]);
+    }
+
+    let count = to_usize(bytes.u32(4)?.into())?;
+    let mut slices = Vec::with_capacity(count);
+    for i in 0..count {
+        let (offset, size) = if magic == FAT_MAGIC_64 {
+            let arch = bytes.entry(8, i, 32)?;
+            (bytes.u64(arch + 8)?, bytes.u64(arch + 16)?)
+        } else {
+            let arch = bytes.entry(8, i, 20)?;
+            (
+                u64::from(bytes.u32(arch + 8)?),
+                u64::from(bytes.u32(arch + 12)?),
+            )
+        };
+        let (offset, size) = (to_usize(offset)?, to_usize(size)?);
+        bytes.slice(offset, size)?;
+        slices.push((offset, size));
+    }
+    Ok(slices)
+}
+
+/// One architecture's header and load commands.
+struct Image<'a> {
+    bytes: Bytes<'a>,
+    is64: bool,
+    header_size: usize,
+    /// `(cmd, offset, size)` of each load command
+    commands: Vec<(u32, usize, usize)>,
+    /// End of the space load commands may occupy
+    limit: usize,
+}
+
+impl<'a> Image<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        let magic = Bytes::new(data, false).u32(0)?;
+        let (is64, big_endian) = match magic {
+            MH_MAGIC => (false, false),
+            MH_MAGIC_64 => (true, false),
+            MH_CIGAM => (false, true),
+            MH_CIGAM_64 => (true, true),
+            _ => bail!("unknown Mach-O magic {:#x}", magic),
+        };
+        let bytes = Bytes::new(data, big_endian);
+        let header_size = if is64 { 32 } else { 28 };
+        let ncmds = bytes.u32(16)?;
+        let sizeofcmds = to_usize(bytes.u32(20)?.into())?;
+
+        let mut commands = Vec::new();
+        let mut limit = data.len();
+        let mut offset = header_size;
+        for _ in 0..ncmds {
+            let cmd = bytes.u32(offset)?;
+            let size = to_usize(bytes.u32(offset + 4)?.into())?;
+            let end = offset.checked_add(size);
+            if size < 8 || end.is_none_or(|end| end > header_size + sizeofcmds) {
+                bail!("malformed load command at offset {:#x}", offset);
+            }
+            bytes.slice(offset, size)?;
+
+            // Load commands can't grow into the first section's contents
+            if cmd == LC_SEGMENT || cmd == LC_SEGMENT_64 {
+                limit = limit.min(segment_data_start(&bytes, offset, cmd == LC_SEGMENT_64)?);
+            }
+
+            commands.push((cmd, offset, size));
+            offset += size;
+        }
+
+        Ok(Image {
+            bytes,
+            is64,
+            header_size,
+            commands,
+            limit,
+        })
+    }
+
+    /// Read the string a load command points to at `field`.
+    fn string(&self, offset: usize, size: usize, field: usize) -> Result<String> {
+        let start = to_usize(self.bytes.u32(offset + field)?.into())?;
+        if start >= size {
+            bail!(
+                "load command string at offset {:#x} is out of range",
+                offset
+            );
+        }
+        self.bytes.cstr(offset + start)
+    }
+}
+
+/// Lowest file offset of a segment's contents, ignoring `__TEXT` which
+/// starts at 0 and contains the header itself.
+fn segment_data_start(bytes: &Bytes<'_>, offset: usize, is64: bool) -> Result<usize> {
+    let (fileoff, filesize, nsects, sections, section_size) = if is64 {
+        (
+            bytes.u64(offset + 40)?,
+            bytes.u64(offset + 48)?,
+            bytes.u32(offset + 64)?,
+            offset + 72,
+            80,
+        )
+    } else {
+        (
+            u64::from(bytes.u32(offset + 32)?),
+            u64::from(bytes.u32(offset + 36)?),
+            bytes.u32(offset + 48)?,
+            offset + 56,
+            68,
+        )
+    };
+
+    let mut start = if fileoff > 0 && filesize > 0 {
+        to_usize(fileoff)?
+    } else {
+        usize::MAX
+    };
+    for i in 0..to_usize(nsects.into())? {
+        let section = bytes.entry(sections, i, section_size)?;
+        let (size, file_offset) = if is64 {
+            (bytes.u64(section + 40)?, bytes.u32(section + 48)?)
+        } else {
+            (
+                u64::from(bytes.u32(section + 36)?),
+                bytes.u32(section + 40)?,
+            )
+        };
+        // Zero-fill sections have no file contents
+        if size > 0 && file_offset > 0 {
+            start = start.min(to_usize(file_offset.into())?);
+        }
+    }
+    Ok(start)
+}
+
+pub(super) fn inspect(data: &[u8]) -> Result<BinaryInfo> {
+    let mut info = BinaryInfo::new(BinaryFormat::MachO);
+
+    // Every slice of a universal binary is built from the same sources
+    let (offset, size) = slices(data)?[0];
+    let image = Image::parse(&data[offset..offset + size])?;
+
+    for &(cmd, offset, size) in &image.commands {
+        match cmd {
+            LC_ID_DYLIB => info.soname = Some(image.string(offset, size, 8)?),
+            LC_RPATH => info.runpath.push(image.string(offset, size, 8)?),
+            LC_CODE_SIGNATURE => info.signed = true,
+            cmd if is_dylib_load(cmd) => info.needed.push(image.string(offset, size, 8)?),
+            _ => {}
+        }
+    }
+
+    Ok(info)
+}
+
+/// Read the external symbols defined in the first slice's symbol table.
+pub(super) fn symbols(data: &[u8]) -> Result<Vec<Symbol>> {
+    let (offset, size) = slices(data)?[0];
+    let image = Image::parse(&data[offset..offset + size])?;
+    let bytes = image.bytes;
+
+    // Segment name of each section, in the order `n_sect` numbers them
+    let mut segments = Vec::new();
+    for &(cmd, offset, _) in &image.commands {
+        let nsects = match cmd {
+            LC_SEGMENT_64 => bytes.u32(offset + 64)?,
+            LC_SEGMENT => bytes.u32(offset + 48)?,
+            _ => continue,
+        };
+        let name = bytes.slice(offset + 8, 16)?;
+        let name = name.split(|&b| b == 0).next().unwrap_or_default();
+        for _ in 0..nsects {
+            segments.push(name);
+        }
+    }
+
+    let Some(&(_, symtab, _)) = image.commands.iter().find(|(cmd, ..)| *cmd == LC_SYMTAB) else {
+        bail!("file has no symbol table");
+    };
+    let symoff = to_usize(bytes.u32(symtab + 8)?.into())?;
+    let nsyms = to_usize(bytes.u32(symtab + 12)?.into())?;
+    let stroff = to_usize(bytes.u32(symtab + 16)?.into())?;
+    let entry_size = if image.is64 { 16 } else { 12 };
+
+    let mut symbols = Vec::new();
+    for i in 0..nsyms {
+        let entry = bytes.entry(symoff, i, entry_size)?;
+        let n_type = bytes.u8(entry + 4)?;
+        if n_type & N_STAB != 0
+            || n_type & N_EXT == 0
+            || n_type & N_PEXT != 0
+            || n_type & N_TYPE != N_SECT
+        {
+            continue;
+        }
+
+        let section = usize::from(bytes.u8(entry + 5)?);
+        let kind = match section.checked_sub(1).and_then(|i| segments.get(i)) {
+            Some(&segment) if segment == b"__TEXT" => SymbolKind::Function,
+            _ => SymbolKind::Data,
+        };
+        let name = bytes.cstr(add_offset(stroff, bytes.u32(entry)?.into())?)?;
+        symbols.push(Symbol {
+            name: name.strip_prefix('_').unwrap_or(&name).to_string(),
+            kind,
+            size: None,
+        });
+    }
+    Ok(symbols)
+}
+
+pub(super) fn edit(data: &mut [u8], edit: &Edit<'_>) -> Result<()> {
+    for (offset, size) in slices(data)? {
+        edit_image(&mut data[offset..offset + size], edit)?;
+    }
+    Ok(())
+}
+
+fn edit_image(data: &mut [u8], edit: &Edit<'_>) -> Result<()> {
+    let image = Image::parse(data)?;
+    let align = if image.is64 { 8 } else { 4 };
+    let big_endian = image.bytes.big_endian;
+
+    let mut commands: Vec<Vec<u8>> = image
+        .commands
+        .iter()
+        .map(|&(_, offset, size)| data[offset..offset + size].to_vec())
+        .collect();
+    let old_end = image
+        .commands
+        .last()
+        .map_or(image.header_size, |&(_, offset, size)| offset + size);
+
+    let mut changed = false;
+    for (i, &(cmd, offset, size)) in image.commands.iter().enumerate() {
+        let replacement = match edit {
+            Edit::InstallName(name) if cmd == LC_ID_DYLIB => name,
+            Edit::Dependency { old, new }
+                if is_dylib_load(cmd) && image.string(offset, size, 8)? == *old =>
+            {
+                new
+            }
+            Edit::AddRpath(path) if cmd == LC_RPATH && image.string(offset, size, 8)? == *path => {
+                return Ok(());
+            }
+            _ => continue,
+        };
+        let field = to_usize(image.bytes.u32(offset + 8)?.into())?;
+        commands[i] = with_string(&commands[i][..field], replacement, align, big_endian);
+        changed = true;
+    }
+
+    match edit {
+        Edit::InstallName(_) if !changed => bail!("file has no install name (not a dylib)"),
+        Edit::Dependency { old, .. } if !changed => bail!("file does not depend on `{}`", old),
+        Edit::AddRpath(path) => {
+            let mut header = vec![0; 12];
+            put_u32(&mut header, 0, LC_RPATH, big_endian);
+            put_u32(&mut header, 8, 12, big_endian);
+            commands.push(with_string(&header, path, align, big_endian));
+        }
+        _ => {}
+    }
+
+    let total: usize = commands.iter().map(Vec::len).sum();
+    let available = image.limit.saturating_sub(image.header_size);
+    if total > available {
+        bail!(
+            "load commands need {} bytes but only {} are free before the first section; \
+             relink with `-Wl,-headerpad_max_install_names`",
+            total,
+            available
+        );
+    }
+
+    let ncmds = commands.len() as u32;
+    let mut offset = image.header_size;
+    for command in &commands {
+        data[offset..offset + command.len()].copy_from_slice(command);
+        offset += command.len();
+    }
+    if offset < old_end {
+        data[offset..old_end].fill(0);
+    }
+    put_u32(data, 16, ncmds, big_endian);
+    put_u32(data, 20, total as u32, big_endian);
+    Ok(())
+}
+
+/// Build a load command from its fixed `header` followed by `s`, padded to
+/// `align` and with `cmdsize` updated.
+fn with_string(header: &[u8], s: &str, align: usize, big_endian: bool) -> Vec<u8> {
+    let mut command = header.to_vec();
+    command.extend_from_slice(s.as_bytes());
+    command.push(0);
+    command.resize(command.len().next_multiple_of(align), 0);
+    let size = command.len() as u32;
+    put_u32(&mut command, 4, size, big_endian);
+    command
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::super::{
+        add_rpath, change_dependency, inspect as inspect_file, set_install_name, symbols,
+    };
+    use super::*;
+    use tempfile::TempDir;
+
+    fn dylib_command(cmd: u32, name: &str) -> Vec<u8> {
+        let mut header = vec![0; 24];
+        put_u32(&mut header, 0, cmd, false);
+        put_u32(&mut header, 8, 24, false);
+        with_string(&header, name, 8, false)
+    }
+
+    /// A minimal arm64 dylib whose only section starts at 0x200.
+    pub(in super::super) fn dylib_image(id: &str, deps: &[&str]) -> Vec<u8> {
+        let mut segment = vec![0; 72 + 80];
+        put_u32(&mut segment, 0, LC_SEGMENT_64, false);
+        put_u32(&mut segment, 4, 152, false);
+        segment[8..14].copy_from_slice(b"__TEXT");
+        segment[48..56].copy_from_slice(&0x210u64.to_le_bytes());
+        put_u32(&mut segment, 64, 1, false);
+        segment[72..78].copy_from_slice(b"__text");
+        segment[72 + 40..72 + 48].copy_from_slice(&0x10u64.to_le_bytes());
+        put_u32(&mut segment, 72 + 48, 0x200, false);
+
+        let mut commands = vec![segment, dylib_command(LC_ID_DYLIB, id)];
+        for dep in deps {
+            commands.push(dylib_command(LC_LOAD_DYLIB, dep));
+        }
+
+        let mut data = vec![0; 0x210];
+        put_u32(&mut data, 0, MH_MAGIC_64, false);
+        put_u32(&mut data, 4, 0x0100_000c, false);
+        put_u32(&mut data, 12, 6, false);
+        put_u32(&mut data, 16, commands.len() as u32, false);
+        let commands = commands.concat();
+        put_u32(&mut data, 20, commands.len() as u32, false);
+        data[32..32 + commands.len()].copy_from_slice(&commands);
+        data
+    }
+
+    #[test]
+    fn test_inspect_and_rewrite_dylib() {
+        let tmp = TempDir::new().unwrap();
+        let lib = tmp.path().join("libmain.dylib");
+        std::fs::write(
+            &lib,
+            dylib_image(
+                "/build/libmain.dylib",
+                &["/usr/lib/libSystem.B.dylib", "/build/deps/libdep.dylib"],
+            ),
+        )
+        .unwrap();
+
+        let info = inspect_file(&lib).unwrap();
+        assert_eq!(info.format, BinaryFormat::MachO);
+        assert_eq!(info.soname.as_deref(), Some("/build/libmain.dylib"));
+        assert_eq!(
+            info.needed,
+            vec!["/usr/lib/libSystem.B.dylib", "/build/deps/libdep.dylib"]
+        );
+        assert!(info.runpath.is_empty());
+        assert!(!info.signed);
+
+        set_install_name(&lib, "@rpath/libmain.dylib").unwrap();
+        change_dependency(&lib, "/build/deps/libdep.dylib", "@rpath/libdep.dylib").unwrap();
+        add_rpath(&lib, "@loader_path").unwrap();
+        add_rpath(&lib, "@loader_path").unwrap();
+
+        let info = inspect_file(&lib).unwrap();
+        assert_eq!(info.soname.as_deref(), Some("@rpath/libmain.dylib"));
+        assert_eq!(
+            info.needed,
+            vec!["/usr/lib/libSystem.B.dylib", "@rpath/libdep.dylib"]
+        );
+        assert_eq!(info.runpath, vec!["@loader_path"]);
+
+        let err = change_dependency(&lib, "/build/libother.dylib", "@rpath/x").unwrap_err();
+        assert!(format!("{:#}", err).contains("does not depend on"));
+
+        // Names can grow only into the padding before the first section
+        let err = set_install_name(&lib, &"x".repeat(0x200)).unwrap_err();
+        assert!(format!("{:#}", err).contains("headerpad_max_install_names"));
+        assert_eq!(
+            inspect_file(&lib).unwrap().soname.as_deref(),
+            Some("@rpath/libmain.dylib")
+        );
+    }
+
+    #[test]
+    fn test_universal_binary() {
+        let slice = dylib_image("@rpath/libfat.dylib", &["/usr/lib/libSystem.B.dylib"]);
+        let mut data = vec![0; 0x100];
+        data[0..4].copy_from_slice(&FAT_MAGIC.to_be_bytes());
+        data[4..8].copy_from_slice(&2u32.to_be_bytes());
+        for (i, offset) in [0x100u32, 0x400].into_iter().enumerate() {
+            let arch = 8 + i * 20;
+            data[arch + 8..arch + 12].copy_from_slice(&offset.to_be_bytes());
+            data[arch + 12..arch + 16].copy_from_slice(&(slice.len() as u32).to_be_bytes());
+        }
+        data.extend_from_slice(&slice);
+        data.resize(0x400, 0);
+        data.extend_from_slice(&slice);
+
+        let tmp = TempDir::new().unwrap();
+        let lib = tmp.path().join("libfat.dylib");
+        std::fs::write(&lib, &data).unwrap();
+
+        let info = inspect_file(&lib).unwrap();
+        assert_eq!(info.soname.as_deref(), Some("@rpath/libfat.dylib"));
+
+        add_rpath(&lib, "@loader_path").unwrap();
+        let data = std::fs::read(&lib).unwrap();
+        for (offset, size) in slices(&data).unwrap() {
+            let image = &data[offset..offset + size];
+            assert_eq!(inspect(image).unwrap().runpath, vec!["@loader_path"]);
+        }
+    }
+
+    #[test]
+    fn test_external_symbols() {
+        // (name, n_type, n_sect): section 1 is __TEXT,__text; 2 doesn't exist
+        let entries: [(&str, u8, u8); 5] = [
+            ("_api_call", N_SECT | N_EXT, 1),
+            ("_api_counter", N_SECT | N_EXT, 2),
+            ("_api_internal", N_SECT | N_EXT | N_PEXT, 1),
+            ("_helper", N_SECT, 1),
+            ("_malloc", N_EXT, 0),
+        ];
+        let mut data = dylib_image("@rpath/libapi.dylib", &[]);
+
+        let mut strings = vec![0u8];
+        let mut table = Vec::new();
+        for (name, n_type, n_sect) in entries {
+            let mut nlist = vec![0; 16];
+            put_u32(&mut nlist, 0, strings.len() as u32, false);
+            nlist[4] = n_type;
+            nlist[5] = n_sect;
+            table.extend(nlist);
+            strings.extend(name.as_bytes());
+            strings.push(0);
+        }
+
+        // Append LC_SYMTAB after the existing load commands
+        let symoff = data.len();
+        let mut command = vec![0; 24];
+        put_u32(&mut command, 0, LC_SYMTAB, false);
+        put_u32(&mut command, 4, 24, false);
+        put_u32(&mut command, 8, symoff as u32, false);
+        put_u32(&mut command, 12, entries.len() as u32, false);
+        put_u32(&mut command, 16, (symoff + table.len()) as u32, false);
+        put_u32(&mut command, 20, strings.len() as u32, false);
+        let ncmds = Bytes::new(&data, false).u32(16).unwrap();
+        let sizeofcmds = Bytes::new(&data, false).u32(20).unwrap();
+        let end = 32 + sizeofcmds as usize;
+        data[end..end + 24].copy_from_slice(&command);
+        put_u32(&mut data, 16, ncmds + 1, false);
+        put_u32(&mut data, 20, sizeofcmds + 24, false);
+        data.extend(table);
+        data.extend(strings);
+
+        let symbols = symbols(&data).unwrap();
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("api_call", SymbolKind::Function),
+                ("api_counter", SymbolKind::Data)
+            ]
+        );
+        assert!(symbols.iter().all(|s| s.size.is_none()));
+    }
+}
@@ -0,0 +1,355 @@
+//! In-process inspection and patching of shared libraries.
+//!
+//! Reads the dependency information the dynamic loader uses straight from
+//! ELF, Mach-O and PE files: `DT_NEEDED`/`DT_SONAME`/`DT_RUNPATH`,
+//! `LC_LOAD_DYLIB`/`LC_ID_DYLIB`/`LC_RPATH` and the import tables, along
+//! with the exported symbols. No loader is executed and no external tools
+//! are needed, so this works for cross-compiled binaries and on minimal CI
+//! images.
+
+mod elf;
+mod macho;
+mod pe;
+mod search;
+
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub use search::{LibrarySearch, Resolution, RuntimeDeps};
+
+/// Object file format of a binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryFormat {
+    /// ELF (Linux, BSD)
+    Elf,
+    /// Mach-O, thin or universal (macOS)
+    MachO,
+    /// PE/COFF (Windows)
+    Pe,
+}
+
+impl BinaryFormat {
+    /// Detect the format from a file's leading bytes.
+    pub fn detect(data: &[u8]) -> Option<BinaryFormat> {
+        match data {
+            [0x7f, b'E', b'L', b'F', ..] => Some(BinaryFormat::Elf),
+            [b'M', b'Z', ..] => Some(BinaryFormat::Pe),
+            [a, b, c, d, ..] if macho::is_macho([*a, *b, *c, *d]) => Some(BinaryFormat::MachO),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BinaryFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryFormat::Elf => write!(f, "ELF"),
+            BinaryFormat::MachO => write!(f, "Mach-O"),
+            BinaryFormat::Pe => write!(f, "PE"),
+        }
+    }
+}
+
+/// Runtime linking information recorded in a binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryInfo {
+    /// Object file format
+    pub format: BinaryFormat,
+
+    /// Name dependents record for this library (`DT_SONAME`, the
+    /// `LC_ID_DYLIB` install name, or the PE export name)
+    pub soname: Option<String>,
+
+    /// Libraries loaded at runtime, exactly as recorded
+    pub needed: Vec<String>,
+
+    /// Runtime search paths (`DT_RUNPATH` or `DT_RPATH`, `LC_RPATH`)
+    pub runpath: Vec<String>,
+
+    /// Carries an embedded code signature, which patching invalidates
+    pub signed: bool,
+}
+
+impl BinaryInfo {
+    fn new(format: BinaryFormat) -> Self {
+        BinaryInfo {
+            format,
+            soname: None,
+            needed: Vec::new(),
+            runpath: Vec::new(),
+            signed: false,
+        }
+    }
+}
+
+/// What an exported symbol refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    /// Code
+    Function,
+    /// A variable or other data object
+    Data,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolKind::Function => write!(f, "function"),
+            SymbolKind::Data => write!(f, "data"),
+        }
+    }
+}
+
+/// A symbol a shared library exports to its dependents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    /// Name as C code spells it, without the Mach-O leading underscore
+    pub name: String,
+
+    /// Function or data
+    pub kind: SymbolKind,
+
+    /// Size in bytes of a data symbol, where the format records it (ELF only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// Read the runtime linking information of a binary on disk.
+pub fn inspect(path: &Path) -> Result<BinaryInfo> {
+    let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse(&data).with_context(|| format!("failed to inspect {}", path.display()))
+}
+
+/// Read the runtime linking information of a binary image.
+pub fn parse(data: &[u8]) -> Result<BinaryInfo> {
+    match BinaryFormat::detect(data) {
+        Some(BinaryFormat::Elf) => elf::inspect(data),
+        Some(BinaryFormat::MachO) => macho::inspect(data),
+        Some(BinaryFormat::Pe) => pe::inspect(data),
+        None => bail!("not an ELF, Mach-O or PE file"),
+    }
+}
+
+/// Read the symbols a shared library on disk exports, sorted by name.
+pub fn exported_symbols(path: &Path) -> Result<Vec<Symbol>> {
+    let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    symbols(&data).with_context(|| format!("failed to read symbols of {}", path.display()))
+}
+
+/// Read the symbols a shared library image exports, sorted by name.
+///
+/// These are the dynamic symbols dependents can bind to: hidden and
+/// local symbols are left out, as are imports.
+pub fn symbols(data: &[u8]) -> Result<Vec<Symbol>> {
+    let mut symbols = match BinaryFormat::detect(data) {
+        Some(BinaryFormat::Elf) => elf::symbols(data)?,
+        Some(BinaryFormat::MachO) => macho::symbols(data)?,
+        Some(BinaryFormat::Pe) => pe::symbols(data)?,
+        None => bail!("not an ELF, Mach-O or PE file"),
+    };
+    // Versioned ELF symbols can appear once per version
+    symbols.sort_by(|a, b| a.name.cmp(&b.name));
+    symbols.dedup_by(|a, b| a.name == b.name);
+    Ok(symbols)
+}
+
+/// Set an ELF library's `DT_RUNPATH` (or existing `DT_RPATH`).
+///
+/// The string table is rewritten in place, so the new value must fit in
+/// the space of the existing entry. Libraries linked without a run path
+/// have no room for one.
+pub fn set_runpath(path: &Path, runpath: &str) -> Result<()> {
+    patch(path, BinaryFormat::Elf, |data| {
+        elf::set_runpath(data, runpath)
+    })
+}
+
+/// Set a Mach-O library's install name (`LC_ID_DYLIB`).
+pub fn set_install_name(path: &Path, name: &str) -> Result<()> {
+    patch(path, BinaryFormat::MachO, |data| {
+        macho::edit(data, &macho::Edit::InstallName(name))
+    })
+}
+
+/// Change a Mach-O binary's recorded dependency from `old` to `new`.
+pub fn change_dependency(path: &Path, old: &str, new: &str) -> Result<()> {
+    patch(path, BinaryFormat::MachO, |data| {
+        macho::edit(data, &macho::Edit::Dependency { old, new })
+    })
+}
+
+/// Add an `LC_RPATH` entry to a Mach-O binary, if it isn't there already.
+pub fn add_rpath(path: &Path, rpath: &str) -> Result<()> {
+    patch(path, BinaryFormat::MachO, |data| {
+        macho::edit(data, &macho::Edit::AddRpath(rpath))
+    })
+}
+
+/// Apply `f` to a binary of the expected format and write it back.
+fn patch(path: &Path, format: BinaryFormat, f: impl FnOnce(&mut [u8]) -> Result<()>) -> Result<()> {
+    let mut data =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    match BinaryFormat::detect(&data) {
+        Some(found) if found == format => {}
+        Some(found) => bail!("{} is a {} file, not {}", path.display(), found, format),
+        None => bail!("{} is not an ELF, Mach-O or PE file", path.display()),
+    }
+
+    f(&mut data).with_context(|| format!("failed to patch {}", path.display()))?;
+
+    // Bundled copies keep the source's permissions, which may be read-only
+    let mut perms = std::fs::metadata(path)?.permissions();
+    if perms.readonly() {
+        #[allow(clippy::permissions_set_readonly_false)]
+        perms.set_readonly(false);
+        std::fs::set_permissions(path, perms)?;
+    }
+    std::fs::write(path, data).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Bounds-checked, endian-aware reads from a binary image.
+#[derive(Clone, Copy)]
+struct Bytes<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Bytes<'a> {
+    fn new(data: &'a [u8], big_endian: bool) -> Self {
+        Bytes { data, big_endian }
+    }
+
+    fn slice(&self, offset: usize, len: usize) -> Result<&'a [u8]> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(offset..end))
+            .with_context(|| format!("unexpected end of file at offset {:#x}", offset))
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8> {
+        Ok(self.slice(offset, 1)?[0])
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16> {
+        let b: [u8; 2] = self.slice(offset, 2)?.try_into().unwrap();
+        Ok(if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32> {
+        let b: [u8; 4] = self.slice(offset, 4)?.try_into().unwrap();
+        Ok(if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64> {
+        let b: [u8; 8] = self.slice(offset, 8)?.try_into().unwrap();
+        Ok(if self.big_endian {
+            u64::from_be_bytes(b)
+        } else {
+            u64::from_le_bytes(b)
+        })
+    }
+
+    /// Offset of entry `index` in a table of `size`-byte entries at `base`.
+    ///
+    /// Fails instead of overflowing, or when the entry runs past the end.
+    fn entry(&self, base: usize, index: usize, size: usize) -> Result<usize> {
+        let offset = index
+            .checked_mul(size)
+            .and_then(|rel| base.checked_add(rel))
+            .with_context(|| format!("table entry {} at {:#x} is out of range", index, base))?;
+        self.slice(offset, size)?;
+        Ok(offset)
+    }
+
+    /// Read a 32- or 64-bit word.
+    fn word(&self, offset: usize, is64: bool) -> Result<u64> {
+        if is64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+
+    /// Read a NUL-terminated string.
+    fn cstr(&self, offset: usize) -> Result<String> {
+        let rest = self
+            .data
+            .get(offset..)
+            .with_context(|| format!("string offset {:#x} is out of range", offset))?;
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .with_context(|| format!("unterminated string at offset {:#x}", offset))?;
+        Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+    }
+}
+
+/// Write a 32-bit value with the given byte order.
+fn put_u32(data: &mut [u8], offset: usize, value: u32, big_endian: bool) {
+    let bytes = if big_endian {
+        value.to_be_bytes()
+    } else {
+        value.to_le_bytes()
+    };
+    data[offset..offset + 4].copy_from_slice(&bytes);
+}
+
+/// Convert a file offset or size read from a header to `usize`.
+fn to_usize(value: u64) -> Result<usize> {
+    usize::try_from(value).with_context(|| format!("offset {:#x} is out of range", value))
+}
+
+/// Add an offset read from a header to a base file offset.
+fn add_offset(base: usize, offset: u64) -> Result<usize> {
+    base.checked_add(to_usize(offset)?)
+        .with_context(|| format!("offset {:#x} + {:#x} is out of range", base, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            BinaryFormat::detect(b"\x7fELF\x02\x01\x01"),
+            Some(BinaryFormat::Elf)
+        );
+        assert_eq!(BinaryFormat::detect(b"MZ\x90\x00"), Some(BinaryFormat::Pe));
+        assert_eq!(
+            BinaryFormat::detect(&[0xcf, 0xfa, 0xed, 0xfe, 0x07]),
+            Some(BinaryFormat::MachO)
+        );
+        assert_eq!(BinaryFormat::detect(b"!<arch>\n"), None);
+        assert!(parse(b"hello").is_err());
+    }
+
+    #[test]
+    fn test_bytes_bounds() {
+        let bytes = Bytes::new(&[1, 0, 0, 0, b'a', b'b', 0], false);
+        assert_eq!(bytes.u32(0).unwrap(), 1);
+        assert_eq!(bytes.cstr(4).unwrap(), "ab");
+        assert!(bytes.u32(4).is_err());
+        assert!(bytes.u64(usize::MAX).is_err());
+        assert!(bytes.cstr(6).unwrap().is_empty());
+        assert!(Bytes::new(b"ab", false).cstr(0).is_err());
+
+        assert_eq!(bytes.entry(0, 1, 2).unwrap(), 2);
+        assert!(bytes.entry(4, 1, 4).is_err());
+        assert!(bytes.entry(usize::MAX, 2, 8).is_err());
+        assert!(bytes.entry(0, usize::MAX, 2).is_err());
+        assert!(add_offset(usize::MAX, 1).is_err());
+    }
+}
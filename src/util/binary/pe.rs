@@ -0,0 +1,301 @@
+//! PE import and export table reading.
+//!
+//! Windows has no run path; DLLs are found next to the executable or on
+//! `PATH`, so PE files are only ever inspected, never patched.
+
+use anyhow::{bail, Result};
+
+use super::{to_usize, BinaryFormat, BinaryInfo, Bytes, Symbol, SymbolKind};
+
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+
+const EXPORT_DIRECTORY: usize = 0;
+const IMPORT_DIRECTORY: usize = 1;
+const DELAY_IMPORT_DIRECTORY: usize = 13;
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+
+/// A section's virtual range and file position.
+struct Section {
+    address: u64,
+    size: u64,
+    file_offset: u64,
+    characteristics: u32,
+}
+
+struct Image<'a> {
+    bytes: Bytes<'a>,
+    /// RVA of each data directory, when present
+    directories: Vec<u32>,
+    sections: Vec<Section>,
+}
+
+impl Image<'_> {
+    fn section(&self, rva: u32) -> Option<&Section> {
+        let rva = u64::from(rva);
+        self.sections
+            .iter()
+            .find(|s| (s.address..s.address + s.size).contains(&rva))
+    }
+
+    /// Map a relative virtual address to a file offset.
+    fn offset(&self, rva: u32) -> Result<usize> {
+        match self.section(rva) {
+            Some(section) => to_usize(u64::from(rva) - section.address + section.file_offset),
+            None => bail!("address {:#x} is not in any section", rva),
+        }
+    }
+
+    fn directory(&self, index: usize) -> Option<u32> {
+        self.directories.get(index).copied().filter(|&rva| rva != 0)
+    }
+
+    fn string(&self, rva: u32) -> Result<String> {
+        self.bytes.cstr(self.offset(rva)?)
+    }
+
+    /// Names of the DLLs in an import table whose descriptors are
+    /// `stride` bytes with the name RVA at `name_field`.
+    fn imports(&self, rva: u32, stride: usize, name_field: usize) -> Result<Vec<String>> {
+        let start = self.offset(rva)?;
+        let mut names = Vec::new();
+        for i in 0.. {
+            let descriptor = start + i * stride;
+            if self
+                .bytes
+                .slice(descriptor, stride)?
+                .iter()
+                .all(|&b| b == 0)
+            {
+                break;
+            }
+            let name = self.bytes.u32(descriptor + name_field)?;
+            if name != 0 {
+                names.push(self.string(name)?);
+            }
+        }
+        Ok(names)
+    }
+}
+
+fn parse(data: &[u8]) -> Result<Image<'_>> {
+    let bytes = Bytes::new(data, false);
+    let pe = to_usize(bytes.u32(0x3c)?.into())?;
+    if bytes.slice(pe, 4)? != b"PE\0\0" {
+        bail!("missing PE signature");
+    }
+
+    let coff = pe + 4;
+    let section_count = usize::from(bytes.u16(coff + 2)?);
+    let optional_size = usize::from(bytes.u16(coff + 16)?);
+    let optional = coff + 20;
+
+    let (count_field, first_directory) = match bytes.u16(optional)? {
+        PE32_MAGIC => (optional + 92, optional + 96),
+        PE32_PLUS_MAGIC => (optional + 108, optional + 112),
+        magic => bail!("unknown PE optional header magic {:#x}", magic),
+    };
+    let directory_count = to_usize(bytes.u32(count_field)?.into())?;
+    let directories = (0..directory_count.min(16))
+        .map(|i| bytes.u32(first_directory + i * 8))
+        .collect::<Result<_>>()?;
+
+    let table = optional + optional_size;
+    let sections = (0..section_count)
+        .map(|i| {
+            let header = table + i * 40;
+            let virtual_size = bytes.u32(header + 8)?;
+            let raw_size = bytes.u32(header + 16)?;
+            Ok(Section {
+                address: bytes.u32(header + 12)?.into(),
+                size: virtual_size.max(raw_size).into(),
+                file_offset: bytes.u32(header + 20)?.into(),
+                characteristics: bytes.u32(header + 36)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Image {
+        bytes,
+        directories,
+        sections,
+    })
+}
+
+pub(super) fn inspect(data: &[u8]) -> Result<BinaryInfo> {
+    let image = parse(data)?;
+    let mut info = BinaryInfo::new(BinaryFormat::Pe);
+
+    if let Some(rva) = image.directory(EXPORT_DIRECTORY) {
+        let name = image.bytes.u32(image.offset(rva)? + 12)?;
+        if name != 0 {
+            info.soname = Some(image.string(name)?);
+        }
+    }
+    if let Some(rva) = image.directory(IMPORT_DIRECTORY) {
+        info.needed.extend(image.imports(rva, 20, 12)?);
+    }
+    if let Some(rva) = image.directory(DELAY_IMPORT_DIRECTORY) {
+        info.needed.extend(image.imports(rva, 32, 4)?);
+    }
+
+    Ok(info)
+}
+
+/// Read the names in the export directory.
+///
+/// Exports by ordinal only have no name and are left out. Forwarded
+/// exports point into the export directory itself and count as data.
+pub(super) fn symbols(data: &[u8]) -> Result<Vec<Symbol>> {
+    let image = parse(data)?;
+    let Some(rva) = image.directory(EXPORT_DIRECTORY) else {
+        return Ok(Vec::new());
+    };
+    let directory = image.offset(rva)?;
+    let count = to_usize(image.bytes.u32(directory + 24)?.into())?;
+    let functions = image.offset(image.bytes.u32(directory + 28)?)?;
+    let names = image.offset(image.bytes.u32(directory + 32)?)?;
+    let ordinals = image.offset(image.bytes.u32(directory + 36)?)?;
+
+    let mut symbols = Vec::with_capacity(count);
+    for i in 0..count {
+        let name = image.string(image.bytes.u32(names + i * 4)?)?;
+        let ordinal = usize::from(image.bytes.u16(ordinals + i * 2)?);
+        let address = image.bytes.u32(functions + ordinal * 4)?;
+        let kind = match image.section(address) {
+            Some(section) if section.characteristics & IMAGE_SCN_MEM_EXECUTE != 0 => {
+                SymbolKind::Function
+            }
+            _ => SymbolKind::Data,
+        };
+        symbols.push(Symbol {
+            name,
+            kind,
+            size: None,
+        });
+    }
+    Ok(symbols)
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    fn put(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// A minimal PE32+ DLL with one section mapped at RVA 0x1000.
+    pub(in super::super) fn dll_image(name: &str, imports: &[&str], delay: &[&str]) -> Vec<u8> {
+        let mut data = vec![0; 0x400];
+        data[0..2].copy_from_slice(b"MZ");
+        put(&mut data, 0x3c, 0x40);
+        data[0x40..0x44].copy_from_slice(b"PE\0\0");
+        data[0x44..0x46].copy_from_slice(&0x8664u16.to_le_bytes());
+        data[0x46..0x48].copy_from_slice(&1u16.to_le_bytes());
+        data[0x54..0x56].copy_from_slice(&0xf0u16.to_le_bytes());
+
+        let optional = 0x58;
+        data[optional..optional + 2].copy_from_slice(&PE32_PLUS_MAGIC.to_le_bytes());
+        put(&mut data, optional + 108, 16);
+        let directory = |i: usize| optional + 112 + i * 8;
+
+        let section = optional + 0xf0;
+        put(&mut data, section + 8, 0x200);
+        put(&mut data, section + 12, 0x1000);
+        put(&mut data, section + 16, 0x200);
+        put(&mut data, section + 20, 0x200);
+
+        // Strings from RVA 0x1100, 0x20 bytes apart
+        let mut strings = 0x1100;
+        let mut string = |data: &mut Vec<u8>, s: &str| {
+            let offset = strings - 0x1000 + 0x200;
+            data[offset..offset + s.len()].copy_from_slice(s.as_bytes());
+            strings += 0x20;
+            (strings - 0x20) as u32
+        };
+
+        put(&mut data, directory(EXPORT_DIRECTORY), 0x1000);
+        let export_name = string(&mut data, name);
+        put(&mut data, 0x200 + 12, export_name);
+
+        put(&mut data, directory(IMPORT_DIRECTORY), 0x1040);
+        for (i, import) in imports.iter().enumerate() {
+            let rva = string(&mut data, import);
+            put(&mut data, 0x240 + i * 20 + 12, rva);
+        }
+
+        if !delay.is_empty() {
+            put(&mut data, directory(DELAY_IMPORT_DIRECTORY), 0x1080);
+            for (i, import) in delay.iter().enumerate() {
+                let rva = string(&mut data, import);
+                put(&mut data, 0x280 + i * 32 + 4, rva);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_inspect_dll() {
+        let data = dll_image(
+            "mylib.dll",
+            &["KERNEL32.dll", "libdep.dll"],
+            &["libdelay.dll"],
+        );
+        let info = inspect(&data).unwrap();
+        assert_eq!(info.format, BinaryFormat::Pe);
+        assert_eq!(info.soname.as_deref(), Some("mylib.dll"));
+        assert_eq!(
+            info.needed,
+            vec!["KERNEL32.dll", "libdep.dll", "libdelay.dll"]
+        );
+        assert!(info.runpath.is_empty());
+    }
+
+    #[test]
+    fn test_reject_truncated_pe() {
+        let data = dll_image("mylib.dll", &["KERNEL32.dll"], &[]);
+        assert!(inspect(&data[..0x100]).is_err());
+        assert!(inspect(b"MZ").is_err());
+    }
+
+    #[test]
+    fn test_export_names() {
+        let mut data = dll_image("api.dll", &["KERNEL32.dll"], &[]);
+        // Two named exports after the strings, at RVA 0x1180
+        put(&mut data, 0x200 + 24, 2);
+        put(&mut data, 0x200 + 28, 0x1180);
+        put(&mut data, 0x200 + 32, 0x1188);
+        put(&mut data, 0x200 + 36, 0x1190);
+        put(&mut data, 0x380, 0x1010);
+        put(&mut data, 0x384, 0x1020);
+        put(&mut data, 0x388, 0x11a0);
+        put(&mut data, 0x38c, 0x11b0);
+        data[0x390..0x394].copy_from_slice(&[1, 0, 0, 0]);
+        data[0x3a0..0x3a8].copy_from_slice(b"api_call");
+        data[0x3b0..0x3b7].copy_from_slice(b"api_ptr");
+
+        let kinds = |data: &[u8]| {
+            symbols(data)
+                .unwrap()
+                .into_iter()
+                .map(|s| (s.name, s.kind))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            kinds(&data),
+            vec![
+                ("api_call".to_string(), SymbolKind::Data),
+                ("api_ptr".to_string(), SymbolKind::Data)
+            ]
+        );
+
+        // Marking the section executable makes both exports code
+        let section = 0x58 + 0xf0;
+        put(&mut data, section + 36, IMAGE_SCN_MEM_EXECUTE);
+        assert!(kinds(&data)
+            .iter()
+            .all(|(_, kind)| *kind == SymbolKind::Function));
+    }
+}
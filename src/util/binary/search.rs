@@ -0,0 +1,383 @@
+//! Resolving recorded dependencies to files, the way each platform's
+//! loader would on the target system.
+
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{inspect, BinaryFormat, BinaryInfo};
+
+/// Where a recorded dependency was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// Built or vendored with the project; ships alongside the library
+    Found(PathBuf),
+    /// Provided by the target system
+    System,
+    /// Not found in any search location
+    Missing,
+}
+
+/// A library's runtime dependencies that don't come with the system.
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeDeps {
+    /// Dependencies to ship with the library, transitively
+    pub libraries: Vec<PathBuf>,
+    /// Recorded names that couldn't be found
+    pub missing: Vec<String>,
+}
+
+/// Search locations for resolving dependencies.
+#[derive(Debug, Clone, Default)]
+pub struct LibrarySearch {
+    /// Directories the build placed libraries in
+    lib_dirs: Vec<PathBuf>,
+    /// Root of the target system, `/` when not cross-compiling
+    sysroot: Option<PathBuf>,
+}
+
+impl LibrarySearch {
+    /// Create an empty search that only knows about system libraries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory containing the build's libraries.
+    pub fn with_lib_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        if !self.lib_dirs.contains(&dir) {
+            self.lib_dirs.push(dir);
+        }
+        self
+    }
+
+    /// Set the target system's root directory.
+    pub fn with_sysroot(mut self, sysroot: impl Into<PathBuf>) -> Self {
+        self.sysroot = Some(sysroot.into());
+        self
+    }
+
+    fn sysroot(&self) -> &Path {
+        self.sysroot.as_deref().unwrap_or(Path::new("/"))
+    }
+
+    /// Default ELF loader directories under the sysroot, including
+    /// Debian-style multiarch ones.
+    fn elf_system_dirs(&self) -> Vec<PathBuf> {
+        let root = self.sysroot();
+        let mut dirs = Vec::new();
+        for base in ["lib", "lib64", "usr/lib", "usr/lib64"] {
+            let dir = root.join(base);
+            if let Ok(entries) = std::fs::read_dir(&dir) {
+                let mut multiarch: Vec<PathBuf> = entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| {
+                        p.is_dir()
+                            && p.file_name()
+                                .is_some_and(|n| n.to_string_lossy().contains("-linux-"))
+                    })
+                    .collect();
+                multiarch.sort();
+                dirs.extend(multiarch);
+            }
+            dirs.push(dir);
+        }
+        dirs
+    }
+
+    /// Resolve each dependency recorded in `info`, read from `binary`.
+    pub fn resolve(&self, binary: &Path, info: &BinaryInfo) -> Vec<(String, Resolution)> {
+        let origin = binary.parent().unwrap_or(Path::new("."));
+        info.needed
+            .iter()
+            .map(|name| {
+                let resolution = match info.format {
+                    BinaryFormat::Elf => self.resolve_elf(name, origin, &info.runpath),
+                    BinaryFormat::MachO => self.resolve_macho(name, origin, &info.runpath),
+                    BinaryFormat::Pe => self.resolve_pe(name, origin),
+                };
+                (name.clone(), resolution)
+            })
+            .collect()
+    }
+
+    fn resolve_elf(&self, name: &str, origin: &Path, runpath: &[String]) -> Resolution {
+        let system_dirs = self.elf_system_dirs();
+        let classify = |path: PathBuf| {
+            if path
+                .parent()
+                .is_some_and(|dir| system_dirs.iter().any(|s| s == dir))
+            {
+                Resolution::System
+            } else {
+                Resolution::Found(path)
+            }
+        };
+
+        if name.contains('/') {
+            let path = PathBuf::from(name);
+            return if path.is_file() {
+                classify(path)
+            } else {
+                Resolution::Missing
+            };
+        }
+
+        let origin = origin.to_string_lossy();
+        let runpath = runpath.iter().map(|dir| {
+            PathBuf::from(
+                dir.replace("${ORIGIN}", &origin)
+                    .replace("$ORIGIN", &origin),
+            )
+        });
+
+        runpath
+            .chain(self.lib_dirs.iter().cloned())
+            .chain(system_dirs.iter().cloned())
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+            .map_or(Resolution::Missing, classify)
+    }
+
+    fn resolve_macho(&self, name: &str, origin: &Path, rpaths: &[String]) -> Resolution {
+        // System libraries live in the dyld shared cache, not on disk
+        if name.starts_with("/usr/lib/") || name.starts_with("/System/") {
+            return Resolution::System;
+        }
+
+        let expand = |path: &str| {
+            for prefix in ["@loader_path", "@executable_path"] {
+                if let Some(rest) = path.strip_prefix(prefix) {
+                    return origin.join(rest.trim_start_matches('/'));
+                }
+            }
+            PathBuf::from(path)
+        };
+
+        let mut candidates = Vec::new();
+        if let Some(rest) = name.strip_prefix("@rpath/") {
+            candidates.extend(rpaths.iter().map(|rpath| expand(rpath).join(rest)));
+        } else if name.starts_with('@') {
+            candidates.push(expand(name));
+        } else {
+            candidates.push(PathBuf::from(name));
+            if let Some(sysroot) = &self.sysroot {
+                candidates.push(sysroot.join(name.trim_start_matches('/')));
+            }
+        }
+
+        // Fall back to the build's own libraries with the same file name
+        if let Some(file_name) = Path::new(name).file_name() {
+            candidates.extend(self.lib_dirs.iter().map(|dir| dir.join(file_name)));
+        }
+
+        candidates
+            .into_iter()
+            .find(|path| path.is_file())
+            .map_or(Resolution::Missing, Resolution::Found)
+    }
+
+    fn resolve_pe(&self, name: &str, origin: &Path) -> Resolution {
+        // DLL names are case-insensitive; MinGW runtime DLLs live in the
+        // sysroot's bin directory and must ship with the library
+        let dirs = std::iter::once(origin.to_path_buf())
+            .chain(self.lib_dirs.iter().cloned())
+            .chain(self.sysroot.iter().map(|root| root.join("bin")));
+
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if entry
+                    .file_name()
+                    .to_string_lossy()
+                    .eq_ignore_ascii_case(name)
+                {
+                    return Resolution::Found(entry.path());
+                }
+            }
+        }
+
+        // Anything else is assumed to come with Windows
+        Resolution::System
+    }
+
+    /// Collect the non-system libraries `lib` needs at runtime, following
+    /// each one's own dependencies.
+    pub fn runtime_deps(&self, lib: &Path) -> Result<RuntimeDeps> {
+        let mut deps = RuntimeDeps::default();
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        seen.insert(lib.canonicalize().unwrap_or_else(|_| lib.to_path_buf()));
+        let mut queue = VecDeque::from([lib.to_path_buf()]);
+
+        while let Some(binary) = queue.pop_front() {
+            let info = inspect(&binary)?;
+            for (name, resolution) in self.resolve(&binary, &info) {
+                match resolution {
+                    Resolution::Found(path) => {
+                        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+                        if seen.insert(key) {
+                            deps.libraries.push(path.clone());
+                            queue.push_back(path);
+                        }
+                    }
+                    Resolution::System => {}
+                    Resolution::Missing => {
+                        if !deps.missing.contains(&name) {
+                            deps.missing.push(name);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(deps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::macho::tests::dylib_image;
+    use super::super::pe::tests::dll_image;
+    use super::*;
+    use tempfile::TempDir;
+
+    fn info(format: BinaryFormat, needed: &[&str], runpath: &[&str]) -> BinaryInfo {
+        BinaryInfo {
+            needed: needed.iter().map(|s| s.to_string()).collect(),
+            runpath: runpath.iter().map(|s| s.to_string()).collect(),
+            ..BinaryInfo::new(format)
+        }
+    }
+
+    #[test]
+    fn test_resolve_elf_against_sysroot() {
+        let tmp = TempDir::new().unwrap();
+        let sysroot = tmp.path().join("sysroot");
+        let multiarch = sysroot.join("usr/lib/aarch64-linux-gnu");
+        std::fs::create_dir_all(&multiarch).unwrap();
+        std::fs::write(multiarch.join("libc.so.6"), "").unwrap();
+
+        let build = tmp.path().join("build");
+        let deps = tmp.path().join("deps");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::create_dir_all(&deps).unwrap();
+        std::fs::write(build.join("libsibling.so"), "").unwrap();
+        std::fs::write(deps.join("libdep.so.1"), "").unwrap();
+
+        let search = LibrarySearch::new()
+            .with_lib_dir(&deps)
+            .with_sysroot(&sysroot);
+        let resolved = search.resolve(
+            &build.join("libmain.so"),
+            &info(
+                BinaryFormat::Elf,
+                &["libsibling.so", "libdep.so.1", "libc.so.6", "libgone.so"],
+                &["$ORIGIN"],
+            ),
+        );
+
+        assert_eq!(
+            resolved,
+            vec![
+                (
+                    "libsibling.so".to_string(),
+                    Resolution::Found(build.join("libsibling.so"))
+                ),
+                (
+                    "libdep.so.1".to_string(),
+                    Resolution::Found(deps.join("libdep.so.1"))
+                ),
+                ("libc.so.6".to_string(), Resolution::System),
+                ("libgone.so".to_string(), Resolution::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_macho_and_pe() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("libdep.dylib"), "").unwrap();
+        std::fs::write(dir.join("LIBDEP.DLL"), "").unwrap();
+
+        let search = LibrarySearch::new().with_lib_dir(dir);
+        let binary = tmp.path().join("elsewhere/libmain");
+
+        let resolved = search.resolve(
+            &binary,
+            &info(
+                BinaryFormat::MachO,
+                &[
+                    "/usr/lib/libSystem.B.dylib",
+                    "@rpath/libdep.dylib",
+                    "/old/build/libdep.dylib",
+                    "@loader_path/libnone.dylib",
+                ],
+                &["@loader_path"],
+            ),
+        );
+        let resolutions: Vec<_> = resolved.into_iter().map(|(_, r)| r).collect();
+        assert_eq!(
+            resolutions,
+            vec![
+                Resolution::System,
+                Resolution::Found(dir.join("libdep.dylib")),
+                Resolution::Found(dir.join("libdep.dylib")),
+                Resolution::Missing,
+            ]
+        );
+
+        let resolved = search.resolve(
+            &binary,
+            &info(BinaryFormat::Pe, &["libdep.dll", "KERNEL32.dll"], &[]),
+        );
+        assert_eq!(resolved[0].1, Resolution::Found(dir.join("LIBDEP.DLL")));
+        assert_eq!(resolved[1].1, Resolution::System);
+    }
+
+    #[test]
+    fn test_runtime_deps_are_transitive() {
+        let tmp = TempDir::new().unwrap();
+        let build = tmp.path().join("build");
+        let deps = tmp.path().join("deps");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::create_dir_all(&deps).unwrap();
+
+        let lib = build.join("mylib.dll");
+        std::fs::write(
+            &lib,
+            dll_image("mylib.dll", &["KERNEL32.dll", "a.dll"], &[]),
+        )
+        .unwrap();
+        std::fs::write(
+            deps.join("a.dll"),
+            dll_image("a.dll", &["b.dll", "mylib.dll"], &[]),
+        )
+        .unwrap();
+        std::fs::write(deps.join("b.dll"), dll_image("b.dll", &["a.dll"], &[])).unwrap();
+
+        let found = LibrarySearch::new()
+            .with_lib_dir(&deps)
+            .runtime_deps(&lib)
+            .unwrap();
+        assert_eq!(
+            found.libraries,
+            vec![deps.join("a.dll"), deps.join("b.dll")]
+        );
+        assert!(found.missing.is_empty());
+
+        let lib = build.join("libmain.dylib");
+        std::fs::write(
+            &lib,
+            dylib_image("@rpath/libmain.dylib", &["@rpath/libx.dylib"]),
+        )
+        .unwrap();
+        let found = LibrarySearch::new().runtime_deps(&lib).unwrap();
+        assert!(found.libraries.is_empty());
+        assert_eq!(found.missing, vec!["@rpath/libx.dylib"]);
+    }
+}
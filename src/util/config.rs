@@ -17,6 +17,7 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::builder::shim::{BackendId, LinkagePreference};
+use crate::util::context::{RegistryEntry, RegistryList};
 
 /// Harbour configuration.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -33,6 +34,10 @@ pub struct Config {
 
     /// Vcpkg integration settings
     pub vcpkg: VcpkgConfig,
+
+    /// Additional package registries (`[[registry]]` tables)
+    #[serde(rename = "registry", skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<RegistryEntry>,
 }
 
 /// Toolchain configuration for compiler overrides.
@@ -252,6 +257,10 @@ pub struct NetConfig {
     /// Offline mode (don't fetch from network)
     #[serde(default)]
     pub offline: bool,
+
+    /// Use the system `git` binary instead of libgit2 for fetching
+    #[serde(default, alias = "git-fetch-with-cli")]
+    pub git_fetch_with_cli: bool,
 }
 
 /// Vcpkg integration configuration.
@@ -455,6 +464,9 @@ impl Config {
         if other.net.offline {
             self.net.offline = true;
         }
+        if other.net.git_fetch_with_cli {
+            self.net.git_fetch_with_cli = true;
+        }
 
         // Vcpkg settings
         if other.vcpkg.enabled.is_some() {
@@ -475,6 +487,23 @@ impl Config {
         if !other.vcpkg.default_features.is_empty() {
             self.vcpkg.default_features = other.vcpkg.default_features;
         }
+
+        // Registries merge by name
+        for entry in other.registries {
+            self.registries.retain(|r| r.name != entry.name);
+            self.registries.push(entry);
+        }
+    }
+
+    /// Build the registry list: built-in defaults plus configured registries.
+    ///
+    /// A configured registry with the same name as a default replaces it.
+    pub fn registry_list(&self) -> RegistryList {
+        let mut list = RegistryList::with_defaults();
+        for entry in &self.registries {
+            list.add(entry.clone());
+        }
+        list
     }
 
     /// Parse backend from config string.
@@ -584,6 +613,34 @@ triplet = "x64-linux"
         assert_eq!(base.vcpkg.triplet, Some("x64-linux".to_string()));
     }
 
+    #[test]
+    fn test_config_registries() {
+        let tmp = TempDir::new().unwrap();
+        let config_path = tmp.path().join("config.toml");
+
+        std::fs::write(
+            &config_path,
+            r#"
+[[registry]]
+name = "internal"
+url = "https://git.example.com/harbour-index"
+priority = 10
+packages = ["internal-*"]
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&config_path).unwrap();
+        assert_eq!(config.registries.len(), 1);
+        assert_eq!(config.registries[0].packages, vec!["internal-*"]);
+        assert!(config.registries[0].enabled);
+
+        let list = config.registry_list();
+        assert_eq!(list.registries.len(), 3);
+        assert_eq!(list.registries[1].name, "internal");
+        assert_eq!(list.candidates("internal-auth").len(), 1);
+    }
+
     #[test]
     fn test_config_parse_backend() {
         let mut config = Config::default();
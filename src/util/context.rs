@@ -5,13 +5,22 @@
 //! ## Multi-Registry Support
 //!
 //! Harbour supports multiple registries with priority-based resolution.
-//! When resolving a package, registries are searched in priority order
-//! (lower number = higher priority). First match wins.
+//! When resolving a version-only dependency (e.g., `zlib = "1.3"`), enabled
+//! registries are searched in priority order (lower number = higher priority).
+//! First match wins, and the winning registry is recorded in `Harbour.lock`.
 //!
-//! Future syntax for pinned registry:
+//! Registries can be configured in `config.toml`:
 //! ```toml
-//! zlib = { version = "1.3", registry = "curated" }
+//! [[registry]]
+//! name = "internal"
+//! url = "https://git.example.com/harbour-index"
+//! priority = 10
+//! packages = ["internal-*"]
 //! ```
+//!
+//! A registry with `packages` patterns only serves matching names, and a
+//! name claimed by such a registry is never looked up anywhere else. This
+//! keeps a public registry from shadowing private packages.
 
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
@@ -56,6 +65,11 @@ pub struct RegistryEntry {
     /// Whether this registry is enabled
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+
+    /// Package name patterns this registry is scoped to (e.g., "internal-*").
+    /// Empty means the registry serves any name not claimed elsewhere.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
 }
 
 fn default_priority() -> i32 {
@@ -74,6 +88,7 @@ impl RegistryEntry {
             url: url.into(),
             priority: default_priority(),
             enabled: true,
+            packages: Vec::new(),
         }
     }
 
@@ -88,6 +103,22 @@ impl RegistryEntry {
         self.enabled = false;
         self
     }
+
+    /// Scope this registry to package names matching the given patterns.
+    pub fn with_packages(mut self, packages: Vec<String>) -> Self {
+        self.packages = packages;
+        self
+    }
+
+    /// Check if this registry is scoped and claims the given package name.
+    ///
+    /// Patterns use glob syntax; an invalid pattern only matches literally.
+    pub fn claims(&self, name: &str) -> bool {
+        self.packages.iter().any(|p| match glob::Pattern::new(p) {
+            Ok(pattern) => pattern.matches(name),
+            Err(_) => p == name,
+        })
+    }
 }
 
 impl Default for RegistryEntry {
@@ -97,6 +128,7 @@ impl Default for RegistryEntry {
             url: DEFAULT_REGISTRY_URL.to_string(),
             priority: default_priority(),
             enabled: true,
+            packages: Vec::new(),
         }
     }
 }
@@ -127,8 +159,9 @@ impl RegistryList {
         }
     }
 
-    /// Add a registry.
+    /// Add a registry, replacing any existing registry with the same name.
     pub fn add(&mut self, entry: RegistryEntry) {
+        self.registries.retain(|r| r.name != entry.name);
         self.registries.push(entry);
         self.sort_by_priority();
     }
@@ -143,6 +176,29 @@ impl RegistryList {
         self.registries.iter().filter(|r| r.enabled)
     }
 
+    /// Get the registries to search for a package, in priority order.
+    ///
+    /// If any enabled registry claims the name via `packages`, only the
+    /// claiming registries are returned. Otherwise all enabled unscoped
+    /// registries are returned. Registries sharing a URL appear once.
+    pub fn candidates(&self, name: &str) -> Vec<&RegistryEntry> {
+        let claiming: Vec<&RegistryEntry> = self.enabled().filter(|r| r.claims(name)).collect();
+        let mut candidates = if claiming.is_empty() {
+            self.enabled().filter(|r| r.packages.is_empty()).collect()
+        } else {
+            claiming
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        candidates.retain(|r| seen.insert(r.url.trim_end_matches('/').to_ascii_lowercase()));
+        candidates
+    }
+
+    /// Check if an enabled registry claims the given package name.
+    pub fn is_scoped(&self, name: &str) -> bool {
+        self.enabled().any(|r| r.claims(name))
+    }
+
     /// Find a registry by name.
     pub fn by_name(&self, name: &str) -> Option<&RegistryEntry> {
         self.registries.iter().find(|r| r.name == name)
@@ -459,6 +515,58 @@ mod tests {
         assert!(list.by_name("nonexistent").is_none());
     }
 
+    #[test]
+    fn test_registry_list_add_replaces_by_name() {
+        let mut list = RegistryList::with_defaults();
+        list.add(RegistryEntry::new("default", "https://mirror.com").with_priority(5));
+
+        assert_eq!(list.registries.len(), 2);
+        assert_eq!(list.registries[0].name, "default");
+        assert_eq!(list.registries[0].url, "https://mirror.com");
+    }
+
+    #[test]
+    fn test_registry_list_candidates_priority_and_dedupe() {
+        let mut list = RegistryList::with_defaults();
+        list.add(RegistryEntry::new("mirror", "https://mirror.com").with_priority(50));
+
+        let names: Vec<_> = list
+            .candidates("zlib")
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        // curated and default share a URL, so default is dropped
+        assert_eq!(names, vec!["curated", "mirror"]);
+    }
+
+    #[test]
+    fn test_registry_list_candidates_scoped() {
+        let mut list = RegistryList::with_defaults();
+        list.add(
+            RegistryEntry::new("internal", "https://internal.example.com")
+                .with_priority(200)
+                .with_packages(vec!["internal-*".to_string()]),
+        );
+
+        // Scoped names only go to the claiming registry, regardless of priority
+        let names: Vec<_> = list
+            .candidates("internal-auth")
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["internal"]);
+        assert!(list.is_scoped("internal-auth"));
+
+        // Other names never reach the scoped registry
+        let names: Vec<_> = list
+            .candidates("zlib")
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["curated"]);
+        assert!(!list.is_scoped("zlib"));
+    }
+
     #[test]
     fn test_context_registries() {
         let ctx = GlobalContext::new().unwrap();
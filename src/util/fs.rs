@@ -23,18 +23,106 @@ pub fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
         if ty.is_dir() {
             copy_dir_all(&src_path, &dst_path)?;
         } else {
-            fs::copy(&src_path, &dst_path).with_context(|| {
-                format!(
-                    "failed to copy {} to {}",
-                    src_path.display(),
-                    dst_path.display()
-                )
-            })?;
+            copy_file_or_link(&src_path, &dst_path)?;
         }
     }
     Ok(())
 }
 
+/// Copy a file, recreating symlinks as symlinks with the same target.
+pub fn copy_file_or_link(src: &Path, dst: &Path) -> Result<()> {
+    let is_link = fs::symlink_metadata(src)
+        .with_context(|| format!("failed to read {}", src.display()))?
+        .file_type()
+        .is_symlink();
+
+    if !is_link {
+        fs::copy(src, dst)
+            .with_context(|| format!("failed to copy {} to {}", src.display(), dst.display()))?;
+        return Ok(());
+    }
+
+    let target =
+        fs::read_link(src).with_context(|| format!("failed to read link {}", src.display()))?;
+    replace_symlink(&target, dst)
+}
+
+/// Follow a chain of symlinks, such as
+/// `libfoo.so -> libfoo.so.1 -> libfoo.so.1.2.3`.
+///
+/// Returns `path`, every link it passes through and, last, the real file.
+/// A link pointing outside its own directory ends the chain at the
+/// canonical path of the file it resolves to.
+pub fn symlink_chain(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut chain = vec![path.to_path_buf()];
+    let mut current = path.to_path_buf();
+
+    while fs::symlink_metadata(&current)
+        .with_context(|| format!("failed to read {}", current.display()))?
+        .file_type()
+        .is_symlink()
+    {
+        if chain.len() > 40 {
+            anyhow::bail!("too many levels of symbolic links: {}", path.display());
+        }
+
+        let target = fs::read_link(&current)
+            .with_context(|| format!("failed to read link {}", current.display()))?;
+        if target.components().count() != 1 {
+            chain.push(
+                fs::canonicalize(&current)
+                    .with_context(|| format!("failed to resolve {}", current.display()))?,
+            );
+            break;
+        }
+
+        current = current.with_file_name(target);
+        chain.push(current.clone());
+    }
+
+    Ok(chain)
+}
+
+/// Copy a file into `dst_dir` together with the symlink chain leading to it.
+///
+/// The real file keeps its own name and each link is recreated next to it.
+/// Returns the destination of `src` itself.
+pub fn copy_with_links(src: &Path, dst_dir: &Path) -> Result<PathBuf> {
+    let chain = symlink_chain(src)?;
+    let (real, links) = chain.split_last().expect("chain contains at least `src`");
+    let file_name = |p: &Path| {
+        p.file_name()
+            .map(PathBuf::from)
+            .with_context(|| format!("no file name: {}", p.display()))
+    };
+
+    fs::copy(real, dst_dir.join(file_name(real)?))
+        .with_context(|| format!("failed to copy {} to {}", real.display(), dst_dir.display()))?;
+
+    for (link, next) in links.iter().zip(&chain[1..]) {
+        let (name, target) = (file_name(link)?, file_name(next)?);
+        if name != target {
+            replace_symlink(&target, &dst_dir.join(name))?;
+        }
+    }
+
+    Ok(dst_dir.join(file_name(src)?))
+}
+
+/// Create a symlink at `link`, replacing whatever is there.
+pub fn replace_symlink(target: &Path, link: &Path) -> Result<()> {
+    if fs::symlink_metadata(link).is_ok() {
+        fs::remove_file(link).with_context(|| format!("failed to remove {}", link.display()))?;
+    }
+    symlink(target, link).with_context(|| {
+        format!(
+            "failed to create symlink {} -> {}",
+            link.display(),
+            target.display()
+        )
+    })
+}
+
 /// Remove a directory and all its contents, if it exists.
 pub fn remove_dir_all_if_exists(path: &Path) -> Result<()> {
     if path.exists() {
@@ -160,4 +248,62 @@ mod tests {
         assert!(dst.join("file.txt").exists());
         assert_eq!(fs::read_to_string(dst.join("file.txt")).unwrap(), "content");
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_with_links() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+
+        fs::write(src.join("libfoo.so.1.2.3"), "elf").unwrap();
+        symlink(Path::new("libfoo.so.1.2.3"), &src.join("libfoo.so.1")).unwrap();
+        symlink(Path::new("libfoo.so.1"), &src.join("libfoo.so")).unwrap();
+
+        let chain = symlink_chain(&src.join("libfoo.so")).unwrap();
+        assert_eq!(
+            chain,
+            vec![
+                src.join("libfoo.so"),
+                src.join("libfoo.so.1"),
+                src.join("libfoo.so.1.2.3")
+            ]
+        );
+
+        let copied = copy_with_links(&src.join("libfoo.so"), &dst).unwrap();
+        assert_eq!(copied, dst.join("libfoo.so"));
+        assert_eq!(
+            fs::read_link(dst.join("libfoo.so")).unwrap(),
+            PathBuf::from("libfoo.so.1")
+        );
+        assert_eq!(
+            fs::read_link(dst.join("libfoo.so.1")).unwrap(),
+            PathBuf::from("libfoo.so.1.2.3")
+        );
+        assert_eq!(fs::read_to_string(&copied).unwrap(), "elf");
+
+        // Copying again replaces the existing links
+        copy_with_links(&src.join("libfoo.so"), &dst).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_dir_all_preserves_links() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("libfoo.so.1"), "elf").unwrap();
+        symlink(Path::new("libfoo.so.1"), &src.join("libfoo.so")).unwrap();
+
+        copy_dir_all(&src, &dst).unwrap();
+
+        assert_eq!(
+            fs::read_link(dst.join("libfoo.so")).unwrap(),
+            PathBuf::from("libfoo.so.1")
+        );
+    }
 }
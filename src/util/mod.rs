@@ -1,5 +1,7 @@
 //! Shared utilities
 
+pub mod auth;
+pub mod binary;
 pub mod config;
 pub mod context;
 pub mod diagnostic;
@@ -85,6 +85,16 @@ impl ProcessBuilder {
         &self.args
     }
 
+    /// Get an environment variable set on the command.
+    pub fn get_env(&self, key: &str) -> Option<&str> {
+        self.env.get(key).map(String::as_str)
+    }
+
+    /// Get the working directory.
+    pub fn get_cwd(&self) -> Option<&Path> {
+        self.cwd.as_deref()
+    }
+
     /// Build the Command.
     fn build_command(&self) -> Command {
         let mut cmd = Command::new(&self.program);
@@ -157,6 +167,29 @@ impl ProcessBuilder {
         Ok(status)
     }
 
+    /// Run the command in place of the current process.
+    ///
+    /// On Unix the process image is replaced, so signals and the exit code go
+    /// straight to the program. Elsewhere the program runs to completion and
+    /// this process exits with its exit code. Only returns on failure to
+    /// start the program.
+    pub fn exec_replace(&self) -> Result<()> {
+        let mut cmd = self.build_command();
+
+        #[cfg(unix)]
+        let err = {
+            use std::os::unix::process::CommandExt;
+            cmd.exec()
+        };
+        #[cfg(not(unix))]
+        let err = match cmd.status() {
+            Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+            Err(err) => err,
+        };
+
+        Err(err).with_context(|| format!("failed to execute `{}`", self.program.display()))
+    }
+
     /// Display the command for error messages.
     pub fn display_command(&self) -> String {
         let mut parts = vec![self.program.display().to_string()];
@@ -100,6 +100,7 @@ pub enum Status {
     Resolving,
     Linking,
     Building,
+    Checking,
 
     // Info statuses (blue/default)
     Info,
@@ -126,6 +127,7 @@ impl Status {
             Status::Resolving => "Resolving",
             Status::Linking => "Linking",
             Status::Building => "Building",
+            Status::Checking => "Checking",
             Status::Info => "Info",
             Status::Skipped => "Skipped",
             Status::Warning => "Warning",
@@ -147,7 +149,8 @@ impl Status {
             | Status::Fetching
             | Status::Resolving
             | Status::Linking
-            | Status::Building => "\x1b[1;36m",
+            | Status::Building
+            | Status::Checking => "\x1b[1;36m",
             // Info: bold blue
             Status::Info => "\x1b[1;34m",
             // Warning: bold yellow
@@ -0,0 +1,101 @@
+// <auto-generated>
+// FFI bindings for mylib.
+//
+// Auto-generated by Harbour FFI binding generator.
+// Do not edit manually.
+// </auto-generated>
+
+using System;
+using System.Runtime.InteropServices;
+
+namespace Mylib
+{
+    public enum Status : int
+    {
+        MYLIB_OK = 0,
+        MYLIB_ERROR = 1,
+        MYLIB_INVALID = 2,
+    }
+
+    public enum Type : int
+    {
+        MYLIB_TYPE_INT = 0,
+        MYLIB_TYPE_FLOAT = 1,
+        MYLIB_TYPE_STRING = 2,
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct Point
+    {
+        public int x;
+        public int y;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct Item
+    {
+        public IntPtr name;
+        public int id;
+        public Type type;
+    }
+
+    // Explicit layout computed for the 64-bit sysv ABI
+    [StructLayout(LayoutKind.Explicit, Size = 16)]
+    public struct Value
+    {
+        [FieldOffset(0)]
+        public Type type;
+        [FieldOffset(8)]
+        public int i;
+        [FieldOffset(8)]
+        public float f;
+        [FieldOffset(8)]
+        public IntPtr s;
+    }
+
+    // Explicit layout computed for the 64-bit sysv ABI
+    [StructLayout(LayoutKind.Explicit, Size = 7)]
+    public struct Header
+    {
+        /// <summary>Bitfields: version (bits 0..4), flags (bits 4..8)</summary>
+        [FieldOffset(0)]
+        public byte _bitfield0;
+        [FieldOffset(1)]
+        public ushort length;
+        [FieldOffset(3)]
+        public uint checksum;
+    }
+
+    public static partial class NativeMethods
+    {
+        public const string LibraryName = "mylib";
+
+        public const int MYLIB_VERSION_MAJOR = 1;
+        public const int MYLIB_VERSION_MINOR = 0;
+        public const int MYLIB_MAX_SIZE = 1024;
+
+        [DllImport(LibraryName, EntryPoint = "mylib_init", CallingConvention = CallingConvention.Cdecl)]
+        public static extern int Init();
+
+        [DllImport(LibraryName, EntryPoint = "mylib_shutdown", CallingConvention = CallingConvention.Cdecl)]
+        public static extern void Shutdown();
+
+        [DllImport(LibraryName, EntryPoint = "mylib_add", CallingConvention = CallingConvention.Cdecl)]
+        public static extern int Add(int a, int b);
+
+        [DllImport(LibraryName, EntryPoint = "mylib_process", CallingConvention = CallingConvention.Cdecl)]
+        public static extern Status Process([MarshalAs(UnmanagedType.LPUTF8Str)] string input, byte[] output, nuint output_size);
+
+        [DllImport(LibraryName, EntryPoint = "mylib_create_point", CallingConvention = CallingConvention.Cdecl)]
+        public static extern Point CreatePoint(int x, int y);
+
+        [DllImport(LibraryName, EntryPoint = "mylib_free_item", CallingConvention = CallingConvention.Cdecl)]
+        public static extern void FreeItem(ref Item item);
+
+        [DllImport(LibraryName, EntryPoint = "mylib_set_value", CallingConvention = CallingConvention.Cdecl)]
+        public static extern Status SetValue(ref Item item, in Value value);
+
+        [DllImport(LibraryName, EntryPoint = "mylib_read_header", CallingConvention = CallingConvention.Cdecl)]
+        public static extern Status ReadHeader(in byte data, nuint size, ref Header header);
+    }
+}
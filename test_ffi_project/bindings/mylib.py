@@ -0,0 +1,157 @@
+"""FFI bindings for mylib.
+
+Auto-generated by Harbour FFI binding generator.
+Do not edit manually.
+"""
+
+import ctypes
+import json
+import os
+import sys
+from enum import IntEnum
+
+# Library path resolution
+_HERE = os.path.dirname(os.path.abspath(__file__))
+_LIBRARY_NAME = 'mylib'
+_LIBRARY_PATH = None
+_LIBRARY_ENV = 'MYLIB_LIBRARY'
+
+
+def _find_library():
+    """Locate the native library.
+
+    Checks the MYLIB_LIBRARY environment variable, then the bundle manifest
+    written by `harbour ffi bundle` next to this module or in an
+    `ffi_bundle` subdirectory, then the platform's default file name.
+    """
+    override = os.environ.get(_LIBRARY_ENV)
+    if override:
+        return override
+    if _LIBRARY_PATH is not None:
+        return os.path.join(_HERE, _LIBRARY_PATH)
+
+    if sys.platform == 'win32':
+        default_name = _LIBRARY_NAME + '.dll'
+    elif sys.platform == 'darwin':
+        default_name = 'lib' + _LIBRARY_NAME + '.dylib'
+    else:
+        default_name = 'lib' + _LIBRARY_NAME + '.so'
+
+    for bundle_dir in (_HERE, os.path.join(_HERE, 'ffi_bundle')):
+        manifest = os.path.join(bundle_dir, 'bundle_manifest.json')
+        if os.path.isfile(manifest):
+            with open(manifest, encoding='utf-8') as f:
+                return os.path.join(bundle_dir, json.load(f)['primary_lib'])
+        candidate = os.path.join(bundle_dir, default_name)
+        if os.path.isfile(candidate):
+            return candidate
+
+    return os.path.join(_HERE, default_name)
+
+
+_LIBRARY_FILE = _find_library()
+if sys.platform == 'win32' and hasattr(os, 'add_dll_directory'):
+    # Bundled runtime dependencies live next to the primary library
+    os.add_dll_directory(os.path.dirname(os.path.abspath(_LIBRARY_FILE)))
+
+
+# Constants
+MYLIB_VERSION_MAJOR = 1
+MYLIB_VERSION_MINOR = 0
+MYLIB_MAX_SIZE = 1024
+
+
+class status_t(IntEnum):
+    MYLIB_OK = 0
+    MYLIB_ERROR = 1
+    MYLIB_INVALID = 2
+
+
+class type_t(IntEnum):
+    MYLIB_TYPE_INT = 0
+    MYLIB_TYPE_FLOAT = 1
+    MYLIB_TYPE_STRING = 2
+
+
+# Load the native library
+_lib = ctypes.CDLL(_LIBRARY_FILE)
+
+
+class point_t(ctypes.Structure):
+    pass
+
+
+class item_t(ctypes.Structure):
+    pass
+
+
+class _value_t__anon1(ctypes.Union):
+    pass
+
+
+class value_t(ctypes.Structure):
+    _anonymous_ = ('_anon1',)
+
+
+class header_t(ctypes.Structure):
+    _pack_ = 1
+
+
+# Struct fields
+point_t._fields_ = [
+    ('x', ctypes.c_int32),
+    ('y', ctypes.c_int32),
+]
+item_t._fields_ = [
+    ('name', ctypes.c_char_p),
+    ('id', ctypes.c_int32),
+    ('type', ctypes.c_int),
+]
+_value_t__anon1._fields_ = [
+    ('i', ctypes.c_int32),
+    ('f', ctypes.c_float),
+    ('s', ctypes.c_char_p),
+]
+value_t._fields_ = [
+    ('type', ctypes.c_int),
+    ('_anon1', _value_t__anon1),
+]
+header_t._fields_ = [
+    ('version', ctypes.c_uint8, 4),
+    ('flags', ctypes.c_uint8, 4),
+    ('length', ctypes.c_uint16),
+    ('checksum', ctypes.c_uint32),
+]
+
+# Function bindings
+init = _lib.mylib_init
+init.argtypes = []
+init.restype = ctypes.c_int32
+
+shutdown = _lib.mylib_shutdown
+shutdown.argtypes = []
+shutdown.restype = None
+
+add = _lib.mylib_add
+add.argtypes = [ctypes.c_int32, ctypes.c_int32]
+add.restype = ctypes.c_int32
+
+process = _lib.mylib_process
+process.argtypes = [ctypes.c_char_p, ctypes.c_char_p, ctypes.c_size_t]
+process.restype = ctypes.c_int
+
+create_point = _lib.mylib_create_point
+create_point.argtypes = [ctypes.c_int32, ctypes.c_int32]
+create_point.restype = point_t
+
+free_item = _lib.mylib_free_item
+free_item.argtypes = [ctypes.POINTER(item_t)]
+free_item.restype = None
+
+set_value = _lib.mylib_set_value
+set_value.argtypes = [ctypes.POINTER(item_t), ctypes.POINTER(value_t)]
+set_value.restype = ctypes.c_int
+
+read_header = _lib.mylib_read_header
+read_header.argtypes = [ctypes.POINTER(ctypes.c_uint8), ctypes.c_size_t, ctypes.POINTER(header_t)]
+read_header.restype = ctypes.c_int
@@ -0,0 +1,167 @@
+//! FFI bindings for mylib.
+//!
+//! Auto-generated by Harbour FFI binding generator.
+//! Do not edit manually.
+
+#![allow(
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    dead_code,
+    unused_imports
+)]
+
+use core::ffi::{c_char, c_int};
+
+pub const MYLIB_VERSION_MAJOR: i32 = 1;
+pub const MYLIB_VERSION_MINOR: i32 = 0;
+pub const MYLIB_MAX_SIZE: i32 = 1024;
+
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct status_t(pub c_int);
+
+impl status_t {
+    pub const MYLIB_OK: status_t = status_t(0);
+    pub const MYLIB_ERROR: status_t = status_t(1);
+    pub const MYLIB_INVALID: status_t = status_t(2);
+}
+
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct type_t(pub c_int);
+
+impl type_t {
+    pub const MYLIB_TYPE_INT: type_t = type_t(0);
+    pub const MYLIB_TYPE_FLOAT: type_t = type_t(1);
+    pub const MYLIB_TYPE_STRING: type_t = type_t(2);
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct point_t {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(core::mem::size_of::<point_t>() == 8);
+    assert!(core::mem::align_of::<point_t>() == 4);
+    assert!(core::mem::offset_of!(point_t, x) == 0);
+    assert!(core::mem::offset_of!(point_t, y) == 4);
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct item_t {
+    pub name: *mut c_char,
+    pub id: i32,
+    pub r#type: type_t,
+}
+
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(core::mem::size_of::<item_t>() == 16);
+    assert!(core::mem::align_of::<item_t>() == 8);
+    assert!(core::mem::offset_of!(item_t, name) == 0);
+    assert!(core::mem::offset_of!(item_t, id) == 8);
+    assert!(core::mem::offset_of!(item_t, r#type) == 12);
+};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union value_t__anon1 {
+    pub i: i32,
+    pub f: f32,
+    pub s: *const c_char,
+}
+
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(core::mem::size_of::<value_t__anon1>() == 8);
+    assert!(core::mem::align_of::<value_t__anon1>() == 8);
+    assert!(core::mem::offset_of!(value_t__anon1, i) == 0);
+    assert!(core::mem::offset_of!(value_t__anon1, f) == 0);
+    assert!(core::mem::offset_of!(value_t__anon1, s) == 0);
+};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct value_t {
+    pub r#type: type_t,
+    pub _anon1: value_t__anon1,
+}
+
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(core::mem::size_of::<value_t>() == 16);
+    assert!(core::mem::align_of::<value_t>() == 8);
+    assert!(core::mem::offset_of!(value_t, r#type) == 0);
+    assert!(core::mem::offset_of!(value_t, _anon1) == 8);
+};
+
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct header_t {
+    /// Bitfields: version (bits 0..4), flags (bits 4..8)
+    pub _bitfield_1: u8,
+    pub length: u16,
+    pub checksum: u32,
+}
+
+#[cfg(all(target_pointer_width = "64", not(windows)))]
+const _: () = {
+    assert!(core::mem::size_of::<header_t>() == 7);
+    assert!(core::mem::align_of::<header_t>() == 1);
+    assert!(core::mem::offset_of!(header_t, _bitfield_1) == 0);
+    assert!(core::mem::offset_of!(header_t, length) == 1);
+    assert!(core::mem::offset_of!(header_t, checksum) == 3);
+};
+
+unsafe extern "C" {
+    #[link_name = "mylib_init"]
+    pub fn init() -> i32;
+
+    #[link_name = "mylib_shutdown"]
+    pub fn shutdown();
+
+    #[link_name = "mylib_add"]
+    pub fn add(a: i32, b: i32) -> i32;
+
+    #[link_name = "mylib_process"]
+    pub fn process(input: *const c_char, output: *mut c_char, output_size: usize) -> status_t;
+
+    #[link_name = "mylib_create_point"]
+    pub fn create_point(x: i32, y: i32) -> point_t;
+
+    #[link_name = "mylib_free_item"]
+    pub fn free_item(item: *mut item_t);
+
+    #[link_name = "mylib_set_value"]
+    pub fn set_value(item: *mut item_t, value: *const value_t) -> status_t;
+
+    #[link_name = "mylib_read_header"]
+    pub fn read_header(data: *const u8, size: usize, header: *mut header_t) -> status_t;
+}
+
+/// Safe wrappers for functions taking plain data, C strings and references.
+pub mod safe {
+    use super::*;
+
+    pub fn init() -> i32 {
+        unsafe { super::init() }
+    }
+
+    pub fn shutdown() {
+        unsafe { super::shutdown() }
+    }
+
+    pub fn add(a: i32, b: i32) -> i32 {
+        unsafe { super::add(a, b) }
+    }
+
+    pub fn create_point(x: i32, y: i32) -> point_t {
+        unsafe { super::create_point(x, y) }
+    }
+}
@@ -28,78 +28,117 @@ const libPath = join(__dirname, 'libmylib' + libExt);
 // Load the native library
 const lib = koffi.load(libPath);
 
-export enum mylib_status_t {
+export enum status_t {
   MYLIB_OK = 0,
   MYLIB_ERROR = 1,
   MYLIB_INVALID = 2,
 }
 
-export enum mylib_type_t {
+export enum type_t {
   MYLIB_TYPE_INT = 0,
   MYLIB_TYPE_FLOAT = 1,
   MYLIB_TYPE_STRING = 2,
 }
 
-const mylib_point_t = koffi.struct('mylib_point_t', {
+const point_t = koffi.struct('point_t', {
   x: 'int32',
   y: 'int32',
 });
 
-const mylib_item_t = koffi.struct('mylib_item_t', {
+const item_t = koffi.struct('item_t', {
   name: 'char*',
   id: 'int32',
-  type: 'mylib_type_t',
+  type: 'type_t',
 });
 
-export interface Imylib_point_t {
+const value_t = koffi.struct('value_t', {
+  type: 'type_t',
+  _anon1: koffi.union({ i: 'int32', f: 'float32', s: 'char*' }),
+});
+
+const header_t = koffi.pack('header_t', {
+  _bitfield0: 'uint8', // version: bits 0..4, flags: bits 4..8
+  length: 'uint16',
+  checksum: 'uint32',
+});
+
+export interface Ipoint_t {
   x: number;
   y: number;
 }
 
-export interface Imylib_item_t {
+export interface Iitem_t {
   name: string | Buffer;
   id: number;
-  type: mylib_type_t;
+  type: type_t;
+}
+
+export interface Ivalue_t {
+  type: type_t;
+  _anon1: { i: number; f: number; s: string };
+}
+
+export interface Iheader_t {
+  _bitfield0: number;
+  length: number;
+  checksum: number;
 }
 
 // Function bindings
 /**
  * @returns number
  */
-export const mylib_init = lib.func('mylib_init', 'int32', []) as () => number;
+export const init = lib.func('mylib_init', 'int32', []) as () => number;
 
 /**
  */
-export const mylib_shutdown = lib.func('mylib_shutdown', 'void', []) as () => void;
+export const shutdown = lib.func('mylib_shutdown', 'void', []) as () => void;
 
 /**
  * @param a - number
  * @param b - number
  * @returns number
  */
-export const mylib_add = lib.func('mylib_add', 'int32', ['int32', 'int32']) as (a: number, b: number) => number;
+export const add = lib.func('mylib_add', 'int32', ['int32', 'int32']) as (a: number, b: number) => number;
 
 /**
  * @param input - string
  * @param output - string | Buffer
  * @param output_size - bigint
- * @returns mylib_status_t
+ * @returns status_t
  */
-export const mylib_process = lib.func('mylib_process', 'mylib_status_t', ['char*', 'char*', 'size_t']) as (input: string, output: string | Buffer, output_size: bigint) => mylib_status_t;
+export const process = lib.func('mylib_process', 'status_t', ['char*', 'char*', 'size_t']) as (input: string, output: string | Buffer, output_size: bigint) => status_t;
 
 /**
  * @param x - number
  * @param y - number
- * @returns mylib_point_t
+ * @returns point_t
  */
-export const mylib_create_point = lib.func('mylib_create_point', 'mylib_point_t', ['int32', 'int32']) as (x: number, y: number) => mylib_point_t;
+export const create_point = lib.func('mylib_create_point', 'point_t', ['int32', 'int32']) as (x: number, y: number) => point_t;
 
 /**
  * @param item - Buffer | number
  */
-export const mylib_free_item = lib.func('mylib_free_item', 'void', ['mylib_item_t*']) as (item: Buffer | number) => void;
+export const free_item = lib.func('mylib_free_item', 'void', ['item_t*']) as (item: Buffer | number) => void;
+
+/**
+ * @param item - Buffer | number
+ * @param value - Buffer | number
+ * @returns status_t
+ */
+export const set_value = lib.func('mylib_set_value', 'status_t', ['item_t*', 'value_t*']) as (item: Buffer | number, value: Buffer | number) => status_t;
+
+/**
+ * @param data - Buffer | number
+ * @param size - bigint
+ * @param header - Buffer | number
+ * @returns status_t
+ */
+export const read_header = lib.func('mylib_read_header', 'status_t', ['uint8*', 'size_t', 'header_t*']) as (data: Buffer | number, size: bigint, header: Buffer | number) => status_t;
 
 
 // Re-export types
-export { mylib_point_t };
-export { mylib_item_t };
+export { point_t };
+export { item_t };
+export { value_t };
+export { header_t };
@@ -0,0 +1,140 @@
+"""FFI bindings for mylib.
+
+Auto-generated by Harbour FFI binding generator.
+Do not edit manually.
+"""
+
+import json
+import os
+import sys
+from enum import IntEnum
+
+from cffi import FFI
+
+# Library path resolution
+_HERE = os.path.dirname(os.path.abspath(__file__))
+_LIBRARY_NAME = 'mylib'
+_LIBRARY_PATH = None
+_LIBRARY_ENV = 'MYLIB_LIBRARY'
+
+
+def _find_library():
+    """Locate the native library.
+
+    Checks the MYLIB_LIBRARY environment variable, then the bundle manifest
+    written by `harbour ffi bundle` next to this module or in an
+    `ffi_bundle` subdirectory, then the platform's default file name.
+    """
+    override = os.environ.get(_LIBRARY_ENV)
+    if override:
+        return override
+    if _LIBRARY_PATH is not None:
+        return os.path.join(_HERE, _LIBRARY_PATH)
+
+    if sys.platform == 'win32':
+        default_name = _LIBRARY_NAME + '.dll'
+    elif sys.platform == 'darwin':
+        default_name = 'lib' + _LIBRARY_NAME + '.dylib'
+    else:
+        default_name = 'lib' + _LIBRARY_NAME + '.so'
+
+    for bundle_dir in (_HERE, os.path.join(_HERE, 'ffi_bundle')):
+        manifest = os.path.join(bundle_dir, 'bundle_manifest.json')
+        if os.path.isfile(manifest):
+            with open(manifest, encoding='utf-8') as f:
+                return os.path.join(bundle_dir, json.load(f)['primary_lib'])
+        candidate = os.path.join(bundle_dir, default_name)
+        if os.path.isfile(candidate):
+            return candidate
+
+    return os.path.join(_HERE, default_name)
+
+
+_LIBRARY_FILE = _find_library()
+if sys.platform == 'win32' and hasattr(os, 'add_dll_directory'):
+    # Bundled runtime dependencies live next to the primary library
+    os.add_dll_directory(os.path.dirname(os.path.abspath(_LIBRARY_FILE)))
+
+
+# Constants
+MYLIB_VERSION_MAJOR = 1
+MYLIB_VERSION_MINOR = 0
+MYLIB_MAX_SIZE = 1024
+
+
+class status_t(IntEnum):
+    MYLIB_OK = 0
+    MYLIB_ERROR = 1
+    MYLIB_INVALID = 2
+
+
+class type_t(IntEnum):
+    MYLIB_TYPE_INT = 0
+    MYLIB_TYPE_FLOAT = 1
+    MYLIB_TYPE_STRING = 2
+
+
+# C declarations
+ffi = FFI()
+ffi.cdef("""
+typedef enum {
+    MYLIB_OK = 0,
+    MYLIB_ERROR = 1,
+    MYLIB_INVALID = 2,
+} status_t;
+typedef enum {
+    MYLIB_TYPE_INT = 0,
+    MYLIB_TYPE_FLOAT = 1,
+    MYLIB_TYPE_STRING = 2,
+} type_t;
+typedef struct point_t point_t;
+typedef struct item_t item_t;
+typedef struct value_t value_t;
+typedef struct header_t header_t;
+struct point_t {
+    int32_t x;
+    int32_t y;
+};
+struct item_t {
+    char *name;
+    int32_t id;
+    type_t type;
+};
+struct value_t {
+    type_t type;
+    union {
+        int32_t i;
+        float f;
+        const char *s;
+    };
+};
+int32_t mylib_init(void);
+void mylib_shutdown(void);
+int32_t mylib_add(int32_t a, int32_t b);
+status_t mylib_process(const char *input, char *output, size_t output_size);
+point_t mylib_create_point(int32_t x, int32_t y);
+void mylib_free_item(item_t *item);
+status_t mylib_set_value(item_t *item, const value_t *value);
+status_t mylib_read_header(const uint8_t *data, size_t size, header_t *header);
+""")
+ffi.cdef("""
+struct header_t {
+    uint8_t version : 4;
+    uint8_t flags : 4;
+    uint16_t length;
+    uint32_t checksum;
+};
+""", packed=True)
+
+# Load the native library (ABI mode)
+lib = ffi.dlopen(_LIBRARY_FILE)
+
+# Function bindings
+init = lib.mylib_init
+shutdown = lib.mylib_shutdown
+add = lib.mylib_add
+process = lib.mylib_process
+create_point = lib.mylib_create_point
+free_item = lib.mylib_free_item
+set_value = lib.mylib_set_value
+read_header = lib.mylib_read_header
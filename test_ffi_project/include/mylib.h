@@ -33,6 +33,24 @@ typedef struct {
     mylib_type_t type;
 } mylib_item_t;
 
+typedef struct {
+    mylib_type_t type;
+    union {
+        int32_t i;
+        float f;
+        const char* s;
+    };
+} mylib_value_t;
+
+#pragma pack(push, 1)
+typedef struct {
+    uint8_t version : 4;
+    uint8_t flags : 4;
+    uint16_t length;
+    uint32_t checksum;
+} mylib_header_t;
+#pragma pack(pop)
+
 // Functions
 int mylib_init(void);
 void mylib_shutdown(void);
@@ -40,5 +58,7 @@ int mylib_add(int a, int b);
 mylib_status_t mylib_process(const char* input, char* output, size_t output_size);
 mylib_point_t mylib_create_point(int32_t x, int32_t y);
 void mylib_free_item(mylib_item_t* item);
+mylib_status_t mylib_set_value(mylib_item_t* item, const mylib_value_t* value);
+mylib_status_t mylib_read_header(const uint8_t* data, size_t size, mylib_header_t* header);
 
 #endif